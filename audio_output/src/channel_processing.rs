@@ -0,0 +1,117 @@
+use std::f32::consts::PI;
+use std::sync::Mutex;
+
+use musium_core::api::UserPreferences;
+
+/// Processing of the left and right channel of stereo audio, for listening comfort and for listeners that hear with
+/// one ear. Mono audio is not processed.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ChannelProcessing {
+  /// Whether to mix both channels together and play the mix on both channels, so that nothing is missed when hearing
+  /// only one channel.
+  pub mono_downmix: bool,
+  /// Balance between the left (-1.0) and right (1.0) channel, where 0.0 plays both channels at full volume.
+  pub balance: f32,
+  /// Amount of headphone crossfeed between 0.0 and 1.0, which mixes the low frequencies of each channel into the other
+  /// channel, like loudspeakers do, to reduce the exaggerated stereo separation of headphones.
+  pub crossfeed: f32,
+}
+
+impl ChannelProcessing {
+  /// Creates channel processing from the preferences of a user, using defaults for unset preferences.
+  pub fn from_user_preferences(preferences: &UserPreferences) -> Self {
+    let default = Self::default();
+    Self {
+      mono_downmix: preferences.mono_downmix.unwrap_or(default.mono_downmix),
+      balance: preferences.channel_balance.unwrap_or(default.balance),
+      crossfeed: preferences.crossfeed.unwrap_or(default.crossfeed),
+    }
+  }
+
+  /// Stores this channel processing into the preferences of a user.
+  pub fn store_in_user_preferences(&self, preferences: &mut UserPreferences) {
+    preferences.mono_downmix = Some(self.mono_downmix);
+    preferences.channel_balance = Some(self.balance);
+    preferences.crossfeed = Some(self.crossfeed);
+  }
+
+  /// Returns whether this channel processing leaves audio unchanged.
+  pub fn is_neutral(&self) -> bool {
+    !self.mono_downmix && self.balance == 0.0 && self.crossfeed == 0.0
+  }
+}
+
+impl Default for ChannelProcessing {
+  fn default() -> Self {
+    Self { mono_downmix: false, balance: 0.0, crossfeed: 0.0 }
+  }
+}
+
+/// Cutoff frequency in Hz of the low-pass filter of crossfed audio. Sound below this frequency reaches both ears from a
+/// loudspeaker, whereas the head shadows higher frequencies.
+const CROSSFEED_CUTOFF: f32 = 700.0;
+/// Level of crossfed audio relative to the direct audio at a crossfeed of 1.0.
+const MAX_CROSSFEED_LEVEL: f32 = 0.6;
+
+/// Applies channel processing to frames of stereo audio. Keeps the state of the crossfeed filter, so one processor must
+/// be used per stream of audio.
+#[derive(Default, Debug)]
+pub struct ChannelProcessor {
+  processing: ChannelProcessing,
+  /// Low-pass filtered left channel, mixed into the right channel for crossfeed.
+  filtered_left: f32,
+  /// Low-pass filtered right channel, mixed into the left channel for crossfeed.
+  filtered_right: f32,
+}
+
+impl ChannelProcessor {
+  pub fn new(processing: ChannelProcessing) -> Self {
+    Self { processing, ..Self::default() }
+  }
+
+  pub fn set_processing(&mut self, processing: ChannelProcessing) {
+    self.processing = processing;
+  }
+
+  /// Returns whether this processor leaves audio unchanged, so that processing can be skipped.
+  pub fn is_neutral(&self) -> bool {
+    self.processing.is_neutral()
+  }
+
+  /// Updates the channel processing from `shared` if it is not locked, so that the audio thread calling this never
+  /// waits for a lock. A locked update is picked up by a later call.
+  pub fn update_from(&mut self, shared: &Mutex<ChannelProcessing>) {
+    if let Ok(processing) = shared.try_lock() {
+      self.processing = *processing;
+    }
+  }
+
+  /// Processes one frame of stereo audio with samples between -1.0 and 1.0, sampled at `sample_rate`.
+  pub fn process(&mut self, sample_rate: u32, left: f32, right: f32) -> (f32, f32) {
+    let ChannelProcessing { mono_downmix, balance, crossfeed } = self.processing;
+    let (mut left, mut right) = if mono_downmix {
+      let mix = (left + right) * 0.5;
+      (mix, mix)
+    } else {
+      (left, right)
+    };
+    // Crossfeed makes no difference for a mono downmix, so skip it to not needlessly colour the sound.
+    if crossfeed > 0.0 && !mono_downmix {
+      let alpha = 1.0 - (-2.0 * PI * CROSSFEED_CUTOFF / sample_rate.max(1) as f32).exp();
+      self.filtered_left += alpha * (left - self.filtered_left);
+      self.filtered_right += alpha * (right - self.filtered_right);
+      let level = crossfeed.clamp(0.0, 1.0) * MAX_CROSSFEED_LEVEL;
+      // Scale down to keep the loudness of audio that is equal on both channels the same, and to prevent clipping.
+      let scale = 1.0 / (1.0 + level);
+      left = (left + level * self.filtered_right) * scale;
+      right = (right + level * self.filtered_left) * scale;
+    }
+    let balance = balance.clamp(-1.0, 1.0);
+    if balance > 0.0 {
+      left *= 1.0 - balance;
+    } else if balance < 0.0 {
+      right *= 1.0 + balance;
+    }
+    (left, right)
+  }
+}
@@ -1,14 +1,29 @@
-use std::fmt::Debug;
+mod channel_processing;
+mod received_audio;
+
+use std::fmt::{Debug, Formatter};
+use std::io::{Cursor, Read, Seek};
 
 use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::broadcast;
 
 use musium_core::api::AudioCodec;
 use musium_core::error::SyncError;
 
+pub use channel_processing::{ChannelProcessing, ChannelProcessor};
+pub use received_audio::{ReceivedAudio, ReceivedAudioReader, ReceivedAudioWriter};
+
 #[async_trait]
 pub trait AudioOutput: 'static + Send + Sync + Clone + Debug {
   type SetAudioDataError: SyncError;
-  async fn set_audio_data(&self, codec: Option<AudioCodec>, data: Vec<u8>) -> Result<(), Self::SetAudioDataError>;
+  /// Sets the audio to play to the encoded audio read from `source`. Audio outputs that support it decode the source
+  /// progressively while playing, so that the source does not have to be loaded into memory entirely.
+  async fn set_audio_source(&self, codec: Option<AudioCodec>, source: AudioSource) -> Result<(), Self::SetAudioDataError>;
+  /// Sets the audio to play to the encoded audio in `data`.
+  async fn set_audio_data(&self, codec: Option<AudioCodec>, data: Vec<u8>) -> Result<(), Self::SetAudioDataError> {
+    self.set_audio_source(codec, AudioSource::from(data)).await
+  }
 
   type IsPlayingError: SyncError;
   async fn is_playing(&self) -> Result<bool, Self::IsPlayingError>;
@@ -44,4 +59,95 @@ pub trait AudioOutput: 'static + Send + Sync + Clone + Debug {
   async fn get_volume(&self) -> Result<f64, Self::GetVolumeError>;
   type SetVolumeError: SyncError;
   async fn set_volume(&self, volume: f64) -> Result<(), Self::SetVolumeError>;
+  /// Sets the linear gain factor that is applied on top of the volume, for normalizing the loudness of tracks.
+  type SetGainError: SyncError;
+  async fn set_gain(&self, gain: f64) -> Result<(), Self::SetGainError>;
+  /// Sets the processing of the left and right channel, such as downmixing to mono, which applies to the audio that is
+  /// playing and to audio that is played later.
+  type SetChannelProcessingError: SyncError;
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), Self::SetChannelProcessingError>;
+  /// Sets the duration in seconds of fading out when pausing or stopping, and fading in when resuming. Audio outputs
+  /// that do not support fading ignore this.
+  type SetFadeDurationError: SyncError;
+  async fn set_fade_duration(&self, fade_duration: f64) -> Result<(), Self::SetFadeDurationError>;
+
+  /// Gets the name of the output device that audio is played on, or `None` if there is no output device.
+  type GetOutputDeviceNameError: SyncError;
+  async fn get_output_device_name(&self) -> Result<Option<String>, Self::GetOutputDeviceNameError>;
+
+  /// Subscribes to errors that occur while playing, which are not the result of a request to this audio output.
+  fn subscribe_playback_errors(&self) -> broadcast::Receiver<PlaybackError>;
+}
+
+/// Error that occurred while playing.
+#[derive(Clone, Debug, Error)]
+pub enum PlaybackError {
+  #[error("Failed to decode audio: {0}")]
+  DecodeFail(String),
+  #[error("Audio output could not keep up with playback")]
+  Underrun,
+  #[error("Output device disconnected; waiting for a new output device")]
+  DeviceDisconnected,
+  #[error("Failed to continue playback on the output device: {0}")]
+  DeviceFail(String),
+  #[error("Audio output failed, but recovered and continued playback: {0}")]
+  OutputRecovered(String),
+  #[error("Audio output failed: {0}")]
+  OutputFail(String),
+}
+
+impl PlaybackError {
+  /// Returns whether playback of the current track stopped because of this error.
+  pub fn is_fatal(&self) -> bool {
+    use PlaybackError::*;
+    match self {
+      DecodeFail(_) | DeviceFail(_) | OutputFail(_) => true,
+      Underrun | DeviceDisconnected | OutputRecovered(_) => false,
+    }
+  }
+}
+
+/// Source of encoded audio that can be read and seeked, such as a file or a download that is in progress.
+pub trait MediaSource: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> MediaSource for T {}
+
+/// Encoded audio to play.
+pub enum AudioSource {
+  /// Encoded audio that is entirely in memory.
+  Data(Vec<u8>),
+  /// Encoded audio that is read from a media source while playing.
+  Stream(Box<dyn MediaSource>),
+  /// Encoded audio that is read while it is received, such as a track that is being downloaded. Unlike a stream, it can
+  /// be read again from the start.
+  Received(ReceivedAudio),
+}
+
+impl AudioSource {
+  pub fn from_stream(source: impl MediaSource + 'static) -> Self {
+    Self::Stream(Box::new(source))
+  }
+
+  /// Converts this into a media source, reading from memory if this is `Data`.
+  pub fn into_media_source(self) -> Box<dyn MediaSource> {
+    match self {
+      AudioSource::Data(data) => Box::new(Cursor::new(data)),
+      AudioSource::Stream(source) => source,
+      AudioSource::Received(received) => Box::new(received.reader()),
+    }
+  }
+}
+
+impl From<Vec<u8>> for AudioSource {
+  fn from(data: Vec<u8>) -> Self { Self::Data(data) }
+}
+
+impl Debug for AudioSource {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AudioSource::Data(data) => f.debug_tuple("Data").field(&data.len()).finish(),
+      AudioSource::Stream(_) => f.debug_tuple("Stream").finish(),
+      AudioSource::Received(received) => f.debug_tuple("Received").field(received).finish(),
+    }
+  }
 }
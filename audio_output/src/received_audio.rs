@@ -0,0 +1,175 @@
+use std::fmt::{Debug, Formatter};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use tokio::sync::Notify;
+
+/// Encoded audio that is received in chunks, such as a track that is being downloaded, which can be read while it is
+/// received. Any number of readers can read it from the start, such as for continuing playback on another output
+/// device, as received audio is kept in memory.
+#[derive(Clone)]
+pub struct ReceivedAudio {
+  shared: Arc<Shared>,
+}
+
+/// Writer that appends received chunks to `ReceivedAudio`. Dropping the writer before calling `finish` or `fail` fails
+/// the received audio, so that readers do not wait for chunks that never arrive, for example when receiving is
+/// cancelled.
+pub struct ReceivedAudioWriter {
+  shared: Arc<Shared>,
+}
+
+/// Reader of `ReceivedAudio`, which blocks while reading or seeking past the audio that was received so far.
+pub struct ReceivedAudioReader {
+  shared: Arc<Shared>,
+  position: u64,
+}
+
+struct Shared {
+  state: Mutex<State>,
+  /// Notifies blocking readers that the state changed.
+  changed: Condvar,
+  /// Notifies asynchronous waiters that the state changed.
+  changed_async: Notify,
+}
+
+struct State {
+  data: Vec<u8>,
+  /// `None` while receiving, `Some(Ok(()))` when all audio was received, and `Some(Err(message))` when receiving failed.
+  end: Option<Result<(), String>>,
+}
+
+impl ReceivedAudio {
+  /// Creates received audio with a writer that appends chunks to it. `expected_length` is the length in bytes of the
+  /// audio if known, for reserving memory up front.
+  pub fn new(expected_length: Option<u64>) -> (Self, ReceivedAudioWriter) {
+    let data = Vec::with_capacity(expected_length.unwrap_or(0) as usize);
+    let shared = Arc::new(Shared {
+      state: Mutex::new(State { data, end: None }),
+      changed: Condvar::new(),
+      changed_async: Notify::new(),
+    });
+    (Self { shared: shared.clone() }, ReceivedAudioWriter { shared })
+  }
+
+  /// Creates a reader that reads this audio from the start.
+  pub fn reader(&self) -> ReceivedAudioReader {
+    ReceivedAudioReader { shared: self.shared.clone(), position: 0 }
+  }
+
+  /// Waits until all audio is received, returning a copy of it. Does not block the thread, for audio outputs that decode
+  /// audio entirely before playing it.
+  pub async fn received_data(&self) -> io::Result<Vec<u8>> {
+    loop {
+      // Create the notification before checking the state, so that a change in between is not missed.
+      let changed = self.shared.changed_async.notified();
+      {
+        let state = self.shared.state.lock().unwrap();
+        match &state.end {
+          Some(Ok(())) => return Ok(state.data.clone()),
+          Some(Err(message)) => return Err(receive_error(message)),
+          None => {}
+        }
+      }
+      changed.await;
+    }
+  }
+}
+
+impl ReceivedAudioWriter {
+  /// Appends `chunk` to the received audio.
+  pub fn push(&self, chunk: &[u8]) {
+    self.shared.update(|state| state.data.extend_from_slice(chunk));
+  }
+
+  /// Ends receiving, as all audio was received.
+  pub fn finish(self) {
+    self.shared.update(|state| state.end = Some(Ok(())));
+  }
+
+  /// Ends receiving, as it failed with `message`. Readers fail when they read past the audio that was received so far.
+  pub fn fail(self, message: String) {
+    self.shared.update(|state| state.end = Some(Err(message)));
+  }
+}
+
+impl Drop for ReceivedAudioWriter {
+  fn drop(&mut self) {
+    self.shared.update(|state| if state.end.is_none() {
+      state.end = Some(Err("receiving audio was cancelled".to_string()));
+    });
+  }
+}
+
+impl Shared {
+  fn update(&self, update_fn: impl FnOnce(&mut State)) {
+    update_fn(&mut self.state.lock().unwrap());
+    self.changed.notify_all();
+    self.changed_async.notify_waiters();
+  }
+
+  /// Waits until `ready_fn` returns true for the state, or until receiving ended.
+  fn wait_until(&self, ready_fn: impl Fn(&State) -> bool) -> MutexGuard<State> {
+    let state = self.state.lock().unwrap();
+    self.changed.wait_while(state, |state| state.end.is_none() && !ready_fn(state)).unwrap()
+  }
+}
+
+impl Read for ReceivedAudioReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if buf.is_empty() { return Ok(0); }
+    let position = self.position;
+    let state = self.shared.wait_until(|state| state.data.len() as u64 > position);
+    let start = position.min(state.data.len() as u64) as usize;
+    let available = &state.data[start..];
+    if available.is_empty() {
+      return match &state.end {
+        Some(Err(message)) => Err(receive_error(message)),
+        _ => Ok(0), // End of the received audio.
+      };
+    }
+    let length = available.len().min(buf.len());
+    buf[..length].copy_from_slice(&available[..length]);
+    self.position += length as u64;
+    Ok(length)
+  }
+}
+
+impl Seek for ReceivedAudioReader {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    let position = match pos {
+      SeekFrom::Start(offset) => Some(offset),
+      SeekFrom::Current(offset) => offset_position(self.position, offset),
+      SeekFrom::End(offset) => {
+        // The end is only known once all audio was received.
+        let state = self.shared.wait_until(|_| false);
+        if let Some(Err(message)) = &state.end {
+          return Err(receive_error(message));
+        }
+        offset_position(state.data.len() as u64, offset)
+      }
+    };
+    let position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))?;
+    self.position = position;
+    Ok(position)
+  }
+}
+
+/// Offsets `position` by `offset`, or returns `None` if the result is negative or overflows.
+fn offset_position(position: u64, offset: i64) -> Option<u64> {
+  i64::try_from(position).ok()?.checked_add(offset).and_then(|position| u64::try_from(position).ok())
+}
+
+fn receive_error(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::Other, format!("Failed to receive audio: {}", message))
+}
+
+impl Debug for ReceivedAudio {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let state = self.shared.state.lock().unwrap();
+    f.debug_struct("ReceivedAudio")
+      .field("received_length", &state.data.len())
+      .field("end", &state.end)
+      .finish()
+  }
+}
@@ -1,10 +1,13 @@
 #![feature(never_type)]
 
 use std::fmt::{Debug, Formatter};
-use std::io::Cursor;
-use std::sync::{Arc, Mutex};
+use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use cpal::traits::{DeviceTrait, HostTrait};
 use kira::{
   instance::{
     handle::InstanceHandle,
@@ -17,21 +20,32 @@ use kira::{
   manager::{
     AudioManager, AudioManagerSettings,
   },
+  mixer::effect::{
+    Effect,
+    EffectSettings,
+  },
+  parameter::Parameters,
   sound::{
     handle::SoundHandle,
     Sound,
     SoundSettings,
   },
+  Frame,
+  Tween,
   Value,
 };
 use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{error, info};
 
-pub use musium_audio_output::AudioOutput;
+pub use musium_audio_output::{AudioOutput, AudioSource, ChannelProcessing, MediaSource, PlaybackError};
+use musium_audio_output::ChannelProcessor;
 use musium_core::api::AudioCodec;
 
 #[derive(Clone)]
 pub struct KiraAudioOutput {
   inner: Arc<Mutex<Inner>>,
+  playback_errors: broadcast::Sender<PlaybackError>,
 }
 
 // Creation
@@ -40,18 +54,34 @@ pub struct KiraAudioOutput {
 pub enum KiraCreateError {
   #[error("Failed to create Kira audio manager")]
   AudioManagerCreateFail(#[from] kira::manager::error::SetupError),
+  #[error("Failed to add channel processing effect to the main track")]
+  AddChannelProcessingFail(#[from] kira::CommandError),
+  #[error("Failed to spawn output device monitor thread")]
+  DeviceMonitorSpawnFail(#[from] std::io::Error),
 }
 
 impl KiraAudioOutput {
   pub fn new() -> Result<Self, KiraCreateError> {
-    let audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+    let channel_processing = Arc::new(Mutex::new(ChannelProcessing::default()));
+    let audio_manager = create_audio_manager(&channel_processing)?;
     let inner = Arc::new(Mutex::new(Inner {
       audio_manager,
+      device_name: default_output_device_name(),
+      current_audio_data: None,
       current_sound_handle: None,
       current_instance_handle: None,
       current_volume: 1.0,
+      current_gain: 1.0,
+      current_fade_duration: 0.0,
+      channel_processing,
     }));
-    Ok(Self { inner })
+    let (playback_errors, _) = broadcast::channel(16);
+    let weak_inner = Arc::downgrade(&inner);
+    let monitor_playback_errors = playback_errors.clone();
+    thread::Builder::new()
+      .name("Kira output device monitor".to_string())
+      .spawn(move || monitor_device(weak_inner, monitor_playback_errors))?;
+    Ok(Self { inner, playback_errors })
   }
 }
 
@@ -61,6 +91,8 @@ impl KiraAudioOutput {
 pub enum KiraSetAudioDataError {
   #[error("No audio codec was specified, and Kira is not able to determine the codec automatically")]
   NoCodecFail,
+  #[error("Failed to read audio source")]
+  ReadFail(#[from] std::io::Error),
   #[error("Failed to load sound")]
   LoadSoundFail(#[from] kira::sound::error::SoundFromFileError),
   #[error("Failed to stop existing sound instance")]
@@ -74,17 +106,21 @@ pub enum KiraSetAudioDataError {
 #[async_trait]
 impl AudioOutput for KiraAudioOutput {
   type SetAudioDataError = KiraSetAudioDataError;
-  async fn set_audio_data(&self, codec: Option<AudioCodec>, data: Vec<u8>) -> Result<(), Self::SetAudioDataError> {
-    use AudioCodec::*;
+  async fn set_audio_source(&self, codec: Option<AudioCodec>, source: AudioSource) -> Result<(), Self::SetAudioDataError> {
     use KiraSetAudioDataError::*;
-    let cursor = Cursor::new(data);
     let codec = codec.ok_or(NoCodecFail)?;
-    let sound = match codec {
-      Mp3 => { Sound::from_mp3_reader(cursor, SoundSettings::default()) }
-      Ogg => { Sound::from_ogg_reader(cursor, SoundSettings::default()) }
-      Flac => { Sound::from_flac_reader(cursor, SoundSettings::default()) }
-      Wav => { Sound::from_wav_reader(cursor, SoundSettings::default()) }
-    }?;
+    // Kira decodes sounds entirely before playing them, so read streamed audio into memory, and wait until received audio
+    // is received entirely.
+    let data: Arc<[u8]> = match source {
+      AudioSource::Data(data) => data.into(),
+      AudioSource::Stream(mut source) => {
+        let mut data = Vec::new();
+        source.read_to_end(&mut data)?;
+        data.into()
+      }
+      AudioSource::Received(received) => received.received_data().await?.into(),
+    };
+    let sound = decode(&codec, data.clone())?;
     let mut inner = self.inner.lock().unwrap();
     if let Some(instance_handle) = &mut inner.current_instance_handle {
       instance_handle.stop(StopInstanceSettings::default())?;
@@ -96,6 +132,7 @@ impl AudioOutput for KiraAudioOutput {
       inner.audio_manager.free_unused_resources();
     }
     inner.current_sound_handle = Some(inner.audio_manager.add_sound(sound)?);
+    inner.current_audio_data = Some((codec, data));
     Ok(())
   }
 
@@ -114,11 +151,12 @@ impl AudioOutput for KiraAudioOutput {
   type PlayError = kira::CommandError;
   async fn play(&self) -> Result<(), Self::PlayError> {
     let mut inner = self.inner.lock().unwrap();
+    let fade_tween = inner.fade_tween();
     if let Some(instance_handle) = &mut inner.current_instance_handle {
-      instance_handle.resume(ResumeInstanceSettings::default())?;
+      instance_handle.resume(ResumeInstanceSettings::new().fade_tween(fade_tween))?;
       return Ok(());
     }
-    let current_volume = inner.current_volume;
+    let current_volume = inner.current_volume * inner.current_gain;
     if let Some(sound_handle) = &mut inner.current_sound_handle {
       let instance_handle = sound_handle.play(InstanceSettings {
         volume: Value::Fixed(current_volume),
@@ -148,8 +186,9 @@ impl AudioOutput for KiraAudioOutput {
   type PauseError = kira::CommandError;
   async fn pause(&self) -> Result<(), Self::PauseError> {
     let mut inner = self.inner.lock().unwrap();
+    let fade_tween = inner.fade_tween();
     if let Some(instance_handle) = &mut inner.current_instance_handle {
-      instance_handle.pause(PauseInstanceSettings::default())?;
+      instance_handle.pause(PauseInstanceSettings::new().fade_tween(fade_tween))?;
     }
     Ok(())
   }
@@ -158,18 +197,19 @@ impl AudioOutput for KiraAudioOutput {
   type TogglePlayError = kira::CommandError;
   async fn toggle_play(&self) -> Result<bool, Self::TogglePlayError> {
     let mut inner = self.inner.lock().unwrap();
+    let fade_tween = inner.fade_tween();
     if let Some(instance_handle) = &mut inner.current_instance_handle {
       let result = match instance_handle.state() {
         InstanceState::Playing => {
-          instance_handle.pause(PauseInstanceSettings::default())?;
+          instance_handle.pause(PauseInstanceSettings::new().fade_tween(fade_tween))?;
           true
         }
         InstanceState::Paused(_) => {
-          instance_handle.resume(ResumeInstanceSettings::default())?;
+          instance_handle.resume(ResumeInstanceSettings::new().fade_tween(fade_tween))?;
           true
         }
         InstanceState::Pausing(_) => {
-          instance_handle.resume(ResumeInstanceSettings::default())?;
+          instance_handle.resume(ResumeInstanceSettings::new().fade_tween(fade_tween))?;
           true
         }
         _ => false
@@ -199,8 +239,9 @@ impl AudioOutput for KiraAudioOutput {
   type StopError = kira::CommandError;
   async fn stop(&self) -> Result<(), Self::StopError> {
     let mut inner = self.inner.lock().unwrap();
+    let fade_tween = inner.fade_tween();
     if let Some(instance_handle) = &mut inner.current_instance_handle {
-      instance_handle.stop(StopInstanceSettings::default())?;
+      instance_handle.stop(StopInstanceSettings::new().fade_tween(fade_tween))?;
     }
     inner.audio_manager.free_unused_resources();
     Ok(())
@@ -280,22 +321,78 @@ impl AudioOutput for KiraAudioOutput {
   type SetVolumeError = kira::CommandError;
   async fn set_volume(&self, volume: f64) -> Result<(), Self::SetVolumeError> {
     let mut inner = self.inner.lock().unwrap();
+    let current_gain = inner.current_gain;
     if let Some(instance_handle) = &mut inner.current_instance_handle {
       let volume = volume.clamp(0.0, 1.0);
-      instance_handle.set_volume(Value::Fixed(volume))?;
+      instance_handle.set_volume(Value::Fixed(volume * current_gain))?;
     }
     inner.current_volume = volume;
     Ok(())
   }
+
+  type SetGainError = kira::CommandError;
+  async fn set_gain(&self, gain: f64) -> Result<(), Self::SetGainError> {
+    let mut inner = self.inner.lock().unwrap();
+    let current_volume = inner.current_volume;
+    if let Some(instance_handle) = &mut inner.current_instance_handle {
+      instance_handle.set_volume(Value::Fixed(current_volume * gain))?;
+    }
+    inner.current_gain = gain;
+    Ok(())
+  }
+
+  type SetChannelProcessingError = !;
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), Self::SetChannelProcessingError> {
+    let inner = self.inner.lock().unwrap();
+    *inner.channel_processing.lock().unwrap() = processing;
+    Ok(())
+  }
+
+  type SetFadeDurationError = !;
+  async fn set_fade_duration(&self, fade_duration: f64) -> Result<(), Self::SetFadeDurationError> {
+    let mut inner = self.inner.lock().unwrap();
+    inner.current_fade_duration = fade_duration.max(0.0);
+    Ok(())
+  }
+
+  type GetOutputDeviceNameError = !;
+  async fn get_output_device_name(&self) -> Result<Option<String>, Self::GetOutputDeviceNameError> {
+    let inner = self.inner.lock().unwrap();
+    Ok(inner.device_name.clone())
+  }
+
+  fn subscribe_playback_errors(&self) -> broadcast::Receiver<PlaybackError> {
+    self.playback_errors.subscribe()
+  }
 }
 
 // Internals
 
 struct Inner {
   audio_manager: AudioManager,
+  /// Name of the output device that the audio manager plays on, or `None` if there was no output device.
+  device_name: Option<String>,
+  /// Audio data of the current sound, kept to recreate the sound when the audio manager is recreated.
+  current_audio_data: Option<(AudioCodec, Arc<[u8]>)>,
   current_sound_handle: Option<SoundHandle>,
   current_instance_handle: Option<InstanceHandle>,
   current_volume: f64,
+  current_gain: f64,
+  /// Duration in seconds of fades when pausing, resuming, and stopping.
+  current_fade_duration: f64,
+  /// Channel processing, shared with the channel processing effect on the main track of the audio manager.
+  channel_processing: Arc<Mutex<ChannelProcessing>>,
+}
+
+impl Inner {
+  /// Gets the tween of fades when pausing, resuming, and stopping, or `None` to not fade.
+  fn fade_tween(&self) -> Option<Tween> {
+    if self.current_fade_duration > 0.0 {
+      Some(Tween::linear(self.current_fade_duration))
+    } else {
+      None
+    }
+  }
 }
 
 impl Debug for KiraAudioOutput {
@@ -304,3 +401,126 @@ impl Debug for KiraAudioOutput {
       .finish()
   }
 }
+
+fn decode(codec: &AudioCodec, data: Arc<[u8]>) -> Result<Sound, kira::sound::error::SoundFromFileError> {
+  use AudioCodec::*;
+  let cursor = Cursor::new(data);
+  match codec {
+    Mp3 => { Sound::from_mp3_reader(cursor, SoundSettings::default()) }
+    Ogg => { Sound::from_ogg_reader(cursor, SoundSettings::default()) }
+    Flac => { Sound::from_flac_reader(cursor, SoundSettings::default()) }
+    Wav => { Sound::from_wav_reader(cursor, SoundSettings::default()) }
+  }
+}
+
+// Channel processing
+
+/// Creates an audio manager on the default output device, with an effect on its main track that applies
+/// `channel_processing`.
+fn create_audio_manager(channel_processing: &Arc<Mutex<ChannelProcessing>>) -> Result<AudioManager, KiraCreateError> {
+  let audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+  let effect = ChannelProcessingEffect { processing: channel_processing.clone(), processor: ChannelProcessor::default() };
+  audio_manager.main_track().add_effect(effect, EffectSettings::default())?;
+  Ok(audio_manager)
+}
+
+/// Effect that applies channel processing, picking up changes to the channel processing while playing.
+#[derive(Debug)]
+struct ChannelProcessingEffect {
+  processing: Arc<Mutex<ChannelProcessing>>,
+  processor: ChannelProcessor,
+}
+
+impl Effect for ChannelProcessingEffect {
+  fn process(&mut self, dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+    self.processor.update_from(&self.processing);
+    let sample_rate = (1.0 / dt).round() as u32;
+    let (left, right) = self.processor.process(sample_rate, input.left, input.right);
+    Frame::new(left, right)
+  }
+}
+
+// Output device monitoring
+
+/// Interval between checks whether the default output device changed, for example because headphones were unplugged
+/// or the system resumed from sleep.
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Error)]
+enum RecreateError {
+  #[error("Failed to create Kira audio manager")]
+  AudioManagerCreateFail(#[from] KiraCreateError),
+  #[error("Failed to load sound")]
+  LoadSoundFail(#[from] kira::sound::error::SoundFromFileError),
+  #[error("Failed to add sound to audio manager")]
+  AddSoundFail(#[from] kira::manager::error::AddSoundError),
+  #[error("Failed to play sound")]
+  PlayFail(#[from] kira::CommandError),
+}
+
+/// Checks whether the default output device changed until the audio output is dropped.
+fn monitor_device(inner: Weak<Mutex<Inner>>, playback_errors: broadcast::Sender<PlaybackError>) {
+  // OK in sends: no subscribers -> we don't care.
+  loop {
+    thread::sleep(DEVICE_CHECK_INTERVAL);
+    let inner = match inner.upgrade() {
+      Some(inner) => inner,
+      None => break, // Audio output was dropped -> stop monitoring.
+    };
+    let device_name = default_output_device_name();
+    let mut inner = inner.lock().unwrap();
+    if device_name == inner.device_name { continue; }
+    if device_name.is_none() {
+      info!(previous_device = ?inner.device_name, "Output device disappeared; waiting for a new output device");
+      inner.device_name = None;
+      playback_errors.send(PlaybackError::DeviceDisconnected).ok();
+      continue;
+    }
+    info!(previous_device = ?inner.device_name, device = ?device_name, "Default output device changed; recreating audio manager");
+    match inner.recreate_audio_manager() {
+      Ok(_) => inner.device_name = device_name,
+      Err(e) => {
+        error!(error = ?e, "Failed to recreate audio manager; retrying at the next device check");
+        playback_errors.send(PlaybackError::DeviceFail(e.to_string())).ok();
+      }
+    }
+  }
+}
+
+impl Inner {
+  /// Recreates the audio manager on the default output device, continuing playback of the current sound at the same
+  /// position. The stream of a device that disappeared stays dead, so it must be recreated to continue playback.
+  fn recreate_audio_manager(&mut self) -> Result<(), RecreateError> {
+    let mut audio_manager = create_audio_manager(&self.channel_processing)?;
+    let (sound_handle, instance_handle) = match &self.current_audio_data {
+      Some((codec, data)) => {
+        let mut sound_handle = audio_manager.add_sound(decode(codec, data.clone())?)?;
+        let instance_handle = match &self.current_instance_handle {
+          Some(previous) if !matches!(previous.state(), InstanceState::Stopped | InstanceState::Stopping) => {
+            let mut instance_handle = sound_handle.play(InstanceSettings {
+              volume: Value::Fixed(self.current_volume * self.current_gain),
+              start_position: previous.position(),
+              ..InstanceSettings::default()
+            })?;
+            if let InstanceState::Paused(_) | InstanceState::Pausing(_) = previous.state() {
+              instance_handle.pause(PauseInstanceSettings::default())?;
+            }
+            Some(instance_handle)
+          }
+          _ => None,
+        };
+        (Some(sound_handle), instance_handle)
+      }
+      None => (None, None),
+    };
+    // Replacing the audio manager drops the previous one, which closes the stream on the previous device.
+    self.audio_manager = audio_manager;
+    self.current_sound_handle = sound_handle;
+    self.current_instance_handle = instance_handle;
+    Ok(())
+  }
+}
+
+fn default_output_device_name() -> Option<String> {
+  cpal::default_host().default_output_device().and_then(|device| device.name().ok())
+}
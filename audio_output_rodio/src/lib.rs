@@ -2,23 +2,44 @@
 
 use std::fmt::{Debug, Formatter};
 use std::io::Cursor;
-use std::sync::Arc;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc as std_mpsc;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use rodio::{OutputStream, OutputStreamHandle, Sink};
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use rodio::cpal::{SampleRate, SupportedStreamConfig};
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
 use thiserror::Error;
-use tokio::sync::{mpsc, oneshot};
-use tracing::instrument;
+use tokio::runtime::Runtime;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{error, info, instrument, warn};
 
-pub use musium_audio_output::AudioOutput;
+pub use musium_audio_output::{AudioOutput, AudioSource, ChannelProcessing, MediaSource, PlaybackError, ReceivedAudio};
+use musium_audio_output::ChannelProcessor;
 use musium_core::api::AudioCodec;
-use musium_core::panic::try_panic_into_string;
-
+use musium_core::panic::{panic_into_string, try_panic_into_string};
+
+/// Audio output that plays audio with Rodio in a worker thread.
+///
+/// Requests are sent to the worker thread over a bounded channel, so that a caller that sends requests faster than the
+/// worker handles them cannot queue an unbounded amount of requests in memory. The overflow policy is:
+///
+/// - Setting audio data keeps at most one pending audio data payload. Setting audio data while a previous payload is
+///   still pending replaces that payload, failing the previous request with `SupersededFail`.
+/// - Setting the volume, gain, or channel processing is coalesced: only the latest pending value is applied, and setting it never waits.
+/// - Other requests wait for room in the channel when it is full, applying backpressure to the caller.
+///
+/// Rodio does not support seeking, so there are no seek requests to coalesce.
 #[derive(Clone)]
 pub struct RodioAudioOutput {
-  tx: mpsc::UnboundedSender<Request>,
+  tx: mpsc::Sender<Request>,
+  coalesced: Arc<Mutex<Coalesced>>,
+  errors: broadcast::Sender<RodioWorkerError>,
+  playback_errors: broadcast::Sender<PlaybackError>,
   worker_thread: Arc<thread::JoinHandle<()>>,
 }
 
@@ -28,16 +49,78 @@ pub struct RodioAudioOutput {
 pub enum RodioCreateError {
   #[error("Failed to create Rodio stream")]
   StreamCreateFail(#[from] rodio::StreamError),
+  #[error("Failed to create runtime for receiving requests in the worker thread")]
+  RuntimeCreateFail(#[from] std::io::Error),
+}
+
+/// Options of the Rodio audio output.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RodioOptions {
+  /// Whether to play audio at its native sample rate, switching the sample rate of the output stream whenever audio with
+  /// a different sample rate is played, so that the audio is not resampled. Falls back to resampling when the output
+  /// device does not support the native sample rate. The output stream is still shared with other applications through
+  /// the mixer of the operating system, as cpal does not support exclusive mode (WASAPI exclusive mode or CoreAudio hog
+  /// mode), so playback is only bit-perfect if that mixer passes audio through unchanged.
+  pub native_sample_rate: bool,
+  /// Duration of audio to decode ahead of playback on a separate thread, or `None` to decode audio while playing it on
+  /// the audio thread of the output stream. A longer duration makes underruns (stutters) less likely when decoding is
+  /// slow, such as on slow or busy machines, at the cost of picking up changes to the channel processing later and
+  /// using more memory. Underruns are counted in the `audio_output.underruns` metric, and reported as a playback error
+  /// once per track. The buffer size of the output stream itself cannot be configured, as Rodio does not support it.
+  pub decode_ahead: Option<Duration>,
 }
 
 impl RodioAudioOutput {
   pub async fn new() -> Result<Self, RodioCreateError> {
-    let (tx, rx) = mpsc::unbounded_channel();
-    let (create_result_tx, create_result_rx) = oneshot::channel();
-    let worker_thread = WorkerThread::new(create_result_tx, rx);
+    Self::new_with_options(RodioOptions::default()).await
+  }
+
+  pub async fn new_with_options(options: RodioOptions) -> Result<Self, RodioCreateError> {
+    let (audio_output, create_result_rx) = Self::spawn(options);
     create_result_rx.await.unwrap()?; // UNWRAP: errors if disconnected which only happens in panic -> we panic as well.
+    Ok(audio_output)
+  }
+
+  /// Creates a Rodio audio output with `options`, blocking until its output stream is created. Must not be called from
+  /// an asynchronous context, as it blocks the thread.
+  pub fn new_blocking_with_options(options: RodioOptions) -> Result<Self, RodioCreateError> {
+    let (audio_output, create_result_rx) = Self::spawn(options);
+    create_result_rx.blocking_recv().unwrap()?; // UNWRAP: errors if disconnected which only happens in panic -> we panic as well.
+    Ok(audio_output)
+  }
+
+  fn spawn(options: RodioOptions) -> (Self, oneshot::Receiver<Result<(), RodioCreateError>>) {
+    let (tx, rx) = mpsc::channel(REQUEST_CHANNEL_CAPACITY);
+    let coalesced = Arc::new(Mutex::new(Coalesced::default()));
+    let (errors, _) = broadcast::channel(16);
+    let (playback_errors, _) = broadcast::channel(16);
+    let (create_result_tx, create_result_rx) = oneshot::channel();
+    let worker_thread = WorkerThread::new(options, create_result_tx, rx, coalesced.clone(), errors.clone(), playback_errors.clone());
     let worker_thread = Arc::new(worker_thread);
-    Ok(Self { tx, worker_thread })
+    (Self { tx, coalesced, errors, playback_errors, worker_thread }, create_result_rx)
+  }
+}
+
+// Error events
+
+/// Error that occurred in the worker thread, outside of handling a specific request.
+#[derive(Clone, Debug, Error)]
+pub enum RodioWorkerError {
+  #[error("Rodio worker panicked with message: {0}; restarted the worker and restored playback")]
+  PanickedAndRestarted(String),
+  #[error("Rodio worker panicked with message: {0}; restarted the worker, but failed to restore playback: {1}")]
+  PanickedAndRestoreFailed(String, String),
+  #[error("Rodio worker panicked {0} times in a row; stopped the worker")]
+  Stopped(u32),
+}
+
+impl RodioAudioOutput {
+  /// Subscribes to errors that occur in the worker thread. When the worker thread panics while handling a request, that
+  /// request fails, and the worker is restarted, restoring the audio data, position, and volume. Subscribers are
+  /// informed of each restart so they can inform the user and retry the failed request. After too many panics in a
+  /// row, the worker thread stops and all requests fail.
+  pub fn on_error(&self) -> broadcast::Receiver<RodioWorkerError> {
+    self.errors.subscribe()
   }
 }
 
@@ -61,7 +144,7 @@ impl RodioAudioOutput {
   /// worker thread (if any), and does not wait for the worker thread to complete first.
   pub fn destroy(self) -> Result<(), RodioDestroyError> {
     use RodioDestroyError::*;
-    let RodioAudioOutput { tx, worker_thread } = self;
+    let RodioAudioOutput { tx, worker_thread, .. } = self;
     drop(tx); // Dropping sender will cause the worker thread to break out of the loop and stop.
     let worker_thread = Arc::try_unwrap(worker_thread).map_err(|_| ClonesStillExist)?;
     if let Err(e) = worker_thread.join() { // Join does not block because worker thread stopped.
@@ -89,6 +172,10 @@ pub enum RodioSetAudioDataError {
   SendCommandFail,
   #[error("Failed to receive command feedback; worker thread was stopped")]
   ReceiveCommandFeedbackFail,
+  #[error("Audio data was superseded by newer audio data before it was set")]
+  SupersededFail,
+  #[error("Failed to spawn thread for decoding audio ahead of playback")]
+  DecodeThreadSpawnFail(#[source] std::io::Error),
 }
 
 #[derive(Debug, Error)]
@@ -102,11 +189,14 @@ pub enum RodioError {
 #[async_trait]
 impl AudioOutput for RodioAudioOutput {
   type SetAudioDataError = RodioSetAudioDataError;
-  #[instrument(skip(self, data))]
-  async fn set_audio_data(&self, _codec: Option<AudioCodec>, data: Vec<u8>) -> Result<(), RodioSetAudioDataError> {
+  #[instrument(skip(self, source))]
+  async fn set_audio_source(&self, _codec: Option<AudioCodec>, source: AudioSource) -> Result<(), RodioSetAudioDataError> {
     use RodioSetAudioDataError::*;
     let (tx, rx) = oneshot::channel();
-    self.tx.send(Request::SetAudioData { data, tx }).map_err(|_| SendCommandFail)?;
+    let superseded = self.coalesce(|c| c.audio_source.replace((source, tx))).await.map_err(|_| SendCommandFail)?;
+    if let Some((_, superseded_tx)) = superseded {
+      superseded_tx.send(Err(SupersededFail)).ok(); // OK: receiver hung up -> we don't care.
+    }
     rx.await.map_err(|_| ReceiveCommandFeedbackFail)?
   }
 
@@ -183,7 +273,31 @@ impl AudioOutput for RodioAudioOutput {
 
   type SetVolumeError = RodioError;
   async fn set_volume(&self, volume: f64) -> Result<(), Self::SetVolumeError> {
-    self.send_receive(move |tx| Request::SetVolume { volume, tx }).await
+    self.coalesce(|c| c.volume = Some(volume)).await
+  }
+
+  type SetGainError = RodioError;
+  async fn set_gain(&self, gain: f64) -> Result<(), Self::SetGainError> {
+    self.coalesce(|c| c.gain = Some(gain)).await
+  }
+
+  type SetChannelProcessingError = RodioError;
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), Self::SetChannelProcessingError> {
+    self.coalesce(|c| c.channel_processing = Some(processing)).await
+  }
+
+  type SetFadeDurationError = !;
+  async fn set_fade_duration(&self, _fade_duration: f64) -> Result<(), Self::SetFadeDurationError> {
+    Ok(()) // Rodio sinks do not support fading.
+  }
+
+  type GetOutputDeviceNameError = RodioError;
+  async fn get_output_device_name(&self) -> Result<Option<String>, Self::GetOutputDeviceNameError> {
+    self.send_receive(|tx| Request::GetOutputDeviceName { tx }).await
+  }
+
+  fn subscribe_playback_errors(&self) -> broadcast::Receiver<PlaybackError> {
+    self.playback_errors.subscribe()
   }
 }
 
@@ -193,9 +307,26 @@ impl RodioAudioOutput {
   async fn send_receive<T>(&self, request_fn: impl FnOnce(oneshot::Sender<T>) -> Request) -> Result<T, RodioError> {
     use RodioError::*;
     let (tx, rx) = oneshot::channel();
-    self.tx.send(request_fn(tx)).map_err(|_| SendCommandFail)?;
+    self.tx.send(request_fn(tx)).await.map_err(|_| SendCommandFail)?;
     Ok(rx.await.map_err(|_| ReceiveCommandFeedbackFail)?)
   }
+
+  /// Stores a request in the coalesced requests with `store_fn`, notifying the worker thread if no coalesced requests
+  /// were pending. When requests were already pending, the worker thread was already notified and will apply this
+  /// request along with the pending ones.
+  async fn coalesce<T>(&self, store_fn: impl FnOnce(&mut Coalesced) -> T) -> Result<T, RodioError> {
+    let (result, notify) = {
+      let mut coalesced = self.coalesced.lock().unwrap();
+      let notify = coalesced.is_empty();
+      (store_fn(&mut coalesced), notify)
+    };
+    if notify {
+      self.tx.send(Request::ApplyCoalesced).await.map_err(|_| RodioError::SendCommandFail)?;
+    } else if self.tx.is_closed() {
+      return Err(RodioError::SendCommandFail);
+    }
+    Ok(result)
+  }
 }
 
 impl Debug for RodioAudioOutput {
@@ -207,8 +338,12 @@ impl Debug for RodioAudioOutput {
 
 // Messages
 
+/// Capacity of the request channel. Senders wait for room when the channel is full.
+const REQUEST_CHANNEL_CAPACITY: usize = 64;
+
 enum Request {
-  SetAudioData { data: Vec<u8>, tx: oneshot::Sender<Result<(), RodioSetAudioDataError>> },
+  /// Applies the requests in `Coalesced`.
+  ApplyCoalesced,
   IsPlaying { tx: oneshot::Sender<bool> },
   Play { tx: oneshot::Sender<()> },
   IsPaused { tx: oneshot::Sender<bool> },
@@ -217,25 +352,78 @@ enum Request {
   IsStopped { tx: oneshot::Sender<bool> },
   Stop { tx: oneshot::Sender<()> },
   GetVolume { tx: oneshot::Sender<f64> },
-  SetVolume { volume: f64, tx: oneshot::Sender<()> },
+  GetOutputDeviceName { tx: oneshot::Sender<Option<String>> },
+}
+
+/// Requests that are superseded by newer requests of the same kind. Instead of queueing these requests in the request
+/// channel, only the latest request of each kind is kept here, to be applied by the worker thread.
+#[derive(Default)]
+struct Coalesced {
+  audio_source: Option<(AudioSource, oneshot::Sender<Result<(), RodioSetAudioDataError>>)>,
+  volume: Option<f64>,
+  gain: Option<f64>,
+  channel_processing: Option<ChannelProcessing>,
+}
+
+impl Coalesced {
+  fn is_empty(&self) -> bool {
+    self.audio_source.is_none() && self.volume.is_none() && self.gain.is_none() && self.channel_processing.is_none()
+  }
 }
 
 // Worker thread
 
+/// Interval between checks whether the default output device changed, for example because headphones were unplugged
+/// or the system resumed from sleep.
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Number of times in a row the worker may panic and be restarted before it stops.
+const MAX_CONSECUTIVE_PANICS: u32 = 5;
+
 struct WorkerThread {
   _stream: OutputStream,
   handle: OutputStreamHandle,
+  /// Name of the output device that the stream plays on, or `None` if there was no output device.
+  device_name: Option<String>,
+  last_device_check: Instant,
+  options: RodioOptions,
+  /// Sample rate that the stream was switched to for playing audio at its native sample rate, or `None` if the stream
+  /// has the default sample rate of the output device.
+  stream_sample_rate: Option<u32>,
   sink: Option<Sink>,
+  /// Audio that is playing, kept to recreate the sink when the output stream is recreated. `None` when streamed audio is
+  /// playing.
+  audio_data: Option<ReplayableAudio>,
+  /// Playback time of the audio data before it was last resumed.
+  elapsed: Duration,
+  /// Instant the audio data was last resumed, or `None` if it is paused or stopped.
+  resumed_at: Option<Instant>,
   volume: f64,
-  rx: mpsc::UnboundedReceiver<Request>,
+  gain: f64,
+  /// Channel processing, shared with the sources of sinks, which pick up changes while playing.
+  channel_processing: Arc<Mutex<ChannelProcessing>>,
+  runtime: Runtime,
+  rx: mpsc::Receiver<Request>,
+  coalesced: Arc<Mutex<Coalesced>>,
+  errors: broadcast::Sender<RodioWorkerError>,
+  playback_errors: broadcast::Sender<PlaybackError>,
 }
 
 impl WorkerThread {
-  fn new(create_result_tx: oneshot::Sender<Result<(), RodioCreateError>>, rx: mpsc::UnboundedReceiver<Request>) -> JoinHandle<()> {
+  fn new(
+    options: RodioOptions,
+    create_result_tx: oneshot::Sender<Result<(), RodioCreateError>>,
+    rx: mpsc::Receiver<Request>,
+    coalesced: Arc<Mutex<Coalesced>>,
+    errors: broadcast::Sender<RodioWorkerError>,
+    playback_errors: broadcast::Sender<PlaybackError>,
+  ) -> JoinHandle<()> {
     thread::spawn(move || {
-      let result: Result<_, RodioCreateError> = rodio::OutputStream::try_default()
-        .map_err(|e| e.into());
-      let (_stream, handle) = match result {
+      let result: Result<_, RodioCreateError> = (|| {
+        let (stream, handle) = rodio::OutputStream::try_default()?;
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+        Ok((stream, handle, runtime))
+      })();
+      let (_stream, handle, runtime) = match result {
         Ok(v) => {
           // UNWRAP: errors if disconnected which only happens in panic -> we panic as well.
           create_result_tx.send(Ok(())).unwrap();
@@ -250,9 +438,22 @@ impl WorkerThread {
       let worker_thread = WorkerThread {
         _stream,
         handle,
+        device_name: default_output_device_name(),
+        last_device_check: Instant::now(),
+        options,
+        stream_sample_rate: None,
         sink: None,
+        audio_data: None,
+        elapsed: Duration::ZERO,
+        resumed_at: None,
         volume: 1.0,
+        gain: 1.0,
+        channel_processing: Arc::new(Mutex::new(ChannelProcessing::default())),
+        runtime,
         rx,
+        coalesced,
+        errors,
+        playback_errors,
       };
       worker_thread.run();
     })
@@ -260,32 +461,101 @@ impl WorkerThread {
 
   #[instrument(skip(self))]
   fn run(mut self) {
-    while let Some(request) = self.rx.blocking_recv() { // Loop until all senders disconnect.
-      // OK: in matches: receiver hung up -> we don't care.
-      match request {
-        Request::SetAudioData { data, tx } => tx.send(self.set_audio_data(data)).ok(),
-        Request::IsPlaying { tx } => tx.send(self.is_playing()).ok(),
-        Request::Play { tx } => tx.send(self.play()).ok(),
-        Request::IsPaused { tx } => tx.send(self.is_paused()).ok(),
-        Request::Pause { tx } => tx.send(self.pause()).ok(),
-        Request::TogglePlay { tx } => tx.send(self.toggle_play()).ok(),
-        Request::IsStopped { tx } => tx.send(self.is_stopped()).ok(),
-        Request::Stop { tx } => tx.send(self.stop()).ok(),
-        Request::GetVolume { tx } => tx.send(self.get_volume()).ok(),
-        Request::SetVolume { volume, tx } => tx.send(self.set_volume(volume)).ok(),
+    let mut consecutive_panics = 0;
+    loop {
+      // Wait for a request, but wake up in time to check whether the default output device changed.
+      let timeout = DEVICE_CHECK_INTERVAL.saturating_sub(self.last_device_check.elapsed());
+      let received = self.runtime.block_on(tokio::time::timeout(timeout, self.rx.recv()));
+      let request = match received {
+        Ok(Some(request)) => Some(request),
+        Ok(None) => break, // Loop until all senders disconnect.
+        Err(_) => None, // Timed out.
       };
+      // Catch panics so that a single failing request does not stop the worker. The sender of a request that panics is
+      // dropped while unwinding, so the request fails with a `ReceiveCommandFeedbackFail` error.
+      let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        if self.last_device_check.elapsed() >= DEVICE_CHECK_INTERVAL {
+          self.check_device();
+        }
+        if let Some(request) = request {
+          self.handle_request(request);
+        }
+      }));
+      match result {
+        Ok(_) => consecutive_panics = 0,
+        Err(panic) => {
+          consecutive_panics += 1;
+          let message = panic_into_string(panic);
+          if consecutive_panics >= MAX_CONSECUTIVE_PANICS {
+            error!(message = %message, "Worker panicked {} times in a row; stopping worker", consecutive_panics);
+            let error = RodioWorkerError::Stopped(consecutive_panics);
+            self.send_playback_error(PlaybackError::OutputFail(error.to_string()));
+            self.errors.send(error).ok(); // OK: no subscribers -> we don't care.
+            break;
+          }
+          warn!(message = %message, "Worker panicked; restarting worker");
+          let error = match panic::catch_unwind(AssertUnwindSafe(|| self.restart())) {
+            Ok(Ok(_)) => RodioWorkerError::PanickedAndRestarted(message),
+            Ok(Err(e)) => RodioWorkerError::PanickedAndRestoreFailed(message, e.to_string()),
+            Err(restart_panic) => RodioWorkerError::PanickedAndRestoreFailed(message, panic_into_string(restart_panic)),
+          };
+          self.send_playback_error(match &error {
+            RodioWorkerError::PanickedAndRestarted(_) => PlaybackError::OutputRecovered(error.to_string()),
+            _ => PlaybackError::OutputFail(error.to_string()),
+          });
+          self.errors.send(error).ok(); // OK: no subscribers -> we don't care.
+        }
+      }
+    }
+  }
+
+  fn handle_request(&mut self, request: Request) {
+    // OK: in matches: receiver hung up -> we don't care.
+    match request {
+      Request::ApplyCoalesced => Some(self.apply_coalesced()),
+      Request::IsPlaying { tx } => tx.send(self.is_playing()).ok(),
+      Request::Play { tx } => tx.send(self.play()).ok(),
+      Request::IsPaused { tx } => tx.send(self.is_paused()).ok(),
+      Request::Pause { tx } => tx.send(self.pause()).ok(),
+      Request::TogglePlay { tx } => tx.send(self.toggle_play()).ok(),
+      Request::IsStopped { tx } => tx.send(self.is_stopped()).ok(),
+      Request::Stop { tx } => tx.send(self.stop()).ok(),
+      Request::GetVolume { tx } => tx.send(self.get_volume()).ok(),
+      Request::GetOutputDeviceName { tx } => tx.send(self.device_name.clone()).ok(),
+    };
+  }
+
+  fn apply_coalesced(&mut self) {
+    let coalesced = std::mem::take(&mut *self.coalesced.lock().unwrap());
+    // Apply volume and gain first, so that a sink created for new audio data starts with the latest volume.
+    if let Some(gain) = coalesced.gain { self.set_gain(gain); }
+    if let Some(volume) = coalesced.volume { self.set_volume(volume); }
+    if let Some(processing) = coalesced.channel_processing { self.set_channel_processing(processing); }
+    if let Some((source, tx)) = coalesced.audio_source {
+      tx.send(self.set_audio_source(source)).ok(); // OK: receiver hung up -> we don't care.
     }
   }
 
-  #[instrument(skip(self, data))]
-  fn set_audio_data(&mut self, data: Vec<u8>) -> Result<(), RodioSetAudioDataError> {
+  #[instrument(skip(self, source))]
+  fn set_audio_source(&mut self, source: AudioSource) -> Result<(), RodioSetAudioDataError> {
     if let Some(sink) = &self.sink { sink.stop(); }
-    let sink = Sink::try_new(&self.handle)?;
-    sink.set_volume(self.volume as f32);
-    let cursor = Cursor::new(data);
-    let decoder = rodio::decoder::Decoder::new(cursor)?;
-    sink.append(decoder);
+    // Keep audio data that is in memory or being received, so that it can be played again when the sink is recreated.
+    // Streamed audio is decoded progressively while it is read, and cannot be played again.
+    let (sink, data) = match source {
+      AudioSource::Data(data) => {
+        let data = ReplayableAudio::Data(data.into());
+        (self.create_sink(data.reader(), Duration::ZERO)?, Some(data))
+      }
+      AudioSource::Received(received) => {
+        let data = ReplayableAudio::Received(received);
+        (self.create_sink(data.reader(), Duration::ZERO)?, Some(data))
+      }
+      AudioSource::Stream(source) => (self.create_sink(source, Duration::ZERO)?, None),
+    };
     self.sink = Some(sink);
+    self.audio_data = data;
+    self.elapsed = Duration::ZERO;
+    self.resumed_at = Some(Instant::now());
     Ok(())
   }
 
@@ -293,9 +563,10 @@ impl WorkerThread {
     !self.is_paused()
   }
 
-  fn play(&self) {
+  fn play(&mut self) {
     if let Some(sink) = &self.sink {
       sink.play();
+      self.resumed_at.get_or_insert_with(Instant::now);
     }
   }
 
@@ -303,28 +574,30 @@ impl WorkerThread {
     self.sink.as_ref().map(|s| s.is_paused()).unwrap_or(false)
   }
 
-  fn pause(&self) {
+  fn pause(&mut self) {
     if let Some(sink) = &self.sink {
       sink.pause();
+      self.elapsed = self.position();
+      self.resumed_at = None;
     }
   }
 
-  fn toggle_play(&self) -> bool {
-    return if let Some(sink) = &self.sink {
-      return if sink.is_paused() {
-        sink.play();
-        true
-      } else {
-        sink.pause();
-        false
-      };
+  fn toggle_play(&mut self) -> bool {
+    if self.sink.is_none() {
+      return false;
+    }
+    if self.is_paused() {
+      self.play();
+      true
     } else {
+      self.pause();
       false
-    };
+    }
   }
 
   fn is_stopped(&self) -> bool {
-    self.sink.is_none()
+    // A sink that played all its audio has stopped as well.
+    self.sink.as_ref().map_or(true, |sink| sink.empty())
   }
 
   fn stop(&mut self) {
@@ -332,6 +605,9 @@ impl WorkerThread {
       sink.stop();
     }
     self.sink = None;
+    self.audio_data = None;
+    self.elapsed = Duration::ZERO;
+    self.resumed_at = None;
   }
 
   fn get_volume(&self) -> f64 {
@@ -341,7 +617,375 @@ impl WorkerThread {
   fn set_volume(&mut self, volume: f64) {
     self.volume = volume.clamp(0.0, 1.0);
     if let Some(sink) = &self.sink {
-      sink.set_volume(volume as f32);
+      sink.set_volume((self.volume * self.gain) as f32);
+    }
+  }
+
+  fn set_gain(&mut self, gain: f64) {
+    self.gain = gain;
+    if let Some(sink) = &self.sink {
+      sink.set_volume((self.volume * self.gain) as f32);
+    }
+  }
+
+  fn set_channel_processing(&mut self, processing: ChannelProcessing) {
+    // UNWRAP: errors if a holder of the lock has panicked -> we panic as well.
+    *self.channel_processing.lock().unwrap() = processing;
+  }
+
+  /// Restarts the worker after a panic by recreating the output stream and sink, as a panic may have left them in an
+  /// inconsistent state. Continues playback of the audio data at the same position, with the same volume.
+  fn restart(&mut self) -> Result<(), RodioSetAudioDataError> {
+    let is_paused = self.is_paused();
+    let position = self.position();
+    if let Some(sink) = self.sink.take() { sink.stop(); }
+    match rodio::OutputStream::try_default() {
+      Ok((stream, handle)) => {
+        self._stream = stream;
+        self.handle = handle;
+        self.device_name = default_output_device_name();
+        self.stream_sample_rate = None;
+      }
+      Err(e) => warn!(error = ?e, "Failed to recreate output stream while restarting worker; keeping the previous stream"),
+    }
+    if let Some(data) = self.audio_data.clone() {
+      let sink = self.create_sink(data.reader(), position)?;
+      if is_paused { sink.pause(); }
+      self.sink = Some(sink);
+    }
+    Ok(())
+  }
+
+  /// Creates a sink on the current output stream that plays `source` from `position`. First switches the output stream
+  /// to the sample rate of `source` when playing at the native sample rate.
+  fn create_sink(&mut self, source: impl MediaSource + 'static, position: Duration) -> Result<Sink, RodioSetAudioDataError> {
+    let decoder = rodio::decoder::Decoder::new(source)?;
+    if self.options.native_sample_rate {
+      self.switch_sample_rate(decoder.sample_rate(), decoder.channels());
+    }
+    let sink = Sink::try_new(&self.handle)?;
+    sink.set_volume((self.volume * self.gain) as f32);
+    let source = decoder.skip_duration(position);
+    match self.options.decode_ahead {
+      Some(decode_ahead) => {
+        let source = DecodeAheadSource::new(source, decode_ahead, self.playback_errors.clone())
+          .map_err(RodioSetAudioDataError::DecodeThreadSpawnFail)?;
+        sink.append(ChannelProcessingSource::new(source, self.channel_processing.clone()));
+      }
+      None => sink.append(ChannelProcessingSource::new(source, self.channel_processing.clone())),
+    }
+    Ok(sink)
+  }
+
+  /// Recreates the output stream with `sample_rate` and `channels` if the stream does not have that sample rate yet and
+  /// the output device supports it. Sinks of the previous stream stop playing, so this must be called before creating a
+  /// sink.
+  fn switch_sample_rate(&mut self, sample_rate: u32, channels: u16) {
+    if self.stream_sample_rate == Some(sample_rate) { return; }
+    let device = match rodio::cpal::default_host().default_output_device() {
+      Some(device) => device,
+      None => return, // No output device -> creating the sink fails instead.
+    };
+    let config = match native_stream_config(&device, sample_rate, channels) {
+      Some(config) => config,
+      None => {
+        info!(sample_rate, channels, "Output device does not support the native sample rate of the audio; resampling instead");
+        return;
+      }
+    };
+    match OutputStream::try_from_device_config(&device, config) {
+      Ok((stream, handle)) => {
+        info!(previous_sample_rate = ?self.stream_sample_rate, sample_rate, "Switched output stream to the native sample rate of the audio");
+        self._stream = stream;
+        self.handle = handle;
+        self.stream_sample_rate = Some(sample_rate);
+      }
+      Err(e) => warn!(error = ?e, sample_rate, "Failed to switch output stream to the native sample rate of the audio; resampling instead"),
     }
   }
+
+  /// Gets the playback time of the audio data.
+  fn position(&self) -> Duration {
+    self.elapsed + self.resumed_at.map_or(Duration::ZERO, |resumed_at| resumed_at.elapsed())
+  }
+
+  fn send_playback_error(&self, error: PlaybackError) {
+    self.playback_errors.send(error).ok(); // OK: no subscribers -> we don't care.
+  }
+
+  /// Recreates the output stream on the default output device if that device changed, continuing playback at the same
+  /// position. The stream of a device that disappeared stays dead, so it must be recreated to continue playback.
+  fn check_device(&mut self) {
+    self.last_device_check = Instant::now();
+    let device_name = default_output_device_name();
+    if device_name == self.device_name { return; }
+    if device_name.is_none() {
+      info!(previous_device = ?self.device_name, "Output device disappeared; waiting for a new output device");
+      self.device_name = None;
+      self.send_playback_error(PlaybackError::DeviceDisconnected);
+      return;
+    }
+    info!(previous_device = ?self.device_name, device = ?device_name, "Default output device changed; recreating output stream");
+    let (stream, handle) = match rodio::OutputStream::try_default() {
+      Ok(v) => v,
+      Err(e) => {
+        error!(error = ?e, "Failed to recreate output stream; retrying at the next device check");
+        return;
+      }
+    };
+    self._stream = stream;
+    self.handle = handle;
+    self.device_name = device_name;
+    self.stream_sample_rate = None;
+    // Recreate the sink, as the sink of the previous stream plays on the previous device.
+    if let Some(sink) = self.sink.take() {
+      if sink.empty() { // Playback already finished -> nothing to continue.
+        self.sink = Some(sink);
+        return;
+      }
+      let data = match self.audio_data.clone() {
+        Some(data) => data,
+        None => {
+          warn!("Cannot continue playback of streamed audio on the new output device; stopping playback");
+          self.send_playback_error(PlaybackError::DeviceFail("streamed audio cannot be played again".to_string()));
+          return;
+        }
+      };
+      let is_paused = sink.is_paused();
+      match self.create_sink(data.reader(), self.position()) {
+        Ok(sink) => {
+          if is_paused { sink.pause(); }
+          self.sink = Some(sink);
+        }
+        Err(e) => {
+          error!(error = ?e, "Failed to continue playback on the new output device");
+          self.send_playback_error(PlaybackError::DeviceFail(e.to_string()));
+        }
+      }
+    }
+  }
+}
+
+/// Audio that can be played again from the start, such as when the output stream is recreated.
+#[derive(Clone)]
+enum ReplayableAudio {
+  Data(Arc<[u8]>),
+  Received(ReceivedAudio),
+}
+
+impl ReplayableAudio {
+  fn reader(&self) -> Box<dyn MediaSource> {
+    match self {
+      ReplayableAudio::Data(data) => Box::new(Cursor::new(data.clone())),
+      ReplayableAudio::Received(received) => Box::new(received.reader()),
+    }
+  }
+}
+
+fn default_output_device_name() -> Option<String> {
+  rodio::cpal::default_host().default_output_device().and_then(|device| device.name().ok())
+}
+
+/// Gets a configuration of an output stream on `device` with `sample_rate` and `channels`, or `None` if `device` does
+/// not support that.
+fn native_stream_config(device: &rodio::cpal::Device, sample_rate: u32, channels: u16) -> Option<SupportedStreamConfig> {
+  let sample_rate = SampleRate(sample_rate);
+  device.supported_output_configs().ok()?
+    .find(|config| config.channels() == channels && config.min_sample_rate() <= sample_rate && sample_rate <= config.max_sample_rate())
+    .map(|config| config.with_sample_rate(sample_rate))
+}
+
+// Decoding ahead
+
+/// Number of frames in a chunk of audio that is decoded ahead.
+const DECODE_AHEAD_CHUNK_FRAMES: usize = 1024;
+/// Duration of silence that is played when decoded audio is not available in time, before checking again.
+const UNDERRUN_SILENCE: Duration = Duration::from_millis(10);
+
+/// Chunk of decoded audio, in which the number of channels and the sample rate do not change.
+struct DecodedChunk {
+  samples: std::vec::IntoIter<i16>,
+  channels: u16,
+  sample_rate: u32,
+}
+
+/// Source that decodes its input source ahead of playback on a separate thread, buffering the decoded audio. Plays
+/// silence when the decoded audio is not available in time.
+///
+/// The next chunk or silence is taken as soon as the current one is played, so that `current_frame_len` is only zero
+/// when decoding has finished, as Rodio stops playing a source when it is zero.
+struct DecodeAheadSource {
+  rx: std_mpsc::Receiver<DecodedChunk>,
+  chunk: DecodedChunk,
+  total_duration: Option<Duration>,
+  /// Number of samples of silence left to play because of an underrun.
+  silence: usize,
+  is_underrun: bool,
+  reported_underrun: bool,
+  playback_errors: broadcast::Sender<PlaybackError>,
+}
+
+impl DecodeAheadSource {
+  fn new<S: Source<Item=i16> + Send + 'static>(
+    mut input: S,
+    decode_ahead: Duration,
+    playback_errors: broadcast::Sender<PlaybackError>,
+  ) -> Result<Self, std::io::Error> {
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let total_duration = input.total_duration();
+    let buffer_frames = (decode_ahead.as_secs_f64() * sample_rate as f64) as usize;
+    let (tx, rx) = std_mpsc::sync_channel((buffer_frames / DECODE_AHEAD_CHUNK_FRAMES).max(1));
+    thread::Builder::new()
+      .name("Rodio decoder".to_string())
+      .spawn(move || {
+        loop {
+          let channels = input.channels();
+          let sample_rate = input.sample_rate();
+          // Do not read past the current frame, as the number of channels or the sample rate may change after it.
+          let len = DECODE_AHEAD_CHUNK_FRAMES * channels as usize;
+          let len = input.current_frame_len().map_or(len, |frame_len| frame_len.min(len));
+          let samples: Vec<i16> = input.by_ref().take(len).collect();
+          if samples.is_empty() { break; } // Input source ended.
+          let chunk = DecodedChunk { samples: samples.into_iter(), channels, sample_rate };
+          if tx.send(chunk).is_err() { break; } // Source was dropped -> stop decoding.
+        }
+      })?;
+    // Wait for the first chunk, to not start with an underrun. Disconnected without a chunk means the input is empty.
+    let chunk = rx.recv().unwrap_or_else(|_| DecodedChunk { samples: Vec::new().into_iter(), channels, sample_rate });
+    Ok(Self { rx, chunk, total_duration, silence: 0, is_underrun: false, reported_underrun: false, playback_errors })
+  }
+
+  /// Takes the next chunk if it was decoded in time, or plays silence otherwise. Leaves the current chunk empty when
+  /// decoding has finished.
+  fn advance(&mut self) {
+    match self.rx.try_recv() {
+      Ok(chunk) => {
+        self.is_underrun = false;
+        self.chunk = chunk;
+      }
+      Err(std_mpsc::TryRecvError::Empty) => self.underrun(),
+      Err(std_mpsc::TryRecvError::Disconnected) => {} // Decoding finished.
+    }
+  }
+
+  fn underrun(&mut self) {
+    if !self.is_underrun {
+      self.is_underrun = true;
+      metrics::counter!("audio_output.underruns", 1);
+      warn!("Decoded audio was not available in time; playing silence");
+      if !self.reported_underrun {
+        self.reported_underrun = true;
+        self.playback_errors.send(PlaybackError::Underrun).ok(); // OK: no subscribers -> we don't care.
+      }
+    }
+    let frames = (UNDERRUN_SILENCE.as_secs_f64() * self.chunk.sample_rate as f64) as usize;
+    self.silence = frames.max(1) * self.chunk.channels as usize;
+  }
+}
+
+impl Iterator for DecodeAheadSource {
+  type Item = i16;
+
+  fn next(&mut self) -> Option<i16> {
+    let sample = if self.silence > 0 {
+      self.silence -= 1;
+      0
+    } else {
+      self.chunk.samples.next()?
+    };
+    if self.silence == 0 && self.chunk.samples.len() == 0 {
+      self.advance();
+    }
+    Some(sample)
+  }
+}
+
+impl Source for DecodeAheadSource {
+  fn current_frame_len(&self) -> Option<usize> {
+    Some(if self.silence > 0 { self.silence } else { self.chunk.samples.len() })
+  }
+
+  fn channels(&self) -> u16 { self.chunk.channels }
+
+  fn sample_rate(&self) -> u32 { self.chunk.sample_rate }
+
+  fn total_duration(&self) -> Option<Duration> { self.total_duration }
+}
+
+// Channel processing
+
+/// Number of frames after which a channel processing source picks up changes to the channel processing.
+const CHANNEL_PROCESSING_UPDATE_FRAMES: usize = 512;
+
+/// Source that applies channel processing to stereo audio of its input source, passing other audio through.
+struct ChannelProcessingSource<S> {
+  input: S,
+  processing: Arc<Mutex<ChannelProcessing>>,
+  processor: ChannelProcessor,
+  /// Processed right sample of the current frame, returned after the left sample.
+  right: Option<i16>,
+  frames_until_update: usize,
+}
+
+impl<S> ChannelProcessingSource<S> {
+  fn new(input: S, processing: Arc<Mutex<ChannelProcessing>>) -> Self {
+    Self { input, processing, processor: ChannelProcessor::default(), right: None, frames_until_update: 0 }
+  }
+}
+
+impl<S: Source<Item=i16>> Iterator for ChannelProcessingSource<S> {
+  type Item = i16;
+
+  fn next(&mut self) -> Option<i16> {
+    if let Some(right) = self.right.take() {
+      return Some(right);
+    }
+    if self.input.channels() != 2 {
+      return self.input.next();
+    }
+    let left = self.input.next()?;
+    let right = match self.input.next() {
+      Some(right) => right,
+      None => return Some(left), // Incomplete frame at the end -> pass through.
+    };
+    if self.frames_until_update == 0 {
+      self.processor.update_from(&self.processing);
+      self.frames_until_update = CHANNEL_PROCESSING_UPDATE_FRAMES;
+    }
+    self.frames_until_update -= 1;
+    if self.processor.is_neutral() { // Pass samples through unchanged, as converting them is lossy.
+      self.right = Some(right);
+      return Some(left);
+    }
+    let (left, right) = self.processor.process(self.input.sample_rate(), sample_to_f32(left), sample_to_f32(right));
+    self.right = Some(sample_from_f32(right));
+    Some(sample_from_f32(left))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (lower, upper) = self.input.size_hint();
+    let right = self.right.is_some() as usize;
+    (lower + right, upper.map(|upper| upper + right))
+  }
+}
+
+impl<S: Source<Item=i16>> Source for ChannelProcessingSource<S> {
+  fn current_frame_len(&self) -> Option<usize> {
+    self.input.current_frame_len().map(|len| len + self.right.is_some() as usize)
+  }
+
+  fn channels(&self) -> u16 { self.input.channels() }
+
+  fn sample_rate(&self) -> u32 { self.input.sample_rate() }
+
+  fn total_duration(&self) -> Option<Duration> { self.input.total_duration() }
+}
+
+fn sample_to_f32(sample: i16) -> f32 {
+  sample as f32 / i16::MAX as f32
+}
+
+fn sample_from_f32(sample: f32) -> i16 {
+  (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
 }
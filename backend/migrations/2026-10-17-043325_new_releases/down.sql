@@ -0,0 +1 @@
+DROP TABLE IF EXISTS new_release;
@@ -0,0 +1,16 @@
+-- Releases of followed artists on Spotify that were discovered after the albums of the Spotify source were synchronized.
+
+CREATE TABLE new_release
+(
+    id                INTEGER  NOT NULL,
+    spotify_source_id INTEGER  NOT NULL,
+    spotify_id        TEXT     NOT NULL,
+    name              TEXT     NOT NULL,
+    artist_name       TEXT     NOT NULL,
+    release_date      TEXT     NOT NULL,
+    discovered_at     DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id),
+    UNIQUE (spotify_source_id, spotify_id)
+);
@@ -0,0 +1,25 @@
+-- Notifications for users, and per-user notification preferences.
+
+CREATE TABLE notification
+(
+    id         INTEGER  NOT NULL,
+    user_id    INTEGER  NOT NULL,
+    kind       INTEGER  NOT NULL,
+    message    TEXT     NOT NULL,
+    created_at DATETIME NOT NULL,
+    is_read    BOOLEAN  NOT NULL DEFAULT false,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
+
+CREATE TABLE notification_preference
+(
+    user_id                 INTEGER NOT NULL,
+    new_releases            BOOLEAN NOT NULL DEFAULT true,
+    sync_failures           BOOLEAN NOT NULL DEFAULT true,
+    shared_playlist_changes BOOLEAN NOT NULL DEFAULT true,
+
+    PRIMARY KEY (user_id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
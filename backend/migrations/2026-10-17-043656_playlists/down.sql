@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS playlist_collaborator;
+DROP TABLE IF EXISTS playlist_track;
+DROP TABLE IF EXISTS playlist;
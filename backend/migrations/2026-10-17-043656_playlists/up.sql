@@ -0,0 +1,36 @@
+-- Playlists, owned by a user, and optionally shared with collaborators or publicly.
+
+CREATE TABLE playlist
+(
+    id            INTEGER NOT NULL,
+    owner_user_id INTEGER NOT NULL,
+    name          TEXT    NOT NULL,
+    public        BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (owner_user_id) REFERENCES user (id)
+);
+
+CREATE TABLE playlist_track
+(
+    id          INTEGER NOT NULL,
+    playlist_id INTEGER NOT NULL,
+    track_id    INTEGER NOT NULL,
+    position    INTEGER NOT NULL,
+    added_by    INTEGER NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (playlist_id) REFERENCES playlist (id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (added_by) REFERENCES user (id)
+);
+
+CREATE TABLE playlist_collaborator
+(
+    playlist_id INTEGER NOT NULL,
+    user_id     INTEGER NOT NULL,
+
+    PRIMARY KEY (playlist_id, user_id),
+    FOREIGN KEY (playlist_id) REFERENCES playlist (id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
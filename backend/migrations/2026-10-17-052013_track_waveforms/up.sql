@@ -0,0 +1,11 @@
+-- Audio analysis data of tracks, derived from their local audio files.
+
+CREATE TABLE track_waveform
+(
+    track_id   INTEGER NOT NULL,
+    local_hash BIGINT  NOT NULL,
+    peaks      BLOB    NOT NULL,
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
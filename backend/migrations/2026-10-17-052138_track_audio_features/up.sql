@@ -0,0 +1,11 @@
+CREATE TABLE track_audio_features
+(
+    track_id        INTEGER NOT NULL,
+    local_hash      BIGINT  NOT NULL,
+    bpm             REAL,
+    key_pitch_class INTEGER,
+    key_minor       BOOLEAN,
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
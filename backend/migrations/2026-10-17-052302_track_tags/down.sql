@@ -0,0 +1,21 @@
+DROP TABLE IF EXISTS track_tag;
+DROP TABLE IF EXISTS tag;
+
+-- SQLite cannot drop columns, so the track audio features table is recreated without the energy and brightness columns.
+CREATE TABLE track_audio_features_without_energy
+(
+    track_id        INTEGER NOT NULL,
+    local_hash      BIGINT  NOT NULL,
+    bpm             REAL,
+    key_pitch_class INTEGER,
+    key_minor       BOOLEAN,
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
+INSERT INTO track_audio_features_without_energy (track_id, local_hash, bpm, key_pitch_class, key_minor)
+SELECT track_id, local_hash, bpm, key_pitch_class, key_minor
+FROM track_audio_features;
+DROP TABLE track_audio_features;
+ALTER TABLE track_audio_features_without_energy
+    RENAME TO track_audio_features;
@@ -0,0 +1,31 @@
+-- Energy and brightness of tracks, between 0 and 1, or null if they could not be detected.
+ALTER TABLE track_audio_features
+    ADD COLUMN energy REAL;
+ALTER TABLE track_audio_features
+    ADD COLUMN brightness REAL;
+-- Tracks are only analyzed again when their file changes, so existing audio features are deleted to have the next
+-- analysis detect the energy, brightness, and mood tags of all tracks. Audio features are derived from the files, so
+-- nothing is lost.
+DELETE
+FROM track_audio_features;
+
+-- Tags of tracks, either assigned by users or machine-generated from audio analysis.
+
+CREATE TABLE tag
+(
+    id   INTEGER NOT NULL,
+    name TEXT    NOT NULL UNIQUE,
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE track_tag
+(
+    track_id          INTEGER NOT NULL,
+    tag_id            INTEGER NOT NULL,
+    machine_generated BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY (track_id, tag_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (tag_id) REFERENCES tag (id)
+);
@@ -0,0 +1,23 @@
+DROP TABLE IF EXISTS playback_preference;
+
+-- SQLite cannot drop columns, so the local track table is recreated without the bitrate column.
+CREATE TABLE local_track_without_bitrate
+(
+    track_id        INTEGER NOT NULL,
+    local_source_id INTEGER NOT NULL,
+    file_path       TEXT,               -- Can be null to indicate that the track has been removed/replaced.
+    hash            BIGINT  NOT NULL,   -- Hash as BIGINT, such that diesel maps to BigInt, which is an i64 containing an u32 hash in the positive bits.
+    -- TODO: MusicBrainz track ID.
+    -- TODO: AcousticID.
+
+    PRIMARY KEY (track_id, local_source_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (local_source_id) REFERENCES local_source (id),
+    UNIQUE (local_source_id, file_path) -- Every track belonging to the same source must have a unique (or null) file path.
+);
+INSERT INTO local_track_without_bitrate (track_id, local_source_id, file_path, hash)
+SELECT track_id, local_source_id, file_path, hash
+FROM local_track;
+DROP TABLE local_track;
+ALTER TABLE local_track_without_bitrate
+    RENAME TO local_track;
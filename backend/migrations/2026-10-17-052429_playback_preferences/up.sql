@@ -0,0 +1,14 @@
+-- Bitrate of local tracks in kbit/s, or null if unknown, such as for tracks synchronized before it was recorded.
+ALTER TABLE local_track
+    ADD COLUMN bitrate INTEGER;
+
+-- Per-user preference of the source to play tracks from.
+CREATE TABLE playback_preference
+(
+    user_id          INTEGER NOT NULL,
+    preferred_source INTEGER NOT NULL DEFAULT 0, -- 0 = prefer local, 1 = prefer streaming.
+    min_local_bitrate INTEGER,                   -- Minimum bitrate in kbit/s of local tracks to prefer them, or null for no minimum.
+
+    PRIMARY KEY (user_id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
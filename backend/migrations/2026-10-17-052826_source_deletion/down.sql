@@ -0,0 +1,44 @@
+-- Deleted sources can no longer be told apart without the deleted at columns, so they are disabled instead.
+UPDATE local_source
+SET enabled = false
+WHERE deleted_at IS NOT NULL;
+UPDATE spotify_source
+SET enabled = false
+WHERE deleted_at IS NOT NULL;
+
+-- SQLite cannot drop columns, so the source tables are recreated without the deleted at columns.
+CREATE TABLE local_source_without_deleted_at
+(
+    id        INTEGER NOT NULL,
+    enabled   BOOLEAN NOT NULL DEFAULT true,
+    directory TEXT    NOT NULL,
+
+    PRIMARY KEY (id),
+    UNIQUE (directory)
+);
+INSERT INTO local_source_without_deleted_at (id, enabled, directory)
+SELECT id, enabled, directory
+FROM local_source;
+DROP TABLE local_source;
+ALTER TABLE local_source_without_deleted_at
+    RENAME TO local_source;
+
+CREATE TABLE spotify_source_without_deleted_at
+(
+    id            INTEGER  NOT NULL,
+    user_id       INTEGER  NOT NULL,
+    enabled       BOOLEAN  NOT NULL DEFAULT true,
+    refresh_token TEXT     NOT NULL,
+    access_token  TEXT     NOT NULL,
+    expiry_date   DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id),
+    UNIQUE (user_id)
+);
+INSERT INTO spotify_source_without_deleted_at (id, user_id, enabled, refresh_token, access_token, expiry_date)
+SELECT id, user_id, enabled, refresh_token, access_token, expiry_date
+FROM spotify_source;
+DROP TABLE spotify_source;
+ALTER TABLE spotify_source_without_deleted_at
+    RENAME TO spotify_source;
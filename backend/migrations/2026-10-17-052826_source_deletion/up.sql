@@ -0,0 +1,6 @@
+-- Time at which the source was (soft-)deleted, or null if it has not been deleted. Links of a deleted source are
+-- preserved, so that they can be reused when the source is restored.
+ALTER TABLE local_source
+    ADD COLUMN deleted_at DATETIME;
+ALTER TABLE spotify_source
+    ADD COLUMN deleted_at DATETIME;
@@ -0,0 +1,17 @@
+-- SQLite cannot drop columns, so the local source table is recreated without the skip if unavailable column.
+CREATE TABLE local_source_without_skip_if_unavailable
+(
+    id         INTEGER  NOT NULL,
+    enabled    BOOLEAN  NOT NULL DEFAULT true,
+    directory  TEXT     NOT NULL,
+    deleted_at DATETIME,
+
+    PRIMARY KEY (id),
+    UNIQUE (directory)
+);
+INSERT INTO local_source_without_skip_if_unavailable (id, enabled, directory, deleted_at)
+SELECT id, enabled, directory, deleted_at
+FROM local_source;
+DROP TABLE local_source;
+ALTER TABLE local_source_without_skip_if_unavailable
+    RENAME TO local_source;
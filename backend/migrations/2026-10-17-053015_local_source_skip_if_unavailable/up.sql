@@ -0,0 +1,4 @@
+-- Whether the local source is skipped during synchronization when its directory is unavailable, instead of setting
+-- all its tracks as removed.
+ALTER TABLE local_source
+    ADD COLUMN skip_if_unavailable BOOLEAN NOT NULL DEFAULT true;
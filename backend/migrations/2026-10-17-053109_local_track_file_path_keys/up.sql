@@ -0,0 +1,17 @@
+-- Normalized file path for comparing paths across platforms and case-insensitive filesystems. Null when file_path is
+-- null.
+ALTER TABLE local_track
+    ADD COLUMN file_path_key TEXT;
+
+-- Compute the keys of existing file paths for which `musium_filesystem_sync::path_key` returns the path as is on every
+-- platform: paths of printable ASCII characters without uppercase letters, backslashes, and repeated or trailing
+-- slashes. Keys of other paths depend on the platform, so they are left null, and are computed at the start of the next
+-- local synchronization instead.
+UPDATE local_track
+SET file_path_key = file_path
+WHERE file_path IS NOT NULL
+  AND file_path NOT GLOB '*[^ -~]*'
+  AND file_path = lower(file_path)
+  AND instr(file_path, '\') = 0
+  AND instr(file_path, '//') = 0
+  AND file_path NOT GLOB '*/';
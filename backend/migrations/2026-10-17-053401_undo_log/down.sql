@@ -0,0 +1,30 @@
+DROP TABLE IF EXISTS undo_log;
+
+-- Deleted playlists can no longer be restored without the undo log, so they are purged.
+DELETE
+FROM playlist_track
+WHERE playlist_id IN (SELECT id FROM playlist WHERE deleted_at IS NOT NULL);
+DELETE
+FROM playlist_collaborator
+WHERE playlist_id IN (SELECT id FROM playlist WHERE deleted_at IS NOT NULL);
+DELETE
+FROM playlist
+WHERE deleted_at IS NOT NULL;
+
+-- SQLite cannot drop columns, so the playlist table is recreated without the deleted at column.
+CREATE TABLE playlist_without_deleted_at
+(
+    id            INTEGER NOT NULL,
+    owner_user_id INTEGER NOT NULL,
+    name          TEXT    NOT NULL,
+    public        BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (owner_user_id) REFERENCES user (id)
+);
+INSERT INTO playlist_without_deleted_at (id, owner_user_id, name, public)
+SELECT id, owner_user_id, name, public
+FROM playlist;
+DROP TABLE playlist;
+ALTER TABLE playlist_without_deleted_at
+    RENAME TO playlist;
@@ -0,0 +1,19 @@
+-- Deleted playlists are kept until their undo log entry expires.
+ALTER TABLE playlist
+    ADD COLUMN deleted_at DATETIME;
+
+-- Undo log of destructive operations, storing how to undo them. Entries expire after a retention window.
+
+CREATE TABLE undo_log
+(
+    id              INTEGER  NOT NULL,
+    user_id         INTEGER  NOT NULL,
+    kind            INTEGER  NOT NULL,
+    target_id       INTEGER  NOT NULL, -- ID of the rated album/track/artist, deleted playlist, or removed source.
+    previous_rating INTEGER,           -- Rating before a rating change, or null if there was no rating.
+    description     TEXT     NOT NULL,
+    created_at      DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
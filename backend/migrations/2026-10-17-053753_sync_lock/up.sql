@@ -0,0 +1,12 @@
+-- Lease on the sync subsystem, ensuring only one server instance writes synced data to this database at a time. Holds
+-- at most one row.
+
+CREATE TABLE sync_lock
+(
+    id          INTEGER  NOT NULL,
+    holder      TEXT     NOT NULL, -- Identifier of the server instance that holds the lock.
+    acquired_at DATETIME NOT NULL,
+    expires_at  DATETIME NOT NULL, -- The lock is stale and can be taken over by other instances after this time.
+
+    PRIMARY KEY (id)
+);
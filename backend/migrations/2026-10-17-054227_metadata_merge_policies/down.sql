@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS metadata_conflict;
+DROP TABLE IF EXISTS metadata_merge_policy;
@@ -0,0 +1,26 @@
+-- Per-field policy for merging conflicting metadata from different sources during synchronization. Fields without a
+-- policy are overwritten by whichever source is synchronized last.
+
+CREATE TABLE metadata_merge_policy
+(
+    field  INTEGER NOT NULL,
+    policy INTEGER NOT NULL,
+
+    PRIMARY KEY (field)
+);
+
+-- Metadata conflicts between sources that were not resolved automatically by a merge policy.
+
+CREATE TABLE metadata_conflict
+(
+    id            INTEGER  NOT NULL,
+    field         INTEGER  NOT NULL,
+    entity_id     INTEGER  NOT NULL, -- ID of the album, track, or artist, depending on the field.
+    current_value TEXT,
+    local_value   TEXT,
+    spotify_value TEXT,
+    detected_at   DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    UNIQUE (field, entity_id)
+);
@@ -0,0 +1,48 @@
+-- SQLite cannot drop columns, so the album, track, and artist tables are recreated without the collation key columns.
+CREATE TABLE album_without_name_key
+(
+    id   INTEGER NOT NULL,
+    name TEXT    NOT NULL,
+
+    PRIMARY KEY (id)
+);
+INSERT INTO album_without_name_key (id, name)
+SELECT id, name
+FROM album;
+DROP TABLE album;
+ALTER TABLE album_without_name_key
+    RENAME TO album;
+
+CREATE TABLE track_without_title_key
+(
+    id           INTEGER NOT NULL,
+    album_id     INTEGER NOT NULL,
+    disc_number  INTEGER,
+    disc_total   INTEGER,
+    track_number INTEGER,
+    track_total  INTEGER,
+    title        TEXT    NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
+INSERT INTO track_without_title_key (id, album_id, disc_number, disc_total, track_number, track_total, title)
+SELECT id, album_id, disc_number, disc_total, track_number, track_total, title
+FROM track;
+DROP TABLE track;
+ALTER TABLE track_without_title_key
+    RENAME TO track;
+
+CREATE TABLE artist_without_name_key
+(
+    id   INTEGER NOT NULL,
+    name TEXT    NOT NULL,
+
+    PRIMARY KEY (id)
+);
+INSERT INTO artist_without_name_key (id, name)
+SELECT id, name
+FROM artist;
+DROP TABLE artist;
+ALTER TABLE artist_without_name_key
+    RENAME TO artist;
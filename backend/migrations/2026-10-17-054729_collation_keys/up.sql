@@ -0,0 +1,24 @@
+-- Collation keys of names and titles: normalized, lowercase, and without accents.
+ALTER TABLE album
+    ADD COLUMN name_key TEXT NOT NULL DEFAULT '';
+ALTER TABLE track
+    ADD COLUMN title_key TEXT NOT NULL DEFAULT '';
+ALTER TABLE artist
+    ADD COLUMN name_key TEXT NOT NULL DEFAULT '';
+
+-- Compute the keys of existing names and titles that only consist of printable ASCII characters, for which the key is
+-- the trimmed lowercase name. SQLite cannot remove accents, so the keys of other names are left empty, and are computed
+-- at the start of the next synchronization instead.
+UPDATE album
+SET name_key = lower(trim(name))
+WHERE name NOT GLOB '*[^ -~]*';
+UPDATE track
+SET title_key = lower(trim(title))
+WHERE title NOT GLOB '*[^ -~]*';
+UPDATE artist
+SET name_key = lower(trim(name))
+WHERE name NOT GLOB '*[^ -~]*';
+
+CREATE INDEX album_name_key ON album (name_key);
+CREATE INDEX track_title_key ON track (title_key);
+CREATE INDEX artist_name_key ON artist (name_key);
@@ -0,0 +1,34 @@
+-- SQLite cannot drop columns, so the album and artist tables are recreated without the sort name columns.
+CREATE TABLE album_without_sort_name
+(
+    id       INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    name_key TEXT    NOT NULL DEFAULT '', -- Collation key of name: normalized, lowercase, and without accents.
+
+    PRIMARY KEY (id)
+);
+INSERT INTO album_without_sort_name (id, name, name_key)
+SELECT id, name, name_key
+FROM album;
+DROP TABLE album;
+ALTER TABLE album_without_sort_name
+    RENAME TO album;
+CREATE INDEX album_name_key ON album (name_key);
+
+CREATE TABLE artist_without_sort_name
+(
+    id       INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    name_key TEXT    NOT NULL DEFAULT '', -- Collation key of name: normalized, lowercase, and without accents.
+
+    PRIMARY KEY (id)
+);
+INSERT INTO artist_without_sort_name (id, name, name_key)
+SELECT id, name, name_key
+FROM artist;
+DROP TABLE artist;
+ALTER TABLE artist_without_sort_name
+    RENAME TO artist;
+CREATE INDEX artist_name_key ON artist (name_key);
+
+DROP TABLE IF EXISTS sort_article;
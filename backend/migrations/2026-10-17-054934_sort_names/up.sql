@@ -0,0 +1,37 @@
+-- Articles that are moved to the end of album and artist names to create their sort names.
+CREATE TABLE sort_article
+(
+    article TEXT NOT NULL,
+
+    PRIMARY KEY (article)
+);
+INSERT INTO sort_article (article) VALUES ('The'), ('A'), ('An');
+
+-- Names for sorting, with leading articles moved to the end: "Beatles, The".
+ALTER TABLE album
+    ADD COLUMN sort_name TEXT NOT NULL DEFAULT '';
+ALTER TABLE album
+    ADD COLUMN sort_name_overridden BOOLEAN NOT NULL DEFAULT false; -- Whether sort_name was set manually instead of computed.
+ALTER TABLE artist
+    ADD COLUMN sort_name TEXT NOT NULL DEFAULT '';
+ALTER TABLE artist
+    ADD COLUMN sort_name_overridden BOOLEAN NOT NULL DEFAULT false; -- Whether sort_name was set manually instead of computed.
+
+-- Compute the sort names of existing albums and artists in the same way as `musium_core::collation::sort_name`: trim
+-- the name, and move the first article that is followed by a space and more text to the end, regardless of case. Only
+-- spaces are treated as whitespace here, which suffices for names in practice; other names get their sort name computed
+-- again when their sort articles are set.
+UPDATE album
+SET sort_name = coalesce((SELECT ltrim(substr(trim(name), length(article) + 1)) || ', ' || substr(trim(name), 1, length(article))
+                          FROM sort_article
+                          WHERE lower(substr(trim(name), 1, length(article))) = lower(article)
+                            AND substr(trim(name), length(article) + 1, 1) = ' '
+                            AND ltrim(substr(trim(name), length(article) + 1)) != ''
+                          LIMIT 1), trim(name));
+UPDATE artist
+SET sort_name = coalesce((SELECT ltrim(substr(trim(name), length(article) + 1)) || ', ' || substr(trim(name), 1, length(article))
+                          FROM sort_article
+                          WHERE lower(substr(trim(name), 1, length(article))) = lower(article)
+                            AND substr(trim(name), length(article) + 1, 1) = ' '
+                            AND ltrim(substr(trim(name), length(article) + 1)) != ''
+                          LIMIT 1), trim(name));
@@ -0,0 +1,23 @@
+-- SQLite cannot drop columns, so the local track table is recreated without the ReplayGain columns.
+CREATE TABLE local_track_without_replay_gain
+(
+    track_id        INTEGER NOT NULL,
+    local_source_id INTEGER NOT NULL,
+    file_path       TEXT,               -- Can be null to indicate that the track has been removed/replaced.
+    file_path_key   TEXT,               -- Normalized file path for comparing paths across platforms and case-insensitive filesystems. Null when file_path is null.
+    hash            BIGINT  NOT NULL,   -- Hash as BIGINT, such that diesel maps to BigInt, which is an i64 containing an u32 hash in the positive bits.
+    bitrate         INTEGER,            -- Bitrate in kbit/s, or null if unknown.
+    -- TODO: MusicBrainz track ID.
+    -- TODO: AcousticID.
+
+    PRIMARY KEY (track_id, local_source_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (local_source_id) REFERENCES local_source (id),
+    UNIQUE (local_source_id, file_path) -- Every track belonging to the same source must have a unique (or null) file path.
+);
+INSERT INTO local_track_without_replay_gain (track_id, local_source_id, file_path, file_path_key, hash, bitrate)
+SELECT track_id, local_source_id, file_path, file_path_key, hash, bitrate
+FROM local_track;
+DROP TABLE local_track;
+ALTER TABLE local_track_without_replay_gain
+    RENAME TO local_track;
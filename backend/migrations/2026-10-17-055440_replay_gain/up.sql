@@ -0,0 +1,10 @@
+-- ReplayGain tags of local tracks, imported from the tags of the file, or null if the file has no such tag. Null for
+-- existing tracks until their file is synchronized again.
+ALTER TABLE local_track
+    ADD COLUMN track_gain REAL; -- ReplayGain track gain in dB.
+ALTER TABLE local_track
+    ADD COLUMN track_peak REAL; -- ReplayGain track peak as a linear sample amplitude.
+ALTER TABLE local_track
+    ADD COLUMN album_gain REAL; -- ReplayGain album gain in dB.
+ALTER TABLE local_track
+    ADD COLUMN album_peak REAL; -- ReplayGain album peak as a linear sample amplitude.
@@ -0,0 +1,12 @@
+-- Front covers of albums, uploaded by users or fetched from the Cover Art Archive.
+
+CREATE TABLE album_cover
+(
+    album_id   INTEGER  NOT NULL,
+    mime_type  TEXT     NOT NULL,
+    data       BLOB     NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    PRIMARY KEY (album_id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
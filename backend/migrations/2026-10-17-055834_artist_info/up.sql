@@ -0,0 +1,14 @@
+-- Images and short biographies of artists, fetched from TheAudioDB or Wikipedia during sync.
+
+CREATE TABLE artist_info
+(
+    artist_id       INTEGER  NOT NULL,
+    biography       TEXT,
+    image_mime_type TEXT,
+    image_data      BLOB,
+    source_url      TEXT,              -- URL of the page the biography and image were taken from.
+    fetched_at      DATETIME NOT NULL, -- Also set when nothing was found, so that the artist is not fetched again on every sync.
+
+    PRIMARY KEY (artist_id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
@@ -0,0 +1,59 @@
+-- The covers, images, and peaks are moved back out of the media cache. Artist images whose blob was evicted are lost
+-- until the artist info is fetched again, and waveforms whose peaks were evicted are generated again.
+
+CREATE TABLE album_cover_with_data
+(
+    album_id   INTEGER  NOT NULL,
+    mime_type  TEXT     NOT NULL,
+    data       BLOB     NOT NULL,
+    updated_at DATETIME NOT NULL,
+
+    PRIMARY KEY (album_id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
+INSERT INTO album_cover_with_data (album_id, mime_type, data, updated_at)
+SELECT album_cover.album_id, media_blob.mime_type, media_blob.data, album_cover.updated_at
+FROM album_cover
+         INNER JOIN media_blob ON media_blob.hash = album_cover.blob_hash;
+DROP TABLE album_cover;
+ALTER TABLE album_cover_with_data
+    RENAME TO album_cover;
+
+CREATE TABLE artist_info_with_image_data
+(
+    artist_id       INTEGER  NOT NULL,
+    biography       TEXT,
+    image_mime_type TEXT,
+    image_data      BLOB,
+    source_url      TEXT,              -- URL of the page the biography and image were taken from.
+    fetched_at      DATETIME NOT NULL, -- Also set when nothing was found, so that the artist is not fetched again on every sync.
+
+    PRIMARY KEY (artist_id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO artist_info_with_image_data (artist_id, biography, image_mime_type, image_data, source_url, fetched_at)
+SELECT artist_info.artist_id, artist_info.biography, media_blob.mime_type, media_blob.data, artist_info.source_url, artist_info.fetched_at
+FROM artist_info
+         LEFT JOIN media_blob ON media_blob.hash = artist_info.image_hash;
+DROP TABLE artist_info;
+ALTER TABLE artist_info_with_image_data
+    RENAME TO artist_info;
+
+CREATE TABLE track_waveform_with_peaks
+(
+    track_id   INTEGER NOT NULL,
+    local_hash BIGINT  NOT NULL,
+    peaks      BLOB    NOT NULL,
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
+INSERT INTO track_waveform_with_peaks (track_id, local_hash, peaks)
+SELECT track_waveform.track_id, track_waveform.local_hash, media_blob.data
+FROM track_waveform
+         INNER JOIN media_blob ON media_blob.hash = track_waveform.peaks_hash;
+DROP TABLE track_waveform;
+ALTER TABLE track_waveform_with_peaks
+    RENAME TO track_waveform;
+
+DROP TABLE IF EXISTS media_blob;
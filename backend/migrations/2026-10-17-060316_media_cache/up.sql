@@ -0,0 +1,87 @@
+-- Content-addressed cache of media, such as album covers, artist images, and waveforms, keyed by the hash of their
+-- data. Unpinned blobs are evicted in least recently accessed order when the cache exceeds its size limit.
+CREATE TABLE media_blob
+(
+    hash             TEXT     NOT NULL, -- Hex-encoded BLAKE3 hash of data.
+    kind             INTEGER  NOT NULL,
+    mime_type        TEXT     NOT NULL,
+    data             BLOB     NOT NULL,
+    size             BIGINT   NOT NULL,
+    pinned           BOOLEAN  NOT NULL, -- Pinned blobs, such as uploaded album covers, cannot be derived again and are never evicted.
+    last_accessed_at DATETIME NOT NULL,
+
+    PRIMARY KEY (hash)
+);
+CREATE INDEX media_blob_eviction ON media_blob (pinned, last_accessed_at);
+
+-- Existing covers, images, and peaks are moved into the media cache. SQLite cannot compute BLAKE3 hashes, so they are
+-- keyed by random hashes of the same shape instead. Blobs are never modified, so these keys stay unique to their data,
+-- but identical data stored later is not deduplicated with them.
+
+-- SQLite cannot change column types, so the album cover table is recreated with a hash of the image in the media cache.
+CREATE TABLE album_cover_with_blob_hash
+(
+    album_id   INTEGER  NOT NULL,
+    blob_hash  TEXT     NOT NULL, -- Hash of the image in the media cache, which is pinned.
+    updated_at DATETIME NOT NULL,
+
+    PRIMARY KEY (album_id),
+    FOREIGN KEY (album_id) REFERENCES album (id),
+    FOREIGN KEY (blob_hash) REFERENCES media_blob (hash)
+);
+INSERT INTO album_cover_with_blob_hash (album_id, blob_hash, updated_at)
+SELECT album_id, lower(hex(randomblob(32))), updated_at
+FROM album_cover;
+INSERT INTO media_blob (hash, kind, mime_type, data, size, pinned, last_accessed_at)
+SELECT new.blob_hash, 0, old.mime_type, old.data, length(old.data), 1, old.updated_at
+FROM album_cover_with_blob_hash new
+         INNER JOIN album_cover old ON old.album_id = new.album_id;
+DROP TABLE album_cover;
+ALTER TABLE album_cover_with_blob_hash
+    RENAME TO album_cover;
+
+-- SQLite cannot drop columns, so the artist info table is recreated with a hash of the image in the media cache.
+CREATE TABLE artist_info_with_image_hash
+(
+    artist_id       INTEGER  NOT NULL,
+    biography       TEXT,
+    image_hash      TEXT,              -- Hash of the image in the media cache. Not pinned, so the image may have been evicted.
+    source_url      TEXT,              -- URL of the page the biography and image were taken from.
+    fetched_at      DATETIME NOT NULL, -- Also set when nothing was found, so that the artist is not fetched again on every sync.
+
+    PRIMARY KEY (artist_id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO artist_info_with_image_hash (artist_id, biography, image_hash, source_url, fetched_at)
+SELECT artist_id, biography, CASE WHEN image_data IS NOT NULL THEN lower(hex(randomblob(32))) END, source_url, fetched_at
+FROM artist_info;
+INSERT INTO media_blob (hash, kind, mime_type, data, size, pinned, last_accessed_at)
+SELECT new.image_hash, 1, old.image_mime_type, old.image_data, length(old.image_data), 0, old.fetched_at
+FROM artist_info_with_image_hash new
+         INNER JOIN artist_info old ON old.artist_id = new.artist_id
+WHERE new.image_hash IS NOT NULL;
+DROP TABLE artist_info;
+ALTER TABLE artist_info_with_image_hash
+    RENAME TO artist_info;
+
+-- SQLite cannot change column types, so the track waveform table is recreated with a hash of the peaks in the media
+-- cache.
+CREATE TABLE track_waveform_with_peaks_hash
+(
+    track_id    INTEGER NOT NULL,
+    local_hash  BIGINT  NOT NULL,
+    peaks_hash  TEXT    NOT NULL, -- Hash of the peaks in the media cache. The peaks are generated again when they were evicted.
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
+INSERT INTO track_waveform_with_peaks_hash (track_id, local_hash, peaks_hash)
+SELECT track_id, local_hash, lower(hex(randomblob(32)))
+FROM track_waveform;
+INSERT INTO media_blob (hash, kind, mime_type, data, size, pinned, last_accessed_at)
+SELECT new.peaks_hash, 2, 'application/octet-stream', old.peaks, length(old.peaks), 0, CURRENT_TIMESTAMP
+FROM track_waveform_with_peaks_hash new
+         INNER JOIN track_waveform old ON old.track_id = new.track_id;
+DROP TABLE track_waveform;
+ALTER TABLE track_waveform_with_peaks_hash
+    RENAME TO track_waveform;
@@ -0,0 +1,18 @@
+-- SQLite cannot drop columns, so the user table is recreated without the hash parameters column. Hashes that were
+-- created with other parameters than the old rust-argon2 parameters no longer verify afterwards.
+CREATE TABLE user_without_hash_params
+(
+    id   INTEGER NOT NULL,
+    name TEXT    NOT NULL,
+    hash BLOB    NOT NULL,
+    salt BLOB    NOT NULL,
+
+    PRIMARY KEY (id),
+    UNIQUE (name)
+);
+INSERT INTO user_without_hash_params (id, name, hash, salt)
+SELECT id, name, hash, salt
+FROM user;
+DROP TABLE user;
+ALTER TABLE user_without_hash_params
+    RENAME TO user;
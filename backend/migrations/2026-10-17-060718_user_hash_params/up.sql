@@ -0,0 +1,4 @@
+-- Parameters of each password hash, in PHC parameter format. Existing hashes were created with the old rust-argon2
+-- parameters, and are re-hashed with the current parameters on login.
+ALTER TABLE user
+    ADD COLUMN hash_params TEXT NOT NULL DEFAULT 'argon2i$v=19$m=4096,t=3,p=1';
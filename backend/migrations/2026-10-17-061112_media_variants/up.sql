@@ -0,0 +1,10 @@
+-- Resized variants of images in the media cache. Not foreign keys, as both the source and variant blobs may be evicted
+-- independently, after which the variant is resized again when requested.
+CREATE TABLE media_variant
+(
+    source_hash  TEXT    NOT NULL,
+    size         INTEGER NOT NULL, -- Maximum width and height in pixels.
+    variant_hash TEXT    NOT NULL,
+
+    PRIMARY KEY (source_hash, size)
+);
@@ -0,0 +1,12 @@
+-- State of the last synchronization of a Spotify source, so that most synchronizations are incremental, only fetching
+-- albums that are not yet linked to the source. Full synchronizations refetch all albums to pick up metadata changes.
+
+CREATE TABLE spotify_sync_state
+(
+    spotify_source_id INTEGER  NOT NULL,
+    full_synced_at    DATETIME NOT NULL,
+    synced_at         DATETIME NOT NULL,
+
+    PRIMARY KEY (spotify_source_id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id)
+);
@@ -0,0 +1,40 @@
+-- SQLite cannot drop columns, so the sync state table is recreated with the time of the last synchronization, taken
+-- from the Spotify source, and the Spotify source table is recreated without the columns of its last synchronization.
+CREATE TABLE spotify_sync_state_with_synced_at
+(
+    spotify_source_id INTEGER  NOT NULL,
+    full_synced_at    DATETIME NOT NULL,
+    synced_at         DATETIME NOT NULL,
+
+    PRIMARY KEY (spotify_source_id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id)
+);
+INSERT INTO spotify_sync_state_with_synced_at (spotify_source_id, full_synced_at, synced_at)
+SELECT spotify_source_id,
+       full_synced_at,
+       COALESCE((SELECT last_synced_at FROM spotify_source WHERE spotify_source.id = spotify_sync_state.spotify_source_id), full_synced_at)
+FROM spotify_sync_state;
+DROP TABLE spotify_sync_state;
+ALTER TABLE spotify_sync_state_with_synced_at
+    RENAME TO spotify_sync_state;
+
+CREATE TABLE spotify_source_without_sync_outcome
+(
+    id            INTEGER  NOT NULL,
+    user_id       INTEGER  NOT NULL,
+    enabled       BOOLEAN  NOT NULL DEFAULT true,
+    refresh_token TEXT     NOT NULL,
+    access_token  TEXT     NOT NULL,
+    expiry_date   DATETIME NOT NULL,
+    deleted_at    DATETIME,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id),
+    UNIQUE (user_id)
+);
+INSERT INTO spotify_source_without_sync_outcome (id, user_id, enabled, refresh_token, access_token, expiry_date, deleted_at)
+SELECT id, user_id, enabled, refresh_token, access_token, expiry_date, deleted_at
+FROM spotify_source;
+DROP TABLE spotify_source;
+ALTER TABLE spotify_source_without_sync_outcome
+    RENAME TO spotify_source;
@@ -0,0 +1,24 @@
+-- When the Spotify source was last synchronized, and the error of its last synchronization if that failed.
+ALTER TABLE spotify_source
+    ADD COLUMN last_synced_at DATETIME;
+ALTER TABLE spotify_source
+    ADD COLUMN last_error TEXT;
+
+-- The time of the last synchronization moves from the sync state to the Spotify source. SQLite cannot drop columns, so
+-- the sync state table is recreated without it.
+UPDATE spotify_source
+SET last_synced_at = (SELECT synced_at FROM spotify_sync_state WHERE spotify_sync_state.spotify_source_id = spotify_source.id);
+CREATE TABLE spotify_sync_state_without_synced_at
+(
+    spotify_source_id INTEGER  NOT NULL,
+    full_synced_at    DATETIME NOT NULL,
+
+    PRIMARY KEY (spotify_source_id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id)
+);
+INSERT INTO spotify_sync_state_without_synced_at (spotify_source_id, full_synced_at)
+SELECT spotify_source_id, full_synced_at
+FROM spotify_sync_state;
+DROP TABLE spotify_sync_state;
+ALTER TABLE spotify_sync_state_without_synced_at
+    RENAME TO spotify_sync_state;
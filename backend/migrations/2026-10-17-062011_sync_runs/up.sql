@@ -0,0 +1,18 @@
+-- History of synchronizations of sources, for diagnosing unexpected changes to the library. Has one row per synchronized
+-- source per synchronization.
+
+CREATE TABLE sync_run
+(
+    id          INTEGER  NOT NULL,
+    source_kind INTEGER  NOT NULL, -- 0 = local source, 1 = Spotify source.
+    source_id   INTEGER  NOT NULL, -- Not a foreign key, as history is kept for sources that are deleted later.
+    started_at  DATETIME NOT NULL,
+    ended_at    DATETIME NOT NULL,
+    added       INTEGER  NOT NULL, -- Number of tracks added to the source.
+    updated     INTEGER  NOT NULL, -- Number of tracks of the source of which the metadata changed.
+    removed     INTEGER  NOT NULL, -- Number of tracks removed from the source.
+    outcome     INTEGER  NOT NULL, -- 0 = completed, 1 = skipped, 2 = failed.
+    error       TEXT,
+
+    PRIMARY KEY (id)
+);
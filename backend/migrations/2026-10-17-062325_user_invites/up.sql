@@ -0,0 +1,16 @@
+-- Single-use invite codes for self-registration of new users.
+CREATE TABLE user_invite
+(
+    id                  INTEGER  NOT NULL,
+    code                TEXT     NOT NULL,
+    created_by_user_id  INTEGER  NOT NULL,
+    created_at          DATETIME NOT NULL,
+    expires_at          DATETIME NOT NULL,
+    redeemed_by_user_id INTEGER,          -- Null if not redeemed, or if the user that redeemed it was deleted.
+    redeemed_at         DATETIME,         -- Null if not redeemed.
+
+    PRIMARY KEY (id),
+    UNIQUE (code),
+    FOREIGN KEY (created_by_user_id) REFERENCES user (id) ON DELETE CASCADE,
+    FOREIGN KEY (redeemed_by_user_id) REFERENCES user (id) ON DELETE SET NULL
+);
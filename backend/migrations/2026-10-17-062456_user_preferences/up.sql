@@ -0,0 +1,10 @@
+-- Generic key/value preferences of clients, which roam across the devices of a user.
+CREATE TABLE user_preference
+(
+    user_id INTEGER NOT NULL,
+    key     TEXT    NOT NULL,
+    value   TEXT    NOT NULL,
+
+    PRIMARY KEY (user_id, key),
+    FOREIGN KEY (user_id) REFERENCES user (id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+-- Problems found when trying to decode the files of local tracks, so that corrupt files are found before they are played.
+
+CREATE TABLE track_issue
+(
+    track_id    INTEGER  NOT NULL,
+    local_hash  BIGINT   NOT NULL, -- Hash of the local track the issue was found in, to detect when the file changes.
+    kind        INTEGER  NOT NULL,
+    message     TEXT     NOT NULL,
+    detected_at DATETIME NOT NULL,
+
+    PRIMARY KEY (track_id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
@@ -0,0 +1,22 @@
+-- SQLite cannot drop columns, so the track table is recreated without the added at column.
+CREATE TABLE track_without_added_at
+(
+    id           INTEGER NOT NULL,
+    album_id     INTEGER NOT NULL,
+    disc_number  INTEGER,
+    disc_total   INTEGER,
+    track_number INTEGER,
+    track_total  INTEGER,
+    title        TEXT    NOT NULL,
+    title_key    TEXT    NOT NULL DEFAULT '', -- Collation key of title: normalized, lowercase, and without accents.
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
+INSERT INTO track_without_added_at (id, album_id, disc_number, disc_total, track_number, track_total, title, title_key)
+SELECT id, album_id, disc_number, disc_total, track_number, track_total, title, title_key
+FROM track;
+DROP TABLE track;
+ALTER TABLE track_without_added_at
+    RENAME TO track;
+CREATE INDEX track_title_key ON track (title_key);
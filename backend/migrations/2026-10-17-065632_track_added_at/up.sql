@@ -0,0 +1,4 @@
+-- When the track was first synchronized from any source. When existing tracks were added is unknown, so they get the
+-- Unix epoch instead, which keeps them out of queries for recently added tracks.
+ALTER TABLE track
+    ADD COLUMN added_at DATETIME NOT NULL DEFAULT '1970-01-01 00:00:00';
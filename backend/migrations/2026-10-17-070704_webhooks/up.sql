@@ -0,0 +1,15 @@
+-- Outgoing webhooks, called with a signed JSON payload on library and sync events, for integrating with home automation
+-- or notification services.
+
+CREATE TABLE webhook
+(
+    id                INTEGER NOT NULL,
+    url               TEXT    NOT NULL,
+    secret            TEXT    NOT NULL, -- Key of the HMAC-SHA256 signature of payloads.
+    on_sync_completed BOOLEAN NOT NULL,
+    on_new_albums     BOOLEAN NOT NULL,
+    on_errors         BOOLEAN NOT NULL,
+    enabled           BOOLEAN NOT NULL DEFAULT true,
+
+    PRIMARY KEY (id)
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS playlist_mix;
+DROP TABLE IF EXISTS play_history;
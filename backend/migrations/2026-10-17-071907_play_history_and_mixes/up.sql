@@ -0,0 +1,29 @@
+-- Tracks played by users, for listening statistics and generated mixes.
+
+CREATE TABLE play_history
+(
+    id        INTEGER  NOT NULL,
+    user_id   INTEGER  NOT NULL,
+    track_id  INTEGER  NOT NULL,
+    played_at DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
+
+-- Playlists generated for users from their ratings, play history, and audio features, which are regenerated
+-- periodically. A user has at most one mix of each kind.
+
+CREATE TABLE playlist_mix
+(
+    playlist_id  INTEGER  NOT NULL,
+    user_id      INTEGER  NOT NULL,
+    kind         INTEGER  NOT NULL,
+    generated_at DATETIME NOT NULL,
+
+    PRIMARY KEY (playlist_id),
+    UNIQUE (user_id, kind),
+    FOREIGN KEY (playlist_id) REFERENCES playlist (id),
+    FOREIGN KEY (user_id) REFERENCES user (id)
+);
@@ -0,0 +1,18 @@
+-- SQLite cannot drop columns, so the play history table is recreated without the skipped column.
+CREATE TABLE play_history_without_skipped
+(
+    id        INTEGER  NOT NULL,
+    user_id   INTEGER  NOT NULL,
+    track_id  INTEGER  NOT NULL,
+    played_at DATETIME NOT NULL,
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES user (id),
+    FOREIGN KEY (track_id) REFERENCES track (id)
+);
+INSERT INTO play_history_without_skipped (id, user_id, track_id, played_at)
+SELECT id, user_id, track_id, played_at
+FROM play_history;
+DROP TABLE play_history;
+ALTER TABLE play_history_without_skipped
+    RENAME TO play_history;
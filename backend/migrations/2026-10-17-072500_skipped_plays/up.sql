@@ -0,0 +1,3 @@
+-- Whether the user skipped the track early on.
+ALTER TABLE play_history
+    ADD COLUMN skipped BOOLEAN NOT NULL DEFAULT false;
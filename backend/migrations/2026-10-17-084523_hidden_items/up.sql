@@ -0,0 +1,12 @@
+-- Tracks, albums, and artists that a user never wants to play, which are skipped when shuffling, in radio, and in
+-- default list views, but remain in the library.
+CREATE TABLE user_hidden_item
+(
+    user_id   INTEGER  NOT NULL,
+    kind      INTEGER  NOT NULL, -- 0 = track, 1 = album, 2 = artist.
+    item_id   INTEGER  NOT NULL,
+    hidden_at DATETIME NOT NULL,
+
+    PRIMARY KEY (user_id, kind, item_id),
+    FOREIGN KEY (user_id) REFERENCES user (id) ON DELETE CASCADE
+);
@@ -0,0 +1,25 @@
+DROP TABLE IF EXISTS user_content_filter;
+
+-- SQLite cannot drop columns, so the track table is recreated without the explicit column.
+CREATE TABLE track_without_explicit
+(
+    id           INTEGER NOT NULL,
+    album_id     INTEGER NOT NULL,
+    disc_number  INTEGER,
+    disc_total   INTEGER,
+    track_number INTEGER,
+    track_total  INTEGER,
+    title        TEXT    NOT NULL,
+    title_key    TEXT     NOT NULL DEFAULT '', -- Collation key of title: normalized, lowercase, and without accents.
+    added_at     DATETIME NOT NULL DEFAULT '1970-01-01 00:00:00', -- When the track was first synchronized from any source.
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
+INSERT INTO track_without_explicit (id, album_id, disc_number, disc_total, track_number, track_total, title, title_key, added_at)
+SELECT id, album_id, disc_number, disc_total, track_number, track_total, title, title_key, added_at
+FROM track;
+DROP TABLE track;
+ALTER TABLE track_without_explicit
+    RENAME TO track;
+CREATE INDEX track_title_key ON track (title_key);
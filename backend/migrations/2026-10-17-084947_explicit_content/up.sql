@@ -0,0 +1,15 @@
+-- Whether the track has explicit content, or NULL when no source says.
+ALTER TABLE track
+    ADD COLUMN explicit BOOLEAN;
+
+-- Per-user filter of explicit content, enforced when listing, shuffling, and recommending tracks. A pinned filter can
+-- only be changed by admins, such as for profiles of children.
+CREATE TABLE user_content_filter
+(
+    user_id INTEGER NOT NULL,
+    filter  INTEGER NOT NULL, -- 0 = off, 1 = exclude explicit, 2 = only clean.
+    pinned  BOOLEAN NOT NULL,
+
+    PRIMARY KEY (user_id),
+    FOREIGN KEY (user_id) REFERENCES user (id) ON DELETE CASCADE
+);
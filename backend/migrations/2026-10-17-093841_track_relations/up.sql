@@ -0,0 +1,15 @@
+-- Links between versions of the same track, such as remasters, live recordings, and remixes of an original track.
+-- Links are either created by users, or machine-generated from the titles of tracks during sync.
+
+CREATE TABLE track_relation
+(
+    original_track_id INTEGER NOT NULL,
+    version_track_id  INTEGER NOT NULL,
+    kind              INTEGER NOT NULL,
+    machine_generated BOOLEAN NOT NULL DEFAULT false,
+
+    PRIMARY KEY (original_track_id, version_track_id),
+    FOREIGN KEY (original_track_id) REFERENCES track (id),
+    FOREIGN KEY (version_track_id) REFERENCES track (id),
+    CHECK (original_track_id != version_track_id)
+);
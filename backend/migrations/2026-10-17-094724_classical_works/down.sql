@@ -0,0 +1,30 @@
+DROP TABLE IF EXISTS track_performer;
+DROP TABLE IF EXISTS track_composer;
+
+-- SQLite cannot drop columns, so the track table is recreated without the work and movement columns.
+DROP INDEX IF EXISTS track_work_id;
+CREATE TABLE track_without_work
+(
+    id           INTEGER NOT NULL,
+    album_id     INTEGER NOT NULL,
+    disc_number  INTEGER,
+    disc_total   INTEGER,
+    track_number INTEGER,
+    track_total  INTEGER,
+    title        TEXT    NOT NULL,
+    title_key    TEXT     NOT NULL DEFAULT '', -- Collation key of title: normalized, lowercase, and without accents.
+    added_at     DATETIME NOT NULL DEFAULT '1970-01-01 00:00:00', -- When the track was first synchronized from any source.
+    explicit     BOOLEAN,           -- Whether the track has explicit content, or NULL when no source says.
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (album_id) REFERENCES album (id)
+);
+INSERT INTO track_without_work (id, album_id, disc_number, disc_total, track_number, track_total, title, title_key, added_at, explicit)
+SELECT id, album_id, disc_number, disc_total, track_number, track_total, title, title_key, added_at, explicit
+FROM track;
+DROP TABLE track;
+ALTER TABLE track_without_work
+    RENAME TO track;
+CREATE INDEX track_title_key ON track (title_key);
+
+DROP TABLE IF EXISTS work;
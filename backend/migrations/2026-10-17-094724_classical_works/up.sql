@@ -0,0 +1,44 @@
+-- Classical works, such as a symphony, of which tracks are movements.
+CREATE TABLE work
+(
+    id          INTEGER NOT NULL,
+    name        TEXT    NOT NULL,
+    name_key    TEXT    NOT NULL, -- Collation key of name: normalized, lowercase, and without accents.
+    composer_id INTEGER,          -- Composer of the work, or NULL if unknown.
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (composer_id) REFERENCES artist (id)
+);
+CREATE INDEX work_name_key ON work (name_key);
+
+-- Classical work the track is a movement of, or NULL if it is not part of a work.
+ALTER TABLE track
+    ADD COLUMN work_id INTEGER REFERENCES work (id);
+-- Name of the movement within the work, such as "I. Allegro".
+ALTER TABLE track
+    ADD COLUMN movement TEXT;
+-- Number of the movement within the work.
+ALTER TABLE track
+    ADD COLUMN movement_number INTEGER;
+CREATE INDEX track_work_id ON track (work_id);
+
+CREATE TABLE track_composer
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+
+    PRIMARY KEY (track_id, artist_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+
+-- Performers of tracks, such as soloists and orchestras, separate from the track artists.
+CREATE TABLE track_performer
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+
+    PRIMARY KEY (track_id, artist_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
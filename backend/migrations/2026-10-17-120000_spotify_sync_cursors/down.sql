@@ -0,0 +1,17 @@
+DROP TABLE IF EXISTS spotify_playlist_snapshot;
+
+-- SQLite cannot drop columns, so the sync state table is recreated without the saved albums cursor.
+CREATE TABLE spotify_sync_state_without_cursor
+(
+    spotify_source_id INTEGER  NOT NULL,
+    full_synced_at    DATETIME NOT NULL,
+
+    PRIMARY KEY (spotify_source_id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id)
+);
+INSERT INTO spotify_sync_state_without_cursor (spotify_source_id, full_synced_at)
+SELECT spotify_source_id, full_synced_at
+FROM spotify_sync_state;
+DROP TABLE spotify_sync_state;
+ALTER TABLE spotify_sync_state_without_cursor
+    RENAME TO spotify_sync_state;
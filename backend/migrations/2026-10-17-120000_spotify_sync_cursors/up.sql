@@ -0,0 +1,18 @@
+-- Cursors of incremental synchronizations of Spotify sources, so that libraries and playlists that did not change since
+-- the last synchronization are skipped.
+
+-- Time at which the most recently saved album in the library of the source was saved, as of the last synchronization.
+ALTER TABLE spotify_sync_state
+    ADD COLUMN saved_albums_added_at DATETIME;
+
+-- Snapshot of each playlist of the source as of the last synchronization. Playlists of which the snapshot did not change
+-- are not fetched again.
+CREATE TABLE spotify_playlist_snapshot
+(
+    spotify_source_id INTEGER NOT NULL,
+    playlist_id       TEXT    NOT NULL,
+    snapshot_id       TEXT    NOT NULL,
+
+    PRIMARY KEY (spotify_source_id, playlist_id),
+    FOREIGN KEY (spotify_source_id) REFERENCES spotify_source (id)
+);
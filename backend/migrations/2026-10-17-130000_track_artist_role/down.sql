@@ -0,0 +1,46 @@
+CREATE TABLE track_composer
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+
+    PRIMARY KEY (track_id, artist_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO track_composer (track_id, artist_id)
+SELECT track_id, artist_id
+FROM track_artist
+WHERE role = 3;
+
+CREATE TABLE track_performer
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+
+    PRIMARY KEY (track_id, artist_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO track_performer (track_id, artist_id)
+SELECT track_id, artist_id
+FROM track_artist
+WHERE role = 5;
+
+-- Featured artists, remixers, and conductors have no place without roles, and are dropped.
+CREATE TABLE track_artist_without_role
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+
+    PRIMARY KEY (track_id, artist_id),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO track_artist_without_role (track_id, artist_id)
+SELECT track_id, artist_id
+FROM track_artist
+WHERE role = 0;
+
+DROP TABLE track_artist;
+ALTER TABLE track_artist_without_role
+    RENAME TO track_artist;
@@ -0,0 +1,32 @@
+-- Credit artists on tracks with a role, replacing the separate composer and performer tables. Roles are stored as
+-- integers: 0 = main, 1 = featured, 2 = remixer, 3 = composer, 4 = conductor, 5 = performer. These values are mapped
+-- to `TrackArtistRole` and must not change. An artist can be credited multiple times on the same track with different
+-- roles.
+
+-- SQLite cannot change the primary key of an existing table, so the table is recreated with the role column.
+CREATE TABLE track_artist_with_role
+(
+    track_id  INTEGER NOT NULL,
+    artist_id INTEGER NOT NULL,
+    role      INTEGER NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (track_id, artist_id, role),
+    FOREIGN KEY (track_id) REFERENCES track (id),
+    FOREIGN KEY (artist_id) REFERENCES artist (id)
+);
+INSERT INTO track_artist_with_role (track_id, artist_id, role)
+SELECT track_id, artist_id, 0
+FROM track_artist;
+INSERT INTO track_artist_with_role (track_id, artist_id, role)
+SELECT track_id, artist_id, 3
+FROM track_composer;
+INSERT INTO track_artist_with_role (track_id, artist_id, role)
+SELECT track_id, artist_id, 5
+FROM track_performer;
+
+DROP TABLE track_artist;
+ALTER TABLE track_artist_with_role
+    RENAME TO track_artist;
+
+DROP TABLE track_composer;
+DROP TABLE track_performer;
@@ -0,0 +1,132 @@
+use std::backtrace::Backtrace;
+
+use reqwest::Url;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::cover::is_supported_cover_mime_type;
+
+/// Settings for fetching artist images and biographies during sync. When `theaudiodb_api_key` is set, TheAudioDB is
+/// tried first, falling back to Wikipedia.
+#[derive(Clone, Default, Debug)]
+pub struct ArtistEnrichmentSettings {
+  pub theaudiodb_api_key: Option<String>,
+}
+
+/// Biography and image URL of an artist, fetched from an external service.
+#[derive(Clone, Default, Debug)]
+pub struct FetchedArtistInfo {
+  pub biography: Option<String>,
+  pub image_url: Option<String>,
+  pub source_url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ArtistInfoFetchError {
+  #[error("Failed to create request URL")]
+  UrlCreateFail(#[from] url::ParseError, Backtrace),
+  #[error("Failed to request artist info")]
+  RequestFail(#[from] reqwest::Error, Backtrace),
+}
+
+/// Fetches the biography and image URL of the artist with `name`, returning `None` if no service knows the artist.
+pub async fn fetch_artist_info(name: &str, settings: &ArtistEnrichmentSettings) -> Result<Option<FetchedArtistInfo>, ArtistInfoFetchError> {
+  if let Some(api_key) = &settings.theaudiodb_api_key {
+    if let Some(info) = fetch_theaudiodb_artist_info(name, api_key).await? {
+      return Ok(Some(info));
+    }
+  }
+  fetch_wikipedia_artist_info(name).await
+}
+
+/// Fetches the image at `url`, returning its MIME type and data, or `None` if it is not a supported image.
+pub async fn fetch_artist_image(url: &str) -> Result<Option<(String, Vec<u8>)>, ArtistInfoFetchError> {
+  let response = reqwest::get(url).await?.error_for_status()?;
+  let mime_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+    .and_then(|mime_type| mime_type.to_str().ok())
+    .map(|mime_type| mime_type.split(';').next().unwrap_or_default().trim().to_string())
+    .unwrap_or_default();
+  if !is_supported_cover_mime_type(&mime_type) { return Ok(None); }
+  Ok(Some((mime_type, response.bytes().await?.to_vec())))
+}
+
+// TheAudioDB
+
+#[derive(Deserialize)]
+struct TheAudioDbSearch {
+  artists: Option<Vec<TheAudioDbArtist>>,
+}
+
+#[derive(Deserialize)]
+struct TheAudioDbArtist {
+  #[serde(rename = "idArtist")]
+  id: String,
+  #[serde(rename = "strBiographyEN")]
+  biography: Option<String>,
+  #[serde(rename = "strArtistThumb")]
+  thumbnail_url: Option<String>,
+}
+
+async fn fetch_theaudiodb_artist_info(name: &str, api_key: &str) -> Result<Option<FetchedArtistInfo>, ArtistInfoFetchError> {
+  let mut url = Url::parse("https://www.theaudiodb.com/api/v1/json/")?.join(&format!("{}/search.php", api_key))?;
+  url.query_pairs_mut().append_pair("s", name);
+  let search: TheAudioDbSearch = reqwest::get(url).await?.error_for_status()?.json().await?;
+  let artist = match search.artists.and_then(|artists| artists.into_iter().next()) {
+    Some(artist) => artist,
+    None => return Ok(None),
+  };
+  let info = FetchedArtistInfo {
+    biography: artist.biography.as_deref().and_then(first_paragraph),
+    image_url: artist.thumbnail_url.filter(|url| !url.is_empty()),
+    source_url: Some(format!("https://www.theaudiodb.com/artist/{}", artist.id)),
+  };
+  if info.biography.is_none() && info.image_url.is_none() { return Ok(None); }
+  Ok(Some(info))
+}
+
+// Wikipedia
+
+#[derive(Deserialize)]
+struct WikipediaSummary {
+  #[serde(rename = "type")]
+  kind: String,
+  extract: Option<String>,
+  thumbnail: Option<WikipediaImage>,
+  content_urls: Option<WikipediaContentUrls>,
+}
+
+#[derive(Deserialize)]
+struct WikipediaImage {
+  source: String,
+}
+
+#[derive(Deserialize)]
+struct WikipediaContentUrls {
+  desktop: WikipediaPageUrls,
+}
+
+#[derive(Deserialize)]
+struct WikipediaPageUrls {
+  page: String,
+}
+
+async fn fetch_wikipedia_artist_info(name: &str) -> Result<Option<FetchedArtistInfo>, ArtistInfoFetchError> {
+  let mut url = Url::parse("https://en.wikipedia.org/api/rest_v1/page/summary/")?;
+  // UNWRAP: HTTPS URLs can always be a base, and thus always have path segments.
+  url.path_segments_mut().unwrap().pop_if_empty().push(name);
+  let response = reqwest::get(url).await?;
+  if response.status() == reqwest::StatusCode::NOT_FOUND { return Ok(None); }
+  let summary: WikipediaSummary = response.error_for_status()?.json().await?;
+  // Disambiguation pages do not describe the artist, and names of artists are often ambiguous.
+  if summary.kind != "standard" { return Ok(None); }
+  Ok(Some(FetchedArtistInfo {
+    biography: summary.extract.as_deref().and_then(first_paragraph),
+    image_url: summary.thumbnail.map(|thumbnail| thumbnail.source),
+    source_url: summary.content_urls.map(|content_urls| content_urls.desktop.page),
+  }))
+}
+
+/// Gets the first non-empty paragraph of `text`, to keep biographies short.
+fn first_paragraph(text: &str) -> Option<String> {
+  text.split('\n').map(|paragraph| paragraph.trim()).find(|paragraph| !paragraph.is_empty()).map(|paragraph| paragraph.to_string())
+}
@@ -0,0 +1,34 @@
+use std::backtrace::Backtrace;
+use std::io::Cursor;
+
+use image::{GenericImageView, ImageFormat, ImageOutputFormat};
+use thiserror::Error;
+
+/// JPEG quality of resized JPEG images.
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Error)]
+pub enum ArtworkResizeError {
+  #[error("Failed to decode or encode image")]
+  ImageFail(#[from] image::ImageError, Backtrace),
+}
+
+/// Resizes image `data` of `mime_type` to fit within a square of `size` pixels, preserving its aspect ratio and format.
+/// Returns `None` if the image already fits, or if its format cannot be resized, in which case the original image
+/// should be used.
+pub fn resize_artwork(mime_type: &str, data: &[u8], size: u32) -> Result<Option<Vec<u8>>, ArtworkResizeError> {
+  let (format, output_format) = match ImageFormat::from_mime_type(mime_type) {
+    Some(format @ ImageFormat::Jpeg) => (format, ImageOutputFormat::Jpeg(JPEG_QUALITY)),
+    Some(format @ ImageFormat::Png) => (format, ImageOutputFormat::Png),
+    _ => return Ok(None),
+  };
+  let image = image::load_from_memory_with_format(data, format)?;
+  let (width, height) = image.dimensions();
+  if width <= size && height <= size {
+    return Ok(None);
+  }
+  let resized = image.thumbnail(size, size);
+  let mut resized_data = Vec::new();
+  resized.write_to(&mut Cursor::new(&mut resized_data), output_format)?;
+  Ok(Some(resized_data))
+}
@@ -0,0 +1,172 @@
+use std::backtrace::Backtrace;
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use rodio::{Decoder, Source};
+use thiserror::Error;
+
+/// Sample rate that audio is downsampled to before analysis. Sufficient for tempo and key detection, while keeping the
+/// analysis cheap.
+const ANALYSIS_SAMPLE_RATE: u32 = 11025;
+
+/// Detected audio features of an audio file. Features that could not be detected (e.g., because the audio is too short
+/// or silent) are `None`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AudioFeatures {
+  pub bpm: Option<f32>,
+  /// Pitch class of the tonic of the key, where 0 is C, 1 is C#, and so forth.
+  pub key_pitch_class: Option<i32>,
+  pub key_minor: Option<bool>,
+  /// Root mean square amplitude of the audio in `0.0..=1.0`.
+  pub energy: Option<f32>,
+  /// Zero-crossing rate of the audio in `0.0..=1.0`, a cheap proxy for how bright (high-frequency heavy) it sounds.
+  pub brightness: Option<f32>,
+}
+
+#[derive(Debug, Error)]
+pub enum AudioFeaturesAnalyzeError {
+  #[error("Failed to open audio file")]
+  OpenFail(#[from] std::io::Error, Backtrace),
+  #[error("Failed to decode audio file")]
+  DecodeFail(#[from] rodio::decoder::DecoderError, Backtrace),
+}
+
+/// Decodes the audio file at `path` and detects its tempo and key.
+pub fn analyze_audio_features(path: impl AsRef<Path>) -> Result<AudioFeatures, AudioFeaturesAnalyzeError> {
+  let samples = decode_mono(path)?;
+  let bpm = detect_bpm(&samples);
+  let key = detect_key(&samples);
+  let (energy, brightness) = detect_energy_and_brightness(&samples);
+  Ok(AudioFeatures {
+    bpm,
+    key_pitch_class: key.map(|(pitch_class, _)| pitch_class),
+    key_minor: key.map(|(_, minor)| minor),
+    energy,
+    brightness,
+  })
+}
+
+/// Decodes the audio file at `path` into mono samples in `-1.0..=1.0` at (approximately) `ANALYSIS_SAMPLE_RATE`, by
+/// averaging over channels and over blocks of frames.
+fn decode_mono(path: impl AsRef<Path>) -> Result<Vec<f32>, AudioFeaturesAnalyzeError> {
+  let file = File::open(path)?;
+  let decoder = Decoder::new(BufReader::new(file))?;
+  let channels = decoder.channels().max(1) as usize;
+  let frames_per_sample = (decoder.sample_rate() / ANALYSIS_SAMPLE_RATE).max(1) as usize;
+  let samples_per_block = channels * frames_per_sample;
+
+  let mut samples = Vec::new();
+  let mut sum = 0.0;
+  let mut count = 0;
+  for sample in decoder {
+    sum += sample as f32 / i16::MAX as f32;
+    count += 1;
+    if count == samples_per_block {
+      samples.push(sum / samples_per_block as f32);
+      sum = 0.0;
+      count = 0;
+    }
+  }
+  Ok(samples)
+}
+
+// Tempo detection
+
+const ONSET_HOP_SIZE: usize = 128;
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+
+/// Detects the tempo in beats per minute, by autocorrelating an onset strength envelope (the positive differences in
+/// log energy between consecutive frames) and picking the lag with the strongest periodicity.
+fn detect_bpm(samples: &[f32]) -> Option<f32> {
+  let energies: Vec<f32> = samples.chunks_exact(ONSET_HOP_SIZE)
+    .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() + 1e-10).ln())
+    .collect();
+  let onsets: Vec<f32> = energies.windows(2).map(|w| (w[1] - w[0]).max(0.0)).collect();
+
+  let frames_per_second = ANALYSIS_SAMPLE_RATE as f32 / ONSET_HOP_SIZE as f32;
+  let min_lag = (frames_per_second * 60.0 / MAX_BPM).floor() as usize;
+  let max_lag = (frames_per_second * 60.0 / MIN_BPM).ceil() as usize;
+  if onsets.len() <= max_lag * 2 { return None; }
+
+  let (best_lag, best_correlation) = (min_lag..=max_lag)
+    .map(|lag| {
+      let correlation = onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum::<f32>() / (onsets.len() - lag) as f32;
+      (lag, correlation)
+    })
+    .fold((0, 0.0), |best, current| if current.1 > best.1 { current } else { best });
+  if best_lag == 0 || best_correlation <= 0.0 { return None; }
+  Some(60.0 * frames_per_second / best_lag as f32)
+}
+
+// Energy and brightness detection
+
+fn detect_energy_and_brightness(samples: &[f32]) -> (Option<f32>, Option<f32>) {
+  if samples.len() < 2 { return (None, None); }
+  let energy = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+  let zero_crossings = samples.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+  let brightness = zero_crossings as f32 / (samples.len() - 1) as f32;
+  (Some(energy), Some(brightness))
+}
+
+// Key detection
+
+const KEY_FRAME_SIZE: usize = 4096;
+/// MIDI note numbers of the lowest and highest pitch that are taken into account; C3 to B5.
+const KEY_MIN_NOTE: i32 = 48;
+const KEY_MAX_NOTE: i32 = 83;
+/// Krumhansl-Kessler key profiles, starting at the tonic.
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Detects the key as a (tonic pitch class, is minor) pair, by accumulating a chromagram using the Goertzel algorithm
+/// and correlating it with the rotated major and minor key profiles.
+fn detect_key(samples: &[f32]) -> Option<(i32, bool)> {
+  let coefficients: Vec<(usize, f32)> = (KEY_MIN_NOTE..=KEY_MAX_NOTE).map(|note| {
+    let frequency = 440.0 * 2f32.powf((note - 69) as f32 / 12.0);
+    let coefficient = 2.0 * (2.0 * PI * frequency / ANALYSIS_SAMPLE_RATE as f32).cos();
+    ((note % 12) as usize, coefficient)
+  }).collect();
+
+  let mut chroma = [0f32; 12];
+  for frame in samples.chunks_exact(KEY_FRAME_SIZE) {
+    for (pitch_class, coefficient) in &coefficients {
+      let (mut s1, mut s2) = (0f32, 0f32);
+      for sample in frame {
+        let s = sample + coefficient * s1 - s2;
+        s2 = s1;
+        s1 = s;
+      }
+      let power = s1 * s1 + s2 * s2 - coefficient * s1 * s2;
+      chroma[*pitch_class] += power.max(0.0).sqrt();
+    }
+  }
+  if chroma.iter().all(|c| *c <= 0.0) { return None; }
+
+  let mut best: Option<(i32, bool, f32)> = None;
+  for tonic in 0..12 {
+    for (minor, profile) in [(false, &MAJOR_PROFILE), (true, &MINOR_PROFILE)] {
+      let rotated: Vec<f32> = (0..12).map(|i| profile[(i + 12 - tonic) % 12]).collect();
+      let correlation = pearson_correlation(&chroma, &rotated);
+      if best.map_or(true, |(_, _, c)| correlation > c) {
+        best = Some((tonic as i32, minor, correlation));
+      }
+    }
+  }
+  best.map(|(tonic, minor, _)| (tonic, minor))
+}
+
+fn pearson_correlation(a: &[f32], b: &[f32]) -> f32 {
+  let mean_a = a.iter().sum::<f32>() / a.len() as f32;
+  let mean_b = b.iter().sum::<f32>() / b.len() as f32;
+  let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+  for (x, y) in a.iter().zip(b) {
+    covariance += (x - mean_a) * (y - mean_b);
+    variance_a += (x - mean_a) * (x - mean_a);
+    variance_b += (y - mean_b) * (y - mean_b);
+  }
+  if variance_a <= 0.0 || variance_b <= 0.0 { return 0.0; }
+  covariance / (variance_a.sqrt() * variance_b.sqrt())
+}
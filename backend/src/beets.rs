@@ -0,0 +1,95 @@
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::path::Path;
+
+use diesel::prelude::*;
+use diesel::sql_types::{Binary, Integer, Nullable, Text};
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_filesystem_sync::{FilesystemSyncTrack, path_key};
+
+/// Item (track) of the library database of beets, with the metadata that musium uses.
+#[derive(QueryableByName, Clone, Debug)]
+pub struct BeetsItem {
+  #[sql_type = "Binary"]
+  pub path: Vec<u8>,
+  #[sql_type = "Nullable<Text>"]
+  pub title: Option<String>,
+  #[sql_type = "Nullable<Text>"]
+  pub artist: Option<String>,
+  #[sql_type = "Nullable<Text>"]
+  pub album: Option<String>,
+  #[sql_type = "Nullable<Text>"]
+  pub albumartist: Option<String>,
+  #[sql_type = "Nullable<Integer>"]
+  pub track: Option<i32>,
+  #[sql_type = "Nullable<Integer>"]
+  pub tracktotal: Option<i32>,
+  #[sql_type = "Nullable<Integer>"]
+  pub disc: Option<i32>,
+  #[sql_type = "Nullable<Integer>"]
+  pub disctotal: Option<i32>,
+  #[sql_type = "Nullable<Text>"]
+  pub composer: Option<String>,
+}
+
+/// Items of the library database of beets, by the path key of their absolute path.
+#[derive(Clone, Default, Debug)]
+pub struct BeetsLibrary {
+  items: HashMap<String, BeetsItem>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReadBeetsLibraryError {
+  #[error("Beets library database '{0}' does not exist")]
+  NotFoundFail(String),
+  #[error("Failed to open beets library database")]
+  ConnectFail(#[from] diesel::ConnectionError, Backtrace),
+  #[error("Failed to query beets library database")]
+  QueryFail(#[from] diesel::result::Error, Backtrace),
+}
+
+impl BeetsLibrary {
+  /// Reads the items of the beets library database at `path`, which is usually `library.db` in the beets configuration
+  /// directory. The database is only read, never written.
+  pub fn read(path: impl AsRef<Path>) -> Result<Self, ReadBeetsLibraryError> {
+    use ReadBeetsLibraryError::*;
+    let path = path.as_ref();
+    // Check whether the database exists, as connecting creates an empty database if it does not.
+    if !path.is_file() {
+      return Err(NotFoundFail(path.display().to_string()));
+    }
+    let connection = SqliteConnection::establish(&path.to_string_lossy())?;
+    let items: Vec<BeetsItem> = diesel::sql_query("SELECT path, title, artist, album, albumartist, track, tracktotal, disc, disctotal, composer FROM items")
+      .load(&connection)?;
+    let items: HashMap<_, _> = items.into_iter()
+      .map(|item| (path_key(&String::from_utf8_lossy(&item.path)), item))
+      .collect();
+    event!(Level::DEBUG, item_count = items.len(), "Read beets library");
+    Ok(Self { items })
+  }
+
+  /// Overrides the metadata of `track` in the local source with `directory` with the metadata of the corresponding item
+  /// in this library, so that the metadata curated with beets is canonical. Values that beets does not know are kept.
+  /// Returns whether a corresponding item was found.
+  pub fn apply(&self, directory: &str, track: &mut FilesystemSyncTrack) -> bool {
+    let absolute_path = Path::new(directory).join(&track.file_path);
+    let item = match self.items.get(&path_key(&absolute_path.to_string_lossy())) {
+      Some(item) => item,
+      None => return false,
+    };
+    let non_empty = |value: &Option<String>| value.as_ref().filter(|value| !value.is_empty()).cloned();
+    let positive = |value: Option<i32>| value.filter(|value| *value > 0);
+    if let Some(title) = non_empty(&item.title) { track.title = title; }
+    if let Some(album) = non_empty(&item.album) { track.album = album; }
+    if let Some(artist) = non_empty(&item.artist) { track.track_artists = vec![artist]; }
+    if let Some(album_artist) = non_empty(&item.albumartist) { track.album_artists = vec![album_artist]; }
+    if let Some(track_number) = positive(item.track) { track.track_number = Some(track_number); }
+    if let Some(track_total) = positive(item.tracktotal) { track.track_total = Some(track_total); }
+    if let Some(disc_number) = positive(item.disc) { track.disc_number = Some(disc_number); }
+    if let Some(disc_total) = positive(item.disctotal) { track.disc_total = Some(disc_total); }
+    if let Some(composer) = non_empty(&item.composer) { track.composers = vec![composer]; }
+    true
+  }
+}
@@ -0,0 +1,57 @@
+use std::backtrace::Backtrace;
+
+use thiserror::Error;
+
+/// Base URL of the Cover Art Archive API.
+const COVER_ART_ARCHIVE_URL: &str = "https://coverartarchive.org";
+
+/// MIME types of cover images that can be set and embedded into ID3v2 tags.
+pub const SUPPORTED_COVER_MIME_TYPES: &[&str] = &["image/jpeg", "image/png"];
+
+pub fn is_supported_cover_mime_type(mime_type: &str) -> bool {
+  SUPPORTED_COVER_MIME_TYPES.contains(&mime_type)
+}
+
+#[derive(Debug, Error)]
+pub enum CoverFetchError {
+  #[error("'{0}' is not a valid MusicBrainz release ID")]
+  InvalidReleaseIdFail(String),
+  #[error("Failed to request cover from the Cover Art Archive")]
+  RequestFail(#[from] reqwest::Error, Backtrace),
+  #[error("The Cover Art Archive has no front cover for MusicBrainz release '{0}'")]
+  NotFoundFail(String),
+  #[error("The Cover Art Archive returned a cover with unsupported MIME type '{0}'")]
+  UnsupportedMimeTypeFail(String),
+}
+
+/// Fetches the front cover of the MusicBrainz release with `release_id` from the Cover Art Archive, returning its MIME
+/// type and image data.
+pub async fn fetch_cover_art_archive_front(release_id: &str) -> Result<(String, Vec<u8>), CoverFetchError> {
+  use CoverFetchError::*;
+  let release_id = release_id.trim().to_ascii_lowercase();
+  if !is_valid_release_id(&release_id) {
+    return Err(InvalidReleaseIdFail(release_id));
+  }
+  let response = reqwest::get(format!("{}/release/{}/front", COVER_ART_ARCHIVE_URL, release_id)).await?;
+  if response.status() == reqwest::StatusCode::NOT_FOUND {
+    return Err(NotFoundFail(release_id));
+  }
+  let response = response.error_for_status()?;
+  let mime_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+    .and_then(|mime_type| mime_type.to_str().ok())
+    .map(|mime_type| mime_type.split(';').next().unwrap_or_default().trim().to_string())
+    .unwrap_or_default();
+  if !is_supported_cover_mime_type(&mime_type) {
+    return Err(UnsupportedMimeTypeFail(mime_type));
+  }
+  let data = response.bytes().await?.to_vec();
+  Ok((mime_type, data))
+}
+
+/// Checks whether `release_id` is a MusicBrainz ID: a lowercase hyphenated UUID.
+fn is_valid_release_id(release_id: &str) -> bool {
+  release_id.len() == 36 && release_id.char_indices().all(|(i, c)| match i {
+    8 | 13 | 18 | 23 => c == '-',
+    _ => c.is_ascii_digit() || ('a'..='f').contains(&c),
+  })
+}
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use musium_core::collation::name_collation_key;
+use musium_core::model::TrackArtistRole;
+use musium_filesystem_sync::FilesystemSyncTrack;
+
+/// Separators between an artist and the artists featured by it, such as in `A feat. B`, in lowercase.
+const FEATURING_SEPARATORS: [&str; 5] = [" feat. ", " feat ", " ft. ", " ft ", " featuring "];
+/// Prefixes of a credit of featured artists in a qualifier, such as in `Song (feat. B)`, in lowercase.
+const FEATURING_PREFIXES: [&str; 6] = ["feat. ", "feat ", "ft. ", "ft ", "featuring ", "with "];
+/// Suffixes of a credit of remixers in a qualifier, such as in `Song (C Remix)`, in lowercase.
+const REMIX_SUFFIXES: [&str; 3] = [" remix", " rmx", " mix"];
+/// Prefixes of a credit of remixers in a qualifier, such as in `Song (Remixed by C)`, in lowercase.
+const REMIX_PREFIXES: [&str; 2] = ["remixed by ", "remix by "];
+/// Words that describe a kind of mix rather than who made it, such as in `Song (Extended Club Mix)`, in lowercase.
+const GENERIC_MIX_WORDS: [&str; 14] = ["original", "extended", "club", "radio", "album", "single", "dub", "instrumental", "vip", "official", "short", "long", "edit", "version"];
+/// Separators between multiple credited artists, such as in `feat. B, C & D`, in lowercase.
+const NAME_SEPARATORS: [&str; 4] = [", ", " & ", " and ", " vs. "];
+
+/// Artists credited in a track title.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct TitleCredits {
+  pub featured_artists: Vec<String>,
+  pub remixers: Vec<String>,
+}
+
+/// Parses the artists credited in `title`, such as `B` as featured artist in `Song (feat. B)` or `Song feat. B`, and `C`
+/// as remixer in `Song (C Remix)` or `Song - C Remix`. Remixes without a remixer, such as `Song (2011 Remix)` or
+/// `Song (Extended Club Mix)`, credit no one.
+pub fn parse_title_credits(title: &str) -> TitleCredits {
+  let mut credits = TitleCredits::default();
+  let (outside, mut qualifiers) = split_bracketed(title);
+  let mut plain = outside.as_str();
+  if let Some((rest, qualifier)) = outside.rsplit_once(" - ") {
+    qualifiers.push(qualifier.trim());
+    plain = rest;
+  }
+  if let Some((_, featured)) = split_featuring(plain) {
+    credits.featured_artists.extend(split_artist_names(featured));
+  }
+  for qualifier in qualifiers {
+    if let Some(featured) = strip_prefix_ignore_case(qualifier, &FEATURING_PREFIXES) {
+      credits.featured_artists.extend(split_artist_names(featured));
+    } else if let Some(remixers) = parse_remixers(qualifier) {
+      credits.remixers.extend(split_artist_names(remixers));
+    }
+  }
+  credits
+}
+
+/// Splits artist name `name`, such as `A feat. B & C` or `A (feat. B)`, into the main artist and the featured artists.
+/// Returns `name` and no featured artists if it features no one.
+pub fn split_featured_artists(name: &str) -> (String, Vec<String>) {
+  let mut main = String::with_capacity(name.len());
+  let mut featured = Vec::new();
+  let mut end = 0;
+  for range in bracketed_ranges(name) {
+    if let Some(names) = strip_prefix_ignore_case(&name[range.start + 1..range.end - 1], &FEATURING_PREFIXES) {
+      featured.extend(split_artist_names(names));
+      main.push_str(&name[end..range.start]);
+      end = range.end;
+    }
+  }
+  main.push_str(&name[end..]);
+  let main = match split_featuring(&main) {
+    Some((main, names)) => {
+      featured.extend(split_artist_names(names));
+      main.to_string()
+    }
+    None => main.trim().to_string(),
+  };
+  if main.is_empty() {
+    return (name.to_string(), Vec::new());
+  }
+  (main, featured)
+}
+
+/// Splits the featured artists and remixers off the track artists and the title of `track`, such as `B` off track artist
+/// `A feat. B`, into its featured artists and remixers, so that they are not credited as main artists. Featured album
+/// artists are split off as well, but dropped, as albums have no featured artists. Artists credited as main artist are
+/// not credited as featured artist or remixer as well.
+pub fn split_track_credits(track: &mut FilesystemSyncTrack) {
+  let mut track_artists = Vec::new();
+  for name in &track.track_artists {
+    let (main, featured) = split_featured_artists(name);
+    track_artists.push(main);
+    track.featured_artists.extend(featured);
+  }
+  track.track_artists = track_artists;
+  track.album_artists = track.album_artists.iter().map(|name| split_featured_artists(name).0).collect();
+  let title_credits = parse_title_credits(&track.title);
+  track.featured_artists.extend(title_credits.featured_artists);
+  track.remixers.extend(title_credits.remixers);
+
+  let mut seen = HashSet::new();
+  retain_unique(&mut track.track_artists, &mut seen);
+  retain_unique(&mut track.album_artists, &mut HashSet::new());
+  let mut seen_featured = seen.clone();
+  retain_unique(&mut track.featured_artists, &mut seen_featured);
+  retain_unique(&mut track.remixers, &mut seen);
+}
+
+/// Gets the role of each of the artists named `artist_names` of a track titled `title`, as streaming services list
+/// featured artists and remixers as track artists as well. Artists credited in the title as featured artist or remixer
+/// get that role, and other artists are main artists. The first artist is a main artist when no other artist is.
+pub fn artist_roles_from_title<'a>(title: &str, artist_names: impl IntoIterator<Item=&'a str>) -> Vec<TrackArtistRole> {
+  let credits = parse_title_credits(title);
+  let keys = |names: &[String]| -> HashSet<String> { names.iter().map(|name| name_collation_key(name)).collect() };
+  let (featured_keys, remixer_keys) = (keys(&credits.featured_artists), keys(&credits.remixers));
+  let mut roles: Vec<TrackArtistRole> = artist_names.into_iter()
+    .map(|name| {
+      let key = name_collation_key(name);
+      if remixer_keys.contains(&key) {
+        TrackArtistRole::Remixer
+      } else if featured_keys.contains(&key) {
+        TrackArtistRole::Featured
+      } else {
+        TrackArtistRole::Main
+      }
+    })
+    .collect();
+  if !roles.contains(&TrackArtistRole::Main) {
+    if let Some(role) = roles.first_mut() {
+      *role = TrackArtistRole::Main;
+    }
+  }
+  roles
+}
+
+/// Splits `text` into the text outside of parentheses and brackets, and the trimmed texts inside them.
+fn split_bracketed(text: &str) -> (String, Vec<&str>) {
+  let mut outside = String::with_capacity(text.len());
+  let mut inside = Vec::new();
+  let mut end = 0;
+  for range in bracketed_ranges(text) {
+    outside.push_str(&text[end..range.start]);
+    inside.push(text[range.start + 1..range.end - 1].trim());
+    end = range.end;
+  }
+  outside.push_str(&text[end..]);
+  (outside.trim().to_string(), inside)
+}
+
+/// Gets the ranges of the parts of `text` in parentheses or brackets, including the parentheses or brackets. Parts are
+/// not nested, as titles rarely nest them: a part ends at the first closing parenthesis or bracket that matches its
+/// opening one.
+fn bracketed_ranges(text: &str) -> Vec<Range<usize>> {
+  let mut ranges = Vec::new();
+  let mut open: Option<(usize, char)> = None;
+  for (i, c) in text.char_indices() {
+    match (open, c) {
+      (None, '(') => open = Some((i, ')')),
+      (None, '[') => open = Some((i, ']')),
+      (Some((start, close)), c) if c == close => {
+        ranges.push(start..i + c.len_utf8());
+        open = None;
+      }
+      _ => {}
+    }
+  }
+  ranges
+}
+
+/// Splits `name` at the first featuring separator, such as in `A feat. B`, into the trimmed texts before and after it.
+fn split_featuring(name: &str) -> Option<(&str, &str)> {
+  // ASCII lowercase keeps the byte offsets of `name`.
+  let lowercase = name.to_ascii_lowercase();
+  let (start, separator) = FEATURING_SEPARATORS.iter()
+    .filter_map(|separator| lowercase.find(separator).map(|start| (start, separator)))
+    .min_by_key(|(start, _)| *start)?;
+  Some((name[..start].trim(), name[start + separator.len()..].trim()))
+}
+
+/// Gets the remixers credited in `qualifier`, such as `C` in `C's Remix` or `Remixed by C`, or `None` if it does not
+/// credit a remixer.
+fn parse_remixers(qualifier: &str) -> Option<&str> {
+  let lowercase = qualifier.to_ascii_lowercase();
+  let remixers = match REMIX_SUFFIXES.iter().find(|suffix| lowercase.ends_with(*suffix)) {
+    Some(suffix) => qualifier[..qualifier.len() - suffix.len()].trim().trim_end_matches("'s"),
+    None => strip_prefix_ignore_case(qualifier, &REMIX_PREFIXES)?,
+  };
+  let is_generic = remixers.split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .all(|word| word.chars().all(|c| c.is_ascii_digit()) || GENERIC_MIX_WORDS.contains(&word.to_lowercase().as_str()));
+  if is_generic { None } else { Some(remixers) }
+}
+
+/// Strips the first of `prefixes`, in lowercase, that `text` starts with regardless of case, returning the trimmed rest.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+  let lowercase = text.to_ascii_lowercase();
+  let prefix = prefixes.iter().find(|prefix| lowercase.starts_with(*prefix))?;
+  Some(text[prefix.len()..].trim())
+}
+
+/// Splits multiple credited artists, such as `B, C & D`, into their names.
+fn split_artist_names(names: &str) -> Vec<String> {
+  let lowercase = names.to_ascii_lowercase();
+  let mut split = Vec::new();
+  let mut start = 0;
+  let mut i = 0;
+  while i < names.len() {
+    if let Some(separator) = NAME_SEPARATORS.iter().find(|separator| lowercase[i..].starts_with(*separator)) {
+      split.push(&names[start..i]);
+      i += separator.len();
+      start = i;
+    } else {
+      i += names[i..].chars().next().map_or(1, |c| c.len_utf8());
+    }
+  }
+  split.push(&names[start..]);
+  split.into_iter()
+    .map(|name| name.trim())
+    .filter(|name| !name.is_empty())
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Retains the non-empty `names` of which the collation key is not in `seen` yet, adding the keys of retained names to
+/// `seen`, so that only the first of names that differ in case or accents is retained.
+fn retain_unique(names: &mut Vec<String>, seen: &mut HashSet<String>) {
+  names.retain(|name| !name.trim().is_empty() && seen.insert(name_collation_key(name)));
+}
@@ -1,9 +1,12 @@
 use std::backtrace::Backtrace;
 use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use diesel::connection::SimpleConnection;
 use diesel::prelude::*;
-use diesel::r2d2::{self, ConnectionManager, Pool, PooledConnection};
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool, PooledConnection};
 use thiserror::Error;
 
 use musium_spotify_client::SpotifyClient;
@@ -19,15 +22,87 @@ macro_rules! time {
   }}
 }
 
+/// Filters boxed query `$q` of tracks by content filter `$f`, leaving out the tracks that it does not allow. A macro
+/// because boxed queries of tracks have different types depending on their joins and selections.
+macro_rules! filter_content {
+  ($q:expr, $f:expr) => {{
+    use musium_core::model::ContentFilter;
+    use musium_core::schema::track::explicit;
+    match $f {
+      ContentFilter::Off => $q,
+      ContentFilter::ExcludeExplicit => $q.filter(explicit.is_null().or(explicit.eq(false))),
+      ContentFilter::OnlyClean => $q.filter(explicit.eq(false)),
+    }
+  }}
+}
+
+/// Filters boxed query `$q` of tracks to leave out the tracks that the user with ID `$u` hid, either directly, or by
+/// hiding their album or one of their artists. Hidden tracks are selected with subqueries instead of binding their IDs,
+/// as a user may hide more tracks than SQLite allows bound variables in a query.
+macro_rules! filter_hidden {
+  ($q:expr, $u:expr) => {{
+    use musium_core::model::{HiddenItemKind, TrackArtistRole};
+    use musium_core::schema::{track, track_artist, user_hidden_item};
+    let hidden_user_id: i32 = $u;
+    let hidden_item_ids = |hidden_kind: HiddenItemKind| user_hidden_item::table
+      .select(user_hidden_item::item_id)
+      .filter(user_hidden_item::user_id.eq(hidden_user_id))
+      .filter(user_hidden_item::kind.eq(hidden_kind));
+    let hidden_artist_track_ids = track_artist::table
+      .select(track_artist::track_id)
+      .filter(track_artist::artist_id.eq_any(hidden_item_ids(HiddenItemKind::Artist)))
+      .filter(track_artist::role.eq(TrackArtistRole::Main));
+    $q.filter(track::id.ne_all(hidden_item_ids(HiddenItemKind::Track)))
+      .filter(track::album_id.ne_all(hidden_item_ids(HiddenItemKind::Album)))
+      .filter(track::id.ne_all(hidden_artist_track_ids))
+  }}
+}
+
 pub mod source;
 pub mod album;
+pub mod audio_features;
+pub mod availability;
+pub mod content_filter;
+pub mod cover;
+pub mod health;
+pub mod hidden_item;
+pub mod home_assistant;
 pub mod track;
+pub mod track_filter;
 pub mod local_track;
+pub mod lyrics;
+pub mod managed_library;
+pub mod media_cache;
 pub mod spotify_track;
 pub mod artist;
+pub mod artist_info;
+pub mod notification;
+pub mod play_history;
 pub mod playback;
+pub mod playlist;
+pub mod playlist_mix;
+pub mod recommendation;
+pub mod release;
+pub mod relink;
+pub mod report;
+pub mod sort_name;
 pub mod user;
+pub mod user_preference;
 pub mod sync;
+pub mod sync_lock;
+pub mod sync_run;
+pub mod tag;
+pub mod tagger_hook;
+#[cfg(test)]
+mod test_database;
+pub mod track_issue;
+pub mod track_relation;
+pub mod track_upload;
+pub mod undo;
+pub mod waveform;
+pub mod webhook;
+pub mod work;
+pub mod year_review;
 
 
 #[derive(Clone)]
@@ -39,6 +114,14 @@ pub struct Database {
 struct Inner {
   spotify_sync: SpotifyClient,
   password_hasher: PasswordHasher,
+  media_cache_size_limit: i64,
+  /// Library database of beets, of which the metadata overrides the metadata from tags of local tracks.
+  beets_database: Option<PathBuf>,
+  /// Directory that contains the database file.
+  directory: PathBuf,
+  /// Whether the database is quiesced, during which reads skip their incidental writes, such as recording plays and
+  /// caching generated media, and background tasks are paused.
+  quiesced: AtomicBool,
 }
 
 
@@ -55,15 +138,58 @@ impl Database {
     database_url: D,
     spotify_sync: SpotifyClient,
     password_hasher: PasswordHasher,
+    media_cache_size_limit: i64,
+    beets_database: Option<PathBuf>,
   ) -> Result<Database, DatabaseCreateError> {
     let connection_pool = Pool::builder()
       .max_size(16)
+      .connection_customizer(Box::new(BusyTimeoutCustomizer))
       .build(ConnectionManager::<SqliteConnection>::new(database_url.as_ref()))?;
-    let inner = Arc::new(Inner { spotify_sync, password_hasher });
+    let directory = match Path::new(database_url.as_ref()).parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+      _ => PathBuf::from("."),
+    };
+    let inner = Arc::new(Inner { spotify_sync, password_hasher, media_cache_size_limit, beets_database, directory, quiesced: AtomicBool::new(false) });
     Ok(Database { connection_pool, inner })
   }
 }
 
+/// Duration, in milliseconds, that a connection waits for the write lock of the database held by another connection,
+/// such as the sync lock heartbeat waiting for a write of the API, before failing.
+const BUSY_TIMEOUT_MILLISECONDS: u32 = 5000;
+
+#[derive(Debug)]
+struct BusyTimeoutCustomizer;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for BusyTimeoutCustomizer {
+  fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+    connection.batch_execute(&format!("PRAGMA busy_timeout = {};", BUSY_TIMEOUT_MILLISECONDS)).map_err(r2d2::Error::QueryError)
+  }
+}
+
+
+// Quiescing
+
+impl Database {
+  /// Sets whether the database is quiesced, for example while the server is in maintenance mode so that backups and
+  /// migrations can run against it.
+  pub fn set_quiesced(&self, quiesced: bool) {
+    self.inner.quiesced.store(quiesced, Ordering::SeqCst);
+  }
+
+  /// Returns whether the database is quiesced, in which case background tasks must not modify it.
+  pub fn is_quiesced(&self) -> bool {
+    self.inner.quiesced.load(Ordering::SeqCst)
+  }
+}
+
+impl DatabaseConnection {
+  /// Returns whether the database is quiesced, in which case incidental writes of reads must be skipped.
+  pub(crate) fn is_quiesced(&self) -> bool {
+    self.inner.quiesced.load(Ordering::SeqCst)
+  }
+}
+
 
 // Connecting to the database
 
@@ -1,21 +1,65 @@
 use diesel::prelude::*;
 
-use musium_core::model::{Album, AlbumArtist, Artist};
+use musium_core::api::{AlphabetSection, HiddenFilter};
+use musium_core::collation::{alphabet_index, name_collation_key};
+use musium_core::model::{Album, AlbumArtist, Artist, HiddenItemKind};
 use musium_core::model::collection::AlbumsRaw;
 use musium_core::schema;
 
 use super::{DatabaseConnection, DatabaseQueryError};
 
 impl DatabaseConnection {
-  pub fn list_albums(&self) -> Result<AlbumsRaw, DatabaseQueryError> {
-    let albums = schema::album::table.load::<Album>(&self.connection)?;
+  /// Lists the albums sorted by sort name, excluding the albums that the user with `user_id` hid unless `filter` includes
+  /// them.
+  pub fn list_albums(&self, user_id: i32, filter: HiddenFilter) -> Result<AlbumsRaw, DatabaseQueryError> {
+    let mut albums = schema::album::table.load::<Album>(&self.connection)?;
+    if !filter.include_hidden {
+      let hidden_album_ids = self.get_hidden_item_ids(user_id, HiddenItemKind::Album)?;
+      albums.retain(|album| !hidden_album_ids.contains(&album.id));
+    }
+    albums.sort_by_cached_key(|album| name_collation_key(&album.sort_name));
     let artists = schema::artist::table.load::<Artist>(&self.connection)?;
     let album_artists = schema::album_artist::table.load::<AlbumArtist>(&self.connection)?;
-    Ok(AlbumsRaw { albums, artists, album_artists })
+    let availability = self.get_album_availability(&self.get_track_availability()?)?;
+    Ok(AlbumsRaw { albums, artists, album_artists, availability })
+  }
+
+  /// Gets the alphabet index of the albums in the order of `list_albums`.
+  pub fn get_album_alphabet_index(&self, user_id: i32, filter: HiddenFilter) -> Result<Vec<AlphabetSection>, DatabaseQueryError> {
+    let hidden_album_ids = if filter.include_hidden { Vec::new() } else { self.get_hidden_item_ids(user_id, HiddenItemKind::Album)? };
+    use schema::album::dsl::*;
+    let mut sort_names: Vec<String> = album.select((id, sort_name)).load::<(i32, String)>(&self.connection)?
+      .into_iter()
+      .filter(|(album_id, _)| !hidden_album_ids.contains(album_id))
+      .map(|(_, album_sort_name)| album_sort_name)
+      .collect();
+    sort_names.sort_by_cached_key(|s| name_collation_key(s));
+    Ok(alphabet_index(sort_names.iter().map(String::as_str)))
   }
 
   pub fn get_album_by_id(&self, input_id: i32) -> Result<Option<Album>, DatabaseQueryError> {
     use schema::album::dsl::*;
     Ok(album.find(input_id).first::<Album>(&self.connection).optional()?)
   }
+
+  pub fn list_albums_by_ids(&self, input_ids: &[i32]) -> Result<Vec<Album>, DatabaseQueryError> {
+    use schema::album::dsl::*;
+    Ok(album.filter(id.eq_any(input_ids)).order(id.asc()).load::<Album>(&self.connection)?)
+  }
+
+  /// Gets the largest ID of all albums, or `None` if there are no albums. Albums that are created later get a larger ID.
+  pub fn get_max_album_id(&self) -> Result<Option<i32>, DatabaseQueryError> {
+    use schema::album::dsl::*;
+    Ok(album.select(diesel::dsl::max(id)).first::<Option<i32>>(&self.connection)?)
+  }
+
+  /// Lists the IDs of albums with an ID larger than `input_id`, or of all albums if it is `None`.
+  pub fn list_album_ids_after(&self, input_id: Option<i32>) -> Result<Vec<i32>, DatabaseQueryError> {
+    use schema::album::dsl::*;
+    let mut query = album.select(id).order(id.asc()).into_boxed();
+    if let Some(input_id) = input_id {
+      query = query.filter(id.gt(input_id));
+    }
+    Ok(query.load::<i32>(&self.connection)?)
+  }
 }
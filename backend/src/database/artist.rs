@@ -1,14 +1,35 @@
 use diesel::prelude::*;
 
-use musium_core::model::Artist;
+use musium_core::api::{AlphabetSection, HiddenFilter};
+use musium_core::collation::{alphabet_index, name_collation_key};
+use musium_core::model::{Artist, HiddenItemKind};
 use musium_core::schema;
 
 use super::{DatabaseConnection, DatabaseQueryError};
 
 impl DatabaseConnection {
-  pub fn list_artists(&self) -> Result<Vec<Artist>, DatabaseQueryError> {
+  /// Lists the artists sorted by sort name, excluding the artists that the user with `user_id` hid unless `filter`
+  /// includes them.
+  pub fn list_artists(&self, user_id: i32, filter: HiddenFilter) -> Result<Vec<Artist>, DatabaseQueryError> {
+    let hidden_artist_ids = if filter.include_hidden { Vec::new() } else { self.get_hidden_item_ids(user_id, HiddenItemKind::Artist)? };
     use schema::artist::dsl::*;
-    Ok(artist.load::<Artist>(&self.connection)?)
+    let mut artists = artist.load::<Artist>(&self.connection)?;
+    artists.retain(|a| !hidden_artist_ids.contains(&a.id));
+    artists.sort_by_cached_key(|a| name_collation_key(&a.sort_name));
+    Ok(artists)
+  }
+
+  /// Gets the alphabet index of the artists in the order of `list_artists`.
+  pub fn get_artist_alphabet_index(&self, user_id: i32, filter: HiddenFilter) -> Result<Vec<AlphabetSection>, DatabaseQueryError> {
+    let hidden_artist_ids = if filter.include_hidden { Vec::new() } else { self.get_hidden_item_ids(user_id, HiddenItemKind::Artist)? };
+    use schema::artist::dsl::*;
+    let mut sort_names: Vec<String> = artist.select((id, sort_name)).load::<(i32, String)>(&self.connection)?
+      .into_iter()
+      .filter(|(artist_id, _)| !hidden_artist_ids.contains(artist_id))
+      .map(|(_, artist_sort_name)| artist_sort_name)
+      .collect();
+    sort_names.sort_by_cached_key(|s| name_collation_key(s));
+    Ok(alphabet_index(sort_names.iter().map(String::as_str)))
   }
 
   pub fn get_artist_by_id(&self, input_id: i32) -> Result<Option<Artist>, DatabaseQueryError> {
@@ -0,0 +1,123 @@
+use std::backtrace::Backtrace;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::ArtistDetail;
+use musium_core::model::{Artist, ArtistInfo, MediaBlob, MediaKind};
+use musium_core::schema;
+
+use crate::artist_info::{ArtistEnrichmentSettings, fetch_artist_image, fetch_artist_info};
+
+use super::{DatabaseConnection, DatabaseQueryError};
+use super::media_cache::MediaVariantError;
+
+/// Number of days after which the info of an artist is fetched again.
+pub const ARTIST_INFO_REFRESH_DAYS: i64 = 30;
+
+/// Maximum number of artists whose info is fetched in a single sync, to keep syncs short and to not overload the
+/// external services.
+pub const ARTIST_ENRICHMENT_BATCH_SIZE: i64 = 50;
+
+#[derive(Debug, Error)]
+pub enum ArtistEnrichmentError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+}
+
+// Enrichment
+
+impl DatabaseConnection {
+  /// Fetches the image and biography of artists that have no info yet or whose info is outdated, using `settings`.
+  /// Artists whose info could not be fetched are skipped, and are tried again in the next sync. Must be called from a
+  /// blocking task inside a Tokio runtime. Returns the number of artists whose info was stored.
+  #[instrument(skip(self, settings))]
+  pub fn enrich_artists(&self, settings: &ArtistEnrichmentSettings) -> Result<usize, ArtistEnrichmentError> {
+    let refresh_before = Utc::now().naive_utc() - Duration::days(ARTIST_INFO_REFRESH_DAYS);
+    let db_artists: Vec<Artist> = {
+      use schema::{artist, artist_info};
+      time!("enrich_artists.select_artists", artist::table
+        .left_join(artist_info::table)
+        .filter(artist_info::fetched_at.is_null().or(artist_info::fetched_at.lt(refresh_before)))
+        .select(artist::all_columns)
+        .limit(ARTIST_ENRICHMENT_BATCH_SIZE)
+        .load(&self.connection)?)
+    };
+    let runtime = tokio::runtime::Handle::current();
+    let mut count = 0;
+    for db_artist in db_artists {
+      let fetched = match runtime.block_on(fetch_artist_info(&db_artist.name, settings)) {
+        Ok(fetched) => fetched.unwrap_or_default(),
+        Err(e) => {
+          event!(Level::WARN, ?db_artist, ?e, "Failed to fetch artist info, skipping artist");
+          continue;
+        }
+      };
+      let image = match &fetched.image_url {
+        Some(image_url) => runtime.block_on(fetch_artist_image(image_url)).unwrap_or_else(|e| {
+          event!(Level::WARN, ?db_artist, ?e, "Failed to fetch artist image");
+          None
+        }),
+        None => None,
+      };
+      // Artist images are not pinned in the media cache, as they are fetched again when the artist info is refreshed.
+      let image_hash = match image {
+        Some((mime_type, data)) => Some(self.put_media_blob(MediaKind::ArtistImage, &mime_type, &data, false)?),
+        None => None,
+      };
+      let info = ArtistInfo {
+        artist_id: db_artist.id,
+        biography: fetched.biography,
+        image_hash,
+        source_url: fetched.source_url,
+        fetched_at: Utc::now().naive_utc(),
+      };
+      event!(Level::DEBUG, ?db_artist, biography = ?info.biography, source_url = ?info.source_url, "Storing artist info");
+      {
+        use schema::artist_info::dsl::*;
+        time!("enrich_artists.replace", diesel::replace_into(artist_info).values(&info).execute(&self.connection)?);
+      }
+      count += 1;
+    }
+    Ok(count)
+  }
+}
+
+// Retrieval
+
+impl DatabaseConnection {
+  /// Gets the artist with `input_id` along with its biography, or `None` if the artist does not exist.
+  pub fn get_artist_detail_by_id(&self, input_id: i32) -> Result<Option<ArtistDetail>, DatabaseQueryError> {
+    let db_artist = match self.get_artist_by_id(input_id)? {
+      Some(db_artist) => db_artist,
+      None => return Ok(None),
+    };
+    let info = self.select_artist_info(input_id)?;
+    let has_image = match info.as_ref().and_then(|info| info.image_hash.as_ref()) {
+      Some(image_hash) => self.media_blob_exists(image_hash)?,
+      None => false,
+    };
+    Ok(Some(ArtistDetail {
+      artist: db_artist,
+      biography: info.as_ref().and_then(|info| info.biography.clone()),
+      biography_source_url: info.as_ref().and_then(|info| info.source_url.clone()),
+      has_image,
+    }))
+  }
+
+  /// Gets the image of the artist with `input_artist_id`, resized to fit within `size` pixels if `size` is set, or
+  /// `None` if it has no image or its image was evicted from the media cache.
+  pub fn get_artist_image(&self, input_artist_id: i32, size: Option<u32>) -> Result<Option<MediaBlob>, MediaVariantError> {
+    match self.select_artist_info(input_artist_id)?.and_then(|info| info.image_hash) {
+      Some(image_hash) => self.get_media_blob_variant(&image_hash, size),
+      None => Ok(None),
+    }
+  }
+
+  fn select_artist_info(&self, input_artist_id: i32) -> Result<Option<ArtistInfo>, diesel::result::Error> {
+    use schema::artist_info::dsl::*;
+    Ok(time!("select_artist_info.select", artist_info.find(input_artist_id).first::<ArtistInfo>(&self.connection).optional()?))
+  }
+}
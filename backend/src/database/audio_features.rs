@@ -0,0 +1,108 @@
+use std::backtrace::Backtrace;
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::AudioFeaturesFilter;
+use musium_core::format_error::FormatError;
+use musium_core::model::{LocalSource, LocalTrack, TrackAudioFeatures};
+use musium_core::schema;
+
+use crate::audio_features::analyze_audio_features;
+use crate::model::LocalSourceEx;
+use crate::mood::classify_mood;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Querying
+
+impl DatabaseConnection {
+  pub fn get_track_audio_features(&self, input_track_id: i32) -> Result<Option<TrackAudioFeatures>, DatabaseQueryError> {
+    use schema::track_audio_features::dsl::*;
+    Ok(time!("get_track_audio_features.select", track_audio_features.find(input_track_id).first::<TrackAudioFeatures>(&self.connection).optional()?))
+  }
+
+  /// Lists the audio features of tracks that match all criteria of `filter`, ordered by BPM.
+  pub fn filter_tracks_by_audio_features(&self, filter: AudioFeaturesFilter) -> Result<Vec<TrackAudioFeatures>, DatabaseQueryError> {
+    use schema::track_audio_features::dsl::*;
+    let mut query = track_audio_features.into_boxed();
+    if let Some(min_bpm) = filter.min_bpm {
+      query = query.filter(bpm.ge(min_bpm));
+    }
+    if let Some(max_bpm) = filter.max_bpm {
+      query = query.filter(bpm.le(max_bpm));
+    }
+    if let Some(input_key_pitch_class) = filter.key_pitch_class {
+      query = query.filter(key_pitch_class.eq(input_key_pitch_class));
+    }
+    if let Some(input_key_minor) = filter.key_minor {
+      query = query.filter(key_minor.eq(input_key_minor));
+    }
+    Ok(time!("filter_tracks_by_audio_features.select", query.order(bpm.asc()).load::<TrackAudioFeatures>(&self.connection)?))
+  }
+}
+
+// Analysis
+
+#[derive(Debug, Error)]
+pub enum AnalyzeAudioFeaturesError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to assign mood tags")]
+  AssignMoodTagsFail(#[from] DatabaseQueryError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Detects the audio features and mood tags of local tracks of enabled local sources that have not been analyzed yet, or whose file
+  /// has changed since they were analyzed. Tracks whose file fails to decode are skipped. Returns the number of analyzed
+  /// tracks.
+  #[instrument(skip(self))]
+  pub fn analyze_audio_features(&self) -> Result<usize, AnalyzeAudioFeaturesError> {
+    let local_tracks: Vec<(LocalTrack, LocalSource)> = {
+      use schema::local_track::dsl::*;
+      time!("analyze_audio_features.select_local_tracks", local_track
+        .inner_join(schema::local_source::table)
+        .filter(schema::local_source::enabled.eq(true))
+        .load::<(LocalTrack, LocalSource)>(&self.connection)?)
+    };
+    let analyzed_hashes: HashMap<i32, i64> = {
+      use schema::track_audio_features::dsl::*;
+      time!("analyze_audio_features.select_analyzed", track_audio_features.select((track_id, local_hash)).load::<(i32, i64)>(&self.connection)?)
+    }.into_iter().collect();
+
+    let mut analyzed_track_ids = HashSet::new();
+    for (local_track, local_source) in local_tracks {
+      if analyzed_hashes.get(&local_track.track_id) == Some(&local_track.hash) || analyzed_track_ids.contains(&local_track.track_id) { continue; }
+      let path = match local_source.track_file_path(&local_track) {
+        Some(path) => path,
+        None => continue,
+      };
+      event!(Level::DEBUG, ?path, "Analyzing audio features");
+      let features = match analyze_audio_features(&path) {
+        Ok(features) => features,
+        Err(e) => {
+          event!(Level::WARN, ?path, "Failed to analyze audio features, skipping: {:?}", FormatError::new(&e));
+          continue;
+        }
+      };
+      let new_features = TrackAudioFeatures {
+        track_id: local_track.track_id,
+        local_hash: local_track.hash,
+        bpm: features.bpm,
+        key_pitch_class: features.key_pitch_class,
+        key_minor: features.key_minor,
+        energy: features.energy,
+        brightness: features.brightness,
+      };
+      {
+        use schema::track_audio_features::dsl::*;
+        time!("analyze_audio_features.replace", diesel::replace_into(track_audio_features).values(&new_features).execute(&self.connection)?);
+      }
+      self.set_machine_generated_track_tags(local_track.track_id, &classify_mood(&features))?;
+      analyzed_track_ids.insert(local_track.track_id);
+    }
+    Ok(analyzed_track_ids.len())
+  }
+}
@@ -0,0 +1,51 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+
+use musium_core::model::Availability;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+impl DatabaseConnection {
+  /// Computes the availability of all tracks, by track ID. Tracks that are unavailable are omitted.
+  pub fn get_track_availability(&self) -> Result<HashMap<i32, Availability>, DatabaseQueryError> {
+    let local_track_ids: HashSet<i32> = {
+      use schema::local_track::dsl::*;
+      time!("get_track_availability.select_local", local_track
+        .inner_join(schema::local_source::table)
+        .select(track_id)
+        .filter(file_path.is_not_null())
+        .filter(schema::local_source::enabled.eq(true))
+        .load::<i32>(&self.connection)?)
+    }.into_iter().collect();
+    let streaming_track_ids: HashSet<i32> = {
+      use schema::spotify_track_source::dsl::*;
+      time!("get_track_availability.select_streaming", spotify_track_source
+        .inner_join(schema::spotify_source::table)
+        .select(track_id)
+        .filter(schema::spotify_source::enabled.eq(true))
+        .load::<i32>(&self.connection)?)
+    }.into_iter().collect();
+    Ok(local_track_ids.union(&streaming_track_ids)
+      .map(|id| (*id, Availability::new(local_track_ids.contains(id), streaming_track_ids.contains(id))))
+      .collect())
+  }
+
+  /// Computes the availability of all albums from `track_availability`, by album ID. Albums that are unavailable are
+  /// omitted.
+  pub fn get_album_availability(&self, track_availability: &HashMap<i32, Availability>) -> Result<HashMap<i32, Availability>, DatabaseQueryError> {
+    let track_albums: Vec<(i32, i32)> = {
+      use schema::track::dsl::*;
+      time!("get_album_availability.select_tracks", track.select((id, album_id)).load(&self.connection)?)
+    };
+    let mut album_availability = HashMap::new();
+    for (track_id, album_id) in track_albums {
+      if let Some(availability) = track_availability.get(&track_id) {
+        let entry = album_availability.entry(album_id).or_insert(Availability::Unavailable);
+        *entry = entry.union(*availability);
+      }
+    }
+    Ok(album_availability)
+  }
+}
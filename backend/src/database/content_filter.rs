@@ -0,0 +1,60 @@
+use std::backtrace::Backtrace;
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use thiserror::Error;
+
+use musium_core::model::{ContentFilter, UserContentFilter};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+#[derive(Debug, Error)]
+pub enum SetContentFilterError {
+  #[error("Content filter of user with id {0} is pinned, and can only be changed by admins")]
+  PinnedFail(i32),
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to get or set content filter")]
+  ContentFilterQueryFail(#[from] DatabaseQueryError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Gets the content filter of the user with `input_user_id`, which is off and not pinned when it was never set.
+  pub fn get_user_content_filter(&self, input_user_id: i32) -> Result<UserContentFilter, DatabaseQueryError> {
+    use schema::user_content_filter::dsl::*;
+    let content_filter = time!("get_user_content_filter.select", user_content_filter.find(input_user_id).first::<UserContentFilter>(&self.connection).optional()?);
+    Ok(content_filter.unwrap_or(UserContentFilter { user_id: input_user_id, ..UserContentFilter::default() }))
+  }
+
+  /// Sets the content filter of the user with `input_user_id` to `filter`, as requested by that user. Fails when the
+  /// content filter of the user is pinned.
+  pub fn set_own_content_filter(&self, input_user_id: i32, filter: ContentFilter) -> Result<UserContentFilter, SetContentFilterError> {
+    self.connection.transaction::<_, SetContentFilterError, _>(|| {
+      let current = self.get_user_content_filter(input_user_id)?;
+      if current.pinned {
+        return Err(SetContentFilterError::PinnedFail(input_user_id));
+      }
+      Ok(self.set_user_content_filter(UserContentFilter { filter, ..current })?)
+    })
+  }
+
+  /// Sets the content filter of a user to `content_filter`, regardless of whether it is pinned, as requested by an
+  /// admin.
+  pub fn set_user_content_filter(&self, content_filter: UserContentFilter) -> Result<UserContentFilter, DatabaseQueryError> {
+    use schema::user_content_filter::dsl::*;
+    time!("set_user_content_filter.replace", diesel::replace_into(user_content_filter).values(&content_filter).execute(&self.connection)?);
+    Ok(content_filter)
+  }
+
+  /// Gets the IDs of the tracks that `filter` leaves out.
+  pub fn get_content_filtered_track_ids(&self, filter: ContentFilter) -> Result<HashSet<i32>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    let filtered_track_ids: Vec<i32> = match filter {
+      ContentFilter::Off => return Ok(HashSet::new()),
+      ContentFilter::ExcludeExplicit => time!("get_content_filtered_track_ids.select", track.select(id).filter(explicit.eq(true)).load(&self.connection)?),
+      ContentFilter::OnlyClean => time!("get_content_filtered_track_ids.select", track.select(id).filter(explicit.is_null().or(explicit.eq(true))).load(&self.connection)?),
+    };
+    Ok(filtered_track_ids.into_iter().collect())
+  }
+}
@@ -0,0 +1,143 @@
+use std::backtrace::Backtrace;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::{AlbumCoverFetch, AlbumCoverResult};
+use musium_core::model::{Album, AlbumCover, LocalSource, LocalTrack, MediaBlob, MediaKind};
+use musium_core::schema;
+
+use crate::cover::{CoverFetchError, fetch_cover_art_archive_front, is_supported_cover_mime_type};
+use crate::model::LocalSourceEx;
+
+use super::DatabaseConnection;
+use super::media_cache::MediaVariantError;
+
+#[derive(Debug, Error)]
+pub enum AlbumCoverError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Cover has unsupported MIME type '{0}'")]
+  UnsupportedMimeTypeFail(String),
+  #[error("Cover image is empty")]
+  EmptyImageFail,
+  #[error("Failed to fetch cover")]
+  FetchFail(#[from] CoverFetchError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Gets the cover image of the album with `input_album_id`, resized to fit within `size` pixels if `size` is set, or
+  /// `None` if it has no cover.
+  pub fn get_album_cover(&self, input_album_id: i32, size: Option<u32>) -> Result<Option<MediaBlob>, MediaVariantError> {
+    let cover: Option<AlbumCover> = {
+      use schema::album_cover::dsl::*;
+      time!("get_album_cover.select", album_cover.find(input_album_id).first(&self.connection).optional()?)
+    };
+    match cover {
+      Some(cover) => self.get_media_blob_variant(&cover.blob_hash, size),
+      None => Ok(None),
+    }
+  }
+
+  /// Sets or replaces the cover of the album with `input_album_id` with image `data` of `mime_type`. When `embed` is
+  /// true, the cover is also embedded into the tags of the local files of the album. Returns `None` if the album does
+  /// not exist.
+  #[instrument(skip(self, data))]
+  pub fn set_album_cover(&self, input_album_id: i32, mime_type: &str, data: Vec<u8>, embed: bool) -> Result<Option<AlbumCoverResult>, AlbumCoverError> {
+    if !is_supported_cover_mime_type(mime_type) {
+      return Err(AlbumCoverError::UnsupportedMimeTypeFail(mime_type.to_string()));
+    }
+    if data.is_empty() {
+      return Err(AlbumCoverError::EmptyImageFail);
+    }
+    let db_album: Option<Album> = {
+      use schema::album::dsl::*;
+      time!("set_album_cover.select_album", album.find(input_album_id).first(&self.connection).optional()?)
+    };
+    if db_album.is_none() { return Ok(None); }
+
+    let (embedded_file_count, embed_errors) = if embed {
+      self.embed_album_cover(input_album_id, mime_type, &data)?
+    } else {
+      (0, Vec::new())
+    };
+    self.connection.transaction::<_, AlbumCoverError, _>(|| {
+      // Covers are pinned in the media cache, as uploaded covers cannot be derived again.
+      let input_blob_hash = self.put_media_blob(MediaKind::AlbumCover, mime_type, &data, true)?;
+      let previous_cover: Option<AlbumCover> = {
+        use schema::album_cover::dsl::*;
+        time!("set_album_cover.select_previous", album_cover.find(input_album_id).first(&self.connection).optional()?)
+      };
+      let cover = AlbumCover { album_id: input_album_id, blob_hash: input_blob_hash, updated_at: Utc::now().naive_utc() };
+      {
+        use schema::album_cover::dsl::*;
+        time!("set_album_cover.replace", diesel::replace_into(album_cover).values(&cover).execute(&self.connection)?);
+      }
+      if let Some(previous_cover) = previous_cover {
+        if previous_cover.blob_hash != cover.blob_hash {
+          self.unpin_unused_cover_blob(&previous_cover.blob_hash)?;
+        }
+      }
+      Ok(())
+    })?;
+    event!(Level::DEBUG, input_album_id, mime_type, embedded_file_count, ?embed_errors, "Set album cover");
+    Ok(Some(AlbumCoverResult { album_id: input_album_id, mime_type: mime_type.to_string(), embedded_file_count, embed_errors }))
+  }
+
+  /// Fetches the front cover of the MusicBrainz release of `fetch` from the Cover Art Archive, and sets it as the cover
+  /// of the album with `input_album_id`. Returns `None` if the album does not exist.
+  pub async fn fetch_album_cover(&self, input_album_id: i32, fetch: &AlbumCoverFetch) -> Result<Option<AlbumCoverResult>, AlbumCoverError> {
+    let db_album: Option<Album> = {
+      use schema::album::dsl::*;
+      time!("fetch_album_cover.select_album", album.find(input_album_id).first(&self.connection).optional()?)
+    };
+    if db_album.is_none() { return Ok(None); }
+    let (mime_type, data) = fetch_cover_art_archive_front(&fetch.release_id).await?;
+    self.set_album_cover(input_album_id, &mime_type, data, fetch.embed)
+  }
+
+  /// Unpins the cover image blob with `input_blob_hash` when no album uses it as its cover anymore, as albums with the
+  /// same cover share the same blob.
+  pub(crate) fn unpin_unused_cover_blob(&self, input_blob_hash: &str) -> Result<(), diesel::result::Error> {
+    let usage_count: i64 = {
+      use schema::album_cover::dsl::*;
+      time!("unpin_unused_cover_blob.count", album_cover.filter(blob_hash.eq(input_blob_hash)).count().get_result(&self.connection)?)
+    };
+    if usage_count == 0 {
+      self.unpin_media_blob(input_blob_hash)?;
+    }
+    Ok(())
+  }
+
+  /// Embeds the cover into the local files of the tracks of the album with `input_album_id`. Returns the number of
+  /// files the cover was embedded into, and descriptions of the files it could not be embedded into.
+  fn embed_album_cover(&self, input_album_id: i32, mime_type: &str, data: &[u8]) -> Result<(usize, Vec<String>), diesel::result::Error> {
+    let local_tracks: Vec<(LocalTrack, LocalSource)> = {
+      use schema::{local_source, local_track, track};
+      time!("embed_album_cover.select_local_tracks", local_track::table
+        .inner_join(track::table)
+        .inner_join(local_source::table)
+        .filter(track::album_id.eq(input_album_id))
+        .select((local_track::all_columns, local_source::all_columns))
+        .load(&self.connection)?)
+    };
+    let mut embedded_file_count = 0;
+    let mut embed_errors = Vec::new();
+    for (local_track, local_source) in local_tracks {
+      let path = match local_source.track_file_path(&local_track) {
+        Some(path) => path,
+        None => continue, // Removed track: there is no file to embed into.
+      };
+      match musium_filesystem_sync::embed_cover(&path, mime_type, data.to_vec()) {
+        Ok(()) => embedded_file_count += 1,
+        Err(e) => {
+          event!(Level::WARN, ?path, ?e, "Failed to embed album cover");
+          embed_errors.push(format!("{}: {}", path.display(), e));
+        }
+      }
+    }
+    Ok((embedded_file_count, embed_errors))
+  }
+}
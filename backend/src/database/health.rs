@@ -0,0 +1,46 @@
+use std::backtrace::Backtrace;
+use std::io;
+use std::path::Path;
+
+use diesel_migrations::{mark_migrations_in_directory, RunMigrationsError};
+use thiserror::Error;
+
+use super::{Database, DatabaseConnection};
+
+/// Directory with the migrations of the database, as created by the Diesel CLI.
+pub const MIGRATIONS_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+
+#[derive(Debug, Error)]
+pub enum ListPendingMigrationsError {
+  #[error("Failed to compare the migrations in the migrations directory against the applied migrations")]
+  MarkMigrationsFail(#[from] RunMigrationsError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Lists the versions of the migrations in `MIGRATIONS_DIRECTORY` that have not been applied to the database yet.
+  /// Diesel marks which migrations have been applied in the same way as its `any_pending_migrations`, but from the
+  /// migrations directory of this crate instead of searching for one in the working directory.
+  pub fn list_pending_migrations(&self) -> Result<Vec<String>, ListPendingMigrationsError> {
+    let migrations = time!("list_pending_migrations.mark", mark_migrations_in_directory(&*self.connection, Path::new(MIGRATIONS_DIRECTORY))?);
+    Ok(migrations.into_iter().filter(|(_, applied)| !applied).map(|(migration, _)| migration.version().to_string()).collect())
+  }
+}
+
+impl Database {
+  /// Gets the number of bytes available on the volume of the database file.
+  pub fn available_disk_space(&self) -> io::Result<u64> {
+    fs2::available_space(&self.inner.directory)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::database::test_database::TestDatabase;
+
+  #[test]
+  fn no_migrations_are_pending_after_migrating() {
+    let test_database = TestDatabase::new();
+    let connection = test_database.database.connect().unwrap();
+    assert!(connection.list_pending_migrations().unwrap().is_empty());
+  }
+}
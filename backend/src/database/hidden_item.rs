@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use diesel::prelude::*;
+
+use musium_core::api::HiddenItemInfo;
+use musium_core::model::{HiddenItem, HiddenItemKind, TrackArtistRole};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+impl DatabaseConnection {
+  /// Lists the items that the user with `input_user_id` hid, most recently hidden first.
+  pub fn list_hidden_items(&self, input_user_id: i32) -> Result<Vec<HiddenItem>, DatabaseQueryError> {
+    use schema::user_hidden_item::dsl::*;
+    Ok(time!("list_hidden_items.select", user_hidden_item
+      .filter(user_id.eq(input_user_id))
+      .order(hidden_at.desc())
+      .load::<HiddenItem>(&self.connection)?))
+  }
+
+  /// Lists the items that the user with `input_user_id` hid along with their name, most recently hidden first.
+  pub fn list_hidden_item_infos(&self, input_user_id: i32) -> Result<Vec<HiddenItemInfo>, DatabaseQueryError> {
+    let hidden_items = self.list_hidden_items(input_user_id)?;
+    let ids_of_kind = |kind: HiddenItemKind| -> Vec<i32> {
+      hidden_items.iter().filter(|i| i.kind == kind).map(|i| i.item_id).collect()
+    };
+    let track_names: HashMap<i32, String> = {
+      use schema::track::dsl::*;
+      time!("list_hidden_item_infos.select_tracks", track.select((id, title)).filter(id.eq_any(ids_of_kind(HiddenItemKind::Track))).load::<(i32, String)>(&self.connection)?)
+        .into_iter().collect()
+    };
+    let album_names: HashMap<i32, String> = {
+      use schema::album::dsl::*;
+      time!("list_hidden_item_infos.select_albums", album.select((id, name)).filter(id.eq_any(ids_of_kind(HiddenItemKind::Album))).load::<(i32, String)>(&self.connection)?)
+        .into_iter().collect()
+    };
+    let artist_names: HashMap<i32, String> = {
+      use schema::artist::dsl::*;
+      time!("list_hidden_item_infos.select_artists", artist.select((id, name)).filter(id.eq_any(ids_of_kind(HiddenItemKind::Artist))).load::<(i32, String)>(&self.connection)?)
+        .into_iter().collect()
+    };
+    Ok(hidden_items.into_iter().map(|item| {
+      let names = match item.kind {
+        HiddenItemKind::Track => &track_names,
+        HiddenItemKind::Album => &album_names,
+        HiddenItemKind::Artist => &artist_names,
+      };
+      HiddenItemInfo { item, name: names.get(&item.item_id).cloned() }
+    }).collect())
+  }
+
+  /// Hides the item of `input_kind` with `input_item_id` for the user with `input_user_id`. Hiding an item that is
+  /// already hidden keeps the time at which it was first hidden.
+  pub fn hide_item(&self, input_user_id: i32, input_kind: HiddenItemKind, input_item_id: i32) -> Result<HiddenItem, DatabaseQueryError> {
+    use schema::user_hidden_item::dsl::*;
+    let hidden_item = HiddenItem { user_id: input_user_id, kind: input_kind, item_id: input_item_id, hidden_at: Utc::now().naive_utc() };
+    time!("hide_item.insert", diesel::insert_or_ignore_into(user_hidden_item).values(&hidden_item).execute(&self.connection)?);
+    Ok(time!("hide_item.select", user_hidden_item.find((input_user_id, input_kind, input_item_id)).first::<HiddenItem>(&self.connection)?))
+  }
+
+  /// Unhides the item of `input_kind` with `input_item_id` for the user with `input_user_id`. Returns whether the item
+  /// was hidden.
+  pub fn unhide_item(&self, input_user_id: i32, input_kind: HiddenItemKind, input_item_id: i32) -> Result<bool, DatabaseQueryError> {
+    use schema::user_hidden_item::dsl::*;
+    let deleted = time!("unhide_item.delete", diesel::delete(user_hidden_item.find((input_user_id, input_kind, input_item_id))).execute(&self.connection)?);
+    Ok(deleted > 0)
+  }
+
+  /// Gets the IDs of the tracks that the user with `input_user_id` hid, either directly, or by hiding their album or
+  /// one of their artists. Hidden items are selected with subqueries instead of binding their IDs, as a user may hide
+  /// more items than SQLite allows bound variables in a query.
+  pub fn get_hidden_track_ids(&self, input_user_id: i32) -> Result<HashSet<i32>, DatabaseQueryError> {
+    let hidden_item_ids = |input_kind: HiddenItemKind| {
+      use schema::user_hidden_item::dsl::*;
+      user_hidden_item.select(item_id).filter(user_id.eq(input_user_id)).filter(kind.eq(input_kind))
+    };
+    let hidden_artist_track_ids = {
+      use schema::track_artist::dsl::*;
+      track_artist.select(track_id).filter(artist_id.eq_any(hidden_item_ids(HiddenItemKind::Artist))).filter(role.eq(TrackArtistRole::Main))
+    };
+    use schema::track::dsl::*;
+    let track_ids: Vec<i32> = time!("get_hidden_track_ids.select", track
+      .select(id)
+      .filter(id.eq_any(hidden_item_ids(HiddenItemKind::Track))
+        .or(album_id.eq_any(hidden_item_ids(HiddenItemKind::Album)))
+        .or(id.eq_any(hidden_artist_track_ids)))
+      .load(&self.connection)?);
+    Ok(track_ids.into_iter().collect())
+  }
+
+  /// Gets the IDs of the items of `input_kind` that the user with `input_user_id` hid directly.
+  pub fn get_hidden_item_ids(&self, input_user_id: i32, input_kind: HiddenItemKind) -> Result<Vec<i32>, DatabaseQueryError> {
+    use schema::user_hidden_item::dsl::*;
+    Ok(time!("get_hidden_item_ids.select", user_hidden_item
+      .select(item_id)
+      .filter(user_id.eq(input_user_id))
+      .filter(kind.eq(input_kind))
+      .load(&self.connection)?))
+  }
+}
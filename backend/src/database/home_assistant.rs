@@ -0,0 +1,30 @@
+use diesel::prelude::*;
+
+use musium_core::api::HomeAssistantMedia;
+use musium_core::model::{Album, Track, TrackArtistRole};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+impl DatabaseConnection {
+  /// Gets the track with `input_track_id` as media shown by Home Assistant, or `None` if it does not exist.
+  pub fn get_home_assistant_media(&self, input_track_id: i32) -> Result<Option<HomeAssistantMedia>, DatabaseQueryError> {
+    let track_and_album = time!("get_home_assistant_media.select_track", schema::track::table
+      .inner_join(schema::album::table)
+      .filter(schema::track::id.eq(input_track_id))
+      .first::<(Track, Album)>(&self.connection)
+      .optional()?);
+    let (track, album) = match track_and_album {
+      Some(track_and_album) => track_and_album,
+      None => return Ok(None),
+    };
+    let artist_names = time!("get_home_assistant_media.select_artists", schema::track_artist::table
+      .inner_join(schema::artist::table)
+      .filter(schema::track_artist::track_id.eq(input_track_id))
+      .filter(schema::track_artist::role.eq(TrackArtistRole::Main))
+      .select(schema::artist::name)
+      .load::<String>(&self.connection)?);
+    let artist = if artist_names.is_empty() { None } else { Some(artist_names.join(", ")) };
+    Ok(Some(HomeAssistantMedia { track_id: track.id, title: track.title, artist, album_id: album.id, album_name: album.name }))
+  }
+}
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use diesel::prelude::*;
 
+use musium_core::api::ReplayGain;
 use musium_core::model::{LocalSource, LocalTrack};
 use musium_core::schema;
 
@@ -11,6 +12,11 @@ use super::{DatabaseConnection, DatabaseQueryError};
 
 impl DatabaseConnection {
   pub fn get_local_track_path_by_track_id(&self, input_track_id: i32) -> Result<Option<PathBuf>, DatabaseQueryError> {
+    Ok(self.get_local_track_path_and_bitrate_by_track_id(input_track_id)?.map(|(path, _)| path))
+  }
+
+  /// Gets the path to the file of the local track with `input_track_id`, along with its bitrate in kbit/s if known.
+  pub fn get_local_track_path_and_bitrate_by_track_id(&self, input_track_id: i32) -> Result<Option<(PathBuf, Option<i32>)>, DatabaseQueryError> {
     let data: Option<(LocalTrack, LocalSource)> = {
       use schema::local_track::dsl::*;
       local_track
@@ -20,8 +26,18 @@ impl DatabaseConnection {
         .optional()?
     };
     if let Some((local_track, local_source)) = data {
-      return Ok(local_source.track_file_path(&local_track));
+      return Ok(local_source.track_file_path(&local_track).map(|path| (path, local_track.bitrate)));
     }
     Ok(None)
   }
+
+  /// Gets the ReplayGain values of the local track with `input_track_id`, or `None` if it has no ReplayGain gains.
+  pub fn get_local_track_replay_gain_by_track_id(&self, input_track_id: i32) -> Result<Option<ReplayGain>, DatabaseQueryError> {
+    use schema::local_track::dsl::*;
+    let db_local_track: Option<LocalTrack> = local_track
+      .filter(track_id.eq(input_track_id))
+      .first::<LocalTrack>(&self.connection)
+      .optional()?;
+    Ok(db_local_track.and_then(|t| ReplayGain::new(t.track_gain, t.track_peak, t.album_gain, t.album_peak)))
+  }
 }
@@ -0,0 +1,46 @@
+use std::backtrace::Backtrace;
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::Lyrics;
+use musium_core::model::{LocalSource, LocalTrack};
+use musium_core::schema;
+
+use crate::lyrics::{LyricsReadError, read_lyrics};
+use crate::model::LocalSourceEx;
+
+use super::DatabaseConnection;
+
+#[derive(Debug, Error)]
+pub enum TrackLyricsError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to read lyrics")]
+  ReadFail(#[from] LyricsReadError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Gets the lyrics of the track with `input_track_id` from the lyrics file next to its local audio file. Returns
+  /// `Ok(None)` if the track has no local audio file or no lyrics file.
+  #[instrument(skip(self))]
+  pub fn get_track_lyrics(&self, input_track_id: i32) -> Result<Option<Lyrics>, TrackLyricsError> {
+    let local_tracks: Vec<(LocalTrack, LocalSource)> = {
+      use schema::local_track::dsl::*;
+      time!("get_track_lyrics.select_local_tracks", local_track
+        .filter(track_id.eq(input_track_id))
+        .inner_join(schema::local_source::table)
+        .load::<(LocalTrack, LocalSource)>(&self.connection)?)
+    };
+    for (local_track, local_source) in local_tracks {
+      if let Some(path) = local_source.track_file_path(&local_track) {
+        event!(Level::DEBUG, ?path, "Reading lyrics");
+        if let Some(lyrics) = read_lyrics(input_track_id, &path)? {
+          return Ok(Some(lyrics));
+        }
+      }
+    }
+    Ok(None)
+  }
+}
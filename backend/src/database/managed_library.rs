@@ -0,0 +1,166 @@
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+
+use diesel::prelude::*;
+use itertools::Itertools;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{Album, LocalSource, LocalTrack, Track};
+use musium_core::schema;
+use musium_filesystem_sync::path_key;
+
+use crate::managed_library::{LibraryPathFields, ManagedLibrarySettings};
+use crate::model::LocalSourceEx;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Tracks of the managed library to organize.
+#[derive(Copy, Clone, Debug)]
+pub enum OrganizeScope {
+  All,
+  Album(i32),
+  Track(i32),
+}
+
+#[derive(Debug, Error)]
+pub enum OrganizeManagedLibraryError {
+  #[error("Failed to query for the managed local source")]
+  QuerySourceFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Managed local source '{0}' does not exist or was deleted")]
+  NoManagedLocalSourceFail(i32),
+  #[error("Failed to create directory '{0}'")]
+  CreateDirectoryFail(PathBuf, #[source] std::io::Error),
+  #[error("Failed to move file '{0}' to '{1}'")]
+  MoveFileFail(PathBuf, PathBuf, #[source] std::io::Error),
+}
+
+impl DatabaseConnection {
+  /// Moves the files of the tracks in `scope` of the managed local source of `managed_library` to the paths given by its
+  /// template, and updates the paths of their local tracks. Directories that are left empty are removed. Returns the
+  /// number of files that were moved.
+  #[instrument(skip(self, managed_library))]
+  pub fn organize_managed_library(&self, managed_library: &ManagedLibrarySettings, scope: OrganizeScope) -> Result<usize, OrganizeManagedLibraryError> {
+    use OrganizeManagedLibraryError::*;
+    self.update_missing_file_path_keys()?;
+    let local_source = self.get_local_source_by_id(managed_library.local_source_id)?
+      .filter(|local_source| local_source.deleted_at.is_none())
+      .ok_or(NoManagedLocalSourceFail(managed_library.local_source_id))?;
+    let local_tracks: Vec<LocalTrack> = {
+      use schema::local_track::dsl::*;
+      let mut query = local_track
+        .filter(local_source_id.eq(local_source.id))
+        .filter(file_path.is_not_null())
+        .into_boxed();
+      match scope {
+        OrganizeScope::All => {}
+        OrganizeScope::Album(input_album_id) => {
+          let album_track_ids: Vec<i32> = {
+            use schema::track::dsl::*;
+            time!("organize_managed_library.select_album_track_ids", track.filter(album_id.eq(input_album_id)).select(id).load(&self.connection)?)
+          };
+          query = query.filter(track_id.eq_any(album_track_ids));
+        }
+        OrganizeScope::Track(input_track_id) => query = query.filter(track_id.eq(input_track_id)),
+      }
+      time!("organize_managed_library.select_local_tracks", query.load(&self.connection)?)
+    };
+    let mut moved_count = 0;
+    for local_track in local_tracks {
+      if self.organize_local_track(managed_library, &local_source, local_track)? {
+        moved_count += 1;
+      }
+    }
+    event!(Level::DEBUG, moved_count, "Organized managed library");
+    Ok(moved_count)
+  }
+
+  /// Moves the file of `db_local_track` to the path given by the template of `managed_library`. Returns whether it was
+  /// moved.
+  fn organize_local_track(&self, managed_library: &ManagedLibrarySettings, local_source: &LocalSource, db_local_track: LocalTrack) -> Result<bool, OrganizeManagedLibraryError> {
+    use OrganizeManagedLibraryError::*;
+    let current_path = match local_source.track_file_path(&db_local_track) {
+      Some(current_path) => current_path,
+      None => return Ok(false),
+    };
+    let db_track: Track = {
+      use schema::track::dsl::*;
+      time!("organize_managed_library.select_track", track.find(db_local_track.track_id).first(&self.connection)?)
+    };
+    let db_album: Album = {
+      use schema::album::dsl::*;
+      time!("organize_managed_library.select_album", album.find(db_track.album_id).first(&self.connection)?)
+    };
+    let album_artists: Vec<String> = {
+      use schema::{album_artist, artist};
+      time!("organize_managed_library.select_album_artists", album_artist::table
+        .inner_join(artist::table)
+        .filter(album_artist::album_id.eq(db_album.id))
+        .select(artist::name)
+        .load::<String>(&self.connection)?)
+        .into_iter().sorted().collect()
+    };
+    let artists = self.select_track_artist_names(db_track.id)?;
+    let fields = LibraryPathFields {
+      album_artists: &album_artists,
+      artists: &artists,
+      album: &db_album.name,
+      title: &db_track.title,
+      track_number: db_track.track_number,
+      disc_number: db_track.disc_number,
+    };
+    let extension = current_path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mut relative_path = managed_library.template.render(&fields, &extension);
+    if db_local_track.file_path_key.as_deref() == Some(path_key(&relative_path.to_string_lossy()).as_str()) {
+      return Ok(false);
+    }
+
+    let directory = Path::new(&local_source.directory);
+    // Do not overwrite other files, such as another version of the same track, but number the file instead.
+    let file_name = relative_path.file_name().map(|file_name| file_name.to_string_lossy().to_string()).unwrap_or_default();
+    let (stem, dot_extension) = file_name.split_at(file_name.len() - if extension.is_empty() { 0 } else { extension.len() + 1 });
+    let mut number = 1;
+    while directory.join(&relative_path).exists() {
+      number += 1;
+      relative_path.set_file_name(format!("{} ({}){}", stem, number, dot_extension));
+      if db_local_track.file_path_key.as_deref() == Some(path_key(&relative_path.to_string_lossy()).as_str()) {
+        return Ok(false);
+      }
+    }
+    let target_path = directory.join(&relative_path);
+    if let Some(parent) = target_path.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| CreateDirectoryFail(parent.to_path_buf(), e))?;
+    }
+    std::fs::rename(&current_path, &target_path).map_err(|e| MoveFileFail(current_path.clone(), target_path.clone(), e))?;
+
+    let relative_path = relative_path.to_string_lossy().to_string();
+    let update_query = {
+      use schema::local_track::dsl::*;
+      diesel::update(local_track)
+        .filter(track_id.eq(db_local_track.track_id))
+        .filter(local_source_id.eq(db_local_track.local_source_id))
+        .set((file_path.eq(&relative_path), file_path_key.eq(path_key(&relative_path))))
+    };
+    if let Err(e) = time!("organize_managed_library.update_local_track", update_query.execute(&self.connection)) {
+      // Move the file back, so that the local track does not point to a file that no longer exists.
+      std::fs::rename(&target_path, &current_path).ok(); // OK: the error of the update is more important.
+      return Err(e.into());
+    }
+    event!(Level::DEBUG, from = %current_path.display(), to = %target_path.display(), "Moved file of local track in managed library");
+    remove_empty_parent_directories(directory, &current_path);
+    Ok(true)
+  }
+}
+
+/// Removes the parent directories of `path` inside `directory` that are empty, from the innermost to the outermost.
+fn remove_empty_parent_directories(directory: &Path, path: &Path) {
+  let mut parent = path.parent();
+  while let Some(current) = parent {
+    if current == directory || !current.starts_with(directory) { break; }
+    // Removing a directory fails when it is not empty, which stops the removal.
+    if std::fs::remove_dir(current).is_err() { break; }
+    parent = current.parent();
+  }
+}
@@ -0,0 +1,175 @@
+use std::backtrace::Backtrace;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::MediaCacheStatus;
+use musium_core::model::{MediaBlob, MediaKind, MediaVariant};
+use musium_core::schema;
+
+use crate::artwork::{ArtworkResizeError, resize_artwork};
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+#[derive(Debug, Error)]
+pub enum MediaVariantError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to resize image")]
+  ResizeFail(#[from] ArtworkResizeError, Backtrace),
+}
+
+// Storing and retrieving
+
+impl DatabaseConnection {
+  /// Stores `data` of `kind` and `mime_type` in the media cache, returning its hash. When a blob with the same data
+  /// already exists, it is reused and pinned if `input_pinned` is true. Unpinned blobs are evicted afterwards if the cache
+  /// exceeds its size limit.
+  #[instrument(skip(self, data))]
+  pub(crate) fn put_media_blob(&self, kind: MediaKind, mime_type: &str, data: &[u8], input_pinned: bool) -> Result<String, diesel::result::Error> {
+    let blob_hash = blake3::hash(data).to_hex().to_string();
+    let now = Utc::now().naive_utc();
+    let existing: Option<bool> = {
+      use schema::media_blob::dsl::*;
+      time!("put_media_blob.select", media_blob.find(&blob_hash).select(pinned).first(&self.connection).optional()?)
+    };
+    match existing {
+      Some(existing_pinned) => {
+        use schema::media_blob::dsl::*;
+        time!("put_media_blob.update", diesel::update(media_blob.find(&blob_hash)).set((pinned.eq(existing_pinned || input_pinned), last_accessed_at.eq(now))).execute(&self.connection)?);
+      }
+      None => {
+        use schema::media_blob::dsl::*;
+        let blob = MediaBlob { hash: blob_hash.clone(), kind, mime_type: mime_type.to_string(), data: data.to_vec(), size: data.len() as i64, pinned: input_pinned, last_accessed_at: now };
+        time!("put_media_blob.insert", diesel::insert_into(media_blob).values(&blob).execute(&self.connection)?);
+        event!(Level::TRACE, %blob_hash, ?kind, size = blob.size, pinned = input_pinned, "Stored media blob");
+        self.evict_media_blobs()?;
+      }
+    }
+    Ok(blob_hash)
+  }
+
+  /// Gets the blob with `input_hash` from the media cache, marking it as accessed unless the database is quiesced.
+  /// Returns `None` if the blob does not exist or was evicted.
+  pub(crate) fn get_media_blob(&self, input_hash: &str) -> Result<Option<MediaBlob>, diesel::result::Error> {
+    use schema::media_blob::dsl::*;
+    let blob: Option<MediaBlob> = time!("get_media_blob.select", media_blob.find(input_hash).first(&self.connection).optional()?);
+    if blob.is_some() && !self.is_quiesced() {
+      time!("get_media_blob.update", diesel::update(media_blob.find(input_hash)).set(last_accessed_at.eq(Utc::now().naive_utc())).execute(&self.connection)?);
+    }
+    Ok(blob)
+  }
+
+  pub(crate) fn media_blob_exists(&self, input_hash: &str) -> Result<bool, diesel::result::Error> {
+    use schema::media_blob::dsl::*;
+    let count: i64 = time!("media_blob_exists.count", media_blob.find(input_hash).count().get_result(&self.connection)?);
+    Ok(count > 0)
+  }
+
+  /// Unpins the blob with `input_hash`, allowing it to be evicted, for example when it is no longer used as an album
+  /// cover.
+  pub(crate) fn unpin_media_blob(&self, input_hash: &str) -> Result<(), diesel::result::Error> {
+    use schema::media_blob::dsl::*;
+    time!("unpin_media_blob.update", diesel::update(media_blob.find(input_hash)).set(pinned.eq(false)).execute(&self.connection)?);
+    self.evict_media_blobs()?;
+    Ok(())
+  }
+}
+
+// Variants
+
+impl DatabaseConnection {
+  /// Gets the image blob with `input_source_hash` from the media cache, resized to fit within `input_size` pixels if
+  /// `input_size` is set. Resized variants are cached as unpinned blobs, and are resized again after they are evicted.
+  /// Resized variants are not cached while the database is quiesced. Returns `None` if the source blob does not exist or
+  /// was evicted.
+  #[instrument(skip(self))]
+  pub(crate) fn get_media_blob_variant(&self, input_source_hash: &str, input_size: Option<u32>) -> Result<Option<MediaBlob>, MediaVariantError> {
+    let input_size = match input_size {
+      Some(input_size) => input_size,
+      None => return Ok(self.get_media_blob(input_source_hash)?),
+    };
+    let variant: Option<MediaVariant> = {
+      use schema::media_variant::dsl::*;
+      time!("get_media_blob_variant.select", media_variant.find((input_source_hash, input_size as i32)).first(&self.connection).optional()?)
+    };
+    if let Some(variant) = variant {
+      if let Some(blob) = self.get_media_blob(&variant.variant_hash)? {
+        return Ok(Some(blob));
+      }
+    }
+
+    let source = match self.get_media_blob(input_source_hash)? {
+      Some(source) => source,
+      None => return Ok(None),
+    };
+    // Images that already fit, or whose format cannot be resized, are their own variant.
+    let blob = match resize_artwork(&source.mime_type, &source.data, input_size)? {
+      Some(data) if self.is_quiesced() => {
+        let variant_hash = blake3::hash(&data).to_hex().to_string();
+        return Ok(Some(MediaBlob { hash: variant_hash, kind: MediaKind::ResizedImage, size: data.len() as i64, data, pinned: false, ..source }));
+      }
+      Some(data) => {
+        let variant_hash = self.put_media_blob(MediaKind::ResizedImage, &source.mime_type, &data, false)?;
+        event!(Level::DEBUG, input_source_hash, input_size, %variant_hash, "Resized image");
+        MediaBlob { hash: variant_hash, kind: MediaKind::ResizedImage, size: data.len() as i64, data, pinned: false, ..source }
+      }
+      None => source,
+    };
+    if self.is_quiesced() { return Ok(Some(blob)); }
+    let variant = MediaVariant { source_hash: input_source_hash.to_string(), size: input_size as i32, variant_hash: blob.hash.clone() };
+    {
+      use schema::media_variant::dsl::*;
+      time!("get_media_blob_variant.replace", diesel::replace_into(media_variant).values(&variant).execute(&self.connection)?);
+    }
+    Ok(Some(blob))
+  }
+}
+
+// Eviction
+
+impl DatabaseConnection {
+  /// Evicts unpinned blobs in least recently accessed order, until their total size is within the size limit of the
+  /// media cache. Returns the number of evicted blobs.
+  pub(crate) fn evict_media_blobs(&self) -> Result<usize, diesel::result::Error> {
+    use schema::media_blob::dsl::*;
+    let size_limit = self.inner.media_cache_size_limit;
+    let mut unpinned_size = self.select_unpinned_media_size()?;
+    if unpinned_size <= size_limit { return Ok(0); }
+    let candidates: Vec<(String, i64)> = time!("evict_media_blobs.select", media_blob
+      .filter(pinned.eq(false))
+      .order(last_accessed_at.asc())
+      .select((hash, size))
+      .load(&self.connection)?);
+    let mut evicted = 0;
+    for (blob_hash, blob_size) in candidates {
+      if unpinned_size <= size_limit { break; }
+      time!("evict_media_blobs.delete", diesel::delete(media_blob.find(&blob_hash)).execute(&self.connection)?);
+      unpinned_size -= blob_size;
+      evicted += 1;
+    }
+    event!(Level::DEBUG, evicted, unpinned_size, size_limit, "Evicted media blobs");
+    Ok(evicted)
+  }
+
+  fn select_unpinned_media_size(&self) -> Result<i64, diesel::result::Error> {
+    use schema::media_blob::dsl::*;
+    // Sum in Rust, as Diesel sums BIGINT columns into NUMERIC values, which SQLite cannot load as integers.
+    let sizes: Vec<i64> = time!("select_unpinned_media_size.select", media_blob.filter(pinned.eq(false)).select(size).load(&self.connection)?);
+    Ok(sizes.into_iter().sum())
+  }
+}
+
+// Status
+
+impl DatabaseConnection {
+  pub fn get_media_cache_status(&self) -> Result<MediaCacheStatus, DatabaseQueryError> {
+    use schema::media_blob::dsl::*;
+    let blob_count: i64 = time!("get_media_cache_status.count", media_blob.count().get_result(&self.connection)?);
+    let sizes: Vec<i64> = time!("get_media_cache_status.select", media_blob.select(size).load(&self.connection)?);
+    let unpinned_size = self.select_unpinned_media_size()?;
+    Ok(MediaCacheStatus { blob_count, total_size: sizes.into_iter().sum(), unpinned_size, size_limit: self.inner.media_cache_size_limit })
+  }
+}
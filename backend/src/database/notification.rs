@@ -0,0 +1,101 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use tracing::{event, Level};
+
+use musium_core::model::{NewNotification, Notification, NotificationKind, NotificationPreference};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Inbox
+
+impl DatabaseConnection {
+  pub fn list_notifications(&self, input_user_id: i32, unread_only: bool) -> Result<Vec<Notification>, DatabaseQueryError> {
+    use schema::notification::dsl::*;
+    let mut query = notification
+      .filter(user_id.eq(input_user_id))
+      .order(created_at.desc())
+      .into_boxed();
+    if unread_only {
+      query = query.filter(is_read.eq(false));
+    }
+    Ok(time!("list_notifications.select", query.load::<Notification>(&self.connection)?))
+  }
+
+  /// Marks the notification with `input_id` of the user with `input_user_id` as read. Returns the updated notification,
+  /// or `None` if no such notification exists.
+  pub fn mark_notification_read(&self, input_id: i32, input_user_id: i32) -> Result<Option<Notification>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::notification::dsl::*;
+      let query = notification.filter(id.eq(input_id)).filter(user_id.eq(input_user_id));
+      time!("mark_notification_read.update", diesel::update(query).set(is_read.eq(true)).execute(&self.connection)?);
+      Ok(time!("mark_notification_read.select", query.first::<Notification>(&self.connection).optional()?))
+    })
+  }
+
+  pub fn mark_all_notifications_read(&self, input_user_id: i32) -> Result<usize, DatabaseQueryError> {
+    use schema::notification::dsl::*;
+    let query = notification.filter(user_id.eq(input_user_id)).filter(is_read.eq(false));
+    Ok(time!("mark_all_notifications_read.update", diesel::update(query).set(is_read.eq(true)).execute(&self.connection)?))
+  }
+}
+
+// Preferences
+
+impl DatabaseConnection {
+  pub fn get_notification_preference(&self, input_user_id: i32) -> Result<NotificationPreference, DatabaseQueryError> {
+    use schema::notification_preference::dsl::*;
+    let preference = time!("get_notification_preference.select", notification_preference.find(input_user_id).first::<NotificationPreference>(&self.connection).optional()?);
+    Ok(preference.unwrap_or_else(|| NotificationPreference::default_for_user(input_user_id)))
+  }
+
+  pub fn set_notification_preference(&self, preference: NotificationPreference) -> Result<NotificationPreference, DatabaseQueryError> {
+    use schema::notification_preference::dsl::*;
+    event!(Level::DEBUG, ?preference, "Setting notification preference");
+    time!("set_notification_preference.replace", diesel::replace_into(notification_preference).values(preference).execute(&self.connection)?);
+    Ok(preference)
+  }
+}
+
+// Emission
+
+impl DatabaseConnection {
+  /// Creates a notification for the user with `input_user_id`, if that user has enabled notifications of `input_kind`.
+  pub fn notify_user(&self, input_user_id: i32, input_kind: NotificationKind, input_message: String) -> Result<Option<Notification>, DatabaseQueryError> {
+    if !self.get_notification_preference(input_user_id)?.is_enabled(input_kind) {
+      return Ok(None);
+    }
+    let new_notification = NewNotification {
+      user_id: input_user_id,
+      kind: input_kind,
+      message: input_message,
+      created_at: Utc::now().naive_utc(),
+      is_read: false,
+    };
+    Ok(Some(self.insert_notification(new_notification)?))
+  }
+
+  /// Creates a notification for all users that have enabled notifications of `input_kind`.
+  pub fn notify_all_users(&self, input_kind: NotificationKind, input_message: String) -> Result<Vec<Notification>, DatabaseQueryError> {
+    let user_ids: Vec<i32> = {
+      use schema::user::dsl::*;
+      time!("notify_all_users.select_users", user.select(id).load::<i32>(&self.connection)?)
+    };
+    let mut notifications = Vec::new();
+    for user_id in user_ids {
+      if let Some(notification) = self.notify_user(user_id, input_kind, input_message.clone())? {
+        notifications.push(notification);
+      }
+    }
+    Ok(notifications)
+  }
+
+  fn insert_notification(&self, new_notification: NewNotification) -> Result<Notification, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::notification::dsl::*;
+      event!(Level::DEBUG, ?new_notification, "Inserting notification");
+      time!("insert_notification.insert", diesel::insert_into(notification).values(new_notification).execute(&self.connection)?);
+      Ok(time!("insert_notification.select_inserted", notification.order(id.desc()).first(&self.connection)?))
+    })
+  }
+}
@@ -0,0 +1,215 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use diesel::prelude::*;
+
+use musium_core::api::{PlayCount, UserStats, UserStatsQuery};
+use musium_core::model::{Album, Artist, NewPlayHistory, PlayHistory, SKIP_THRESHOLD, Track, TrackArtistRole};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Number of top artists, albums, and tracks in listening statistics.
+const STATS_TOP_COUNT: usize = 10;
+/// Maximum duration that a play is assumed to last, as the duration of plays is not recorded.
+const MAX_PLAY_SECONDS: i64 = 10 * 60;
+/// Duration that the last play is assumed to last, as there is no next play to estimate its duration from.
+const LAST_PLAY_SECONDS: i64 = 4 * 60;
+
+impl DatabaseConnection {
+  /// Records that the user with `input_user_id` played the track with `input_track_id` just now. Plays are not recorded
+  /// while the database is quiesced.
+  pub fn record_play(&self, input_user_id: i32, input_track_id: i32) -> Result<(), DatabaseQueryError> {
+    if self.is_quiesced() { return Ok(()); }
+    use schema::play_history::dsl::*;
+    let new_play = NewPlayHistory { user_id: input_user_id, track_id: input_track_id, played_at: Utc::now().naive_utc(), skipped: false };
+    time!("record_play.insert", diesel::insert_into(play_history).values(new_play).execute(&self.connection)?);
+    Ok(())
+  }
+
+  /// Records that the user with `input_user_id` skipped the track with `input_track_id` at `position_relative`, by
+  /// marking their last play of the track as skipped. Stopping playback after `SKIP_THRESHOLD` of the track was played
+  /// does not count as skipping it. Returns whether a skip was recorded.
+  pub fn report_skip(&self, input_user_id: i32, input_track_id: i32, position_relative: f64) -> Result<bool, DatabaseQueryError> {
+    if !(0.0..SKIP_THRESHOLD).contains(&position_relative) {
+      return Ok(false);
+    }
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::play_history::dsl::*;
+      let last_play_id: Option<i32> = time!("report_skip.select", play_history
+        .select(id)
+        .filter(user_id.eq(input_user_id))
+        .filter(track_id.eq(input_track_id))
+        .order((played_at.desc(), id.desc()))
+        .first(&self.connection)
+        .optional()?);
+      if let Some(last_play_id) = last_play_id {
+        time!("report_skip.update", diesel::update(play_history.find(last_play_id)).set(skipped.eq(true)).execute(&self.connection)?);
+      }
+      Ok(last_play_id.is_some())
+    })
+  }
+
+  /// Gets the fraction of plays of the user with `input_user_id` that were skipped, for each track they skipped.
+  pub(crate) fn get_skip_ratios(&self, input_user_id: i32) -> Result<HashMap<i32, f64>, DatabaseQueryError> {
+    use schema::play_history::dsl::*;
+    let plays: Vec<(i32, bool)> = time!("get_skip_ratios.select", play_history.select((track_id, skipped)).filter(user_id.eq(input_user_id)).load(&self.connection)?);
+    let mut counts: HashMap<i32, (i64, i64)> = HashMap::new();
+    for (play_track_id, play_skipped) in plays {
+      let (play_count, skip_count) = counts.entry(play_track_id).or_default();
+      *play_count += 1;
+      if play_skipped { *skip_count += 1; }
+    }
+    Ok(counts.into_iter()
+      .filter(|(_, (_, skip_count))| *skip_count > 0)
+      .map(|(play_track_id, (play_count, skip_count))| (play_track_id, skip_count as f64 / play_count as f64))
+      .collect())
+  }
+
+  /// Computes listening statistics of the user with `input_user_id` from their play history.
+  pub fn get_user_stats(&self, input_user_id: i32, query: UserStatsQuery) -> Result<UserStats, DatabaseQueryError> {
+    let plays: Vec<PlayHistory> = {
+      use schema::play_history::dsl::*;
+      time!("get_user_stats.select_plays", play_history
+        .filter(user_id.eq(input_user_id))
+        .order(played_at.asc())
+        .load(&self.connection)?)
+    };
+    let now = Utc::now().naive_utc();
+    let utc_offset = Duration::minutes(query.utc_offset_minutes as i64);
+    let local_date = |at: NaiveDateTime| (at + utc_offset).date();
+    let current_streak_days = {
+      let play_dates: BTreeSet<NaiveDate> = plays.iter().map(|p| local_date(p.played_at)).collect();
+      let today = local_date(now);
+      let mut date = if play_dates.contains(&today) { today } else { today.pred() };
+      let mut streak = 0;
+      while play_dates.contains(&date) {
+        streak += 1;
+        date = date.pred();
+      }
+      streak
+    };
+
+    let since = query.period.duration().map(|duration| now - duration);
+    let plays: Vec<PlayHistory> = plays.into_iter().filter(|p| since.map_or(true, |since| p.played_at >= since)).collect();
+    let mut hour_histogram = [0; 24];
+    for play in &plays {
+      hour_histogram[(play.played_at + utc_offset).hour() as usize] += 1;
+    }
+    let longest_streak_days = {
+      let play_dates: BTreeSet<NaiveDate> = plays.iter().map(|p| local_date(p.played_at)).collect();
+      let mut longest = 0;
+      let mut streak = 0;
+      let mut previous_date: Option<NaiveDate> = None;
+      for date in play_dates {
+        streak = if previous_date.map_or(false, |previous_date| previous_date.succ() == date) { streak + 1 } else { 1 };
+        longest = longest.max(streak);
+        previous_date = Some(date);
+      }
+      longest
+    };
+    let track_play_counts = track_play_counts(&plays);
+    let artist_play_counts = self.artist_play_counts(&track_play_counts)?;
+    let album_play_counts = self.album_play_counts(&track_play_counts)?;
+    let track_skip_counts = track_skip_counts(&plays);
+
+    Ok(UserStats {
+      period: query.period,
+      play_count: plays.len() as i64,
+      listening_seconds: listening_seconds(&plays, now),
+      top_artists: self.top_artists(&artist_play_counts)?,
+      top_albums: self.top_albums(&album_play_counts)?,
+      top_tracks: self.top_tracks(&track_play_counts)?,
+      skip_count: track_skip_counts.values().sum(),
+      most_skipped_tracks: self.top_tracks(&track_skip_counts)?,
+      hour_histogram,
+      current_streak_days,
+      longest_streak_days,
+    })
+  }
+
+  /// Sums the counts in `track_play_counts` per album.
+  pub(crate) fn album_play_counts(&self, track_play_counts: &HashMap<i32, i64>) -> Result<HashMap<i32, i64>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    let track_albums: Vec<(i32, i32)> = time!("album_play_counts.select", track.select((id, album_id)).load(&self.connection)?);
+    let mut album_play_counts: HashMap<i32, i64> = HashMap::new();
+    for (track_id, track_album_id) in track_albums {
+      if let Some(count) = track_play_counts.get(&track_id) {
+        *album_play_counts.entry(track_album_id).or_default() += count;
+      }
+    }
+    Ok(album_play_counts)
+  }
+
+  /// Sums the counts in `track_play_counts` per main artist of the tracks.
+  pub(crate) fn artist_play_counts(&self, track_play_counts: &HashMap<i32, i64>) -> Result<HashMap<i32, i64>, DatabaseQueryError> {
+    use schema::track_artist::dsl::*;
+    let track_artists: Vec<(i32, i32)> = time!("artist_play_counts.select", track_artist.select((track_id, artist_id)).filter(role.eq(TrackArtistRole::Main)).load(&self.connection)?);
+    let mut artist_play_counts: HashMap<i32, i64> = HashMap::new();
+    for (artist_track_id, track_artist_id) in track_artists {
+      if let Some(count) = track_play_counts.get(&artist_track_id) {
+        *artist_play_counts.entry(track_artist_id).or_default() += count;
+      }
+    }
+    Ok(artist_play_counts)
+  }
+
+  pub(crate) fn top_artists(&self, artist_play_counts: &HashMap<i32, i64>) -> Result<Vec<PlayCount<Artist>>, DatabaseQueryError> {
+    use schema::artist::dsl::*;
+    let top_ids = top_ids(artist_play_counts);
+    let artists = time!("top_artists.select", artist.filter(id.eq_any(&top_ids)).load::<Artist>(&self.connection)?);
+    Ok(into_play_counts(artists, &top_ids, artist_play_counts, |a| a.id))
+  }
+
+  pub(crate) fn top_albums(&self, album_play_counts: &HashMap<i32, i64>) -> Result<Vec<PlayCount<Album>>, DatabaseQueryError> {
+    use schema::album::dsl::*;
+    let top_ids = top_ids(album_play_counts);
+    let albums = time!("top_albums.select", album.filter(id.eq_any(&top_ids)).load::<Album>(&self.connection)?);
+    Ok(into_play_counts(albums, &top_ids, album_play_counts, |a| a.id))
+  }
+
+  pub(crate) fn top_tracks(&self, track_play_counts: &HashMap<i32, i64>) -> Result<Vec<PlayCount<Track>>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    let top_ids = top_ids(track_play_counts);
+    let tracks = time!("top_tracks.select", track.filter(id.eq_any(&top_ids)).load::<Track>(&self.connection)?);
+    Ok(into_play_counts(tracks, &top_ids, track_play_counts, |t| t.id))
+  }
+}
+
+/// Counts the plays of each track in `plays`.
+pub(crate) fn track_play_counts(plays: &[PlayHistory]) -> HashMap<i32, i64> {
+  let mut track_play_counts: HashMap<i32, i64> = HashMap::new();
+  for play in plays {
+    *track_play_counts.entry(play.track_id).or_default() += 1;
+  }
+  track_play_counts
+}
+
+/// Counts the skipped plays of each track in `plays`, leaving out tracks that were not skipped.
+pub(crate) fn track_skip_counts(plays: &[PlayHistory]) -> HashMap<i32, i64> {
+  track_play_counts(&plays.iter().filter(|p| p.skipped).copied().collect::<Vec<_>>())
+}
+
+/// Estimates the time in seconds spent listening to `plays`, which are ordered by when they were played. The duration
+/// of plays is not recorded, so plays are assumed to last until the next play, up to a maximum.
+pub(crate) fn listening_seconds(plays: &[PlayHistory], now: NaiveDateTime) -> i64 {
+  plays.iter().enumerate().map(|(i, play)| match plays.get(i + 1) {
+    Some(next_play) => (next_play.played_at - play.played_at).num_seconds().clamp(0, MAX_PLAY_SECONDS),
+    None => (now - play.played_at).num_seconds().clamp(0, LAST_PLAY_SECONDS),
+  }).sum()
+}
+
+/// Gets the IDs with the highest count in `play_counts`, highest count first, breaking ties by ID.
+fn top_ids(play_counts: &HashMap<i32, i64>) -> Vec<i32> {
+  let mut play_counts: Vec<(i32, i64)> = play_counts.iter().map(|(id, count)| (*id, *count)).collect();
+  play_counts.sort_by(|(id_a, count_a), (id_b, count_b)| count_b.cmp(count_a).then(id_a.cmp(id_b)));
+  play_counts.into_iter().take(STATS_TOP_COUNT).map(|(id, _)| id).collect()
+}
+
+/// Pairs `items` with their count in `play_counts`, in the order of `top_ids`.
+fn into_play_counts<T>(items: Vec<T>, top_ids: &[i32], play_counts: &HashMap<i32, i64>, get_id: impl Fn(&T) -> i32) -> Vec<PlayCount<T>> {
+  let mut items: HashMap<i32, T> = items.into_iter().map(|item| (get_id(&item), item)).collect();
+  top_ids.iter()
+    .filter_map(|id| items.remove(id).map(|item| PlayCount { item, play_count: play_counts[id] }))
+    .collect()
+}
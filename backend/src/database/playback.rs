@@ -1,16 +1,20 @@
 use std::backtrace::Backtrace;
 use std::path::PathBuf;
 
+use diesel::prelude::*;
 use thiserror::Error;
+use tracing::{event, Level};
 
-use musium_core::api::PlaySourceKind;
+use musium_core::api::{PlaySourceKind, ReplayGain};
+use musium_core::model::{PlaybackPreference, PreferredPlaybackSource};
+use musium_core::schema;
 
 use crate::database::spotify_track::SpotifyPlayError;
 
 use super::{DatabaseConnection, DatabaseQueryError};
 
 pub enum BackendPlaySource {
-  AudioData(PathBuf),
+  AudioData { path: PathBuf, album_id: i32, replay_gain: Option<ReplayGain> },
   ExternallyPlayedOnSpotify,
 }
 
@@ -22,24 +26,78 @@ pub enum PlayError {
   SpotifyPlayFail(#[from] SpotifyPlayError, Backtrace),
 }
 
+// Preferences
+
 impl DatabaseConnection {
-  pub fn get_track_play_source_kind_by_id(&self, track_id: i32) -> Result<Option<PlaySourceKind>, PlayError> {
-    Ok(if let Some(_) = self.get_local_track_path_by_track_id(track_id)? {
-      Some(PlaySourceKind::AudioData)
-    } else if let Some(_) = self.get_spotify_track_by_track_id(track_id)? {
-      Some(PlaySourceKind::ExternalOnSpotify)
-    } else {
-      None
-    })
+  pub fn get_playback_preference(&self, input_user_id: i32) -> Result<PlaybackPreference, DatabaseQueryError> {
+    use schema::playback_preference::dsl::*;
+    let preference = time!("get_playback_preference.select", playback_preference.find(input_user_id).first::<PlaybackPreference>(&self.connection).optional()?);
+    Ok(preference.unwrap_or_else(|| PlaybackPreference::default_for_user(input_user_id)))
+  }
+
+  pub fn set_playback_preference(&self, preference: PlaybackPreference) -> Result<PlaybackPreference, DatabaseQueryError> {
+    use schema::playback_preference::dsl::*;
+    event!(Level::DEBUG, ?preference, "Setting playback preference");
+    time!("set_playback_preference.replace", diesel::replace_into(playback_preference).values(preference).execute(&self.connection)?);
+    Ok(preference)
+  }
+}
+
+// Source selection
+
+/// Candidate sources to play a track from, in order of preference.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum PlaybackCandidate {
+  Local,
+  Spotify,
+}
+
+impl DatabaseConnection {
+  /// Determines from which sources the track with `track_id` can be played, ordered by the playback preference of the
+  /// user with `user_id`. Local tracks with a bitrate below the minimum bitrate of the user are ordered after Spotify.
+  fn get_playback_candidates(&self, track_id: i32, user_id: i32) -> Result<(Vec<PlaybackCandidate>, Option<PathBuf>), DatabaseQueryError> {
+    let preference = self.get_playback_preference(user_id)?;
+    let local = self.get_local_track_path_and_bitrate_by_track_id(track_id)?;
+    let has_spotify = self.get_spotify_track_by_track_id(track_id)?.is_some();
+    let local_meets_bitrate = match (&local, preference.min_local_bitrate) {
+      (Some((_, Some(bitrate))), Some(min_local_bitrate)) => *bitrate >= min_local_bitrate,
+      _ => true, // Unknown bitrates or no minimum bitrate: assume the local track is good enough.
+    };
+    let prefer_local = preference.preferred_source == PreferredPlaybackSource::Local && local_meets_bitrate;
+
+    let mut candidates = Vec::new();
+    if prefer_local && local.is_some() { candidates.push(PlaybackCandidate::Local); }
+    if has_spotify { candidates.push(PlaybackCandidate::Spotify); }
+    if !prefer_local && local.is_some() { candidates.push(PlaybackCandidate::Local); }
+    Ok((candidates, local.map(|(path, _)| path)))
+  }
+
+  pub fn get_track_play_source_kind_by_id(&self, track_id: i32, user_id: i32) -> Result<Option<PlaySourceKind>, PlayError> {
+    let (candidates, _) = self.get_playback_candidates(track_id, user_id)?;
+    Ok(candidates.first().map(|candidate| match candidate {
+      PlaybackCandidate::Local => PlaySourceKind::AudioData,
+      PlaybackCandidate::Spotify => PlaySourceKind::ExternalOnSpotify,
+    }))
   }
 
-  pub async fn play_track_by_id(&self, track_id: i32, user_id: i32) -> Result<Option<BackendPlaySource>, PlayError> {
-    Ok(if let Some(path) = self.get_local_track_path_by_track_id(track_id)? { // TODO: fix blocking code in async
-      Some(BackendPlaySource::AudioData(path))
-    } else if let true = self.play_spotify_track(track_id, user_id).await? {
-      Some(BackendPlaySource::ExternallyPlayedOnSpotify)
-    } else {
-      None
-    })
+  /// Plays the track with `track_id` for the user with `user_id` from the first playback source that can play it,
+  /// recording the play in the play history of the user if `record_play` is true.
+  pub async fn play_track_by_id(&self, track_id: i32, user_id: i32, record_play: bool) -> Result<Option<BackendPlaySource>, PlayError> {
+    let (candidates, local_path) = self.get_playback_candidates(track_id, user_id)?; // TODO: fix blocking code in async
+    for candidate in candidates {
+      match candidate {
+        PlaybackCandidate::Local => if let Some(path) = &local_path {
+          let album_id = self.select_track_by_id(track_id).map_err(DatabaseQueryError::from)?.album_id;
+          let replay_gain = self.get_local_track_replay_gain_by_track_id(track_id)?;
+          if record_play { self.record_play(user_id, track_id)?; }
+          return Ok(Some(BackendPlaySource::AudioData { path: path.clone(), album_id, replay_gain }));
+        }
+        PlaybackCandidate::Spotify => if self.play_spotify_track(track_id, user_id).await? {
+          if record_play { self.record_play(user_id, track_id)?; }
+          return Ok(Some(BackendPlaySource::ExternallyPlayedOnSpotify));
+        }
+      }
+    }
+    Ok(None)
   }
 }
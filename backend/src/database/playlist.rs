@@ -0,0 +1,280 @@
+use std::backtrace::Backtrace;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::model::{NewPlaylist, NewPlaylistTrack, Notification, NotificationKind, Playlist, PlaylistCollaborator, PlaylistTrack, UndoKind};
+use musium_core::model::collection::PlaylistRaw;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+#[derive(Debug, Error)]
+pub enum PlaylistError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to notify users of a playlist change")]
+  NotifyFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("User with ID '{0}' is not permitted to {1} playlist with ID '{2}'")]
+  PermissionDenied(i32, &'static str, i32),
+}
+
+// Permissions
+
+/// What a user can do with a playlist: viewing is allowed for public playlists, editing tracks is allowed for
+/// collaborators, and managing (renaming, sharing, deleting) is only allowed for the owner.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Debug)]
+enum PlaylistPermission {
+  None,
+  View,
+  Edit,
+  Manage,
+}
+
+impl DatabaseConnection {
+  fn get_playlist_permission(&self, playlist: &Playlist, input_user_id: i32) -> Result<PlaylistPermission, diesel::result::Error> {
+    if playlist.owner_user_id == input_user_id {
+      return Ok(PlaylistPermission::Manage);
+    }
+    let is_collaborator = {
+      use schema::playlist_collaborator::dsl::*;
+      time!("get_playlist_permission.select_collaborator", playlist_collaborator.find((playlist.id, input_user_id)).first::<PlaylistCollaborator>(&self.connection).optional()?).is_some()
+    };
+    Ok(if is_collaborator {
+      PlaylistPermission::Edit
+    } else if playlist.public {
+      PlaylistPermission::View
+    } else {
+      PlaylistPermission::None
+    })
+  }
+
+  /// Gets the playlist with `input_id` if it exists and the user with `input_user_id` has at least `permission` for it.
+  /// Returns `Ok(None)` if the playlist does not exist or was deleted, or if the user may not even view it.
+  fn get_playlist_with_permission(&self, input_id: i32, input_user_id: i32, permission: PlaylistPermission, action: &'static str) -> Result<Option<Playlist>, PlaylistError> {
+    let playlist = {
+      use schema::playlist::dsl::*;
+      time!("get_playlist_with_permission.select", playlist.find(input_id).filter(deleted_at.is_null()).first::<Playlist>(&self.connection).optional()?)
+    };
+    let playlist = match playlist {
+      Some(playlist) => playlist,
+      None => return Ok(None),
+    };
+    let actual_permission = self.get_playlist_permission(&playlist, input_user_id)?;
+    if actual_permission == PlaylistPermission::None {
+      Ok(None)
+    } else if actual_permission < permission {
+      Err(PlaylistError::PermissionDenied(input_user_id, action, input_id))
+    } else {
+      Ok(Some(playlist))
+    }
+  }
+}
+
+// Querying
+
+impl DatabaseConnection {
+  /// Lists playlists that the user with `input_user_id` owns, collaborates on, or that are public.
+  pub fn list_playlists(&self, input_user_id: i32) -> Result<Vec<Playlist>, DatabaseQueryError> {
+    let collaborating_playlist_ids: Vec<i32> = {
+      use schema::playlist_collaborator::dsl::*;
+      time!("list_playlists.select_collaborating", playlist_collaborator.select(playlist_id).filter(user_id.eq(input_user_id)).load(&self.connection)?)
+    };
+    use schema::playlist::dsl::*;
+    let query = playlist
+      .filter(owner_user_id.eq(input_user_id).or(public.eq(true)).or(id.eq_any(collaborating_playlist_ids)))
+      .filter(deleted_at.is_null())
+      .order(name.asc());
+    Ok(time!("list_playlists.select", query.load::<Playlist>(&self.connection)?))
+  }
+
+  pub fn get_playlist_by_id(&self, input_id: i32, input_user_id: i32) -> Result<Option<PlaylistRaw>, PlaylistError> {
+    let playlist = match self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::View, "view")? {
+      Some(playlist) => playlist,
+      None => return Ok(None),
+    };
+    let playlist_tracks = {
+      use schema::playlist_track::dsl::*;
+      time!("get_playlist_by_id.select_tracks", playlist_track.filter(playlist_id.eq(input_id)).order(position.asc()).load::<PlaylistTrack>(&self.connection)?)
+    };
+    let collaborators = {
+      use schema::playlist_collaborator::dsl::*;
+      time!("get_playlist_by_id.select_collaborators", playlist_collaborator.filter(playlist_id.eq(input_id)).load::<PlaylistCollaborator>(&self.connection)?)
+    };
+    Ok(Some(PlaylistRaw { playlist, playlist_tracks, collaborators }))
+  }
+}
+
+// Managing
+
+impl DatabaseConnection {
+  pub fn create_playlist(&self, new_playlist: NewPlaylist) -> Result<Playlist, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::playlist::dsl::*;
+      event!(Level::DEBUG, ?new_playlist, "Inserting playlist");
+      time!("create_playlist.insert", diesel::insert_into(playlist).values(new_playlist).execute(&self.connection)?);
+      Ok(time!("create_playlist.select_inserted", playlist.order(id.desc()).first(&self.connection)?))
+    })
+  }
+
+  pub fn update_playlist(&self, input_id: i32, input_name: String, input_public: bool, input_user_id: i32) -> Result<Option<Playlist>, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      let mut db_playlist = match self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Manage, "manage")? {
+        Some(playlist) => playlist,
+        None => return Ok(None),
+      };
+      db_playlist.name = input_name;
+      db_playlist.public = input_public;
+      Ok(Some(time!("update_playlist.update", db_playlist.save_changes::<Playlist>(&*self.connection)?)))
+    })
+  }
+
+  /// Deletes the playlist with `input_id`, returning `false` if it does not exist. The playlist is kept until its
+  /// deletion can no longer be undone, see [DatabaseConnection::undo].
+  pub fn delete_playlist(&self, input_id: i32, input_user_id: i32) -> Result<bool, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      let mut db_playlist = match self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Manage, "delete")? {
+        Some(playlist) => playlist,
+        None => return Ok(false),
+      };
+      db_playlist.deleted_at = Some(Utc::now().naive_utc());
+      time!("delete_playlist.update", db_playlist.save_changes::<Playlist>(&*self.connection)?);
+      self.log_undo(input_user_id, UndoKind::PlaylistDeletion, input_id, None, format!("Delete playlist '{}'", db_playlist.name))?;
+      Ok(true)
+    })
+  }
+
+  /// Permanently deletes the deleted playlist with `input_id`, including its tracks and collaborators.
+  pub(crate) fn purge_deleted_playlist(&self, input_id: i32) -> Result<(), diesel::result::Error> {
+    {
+      use schema::playlist_track::dsl::*;
+      time!("purge_deleted_playlist.delete_tracks", diesel::delete(playlist_track.filter(playlist_id.eq(input_id))).execute(&self.connection)?);
+    }
+    {
+      use schema::playlist_collaborator::dsl::*;
+      time!("purge_deleted_playlist.delete_collaborators", diesel::delete(playlist_collaborator.filter(playlist_id.eq(input_id))).execute(&self.connection)?);
+    }
+    {
+      use schema::playlist_mix::dsl::*;
+      time!("purge_deleted_playlist.delete_mix", diesel::delete(playlist_mix.filter(playlist_id.eq(input_id))).execute(&self.connection)?);
+    }
+    use schema::playlist::dsl::*;
+    time!("purge_deleted_playlist.delete", diesel::delete(playlist.find(input_id).filter(deleted_at.is_not_null())).execute(&self.connection)?);
+    Ok(())
+  }
+
+  pub fn add_playlist_collaborator(&self, input_id: i32, input_collaborator_user_id: i32, input_user_id: i32) -> Result<Option<PlaylistCollaborator>, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      if self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Manage, "share")?.is_none() {
+        return Ok(None);
+      }
+      let collaborator = PlaylistCollaborator { playlist_id: input_id, user_id: input_collaborator_user_id };
+      event!(Level::DEBUG, ?collaborator, "Inserting playlist collaborator");
+      use schema::playlist_collaborator::dsl::*;
+      time!("add_playlist_collaborator.insert", diesel::replace_into(playlist_collaborator).values(collaborator).execute(&self.connection)?);
+      Ok(Some(collaborator))
+    })
+  }
+
+  /// Removes a collaborator from the playlist with `input_id`, returning `false` if the playlist or collaborator does
+  /// not exist.
+  pub fn remove_playlist_collaborator(&self, input_id: i32, input_collaborator_user_id: i32, input_user_id: i32) -> Result<bool, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      if self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Manage, "share")?.is_none() {
+        return Ok(false);
+      }
+      use schema::playlist_collaborator::dsl::*;
+      Ok(time!("remove_playlist_collaborator.delete", diesel::delete(playlist_collaborator.find((input_id, input_collaborator_user_id))).execute(&self.connection)?) > 0)
+    })
+  }
+}
+
+// Editing tracks
+
+impl DatabaseConnection {
+  /// Appends the track with `input_track_id` to the end of the playlist with `input_id`.
+  pub fn add_playlist_track(&self, input_id: i32, input_track_id: i32, input_user_id: i32) -> Result<Option<PlaylistTrack>, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      if self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Edit, "edit")?.is_none() {
+        return Ok(None);
+      }
+      use schema::playlist_track::dsl::*;
+      let last_position: Option<i32> = time!("add_playlist_track.select_last_position", playlist_track.select(diesel::dsl::max(position)).filter(playlist_id.eq(input_id)).first(&self.connection)?);
+      let new_playlist_track = NewPlaylistTrack {
+        playlist_id: input_id,
+        track_id: input_track_id,
+        position: last_position.map_or(0, |p| p + 1),
+        added_by: input_user_id,
+      };
+      event!(Level::DEBUG, ?new_playlist_track, "Inserting playlist track");
+      time!("add_playlist_track.insert", diesel::insert_into(playlist_track).values(new_playlist_track).execute(&self.connection)?);
+      Ok(Some(time!("add_playlist_track.select_inserted", playlist_track.order(id.desc()).first(&self.connection)?)))
+    })
+  }
+
+  /// Appends the tracks with `input_track_ids` to the end of the playlist with `input_id`, in order. Tracks that do not
+  /// exist are skipped.
+  pub fn add_playlist_tracks(&self, input_id: i32, input_track_ids: &[i32], input_user_id: i32) -> Result<Option<Vec<PlaylistTrack>>, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      if self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Edit, "edit")?.is_none() {
+        return Ok(None);
+      }
+      let input_track_ids = self.filter_existing_track_ids(input_track_ids)?;
+      use schema::playlist_track::dsl::*;
+      let last_position: Option<i32> = time!("add_playlist_tracks.select_last_position", playlist_track.select(diesel::dsl::max(position)).filter(playlist_id.eq(input_id)).first(&self.connection)?);
+      let first_position = last_position.map_or(0, |p| p + 1);
+      let new_playlist_tracks: Vec<_> = input_track_ids.iter().enumerate().map(|(i, input_track_id)| NewPlaylistTrack {
+        playlist_id: input_id,
+        track_id: *input_track_id,
+        position: first_position + i as i32,
+        added_by: input_user_id,
+      }).collect();
+      event!(Level::DEBUG, ?new_playlist_tracks, "Inserting playlist tracks");
+      time!("add_playlist_tracks.insert", diesel::insert_into(playlist_track).values(&new_playlist_tracks).execute(&self.connection)?);
+      Ok(Some(time!("add_playlist_tracks.select_inserted", playlist_track
+        .filter(playlist_id.eq(input_id))
+        .filter(position.ge(first_position))
+        .order(position.asc())
+        .load(&self.connection)?)))
+    })
+  }
+
+  /// Removes the playlist track with `input_playlist_track_id` from the playlist with `input_id`, returning `false` if
+  /// the playlist or playlist track does not exist.
+  pub fn remove_playlist_track(&self, input_id: i32, input_playlist_track_id: i32, input_user_id: i32) -> Result<bool, PlaylistError> {
+    self.connection.transaction::<_, PlaylistError, _>(|| {
+      if self.get_playlist_with_permission(input_id, input_user_id, PlaylistPermission::Edit, "edit")?.is_none() {
+        return Ok(false);
+      }
+      use schema::playlist_track::dsl::*;
+      let query = playlist_track.filter(id.eq(input_playlist_track_id)).filter(playlist_id.eq(input_id));
+      Ok(time!("remove_playlist_track.delete", diesel::delete(query).execute(&self.connection)?) > 0)
+    })
+  }
+}
+
+// Change notifications
+
+impl DatabaseConnection {
+  /// Notifies the owner and collaborators of the playlist with `input_id` of a change, except the user with
+  /// `input_user_id` that made the change.
+  pub fn notify_playlist_change(&self, input_id: i32, input_user_id: i32, message: String) -> Result<Vec<Notification>, PlaylistError> {
+    let owner_user_id: Option<i32> = {
+      use schema::playlist::dsl::*;
+      time!("notify_playlist_change.select_owner", playlist.select(owner_user_id).find(input_id).first(&self.connection).optional()?)
+    };
+    let collaborator_user_ids: Vec<i32> = {
+      use schema::playlist_collaborator::dsl::*;
+      time!("notify_playlist_change.select_collaborators", playlist_collaborator.select(user_id).filter(playlist_id.eq(input_id)).load(&self.connection)?)
+    };
+    let mut notifications = Vec::new();
+    for user_id in owner_user_id.into_iter().chain(collaborator_user_ids).filter(|u| *u != input_user_id) {
+      if let Some(notification) = self.notify_user(user_id, NotificationKind::SharedPlaylistChange, message.clone())? {
+        notifications.push(notification);
+      }
+    }
+    Ok(notifications)
+  }
+}
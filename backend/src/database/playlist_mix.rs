@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{MAX_RATING, NewPlaylist, NewPlaylistTrack, Playlist, PlaylistMix, PlaylistMixKind, TrackArtistRole};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Number of tracks in each mix.
+const MIX_TRACK_COUNT: usize = 30;
+/// Number of days of play history that mixes are generated from.
+const MIX_HISTORY_DAYS: i64 = 90;
+/// Score of a track for each play in the play history.
+const PLAY_SCORE: f64 = 1.0;
+/// Score of a track with the maximum rating. Tracks rated below half the maximum rating are scored negatively, down to
+/// the negation of this score.
+const RATING_SCORE: f64 = 3.0;
+/// Number of top artists that each daily mix is made of. Top artists are dealt round-robin over the daily mixes.
+const DAILY_MIX_ARTISTS: usize = 5;
+/// Maximum number of tracks of the same artist in the discovery mix, so that it is not dominated by a single artist.
+const DISCOVERY_TRACKS_PER_ARTIST: usize = 3;
+
+// Querying
+
+impl DatabaseConnection {
+  /// Lists the mixes generated for the user with `input_user_id`. Mixes are playlists owned by the user, which are
+  /// available through the playlist API.
+  pub fn list_playlist_mixes(&self, input_user_id: i32) -> Result<Vec<PlaylistMix>, DatabaseQueryError> {
+    use schema::playlist_mix::dsl::*;
+    Ok(time!("list_playlist_mixes.select", playlist_mix.filter(user_id.eq(input_user_id)).order(kind.asc()).load::<PlaylistMix>(&self.connection)?))
+  }
+}
+
+// Generation
+
+impl DatabaseConnection {
+  /// Regenerates the mixes of all users that have not been generated yet, or that were generated longer ago than the
+  /// regeneration interval of their kind. Mixes without any tracks are not generated, and mixes whose playlist was
+  /// deleted by the user are not regenerated until the deletion can no longer be undone. Returns the number of
+  /// regenerated mixes.
+  #[instrument(skip(self))]
+  pub fn regenerate_stale_playlist_mixes(&self) -> Result<usize, DatabaseQueryError> {
+    let now = Utc::now().naive_utc();
+    let mut library: Option<MixLibrary> = None;
+    let mut regenerated = 0;
+    for user in self.list_users()? {
+      let mixes = self.list_playlist_mixes(user.id)?;
+      let stale_kinds: Vec<_> = PlaylistMixKind::ALL.iter().copied()
+        .filter(|k| mixes.iter().find(|m| m.kind == *k).map_or(true, |m| now - m.generated_at >= k.regeneration_interval()))
+        .collect();
+      if stale_kinds.is_empty() { continue; }
+      // Only load the library when there are stale mixes, which is rare as mixes are regenerated at most daily.
+      if library.is_none() {
+        library = Some(self.load_mix_library()?);
+      }
+      // UNWRAP: set above if it was not set yet.
+      let library = library.as_ref().unwrap();
+      let profile = self.load_listening_profile(user.id, library, now)?;
+      for kind in stale_kinds {
+        let track_ids = match kind {
+          PlaylistMixKind::DailyMix1 => profile.daily_mix(library, 0),
+          PlaylistMixKind::DailyMix2 => profile.daily_mix(library, 1),
+          PlaylistMixKind::DailyMix3 => profile.daily_mix(library, 2),
+          PlaylistMixKind::DiscoveryWeekly => profile.discovery_mix(library),
+        };
+        if track_ids.is_empty() { continue; }
+        let existing = mixes.iter().find(|m| m.kind == kind).copied();
+        if self.materialize_playlist_mix(user.id, kind, existing, &track_ids, now)? {
+          regenerated += 1;
+        }
+      }
+    }
+    Ok(regenerated)
+  }
+
+  /// Replaces the tracks of the playlist of `existing` mix with `track_ids`, or creates a playlist for a new mix of
+  /// `kind` when `existing` is `None`. Returns `false` if the playlist of the existing mix was deleted.
+  fn materialize_playlist_mix(&self, input_user_id: i32, input_kind: PlaylistMixKind, existing: Option<PlaylistMix>, track_ids: &[i32], now: NaiveDateTime) -> Result<bool, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      let input_playlist_id = match existing {
+        Some(mix) => {
+          let db_playlist = {
+            use schema::playlist::dsl::*;
+            time!("materialize_playlist_mix.select_playlist", playlist.find(mix.playlist_id).first::<Playlist>(&self.connection)?)
+          };
+          if db_playlist.deleted_at.is_some() { return Ok(false); }
+          {
+            use schema::playlist_track::dsl::*;
+            time!("materialize_playlist_mix.delete_tracks", diesel::delete(playlist_track.filter(playlist_id.eq(mix.playlist_id))).execute(&self.connection)?);
+          }
+          use schema::playlist_mix::dsl::*;
+          time!("materialize_playlist_mix.update", diesel::update(playlist_mix.find(mix.playlist_id)).set(generated_at.eq(now)).execute(&self.connection)?);
+          mix.playlist_id
+        }
+        None => {
+          let new_playlist = NewPlaylist { owner_user_id: input_user_id, name: input_kind.name().to_string(), public: false };
+          let db_playlist = self.create_playlist(new_playlist)?;
+          let new_mix = PlaylistMix { playlist_id: db_playlist.id, user_id: input_user_id, kind: input_kind, generated_at: now };
+          use schema::playlist_mix::dsl::*;
+          time!("materialize_playlist_mix.insert", diesel::insert_into(playlist_mix).values(new_mix).execute(&self.connection)?);
+          db_playlist.id
+        }
+      };
+      let new_playlist_tracks: Vec<_> = track_ids.iter().enumerate()
+        .map(|(i, track_id)| NewPlaylistTrack { playlist_id: input_playlist_id, track_id: *track_id, position: i as i32, added_by: input_user_id })
+        .collect();
+      use schema::playlist_track::dsl::*;
+      time!("materialize_playlist_mix.insert_tracks", diesel::insert_into(playlist_track).values(new_playlist_tracks).execute(&self.connection)?);
+      event!(Level::DEBUG, user_id = input_user_id, kind = ?input_kind, tracks = track_ids.len(), "Generated playlist mix");
+      Ok(true)
+    })
+  }
+
+  fn load_mix_library(&self) -> Result<MixLibrary, DatabaseQueryError> {
+    let playable_track_ids = self.get_track_availability()?.into_iter()
+      .filter(|(_, availability)| availability.is_playable())
+      .map(|(track_id, _)| track_id)
+      .collect();
+    let track_artists: Vec<(i32, i32)> = {
+      use schema::track_artist::dsl::*;
+      time!("load_mix_library.select_track_artists", track_artist.select((track_id, artist_id)).filter(role.eq(TrackArtistRole::Main)).load(&self.connection)?)
+    };
+    let mut artist_track_ids: HashMap<i32, Vec<i32>> = HashMap::new();
+    let mut track_artist_ids: HashMap<i32, Vec<i32>> = HashMap::new();
+    for (track_id, artist_id) in track_artists {
+      artist_track_ids.entry(artist_id).or_default().push(track_id);
+      track_artist_ids.entry(track_id).or_default().push(artist_id);
+    }
+    let audio_features: Vec<(i32, Option<f32>, Option<f32>)> = {
+      use schema::track_audio_features::dsl::*;
+      time!("load_mix_library.select_audio_features", track_audio_features.select((track_id, energy, bpm)).load(&self.connection)?)
+    };
+    let audio_features = audio_features.into_iter().map(|(track_id, energy, bpm)| (track_id, (energy, bpm))).collect();
+    Ok(MixLibrary { playable_track_ids, artist_track_ids, track_artist_ids, audio_features })
+  }
+
+  fn load_listening_profile(&self, input_user_id: i32, library: &MixLibrary, now: NaiveDateTime) -> Result<ListeningProfile, DatabaseQueryError> {
+    let mut track_scores: HashMap<i32, f64> = HashMap::new();
+    let played_track_ids: HashSet<i32> = {
+      use schema::play_history::dsl::*;
+      let since = now - Duration::days(MIX_HISTORY_DAYS);
+      let plays: Vec<(i32, NaiveDateTime)> = time!("load_listening_profile.select_plays", play_history
+        .select((track_id, played_at))
+        .filter(user_id.eq(input_user_id))
+        .load(&self.connection)?);
+      for (played_track_id, _) in plays.iter().filter(|(_, at)| *at >= since) {
+        *track_scores.entry(*played_track_id).or_default() += PLAY_SCORE;
+      }
+      plays.into_iter().map(|(played_track_id, _)| played_track_id).collect()
+    };
+    let half_rating = MAX_RATING as f64 / 2.0;
+    let rated_track_ids: HashSet<i32> = {
+      use schema::user_track_rating::dsl::*;
+      let ratings: Vec<(i32, i32)> = time!("load_listening_profile.select_track_ratings", user_track_rating.select((track_id, rating)).filter(user_id.eq(input_user_id)).load(&self.connection)?);
+      for (rated_track_id, track_rating) in &ratings {
+        *track_scores.entry(*rated_track_id).or_default() += (*track_rating as f64 - half_rating) / half_rating * RATING_SCORE;
+      }
+      ratings.into_iter().map(|(rated_track_id, _)| rated_track_id).collect()
+    };
+    let mut artist_scores: HashMap<i32, f64> = HashMap::new();
+    for (track_id, score) in track_scores.iter().filter(|(_, score)| **score > 0.0) {
+      for artist_id in library.track_artist_ids.get(track_id).into_iter().flatten() {
+        *artist_scores.entry(*artist_id).or_default() += score;
+      }
+    }
+    {
+      use schema::user_artist_rating::dsl::*;
+      let ratings: Vec<(i32, i32)> = time!("load_listening_profile.select_artist_ratings", user_artist_rating.select((artist_id, rating)).filter(user_id.eq(input_user_id)).load(&self.connection)?);
+      for (rated_artist_id, artist_rating) in ratings {
+        *artist_scores.entry(rated_artist_id).or_default() += (artist_rating as f64 - half_rating) / half_rating * RATING_SCORE;
+      }
+    }
+    let mut top_artist_ids: Vec<(i32, f64)> = artist_scores.into_iter().filter(|(_, score)| *score > 0.0).collect();
+    top_artist_ids.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let top_artist_ids = top_artist_ids.into_iter().map(|(artist_id, _)| artist_id).collect();
+    Ok(ListeningProfile { track_scores, played_track_ids, rated_track_ids, top_artist_ids })
+  }
+}
+
+/// Library data that mixes of all users are generated from.
+struct MixLibrary {
+  playable_track_ids: HashSet<i32>,
+  artist_track_ids: HashMap<i32, Vec<i32>>,
+  track_artist_ids: HashMap<i32, Vec<i32>>,
+  /// Energy and tempo of tracks, by track ID.
+  audio_features: HashMap<i32, (Option<f32>, Option<f32>)>,
+}
+
+/// Listening behavior of a user that their mixes are generated from.
+struct ListeningProfile {
+  /// Scores of tracks the user recently played or rated, where favorite tracks have a positive score and disliked tracks
+  /// a negative score.
+  track_scores: HashMap<i32, f64>,
+  /// Tracks the user ever played.
+  played_track_ids: HashSet<i32>,
+  rated_track_ids: HashSet<i32>,
+  /// Artists of the favorite tracks and favorite artists of the user, most favorite first.
+  top_artist_ids: Vec<i32>,
+}
+
+impl ListeningProfile {
+  /// Gets the tracks of daily mix `index`: mostly favorite tracks of its group of top artists, topped up with tracks of
+  /// those artists that the user has not played yet, in random order.
+  fn daily_mix(&self, library: &MixLibrary, index: usize) -> Vec<i32> {
+    let mut rng = rand::thread_rng();
+    let artist_ids = self.top_artist_ids.iter().skip(index).step_by(3).take(DAILY_MIX_ARTISTS);
+    let track_ids: HashSet<i32> = artist_ids
+      .flat_map(|artist_id| library.artist_track_ids.get(artist_id).into_iter().flatten().copied())
+      .filter(|track_id| library.playable_track_ids.contains(track_id))
+      .collect();
+    let mut favorites: Vec<(i32, f64)> = track_ids.iter()
+      .filter_map(|track_id| self.track_scores.get(track_id).filter(|score| **score > 0.0).map(|score| (*track_id, score + rng.gen::<f64>())))
+      .collect();
+    favorites.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let mut mix: Vec<i32> = favorites.into_iter().take(MIX_TRACK_COUNT * 2 / 3).map(|(track_id, _)| track_id).collect();
+    let mut unheard: Vec<i32> = track_ids.into_iter()
+      .filter(|track_id| !self.played_track_ids.contains(track_id) && !self.rated_track_ids.contains(track_id))
+      .collect();
+    unheard.shuffle(&mut rng);
+    let remaining = MIX_TRACK_COUNT - mix.len();
+    mix.extend(unheard.into_iter().take(remaining));
+    mix.shuffle(&mut rng);
+    mix
+  }
+
+  /// Gets the tracks of the discovery mix: tracks the user has not played or rated yet, scored by whether they are of a
+  /// top artist, and by how close their energy and tempo are to those of the favorite tracks of the user.
+  fn discovery_mix(&self, library: &MixLibrary) -> Vec<i32> {
+    let mut rng = rand::thread_rng();
+    let favorite_features: Vec<(Option<f32>, Option<f32>)> = self.track_scores.iter()
+      .filter(|(_, score)| **score > 0.0)
+      .filter_map(|(track_id, _)| library.audio_features.get(track_id).copied())
+      .collect();
+    let mean = |values: Vec<f32>| if values.is_empty() { None } else { Some(values.iter().sum::<f32>() / values.len() as f32) };
+    let mean_energy = mean(favorite_features.iter().filter_map(|(energy, _)| *energy).collect());
+    let mean_bpm = mean(favorite_features.iter().filter_map(|(_, bpm)| *bpm).collect());
+    let top_artist_ranks: HashMap<i32, usize> = self.top_artist_ids.iter().enumerate().map(|(rank, artist_id)| (*artist_id, rank)).collect();
+    let mut candidates: Vec<(i32, f64)> = library.playable_track_ids.iter()
+      .filter(|track_id| !self.played_track_ids.contains(track_id) && !self.rated_track_ids.contains(track_id))
+      .map(|track_id| {
+        let mut score = rng.gen::<f64>();
+        // Prefer tracks of higher ranked top artists.
+        if let Some(rank) = library.track_artist_ids.get(track_id).into_iter().flatten().filter_map(|artist_id| top_artist_ranks.get(artist_id)).min() {
+          score += 2.0 / (1.0 + *rank as f64).sqrt();
+        }
+        if let Some((energy, bpm)) = library.audio_features.get(track_id) {
+          if let (Some(energy), Some(mean_energy)) = (energy, mean_energy) {
+            score += 1.0 - (energy - mean_energy).abs().min(1.0) as f64;
+          }
+          if let (Some(bpm), Some(mean_bpm)) = (bpm, mean_bpm) {
+            score += 1.0 - ((bpm - mean_bpm).abs() / 60.0).min(1.0) as f64;
+          }
+        }
+        (*track_id, score)
+      })
+      .collect();
+    candidates.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let mut tracks_per_artist: HashMap<i32, usize> = HashMap::new();
+    candidates.into_iter()
+      .filter(|(track_id, _)| {
+        let artist_ids = library.track_artist_ids.get(track_id).map(|ids| ids.as_slice()).unwrap_or_default();
+        if artist_ids.iter().any(|artist_id| tracks_per_artist.get(artist_id).copied().unwrap_or(0) >= DISCOVERY_TRACKS_PER_ARTIST) {
+          return false;
+        }
+        for artist_id in artist_ids {
+          *tracks_per_artist.entry(*artist_id).or_default() += 1;
+        }
+        true
+      })
+      .take(MIX_TRACK_COUNT)
+      .map(|(track_id, _)| track_id)
+      .collect()
+  }
+}
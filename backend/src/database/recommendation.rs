@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use rand::Rng;
+
+use musium_core::api::TrackRecommendationRequest;
+use musium_core::model::{MAX_RATING, Track, TrackArtistRole};
+use musium_core::model::collection::TracksRaw;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+use super::track::select_track_ids_in;
+
+/// Number of tracks that are recommended when the number of tracks is not given.
+pub const DEFAULT_RECOMMENDED_TRACKS_COUNT: i64 = 20;
+/// Maximum number of tracks that are recommended at once.
+pub const MAX_RECOMMENDED_TRACKS_COUNT: i64 = 100;
+/// Score of a track for each artist it shares with the seed tracks.
+const SHARED_ARTIST_SCORE: f64 = 2.0;
+/// Score of a track for each tag it shares with the seed tracks.
+const SHARED_TAG_SCORE: f64 = 1.0;
+/// Score of a track with the maximum rating. Tracks rated below half the maximum rating are scored negatively, down to
+/// the negation of this score.
+const RATING_SCORE: f64 = 2.0;
+/// Score subtracted from a track that the user skipped on every play, in proportion to how often it was skipped.
+const SKIPPED_SCORE: f64 = 3.0;
+/// Maximum random score added to each track, so that recommendations for the same seed tracks vary.
+const RANDOM_SCORE: f64 = 1.0;
+
+impl DatabaseConnection {
+  /// Recommends tracks similar to the seed tracks of `request`, scoring playable tracks by the artists and tags they
+  /// share with the seed tracks, by the rating the user with `user_id` gave them, and by how often they skipped them
+  /// when requested, along with only the albums and artists those tracks refer to. Tracks are ordered by descending
+  /// score. Tracks that the user hid or that the content filter of the user leaves out are never recommended.
+  pub fn recommend_tracks(&self, user_id: i32, request: &TrackRecommendationRequest) -> Result<TracksRaw, DatabaseQueryError> {
+    let count = request.count.unwrap_or(DEFAULT_RECOMMENDED_TRACKS_COUNT).clamp(1, MAX_RECOMMENDED_TRACKS_COUNT);
+    let seed_track_ids = &request.seed_track_ids;
+    let mut scores: HashMap<i32, f64> = HashMap::new();
+    {
+      use schema::track_artist::dsl::*;
+      let seed_artist_ids = track_artist.select(artist_id).filter(track_id.eq_any(seed_track_ids)).filter(role.eq(TrackArtistRole::Main));
+      let sharing_track_ids: Vec<i32> = time!("recommend_tracks.select_sharing_artists", track_artist.select(track_id).filter(artist_id.eq_any(seed_artist_ids)).filter(role.eq(TrackArtistRole::Main)).load(&self.connection)?);
+      for sharing_track_id in sharing_track_ids {
+        *scores.entry(sharing_track_id).or_default() += SHARED_ARTIST_SCORE;
+      }
+    }
+    {
+      use schema::track_tag::dsl::*;
+      let seed_tag_ids = track_tag.select(tag_id).filter(track_id.eq_any(seed_track_ids));
+      let sharing_track_ids: Vec<i32> = time!("recommend_tracks.select_sharing_tags", track_tag.select(track_id).filter(tag_id.eq_any(seed_tag_ids)).load(&self.connection)?);
+      for sharing_track_id in sharing_track_ids {
+        *scores.entry(sharing_track_id).or_default() += SHARED_TAG_SCORE;
+      }
+    }
+    let availability = self.get_track_availability()?;
+    let hidden_track_ids = self.get_hidden_track_ids(user_id)?;
+    let content_filtered_track_ids = self.get_content_filtered_track_ids(self.get_user_content_filter(user_id)?.filter)?;
+    scores.retain(|id, _| !seed_track_ids.contains(id)
+      && !request.exclude_track_ids.contains(id)
+      && !hidden_track_ids.contains(id)
+      && !content_filtered_track_ids.contains(id)
+      && availability.get(id).map_or(false, |a| a.is_playable()));
+    {
+      use schema::user_track_rating::dsl::{rating, track_id, user_id as rating_user_id, user_track_rating};
+      // All ratings of the user are selected instead of binding the IDs of the candidates, as there may be more
+      // candidates than SQLite allows bound variables in a query. Ratings of other tracks are skipped below.
+      let ratings: Vec<(i32, i32)> = time!("recommend_tracks.select_ratings", user_track_rating
+        .select((track_id, rating))
+        .filter(rating_user_id.eq(user_id))
+        .load(&self.connection)?);
+      let half_rating = MAX_RATING as f64 / 2.0;
+      for (rated_track_id, track_rating) in ratings {
+        if let Some(score) = scores.get_mut(&rated_track_id) {
+          *score += (track_rating as f64 - half_rating) / half_rating * RATING_SCORE;
+        }
+      }
+    }
+    if request.downrank_skipped {
+      for (skipped_track_id, skip_ratio) in self.get_skip_ratios(user_id)? {
+        if let Some(score) = scores.get_mut(&skipped_track_id) {
+          *score -= skip_ratio * SKIPPED_SCORE;
+        }
+      }
+    }
+    let mut rng = rand::thread_rng();
+    let mut scored: Vec<(i32, f64)> = scores.into_iter().map(|(id, score)| (id, score + rng.gen::<f64>() * RANDOM_SCORE)).collect();
+    scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let recommended_ids: Vec<i32> = scored.into_iter().take(count as usize).map(|(id, _)| id).collect();
+    let mut tracks = {
+      use schema::track::dsl::*;
+      time!("recommend_tracks.select_tracks", track.filter(id.eq_any(&recommended_ids)).load::<Track>(&self.connection)?)
+    };
+    // Restore the order of the recommendation, as the database returns the tracks in arbitrary order.
+    tracks.sort_by_key(|t| recommended_ids.iter().position(|recommended_id| *recommended_id == t.id));
+    self.select_tracks_raw(tracks, || select_track_ids_in(&recommended_ids))
+  }
+}
@@ -0,0 +1,131 @@
+use std::backtrace::Backtrace;
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{NewNewRelease, NewRelease, Notification, NotificationKind, SpotifySource};
+use musium_core::schema;
+use musium_spotify_client::{AlbumSimple, Authorization};
+
+use crate::model::SpotifySourceEx;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// List new releases
+
+impl DatabaseConnection {
+  pub fn list_new_releases(&self) -> Result<Vec<NewRelease>, DatabaseQueryError> {
+    use schema::new_release::dsl::*;
+    Ok(time!("list_new_releases.select", new_release.order(discovered_at.desc()).load::<NewRelease>(&self.connection)?))
+  }
+}
+
+// Check for new releases
+
+#[derive(Debug, Error)]
+pub enum CheckNewReleasesError {
+  #[error("Failed to query database")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Call to Spotify API failed")]
+  SpotifyApiFail(#[from] musium_spotify_client::HttpRequestError, Backtrace),
+  #[error("Failed to notify users of new releases")]
+  NotifyFail(#[from] DatabaseQueryError, Backtrace),
+}
+
+#[derive(Default, Debug)]
+pub struct NewReleasesCheck {
+  pub new_releases: Vec<NewRelease>,
+  pub notifications: Vec<Notification>,
+}
+
+/// Albums of the followed artists of a Spotify source, fetched from the Spotify API.
+struct FetchedArtistAlbums {
+  spotify_source: SpotifySource,
+  /// Authorization of the Spotify source, which may have been refreshed while fetching.
+  authorization: Authorization,
+  /// Albums along with the name of the followed artist they were fetched for.
+  albums: Vec<(String, AlbumSimple)>,
+}
+
+impl DatabaseConnection {
+  /// Checks the followed artists of all enabled Spotify sources for releases that are not yet known, either because
+  /// they were synchronized or because a previous check already discovered them. Notifies the user of the Spotify
+  /// source of each discovered release. Returns the newly discovered releases and the created notifications.
+  ///
+  /// Albums are fetched from the Spotify API before opening a transaction, so that the database is not locked while
+  /// waiting for Spotify. The transaction only inserts the releases that are still not known at that time.
+  #[instrument(skip(self))]
+  pub fn check_new_releases(&self) -> Result<NewReleasesCheck, CheckNewReleasesError> {
+    let spotify_sources: Vec<SpotifySource> = {
+      use schema::spotify_source::dsl::*;
+      time!("check_new_releases.select_spotify_sources", spotify_source.filter(enabled.eq(true)).load(&self.connection)?)
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let fetched = runtime.block_on(self.fetch_followed_artist_albums(spotify_sources))?;
+    self.connection.transaction::<_, CheckNewReleasesError, _>(|| {
+      let known_spotify_album_ids: HashSet<String> = {
+        use schema::spotify_album::dsl::*;
+        time!("check_new_releases.select_spotify_albums", spotify_album.select(spotify_id).load::<String>(&self.connection)?)
+      }.into_iter().collect();
+      let mut check = NewReleasesCheck::default();
+      for FetchedArtistAlbums { mut spotify_source, authorization, albums } in fetched {
+        let known_new_release_ids: HashSet<String> = {
+          use schema::new_release::dsl::*;
+          time!("check_new_releases.select_new_releases", new_release.select(spotify_id).filter(spotify_source_id.eq(spotify_source.id)).load::<String>(&self.connection)?)
+        }.into_iter().collect();
+        for (artist_name, album) in albums {
+          if known_spotify_album_ids.contains(&album.id) || known_new_release_ids.contains(&album.id) { continue; }
+          let new_new_release = NewNewRelease {
+            spotify_source_id: spotify_source.id,
+            spotify_id: album.id,
+            name: album.name,
+            artist_name,
+            release_date: album.release_date,
+            discovered_at: Utc::now().naive_utc(),
+          };
+          let new_release = self.insert_new_release(new_new_release)?;
+          let message = format!("New album '{}' by {}", new_release.name, new_release.artist_name);
+          if let Some(notification) = self.notify_user(spotify_source.user_id, NotificationKind::NewRelease, message)? {
+            check.notifications.push(notification);
+          }
+          check.new_releases.push(new_release);
+        }
+
+        if spotify_source.update_from_spotify_authorization(authorization) {
+          event!(Level::DEBUG, ?spotify_source, "Spotify source has changed, updating the database");
+          spotify_source.save_changes::<SpotifySource>(&*self.connection)?;
+        }
+      }
+      Ok(check)
+    })
+  }
+
+  async fn fetch_followed_artist_albums(&self, spotify_sources: Vec<SpotifySource>) -> Result<Vec<FetchedArtistAlbums>, CheckNewReleasesError> {
+    let mut fetched = Vec::with_capacity(spotify_sources.len());
+    for spotify_source in spotify_sources {
+      let mut authorization = spotify_source.to_spotify_authorization();
+      let mut albums = Vec::new();
+      let followed_artists = self.inner.spotify_sync.get_followed_artists(&mut authorization).await?;
+      for artist in followed_artists {
+        let artist_albums = self.inner.spotify_sync.get_artist_albums_simple(artist.id, &mut authorization).await?;
+        albums.extend(artist_albums.into_iter().map(|album| (artist.name.clone(), album)));
+      }
+      fetched.push(FetchedArtistAlbums { spotify_source, authorization, albums });
+    }
+    Ok(fetched)
+  }
+
+  fn insert_new_release(&self, new_new_release: NewNewRelease) -> Result<NewRelease, diesel::result::Error> {
+    use schema::new_release::dsl::*;
+    event!(Level::DEBUG, ?new_new_release, "Inserting new release");
+    time!("insert_new_release.insert", diesel::insert_into(new_release).values(new_new_release).execute(&self.connection)?);
+    // NOTE: must be executed in a transaction for consistency
+    Ok(time!("insert_new_release.select_inserted", new_release.order(id.desc()).first(&self.connection)?))
+  }
+}
@@ -0,0 +1,184 @@
+use std::backtrace::Backtrace;
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::api::AlbumSplit;
+use musium_core::model::{Album, Artist, NewAlbumArtist, NewLocalAlbum, Track, TrackArtistRole};
+use musium_core::schema;
+
+use super::DatabaseConnection;
+
+#[derive(Debug, Error)]
+pub enum RelinkError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Artist with ID '{0}' does not exist")]
+  ArtistNotFound(i32),
+  #[error("Track with ID '{0}' is not on album with ID '{1}'")]
+  TrackNotOnAlbum(i32, i32),
+  #[error("Cannot split album with ID '{0}' without any tracks to move to the new album")]
+  NoTracksToSplit(i32),
+}
+
+// Track album
+
+impl DatabaseConnection {
+  /// Moves the track with `input_track_id` to the album with `input_album_id`, for fixing tracks that were matched to
+  /// the wrong album. Returns the updated track, or `None` if the track or album does not exist.
+  pub fn move_track_to_album(&self, input_track_id: i32, input_album_id: i32) -> Result<Option<Track>, RelinkError> {
+    self.connection.transaction::<_, RelinkError, _>(|| {
+      let db_track: Option<Track> = {
+        use schema::track::dsl::*;
+        time!("move_track_to_album.select_track", track.find(input_track_id).first(&self.connection).optional()?)
+      };
+      let db_album: Option<Album> = {
+        use schema::album::dsl::*;
+        time!("move_track_to_album.select_album", album.find(input_album_id).first(&self.connection).optional()?)
+      };
+      match (db_track, db_album) {
+        (Some(db_track), Some(db_album)) => Ok(Some(self.relink_track_album(db_track, db_album.id)?)),
+        _ => Ok(None),
+      }
+    })
+  }
+
+  /// Sets the album of `db_track` to the album with `new_album_id`, keeping local album links consistent: the new
+  /// album is linked to the local sources of the track, and the old album is unlinked from local sources that no longer
+  /// have any tracks on it.
+  fn relink_track_album(&self, mut db_track: Track, new_album_id: i32) -> Result<Track, diesel::result::Error> {
+    let old_album_id = db_track.album_id;
+    if old_album_id == new_album_id { return Ok(db_track); }
+    db_track.album_id = new_album_id;
+    event!(Level::DEBUG, ?db_track, old_album_id, "Moving track to album");
+    let db_track = time!("relink_track_album.update_track", db_track.save_changes::<Track>(&*self.connection)?);
+
+    let local_source_ids: Vec<i32> = {
+      use schema::local_track::dsl::*;
+      time!("relink_track_album.select_local_source_ids", local_track.filter(track_id.eq(db_track.id)).select(local_source_id).load(&self.connection)?)
+    };
+    for input_local_source_id in local_source_ids {
+      {
+        use schema::local_album::dsl::*;
+        let new_local_album = NewLocalAlbum { album_id: new_album_id, local_source_id: input_local_source_id };
+        time!("relink_track_album.insert_local_album", diesel::insert_or_ignore_into(local_album).values(new_local_album).execute(&self.connection)?);
+      }
+      let remaining_track_count: i64 = {
+        use schema::{local_track, track};
+        time!("relink_track_album.count_remaining_tracks", track::table
+          .inner_join(local_track::table)
+          .filter(track::album_id.eq(old_album_id))
+          .filter(local_track::local_source_id.eq(input_local_source_id))
+          .count()
+          .get_result(&self.connection)?)
+      };
+      if remaining_track_count == 0 {
+        use schema::local_album::dsl::*;
+        time!("relink_track_album.delete_local_album", diesel::delete(local_album.find((old_album_id, input_local_source_id))).execute(&self.connection)?);
+      }
+    }
+    Ok(db_track)
+  }
+}
+
+// Track and album artists
+
+impl DatabaseConnection {
+  /// Sets the artists credited in `role` on the track with `input_track_id` to the artists with `artist_ids`. Returns the
+  /// artists, or `None` if the track does not exist.
+  pub fn set_track_artists(&self, input_track_id: i32, role: TrackArtistRole, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, RelinkError> {
+    self.connection.transaction::<_, RelinkError, _>(|| {
+      let db_track: Option<Track> = {
+        use schema::track::dsl::*;
+        time!("set_track_artists.select_track", track.find(input_track_id).first(&self.connection).optional()?)
+      };
+      let db_track = match db_track {
+        Some(db_track) => db_track,
+        None => return Ok(None),
+      };
+      let db_artists = self.select_existing_artists(artist_ids)?;
+      self.sync_track_artists(&db_track, role, db_artists.iter().map(|a| a.id).collect())?;
+      Ok(Some(db_artists))
+    })
+  }
+
+  /// Sets the artists of the album with `input_album_id` to the artists with `artist_ids`. Returns the artists of the
+  /// album, or `None` if the album does not exist.
+  pub fn set_album_artists(&self, input_album_id: i32, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, RelinkError> {
+    self.connection.transaction::<_, RelinkError, _>(|| {
+      let db_album: Option<Album> = {
+        use schema::album::dsl::*;
+        time!("set_album_artists.select_album", album.find(input_album_id).first(&self.connection).optional()?)
+      };
+      let db_album = match db_album {
+        Some(db_album) => db_album,
+        None => return Ok(None),
+      };
+      let db_artists = self.select_existing_artists(artist_ids)?;
+      self.sync_album_artists(&db_album, db_artists.iter().map(|a| a.id).collect())?;
+      Ok(Some(db_artists))
+    })
+  }
+
+  fn select_existing_artists(&self, artist_ids: &[i32]) -> Result<Vec<Artist>, RelinkError> {
+    let artist_ids: HashSet<i32> = artist_ids.iter().copied().collect();
+    let db_artists: Vec<Artist> = {
+      use schema::artist::dsl::*;
+      time!("select_existing_artists.select", artist.filter(id.eq_any(artist_ids.iter().copied().collect::<Vec<_>>())).load(&self.connection)?)
+    };
+    if let Some(missing_artist_id) = artist_ids.iter().find(|artist_id| !db_artists.iter().any(|a| a.id == **artist_id)) {
+      return Err(RelinkError::ArtistNotFound(*missing_artist_id));
+    }
+    Ok(db_artists)
+  }
+}
+
+// Album split
+
+impl DatabaseConnection {
+  /// Splits an incorrectly merged album with `input_album_id`, by creating a new album with the name and artists of
+  /// `split`, and moving the tracks of `split` to it. When `split` has no artists, the artists of the original album are
+  /// copied. Returns the new album, or `None` if the original album does not exist.
+  pub fn split_album(&self, input_album_id: i32, split: &AlbumSplit) -> Result<Option<Album>, RelinkError> {
+    self.connection.transaction::<_, RelinkError, _>(|| {
+      let db_album: Option<Album> = {
+        use schema::album::dsl::*;
+        time!("split_album.select_album", album.find(input_album_id).first(&self.connection).optional()?)
+      };
+      if db_album.is_none() { return Ok(None); }
+      if split.track_ids.is_empty() { return Err(RelinkError::NoTracksToSplit(input_album_id)); }
+
+      let mut db_tracks = Vec::with_capacity(split.track_ids.len());
+      for input_track_id in &split.track_ids {
+        let db_track: Option<Track> = {
+          use schema::track::dsl::*;
+          time!("split_album.select_track", track.find(input_track_id).first(&self.connection).optional()?)
+        };
+        match db_track {
+          Some(db_track) if db_track.album_id == input_album_id => db_tracks.push(db_track),
+          _ => return Err(RelinkError::TrackNotOnAlbum(*input_track_id, input_album_id)),
+        }
+      }
+
+      let new_album = self.insert_album(&split.name)?;
+      let artist_ids: Vec<i32> = if split.artist_ids.is_empty() {
+        use schema::album_artist::dsl::*;
+        time!("split_album.select_album_artists", album_artist.filter(album_id.eq(input_album_id)).select(artist_id).load(&self.connection)?)
+      } else {
+        self.select_existing_artists(&split.artist_ids)?.into_iter().map(|a| a.id).collect()
+      };
+      for input_artist_id in artist_ids {
+        use schema::album_artist::dsl::*;
+        let new_album_artist = NewAlbumArtist { album_id: new_album.id, artist_id: input_artist_id };
+        time!("split_album.insert_album_artist", diesel::insert_into(album_artist).values(new_album_artist).execute(&self.connection)?);
+      }
+      for db_track in db_tracks {
+        self.relink_track_album(db_track, new_album.id)?;
+      }
+      event!(Level::DEBUG, ?new_album, input_album_id, "Split album");
+      Ok(Some(new_album))
+    })
+  }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+
+use musium_core::api::LibrarySummary;
+use musium_core::model::{Album, AlbumArtist, Artist};
+use musium_core::model::collection::AlbumsRaw;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Missing albums
+
+impl DatabaseConnection {
+  /// Lists albums that exist on Spotify, by artists that are in the local library, but that do not have any local
+  /// files.
+  pub fn list_missing_albums(&self) -> Result<AlbumsRaw, DatabaseQueryError> {
+    // Albums by artists that are in the local library.
+    let local_artist_album_ids = schema::album_artist::table
+      .select(schema::album_artist::album_id)
+      .filter(schema::album_artist::artist_id.eq_any(schema::local_artist::table.select(schema::local_artist::artist_id)));
+    // Albums that have at least one (non-removed) local file.
+    let local_album_ids = schema::local_track::table
+      .inner_join(schema::track::table)
+      .filter(schema::local_track::file_path.is_not_null())
+      .select(schema::track::album_id);
+    // Missing albums are selected with subqueries instead of binding their IDs, as a library may have more albums than
+    // SQLite allows bound variables in a query.
+    let missing_albums = || schema::album::table
+      .filter(schema::album::id.eq_any(local_artist_album_ids.clone()))
+      .filter(schema::album::id.eq_any(schema::spotify_album::table.select(schema::spotify_album::album_id)))
+      .filter(schema::album::id.ne_all(local_album_ids.clone()));
+
+    let albums = time!("list_missing_albums.select_albums", missing_albums().load::<Album>(&self.connection)?);
+    let album_artists = {
+      use schema::album_artist::dsl::*;
+      time!("list_missing_albums.select_album_artists", album_artist.filter(album_id.eq_any(missing_albums().select(schema::album::id))).load::<AlbumArtist>(&self.connection)?)
+    };
+    let artists = {
+      use schema::artist::dsl::*;
+      let missing_album_artist_ids = schema::album_artist::table
+        .select(schema::album_artist::artist_id)
+        .filter(schema::album_artist::album_id.eq_any(missing_albums().select(schema::album::id)));
+      time!("list_missing_albums.select_artists", artist.filter(id.eq_any(missing_album_artist_ids)).load::<Artist>(&self.connection)?)
+    };
+    let album_ids: HashSet<i32> = albums.iter().map(|a| a.id).collect();
+    let mut availability = self.get_album_availability(&self.get_track_availability()?)?;
+    availability.retain(|album_id, _| album_ids.contains(album_id));
+    Ok(AlbumsRaw { albums, artists, album_artists, availability })
+  }
+}
+
+// Library summary
+
+impl DatabaseConnection {
+  /// Counts the tracks, albums, artists, and playlists in the library. Deleted playlists are not counted.
+  pub fn get_library_summary(&self) -> Result<LibrarySummary, DatabaseQueryError> {
+    use diesel::dsl::count_star;
+    Ok(LibrarySummary {
+      track_count: time!("get_library_summary.count_tracks", schema::track::table.select(count_star()).first(&self.connection)?),
+      album_count: time!("get_library_summary.count_albums", schema::album::table.select(count_star()).first(&self.connection)?),
+      artist_count: time!("get_library_summary.count_artists", schema::artist::table.select(count_star()).first(&self.connection)?),
+      playlist_count: time!("get_library_summary.count_playlists", schema::playlist::table.select(count_star()).filter(schema::playlist::deleted_at.is_null()).first(&self.connection)?),
+    })
+  }
+}
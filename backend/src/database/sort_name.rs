@@ -0,0 +1,136 @@
+use diesel::prelude::*;
+use tracing::{event, Level};
+
+use musium_core::collation::{name_collation_key, sort_name as create_sort_name};
+use musium_core::model::{Album, Artist};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Sort articles
+
+impl DatabaseConnection {
+  pub fn list_sort_articles(&self) -> Result<Vec<String>, DatabaseQueryError> {
+    Ok(self.select_sort_articles()?)
+  }
+
+  /// Sets the articles that are moved to the end of album and artist names to create their sort names, and recomputes
+  /// the sort names of albums and artists whose sort name was not overridden. Returns the set articles.
+  pub fn set_sort_articles(&self, articles: &[String]) -> Result<Vec<String>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      {
+        use schema::sort_article::dsl::*;
+        time!("set_sort_articles.delete", diesel::delete(sort_article).execute(&self.connection)?);
+        for input_article in articles.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+          time!("set_sort_articles.insert", diesel::insert_or_ignore_into(sort_article).values(article.eq(input_article)).execute(&self.connection)?);
+        }
+      }
+      let articles = self.select_sort_articles()?;
+      event!(Level::DEBUG, ?articles, "Set sort articles, recomputing sort names");
+
+      let db_albums: Vec<Album> = {
+        use schema::album::dsl::*;
+        time!("set_sort_articles.select_albums", album.filter(sort_name_overridden.eq(false)).load(&self.connection)?)
+      };
+      for mut db_album in db_albums {
+        let new_sort_name = create_sort_name(&db_album.name, &articles);
+        if db_album.sort_name != new_sort_name {
+          db_album.sort_name = new_sort_name;
+          time!("set_sort_articles.update_album", db_album.save_changes::<Album>(&*self.connection)?);
+        }
+      }
+      let db_artists: Vec<Artist> = {
+        use schema::artist::dsl::*;
+        time!("set_sort_articles.select_artists", artist.filter(sort_name_overridden.eq(false)).load(&self.connection)?)
+      };
+      for mut db_artist in db_artists {
+        let new_sort_name = create_sort_name(&db_artist.name, &articles);
+        if db_artist.sort_name != new_sort_name {
+          db_artist.sort_name = new_sort_name;
+          time!("set_sort_articles.update_artist", db_artist.save_changes::<Artist>(&*self.connection)?);
+        }
+      }
+      Ok(articles)
+    })
+  }
+
+  pub(crate) fn select_sort_articles(&self) -> Result<Vec<String>, diesel::result::Error> {
+    use schema::sort_article::dsl::*;
+    Ok(time!("select_sort_articles.select", sort_article.select(article).load(&self.connection)?))
+  }
+
+  /// Computes the sort name of `name` with the configured sort articles.
+  pub(crate) fn compute_sort_name(&self, name: &str) -> Result<String, diesel::result::Error> {
+    Ok(create_sort_name(name, &self.select_sort_articles()?))
+  }
+}
+
+// Sort name overrides
+
+impl DatabaseConnection {
+  /// Overrides the sort name of the album with `input_album_id` with `input_sort_name`, or removes the override when
+  /// `input_sort_name` is `None`. Returns the updated album, or `None` if the album does not exist.
+  pub fn set_album_sort_name(&self, input_album_id: i32, input_sort_name: Option<&str>) -> Result<Option<Album>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      let db_album: Option<Album> = {
+        use schema::album::dsl::*;
+        time!("set_album_sort_name.select", album.find(input_album_id).first(&self.connection).optional()?)
+      };
+      let mut db_album = match db_album {
+        Some(db_album) => db_album,
+        None => return Ok(None),
+      };
+      db_album.sort_name_overridden = input_sort_name.is_some();
+      db_album.sort_name = match input_sort_name {
+        Some(input_sort_name) => input_sort_name.to_string(),
+        None => self.compute_sort_name(&db_album.name)?,
+      };
+      event!(Level::DEBUG, ?db_album, "Setting album sort name");
+      Ok(Some(time!("set_album_sort_name.update", db_album.save_changes::<Album>(&*self.connection)?)))
+    })
+  }
+
+  /// Overrides the sort name of the artist with `input_artist_id` with `input_sort_name`, or removes the override when
+  /// `input_sort_name` is `None`. Returns the updated artist, or `None` if the artist does not exist.
+  pub fn set_artist_sort_name(&self, input_artist_id: i32, input_sort_name: Option<&str>) -> Result<Option<Artist>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      let db_artist: Option<Artist> = {
+        use schema::artist::dsl::*;
+        time!("set_artist_sort_name.select", artist.find(input_artist_id).first(&self.connection).optional()?)
+      };
+      let mut db_artist = match db_artist {
+        Some(db_artist) => db_artist,
+        None => return Ok(None),
+      };
+      db_artist.sort_name_overridden = input_sort_name.is_some();
+      db_artist.sort_name = match input_sort_name {
+        Some(input_sort_name) => input_sort_name.to_string(),
+        None => self.compute_sort_name(&db_artist.name)?,
+      };
+      event!(Level::DEBUG, ?db_artist, "Setting artist sort name");
+      Ok(Some(time!("set_artist_sort_name.update", db_artist.save_changes::<Artist>(&*self.connection)?)))
+    })
+  }
+}
+
+// Derived names
+
+impl DatabaseConnection {
+  /// Updates the name key and, unless it was overridden, the sort name of `db_album` after its name has changed.
+  pub(crate) fn update_album_derived_names(&self, db_album: &mut Album) -> Result<(), diesel::result::Error> {
+    db_album.name_key = name_collation_key(&db_album.name);
+    if !db_album.sort_name_overridden {
+      db_album.sort_name = self.compute_sort_name(&db_album.name)?;
+    }
+    Ok(())
+  }
+
+  /// Updates the name key and, unless it was overridden, the sort name of `db_artist` after its name has changed.
+  pub(crate) fn update_artist_derived_names(&self, db_artist: &mut Artist) -> Result<(), diesel::result::Error> {
+    db_artist.name_key = name_collation_key(&db_artist.name);
+    if !db_artist.sort_name_overridden {
+      db_artist.sort_name = self.compute_sort_name(&db_artist.name)?;
+    }
+    Ok(())
+  }
+}
@@ -1,14 +1,26 @@
+use std::backtrace::Backtrace;
+
+use chrono::Utc;
 use diesel::prelude::*;
+use thiserror::Error;
 
-use musium_core::model::{LocalSource, NewLocalSource};
+use musium_core::api::{LocalSourceRelocation, LocalSourceRelocationResult, RelocatedTrack};
+use musium_core::model::{LocalSource, LocalTrack, NewLocalSource, UndoKind};
 use musium_core::schema;
+use musium_filesystem_sync::path_key;
 
 use crate::database::{DatabaseConnection, DatabaseQueryError};
+use crate::model::LocalSourceEx;
 
 impl DatabaseConnection {
   pub fn list_local_sources(&self) -> Result<Vec<LocalSource>, DatabaseQueryError> {
     use schema::local_source::dsl::*;
-    Ok(time!("list_local_sources.select", local_source.load::<LocalSource>(&self.connection)?))
+    Ok(time!("list_local_sources.select", local_source.filter(deleted_at.is_null()).load::<LocalSource>(&self.connection)?))
+  }
+
+  pub fn list_deleted_local_sources(&self) -> Result<Vec<LocalSource>, DatabaseQueryError> {
+    use schema::local_source::dsl::*;
+    Ok(time!("list_deleted_local_sources.select", local_source.filter(deleted_at.is_not_null()).load::<LocalSource>(&self.connection)?))
   }
 
   pub fn get_local_source_by_id(&self, local_source_id: i32) -> Result<Option<LocalSource>, DatabaseQueryError> {
@@ -19,15 +31,22 @@ impl DatabaseConnection {
     Ok(time!("get_local_source_by_id.select", query.first::<LocalSource>(&self.connection).optional()?))
   }
 
+  /// Creates a local source for the directory of `new_local_source`, or enables the existing local source with that
+  /// directory. If that local source was deleted, it is restored, reusing its preserved links. Directories are compared
+  /// by their normalized path key, such that a source is reused when only the case or separators of its directory
+  /// changed.
   pub fn create_or_enable_local_source(&self, new_local_source: &NewLocalSource) -> Result<LocalSource, DatabaseQueryError> {
-    let select_by_directory_query = {
+    let directory_key = path_key(&new_local_source.directory);
+    let db_local_sources: Vec<LocalSource> = {
       use schema::local_source::dsl::*;
-      local_source.filter(directory.eq(&new_local_source.directory))
+      time!("create_or_enable_local_source.select", local_source.load::<LocalSource>(&self.connection)?)
     };
-    let db_local_source: Option<LocalSource> = time!("create_or_enable_local_source.select", select_by_directory_query.first::<LocalSource>(&self.connection).optional()?);
+    let db_local_source = db_local_sources.into_iter().find(|local_source| path_key(&local_source.directory) == directory_key);
     Ok(if let Some(mut db_local_source) = db_local_source {
-      if !db_local_source.enabled {
+      if !db_local_source.enabled || db_local_source.deleted_at.is_some() || db_local_source.directory != new_local_source.directory {
         db_local_source.enabled = true;
+        db_local_source.deleted_at = None;
+        db_local_source.directory = new_local_source.directory.clone();
         time!("create_or_enable_local_source.update", db_local_source.save_changes::<LocalSource>(&*self.connection)?);
       }
       db_local_source
@@ -60,6 +79,20 @@ impl DatabaseConnection {
     }
   }
 
+  pub fn set_local_source_skip_if_unavailable_by_id(&self, local_source_id: i32, skip_if_unavailable: bool) -> Result<Option<LocalSource>, DatabaseQueryError> {
+    let local_source = {
+      use schema::local_source::dsl::*;
+      time!("set_local_source_skip_if_unavailable_by_id.select", local_source.find(local_source_id).first::<LocalSource>(&self.connection).optional()?)
+    };
+    if let Some(mut local_source) = local_source {
+      local_source.skip_if_unavailable = skip_if_unavailable;
+      time!("set_local_source_skip_if_unavailable_by_id.update", local_source.save_changes::<LocalSource>(&*self.connection)?);
+      Ok(Some(local_source))
+    } else {
+      Ok(None)
+    }
+  }
+
   pub fn enable_local_source_by_id(&self, local_source_id: i32) -> Result<Option<LocalSource>, DatabaseQueryError> {
     self.set_local_source_enabled_by_id(local_source_id, true)
   }
@@ -68,3 +101,103 @@ impl DatabaseConnection {
     self.set_local_source_enabled_by_id(local_source_id, false)
   }
 }
+
+// Soft-delete/restore
+
+impl DatabaseConnection {
+  /// Soft-deletes the local source with `local_source_id` by disabling it and setting its deletion time. Links to
+  /// albums, tracks, and artists are preserved, so that they can be reused when the source is restored. The removal
+  /// is logged in the undo log of the user with `input_user_id`.
+  pub fn delete_local_source_by_id(&self, local_source_id: i32, input_user_id: i32) -> Result<Option<LocalSource>, DatabaseQueryError> {
+    let local_source = {
+      use schema::local_source::dsl::*;
+      time!("delete_local_source_by_id.select", local_source.find(local_source_id).first::<LocalSource>(&self.connection).optional()?)
+    };
+    if let Some(mut local_source) = local_source {
+      if local_source.deleted_at.is_none() {
+        local_source.enabled = false;
+        local_source.deleted_at = Some(Utc::now().naive_utc());
+        time!("delete_local_source_by_id.update", local_source.save_changes::<LocalSource>(&*self.connection)?);
+        self.log_undo(input_user_id, UndoKind::LocalSourceRemoval, local_source_id, None, format!("Remove local source '{}'", local_source.directory))?;
+      }
+      Ok(Some(local_source))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Restores the soft-deleted local source with `local_source_id`, enabling it again.
+  pub fn restore_local_source_by_id(&self, local_source_id: i32) -> Result<Option<LocalSource>, DatabaseQueryError> {
+    let local_source = {
+      use schema::local_source::dsl::*;
+      time!("restore_local_source_by_id.select", local_source.find(local_source_id).first::<LocalSource>(&self.connection).optional()?)
+    };
+    if let Some(mut local_source) = local_source {
+      if local_source.deleted_at.is_some() {
+        local_source.enabled = true;
+        local_source.deleted_at = None;
+        time!("restore_local_source_by_id.update", local_source.save_changes::<LocalSource>(&*self.connection)?);
+      }
+      Ok(Some(local_source))
+    } else {
+      Ok(None)
+    }
+  }
+}
+
+// Relocation
+
+#[derive(Debug, Error)]
+pub enum RelocateLocalSourceError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Cannot relocate local source to directory '{0}', as it is already used by local source with ID '{1}'")]
+  DirectoryInUse(String, i32),
+}
+
+impl DatabaseConnection {
+  /// Relocates the local source with `local_source_id` to the new directory of `relocation`, returning the old and new
+  /// paths of all its tracks. Track file paths are stored relative to the directory of their source, so rewriting the
+  /// directory of the source relocates all its tracks at once. When `relocation.dry_run` is set, nothing is changed.
+  pub fn relocate_local_source_by_id(&self, local_source_id: i32, relocation: &LocalSourceRelocation) -> Result<Option<LocalSourceRelocationResult>, RelocateLocalSourceError> {
+    self.connection.transaction::<_, RelocateLocalSourceError, _>(|| {
+      let db_local_sources: Vec<LocalSource> = {
+        use schema::local_source::dsl::*;
+        time!("relocate_local_source_by_id.select_sources", local_source.load::<LocalSource>(&self.connection)?)
+      };
+      let new_directory_key = path_key(&relocation.new_directory);
+      if let Some(other) = db_local_sources.iter().find(|s| s.id != local_source_id && path_key(&s.directory) == new_directory_key) {
+        return Err(RelocateLocalSourceError::DirectoryInUse(relocation.new_directory.clone(), other.id));
+      }
+      let db_local_source = match db_local_sources.into_iter().find(|s| s.id == local_source_id) {
+        Some(db_local_source) => db_local_source,
+        None => return Ok(None),
+      };
+      let local_tracks: Vec<LocalTrack> = {
+        use schema::local_track::dsl::*;
+        time!("relocate_local_source_by_id.select_tracks", local_track.filter(local_source_id.eq(db_local_source.id)).filter(file_path.is_not_null()).load::<LocalTrack>(&self.connection)?)
+      };
+      let mut relocated_local_source = db_local_source.clone();
+      relocated_local_source.directory = relocation.new_directory.clone();
+      let tracks = local_tracks.iter().filter_map(|local_track| {
+        let old_path = db_local_source.track_file_path(local_track)?;
+        let new_path = relocated_local_source.track_file_path(local_track)?;
+        Some(RelocatedTrack {
+          track_id: local_track.track_id,
+          old_path: old_path.to_string_lossy().to_string(),
+          found: new_path.is_file(),
+          new_path: new_path.to_string_lossy().to_string(),
+        })
+      }).collect();
+      if !relocation.dry_run {
+        time!("relocate_local_source_by_id.update", relocated_local_source.save_changes::<LocalSource>(&*self.connection)?);
+      }
+      Ok(Some(LocalSourceRelocationResult {
+        local_source: relocated_local_source,
+        old_directory: db_local_source.directory,
+        dry_run: relocation.dry_run,
+        tracks,
+      }))
+    })
+  }
+}
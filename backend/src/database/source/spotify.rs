@@ -1,11 +1,12 @@
 use std::backtrace::Backtrace;
 
+use chrono::Utc;
 use diesel::prelude::*;
 use thiserror::Error;
 use tracing::{event, Level};
 
-use musium_core::api::SpotifyMeInfo;
-use musium_core::model::{NewSpotifySource, SpotifySource, User};
+use musium_core::api::{SpotifyMeInfo, SpotifyPlaylistAddResult};
+use musium_core::model::{NewSpotifySource, SpotifySource, UndoKind, User};
 use musium_core::schema;
 
 use crate::database::{DatabaseConnection, DatabaseQueryError};
@@ -14,7 +15,12 @@ use crate::model::SpotifySourceEx;
 impl DatabaseConnection {
   pub fn list_spotify_sources(&self) -> Result<Vec<SpotifySource>, DatabaseQueryError> {
     use schema::spotify_source::dsl::*;
-    Ok(time!("list_spotify_sources.select", spotify_source.load::<SpotifySource>(&self.connection)?))
+    Ok(time!("list_spotify_sources.select", spotify_source.filter(deleted_at.is_null()).load::<SpotifySource>(&self.connection)?))
+  }
+
+  pub fn list_deleted_spotify_sources(&self) -> Result<Vec<SpotifySource>, DatabaseQueryError> {
+    use schema::spotify_source::dsl::*;
+    Ok(time!("list_deleted_spotify_sources.select", spotify_source.filter(deleted_at.is_not_null()).load::<SpotifySource>(&self.connection)?))
   }
 
   pub fn get_spotify_source_by_id(&self, local_source_id: i32) -> Result<Option<SpotifySource>, DatabaseQueryError> {
@@ -46,10 +52,10 @@ impl DatabaseConnection {
     state: Option<String>,
   ) -> Result<String, CreateAuthorizationUrlError> {
     use CreateAuthorizationUrlError::*;
-    // First check if user already has a spotify source.
+    // First check if user already has a spotify source. A deleted spotify source is restored by the callback instead.
     let select_by_user_id_query = {
       use schema::spotify_source::dsl::*;
-      spotify_source.filter(user_id.eq(user.id))
+      spotify_source.filter(user_id.eq(user.id)).filter(deleted_at.is_null())
     };
     let db_spotify_source: Option<SpotifySource> = time!("create_spotify_authorization_url.select", select_by_user_id_query.first::<SpotifySource>(&self.connection).optional()?);
     if db_spotify_source.is_some() {
@@ -87,6 +93,22 @@ impl DatabaseConnection {
       expiry_date: authorization_info.expiry_date,
     };
     self.connection.transaction::<_, CreateError, _>(|| {
+      // Reuse the (possibly deleted) spotify source of the user, so that its preserved links are reused as well.
+      let select_by_user_id_query = {
+        use schema::spotify_source::dsl::*;
+        spotify_source.filter(user_id.eq(new_spotify_source.user_id))
+      };
+      let db_spotify_source = time!("create_spotify_authorization_url.select_existing", select_by_user_id_query.first::<SpotifySource>(&self.connection).optional()?);
+      if let Some(mut db_spotify_source) = db_spotify_source {
+        event!(Level::DEBUG, ?db_spotify_source, "Restoring and updating existing Spotify source");
+        db_spotify_source.enabled = true;
+        db_spotify_source.refresh_token = new_spotify_source.refresh_token;
+        db_spotify_source.access_token = new_spotify_source.access_token;
+        db_spotify_source.expiry_date = new_spotify_source.expiry_date;
+        db_spotify_source.deleted_at = None;
+        time!("create_spotify_authorization_url.update_existing", db_spotify_source.save_changes::<SpotifySource>(&*self.connection)?);
+        return Ok(db_spotify_source);
+      }
       event!(Level::DEBUG, ?new_spotify_source, "Inserting Spotify source");
       let insert_query = {
         use schema::spotify_source::dsl::*;
@@ -128,6 +150,49 @@ impl DatabaseConnection {
   }
 }
 
+// Soft-delete/restore
+
+impl DatabaseConnection {
+  /// Soft-deletes the spotify source with `spotify_source_id` by disabling it and setting its deletion time. Links to
+  /// albums, tracks, and artists are preserved, so that they can be reused when the source is restored. The removal
+  /// is logged in the undo log of the user with `input_user_id`.
+  pub fn delete_spotify_source_by_id(&self, spotify_source_id: i32, input_user_id: i32) -> Result<Option<SpotifySource>, DatabaseQueryError> {
+    let spotify_source = {
+      use schema::spotify_source::dsl::*;
+      time!("delete_spotify_source_by_id.select", spotify_source.find(spotify_source_id).first::<SpotifySource>(&self.connection).optional()?)
+    };
+    if let Some(mut spotify_source) = spotify_source {
+      if spotify_source.deleted_at.is_none() {
+        spotify_source.enabled = false;
+        spotify_source.deleted_at = Some(Utc::now().naive_utc());
+        time!("delete_spotify_source_by_id.update", spotify_source.save_changes::<SpotifySource>(&*self.connection)?);
+        self.log_undo(input_user_id, UndoKind::SpotifySourceRemoval, spotify_source_id, None, format!("Remove Spotify source with ID '{}'", spotify_source_id))?;
+      }
+      Ok(Some(spotify_source))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Restores the soft-deleted spotify source with `spotify_source_id`, enabling it again.
+  pub fn restore_spotify_source_by_id(&self, spotify_source_id: i32) -> Result<Option<SpotifySource>, DatabaseQueryError> {
+    let spotify_source = {
+      use schema::spotify_source::dsl::*;
+      time!("restore_spotify_source_by_id.select", spotify_source.find(spotify_source_id).first::<SpotifySource>(&self.connection).optional()?)
+    };
+    if let Some(mut spotify_source) = spotify_source {
+      if spotify_source.deleted_at.is_some() {
+        spotify_source.enabled = true;
+        spotify_source.deleted_at = None;
+        time!("restore_spotify_source_by_id.update", spotify_source.save_changes::<SpotifySource>(&*self.connection)?);
+      }
+      Ok(Some(spotify_source))
+    } else {
+      Ok(None)
+    }
+  }
+}
+
 // Me info
 
 #[derive(Debug, Error)]
@@ -163,3 +228,99 @@ impl DatabaseConnection {
     }
   }
 }
+
+// Write back
+
+#[derive(Debug, Error)]
+pub enum WriteBackError {
+  #[error("User {0:?} does not have a spotify source")]
+  NoSpotifySource(User),
+  #[error("Failed to execute Spotify API")]
+  SpotifyApiFail(#[from] musium_spotify_client::HttpRequestError, Backtrace),
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Saves the album with `input_album_id` to the Spotify library of `user`. Returns `false` if the album is not
+  /// available on Spotify.
+  pub async fn save_spotify_album(&self, input_album_id: i32, user: &User) -> Result<bool, WriteBackError> {
+    let spotify_ids = self.get_spotify_album_ids(input_album_id)?;
+    if spotify_ids.is_empty() { return Ok(false); }
+    let (mut spotify_source, mut authorization) = self.get_spotify_source_authorization(user)?;
+    self.inner.spotify_sync.save_album(spotify_ids, &mut authorization).await?;
+    self.update_spotify_source_authorization(&mut spotify_source, authorization)?;
+    Ok(true)
+  }
+
+  /// Removes the album with `input_album_id` from the Spotify library of `user`. Returns `false` if the album is not
+  /// available on Spotify.
+  pub async fn remove_saved_spotify_album(&self, input_album_id: i32, user: &User) -> Result<bool, WriteBackError> {
+    let spotify_ids = self.get_spotify_album_ids(input_album_id)?;
+    if spotify_ids.is_empty() { return Ok(false); }
+    let (mut spotify_source, mut authorization) = self.get_spotify_source_authorization(user)?;
+    self.inner.spotify_sync.remove_saved_album(spotify_ids, &mut authorization).await?;
+    self.update_spotify_source_authorization(&mut spotify_source, authorization)?;
+    Ok(true)
+  }
+
+  /// Follows the artist with `input_artist_id` on Spotify for `user`. Returns `false` if the artist is not available on
+  /// Spotify.
+  pub async fn follow_spotify_artist(&self, input_artist_id: i32, user: &User) -> Result<bool, WriteBackError> {
+    let spotify_ids: Vec<String> = {
+      use schema::spotify_artist::dsl::*;
+      let query = spotify_artist.filter(artist_id.eq(input_artist_id)).select(spotify_id);
+      time!("follow_spotify_artist.select_spotify_ids", query.load::<String>(&self.connection)?)
+    };
+    if spotify_ids.is_empty() { return Ok(false); }
+    let (mut spotify_source, mut authorization) = self.get_spotify_source_authorization(user)?;
+    self.inner.spotify_sync.follow_artist(spotify_ids, &mut authorization).await?;
+    self.update_spotify_source_authorization(&mut spotify_source, authorization)?;
+    Ok(true)
+  }
+
+  /// Appends the tracks with `input_track_ids` to the Spotify playlist with `spotify_playlist_id` of `user`, in order,
+  /// skipping tracks that are not available on Spotify.
+  pub async fn add_tracks_to_spotify_playlist(&self, spotify_playlist_id: &String, input_track_ids: &[i32], user: &User) -> Result<SpotifyPlaylistAddResult, WriteBackError> {
+    let mut spotify_ids = Vec::new();
+    let mut skipped_track_ids = Vec::new();
+    for &input_track_id in input_track_ids {
+      use schema::spotify_track::dsl::*;
+      let query = spotify_track.filter(track_id.eq(input_track_id)).select(spotify_id);
+      match time!("add_tracks_to_spotify_playlist.select_spotify_id", query.first::<String>(&self.connection).optional()?) {
+        Some(spotify_track_id) => spotify_ids.push(spotify_track_id),
+        None => skipped_track_ids.push(input_track_id),
+      }
+    }
+    let added = spotify_ids.len();
+    if added > 0 {
+      let (mut spotify_source, mut authorization) = self.get_spotify_source_authorization(user)?;
+      self.inner.spotify_sync.add_tracks_to_playlist(spotify_playlist_id, spotify_ids, &mut authorization).await?;
+      self.update_spotify_source_authorization(&mut spotify_source, authorization)?;
+    }
+    Ok(SpotifyPlaylistAddResult { added, skipped_track_ids })
+  }
+
+  fn get_spotify_album_ids(&self, input_album_id: i32) -> Result<Vec<String>, diesel::result::Error> {
+    use schema::spotify_album::dsl::*;
+    let query = spotify_album.filter(album_id.eq(input_album_id)).select(spotify_id);
+    Ok(time!("get_spotify_album_ids.select", query.load::<String>(&self.connection)?))
+  }
+
+  fn get_spotify_source_authorization(&self, user: &User) -> Result<(SpotifySource, musium_spotify_client::Authorization), WriteBackError> {
+    use schema::spotify_source::dsl::*;
+    let query = spotify_source.filter(user_id.eq(user.id));
+    let db_spotify_source = time!("get_spotify_source_authorization.select", query.first::<SpotifySource>(&self.connection).optional()?);
+    let db_spotify_source = db_spotify_source.ok_or_else(|| WriteBackError::NoSpotifySource(user.clone()))?;
+    let authorization = db_spotify_source.to_spotify_authorization();
+    Ok((db_spotify_source, authorization))
+  }
+
+  fn update_spotify_source_authorization(&self, spotify_source: &mut SpotifySource, authorization: musium_spotify_client::Authorization) -> Result<(), diesel::result::Error> {
+    if spotify_source.update_from_spotify_authorization(authorization) {
+      event!(Level::DEBUG, ?spotify_source, "Spotify source has changed, updating the database");
+      spotify_source.save_changes::<SpotifySource>(&*self.connection)?;
+    }
+    Ok(())
+  }
+}
@@ -2,13 +2,17 @@
 
 use std::backtrace::Backtrace;
 use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::sync::Arc;
 
+use chrono::Utc;
 use diesel::prelude::*;
 use itertools::Itertools;
 use thiserror::Error;
 use tracing::{event, instrument, Level};
 
-use musium_core::model::{Album, AlbumArtist, Artist, NewAlbum, NewAlbumArtist, NewArtist, NewTrack, NewTrackArtist, Track, TrackArtist};
+use musium_core::collation::{name_collation_key, normalize_name};
+use musium_core::model::{Album, AlbumArtist, Artist, NewAlbum, NewAlbumArtist, NewArtist, NewTrack, NewTrackArtist, Track, TrackArtist, TrackArtistRole};
 use musium_core::schema;
 use musium_filesystem_sync::FilesystemSyncError;
 
@@ -17,6 +21,8 @@ use crate::database::sync::local::LocalSyncError;
 use crate::database::sync::spotify::SpotifySyncError;
 
 pub mod local;
+pub mod merge;
+pub mod preview;
 pub mod spotify;
 
 // All sources sync
@@ -33,13 +39,14 @@ pub enum SyncAllSourcesError {
 
 impl DatabaseConnection {
   /// Synchronize with all sources, adding/removing/changing tracks/albums/artists in the database. When a LocalSyncFail
-  /// error is returned, the database has already received a partial update.
-  #[instrument(skip(self))]
-  pub fn sync_all_sources(&self) -> Result<(), SyncAllSourcesError> {
+  /// error is returned, the database has already received a partial update. Returns the IDs of local sources that were
+  /// skipped because their directory is unavailable.
+  #[instrument(skip(self, progress))]
+  pub fn sync_all_sources(&self, progress: &SyncProgress) -> Result<Vec<i32>, SyncAllSourcesError> {
     self.connection.transaction::<_, SyncAllSourcesError, _>(|| {
-      self.sync_local_sources()?;
-      self.sync_spotify_sources()?;
-      Ok(())
+      let skipped_local_source_ids = self.sync_local_sources(&progress.part(0, 2))?;
+      self.sync_spotify_sources(&progress.part(1, 2))?;
+      Ok(skipped_local_source_ids)
     })
   }
 }
@@ -59,30 +66,39 @@ pub enum SyncLocalSourcesError {
 }
 
 impl DatabaseConnection {
-  #[instrument(skip(self))]
-  pub fn sync_local_sources(&self) -> Result<(), SyncLocalSourcesError> {
+  /// Synchronize with all local sources. Returns the IDs of local sources that were skipped because their directory is
+  /// unavailable.
+  #[instrument(skip(self, progress))]
+  pub fn sync_local_sources(&self, progress: &SyncProgress) -> Result<Vec<i32>, SyncLocalSourcesError> {
     use SyncLocalSourcesError::*;
     self.connection.transaction::<_, SyncLocalSourcesError, _>(|| {
+      self.update_missing_collation_keys()?;
       let local_sources = self.list_local_sources()?;
-      let local_sync_errors = self.local_sync(local_sources)?;
+      let (local_sources, skipped_local_source_ids) = Self::partition_reachable_local_sources(local_sources);
+      let local_sync_errors = self.local_sync(local_sources, progress)?;
       if !local_sync_errors.is_empty() {
         Err(SyncNonFatalFail(local_sync_errors))
       } else {
-        Ok(())
+        Ok(skipped_local_source_ids)
       }
     })
   }
 
-  #[instrument(skip(self))]
-  pub fn sync_local_source(&self, local_source_id: i32) -> Result<(), SyncLocalSourcesError> {
+  /// Synchronize with the local source with `local_source_id`. Returns a vector with the ID of the local source if it was
+  /// skipped because its directory is unavailable.
+  #[instrument(skip(self, progress))]
+  pub fn sync_local_source(&self, local_source_id: i32, progress: &SyncProgress) -> Result<Vec<i32>, SyncLocalSourcesError> {
     use SyncLocalSourcesError::*;
     self.connection.transaction::<_, SyncLocalSourcesError, _>(|| {
-      let local_source = self.get_local_source_by_id(local_source_id)?;
-      let local_sync_errors = self.local_sync(local_source.into_iter().collect_vec())?;
+      self.update_missing_collation_keys()?;
+      // Deleted local sources are not synchronized, so that their links are preserved.
+      let local_source = self.get_local_source_by_id(local_source_id)?.filter(|local_source| local_source.deleted_at.is_none());
+      let (local_sources, skipped_local_source_ids) = Self::partition_reachable_local_sources(local_source.into_iter().collect_vec());
+      let local_sync_errors = self.local_sync(local_sources, progress)?;
       if !local_sync_errors.is_empty() {
         Err(SyncNonFatalFail(local_sync_errors))
       } else {
-        Ok(())
+        Ok(skipped_local_source_ids)
       }
     })
   }
@@ -101,35 +117,126 @@ pub enum SyncSpotifySourcesError {
 }
 
 impl DatabaseConnection {
-  #[instrument(skip(self))]
-  pub fn sync_spotify_sources(&self) -> Result<(), SyncSpotifySourcesError> {
+  #[instrument(skip(self, progress))]
+  pub fn sync_spotify_sources(&self, progress: &SyncProgress) -> Result<(), SyncSpotifySourcesError> {
     self.connection.transaction::<_, SyncSpotifySourcesError, _>(|| {
+      self.update_missing_collation_keys()?;
       let spotify_sources = self.list_spotify_sources()?;
       let runtime = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .unwrap();
-      runtime.block_on(self.spotify_sync(spotify_sources))?;
+      runtime.block_on(self.spotify_sync(spotify_sources, progress))?;
       Ok(())
     })
   }
 
-  #[instrument(skip(self))]
-  pub fn sync_spotify_source(&self, spotify_source_id: i32) -> Result<(), SyncSpotifySourcesError> {
+  #[instrument(skip(self, progress))]
+  pub fn sync_spotify_source(&self, spotify_source_id: i32, progress: &SyncProgress) -> Result<(), SyncSpotifySourcesError> {
     self.connection.transaction::<_, SyncSpotifySourcesError, _>(|| {
-      let spotify_source = self.get_spotify_source_by_id(spotify_source_id)?;
+      self.update_missing_collation_keys()?;
+      // Deleted Spotify sources are not synchronized, so that their links are preserved.
+      let spotify_source = self.get_spotify_source_by_id(spotify_source_id)?.filter(|spotify_source| spotify_source.deleted_at.is_none());
       let runtime = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .unwrap();
-      runtime.block_on(self.spotify_sync(spotify_source.into_iter().collect_vec()))?;
+      runtime.block_on(self.spotify_sync(spotify_source.into_iter().collect_vec(), progress))?;
       Ok(())
     })
   }
 }
 
+// Collation keys
+
+impl DatabaseConnection {
+  /// Computes the empty collation keys of albums, tracks, and artists. The migration that added the keys leaves them
+  /// empty for names with non-ASCII characters, as SQLite cannot remove their accents. Synchronization compares names by
+  /// their keys, so these are computed before synchronizing. Returns the number of computed keys.
+  pub(crate) fn update_missing_collation_keys(&self) -> Result<usize, diesel::result::Error> {
+    let mut updated = 0;
+    {
+      use schema::album::dsl::*;
+      let albums: Vec<(i32, String)> = time!("update_missing_collation_keys.select_albums", album.filter(name_key.eq("")).select((id, name)).load(&self.connection)?);
+      for (album_id, album_name) in albums {
+        let key = name_collation_key(&album_name);
+        if key.is_empty() { continue; }
+        updated += time!("update_missing_collation_keys.update_album", diesel::update(album.find(album_id)).set(name_key.eq(key)).execute(&self.connection)?);
+      }
+    }
+    {
+      use schema::track::dsl::*;
+      let tracks: Vec<(i32, String)> = time!("update_missing_collation_keys.select_tracks", track.filter(title_key.eq("")).select((id, title)).load(&self.connection)?);
+      for (track_id, track_title) in tracks {
+        let key = name_collation_key(&track_title);
+        if key.is_empty() { continue; }
+        updated += time!("update_missing_collation_keys.update_track", diesel::update(track.find(track_id)).set(title_key.eq(key)).execute(&self.connection)?);
+      }
+    }
+    {
+      use schema::artist::dsl::*;
+      let artists: Vec<(i32, String)> = time!("update_missing_collation_keys.select_artists", artist.filter(name_key.eq("")).select((id, name)).load(&self.connection)?);
+      for (artist_id, artist_name) in artists {
+        let key = name_collation_key(&artist_name);
+        if key.is_empty() { continue; }
+        updated += time!("update_missing_collation_keys.update_artist", diesel::update(artist.find(artist_id)).set(name_key.eq(key)).execute(&self.connection)?);
+      }
+    }
+    if updated > 0 {
+      event!(Level::INFO, updated, "Computed missing collation keys");
+    }
+    Ok(updated)
+  }
+}
+
 // Shared sync API for specific sync implementations
 
+/// Reports the progress of a sync as a fraction between 0 and 1. Each part of a sync, such as a single source, reports
+/// its own progress from 0 to 1 through a `part` of the progress of the whole sync.
+#[derive(Clone)]
+pub struct SyncProgress {
+  report: Option<Arc<dyn Fn(f32) + Send + Sync>>,
+  start: f32,
+  length: f32,
+}
+
+impl SyncProgress {
+  /// Creates a progress that reports to `report`.
+  pub fn new(report: impl 'static + Fn(f32) + Send + Sync) -> Self {
+    Self { report: Some(Arc::new(report)), start: 0.0, length: 1.0 }
+  }
+
+  /// Creates a progress that is not reported, for syncs of which nobody observes the progress.
+  pub fn ignored() -> Self {
+    Self { report: None, start: 0.0, length: 1.0 }
+  }
+
+  /// Reports that this part is done for `fraction`, between 0 and 1.
+  pub fn report(&self, fraction: f32) {
+    if let Some(report) = &self.report {
+      report(self.start + fraction.clamp(0.0, 1.0) * self.length);
+    }
+  }
+
+  /// Gets the progress of part `index` of `count` equally sized consecutive parts of this progress.
+  pub fn part(&self, index: usize, count: usize) -> Self {
+    let length = self.length / count.max(1) as f32;
+    Self { report: self.report.clone(), start: self.start + index as f32 * length, length }
+  }
+}
+
+/// Formats `error` and its chain of sources on a single line, for storing it in the database.
+pub(crate) fn error_chain_message(error: &dyn StdError) -> String {
+  let mut message = error.to_string();
+  let mut source = error.source();
+  while let Some(cause) = source {
+    message.push_str(": ");
+    message.push_str(&cause.to_string());
+    source = cause.source();
+  }
+  message
+}
+
 pub enum SelectOrInsert<T> {
   Selected(Vec<T>),
   Inserted(T),
@@ -169,7 +276,7 @@ impl DatabaseConnection {
   pub(crate) fn select_albums_by_name(&self, input_name: &String) -> Result<Vec<Album>, diesel::result::Error> {
     use schema::album::dsl::*;
     let db_albums: Vec<Album> = album
-      .filter(name.eq(input_name))
+      .filter(name_key.eq(name_collation_key(input_name)))
       .order(id.desc())
       .load(&self.connection)?;
     Ok(db_albums)
@@ -186,7 +293,12 @@ impl DatabaseConnection {
 
   pub(crate) fn insert_album(&self, input_name: &String) -> Result<Album, diesel::result::Error> {
     use schema::album::dsl::*;
-    let new_album = NewAlbum { name: input_name.clone() };
+    let input_name = normalize_name(input_name);
+    let new_album = NewAlbum {
+      name_key: name_collation_key(&input_name),
+      sort_name: self.compute_sort_name(&input_name)?,
+      name: input_name,
+    };
     event!(Level::DEBUG, ?new_album, "Inserting album");
     time!("insert_album.insert", diesel::insert_into(album).values(new_album).execute(&self.connection)?);
     // NOTE: must be executed in a transaction for consistency
@@ -210,6 +322,55 @@ impl DatabaseConnection {
     };
     Ok(result)
   }
+
+  pub(crate) fn select_album_artist_name_keys(&self, input_album_id: i32) -> Result<Vec<String>, diesel::result::Error> {
+    use schema::{album_artist, artist};
+    let name_keys: Vec<String> = time!("select_album_artist_name_keys.select", album_artist::table
+      .inner_join(artist::table)
+      .filter(album_artist::album_id.eq(input_album_id))
+      .select(artist::name_key)
+      .load(&self.connection)?);
+    Ok(name_keys.into_iter().sorted().collect_vec())
+  }
+
+  /// Selects the album with `input_name` and album artists `album_artist_names`, or inserts a new album if no such album
+  /// exists. Albums are matched by name and album artists, so that different albums with the same name, such as
+  /// "Greatest Hits" of different artists, are not merged into one. When no album matches exactly, falls back to:
+  ///
+  /// - if `album_artist_names` is empty because the album artists are unknown: the album with the same name whose album
+  ///   artists overlap the most with `fallback_artist_names`, such as the artists of a track on the album;
+  /// - otherwise: an album with the same name that has no album artists yet, as its album artists are set afterwards.
+  pub(crate) fn select_or_insert_album_by_artists(
+    &self,
+    input_name: &String,
+    album_artist_names: &[String],
+    fallback_artist_names: &[String],
+  ) -> Result<SelectOrInsertOne<Album>, diesel::result::Error> {
+    let album_artist_name_keys = album_artist_names.iter().map(|name| name_collation_key(name)).sorted().dedup().collect_vec();
+    let fallback_artist_name_keys = fallback_artist_names.iter().map(|name| name_collation_key(name)).collect_vec();
+    let mut candidates = Vec::new();
+    for db_album in self.select_albums_by_name(input_name)? {
+      let db_album_artist_name_keys = self.select_album_artist_name_keys(db_album.id)?;
+      if db_album_artist_name_keys == album_artist_name_keys {
+        return Ok(SelectOrInsertOne::Selected(db_album));
+      }
+      candidates.push((db_album, db_album_artist_name_keys));
+    }
+    let fallback = if album_artist_name_keys.is_empty() {
+      // Albums are ordered by descending ID, and `max_by_key` returns the last maximum, so the oldest album wins ties.
+      candidates.into_iter().max_by_key(|(_, name_keys)| name_keys.iter().filter(|name_key| fallback_artist_name_keys.contains(name_key)).count())
+    } else {
+      candidates.into_iter().find(|(_, name_keys)| name_keys.is_empty())
+    };
+    let result = match fallback {
+      Some((db_album, _)) => {
+        event!(Level::DEBUG, ?db_album, ?album_artist_names, "No album with the same name and album artists was found: falling back to album with the same name");
+        SelectOrInsertOne::Selected(db_album)
+      }
+      None => SelectOrInsertOne::Inserted(self.insert_album(input_name)?),
+    };
+    Ok(result)
+  }
 }
 
 // Track
@@ -239,7 +400,7 @@ impl DatabaseConnection {
       use schema::track::dsl::*;
       let mut query = track
         .filter(album_id.eq(input_album_id))
-        .filter(title.eq(input_title))
+        .filter(title_key.eq(name_collation_key(input_title)))
         .into_boxed();
       if let Some(input_disc_number) = input_disc_number {
         query = query.filter(disc_number.eq(input_disc_number))
@@ -259,8 +420,9 @@ impl DatabaseConnection {
 
   pub(crate) fn insert_track(&self, new_track: NewTrack) -> Result<Track, diesel::result::Error> {
     use schema::track::dsl::*;
+    let new_track = NewTrack { title: normalize_name(&new_track.title), title_key: name_collation_key(&new_track.title), ..new_track };
     event!(Level::DEBUG, ?new_track, "Inserting track");
-    time!("insert_track.insert", diesel::insert_into(track).values(new_track).execute(&self.connection)?);
+    time!("insert_track.insert", diesel::insert_into(track).values((new_track, added_at.eq(Utc::now().naive_utc()))).execute(&self.connection)?);
     // NOTE: must be executed in a transaction for consistency
     Ok(time!("insert_track.select_inserted", track.order(id.desc()).first(&self.connection)?))
   }
@@ -318,7 +480,7 @@ impl DatabaseConnection {
   pub(crate) fn select_artists_by_name(&self, input_name: &String) -> Result<Vec<Artist>, diesel::result::Error> {
     use schema::artist::dsl::*;
     let db_artists: Vec<Artist> = artist
-      .filter(name.eq(input_name))
+      .filter(name_key.eq(name_collation_key(input_name)))
       .order(id.desc())
       .load(&self.connection)?;
     Ok(db_artists)
@@ -335,7 +497,12 @@ impl DatabaseConnection {
 
   pub(crate) fn insert_artist(&self, input_name: &String) -> Result<Artist, diesel::result::Error> {
     use schema::artist::dsl::*;
-    let new_artist = NewArtist { name: input_name.clone() };
+    let input_name = normalize_name(input_name);
+    let new_artist = NewArtist {
+      name_key: name_collation_key(&input_name),
+      sort_name: self.compute_sort_name(&input_name)?,
+      name: input_name,
+    };
     event!(Level::DEBUG, ?new_artist, "Inserting artist");
     time!("insert_artist.insert", diesel::insert_into(artist).values(new_artist).execute(&self.connection)?);
     // NOTE: must be executed in a transaction for consistency
@@ -364,7 +531,7 @@ impl DatabaseConnection {
 // Album and track artist associations.
 
 impl DatabaseConnection {
-  fn sync_album_artists(&self, album: &Album, mut artist_ids: HashSet<i32>) -> Result<(), diesel::result::Error> {
+  pub(crate) fn sync_album_artists(&self, album: &Album, mut artist_ids: HashSet<i32>) -> Result<(), diesel::result::Error> {
     let select_query = {
       use schema::album_artist::dsl::*;
       album_artist
@@ -398,11 +565,14 @@ impl DatabaseConnection {
     Ok(())
   }
 
-  fn sync_track_artists(&self, track: &Track, mut artist_ids: HashSet<i32>) -> Result<(), diesel::result::Error> {
+  /// Sets the artists credited in `input_role` on `track` to the artists with `artist_ids`, leaving artists credited in
+  /// other roles alone.
+  pub(crate) fn sync_track_artists(&self, track: &Track, input_role: TrackArtistRole, mut artist_ids: HashSet<i32>) -> Result<(), diesel::result::Error> {
     let select_query = {
       use schema::track_artist::dsl::*;
       track_artist
         .filter(track_id.eq(track.id))
+        .filter(role.eq(input_role))
         .order(artist_id.desc())
     };
     let db_track_artists: Vec<TrackArtist> = time!("sync_track_artists.select", select_query.load(&self.connection)?);
@@ -421,7 +591,7 @@ impl DatabaseConnection {
       artist_ids.remove(&artist_id);
     }
     for artist_id in artist_ids {
-      let new_track_artist = NewTrackArtist { track_id: track.id, artist_id };
+      let new_track_artist = NewTrackArtist { track_id: track.id, artist_id, role: input_role };
       event!(Level::DEBUG, ?new_track_artist, "Inserting track-artist");
       let insert_query = {
         use schema::track_artist::dsl::*;
@@ -2,17 +2,20 @@ use std::backtrace::Backtrace;
 use std::collections::{HashMap, HashSet};
 
 use diesel::prelude::*;
-use itertools::{Either, Itertools};
+use itertools::Itertools;
 use thiserror::Error;
 use tracing::{event, instrument, Level};
 
-use musium_core::model::{Album, Artist, LocalAlbum, LocalArtist, LocalSource, LocalTrack, NewLocalAlbum, NewLocalArtist, NewLocalTrack, NewTrack, Track};
+use musium_core::model::{Album, Artist, LocalAlbum, LocalArtist, LocalSource, LocalTrack, NewLocalAlbum, NewLocalArtist, NewLocalTrack, NewTrack, Track, TrackArtistRole};
 use musium_core::schema;
-use musium_filesystem_sync::{FilesystemSyncError, FilesystemSyncTrack};
+use musium_filesystem_sync::{FilesystemSyncError, FilesystemSyncTrack, path_key};
 
+use crate::beets::{BeetsLibrary, ReadBeetsLibraryError};
+use crate::credits::split_track_credits;
 use crate::database::DatabaseConnection;
-use crate::database::sync::{SelectAlbumError, SelectArtistError};
-use crate::model::{LocalTrackEx, TrackEx, UpdateTrackFrom};
+use crate::database::sync::{SelectAlbumError, SelectArtistError, SyncProgress};
+use crate::database::sync::merge::MetadataSource;
+use crate::model::{LocalTrackEx, TrackEx};
 
 #[derive(Debug, Error)]
 pub enum LocalSyncError {
@@ -22,59 +25,209 @@ pub enum LocalSyncError {
   SelectAlbumFail(#[from] SelectAlbumError, Backtrace),
   #[error("Failed to select an artist")]
   SelectArtistFail(#[from] SelectArtistError, Backtrace),
+  #[error("Failed to read beets library")]
+  ReadBeetsLibraryFail(#[from] ReadBeetsLibraryError, Backtrace),
   #[error("Attempted to update possibly moved locally synchronized track {0:#?}, but found multiple local tracks in the database with the same source and hash: {1:#?}")]
   HashCollisionFail(FilesystemSyncTrack, Vec<LocalTrack>),
 }
 
 impl DatabaseConnection {
-  #[instrument(skip(self, local_sources))]
-  pub(crate) fn local_sync(&self, local_sources: Vec<LocalSource>) -> Result<Vec<FilesystemSyncError>, LocalSyncError> {
-    let (filesystem_sync_tracks, filesystem_sync_errors) = self.get_filesystem_sync_tracks(local_sources)?;
-    let mut synced_file_paths = HashMap::<i32, HashSet<String>>::new();
+  /// Synchronizes `local_sources`, reporting the progress of reading their files as the first half of `progress`, and of
+  /// synchronizing the read tracks with the database as the second half.
+  #[instrument(skip(self, local_sources, progress))]
+  pub(crate) fn local_sync(&self, local_sources: Vec<LocalSource>, progress: &SyncProgress) -> Result<Vec<FilesystemSyncError>, LocalSyncError> {
+    self.update_missing_file_path_keys()?;
+    let directories: HashMap<i32, String> = local_sources.iter().map(|local_source| (local_source.id, local_source.directory.clone())).collect();
+    let (filesystem_sync_tracks, filesystem_sync_errors) = self.get_filesystem_sync_tracks(local_sources, &progress.part(0, 2))?;
+    self.sync_local_sync_tracks(&directories, filesystem_sync_tracks, &progress.part(1, 2))?;
+    Ok(filesystem_sync_errors)
+  }
+
+  /// Computes the missing file path keys of local tracks that have a file path. The migration that added the keys leaves
+  /// them missing for paths of which the key depends on the platform. Synchronization compares paths by their keys, so
+  /// these are computed before synchronizing. Returns the number of computed keys.
+  pub(crate) fn update_missing_file_path_keys(&self) -> Result<usize, diesel::result::Error> {
+    use schema::local_track::dsl::*;
+    let db_local_tracks: Vec<(i32, i32, Option<String>)> = time!("update_missing_file_path_keys.select", local_track
+      .select((track_id, local_source_id, file_path))
+      .filter(file_path.is_not_null())
+      .filter(file_path_key.is_null())
+      .load(&self.connection)?);
+    let mut updated = 0;
+    for (db_track_id, db_local_source_id, db_file_path) in db_local_tracks {
+      let db_file_path = match db_file_path {
+        Some(db_file_path) => db_file_path,
+        None => continue,
+      };
+      let update_query = diesel::update(local_track)
+        .filter(track_id.eq(db_track_id))
+        .filter(local_source_id.eq(db_local_source_id))
+        .set(file_path_key.eq(path_key(&db_file_path)));
+      updated += time!("update_missing_file_path_keys.update", update_query.execute(&self.connection)?);
+    }
+    if updated > 0 {
+      event!(Level::INFO, updated, "Computed missing file path keys of local tracks");
+    }
+    Ok(updated)
+  }
+
+  /// Synchronizes `filesystem_sync_tracks` read from the local sources with the IDs and directories in `directories`,
+  /// and cleans up the tracks and albums of those sources that were not seen. Reports progress after each track.
+  fn sync_local_sync_tracks(&self, directories: &HashMap<i32, String>, filesystem_sync_tracks: Vec<(i32, FilesystemSyncTrack)>, progress: &SyncProgress) -> Result<(), LocalSyncError> {
+    // Track synced file path keys of all synchronized sources, even of those without any files, so that tracks of sources
+    // that are synchronized while their directory is unavailable are set as removed.
+    let mut synced_file_path_keys: HashMap<i32, HashSet<String>> = directories.keys().map(|local_source_id| (*local_source_id, HashSet::new())).collect();
+    // Track which albums have tracks with and without album artists, to clear album artists that were removed from tags.
+    let mut album_ids_with_album_artists = HashSet::new();
+    let mut album_ids_without_album_artists = HashSet::new();
+    let beets_library = self.read_beets_library()?;
     // Insert tracks and related entities.
-    for (local_source_id, local_sync_track) in filesystem_sync_tracks {
+    let track_count = filesystem_sync_tracks.len();
+    for (index, (local_source_id, mut local_sync_track)) in filesystem_sync_tracks.into_iter().enumerate() {
+      if let (Some(beets_library), Some(directory)) = (&beets_library, directories.get(&local_source_id)) {
+        beets_library.apply(directory, &mut local_sync_track);
+      }
       event!(Level::TRACE, ?local_sync_track, "Processing local sync track");
-      synced_file_paths.entry(local_source_id)
+      synced_file_path_keys.entry(local_source_id)
         .or_default()
-        .insert(local_sync_track.file_path.clone());
+        .insert(local_sync_track.file_path_key.clone());
+      let has_album_artists = local_sync_track.album_artists.iter().any(|name| !name.trim().is_empty());
+      let track = self.sync_local_sync_track(local_source_id, &local_sync_track)?;
+      if has_album_artists {
+        album_ids_with_album_artists.insert(track.album_id);
+      } else {
+        album_ids_without_album_artists.insert(track.album_id);
+      }
+      progress.report((index + 1) as f32 / track_count as f32);
+    }
+    let synced_local_source_ids: HashSet<i32> = directories.keys().copied().collect();
+    let album_ids_without_album_artists = album_ids_without_album_artists.difference(&album_ids_with_album_artists).copied().collect();
+    self.clear_removed_album_artists(album_ids_without_album_artists, &synced_local_source_ids)?;
+    self.cleanup_local_tracks(synced_file_path_keys)?;
+    self.cleanup_local_albums()?;
+    self.cleanup_orphaned_albums()?;
+    Ok(())
+  }
 
-      let album = self.sync_local_album(local_source_id, &local_sync_track)?;
-      let artist_ids: Result<HashSet<_>, _> = local_sync_track.album_artists.iter()
-        .map(|album_artist_name| self.sync_local_artist(local_source_id, album_artist_name.clone()).map(|artist| artist.id))
-        .collect();
-      let artist_ids = artist_ids?;
+  /// Synchronizes the album, track, and artists of a single locally synchronized track of the local source with
+  /// `local_source_id`, returning the synchronized track. Does not clean up tracks and albums that were not seen.
+  pub(crate) fn sync_local_sync_track(&self, local_source_id: i32, local_sync_track: &FilesystemSyncTrack) -> Result<Track, LocalSyncError> {
+    // Split off featured artists and remixers first, so that they are not synchronized as main artists.
+    let mut local_sync_track = local_sync_track.clone();
+    split_track_credits(&mut local_sync_track);
+    let local_sync_track = &local_sync_track;
+    let album = self.sync_local_album(local_source_id, local_sync_track)?;
+    let artist_ids: Result<HashSet<_>, _> = local_sync_track.album_artists.iter()
+      .map(|album_artist_name| self.sync_local_artist(local_source_id, album_artist_name.clone()).map(|artist| artist.id))
+      .collect();
+    let artist_ids = artist_ids?;
+    // Keep the album artists when they are unknown, as the album was selected by a fallback heuristic in that case, and
+    // other tracks of the album may still have album artists. Album artists that were removed from the tags of all tracks
+    // of an album are cleared in `clear_removed_album_artists` after all tracks are synchronized.
+    if !artist_ids.is_empty() {
       self.sync_album_artists(&album, artist_ids)?;
+    }
 
-      let track = self.sync_local_track(local_source_id, &album, &local_sync_track)?;
-      let artist_ids: Result<HashSet<_>, _> = local_sync_track.track_artists.iter()
-        .map(|track_artist_name| self.sync_local_artist(local_source_id, track_artist_name.clone()).map(|artist| artist.id))
-        .collect();
-      let artist_ids = artist_ids?;
-      self.sync_track_artists(&track, artist_ids)?;
+    let track = self.sync_local_track(local_source_id, &album, local_sync_track)?;
+    let artist_ids: Result<HashSet<_>, _> = local_sync_track.track_artists.iter()
+      .map(|track_artist_name| self.sync_local_artist(local_source_id, track_artist_name.clone()).map(|artist| artist.id))
+      .collect();
+    let artist_ids = artist_ids?;
+    if self.merge_track_artists(&track, MetadataSource::Local, &artist_ids)? {
+      self.sync_track_artists(&track, TrackArtistRole::Main, artist_ids)?;
     }
-    self.cleanup_local_tracks(synced_file_paths)?;
-    Ok(filesystem_sync_errors)
+    let composer_ids = self.sync_local_track_credits(local_source_id, &track, local_sync_track)?;
+    // The first composer is the composer of the work, as further composers are usually arrangers or lyricists.
+    self.sync_local_work(track, local_sync_track, composer_ids.first().copied())
   }
 
-  fn get_filesystem_sync_tracks(&self, local_sources: Vec<LocalSource>) -> Result<(Vec<(i32, FilesystemSyncTrack)>, Vec<FilesystemSyncError>), LocalSyncError> {
-    let do_local_sync = {
-      || local_sources
-        .into_iter()
-        .flat_map(|local_source|
-          musium_filesystem_sync::sync(local_source.directory.clone()).map(move |track| track.map(|track| (local_source.id, track)))
-        )
-        .partition_map(|r| {
-          match r {
-            Ok(v) => Either::Left(v),
-            Err(v) => Either::Right(v)
+  /// Synchronizes the artists credited in other roles than the main artist on `track` from `local_sync_track`, returning
+  /// the IDs of the composers in the order they are credited. Tags and titles are the only source of composers,
+  /// conductors, and performers, so credits are set directly instead of merged.
+  fn sync_local_track_credits(&self, local_source_id: i32, track: &Track, local_sync_track: &FilesystemSyncTrack) -> Result<Vec<i32>, LocalSyncError> {
+    let mut composer_ids = Vec::new();
+    for (role, artist_names) in [
+      (TrackArtistRole::Featured, &local_sync_track.featured_artists),
+      (TrackArtistRole::Remixer, &local_sync_track.remixers),
+      (TrackArtistRole::Composer, &local_sync_track.composers),
+      (TrackArtistRole::Conductor, &local_sync_track.conductors),
+      (TrackArtistRole::Performer, &local_sync_track.performers),
+    ] {
+      let mut artist_ids = Vec::new();
+      for artist_name in artist_names {
+        artist_ids.push(self.sync_local_artist(local_source_id, artist_name.clone())?.id);
+      }
+      self.sync_track_artists(track, role, artist_ids.iter().copied().collect())?;
+      if role == TrackArtistRole::Composer {
+        composer_ids = artist_ids;
+      }
+    }
+    Ok(composer_ids)
+  }
+
+  /// Synchronizes the work and movement of `track` from `local_sync_track`, where the work is composed by the artist with
+  /// `composer_id`. Tags are the only source of these, so they are set directly instead of merged.
+  fn sync_local_work(&self, mut track: Track, local_sync_track: &FilesystemSyncTrack, composer_id: Option<i32>) -> Result<Track, LocalSyncError> {
+    let work_id = match &local_sync_track.work {
+      Some(work_name) => Some(self.select_or_insert_work(work_name, composer_id)?.id),
+      None => None,
+    };
+    if track.work_id != work_id || track.movement != local_sync_track.movement || track.movement_number != local_sync_track.movement_number {
+      track.work_id = work_id;
+      track.movement = local_sync_track.movement.clone();
+      track.movement_number = local_sync_track.movement_number;
+      event!(Level::DEBUG, ?track, "Updating work and movement of track");
+      track = time!("sync.update_track_work", track.save_changes(&*self.connection)?);
+    }
+    Ok(track)
+  }
+
+  /// Reads the beets library database if one is configured, of which the metadata overrides the metadata from tags.
+  pub(crate) fn read_beets_library(&self) -> Result<Option<BeetsLibrary>, ReadBeetsLibraryError> {
+    match &self.inner.beets_database {
+      Some(beets_database) => Ok(Some(time!("sync.read_beets_library", BeetsLibrary::read(beets_database)?))),
+      None => Ok(None),
+    }
+  }
+
+  /// Partitions `local_sources` into sources to synchronize, and IDs of sources that must be skipped because their
+  /// directory is unreachable and they are configured to be skipped when unavailable.
+  pub(crate) fn partition_reachable_local_sources(local_sources: Vec<LocalSource>) -> (Vec<LocalSource>, Vec<i32>) {
+    let mut skipped_local_source_ids = Vec::new();
+    let local_sources = local_sources.into_iter().filter(|local_source| {
+      if local_source.skip_if_unavailable && !musium_filesystem_sync::is_reachable(&local_source.directory) {
+        event!(Level::WARN, ?local_source, "Skipping synchronization of local source '{}': directory '{}' is unavailable", local_source.id, local_source.directory);
+        skipped_local_source_ids.push(local_source.id);
+        false
+      } else {
+        true
+      }
+    }).collect();
+    (local_sources, skipped_local_source_ids)
+  }
+
+  /// Reads the tracks of the files of `local_sources`, reporting progress after reading each source.
+  fn get_filesystem_sync_tracks(&self, local_sources: Vec<LocalSource>, progress: &SyncProgress) -> Result<(Vec<(i32, FilesystemSyncTrack)>, Vec<FilesystemSyncError>), LocalSyncError> {
+    let source_count = local_sources.len();
+    let do_local_sync = || {
+      let mut tracks = Vec::new();
+      let mut errors = Vec::new();
+      for (index, local_source) in local_sources.into_iter().enumerate() {
+        for result in musium_filesystem_sync::sync(local_source.directory.clone()) {
+          match result {
+            Ok(track) => tracks.push((local_source.id, track)),
+            Err(e) => errors.push(e),
           }
-        })
+        }
+        progress.part(index, source_count).report(1.0);
+      }
+      (tracks, errors)
     };
     Ok(time!("sync.local_sync", do_local_sync()))
   }
 
   fn sync_local_album(&self, local_source_id: i32, filesystem_sync_track: &FilesystemSyncTrack) -> Result<Album, LocalSyncError> {
-    let db_album = self.select_one_or_insert_album(&filesystem_sync_track.album)?.into();
+    let db_album = self.select_or_insert_album_by_artists(&filesystem_sync_track.album, &filesystem_sync_track.album_artists, &filesystem_sync_track.track_artists)?.into();
 
     let select_local_album_query = {
       use schema::local_album::dsl::*;
@@ -95,33 +248,37 @@ impl DatabaseConnection {
       time!("sync.insert_local_album", insert_local_album_query.execute(&self.connection)?);
     };
     Ok(db_album)
-
-    // TODO: when there are multiple albums with the same name, but no local albums for any of them: create a local
-    //       album for the first one and emit a persistent warning that the user may have to disambiguate manually.
-    //       Return the selected album.
-    // TODO: when there are multiple albums with the same name, and local albums for some of them, try to match the
-    //       local album with an external ID such as the MusicBrainz Album ID. If a match was found, return that
-    //       album. If no match was found, and there are local albums for all albums, create a new album and local
-    //       album. If no match was found, but there is no local album for some albums, take the first of those albums,
-    //       create a local album for it, and emit a persistent warning that the user may have to disambiguate manually.
   }
 
   fn sync_local_track(&self, local_source_id: i32, album: &Album, local_sync_track: &FilesystemSyncTrack) -> Result<Track, LocalSyncError> {
     use LocalSyncError::*;
 
-    let track_file_path = local_sync_track.file_path.clone();
+    // Compare by normalized file path key, so that a track is not duplicated when only the case or separators of its path
+    // changed, for example because the source directory was mounted under a path with different case.
+    let track_file_path_key = local_sync_track.file_path_key.clone();
 
     let local_track_select_query = {
       use schema::local_track::dsl::*;
       local_track
         .filter(local_source_id.eq(local_source_id))
-        .filter(file_path.eq(&track_file_path))
+        .filter(file_path_key.eq(&track_file_path_key))
     };
     let db_local_track = time!("sync.select_local_track", local_track_select_query.first::<LocalTrack>(&self.connection).optional()?);
     let db_track = if let Some(db_local_track) = db_local_track {
       // A local track with the same path as the locally synchronized track was found. Either track meta-data has been
       // updated, or the track has been replaced by a new one.
       let mut db_local_track: LocalTrack = db_local_track;
+      if db_local_track.file_path.as_ref() != Some(&local_sync_track.file_path) {
+        // The path only differs in case or separators: update it to the path as it currently is on the filesystem.
+        db_local_track.file_path = Some(local_sync_track.file_path.clone());
+        event!(Level::TRACE, ?db_local_track, "Updating file path of local track");
+        time!("sync.update_local_track_file_path", db_local_track.save_changes::<LocalTrack>(&*self.connection)?);
+      }
+      if db_local_track.update_replay_gain_from(&local_sync_track) {
+        // ReplayGain tags are not part of the hash, so they can change without the hash changing.
+        event!(Level::TRACE, ?db_local_track, "Updating ReplayGain values of local track");
+        time!("sync.update_local_track_replay_gain", db_local_track.save_changes::<LocalTrack>(&*self.connection)?);
+      }
 
       // Get track corresponding to the local track. There is always one due to referential integrity.
       let track_select_query = {
@@ -140,6 +297,7 @@ impl DatabaseConnection {
         // instead set the track in the database as removed (NULL file_path), and insert the scanned track as a
         // new one.
         db_local_track.file_path = None;
+        db_local_track.file_path_key = None;
         event!(Level::DEBUG, ?db_local_track, "Local track has been replaced, setting the local track as removed in the database");
         time!("sync.update_replaced_local_track", db_local_track.save_changes::<LocalTrack>(&*self.connection)?);
         // Insert replaced track as a new one.
@@ -147,15 +305,17 @@ impl DatabaseConnection {
         self.insert_new_track_and_local_track(local_source_id, &album, &local_sync_track)?
       } else if hash_changed {
         // When the hash is different, but the metadata is not, we assume that the track's audio data has (somehow)
-        // changed, and just update the hash.
+        // changed, and just update the hash and bitrate.
         event!(Level::TRACE, ?db_local_track, "Updating hash of local track");
         db_local_track.hash = local_sync_track.hash as i64;
+        db_local_track.bitrate = local_sync_track.bitrate;
         time!("sync.update_local_track_hash", db_local_track.save_changes::<LocalTrack>(&*self.connection)?);
         db_track
       } else if metadata_changed {
-        // When the hash is the same, but the metadata is not, the metadata of the track was changed, and we just update it.
+        // When the hash is the same, but the metadata is not, the metadata of the track was changed, and we just update it
+        // according to the metadata merge policies.
         event!(Level::TRACE, ?db_track, "Updating track with values from locally synchronized track");
-        if db_track.update_from(&album, local_sync_track) {
+        if self.merge_track_from_local(&mut db_track, &album, local_sync_track)? {
           event!(Level::DEBUG, ?db_track, "Track has changed, updating the track in the database");
           time!("sync.update_track", db_track.save_changes(&*self.connection)?)
         } else {
@@ -196,9 +356,9 @@ impl DatabaseConnection {
           };
           let mut db_track: Track = time!("sync.select_track", track_select_query.first::<Track>(&self.connection)?);
 
-          // Update the corresponding track as well.
+          // Update the corresponding track as well, according to the metadata merge policies.
           event!(Level::TRACE, ?db_track, "Updating track with values from locally synchronized track");
-          if db_track.update_from(&album, local_sync_track) {
+          if self.merge_track_from_local(&mut db_track, &album, local_sync_track)? {
             event!(Level::DEBUG, ?db_track, "Track has changed, updating the track in the database");
             time!("sync.update_track", db_track.save_changes(&*self.connection)?)
           } else {
@@ -222,12 +382,20 @@ impl DatabaseConnection {
       track_number: local_sync_track.track_number,
       track_total: local_sync_track.track_total,
       title: local_sync_track.title.clone(),
+      explicit: local_sync_track.explicit,
+      ..NewTrack::default()
     })?;
     let new_local_track = NewLocalTrack {
       track_id: db_track.id,
       local_source_id,
       file_path: Some(local_sync_track.file_path.clone()),
+      file_path_key: Some(local_sync_track.file_path_key.clone()),
       hash: local_sync_track.hash as i64,
+      bitrate: local_sync_track.bitrate,
+      track_gain: local_sync_track.replay_gain.track_gain,
+      track_peak: local_sync_track.replay_gain.track_peak,
+      album_gain: local_sync_track.replay_gain.album_gain,
+      album_peak: local_sync_track.replay_gain.album_peak,
     };
     event!(Level::DEBUG, ?new_local_track, "Inserting local track");
     let local_track_insert_query = diesel::insert_into(schema::local_track::table).values(new_local_track);
@@ -286,24 +454,24 @@ impl DatabaseConnection {
     //       create a local artist for it, and emit a persistent warning that the user may have to disambiguate manually.
   }
 
-  fn cleanup_local_tracks(&self, synced_file_paths: HashMap::<i32, HashSet<String>>) -> Result<(), LocalSyncError> {
+  fn cleanup_local_tracks(&self, synced_file_path_keys: HashMap::<i32, HashSet<String>>) -> Result<(), LocalSyncError> {
     let db_local_track_data: Vec<(i32, i32, Option<String>)> = {
       use schema::local_track::dsl::*;
       local_track
-        .select((track_id, local_source_id, file_path))
+        .select((track_id, local_source_id, file_path_key))
         .filter(file_path.is_not_null())
         .load::<(i32, i32, Option<String>)>(&self.connection)?
     };
-    for (db_track_id, db_local_source_id, db_file_path) in db_local_track_data {
-      if let (Some(db_file_path), Some(synced_file_paths)) = (db_file_path, synced_file_paths.get(&db_local_source_id)) {
-        if !synced_file_paths.contains(&db_file_path) {
-          event!(Level::DEBUG, ?db_track_id, ?db_file_path, "Local track '{}' at '{}' was not seen during synchronization: setting it as removed in the database", db_track_id, db_file_path);
+    for (db_track_id, db_local_source_id, db_file_path_key) in db_local_track_data {
+      if let Some(synced_file_path_keys) = synced_file_path_keys.get(&db_local_source_id) {
+        if db_file_path_key.map_or(true, |db_file_path_key| !synced_file_path_keys.contains(&db_file_path_key)) {
+          event!(Level::DEBUG, ?db_track_id, "Local track '{}' was not seen during synchronization: setting it as removed in the database", db_track_id);
           let update_query = {
             use schema::local_track::dsl::*;
             diesel::update(local_track)
               .filter(track_id.eq(db_track_id))
               .filter(local_source_id.eq(db_local_source_id))
-              .set(file_path.eq::<Option<String>>(None))
+              .set((file_path.eq::<Option<String>>(None), file_path_key.eq::<Option<String>>(None)))
           };
           time!("sync.update_removed_local_track", update_query.execute(&self.connection)?);
         }
@@ -311,4 +479,313 @@ impl DatabaseConnection {
     }
     Ok(())
   }
+
+  /// Clears the album artists of the albums with `album_ids`, of which all synchronized tracks have no album artists,
+  /// because the album artists were removed from their tags. Albums that also have tracks from Spotify or from local
+  /// sources other than `synced_local_source_ids` are skipped, as those tracks may still have album artists.
+  fn clear_removed_album_artists(&self, album_ids: HashSet<i32>, synced_local_source_ids: &HashSet<i32>) -> Result<(), LocalSyncError> {
+    for input_album_id in album_ids {
+      let spotify_album_count: i64 = {
+        use schema::spotify_album::dsl::*;
+        time!("sync.count_spotify_albums", spotify_album.filter(album_id.eq(input_album_id)).count().get_result(&self.connection)?)
+      };
+      let other_local_album_count: i64 = {
+        use schema::local_album::dsl::*;
+        time!("sync.count_other_local_albums", local_album
+          .filter(album_id.eq(input_album_id))
+          .filter(local_source_id.ne_all(synced_local_source_ids.iter().copied().collect_vec()))
+          .count()
+          .get_result(&self.connection)?)
+      };
+      if spotify_album_count == 0 && other_local_album_count == 0 {
+        let db_album = self.select_album_by_id(input_album_id)?;
+        event!(Level::DEBUG, ?db_album, "Album artists were removed from all tracks of the album: clearing them");
+        self.sync_album_artists(&db_album, HashSet::new())?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Deletes local albums for which the local source no longer has any tracks on the album, for example because the
+  /// tracks were matched to a different album with the same name but different album artists. This splits albums that
+  /// were previously merged because they were matched by name only.
+  fn cleanup_local_albums(&self) -> Result<(), LocalSyncError> {
+    let delete_query = diesel::sql_query("DELETE FROM local_album WHERE NOT EXISTS (\
+      SELECT 1 FROM track INNER JOIN local_track ON local_track.track_id = track.id \
+      WHERE track.album_id = local_album.album_id AND local_track.local_source_id = local_album.local_source_id\
+    )");
+    let deleted_count = time!("sync.delete_local_albums", delete_query.execute(&self.connection)?);
+    event!(Level::DEBUG, deleted_count, "Removed local albums that no longer have any tracks");
+    Ok(())
+  }
+
+  /// Deletes albums without any tracks that are not associated with a Spotify album, for example because all their
+  /// tracks were matched to a different album, along with all rows that refer to them: their album artists, local albums,
+  /// Spotify source links, cover, ratings, hidden items, undoable rating changes, and metadata conflicts. Orphaned albums
+  /// are selected with a subquery in each delete instead of binding their IDs, as splitting albums may orphan more albums
+  /// than SQLite allows bound variables in a query.
+  ///
+  /// Albums that were wrongly merged before albums were matched by album artists are intentionally not split by a
+  /// migration, as splitting them requires the tags of their local files. They are split on the next synchronization,
+  /// which rematches their tracks, after which this cleans up the albums that were left without tracks.
+  fn cleanup_orphaned_albums(&self) -> Result<(), LocalSyncError> {
+    use musium_core::model::{HiddenItemKind, MetadataField, UndoKind};
+    use schema::{album, album_artist, album_cover, local_album, metadata_conflict, spotify_album, spotify_album_source, track, undo_log, user_album_rating, user_hidden_item};
+    let orphaned_album_ids = || album::table
+      .select(album::id)
+      .filter(album::id.ne_all(track::table.select(track::album_id)))
+      .filter(album::id.ne_all(spotify_album::table.select(spotify_album::album_id)));
+    let cover_blob_hashes: Vec<String> = time!("sync.select_orphaned_album_covers", album_cover::table
+      .select(album_cover::blob_hash)
+      .filter(album_cover::album_id.eq_any(orphaned_album_ids()))
+      .load(&self.connection)?);
+    time!("sync.delete_orphaned_album_covers", diesel::delete(album_cover::table.filter(album_cover::album_id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    for blob_hash in cover_blob_hashes.into_iter().unique() {
+      self.unpin_unused_cover_blob(&blob_hash)?;
+    }
+    time!("sync.delete_orphaned_album_artists", diesel::delete(album_artist::table.filter(album_artist::album_id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    time!("sync.delete_orphaned_local_albums", diesel::delete(local_album::table.filter(local_album::album_id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    time!("sync.delete_orphaned_spotify_album_sources", diesel::delete(spotify_album_source::table.filter(spotify_album_source::album_id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    time!("sync.delete_orphaned_album_ratings", diesel::delete(user_album_rating::table.filter(user_album_rating::album_id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    time!("sync.delete_orphaned_hidden_albums", diesel::delete(user_hidden_item::table
+      .filter(user_hidden_item::kind.eq(HiddenItemKind::Album))
+      .filter(user_hidden_item::item_id.eq_any(orphaned_album_ids())))
+      .execute(&self.connection)?);
+    time!("sync.delete_orphaned_album_undo_log", diesel::delete(undo_log::table
+      .filter(undo_log::kind.eq(UndoKind::AlbumRatingChange))
+      .filter(undo_log::target_id.eq_any(orphaned_album_ids())))
+      .execute(&self.connection)?);
+    time!("sync.delete_orphaned_album_metadata_conflicts", diesel::delete(metadata_conflict::table
+      .filter(metadata_conflict::field.eq(MetadataField::AlbumName))
+      .filter(metadata_conflict::entity_id.eq_any(orphaned_album_ids())))
+      .execute(&self.connection)?);
+    let deleted_count = time!("sync.delete_orphaned_albums", diesel::delete(album::table.filter(album::id.eq_any(orphaned_album_ids()))).execute(&self.connection)?);
+    if deleted_count > 0 {
+      event!(Level::DEBUG, deleted_count, "Removed albums that no longer have any tracks");
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use musium_core::model::{HiddenItemKind, NewLocalSource};
+  use musium_filesystem_sync::{FilesystemSyncReplayGain, path_key};
+
+  use super::*;
+  use crate::database::test_database::TestDatabase;
+
+  const DIRECTORY: &str = "/music";
+
+  fn filesystem_sync_track(hash: u32, title: &str, album: &str, track_artist: &str, album_artists: &[&str]) -> FilesystemSyncTrack {
+    let file_path = format!("{}/{}.mp3", DIRECTORY, title);
+    FilesystemSyncTrack {
+      disc_number: None,
+      disc_total: None,
+      track_number: None,
+      track_total: None,
+      title: title.to_string(),
+      album: album.to_string(),
+      track_artists: vec![track_artist.to_string()],
+      album_artists: album_artists.iter().map(|name| name.to_string()).collect(),
+      file_path_key: path_key(&file_path),
+      file_path,
+      hash,
+      bitrate: None,
+      replay_gain: FilesystemSyncReplayGain::default(),
+      explicit: None,
+      featured_artists: Vec::new(),
+      remixers: Vec::new(),
+      conductors: Vec::new(),
+      composers: Vec::new(),
+      performers: Vec::new(),
+      work: None,
+      movement: None,
+      movement_number: None,
+    }
+  }
+
+  struct TestSync {
+    test_database: TestDatabase,
+    directories: HashMap<i32, String>,
+  }
+
+  impl TestSync {
+    fn new() -> Self {
+      let test_database = TestDatabase::new();
+      let local_source = test_database.database.connect().unwrap()
+        .create_or_enable_local_source(&NewLocalSource { enabled: true, directory: DIRECTORY.to_string(), skip_if_unavailable: false })
+        .unwrap();
+      let directories = std::iter::once((local_source.id, local_source.directory)).collect();
+      Self { test_database, directories }
+    }
+
+    fn connect(&self) -> DatabaseConnection {
+      self.test_database.database.connect().unwrap()
+    }
+
+    fn sync(&self, tracks: Vec<FilesystemSyncTrack>) {
+      let local_source_id = *self.directories.keys().next().unwrap();
+      let tracks = tracks.into_iter().map(|track| (local_source_id, track)).collect();
+      self.connect().sync_local_sync_tracks(&self.directories, tracks, &SyncProgress::ignored()).unwrap();
+    }
+
+    /// Gets the album artist name keys and track titles of the albums named `name`, sorted by album artists.
+    fn albums(&self, name: &str) -> Vec<(Vec<String>, Vec<String>)> {
+      let connection = self.connect();
+      let mut albums = Vec::new();
+      for db_album in connection.select_albums_by_name(&name.to_string()).unwrap() {
+        let titles: Vec<String> = schema::track::table
+          .select(schema::track::title)
+          .filter(schema::track::album_id.eq(db_album.id))
+          .order(schema::track::title)
+          .load(&connection.connection)
+          .unwrap();
+        albums.push((connection.select_album_artist_name_keys(db_album.id).unwrap(), titles));
+      }
+      albums.sort();
+      albums
+    }
+
+    fn album_count(&self) -> i64 {
+      schema::album::table.count().get_result(&self.connect().connection).unwrap()
+    }
+
+    fn local_album_count(&self) -> i64 {
+      schema::local_album::table.count().get_result(&self.connect().connection).unwrap()
+    }
+  }
+
+  fn strings(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|string| string.to_string()).collect()
+  }
+
+  #[test]
+  fn changed_album_artist_moves_album() {
+    let test_sync = TestSync::new();
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Queen"]),
+    ]);
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen + Paul Rodgers"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Queen + Paul Rodgers"]),
+    ]);
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![(strings(&["queen + paul rodgers"]), strings(&["One Vision", "Radio Ga Ga"]))]);
+    assert_eq!(test_sync.album_count(), 1);
+    assert_eq!(test_sync.local_album_count(), 1);
+  }
+
+  #[test]
+  fn removed_album_artist_clears_album_artists() {
+    let test_sync = TestSync::new();
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Queen"]),
+    ]);
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &[]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &[]),
+    ]);
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![(Vec::new(), strings(&["One Vision", "Radio Ga Ga"]))]);
+    assert_eq!(test_sync.album_count(), 1);
+  }
+
+  #[test]
+  fn albums_with_same_name_are_split() {
+    let test_sync = TestSync::new();
+    // Merged into one album, as the album artist of the ABBA tracks is wrong.
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "SOS", "Greatest Hits", "ABBA", &["Queen"]),
+    ]);
+    assert_eq!(test_sync.album_count(), 1);
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "SOS", "Greatest Hits", "ABBA", &["ABBA"]),
+    ]);
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![
+      (strings(&["abba"]), strings(&["SOS"])),
+      (strings(&["queen"]), strings(&["One Vision"])),
+    ]);
+    assert_eq!(test_sync.album_count(), 2);
+    assert_eq!(test_sync.local_album_count(), 2);
+  }
+
+  #[test]
+  fn wrongly_merged_albums_are_split_on_next_sync() {
+    let test_sync = TestSync::new();
+    // Wrongly merged album, as created by matching albums by name only before upgrading.
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "SOS", "Greatest Hits", "ABBA", &["Queen"]),
+    ]);
+    let connection = test_sync.connect();
+    let merged_album_id = connection.select_albums_by_name(&"Greatest Hits".to_string()).unwrap()[0].id;
+    let local_source_id = *test_sync.directories.keys().next().unwrap();
+    let abba_artist_id = connection.sync_local_artist(local_source_id, "ABBA".to_string()).unwrap().id;
+    diesel::insert_into(schema::album_artist::table)
+      .values((schema::album_artist::album_id.eq(merged_album_id), schema::album_artist::artist_id.eq(abba_artist_id)))
+      .execute(&connection.connection)
+      .unwrap();
+    // Not split until the next synchronization.
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![(strings(&["abba", "queen"]), strings(&["One Vision", "SOS"]))]);
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "SOS", "Greatest Hits", "ABBA", &["ABBA"]),
+    ]);
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![
+      (strings(&["abba"]), strings(&["SOS"])),
+      (strings(&["queen"]), strings(&["One Vision"])),
+    ]);
+    assert_eq!(test_sync.album_count(), 2);
+    assert_eq!(test_sync.local_album_count(), 2);
+  }
+
+  #[test]
+  fn orphaned_albums_are_removed_with_their_references() {
+    let test_sync = TestSync::new();
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Freddie Mercury"]),
+    ]);
+    let orphaned_album_id = test_sync.connect().select_albums_by_name(&"Greatest Hits".to_string()).unwrap().into_iter()
+      .find(|db_album| test_sync.connect().select_album_artist_name_keys(db_album.id).unwrap() == strings(&["freddie mercury"]))
+      .unwrap()
+      .id;
+    let connection = test_sync.connect();
+    connection.hide_item(1, HiddenItemKind::Album, orphaned_album_id).unwrap();
+    connection.set_user_album_rating(1, orphaned_album_id, 5).unwrap();
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Queen"]),
+    ]);
+    assert_eq!(test_sync.album_count(), 1);
+    let hidden_item_count: i64 = schema::user_hidden_item::table.count().get_result(&connection.connection).unwrap();
+    assert_eq!(hidden_item_count, 0);
+    let rating_count: i64 = schema::user_album_rating::table.count().get_result(&connection.connection).unwrap();
+    assert_eq!(rating_count, 0);
+    let undo_count: i64 = schema::undo_log::table.count().get_result(&connection.connection).unwrap();
+    assert_eq!(undo_count, 0);
+    let album_artist_count: i64 = schema::album_artist::table.filter(schema::album_artist::album_id.eq(orphaned_album_id)).count().get_result(&connection.connection).unwrap();
+    assert_eq!(album_artist_count, 0);
+  }
+
+  #[test]
+  fn split_albums_are_merged() {
+    let test_sync = TestSync::new();
+    // Split into two albums, as the album artist of one track is wrong.
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Freddie Mercury"]),
+    ]);
+    assert_eq!(test_sync.album_count(), 2);
+    test_sync.sync(vec![
+      filesystem_sync_track(1, "One Vision", "Greatest Hits", "Queen", &["Queen"]),
+      filesystem_sync_track(2, "Radio Ga Ga", "Greatest Hits", "Queen", &["Queen"]),
+    ]);
+    assert_eq!(test_sync.albums("Greatest Hits"), vec![(strings(&["queen"]), strings(&["One Vision", "Radio Ga Ga"]))]);
+    assert_eq!(test_sync.album_count(), 1);
+    assert_eq!(test_sync.local_album_count(), 1);
+  }
 }
@@ -0,0 +1,359 @@
+use std::backtrace::Backtrace;
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use itertools::Itertools;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::collation::name_collation_key;
+use musium_core::model::{Album, Artist, MetadataConflict, MetadataField, MetadataMergePolicy, MetadataMergePolicySetting, NewMetadataConflict, Track, TrackArtistRole};
+use musium_core::schema;
+use musium_filesystem_sync::FilesystemSyncTrack;
+
+use crate::database::{DatabaseConnection, DatabaseQueryError};
+use crate::database::sync::SelectArtistError;
+use crate::model::merge_explicit;
+
+/// Separator between artist names in values of the track artists field.
+pub const ARTIST_NAMES_SEPARATOR: &str = "; ";
+
+/// Source of metadata that is being synchronized.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum MetadataSource {
+  Local,
+  Spotify,
+}
+
+/// Value of a metadata field that can be stored in a metadata conflict.
+pub(crate) trait MetadataValue: PartialEq {
+  fn to_metadata_value(&self) -> Option<String>;
+}
+
+impl MetadataValue for String {
+  fn to_metadata_value(&self) -> Option<String> { Some(self.clone()) }
+}
+
+impl MetadataValue for Option<i32> {
+  fn to_metadata_value(&self) -> Option<String> { self.map(|v| v.to_string()) }
+}
+
+// Merging during synchronization
+
+impl DatabaseConnection {
+  /// Merges `new_value` of `field` from `source` into `current_value` of the album, track, or artist with `entity_id`,
+  /// according to the merge policy of `field`. Sets `changed` to `true` if `current_value` was overwritten.
+  pub(crate) fn merge_metadata<T: MetadataValue>(
+    &self,
+    field: MetadataField,
+    entity_id: i32,
+    source: MetadataSource,
+    current_value: &mut T,
+    new_value: T,
+    changed: &mut bool,
+  ) -> Result<(), diesel::result::Error> {
+    if *current_value == new_value { return Ok(()); }
+    if self.may_overwrite_metadata(field, entity_id, source, current_value.to_metadata_value(), new_value.to_metadata_value())? {
+      *current_value = new_value;
+      *changed = true;
+    }
+    Ok(())
+  }
+
+  /// Merges the metadata of `sync_track` from a local source into `track`, returning whether `track` has changed.
+  pub(crate) fn merge_track_from_local(&self, track: &mut Track, album: &Album, sync_track: &FilesystemSyncTrack) -> Result<bool, diesel::result::Error> {
+    let mut changed = false;
+    let track_id = track.id;
+    if track.album_id != album.id {
+      track.album_id = album.id;
+      changed = true;
+    }
+    self.merge_metadata(MetadataField::TrackTitle, track_id, MetadataSource::Local, &mut track.title, sync_track.title.clone(), &mut changed)?;
+    track.title_key = name_collation_key(&track.title);
+    self.merge_metadata(MetadataField::DiscNumber, track_id, MetadataSource::Local, &mut track.disc_number, sync_track.disc_number, &mut changed)?;
+    self.merge_metadata(MetadataField::TrackNumber, track_id, MetadataSource::Local, &mut track.track_number, sync_track.track_number, &mut changed)?;
+    // Totals are only provided by local sources, so they cannot conflict.
+    if track.disc_total != sync_track.disc_total {
+      track.disc_total = sync_track.disc_total;
+      changed = true;
+    }
+    if track.track_total != sync_track.track_total {
+      track.track_total = sync_track.track_total;
+      changed = true;
+    }
+    // Explicit flags are merged from all sources instead of following merge policies, see `merge_explicit`.
+    let explicit = merge_explicit(track.explicit, sync_track.explicit);
+    if track.explicit != explicit {
+      track.explicit = explicit;
+      changed = true;
+    }
+    Ok(changed)
+  }
+
+  /// Merges the metadata of `spotify_track` into `track`, returning whether `track` has changed.
+  pub(crate) fn merge_track_from_spotify(&self, track: &mut Track, album: &Album, spotify_track: &musium_spotify_client::TrackSimple) -> Result<bool, diesel::result::Error> {
+    let mut changed = false;
+    let track_id = track.id;
+    if track.album_id != album.id {
+      track.album_id = album.id;
+      changed = true;
+    }
+    self.merge_metadata(MetadataField::TrackTitle, track_id, MetadataSource::Spotify, &mut track.title, spotify_track.name.clone(), &mut changed)?;
+    track.title_key = name_collation_key(&track.title);
+    self.merge_metadata(MetadataField::DiscNumber, track_id, MetadataSource::Spotify, &mut track.disc_number, Some(spotify_track.disc_number), &mut changed)?;
+    self.merge_metadata(MetadataField::TrackNumber, track_id, MetadataSource::Spotify, &mut track.track_number, Some(spotify_track.track_number), &mut changed)?;
+    let explicit = merge_explicit(track.explicit, Some(spotify_track.explicit));
+    if track.explicit != explicit {
+      track.explicit = explicit;
+      changed = true;
+    }
+    Ok(changed)
+  }
+
+  /// Returns whether the artists of `track` may be set to the artists with `artist_ids` from `source`, according to the
+  /// merge policy of the track artists field.
+  pub(crate) fn merge_track_artists(&self, track: &Track, source: MetadataSource, artist_ids: &HashSet<i32>) -> Result<bool, diesel::result::Error> {
+    let current_artist_names = self.select_track_artist_names(track.id)?;
+    let new_artist_names: Vec<String> = {
+      use schema::artist::dsl::*;
+      artist.filter(id.eq_any(artist_ids.iter().copied().collect_vec())).select(name).load(&self.connection)?
+    };
+    let new_artist_names = new_artist_names.into_iter().sorted().collect_vec();
+    if current_artist_names == new_artist_names { return Ok(true); }
+    let current_value = Some(current_artist_names.join(ARTIST_NAMES_SEPARATOR));
+    let new_value = Some(new_artist_names.join(ARTIST_NAMES_SEPARATOR));
+    self.may_overwrite_metadata(MetadataField::TrackArtists, track.id, source, current_value, new_value)
+  }
+
+  /// Returns the sorted names of the main artists of the track with `input_track_id`.
+  pub(crate) fn select_track_artist_names(&self, input_track_id: i32) -> Result<Vec<String>, diesel::result::Error> {
+    use schema::{artist, track_artist};
+    let names: Vec<String> = time!("select_track_artist_names.select", track_artist::table
+      .inner_join(artist::table)
+      .filter(track_artist::track_id.eq(input_track_id))
+      .filter(track_artist::role.eq(TrackArtistRole::Main))
+      .select(artist::name)
+      .load(&self.connection)?);
+    Ok(names.into_iter().sorted().collect_vec())
+  }
+
+  /// Returns whether `source` may overwrite the value of `field` of the album, track, or artist with `entity_id`, which
+  /// differs from `new_value`. Records a conflict if the entity is also provided by another source and the merge policy
+  /// of `field` requires manual resolution.
+  fn may_overwrite_metadata(
+    &self,
+    input_field: MetadataField,
+    input_entity_id: i32,
+    source: MetadataSource,
+    input_current_value: Option<String>,
+    new_value: Option<String>,
+  ) -> Result<bool, diesel::result::Error> {
+    let policy = match self.get_metadata_merge_policy(input_field)? {
+      None => return Ok(true), // No policy: whichever source is synchronized last wins.
+      Some(policy) => policy,
+    };
+    if !self.has_other_metadata_source(input_field, input_entity_id, source)? {
+      return Ok(true); // Only provided by `source`: there is nothing to conflict with.
+    }
+    let may_overwrite = match policy {
+      MetadataMergePolicy::PreferLocal => source == MetadataSource::Local,
+      MetadataMergePolicy::PreferSpotify => source == MetadataSource::Spotify,
+      MetadataMergePolicy::PreferMusicBrainz | MetadataMergePolicy::Manual => {
+        self.record_metadata_conflict(input_field, input_entity_id, source, input_current_value, new_value)?;
+        false
+      }
+    };
+    if !may_overwrite {
+      event!(Level::DEBUG, field = %input_field, entity_id = input_entity_id, ?source, %policy, "Not overwriting metadata due to merge policy");
+    }
+    Ok(may_overwrite)
+  }
+
+  fn has_other_metadata_source(&self, field: MetadataField, entity_id: i32, source: MetadataSource) -> Result<bool, diesel::result::Error> {
+    use diesel::dsl::exists;
+    use schema::{local_album, local_artist, local_track, spotify_album, spotify_artist, spotify_track};
+    let query_result = match (field, source) {
+      (MetadataField::AlbumName, MetadataSource::Local) => diesel::select(exists(spotify_album::table.filter(spotify_album::album_id.eq(entity_id)))).get_result(&self.connection),
+      (MetadataField::AlbumName, MetadataSource::Spotify) => diesel::select(exists(local_album::table.filter(local_album::album_id.eq(entity_id)))).get_result(&self.connection),
+      (MetadataField::ArtistName, MetadataSource::Local) => diesel::select(exists(spotify_artist::table.filter(spotify_artist::artist_id.eq(entity_id)))).get_result(&self.connection),
+      (MetadataField::ArtistName, MetadataSource::Spotify) => diesel::select(exists(local_artist::table.filter(local_artist::artist_id.eq(entity_id)))).get_result(&self.connection),
+      (_, MetadataSource::Local) => diesel::select(exists(spotify_track::table.filter(spotify_track::track_id.eq(entity_id)))).get_result(&self.connection),
+      (_, MetadataSource::Spotify) => diesel::select(exists(local_track::table.filter(local_track::track_id.eq(entity_id)))).get_result(&self.connection),
+    };
+    Ok(time!("has_other_metadata_source.select", query_result?))
+  }
+
+  fn record_metadata_conflict(
+    &self,
+    input_field: MetadataField,
+    input_entity_id: i32,
+    source: MetadataSource,
+    input_current_value: Option<String>,
+    new_value: Option<String>,
+  ) -> Result<(), diesel::result::Error> {
+    use schema::metadata_conflict::dsl::*;
+    let now = Utc::now().naive_utc();
+    let db_conflict: Option<MetadataConflict> = time!("record_metadata_conflict.select", metadata_conflict
+      .filter(field.eq(input_field))
+      .filter(entity_id.eq(input_entity_id))
+      .first(&self.connection)
+      .optional()?);
+    if let Some(mut db_conflict) = db_conflict {
+      db_conflict.current_value = input_current_value;
+      match source {
+        MetadataSource::Local => db_conflict.local_value = new_value,
+        MetadataSource::Spotify => db_conflict.spotify_value = new_value,
+      }
+      db_conflict.detected_at = now;
+      event!(Level::DEBUG, ?db_conflict, "Updating metadata conflict");
+      time!("record_metadata_conflict.update", db_conflict.save_changes::<MetadataConflict>(&*self.connection)?);
+    } else {
+      let (input_local_value, input_spotify_value) = match source {
+        MetadataSource::Local => (new_value, None),
+        MetadataSource::Spotify => (None, new_value),
+      };
+      let new_conflict = NewMetadataConflict {
+        field: input_field,
+        entity_id: input_entity_id,
+        current_value: input_current_value,
+        local_value: input_local_value,
+        spotify_value: input_spotify_value,
+        detected_at: now,
+      };
+      event!(Level::DEBUG, ?new_conflict, "Inserting metadata conflict");
+      time!("record_metadata_conflict.insert", diesel::insert_into(metadata_conflict).values(new_conflict).execute(&self.connection)?);
+    }
+    Ok(())
+  }
+}
+
+// Merge policies
+
+impl DatabaseConnection {
+  pub fn list_metadata_merge_policies(&self) -> Result<Vec<MetadataMergePolicySetting>, DatabaseQueryError> {
+    use schema::metadata_merge_policy::dsl::*;
+    Ok(time!("list_metadata_merge_policies.select", metadata_merge_policy.load(&self.connection)?))
+  }
+
+  pub fn set_metadata_merge_policy(&self, setting: MetadataMergePolicySetting) -> Result<MetadataMergePolicySetting, DatabaseQueryError> {
+    use schema::metadata_merge_policy::dsl::*;
+    event!(Level::DEBUG, ?setting, "Setting metadata merge policy");
+    time!("set_metadata_merge_policy.replace", diesel::replace_into(metadata_merge_policy).values(&setting).execute(&self.connection)?);
+    Ok(setting)
+  }
+
+  /// Removes the merge policy of `input_field`, so that whichever source is synchronized last wins again. Returns the
+  /// removed setting, or `None` if no policy was set.
+  pub fn remove_metadata_merge_policy(&self, input_field: MetadataField) -> Result<Option<MetadataMergePolicySetting>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::metadata_merge_policy::dsl::*;
+      let setting: Option<MetadataMergePolicySetting> = time!("remove_metadata_merge_policy.select", metadata_merge_policy.find(input_field).first(&self.connection).optional()?);
+      if setting.is_some() {
+        time!("remove_metadata_merge_policy.delete", diesel::delete(metadata_merge_policy.find(input_field)).execute(&self.connection)?);
+      }
+      Ok(setting)
+    })
+  }
+
+  fn get_metadata_merge_policy(&self, input_field: MetadataField) -> Result<Option<MetadataMergePolicy>, diesel::result::Error> {
+    use schema::metadata_merge_policy::dsl::*;
+    Ok(time!("get_metadata_merge_policy.select", metadata_merge_policy.find(input_field).select(policy).first(&self.connection).optional()?))
+  }
+}
+
+// Conflicts
+
+#[derive(Debug, Error)]
+pub enum ResolveMetadataConflictError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to select an artist")]
+  SelectArtistFail(#[from] SelectArtistError, Backtrace),
+  #[error("Value '{0}' is not valid for metadata field '{1}'")]
+  InvalidValue(String, MetadataField),
+}
+
+impl DatabaseConnection {
+  pub fn list_metadata_conflicts(&self) -> Result<Vec<MetadataConflict>, DatabaseQueryError> {
+    use schema::metadata_conflict::dsl::*;
+    Ok(time!("list_metadata_conflicts.select", metadata_conflict.order(detected_at.desc()).load(&self.connection)?))
+  }
+
+  /// Dismisses the metadata conflict with `input_id`, keeping the current value. Returns the dismissed conflict, or
+  /// `None` if it does not exist.
+  pub fn dismiss_metadata_conflict(&self, input_id: i32) -> Result<Option<MetadataConflict>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::metadata_conflict::dsl::*;
+      let conflict: Option<MetadataConflict> = time!("dismiss_metadata_conflict.select", metadata_conflict.find(input_id).first(&self.connection).optional()?);
+      if conflict.is_some() {
+        time!("dismiss_metadata_conflict.delete", diesel::delete(metadata_conflict.find(input_id)).execute(&self.connection)?);
+      }
+      Ok(conflict)
+    })
+  }
+
+  /// Resolves the metadata conflict with `input_id` by setting its field to `value`, and removes the conflict. Track
+  /// artists are given as names separated by `ARTIST_NAMES_SEPARATOR`, and an empty value unsets a track or disc number.
+  /// Returns the resolved conflict, or `None` if it does not exist.
+  pub fn resolve_metadata_conflict(&self, input_id: i32, value: &str) -> Result<Option<MetadataConflict>, ResolveMetadataConflictError> {
+    self.connection.transaction::<_, ResolveMetadataConflictError, _>(|| {
+      let conflict: Option<MetadataConflict> = {
+        use schema::metadata_conflict::dsl::*;
+        time!("resolve_metadata_conflict.select", metadata_conflict.find(input_id).first(&self.connection).optional()?)
+      };
+      let conflict = match conflict {
+        Some(conflict) => conflict,
+        None => return Ok(None),
+      };
+      let parse_number = || -> Result<Option<i32>, ResolveMetadataConflictError> {
+        if value.is_empty() { return Ok(None); }
+        value.parse().map(Some).map_err(|_| ResolveMetadataConflictError::InvalidValue(value.to_string(), conflict.field))
+      };
+      match conflict.field {
+        MetadataField::AlbumName => {
+          let mut db_album = self.select_album_by_id(conflict.entity_id)?;
+          db_album.name = value.to_string();
+          self.update_album_derived_names(&mut db_album)?;
+          db_album.save_changes::<Album>(&*self.connection)?;
+        }
+        MetadataField::TrackTitle => {
+          let mut db_track = self.select_track_by_id(conflict.entity_id)?;
+          db_track.title = value.to_string();
+          db_track.title_key = name_collation_key(value);
+          db_track.save_changes::<Track>(&*self.connection)?;
+        }
+        MetadataField::TrackNumber => {
+          let mut db_track = self.select_track_by_id(conflict.entity_id)?;
+          db_track.track_number = parse_number()?;
+          db_track.save_changes::<Track>(&*self.connection)?;
+        }
+        MetadataField::DiscNumber => {
+          let mut db_track = self.select_track_by_id(conflict.entity_id)?;
+          db_track.disc_number = parse_number()?;
+          db_track.save_changes::<Track>(&*self.connection)?;
+        }
+        MetadataField::ArtistName => {
+          let mut db_artist = self.select_artist_by_id(conflict.entity_id)?;
+          db_artist.name = value.to_string();
+          self.update_artist_derived_names(&mut db_artist)?;
+          db_artist.save_changes::<Artist>(&*self.connection)?;
+        }
+        MetadataField::TrackArtists => {
+          let db_track = self.select_track_by_id(conflict.entity_id)?;
+          let mut artist_ids = HashSet::new();
+          for artist_name in value.split(ARTIST_NAMES_SEPARATOR).map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            artist_ids.insert(self.select_one_or_insert_artist(&artist_name.to_string())?.into().id);
+          }
+          self.sync_track_artists(&db_track, TrackArtistRole::Main, artist_ids)?;
+        }
+      }
+      event!(Level::DEBUG, ?conflict, value, "Resolved metadata conflict");
+      {
+        use schema::metadata_conflict::dsl::*;
+        time!("resolve_metadata_conflict.delete", diesel::delete(metadata_conflict.find(input_id)).execute(&self.connection)?);
+      }
+      Ok(Some(conflict))
+    })
+  }
+}
@@ -0,0 +1,207 @@
+use std::backtrace::Backtrace;
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use itertools::Itertools;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::{SyncPreview, SyncPreviewChange, SyncPreviewChanges};
+use musium_core::format_error::FormatError;
+use musium_core::model::{LocalTrack, Track};
+use musium_core::schema;
+use musium_filesystem_sync::FilesystemSyncTrack;
+
+use crate::beets::ReadBeetsLibraryError;
+use crate::credits::split_track_credits;
+use crate::database::{DatabaseConnection, DatabaseQueryError};
+use crate::model::LocalTrackEx;
+
+/// Maximum number of changes of each kind that are included as a sample in a sync preview.
+pub const SYNC_PREVIEW_SAMPLE_SIZE: usize = 25;
+
+#[derive(Debug, Error)]
+pub enum SyncPreviewError {
+  #[error("Failed to query for local source(s)")]
+  QuerySourceFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Failed to query database")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to read beets library")]
+  ReadBeetsLibraryFail(#[from] ReadBeetsLibraryError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Previews synchronizing with all local sources, by scanning them and comparing the scanned tracks against the
+  /// database in the same way as synchronization does, without writing anything to the database.
+  #[instrument(skip(self))]
+  pub fn preview_local_sources_sync(&self) -> Result<SyncPreview, SyncPreviewError> {
+    let local_sources = self.list_local_sources()?;
+    let (local_sources, skipped_local_source_ids) = Self::partition_reachable_local_sources(local_sources);
+    let mut preview = SyncPreview { skipped_local_source_ids, ..SyncPreview::default() };
+    let mut scanned_file_path_keys: HashMap<i32, HashSet<String>> = local_sources.iter().map(|local_source| (local_source.id, HashSet::new())).collect();
+    let mut moved_track_ids = HashSet::new();
+    let beets_library = self.read_beets_library()?;
+    for local_source in local_sources {
+      for result in musium_filesystem_sync::sync(local_source.directory.clone()) {
+        match result {
+          Ok(mut sync_track) => {
+            if let Some(beets_library) = &beets_library {
+              beets_library.apply(&local_source.directory, &mut sync_track);
+            }
+            split_track_credits(&mut sync_track);
+            scanned_file_path_keys.entry(local_source.id)
+              .or_default()
+              .insert(sync_track.file_path_key.clone());
+            self.preview_local_track(local_source.id, &sync_track, &mut preview, &mut moved_track_ids)?;
+          }
+          Err(e) => preview.errors.push(format!("{}{:?}", e, FormatError::new(&e))),
+        }
+      }
+    }
+    self.preview_removed_local_tracks(&scanned_file_path_keys, &moved_track_ids, &mut preview)?;
+    event!(Level::DEBUG, additions = preview.additions.count, removals = preview.removals.count, moves = preview.moves.count, metadata_changes = preview.metadata_changes.count, "Previewed local sources sync");
+    Ok(preview)
+  }
+
+  fn preview_local_track(
+    &self,
+    input_local_source_id: i32,
+    sync_track: &FilesystemSyncTrack,
+    preview: &mut SyncPreview,
+    moved_track_ids: &mut HashSet<i32>,
+  ) -> Result<(), SyncPreviewError> {
+    let db_local_track: Option<LocalTrack> = {
+      use schema::local_track::dsl::*;
+      time!("preview_local_track.select_local_track", local_track
+        .filter(local_source_id.eq(input_local_source_id))
+        .filter(file_path_key.eq(&sync_track.file_path_key))
+        .first(&self.connection)
+        .optional()?)
+    };
+    if let Some(db_local_track) = db_local_track {
+      // A local track with the same path was found: it was either unchanged, updated, or replaced.
+      let db_track = self.select_track_by_id(db_local_track.track_id)?;
+      let metadata_changes = self.describe_track_metadata_changes(&db_track, sync_track)?;
+      let hash_changed = db_local_track.check_hash_changed(sync_track);
+      if hash_changed && !metadata_changes.is_empty() {
+        let description = format!("Replaces track '{}' with a different file", db_track.title);
+        push_change(&mut preview.additions, new_change(input_local_source_id, None, sync_track, None, description));
+        let description = format!("Track '{}' was replaced by a different file", db_track.title);
+        push_change(&mut preview.removals, new_change(input_local_source_id, Some(db_track.id), sync_track, None, description));
+        return Ok(());
+      }
+      if db_local_track.file_path.as_ref() != Some(&sync_track.file_path) {
+        let description = "Path only differs in case or separators".to_string();
+        push_change(&mut preview.moves, new_change(input_local_source_id, Some(db_track.id), sync_track, db_local_track.file_path.clone(), description));
+      }
+      if hash_changed {
+        let description = "Audio data changed".to_string();
+        push_change(&mut preview.metadata_changes, new_change(input_local_source_id, Some(db_track.id), sync_track, None, description));
+      } else if !metadata_changes.is_empty() {
+        let description = metadata_changes.join("; ");
+        push_change(&mut preview.metadata_changes, new_change(input_local_source_id, Some(db_track.id), sync_track, None, description));
+      }
+    } else {
+      // No local track with the same path was found: the track is either new, or was moved, which is checked by hash.
+      let db_local_tracks_by_hash: Vec<LocalTrack> = {
+        use schema::local_track::dsl::*;
+        time!("preview_local_track.select_local_tracks_by_hash", local_track
+          .filter(local_source_id.eq(input_local_source_id))
+          .filter(hash.eq(sync_track.hash as i64))
+          .load(&self.connection)?)
+      };
+      match db_local_tracks_by_hash.len() {
+        0 => {
+          let description = format!("New track '{}' on album '{}'", sync_track.title, sync_track.album);
+          push_change(&mut preview.additions, new_change(input_local_source_id, None, sync_track, None, description));
+        }
+        1 => {
+          let db_local_track = db_local_tracks_by_hash.into_iter().next().unwrap();
+          let db_track = self.select_track_by_id(db_local_track.track_id)?;
+          moved_track_ids.insert(db_track.id);
+          let description = if db_local_track.file_path.is_some() {
+            format!("Track '{}' was moved", db_track.title)
+          } else {
+            format!("Previously removed track '{}' was found again", db_track.title)
+          };
+          push_change(&mut preview.moves, new_change(input_local_source_id, Some(db_track.id), sync_track, db_local_track.file_path.clone(), description));
+          let metadata_changes = self.describe_track_metadata_changes(&db_track, sync_track)?;
+          if !metadata_changes.is_empty() {
+            let description = metadata_changes.join("; ");
+            push_change(&mut preview.metadata_changes, new_change(input_local_source_id, Some(db_track.id), sync_track, None, description));
+          }
+        }
+        _ => {
+          preview.errors.push(format!("Found multiple local tracks with the same hash as '{}'; synchronization would fail", sync_track.file_path));
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn preview_removed_local_tracks(
+    &self,
+    scanned_file_path_keys: &HashMap<i32, HashSet<String>>,
+    moved_track_ids: &HashSet<i32>,
+    preview: &mut SyncPreview,
+  ) -> Result<(), SyncPreviewError> {
+    let db_local_tracks: Vec<LocalTrack> = {
+      use schema::local_track::dsl::*;
+      time!("preview_removed_local_tracks.select", local_track.filter(file_path.is_not_null()).load(&self.connection)?)
+    };
+    for db_local_track in db_local_tracks {
+      // Only tracks of scanned local sources are removed, and moved tracks are not removed.
+      let scanned_file_path_keys = match scanned_file_path_keys.get(&db_local_track.local_source_id) {
+        Some(scanned_file_path_keys) => scanned_file_path_keys,
+        None => continue,
+      };
+      if moved_track_ids.contains(&db_local_track.track_id) { continue; }
+      if db_local_track.file_path_key.as_ref().map_or(false, |key| scanned_file_path_keys.contains(key)) { continue; }
+      let db_track = self.select_track_by_id(db_local_track.track_id)?;
+      push_change(&mut preview.removals, SyncPreviewChange {
+        local_source_id: db_local_track.local_source_id,
+        track_id: Some(db_track.id),
+        file_path: db_local_track.file_path.clone().unwrap_or_default(),
+        previous_file_path: None,
+        description: format!("Track '{}' was not found", db_track.title),
+      });
+    }
+    Ok(())
+  }
+
+  /// Describes the differences between the metadata of `db_track` and `sync_track`, returning an empty vector if the
+  /// metadata is the same.
+  fn describe_track_metadata_changes(&self, db_track: &Track, sync_track: &FilesystemSyncTrack) -> Result<Vec<String>, diesel::result::Error> {
+    let mut changes = Vec::new();
+    let db_album = self.select_album_by_id(db_track.album_id)?;
+    if db_album.name != sync_track.album {
+      changes.push(format!("album '{}' -> '{}'", db_album.name, sync_track.album));
+    }
+    if db_track.title != sync_track.title {
+      changes.push(format!("title '{}' -> '{}'", db_track.title, sync_track.title));
+    }
+    if db_track.disc_number != sync_track.disc_number || db_track.disc_total != sync_track.disc_total {
+      changes.push(format!("disc {:?}/{:?} -> {:?}/{:?}", db_track.disc_number, db_track.disc_total, sync_track.disc_number, sync_track.disc_total));
+    }
+    if db_track.track_number != sync_track.track_number || db_track.track_total != sync_track.track_total {
+      changes.push(format!("track {:?}/{:?} -> {:?}/{:?}", db_track.track_number, db_track.track_total, sync_track.track_number, sync_track.track_total));
+    }
+    let db_track_artists = self.select_track_artist_names(db_track.id)?;
+    let sync_track_artists = sync_track.track_artists.iter().cloned().sorted().dedup().collect_vec();
+    if db_track_artists != sync_track_artists {
+      changes.push(format!("artists '{}' -> '{}'", db_track_artists.join(", "), sync_track_artists.join(", ")));
+    }
+    Ok(changes)
+  }
+}
+
+fn new_change(local_source_id: i32, track_id: Option<i32>, sync_track: &FilesystemSyncTrack, previous_file_path: Option<String>, description: String) -> SyncPreviewChange {
+  SyncPreviewChange { local_source_id, track_id, file_path: sync_track.file_path.clone(), previous_file_path, description }
+}
+
+fn push_change(changes: &mut SyncPreviewChanges, change: SyncPreviewChange) {
+  changes.count += 1;
+  if changes.sample.len() < SYNC_PREVIEW_SAMPLE_SIZE {
+    changes.sample.push(change);
+  }
+}
@@ -1,16 +1,22 @@
 use std::backtrace::Backtrace;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use diesel::prelude::*;
+use itertools::Itertools;
 use thiserror::Error;
 use tracing::{event, instrument, Level};
 
-use musium_core::model::{Album, Artist, NewSpotifyAlbum, NewSpotifyAlbumSource, NewSpotifyArtist, NewSpotifyArtistSource, NewSpotifyTrack, NewSpotifyTrackSource, NewTrack, SpotifyAlbum, SpotifyAlbumSource, SpotifyArtist, SpotifyArtistSource, SpotifySource, SpotifyTrack, SpotifyTrackSource, Track};
+use musium_core::collation::name_collation_key;
+use musium_core::model::{Album, Artist, MetadataField, NewSpotifyAlbum, NewSpotifyAlbumSource, NewSpotifyArtist, NewSpotifyArtistSource, NewSpotifyTrack, NewSpotifyTrackSource, NewTrack, SpotifyAlbum, SpotifyAlbumSource, SpotifyArtist, SpotifyArtistSource, SpotifyPlaylistSnapshot, SpotifySource, SpotifySyncState, SpotifyTrack, SpotifyTrackSource, Track, TrackArtistRole};
 use musium_core::schema;
+use musium_spotify_client::Authorization;
 
+use crate::credits::artist_roles_from_title;
 use crate::database::{DatabaseConnection, DatabaseQueryError};
-use crate::database::sync::{SelectAlbumError, SelectArtistError, SelectOrInsert, SelectOrInsertOne, SelectTrackError};
-use crate::model::{SpotifySourceEx, UpdateFrom, UpdateTrackFrom};
+use crate::database::sync::{error_chain_message, SelectAlbumError, SelectArtistError, SelectOrInsert, SelectOrInsertOne, SelectTrackError, SyncProgress};
+use crate::database::sync::merge::MetadataSource;
+use crate::model::{SpotifySourceEx, UpdateTrackFrom};
 
 #[derive(Debug, Error)]
 pub enum SpotifySyncError {
@@ -34,46 +40,148 @@ impl From<DatabaseQueryError> for SpotifySyncError {
   }
 }
 
+/// Interval between full synchronizations of a Spotify source. Synchronizations in between are incremental: they only
+/// fetch albums that were saved to the library after the last synchronization, and albums of playlists of which the
+/// snapshot changed. They do not list the albums of followed artists, do not pick up metadata changes of already linked
+/// albums, and do not remove albums, which is left to the next full synchronization.
+const FULL_SYNC_INTERVAL_DAYS: i64 = 7;
+
 impl DatabaseConnection {
-  #[instrument(skip(self, spotify_sources))]
-  pub(crate) async fn spotify_sync(&self, spotify_sources: Vec<SpotifySource>) -> Result<(), SpotifySyncError> {
-    for mut spotify_source in spotify_sources {
+  /// Synchronizes `spotify_sources`, recording the time of the synchronization or its error in each source. A failure
+  /// to synchronize one source does not prevent synchronizing the other sources. Each source reports its progress as an
+  /// equal part of `progress`.
+  #[instrument(skip(self, spotify_sources, progress))]
+  pub(crate) async fn spotify_sync(&self, spotify_sources: Vec<SpotifySource>, progress: &SyncProgress) -> Result<(), SpotifySyncError> {
+    let source_count = spotify_sources.len();
+    for (index, mut spotify_source) in spotify_sources.into_iter().enumerate() {
       let mut authorization = spotify_source.to_spotify_authorization();
-
-      let spotify_albums = self.inner.spotify_sync.get_albums_of_followed_artists(&mut authorization).await?;
-      let mut synced_album_ids = HashSet::<i32>::new();
-      let mut synced_track_ids = HashSet::<i32>::new();
-      let mut synced_artist_ids = HashSet::<i32>::new();
-      for spotify_album in spotify_albums {
-        let db_album = self.sync_spotify_album(&spotify_album, spotify_source.id)?;
-        synced_album_ids.insert(db_album.id);
-        let artist_ids: Result<HashSet<_>, _> = spotify_album.artists.iter()
-          .map(|spotify_artist| self.sync_spotify_artist(spotify_artist, spotify_source.id).map(|artist| artist.id))
-          .collect();
-        let artist_ids = artist_ids?;
-        synced_artist_ids.extend(artist_ids.iter());
-        self.sync_album_artists(&db_album, artist_ids)?;
-
-        for spotify_track in &spotify_album.tracks.items {
-          let db_track = self.sync_spotify_track(spotify_track, &db_album, spotify_source.id)?;
-          synced_track_ids.insert(db_track.id);
-          let artist_ids: Result<HashSet<_>, _> = spotify_track.artists.iter()
-            .map(|spotify_artist| self.sync_spotify_artist(spotify_artist, spotify_source.id).map(|artist| artist.id))
-            .collect();
-          let artist_ids = artist_ids?;
-          synced_artist_ids.extend(artist_ids.iter());
-          self.sync_track_artists(&db_track, artist_ids)?;
+      let now = Utc::now().naive_utc();
+      let source_progress = progress.part(index, source_count);
+      match self.spotify_sync_source(spotify_source.id, &mut authorization, now, &source_progress).await {
+        Ok(()) => {
+          spotify_source.last_synced_at = Some(now);
+          spotify_source.last_error = None;
+        }
+        Err(e) => {
+          event!(Level::ERROR, ?e, "Failed to synchronize Spotify source with ID '{}'", spotify_source.id);
+          spotify_source.last_error = Some(error_chain_message(&e));
         }
       }
-      self.cleanup_spotify_album_sources(synced_album_ids, spotify_source.id)?;
-      self.cleanup_spotify_track_sources(synced_track_ids, spotify_source.id)?;
-      self.cleanup_spotify_artist_sources(synced_artist_ids, spotify_source.id)?;
+      spotify_source.update_from_spotify_authorization(authorization);
+      event!(Level::DEBUG, ?spotify_source, "Updating synchronized Spotify source in the database");
+      spotify_source.save_changes::<SpotifySource>(&*self.connection)?;
+      source_progress.report(1.0);
+    }
+    Ok(())
+  }
 
-      if spotify_source.update_from_spotify_authorization(authorization) {
-        event!(Level::DEBUG, ?spotify_source, "Spotify source has changed, updating the database");
-        spotify_source.save_changes::<SpotifySource>(&*self.connection)?;
+  /// Synchronizes the Spotify source with `spotify_source_id`, reporting the progress of fetching albums from Spotify as
+  /// the first half of `progress`, and of synchronizing each fetched album with the database as the second half.
+  async fn spotify_sync_source(&self, spotify_source_id: i32, authorization: &mut Authorization, now: NaiveDateTime, progress: &SyncProgress) -> Result<(), SpotifySyncError> {
+    let sync_state = self.select_spotify_sync_state(spotify_source_id)?;
+    let full_synced_at = match &sync_state {
+      Some(sync_state) if now - sync_state.full_synced_at < Duration::days(FULL_SYNC_INTERVAL_DAYS) => Some(sync_state.full_synced_at),
+      _ => None,
+    };
+    let full = full_synced_at.is_none();
+    let known_album_ids = if full {
+      HashMap::new()
+    } else {
+      self.select_spotify_album_ids_by_source(spotify_source_id)?
+    };
+    event!(Level::DEBUG, full, known_albums = known_album_ids.len(), "Synchronizing Spotify source with ID '{}'", spotify_source_id);
+
+    let fetch_progress = progress.part(0, 2);
+    let mut spotify_albums = Vec::new();
+    let mut seen_spotify_album_ids = HashSet::new();
+    let unchanged_spotify_album_ids: HashSet<String> = if full {
+      // Full synchronizations fetch all albums, picking up new releases of followed artists and metadata changes, and
+      // remove albums that were not fetched.
+      for album in self.inner.spotify_sync.get_albums_of_followed_artists(authorization).await? {
+        if seen_spotify_album_ids.insert(album.id.clone()) {
+          spotify_albums.push(album);
+        }
       }
+      HashSet::new()
+    } else {
+      // Incremental synchronizations do not list the albums of followed artists, which is what most API calls of a full
+      // synchronization are spent on: known albums are kept until the next full synchronization.
+      known_album_ids.keys().cloned().collect()
+    };
+    fetch_progress.report(1.0 / 3.0);
+
+    // Albums saved to the library after the cursor, all of them on a full synchronization.
+    let saved_albums_added_after = sync_state.as_ref().filter(|_| !full).and_then(|sync_state| sync_state.saved_albums_added_at);
+    let saved_albums = self.inner.spotify_sync.get_saved_albums_added_after(saved_albums_added_after.map(|added_after| DateTime::<Utc>::from_utc(added_after, Utc)), authorization).await?;
+    // Saved albums are listed most recently saved first.
+    let saved_albums_added_at = saved_albums.first().map(|saved_album| saved_album.added_at.naive_utc()).or(saved_albums_added_after);
+    for saved_album in saved_albums {
+      if unchanged_spotify_album_ids.contains(&saved_album.album.id) || !seen_spotify_album_ids.insert(saved_album.album.id.clone()) { continue; }
+      spotify_albums.push(saved_album.album);
     }
+    fetch_progress.report(2.0 / 3.0);
+
+    // Albums of the tracks of playlists of which the snapshot changed, of all playlists on a full synchronization.
+    let known_snapshot_ids = if full { HashMap::new() } else { self.select_spotify_playlist_snapshot_ids(spotify_source_id)? };
+    let playlists = self.inner.spotify_sync.get_playlists(authorization).await?;
+    let mut playlist_album_ids = Vec::new();
+    for playlist in &playlists {
+      if known_snapshot_ids.get(&playlist.id) == Some(&playlist.snapshot_id) { continue; }
+      for album_id in self.inner.spotify_sync.get_playlist_album_ids(&playlist.id, authorization).await? {
+        if unchanged_spotify_album_ids.contains(&album_id) || !seen_spotify_album_ids.insert(album_id.clone()) { continue; }
+        playlist_album_ids.push(album_id);
+      }
+    }
+    spotify_albums.extend(self.inner.spotify_sync.get_albums(playlist_album_ids, authorization).await?);
+    fetch_progress.report(1.0);
+
+    let album_progress = progress.part(1, 2);
+    let album_count = spotify_albums.len();
+    let mut synced_album_ids = HashSet::<i32>::new();
+    let mut synced_track_ids = HashSet::<i32>::new();
+    let mut synced_artist_ids = HashSet::<i32>::new();
+    // Incremental synchronizations only fetch albums that are not known yet, so known albums are unchanged: keep them,
+    // their tracks, and their artists.
+    if !full {
+      self.select_unchanged_spotify_album_data(spotify_source_id, &mut synced_album_ids, &mut synced_track_ids, &mut synced_artist_ids)?;
+    }
+    for (index, spotify_album) in spotify_albums.into_iter().enumerate() {
+      let db_album = self.sync_spotify_album(&spotify_album, spotify_source_id)?;
+      synced_album_ids.insert(db_album.id);
+      let artist_ids: Result<HashSet<_>, _> = spotify_album.artists.iter()
+        .map(|spotify_artist| self.sync_spotify_artist(spotify_artist, spotify_source_id).map(|artist| artist.id))
+        .collect();
+      let artist_ids = artist_ids?;
+      synced_artist_ids.extend(artist_ids.iter());
+      self.sync_album_artists(&db_album, artist_ids)?;
+
+      for spotify_track in &spotify_album.tracks.items {
+        let db_track = self.sync_spotify_track(spotify_track, &db_album, spotify_source_id)?;
+        synced_track_ids.insert(db_track.id);
+        // Spotify lists featured artists and remixers as track artists, so tell them apart by the title.
+        let roles = artist_roles_from_title(&spotify_track.name, spotify_track.artists.iter().map(|spotify_artist| spotify_artist.name.as_str()));
+        let mut artist_ids_by_role: HashMap<TrackArtistRole, HashSet<i32>> = HashMap::new();
+        for (spotify_artist, role) in spotify_track.artists.iter().zip(roles) {
+          let artist_id = self.sync_spotify_artist(spotify_artist, spotify_source_id)?.id;
+          synced_artist_ids.insert(artist_id);
+          artist_ids_by_role.entry(role).or_default().insert(artist_id);
+        }
+        let artist_ids = artist_ids_by_role.remove(&TrackArtistRole::Main).unwrap_or_default();
+        if self.merge_track_artists(&db_track, MetadataSource::Spotify, &artist_ids)? {
+          self.sync_track_artists(&db_track, TrackArtistRole::Main, artist_ids)?;
+        }
+        // Only set credits that Spotify has, so that credits from tags that Spotify does not know about are kept.
+        for (role, artist_ids) in artist_ids_by_role {
+          self.sync_track_artists(&db_track, role, artist_ids)?;
+        }
+      }
+      album_progress.report((index + 1) as f32 / album_count as f32);
+    }
+    self.cleanup_spotify_album_sources(synced_album_ids, spotify_source_id)?;
+    self.cleanup_spotify_track_sources(synced_track_ids, spotify_source_id)?;
+    self.cleanup_spotify_artist_sources(synced_artist_ids, spotify_source_id)?;
+    self.save_spotify_sync_state(SpotifySyncState { spotify_source_id, full_synced_at: full_synced_at.unwrap_or(now), saved_albums_added_at })?;
+    self.save_spotify_playlist_snapshots(spotify_source_id, &playlists)?;
     Ok(())
   }
 
@@ -85,7 +193,10 @@ impl DatabaseConnection {
       Some(db_spotify_album) => {
         self.ensure_spotify_album_source_exists(db_spotify_album.album_id, spotify_source_id)?;
         let mut db_album = self.select_album_by_id(db_spotify_album.album_id)?;
-        if db_album.update_from(spotify_album) {
+        let mut changed = false;
+        self.merge_metadata(MetadataField::AlbumName, db_album.id, MetadataSource::Spotify, &mut db_album.name, spotify_album.name.clone(), &mut changed)?;
+        if changed {
+          self.update_album_derived_names(&mut db_album)?;
           db_album.save_changes::<Album>(&*self.connection)?
         } else {
           db_album
@@ -108,9 +219,15 @@ impl DatabaseConnection {
   }
 
   fn sync_spotify_album_with_existing_albums(&self, db_albums: Vec<Album>, spotify_album: &musium_spotify_client::Album, spotify_source_id: i32) -> Result<Album, SpotifySyncError> {
-    // Album(s) with same name already exist: find one without an associated Spotify album and associate it.
+    // Album(s) with same name already exist: find one with the same album artists (or without album artists) and without
+    // an associated Spotify album, and associate it.
+    let spotify_artist_name_keys = spotify_album.artists.iter().map(|a| name_collation_key(&a.name)).sorted().dedup().collect_vec();
     for db_album in db_albums {
       // OPTO: select all relevant data in a single query.
+      let db_album_artist_name_keys = self.select_album_artist_name_keys(db_album.id)?;
+      if !db_album_artist_name_keys.is_empty() && db_album_artist_name_keys != spotify_artist_name_keys {
+        continue; // Different album with the same name: skip.
+      }
       if let Some(_) = self.select_spotify_album_by_album_id(db_album.id)? {
         continue; // Already associated: skip.
       } else {
@@ -135,7 +252,7 @@ impl DatabaseConnection {
       Some(db_spotify_track) => {
         self.ensure_spotify_track_source_exists(db_spotify_track.track_id, spotify_source_id)?;
         let mut db_track = self.select_track_by_id(db_spotify_track.track_id)?;
-        if db_track.update_from(album, spotify_track) {
+        if self.merge_track_from_spotify(&mut db_track, album, spotify_track)? {
           db_track.save_changes::<Track>(&*self.connection)?
         } else {
           db_track
@@ -151,6 +268,7 @@ impl DatabaseConnection {
           track_number,
           |default_new_track| {
             NewTrack {
+              explicit: Some(spotify_track.explicit),
               ..default_new_track
             }
           },
@@ -182,7 +300,10 @@ impl DatabaseConnection {
       Some(db_spotify_artist) => {
         self.ensure_spotify_artist_source_exists(db_spotify_artist.artist_id, spotify_source_id)?;
         let mut db_artist = self.select_artist_by_id(db_spotify_artist.artist_id)?;
-        if db_artist.update_from(spotify_artist) {
+        let mut changed = false;
+        self.merge_metadata(MetadataField::ArtistName, db_artist.id, MetadataSource::Spotify, &mut db_artist.name, spotify_artist.name.clone(), &mut changed)?;
+        if changed {
+          self.update_artist_derived_names(&mut db_artist)?;
           db_artist.save_changes::<Artist>(&*self.connection)?
         } else {
           db_artist
@@ -224,6 +345,94 @@ impl DatabaseConnection {
     Ok(db_artist)
   }
 
+  // Incremental sync
+
+  /// Selects the IDs of albums linked to the Spotify source with `input_spotify_source_id`, keyed by their Spotify ID.
+  fn select_spotify_album_ids_by_source(&self, input_spotify_source_id: i32) -> Result<HashMap<String, i32>, SpotifySyncError> {
+    let source_album_ids: HashSet<i32> = {
+      use schema::spotify_album_source::dsl::*;
+      let query = spotify_album_source.select(album_id).filter(spotify_source_id.eq(input_spotify_source_id));
+      time!("select_spotify_album_ids_by_source.select_sources", query.load::<i32>(&self.connection)?).into_iter().collect()
+    };
+    let db_spotify_albums: Vec<(i32, String)> = {
+      use schema::spotify_album::dsl::*;
+      time!("select_spotify_album_ids_by_source.select_albums", spotify_album.select((album_id, spotify_id)).load(&self.connection)?)
+    };
+    Ok(db_spotify_albums.into_iter()
+      .filter(|(db_album_id, _)| source_album_ids.contains(db_album_id))
+      .map(|(db_album_id, db_spotify_id)| (db_spotify_id, db_album_id))
+      .collect())
+  }
+
+  /// Selects the albums linked to the Spotify source with `input_spotify_source_id`, and their tracks and artists, which
+  /// an incremental synchronization keeps unchanged. Albums and tracks are selected with subqueries, as binding their IDs
+  /// exceeds the maximum number of bound variables of SQLite for large libraries.
+  fn select_unchanged_spotify_album_data(
+    &self,
+    input_spotify_source_id: i32,
+    synced_album_ids: &mut HashSet<i32>,
+    synced_track_ids: &mut HashSet<i32>,
+    synced_artist_ids: &mut HashSet<i32>,
+  ) -> Result<(), SpotifySyncError> {
+    let source_album_ids = {
+      use schema::spotify_album::dsl::*;
+      let linked_album_ids = {
+        use schema::spotify_album_source::dsl::*;
+        spotify_album_source.select(album_id).filter(spotify_source_id.eq(input_spotify_source_id))
+      };
+      spotify_album.select(album_id).filter(album_id.eq_any(linked_album_ids))
+    };
+    let source_track_ids = {
+      use schema::track::dsl::*;
+      track.select(id).filter(album_id.eq_any(source_album_ids.clone()))
+    };
+    let album_ids: Vec<i32> = time!("select_unchanged_spotify_album_data.select_albums", source_album_ids.clone().load(&self.connection)?);
+    let track_ids: Vec<i32> = time!("select_unchanged_spotify_album_data.select_tracks", source_track_ids.clone().load(&self.connection)?);
+    let album_artist_ids: Vec<i32> = {
+      use schema::album_artist::dsl::*;
+      time!("select_unchanged_spotify_album_data.select_album_artists", album_artist.select(artist_id).filter(album_id.eq_any(source_album_ids)).distinct().load(&self.connection)?)
+    };
+    let track_artist_ids: Vec<i32> = {
+      use schema::track_artist::dsl::*;
+      time!("select_unchanged_spotify_album_data.select_track_artists", track_artist.select(artist_id).filter(track_id.eq_any(source_track_ids)).distinct().load(&self.connection)?)
+    };
+    synced_album_ids.extend(album_ids);
+    synced_track_ids.extend(track_ids);
+    synced_artist_ids.extend(album_artist_ids);
+    synced_artist_ids.extend(track_artist_ids);
+    Ok(())
+  }
+
+  fn select_spotify_sync_state(&self, input_spotify_source_id: i32) -> Result<Option<SpotifySyncState>, SpotifySyncError> {
+    use schema::spotify_sync_state::dsl::*;
+    Ok(time!("select_spotify_sync_state.select", spotify_sync_state.find(input_spotify_source_id).first::<SpotifySyncState>(&self.connection).optional()?))
+  }
+
+  fn save_spotify_sync_state(&self, sync_state: SpotifySyncState) -> Result<(), SpotifySyncError> {
+    use schema::spotify_sync_state::dsl::*;
+    event!(Level::DEBUG, ?sync_state, "Saving Spotify sync state");
+    time!("save_spotify_sync_state.replace", diesel::replace_into(spotify_sync_state).values(sync_state).execute(&self.connection)?);
+    Ok(())
+  }
+
+  fn select_spotify_playlist_snapshot_ids(&self, input_spotify_source_id: i32) -> Result<HashMap<String, String>, SpotifySyncError> {
+    use schema::spotify_playlist_snapshot::dsl::*;
+    let query = spotify_playlist_snapshot.select((playlist_id, snapshot_id)).filter(spotify_source_id.eq(input_spotify_source_id));
+    Ok(time!("select_spotify_playlist_snapshot_ids.select", query.load::<(String, String)>(&self.connection)?).into_iter().collect())
+  }
+
+  /// Replaces the playlist snapshots of the Spotify source with `input_spotify_source_id` with the snapshots of
+  /// `playlists`, so that snapshots of playlists that the user no longer has are removed.
+  fn save_spotify_playlist_snapshots(&self, input_spotify_source_id: i32, playlists: &[musium_spotify_client::PlaylistSimple]) -> Result<(), SpotifySyncError> {
+    use schema::spotify_playlist_snapshot::dsl::*;
+    time!("save_spotify_playlist_snapshots.delete", diesel::delete(spotify_playlist_snapshot.filter(spotify_source_id.eq(input_spotify_source_id))).execute(&self.connection)?);
+    let snapshots = playlists.iter()
+      .map(|playlist| SpotifyPlaylistSnapshot { spotify_source_id: input_spotify_source_id, playlist_id: playlist.id.clone(), snapshot_id: playlist.snapshot_id.clone() })
+      .collect_vec();
+    time!("save_spotify_playlist_snapshots.insert", diesel::insert_into(spotify_playlist_snapshot).values(&snapshots).execute(&self.connection)?);
+    Ok(())
+  }
+
   // Cleanup
 
   fn cleanup_spotify_album_sources(&self, synced_album_ids: HashSet::<i32>, input_spotify_source_id: i32) -> Result<(), SpotifySyncError> {
@@ -0,0 +1,190 @@
+use std::backtrace::Backtrace;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::format_error::FormatError;
+use musium_core::model::SyncLock;
+use musium_core::schema;
+
+use super::{Database, DatabaseConnection, DatabaseQueryError};
+
+/// ID of the single row of the sync lock table.
+const SYNC_LOCK_ID: i32 = 1;
+
+/// Duration, in minutes, of the lease on the sync lock. A sync lock that was not renewed within this duration, for
+/// example because the instance holding it crashed, is considered stale and can be taken over by another instance.
+pub const SYNC_LOCK_LEASE_MINUTES: i64 = 10;
+
+/// Interval, in seconds, at which the instance holding the sync lock renews its lease while syncing. Must be well within
+/// the lease, so that the lease of a running sync does not expire.
+pub const SYNC_LOCK_HEARTBEAT_SECONDS: u64 = 60;
+
+// Acquiring and releasing
+
+#[derive(Debug, Error)]
+pub enum SyncLockError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Sync lock is held by another server instance '{0}' until '{1}'")]
+  HeldByOtherInstance(String, NaiveDateTime),
+}
+
+impl DatabaseConnection {
+  /// Acquires the sync lock for the server instance identified by `input_holder`, or renews it if that instance
+  /// already holds it. Fails if another instance holds a lock that has not expired yet.
+  pub fn acquire_sync_lock(&self, input_holder: &str) -> Result<SyncLock, SyncLockError> {
+    self.acquire_sync_lock_with_lease(input_holder, Duration::minutes(SYNC_LOCK_LEASE_MINUTES))
+  }
+
+  fn acquire_sync_lock_with_lease(&self, input_holder: &str, lease: Duration) -> Result<SyncLock, SyncLockError> {
+    // Use an immediate transaction to acquire the database write lock before reading, so that other processes cannot
+    // acquire the sync lock in between reading and writing.
+    self.connection.immediate_transaction::<_, SyncLockError, _>(|| {
+      use schema::sync_lock::dsl::*;
+      let now = Utc::now().naive_utc();
+      let current: Option<SyncLock> = time!("acquire_sync_lock.select", sync_lock.find(SYNC_LOCK_ID).first(&self.connection).optional()?);
+      if let Some(current) = current {
+        if current.holder != input_holder && current.expires_at > now {
+          return Err(SyncLockError::HeldByOtherInstance(current.holder, current.expires_at));
+        }
+        if current.holder != input_holder {
+          event!(Level::WARN, ?current, "Taking over stale sync lock");
+        }
+      }
+      let lock = SyncLock {
+        id: SYNC_LOCK_ID,
+        holder: input_holder.to_string(),
+        acquired_at: now,
+        expires_at: now + lease,
+      };
+      time!("acquire_sync_lock.replace", diesel::replace_into(sync_lock).values(&lock).execute(&self.connection)?);
+      Ok(lock)
+    })
+  }
+
+  /// Renews the lease on the sync lock if it is held by the server instance identified by `input_holder`. Returns
+  /// whether the lease was renewed.
+  pub fn renew_sync_lock(&self, input_holder: &str) -> Result<bool, DatabaseQueryError> {
+    self.renew_sync_lock_with_lease(input_holder, Duration::minutes(SYNC_LOCK_LEASE_MINUTES))
+  }
+
+  fn renew_sync_lock_with_lease(&self, input_holder: &str, lease: Duration) -> Result<bool, DatabaseQueryError> {
+    use schema::sync_lock::dsl::*;
+    let renewed = time!("renew_sync_lock.update", diesel::update(sync_lock.filter(id.eq(SYNC_LOCK_ID)).filter(holder.eq(input_holder)))
+      .set(expires_at.eq(Utc::now().naive_utc() + lease))
+      .execute(&self.connection)?);
+    Ok(renewed > 0)
+  }
+
+  /// Releases the sync lock if it is held by the server instance identified by `input_holder`. Returns whether the
+  /// lock was released.
+  pub fn release_sync_lock(&self, input_holder: &str) -> Result<bool, DatabaseQueryError> {
+    use schema::sync_lock::dsl::*;
+    let deleted = time!("release_sync_lock.delete", diesel::delete(sync_lock.filter(id.eq(SYNC_LOCK_ID)).filter(holder.eq(input_holder))).execute(&self.connection)?);
+    Ok(deleted > 0)
+  }
+}
+
+// Heartbeat
+
+/// Periodically renews the lease on the sync lock held by a server instance on a separate thread, so that a sync that
+/// runs longer than the lease is not taken over by another instance. Renewing fails while the sync holds the write lock
+/// of the database, but other instances cannot take over the sync lock then either, as that requires the write lock as
+/// well. Stops renewing when dropped.
+pub struct SyncLockHeartbeat {
+  _stop_tx: mpsc::Sender<()>,
+}
+
+impl SyncLockHeartbeat {
+  pub fn spawn(database: Database, holder: String) -> Self {
+    Self::spawn_with_lease(database, holder, std::time::Duration::from_secs(SYNC_LOCK_HEARTBEAT_SECONDS), Duration::minutes(SYNC_LOCK_LEASE_MINUTES))
+  }
+
+  fn spawn_with_lease(database: Database, holder: String, interval: std::time::Duration, lease: Duration) -> Self {
+    let (stop_tx, stop_rx) = mpsc::channel();
+    thread::spawn(move || {
+      // Dropping the heartbeat drops the sender, which disconnects the channel and stops the thread.
+      while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+        let result = database.connect()
+          .map_err(|e| event!(Level::WARN, "Failed to renew sync lock: {:?}", FormatError::new(&e)))
+          .and_then(|c| c.renew_sync_lock_with_lease(&holder, lease).map_err(|e| event!(Level::WARN, "Failed to renew sync lock: {:?}", FormatError::new(&e))));
+        if let Ok(false) = result {
+          event!(Level::WARN, %holder, "Sync lock was taken over by another server instance while syncing");
+          break;
+        }
+      }
+    });
+    Self { _stop_tx: stop_tx }
+  }
+}
+
+// Administration
+
+impl DatabaseConnection {
+  pub fn get_sync_lock(&self) -> Result<Option<SyncLock>, DatabaseQueryError> {
+    use schema::sync_lock::dsl::*;
+    Ok(time!("get_sync_lock.select", sync_lock.find(SYNC_LOCK_ID).first(&self.connection).optional()?))
+  }
+
+  /// Forcibly releases the sync lock regardless of which instance holds it, for recovering from a stale lock. Returns
+  /// the released lock, or `None` if no lock was held.
+  pub fn force_release_sync_lock(&self) -> Result<Option<SyncLock>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::sync_lock::dsl::*;
+      let lock: Option<SyncLock> = time!("force_release_sync_lock.select", sync_lock.find(SYNC_LOCK_ID).first(&self.connection).optional()?);
+      if let Some(lock) = &lock {
+        event!(Level::WARN, ?lock, "Forcibly releasing sync lock");
+        time!("force_release_sync_lock.delete", diesel::delete(sync_lock.find(SYNC_LOCK_ID)).execute(&self.connection)?);
+      }
+      Ok(lock)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration as StdDuration;
+
+  use super::*;
+  use crate::database::test_database::TestDatabase;
+
+  const LEASE_MILLISECONDS: i64 = 500;
+
+  #[test]
+  fn lock_held_by_other_instance_is_not_taken_over() {
+    let test_database = TestDatabase::new();
+    let connection = test_database.database.connect().unwrap();
+    connection.acquire_sync_lock("a").unwrap();
+    assert!(matches!(connection.acquire_sync_lock("b"), Err(SyncLockError::HeldByOtherInstance(..))));
+  }
+
+  #[test]
+  fn stale_lock_is_taken_over() {
+    let test_database = TestDatabase::new();
+    let connection = test_database.database.connect().unwrap();
+    let lease = Duration::milliseconds(LEASE_MILLISECONDS);
+    connection.acquire_sync_lock_with_lease("a", lease).unwrap();
+    thread::sleep(StdDuration::from_millis(2 * LEASE_MILLISECONDS as u64));
+    assert_eq!(connection.acquire_sync_lock_with_lease("b", lease).unwrap().holder, "b");
+  }
+
+  #[test]
+  fn lock_is_not_taken_over_while_heartbeat_is_active() {
+    let test_database = TestDatabase::new();
+    let connection = test_database.database.connect().unwrap();
+    let lease = Duration::milliseconds(LEASE_MILLISECONDS);
+    connection.acquire_sync_lock_with_lease("a", lease).unwrap();
+    let heartbeat = SyncLockHeartbeat::spawn_with_lease(test_database.database.clone(), "a".to_string(), StdDuration::from_millis(LEASE_MILLISECONDS as u64 / 5), lease);
+    thread::sleep(StdDuration::from_millis(4 * LEASE_MILLISECONDS as u64));
+    assert!(matches!(connection.acquire_sync_lock_with_lease("b", lease), Err(SyncLockError::HeldByOtherInstance(..))));
+
+    drop(heartbeat);
+    thread::sleep(StdDuration::from_millis(2 * LEASE_MILLISECONDS as u64));
+    assert_eq!(connection.acquire_sync_lock_with_lease("b", lease).unwrap().holder, "b");
+  }
+}
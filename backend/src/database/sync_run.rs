@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use tracing::{event, Level};
+
+use musium_core::model::{NewSyncRun, SyncRun, SyncRunOutcome, SyncSourceKind, Track};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Number of days sync runs are kept in the sync history.
+pub const SYNC_RUN_RETENTION_DAYS: i64 = 90;
+
+/// Sources that are synchronized by a synchronization.
+#[derive(Copy, Clone, Debug)]
+pub enum SyncScope {
+  All,
+  LocalSources,
+  LocalSource(i32),
+  SpotifySources,
+  SpotifySource(i32),
+}
+
+/// Tracks of the sources in a sync scope before synchronizing them, to count how the tracks changed after
+/// synchronizing.
+pub struct SyncSnapshot {
+  started_at: NaiveDateTime,
+  sources: Vec<(SyncSourceKind, i32, HashMap<i32, Track>)>,
+}
+
+/// Numbers of tracks that were added, updated, and removed by a synchronization, summed over the recorded sync runs.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SyncRunCounts {
+  pub added: i32,
+  pub updated: i32,
+  pub removed: i32,
+}
+
+// Recording
+
+impl DatabaseConnection {
+  /// Takes a snapshot of the tracks of the sources in `scope`, before synchronizing them.
+  pub fn snapshot_sync_sources(&self, scope: SyncScope) -> Result<SyncSnapshot, DatabaseQueryError> {
+    let started_at = Utc::now().naive_utc();
+    let mut sources = Vec::new();
+    for (source_kind, source_id) in self.list_sync_scope_sources(scope)? {
+      sources.push((source_kind, source_id, self.select_sync_source_tracks(source_kind, source_id)?));
+    }
+    Ok(SyncSnapshot { started_at, sources })
+  }
+
+  /// Records a sync run for each source in `snapshot`, after synchronizing them. `result` is either the IDs of local
+  /// sources that were skipped, or the error message of a failed synchronization. Spotify sources that failed to
+  /// synchronize on their own are recorded with the error stored in the source. Returns the numbers of tracks that were
+  /// added, updated, and removed, summed over all sources.
+  pub fn record_sync_runs(&self, snapshot: SyncSnapshot, result: Result<&[i32], String>) -> Result<SyncRunCounts, DatabaseQueryError> {
+    let ended_at = Utc::now().naive_utc();
+    let mut counts = SyncRunCounts::default();
+    for (source_kind, source_id, tracks_before) in snapshot.sources {
+      let tracks_after = self.select_sync_source_tracks(source_kind, source_id)?;
+      let added = tracks_after.keys().filter(|id| !tracks_before.contains_key(*id)).count() as i32;
+      let removed = tracks_before.keys().filter(|id| !tracks_after.contains_key(*id)).count() as i32;
+      let updated = tracks_after.iter()
+        .filter(|(id, track)| tracks_before.get(*id).map_or(false, |track_before| track_before != *track))
+        .count() as i32;
+      let (outcome, error) = match (&result, source_kind) {
+        (Err(error), _) => (SyncRunOutcome::Failed, Some(error.clone())),
+        (Ok(skipped_local_source_ids), SyncSourceKind::Local) if skipped_local_source_ids.contains(&source_id) => (SyncRunOutcome::Skipped, None),
+        (Ok(_), SyncSourceKind::Spotify) => match self.get_spotify_source_by_id(source_id)?.and_then(|spotify_source| spotify_source.last_error) {
+          Some(error) => (SyncRunOutcome::Failed, Some(error)),
+          None => (SyncRunOutcome::Completed, None),
+        },
+        (Ok(_), _) => (SyncRunOutcome::Completed, None),
+      };
+      counts.added += added;
+      counts.updated += updated;
+      counts.removed += removed;
+      let new_sync_run = NewSyncRun { source_kind, source_id, started_at: snapshot.started_at, ended_at, added, updated, removed, outcome, error };
+      event!(Level::DEBUG, ?new_sync_run, "Recording sync run");
+      time!("record_sync_runs.insert", diesel::insert_into(schema::sync_run::table).values(new_sync_run).execute(&self.connection)?);
+    }
+    self.purge_expired_sync_runs()?;
+    Ok(counts)
+  }
+
+  fn list_sync_scope_sources(&self, scope: SyncScope) -> Result<Vec<(SyncSourceKind, i32)>, DatabaseQueryError> {
+    let local_sources = || -> Result<Vec<(SyncSourceKind, i32)>, DatabaseQueryError> {
+      Ok(self.list_local_sources()?.into_iter().map(|s| (SyncSourceKind::Local, s.id)).collect())
+    };
+    let spotify_sources = || -> Result<Vec<(SyncSourceKind, i32)>, DatabaseQueryError> {
+      Ok(self.list_spotify_sources()?.into_iter().map(|s| (SyncSourceKind::Spotify, s.id)).collect())
+    };
+    // Deleted sources are not synchronized, so they are not recorded either.
+    let sources = match scope {
+      SyncScope::All => local_sources()?.into_iter().chain(spotify_sources()?).collect(),
+      SyncScope::LocalSources => local_sources()?,
+      SyncScope::LocalSource(id) => self.get_local_source_by_id(id)?.filter(|s| s.deleted_at.is_none()).map(|s| (SyncSourceKind::Local, s.id)).into_iter().collect(),
+      SyncScope::SpotifySources => spotify_sources()?,
+      SyncScope::SpotifySource(id) => self.get_spotify_source_by_id(id)?.filter(|s| s.deleted_at.is_none()).map(|s| (SyncSourceKind::Spotify, s.id)).into_iter().collect(),
+    };
+    Ok(sources)
+  }
+
+  fn select_sync_source_tracks(&self, source_kind: SyncSourceKind, source_id: i32) -> Result<HashMap<i32, Track>, DatabaseQueryError> {
+    use schema::{local_track, spotify_track_source, track};
+    let tracks: Vec<Track> = match source_kind {
+      SyncSourceKind::Local => time!("select_sync_source_tracks.select_local", track::table
+        .inner_join(local_track::table)
+        .filter(local_track::local_source_id.eq(source_id))
+        .filter(local_track::file_path.is_not_null())
+        .select(track::all_columns)
+        .load(&self.connection)?),
+      SyncSourceKind::Spotify => time!("select_sync_source_tracks.select_spotify", track::table
+        .inner_join(spotify_track_source::table)
+        .filter(spotify_track_source::spotify_source_id.eq(source_id))
+        .select(track::all_columns)
+        .load(&self.connection)?),
+    };
+    Ok(tracks.into_iter().map(|track| (track.id, track)).collect())
+  }
+
+  fn purge_expired_sync_runs(&self) -> Result<(), DatabaseQueryError> {
+    use schema::sync_run::dsl::*;
+    let expired_before = Utc::now().naive_utc() - Duration::days(SYNC_RUN_RETENTION_DAYS);
+    time!("purge_expired_sync_runs.delete", diesel::delete(sync_run.filter(ended_at.lt(expired_before))).execute(&self.connection)?);
+    Ok(())
+  }
+}
+
+// Listing
+
+impl DatabaseConnection {
+  /// Lists the sync runs of the last `SYNC_RUN_RETENTION_DAYS` days, most recent first.
+  pub fn list_sync_runs(&self) -> Result<Vec<SyncRun>, DatabaseQueryError> {
+    use schema::sync_run::dsl::*;
+    Ok(time!("list_sync_runs.select", sync_run.order((started_at.desc(), id.desc())).load::<SyncRun>(&self.connection)?))
+  }
+}
@@ -0,0 +1,106 @@
+use diesel::prelude::*;
+use tracing::{event, Level};
+
+use musium_core::api::AssignedTag;
+use musium_core::model::{NewTag, Tag, TrackTag};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Querying
+
+impl DatabaseConnection {
+  pub fn list_tags(&self) -> Result<Vec<Tag>, DatabaseQueryError> {
+    use schema::tag::dsl::*;
+    Ok(time!("list_tags.select", tag.order(name.asc()).load::<Tag>(&self.connection)?))
+  }
+
+  pub fn list_track_tags(&self, input_track_id: i32) -> Result<Vec<AssignedTag>, DatabaseQueryError> {
+    use schema::track_tag::dsl::*;
+    let track_tags: Vec<(TrackTag, Tag)> = time!("list_track_tags.select", track_tag
+      .filter(track_id.eq(input_track_id))
+      .inner_join(schema::tag::table)
+      .order(schema::tag::name.asc())
+      .load(&self.connection)?);
+    Ok(track_tags.into_iter().map(|(track_tag, tag)| AssignedTag { tag, machine_generated: track_tag.machine_generated }).collect())
+  }
+}
+
+// Managing
+
+impl DatabaseConnection {
+  /// Tags the track with `input_track_id` with the tag named `input_name`, creating the tag if it does not exist. If the
+  /// track already has the tag as a machine-generated tag, it becomes a user-assigned tag.
+  pub fn add_track_tag(&self, input_track_id: i32, input_name: &str) -> Result<TrackTag, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      let tag = self.get_or_create_tag(input_name)?;
+      let new_track_tag = TrackTag { track_id: input_track_id, tag_id: tag.id, machine_generated: false };
+      event!(Level::DEBUG, ?new_track_tag, "Inserting track tag");
+      use schema::track_tag::dsl::*;
+      time!("add_track_tag.replace", diesel::replace_into(track_tag).values(new_track_tag).execute(&self.connection)?);
+      Ok(new_track_tag)
+    })
+  }
+
+  /// Tags the tracks with `input_track_ids` with the tag named `input_name`, creating the tag if it does not exist.
+  /// Tracks that do not exist are skipped.
+  pub fn add_tracks_tag(&self, input_track_ids: &[i32], input_name: &str) -> Result<Vec<TrackTag>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      let tag = self.get_or_create_tag(input_name)?;
+      let new_track_tags: Vec<_> = self.filter_existing_track_ids(input_track_ids)?.into_iter()
+        .map(|input_track_id| TrackTag { track_id: input_track_id, tag_id: tag.id, machine_generated: false })
+        .collect();
+      event!(Level::DEBUG, ?new_track_tags, "Inserting track tags");
+      use schema::track_tag::dsl::*;
+      for new_track_tag in &new_track_tags {
+        time!("add_tracks_tag.replace", diesel::replace_into(track_tag).values(new_track_tag).execute(&self.connection)?);
+      }
+      Ok(new_track_tags)
+    })
+  }
+
+  /// Removes the tag named `input_name` from the track with `input_track_id`, returning `false` if the track did not have
+  /// the tag.
+  pub fn remove_track_tag(&self, input_track_id: i32, input_name: &str) -> Result<bool, DatabaseQueryError> {
+    let input_tag_id: Option<i32> = {
+      use schema::tag::dsl::*;
+      time!("remove_track_tag.select_tag", tag.select(id).filter(name.eq(input_name)).first(&self.connection).optional()?)
+    };
+    let input_tag_id = match input_tag_id {
+      Some(input_tag_id) => input_tag_id,
+      None => return Ok(false),
+    };
+    use schema::track_tag::dsl::*;
+    Ok(time!("remove_track_tag.delete", diesel::delete(track_tag.find((input_track_id, input_tag_id))).execute(&self.connection)?) > 0)
+  }
+
+  /// Replaces the machine-generated tags of the track with `input_track_id` with tags named `names`. Tags assigned by
+  /// users are kept.
+  pub fn set_machine_generated_track_tags(&self, input_track_id: i32, names: &[&str]) -> Result<(), DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      {
+        use schema::track_tag::dsl::*;
+        time!("set_machine_generated_track_tags.delete", diesel::delete(track_tag.filter(track_id.eq(input_track_id)).filter(machine_generated.eq(true))).execute(&self.connection)?);
+      }
+      for input_name in names {
+        let tag = self.get_or_create_tag(input_name)?;
+        let new_track_tag = TrackTag { track_id: input_track_id, tag_id: tag.id, machine_generated: true };
+        use schema::track_tag::dsl::*;
+        time!("set_machine_generated_track_tags.insert", diesel::insert_or_ignore_into(track_tag).values(new_track_tag).execute(&self.connection)?);
+      }
+      Ok(())
+    })
+  }
+
+  fn get_or_create_tag(&self, input_name: &str) -> Result<Tag, diesel::result::Error> {
+    use schema::tag::dsl::*;
+    if let Some(db_tag) = time!("get_or_create_tag.select", tag.filter(name.eq(input_name)).first::<Tag>(&self.connection).optional()?) {
+      return Ok(db_tag);
+    }
+    let new_tag = NewTag { name: input_name.to_string() };
+    event!(Level::DEBUG, ?new_tag, "Inserting tag");
+    time!("get_or_create_tag.insert", diesel::insert_into(tag).values(new_tag).execute(&self.connection)?);
+    // NOTE: must be executed in a transaction for consistency
+    time!("get_or_create_tag.select_inserted", tag.order(id.desc()).first(&self.connection))
+  }
+}
@@ -0,0 +1,88 @@
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use diesel::prelude::*;
+use itertools::Itertools;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{Album, LocalSource, LocalTrack};
+use musium_core::schema;
+
+use crate::beets::ReadBeetsLibraryError;
+use crate::database::sync::local::LocalSyncError;
+use crate::model::LocalSourceEx;
+use crate::tagger_hook::{RunTaggerHookError, TaggerHookSettings};
+
+use super::DatabaseConnection;
+
+#[derive(Debug, Error)]
+pub enum TaggerHookError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to run tagger hook")]
+  RunFail(#[from] RunTaggerHookError, Backtrace),
+  #[error("Failed to read beets library")]
+  ReadBeetsLibraryFail(#[from] ReadBeetsLibraryError, Backtrace),
+  #[error("Failed to synchronize re-read file")]
+  SyncFail(#[from] LocalSyncError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Runs the tagger hook of `tagger_hook` for the album with `album_id`, and then reads the tags of the local files of
+  /// the album again. Files that the hook moved are picked up by the next sync. Returns the number of files that were
+  /// read again, which is zero when the album has no local files.
+  #[instrument(skip(self, tagger_hook))]
+  pub fn run_tagger_hook(&self, tagger_hook: &TaggerHookSettings, album_id: i32) -> Result<usize, TaggerHookError> {
+    let db_album: Album = {
+      use schema::album::dsl::*;
+      time!("run_tagger_hook.select_album", album.find(album_id).first(&self.connection)?)
+    };
+    let local_tracks: Vec<(LocalTrack, LocalSource)> = {
+      use schema::{local_source, local_track, track};
+      time!("run_tagger_hook.select_local_tracks", local_track::table
+        .inner_join(track::table)
+        .inner_join(local_source::table)
+        .filter(track::album_id.eq(album_id))
+        .filter(local_source::deleted_at.is_null())
+        .select((local_track::all_columns, local_source::all_columns))
+        .load(&self.connection)?)
+    };
+    let files: Vec<(LocalSource, PathBuf)> = local_tracks.into_iter()
+      .filter_map(|(local_track, local_source)| local_source.track_file_path(&local_track).map(|path| (local_source, path)))
+      .collect();
+    if files.is_empty() {
+      return Ok(0);
+    }
+    let directories: Vec<PathBuf> = files.iter()
+      .filter_map(|(_, path)| path.parent().map(|parent| parent.to_path_buf()))
+      .unique()
+      .collect();
+    tagger_hook.run(db_album.id, &db_album.name, &directories)?;
+
+    let beets_library = self.read_beets_library()?;
+    let mut read_count = 0;
+    for (local_source, path) in files {
+      // Skip files that the hook moved or removed; the next sync picks these up.
+      if !path.is_file() { continue; }
+      let mut local_sync_track = match musium_filesystem_sync::sync_file(&local_source.directory, &path) {
+        Some(Ok(local_sync_track)) => local_sync_track,
+        Some(Err(e)) => {
+          event!(Level::WARN, path = %path.display(), error = ?e, "Failed to read file after running tagger hook");
+          continue;
+        }
+        None => continue,
+      };
+      if let Some(beets_library) = &beets_library {
+        beets_library.apply(&local_source.directory, &mut local_sync_track);
+      }
+      self.connection.transaction::<_, TaggerHookError, _>(|| {
+        self.sync_local_sync_track(local_source.id, &local_sync_track)?;
+        Ok(())
+      })?;
+      read_count += 1;
+    }
+    event!(Level::DEBUG, read_count, "Read files again after running tagger hook");
+    Ok(read_count)
+  }
+}
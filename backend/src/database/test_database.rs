@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use diesel_migrations::run_pending_migrations_in_directory;
+
+use musium_spotify_client::SpotifyClient;
+
+use crate::password::PasswordHasher;
+
+use super::Database;
+use super::health::MIGRATIONS_DIRECTORY;
+
+/// Database in a new temporary file with all migrations applied, for tests. The file is deleted when dropped.
+pub struct TestDatabase {
+  pub database: Database,
+  path: PathBuf,
+}
+
+impl TestDatabase {
+  pub fn new() -> Self {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = format!("musium_test_{}_{}.db", std::process::id(), COUNTER.fetch_add(1, Ordering::SeqCst));
+    let path = std::env::temp_dir().join(file_name);
+    let spotify_sync = SpotifyClient::new_from_client_id_secret(String::new(), String::new()).unwrap();
+    let password_hasher = PasswordHasher::new("test");
+    let database = Database::new(path.to_string_lossy(), spotify_sync, password_hasher, i64::MAX, None).unwrap();
+    let connection = database.connect().unwrap();
+    run_pending_migrations_in_directory(&*connection.connection, Path::new(MIGRATIONS_DIRECTORY), &mut std::io::sink()).unwrap();
+    Self { database, path }
+  }
+}
+
+impl Drop for TestDatabase {
+  fn drop(&mut self) {
+    std::fs::remove_file(&self.path).ok(); // OK: the file is temporary.
+  }
+}
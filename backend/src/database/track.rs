@@ -1,10 +1,38 @@
+use std::collections::HashSet;
+
 use diesel::prelude::*;
+use diesel::sql_types::Integer;
+use diesel::sqlite::Sqlite;
+use itertools::Itertools;
+use rand::Rng;
+use rand::seq::SliceRandom;
 
-use musium_core::model::{Album, AlbumArtist, Artist, Track, TrackArtist};
+use musium_core::api::{LocalTrackFileInfo, PageQuery, PlayTracksQuery, TrackFilter, TrackSearch, TrackSort, TrackSortColumn, TrackTechnicalInfo, TracksPage};
+use musium_core::collation::name_collation_key;
+use musium_core::format_error::FormatError;
+use musium_core::model::{Album, AlbumArtist, Artist, ContentFilter, LocalSource, LocalTrack, SpotifyTrack, Track, TrackArtist, TrackArtistRole};
 use musium_core::model::collection::TracksRaw;
 use musium_core::schema;
 
+use crate::model::LocalSourceEx;
+use crate::track_inspection::inspect_track_file;
+
 use super::{DatabaseConnection, DatabaseQueryError};
+use super::track_filter::select_matching_track_ids;
+
+/// Number of tracks in a page when the page query has no limit.
+pub const DEFAULT_TRACKS_PAGE_LIMIT: i64 = 250;
+/// Maximum number of tracks in a page.
+pub const MAX_TRACKS_PAGE_LIMIT: i64 = 500;
+/// Number of tracks that are sampled when the number of random tracks is not given.
+pub const DEFAULT_RANDOM_TRACKS_COUNT: i64 = 50;
+/// Maximum number of tracks that are sampled at once.
+pub const MAX_RANDOM_TRACKS_COUNT: i64 = 500;
+/// Fraction by which the shuffle weight of a track is lowered when the user skipped all of its plays, in proportion to
+/// how often it was skipped.
+const SKIPPED_SHUFFLE_DOWNRANK: f64 = 0.9;
+
+no_arg_sql_function!(random, Integer, "Represents the SQL RANDOM() function");
 
 impl DatabaseConnection {
   pub fn list_tracks(&self) -> Result<TracksRaw, DatabaseQueryError> {
@@ -13,11 +41,209 @@ impl DatabaseConnection {
     let artists = schema::artist::table.load::<Artist>(&self.connection)?;
     let track_artists = schema::track_artist::table.load::<TrackArtist>(&self.connection)?;
     let album_artists = schema::album_artist::table.load::<AlbumArtist>(&self.connection)?;
-    Ok(TracksRaw { albums, tracks, artists, album_artists, track_artists })
+    let availability = self.get_track_availability()?;
+    Ok(TracksRaw { albums, tracks, artists, album_artists, track_artists, availability })
+  }
+
+  /// Lists the tracks in `page` of the tracks matching `filter`, sorted by `sort`, along with only the albums and artists
+  /// those tracks refer to. Ratings in `filter` are matched against ratings of the user with `user_id`, tracks that the
+  /// user hid are excluded unless `filter` includes them, and tracks are always filtered by the content filter of the
+  /// user.
+  pub fn list_tracks_page(&self, user_id: i32, page: PageQuery, sort: TrackSort, filter: &TrackFilter) -> Result<TracksPage, DatabaseQueryError> {
+    let content_filter = self.get_user_content_filter(user_id)?.filter;
+    self.select_tracks_page(page, sort, || filter_content!(select_matching_track_ids(user_id, filter), content_filter))
+  }
+
+  /// Selects the tracks in `page` of the tracks whose ID is selected by the query created by `matching_track_ids`,
+  /// sorted by `sort`, along with only the albums and artists those tracks refer to.
+  pub(crate) fn select_tracks_page<'a>(
+    &self,
+    page: PageQuery,
+    sort: TrackSort,
+    matching_track_ids: impl Fn() -> schema::track::BoxedQuery<'a, Sqlite, Integer>,
+  ) -> Result<TracksPage, DatabaseQueryError> {
+    let offset = page.offset.max(0);
+    let limit = page.limit.unwrap_or(DEFAULT_TRACKS_PAGE_LIMIT).clamp(1, MAX_TRACKS_PAGE_LIMIT);
+    let (total, tracks) = {
+      use schema::track::dsl::*;
+      use schema::album::sort_name as album_sort_name;
+      let count_query = track.filter(id.eq_any(matching_track_ids())).count();
+      let query = track.inner_join(schema::album::table).select(schema::track::all_columns).filter(id.eq_any(matching_track_ids())).into_boxed();
+      let total = time!("select_tracks_page.count", count_query.get_result::<i64>(&self.connection)?);
+      let query = match (sort.sort, sort.descending) {
+        (TrackSortColumn::Id, false) => query.order(id.asc()),
+        (TrackSortColumn::Id, true) => query.order(id.desc()),
+        (TrackSortColumn::Title, false) => query.order((title_key.asc(), id.asc())),
+        (TrackSortColumn::Title, true) => query.order((title_key.desc(), id.desc())),
+        (TrackSortColumn::Album, false) => query.order((album_sort_name.asc(), disc_number.asc(), track_number.asc(), id.asc())),
+        (TrackSortColumn::Album, true) => query.order((album_sort_name.desc(), disc_number.desc(), track_number.desc(), id.desc())),
+      };
+      let tracks = time!("select_tracks_page.select_tracks", query.offset(offset).limit(limit).load::<Track>(&self.connection)?);
+      (total, tracks)
+    };
+    let track_ids: Vec<i32> = tracks.iter().map(|t| t.id).collect();
+    let tracks = self.select_tracks_raw(tracks, || select_track_ids_in(&track_ids))?;
+    Ok(TracksPage { offset, total, tracks })
+  }
+
+  /// Samples at most `count` random tracks that match `filter`, along with only the albums and artists those tracks
+  /// refer to. Only the IDs of matching tracks are selected, without sorting them in the database, and are then sampled
+  /// in memory, so that sampling stays fast for large libraries. Ratings in `filter` are matched against ratings of the
+  /// user with `user_id`, tracks that the user hid are excluded unless `filter` includes them, and tracks are always
+  /// filtered by the content filter of the user.
+  pub fn sample_random_tracks(&self, user_id: i32, count: Option<i64>, filter: &TrackFilter) -> Result<TracksRaw, DatabaseQueryError> {
+    let count = count.unwrap_or(DEFAULT_RANDOM_TRACKS_COUNT).clamp(1, MAX_RANDOM_TRACKS_COUNT);
+    let content_filter = self.get_user_content_filter(user_id)?.filter;
+    use schema::track::dsl::*;
+    let matching_query = filter_content!(select_matching_track_ids(user_id, filter), content_filter);
+    let mut matching_ids: Vec<i32> = time!("sample_random_tracks.select_matching_ids", matching_query.load(&self.connection)?);
+    let sampled_ids = matching_ids.partial_shuffle(&mut rand::thread_rng(), count as usize).0.to_vec();
+    let mut tracks = time!("sample_random_tracks.select_tracks", track.filter(id.eq_any(&sampled_ids)).load::<Track>(&self.connection)?);
+    // Restore the random order of the sample, as the database returns the tracks in arbitrary order.
+    tracks.sort_by_key(|t| sampled_ids.iter().position(|sampled_id| *sampled_id == t.id));
+    self.select_tracks_raw(tracks, || select_track_ids_in(&sampled_ids))
+  }
+
+  /// Lists the tracks to play that match `query`, in play order, along with only the albums and artists those tracks
+  /// refer to. Tracks are ordered in the database, so that clients do not have to page through all tracks of an artist.
+  /// When shuffling with `downrank_skipped`, tracks are downranked by how often the user with `user_id` skipped them.
+  /// Tracks that the user hid or that the content filter of the user leaves out are never played.
+  pub fn list_tracks_to_play(&self, user_id: i32, query: PlayTracksQuery) -> Result<TracksRaw, DatabaseQueryError> {
+    let content_filter = self.get_user_content_filter(user_id)?.filter;
+    use schema::track::dsl::*;
+    use schema::album::sort_name as album_sort_name;
+    // Tracks to play are selected with a subquery instead of binding their IDs, as an artist or the whole library may
+    // have more tracks than SQLite allows bound variables in a query.
+    let track_ids_to_play = || {
+      let mut track_ids_query = track.select(id).into_boxed();
+      if let Some(input_album_id) = query.album_id {
+        track_ids_query = track_ids_query.filter(album_id.eq(input_album_id));
+      }
+      if let Some(input_artist_id) = query.artist_id {
+        use schema::track_artist::dsl::{artist_id, role, track_artist, track_id};
+        track_ids_query = track_ids_query.filter(id.eq_any(track_artist.select(track_id).filter(artist_id.eq(input_artist_id)).filter(role.eq(TrackArtistRole::Main))));
+      }
+      track_ids_query = filter_hidden!(track_ids_query, user_id);
+      filter_content!(track_ids_query, content_filter)
+    };
+    let select_query = track.inner_join(schema::album::table).select(schema::track::all_columns).filter(id.eq_any(track_ids_to_play())).into_boxed();
+    let select_query = if query.shuffle {
+      select_query.order(random)
+    } else {
+      select_query.order((album_sort_name.asc(), disc_number.asc(), track_number.asc(), id.asc()))
+    };
+    let mut tracks = time!("list_tracks_to_play.select_tracks", select_query.load::<Track>(&self.connection)?);
+    if query.shuffle && query.downrank_skipped {
+      // Weighted shuffle: order by descending random keys of `u^(1/weight)`, so that tracks with a lower weight tend to
+      // end up further back, while any order remains possible.
+      let skip_ratios = self.get_skip_ratios(user_id)?;
+      let mut rng = rand::thread_rng();
+      let mut keyed: Vec<(f64, Track)> = tracks.into_iter().map(|t| {
+        let weight = 1.0 - skip_ratios.get(&t.id).copied().unwrap_or(0.0) * SKIPPED_SHUFFLE_DOWNRANK;
+        (rng.gen::<f64>().powf(1.0 / weight), t)
+      }).collect();
+      keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+      tracks = keyed.into_iter().map(|(_, t)| t).collect();
+    }
+    self.select_tracks_raw(tracks, track_ids_to_play)
+  }
+
+  /// Selects the albums and artists that `tracks` refer to, along with the availability of `tracks`. The IDs of `tracks`
+  /// must be selected by the query created by `track_ids`, which is used as a subquery instead of binding the IDs.
+  pub(crate) fn select_tracks_raw<'a>(
+    &self,
+    tracks: Vec<Track>,
+    track_ids: impl Fn() -> schema::track::BoxedQuery<'a, Sqlite, Integer>,
+  ) -> Result<TracksRaw, DatabaseQueryError> {
+    let album_ids = || schema::track::table.select(schema::track::album_id).filter(schema::track::id.eq_any(track_ids()));
+    let albums = time!("select_tracks_raw.select_albums", schema::album::table
+      .filter(schema::album::id.eq_any(album_ids()))
+      .load::<Album>(&self.connection)?);
+    let track_artists = time!("select_tracks_raw.select_track_artists", schema::track_artist::table
+      .filter(schema::track_artist::track_id.eq_any(track_ids()))
+      .load::<TrackArtist>(&self.connection)?);
+    let album_artists = time!("select_tracks_raw.select_album_artists", schema::album_artist::table
+      .filter(schema::album_artist::album_id.eq_any(album_ids()))
+      .load::<AlbumArtist>(&self.connection)?);
+    // Artists of tracks and of albums are selected separately, so that each query uses the subquery only once.
+    let track_artist_artists = time!("select_tracks_raw.select_track_artist_artists", schema::artist::table
+      .filter(schema::artist::id.eq_any(schema::track_artist::table.select(schema::track_artist::artist_id).filter(schema::track_artist::track_id.eq_any(track_ids()))))
+      .load::<Artist>(&self.connection)?);
+    let album_artist_artists = time!("select_tracks_raw.select_album_artist_artists", schema::artist::table
+      .filter(schema::artist::id.eq_any(schema::album_artist::table.select(schema::album_artist::artist_id).filter(schema::album_artist::album_id.eq_any(album_ids()))))
+      .load::<Artist>(&self.connection)?);
+    let artists = track_artist_artists.into_iter().chain(album_artist_artists).unique_by(|a| a.id).collect();
+    let track_ids: HashSet<i32> = tracks.iter().map(|t| t.id).collect();
+    let mut availability = self.get_track_availability()?;
+    availability.retain(|track_id, _| track_ids.contains(track_id));
+    Ok(TracksRaw { albums, tracks, artists, album_artists, track_artists, availability })
   }
 
   pub fn get_track_by_id(&self, input_id: i32) -> Result<Option<Track>, DatabaseQueryError> {
     use schema::track::dsl::*;
     Ok(track.find(input_id).first::<Track>(&self.connection).optional()?)
   }
+
+  /// Gets technical details of the track with `input_id`, inspecting each of its local files. Files that cannot be
+  /// inspected are returned with the error, instead of failing. Returns `Ok(None)` if the track does not exist.
+  pub fn get_track_technical_info(&self, input_id: i32) -> Result<Option<TrackTechnicalInfo>, DatabaseQueryError> {
+    let track = if let Some(track) = self.get_track_by_id(input_id)? { track } else { return Ok(None); };
+    let local_tracks = time!("get_track_technical_info.select_local_tracks", schema::local_track::table
+      .inner_join(schema::local_source::table)
+      .filter(schema::local_track::track_id.eq(input_id))
+      .load::<(LocalTrack, LocalSource)>(&self.connection)?);
+    let local_files = local_tracks.into_iter().map(|(local_track, local_source)| {
+      let path = local_source.track_file_path(&local_track);
+      let (file, error) = match path.as_ref().map(|path| inspect_track_file(path)) {
+        Some(Ok(file)) => (Some(file), None),
+        Some(Err(e)) => (None, Some(format!("{:?}", FormatError::new(&e)))),
+        None => (None, None),
+      };
+      LocalTrackFileInfo { local_track, path: path.map(|path| path.to_string_lossy().to_string()), file, error }
+    }).collect();
+    let spotify_tracks = time!("get_track_technical_info.select_spotify_tracks", schema::spotify_track::table
+      .filter(schema::spotify_track::track_id.eq(input_id))
+      .load::<SpotifyTrack>(&self.connection)?);
+    Ok(Some(TrackTechnicalInfo { track, local_files, spotify_tracks, paths_hidden: false }))
+  }
+
+  /// Gets the largest ID of all tracks, or `None` if there are no tracks. Tracks that are created later get a larger ID.
+  pub fn get_max_track_id(&self) -> Result<Option<i32>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    Ok(track.select(diesel::dsl::max(id)).first::<Option<i32>>(&self.connection)?)
+  }
+
+  /// Returns the IDs in `input_ids` of tracks that exist, in the order of `input_ids`, without duplicates.
+  pub fn filter_existing_track_ids(&self, input_ids: &[i32]) -> Result<Vec<i32>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    let existing_ids: Vec<i32> = time!("filter_existing_track_ids.select", track.select(id).filter(id.eq_any(input_ids)).load(&self.connection)?);
+    Ok(input_ids.iter().copied().filter(|input_id| existing_ids.contains(input_id)).unique().collect())
+  }
+
+  /// Searches for at most `limit` tracks matching `search` that pass `content_filter`.
+  pub fn search_tracks(&self, search: &TrackSearch, content_filter: ContentFilter, limit: i64) -> Result<Vec<Track>, DatabaseQueryError> {
+    use schema::track::dsl::*;
+    let pattern = format!("%{}%", name_collation_key(&search.query));
+    let mut query = track.filter(title_key.like(pattern)).into_boxed();
+    if let Some(tag_name) = &search.tag {
+      use schema::track_tag::dsl::{machine_generated, track_id, track_tag};
+      let mut tagged = track_tag
+        .inner_join(schema::tag::table)
+        .select(track_id)
+        .filter(schema::tag::name.eq(tag_name))
+        .into_boxed();
+      if !search.include_machine_generated_tags {
+        tagged = tagged.filter(machine_generated.eq(false));
+      }
+      query = query.filter(id.eq_any(tagged));
+    }
+    query = filter_content!(query, content_filter);
+    Ok(time!("search_tracks.select", query.order(title.asc()).limit(limit).load::<Track>(&self.connection)?))
+  }
+}
+
+/// Creates a query selecting the IDs of tracks with `ids`, for passing tracks of which there are at most a page to
+/// `DatabaseConnection::select_tracks_raw`.
+pub(crate) fn select_track_ids_in(ids: &[i32]) -> schema::track::BoxedQuery<'_, Sqlite, Integer> {
+  schema::track::table.select(schema::track::id).filter(schema::track::id.eq_any(ids)).into_boxed()
 }
@@ -0,0 +1,76 @@
+use diesel::prelude::*;
+use diesel::sql_types::Integer;
+use diesel::sqlite::Sqlite;
+
+use musium_core::api::TrackFilter;
+use musium_core::model::{Availability, TrackArtistRole};
+use musium_core::schema;
+
+/// Compiles `filter` into a query selecting the IDs of matching tracks. Every set field of `filter` adds a filter on the
+/// track ID, using a subselect of the table that field refers to. Ratings are matched against ratings of the user with
+/// `input_user_id`, and tracks that the user hid are excluded unless `filter` includes them.
+pub(crate) fn select_matching_track_ids<'a>(input_user_id: i32, filter: &'a TrackFilter) -> schema::track::BoxedQuery<'a, Sqlite, Integer> {
+  use schema::track::dsl::*;
+  let mut matching = track.select(id).into_boxed();
+  if let Some(input_artist_id) = filter.artist_id {
+    use schema::track_artist::dsl::{artist_id, role, track_artist, track_id};
+    matching = matching.filter(id.eq_any(track_artist.select(track_id).filter(artist_id.eq(input_artist_id)).filter(role.eq(TrackArtistRole::Main))));
+  }
+  if let Some(input_album_id) = filter.album_id {
+    matching = matching.filter(album_id.eq(input_album_id));
+  }
+  if let Some(tag_name) = &filter.tag {
+    use schema::track_tag::dsl::{track_id, track_tag};
+    matching = matching.filter(id.eq_any(track_tag.inner_join(schema::tag::table).select(track_id).filter(schema::tag::name.eq(tag_name))));
+  }
+  if filter.min_rating.is_some() || filter.max_rating.is_some() {
+    use schema::user_track_rating::dsl::{rating, track_id, user_id, user_track_rating};
+    let mut rated = user_track_rating.select(track_id).filter(user_id.eq(input_user_id)).into_boxed();
+    if let Some(min_rating) = filter.min_rating {
+      rated = rated.filter(rating.ge(min_rating));
+    }
+    if let Some(max_rating) = filter.max_rating {
+      rated = rated.filter(rating.le(max_rating));
+    }
+    matching = matching.filter(id.eq_any(rated));
+  }
+  if let Some(input_local_source_id) = filter.local_source_id {
+    use schema::local_track::dsl::{file_path, local_source_id, local_track, track_id};
+    matching = matching.filter(id.eq_any(local_track.select(track_id).filter(local_source_id.eq(input_local_source_id)).filter(file_path.is_not_null())));
+  }
+  if let Some(input_spotify_source_id) = filter.spotify_source_id {
+    use schema::spotify_track_source::dsl::{spotify_source_id, spotify_track_source, track_id};
+    matching = matching.filter(id.eq_any(spotify_track_source.select(track_id).filter(spotify_source_id.eq(input_spotify_source_id))));
+  }
+  if let Some(availability) = filter.availability {
+    // Mirrors `DatabaseConnection::get_track_availability`.
+    let local_track_ids = || schema::local_track::table
+      .inner_join(schema::local_source::table)
+      .select(schema::local_track::track_id)
+      .filter(schema::local_track::file_path.is_not_null())
+      .filter(schema::local_source::enabled.eq(true));
+    let streaming_track_ids = || schema::spotify_track_source::table
+      .inner_join(schema::spotify_source::table)
+      .select(schema::spotify_track_source::track_id)
+      .filter(schema::spotify_source::enabled.eq(true));
+    if availability == Availability::Unavailable {
+      matching = matching.filter(id.ne_all(local_track_ids())).filter(id.ne_all(streaming_track_ids()));
+    }
+    if availability.is_local() {
+      matching = matching.filter(id.eq_any(local_track_ids()));
+    }
+    if availability.is_streaming() {
+      matching = matching.filter(id.eq_any(streaming_track_ids()));
+    }
+  }
+  if let Some(input_added_after) = filter.added_after {
+    matching = matching.filter(added_at.ge(input_added_after));
+  }
+  if let Some(input_added_before) = filter.added_before {
+    matching = matching.filter(added_at.lt(input_added_before));
+  }
+  if !filter.include_hidden {
+    matching = filter_hidden!(matching, input_user_id);
+  }
+  matching
+}
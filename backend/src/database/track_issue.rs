@@ -0,0 +1,79 @@
+use std::backtrace::Backtrace;
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::format_error::FormatError;
+use musium_core::model::{LocalSource, LocalTrack, TrackIssue};
+use musium_core::schema;
+
+use crate::model::LocalSourceEx;
+use crate::track_validation::validate_track_file;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Querying
+
+impl DatabaseConnection {
+  /// Lists the issues found by the last validation of local tracks, ordered by when they were detected.
+  pub fn list_track_issues(&self) -> Result<Vec<TrackIssue>, DatabaseQueryError> {
+    use schema::track_issue::dsl::*;
+    Ok(time!("list_track_issues.select", track_issue.order(detected_at.asc()).load::<TrackIssue>(&self.connection)?))
+  }
+}
+
+// Validation
+
+#[derive(Debug, Error)]
+pub enum ValidateLocalTracksError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Validates the files of local tracks of enabled local sources by decoding their first seconds, recording an issue
+  /// for each track whose file cannot be read, cannot be decoded, or is truncated. Issues of tracks whose file now
+  /// validates are removed. Returns the number of tracks with an issue.
+  #[instrument(skip(self))]
+  pub fn validate_local_tracks(&self) -> Result<usize, ValidateLocalTracksError> {
+    let local_tracks: Vec<(LocalTrack, LocalSource)> = {
+      use schema::local_track::dsl::*;
+      time!("validate_local_tracks.select_local_tracks", local_track
+        .inner_join(schema::local_source::table)
+        .filter(schema::local_source::enabled.eq(true))
+        .load::<(LocalTrack, LocalSource)>(&self.connection)?)
+    };
+    let mut issue_track_ids = HashSet::new();
+    for (local_track, local_source) in local_tracks {
+      let path = match local_source.track_file_path(&local_track) {
+        Some(path) => path,
+        None => continue,
+      };
+      event!(Level::TRACE, ?path, "Validating track file");
+      match validate_track_file(&path) {
+        // Keep the issue of another file of the same track.
+        Ok(_) => if !issue_track_ids.contains(&local_track.track_id) {
+          use schema::track_issue::dsl::*;
+          time!("validate_local_tracks.delete", diesel::delete(track_issue.find(local_track.track_id)).execute(&self.connection)?);
+        }
+        Err(e) => {
+          event!(Level::WARN, ?path, "Found issue in track file: {:?}", FormatError::new(&e));
+          let new_issue = TrackIssue {
+            track_id: local_track.track_id,
+            local_hash: local_track.hash,
+            kind: e.kind(),
+            message: e.to_string(),
+            detected_at: Utc::now().naive_utc(),
+          };
+          use schema::track_issue::dsl::*;
+          time!("validate_local_tracks.replace", diesel::replace_into(track_issue).values(&new_issue).execute(&self.connection)?);
+          issue_track_ids.insert(local_track.track_id);
+        }
+      }
+    }
+    Ok(issue_track_ids.len())
+  }
+}
@@ -0,0 +1,181 @@
+use std::backtrace::Backtrace;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::api::{NewTrackRelation, TrackVersion};
+use musium_core::model::{Track, TrackArtistRole, TrackRelation, TrackRelationKind};
+use musium_core::schema;
+
+use crate::track_version::parse_version_title;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+#[derive(Debug, Error)]
+pub enum TrackRelationError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to query tracks")]
+  QueryTracksFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Track with ID '{0}' cannot be a version of itself")]
+  SelfRelationFail(i32),
+}
+
+// Querying
+
+impl DatabaseConnection {
+  pub fn list_track_relations(&self) -> Result<Vec<TrackRelation>, DatabaseQueryError> {
+    use schema::track_relation::dsl::*;
+    Ok(time!("list_track_relations.select", track_relation.order((original_track_id.asc(), version_track_id.asc())).load::<TrackRelation>(&self.connection)?))
+  }
+
+  /// Lists the other versions of the track with `input_track_id`: its versions, its original tracks, and the other
+  /// versions of its original tracks, ordered by track ID.
+  pub fn list_track_versions(&self, input_track_id: i32) -> Result<Vec<TrackVersion>, DatabaseQueryError> {
+    use schema::track_relation::dsl::*;
+    let original_track_ids: Vec<i32> = time!("list_track_versions.select_originals", track_relation
+      .select(original_track_id)
+      .filter(version_track_id.eq(input_track_id))
+      .load(&self.connection)?);
+    let relations: Vec<TrackRelation> = time!("list_track_versions.select_relations", track_relation
+      .filter(original_track_id.eq(input_track_id).or(original_track_id.eq_any(&original_track_ids)))
+      .load(&self.connection)?);
+    // Link each other track by the relation to the original, or by the relation to the version when it is the original.
+    let mut related: HashMap<i32, TrackRelation> = HashMap::new();
+    for relation in relations {
+      if relation.version_track_id != input_track_id {
+        related.entry(relation.version_track_id).or_insert(relation);
+      } else {
+        related.insert(relation.original_track_id, relation);
+      }
+    }
+    let related_track_ids: Vec<i32> = related.keys().copied().collect();
+    let tracks: Vec<Track> = {
+      use schema::track::dsl::*;
+      time!("list_track_versions.select_tracks", track.filter(id.eq_any(related_track_ids)).order(id.asc()).load(&self.connection)?)
+    };
+    Ok(tracks.into_iter()
+      .filter_map(|track| related.remove(&track.id).map(|relation| TrackVersion { relation, track }))
+      .collect())
+  }
+}
+
+// Managing
+
+impl DatabaseConnection {
+  /// Links the track with `version_track_id` of `new_relation` as a version of the original track with
+  /// `original_track_id`, replacing an existing link between the two tracks. Returns the link, or `None` if either track
+  /// does not exist.
+  pub fn add_track_relation(&self, new_relation: NewTrackRelation) -> Result<Option<TrackRelation>, TrackRelationError> {
+    let NewTrackRelation { original_track_id: input_original_track_id, version_track_id: input_version_track_id, kind: input_kind } = new_relation;
+    if input_original_track_id == input_version_track_id {
+      return Err(TrackRelationError::SelfRelationFail(input_original_track_id));
+    }
+    self.connection.transaction::<_, TrackRelationError, _>(|| {
+      if self.filter_existing_track_ids(&[input_original_track_id, input_version_track_id])?.len() != 2 {
+        return Ok(None);
+      }
+      let relation = TrackRelation { original_track_id: input_original_track_id, version_track_id: input_version_track_id, kind: input_kind, machine_generated: false };
+      event!(Level::DEBUG, ?relation, "Inserting track relation");
+      use schema::track_relation::dsl::*;
+      time!("add_track_relation.delete_reverse", diesel::delete(track_relation.find((input_version_track_id, input_original_track_id))).execute(&self.connection)?);
+      time!("add_track_relation.replace", diesel::replace_into(track_relation).values(relation).execute(&self.connection)?);
+      Ok(Some(relation))
+    })
+  }
+
+  /// Removes the link from the track with `input_version_track_id` to the original track with
+  /// `input_original_track_id`, returning `false` if the tracks were not linked.
+  pub fn remove_track_relation(&self, input_original_track_id: i32, input_version_track_id: i32) -> Result<bool, DatabaseQueryError> {
+    use schema::track_relation::dsl::*;
+    Ok(time!("remove_track_relation.delete", diesel::delete(track_relation.find((input_original_track_id, input_version_track_id))).execute(&self.connection)?) > 0)
+  }
+
+  /// Links tracks with an ID larger than `after_track_id`, or all tracks if it is `None`, to other versions of the same
+  /// track, based on their titles: a track titled `Song (Live)` is linked as a live version of a track titled `Song` by
+  /// the same main artist. Tracks that are already a version of another track are left alone, and only tracks that were added
+  /// are considered, so that links that users removed are not created again. Returns the number of created links.
+  #[instrument(skip(self))]
+  pub fn link_track_versions(&self, after_track_id: Option<i32>) -> Result<usize, DatabaseQueryError> {
+    let tracks: Vec<(i32, String)> = {
+      use schema::track::dsl::*;
+      time!("link_track_versions.select_tracks", track.select((id, title_key)).load(&self.connection)?)
+    };
+    let is_added = |track_id: i32| after_track_id.map_or(true, |after_track_id| track_id > after_track_id);
+    if !tracks.iter().any(|(track_id, _)| is_added(*track_id)) {
+      return Ok(0);
+    }
+    // Group tracks by the title of their original, into original tracks and versions.
+    let mut groups: HashMap<&str, (Vec<i32>, Vec<(i32, TrackRelationKind)>)> = HashMap::new();
+    for (track_id, title_key) in &tracks {
+      let (base, kind) = parse_version_title(title_key);
+      let (originals, versions) = groups.entry(base).or_default();
+      match kind {
+        Some(kind) => versions.push((*track_id, kind)),
+        None => originals.push(*track_id),
+      }
+    }
+    let candidates: Vec<(Vec<i32>, Vec<(i32, TrackRelationKind)>)> = groups.into_values()
+      .filter(|(originals, versions)| !originals.is_empty() && !versions.is_empty())
+      .filter(|(originals, versions)| originals.iter().copied().chain(versions.iter().map(|(track_id, _)| *track_id)).any(is_added))
+      .collect();
+    if candidates.is_empty() {
+      return Ok(0);
+    }
+
+    let candidate_track_ids: Vec<i32> = candidates.iter()
+      .flat_map(|(originals, versions)| originals.iter().copied().chain(versions.iter().map(|(track_id, _)| *track_id)))
+      .collect();
+    let track_artist_ids: HashMap<i32, BTreeSet<i32>> = {
+      use schema::track_artist::dsl::*;
+      let pairs: Vec<(i32, i32)> = time!("link_track_versions.select_track_artists", track_artist
+        .select((track_id, artist_id))
+        .filter(track_id.eq_any(&candidate_track_ids))
+        .filter(role.eq(TrackArtistRole::Main))
+        .load(&self.connection)?);
+      let mut track_artist_ids: HashMap<i32, BTreeSet<i32>> = HashMap::new();
+      for (input_track_id, input_artist_id) in pairs {
+        track_artist_ids.entry(input_track_id).or_default().insert(input_artist_id);
+      }
+      track_artist_ids
+    };
+    let linked_version_ids: HashSet<i32> = {
+      use schema::track_relation::dsl::*;
+      time!("link_track_versions.select_linked_versions", track_relation
+        .select(version_track_id)
+        .filter(version_track_id.eq_any(&candidate_track_ids))
+        .load::<i32>(&self.connection)?)
+        .into_iter().collect()
+    };
+    let shares_artist = |a: i32, b: i32| match (track_artist_ids.get(&a), track_artist_ids.get(&b)) {
+      (Some(a), Some(b)) => !a.is_disjoint(b),
+      _ => false,
+    };
+
+    let mut new_relations = Vec::new();
+    for (mut originals, versions) in candidates {
+      // Link to the earliest original, as later originals are usually copies on compilations.
+      originals.sort_unstable();
+      for (version_id, kind) in versions {
+        if linked_version_ids.contains(&version_id) { continue; }
+        if let Some(original_id) = originals.iter().copied().find(|original_id| shares_artist(*original_id, version_id)) {
+          if is_added(original_id) || is_added(version_id) {
+            new_relations.push(TrackRelation { original_track_id: original_id, version_track_id: version_id, kind, machine_generated: true });
+          }
+        }
+      }
+    }
+    let mut linked_count = 0;
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::track_relation::dsl::*;
+      for new_relation in &new_relations {
+        linked_count += time!("link_track_versions.insert", diesel::insert_or_ignore_into(track_relation).values(new_relation).execute(&self.connection)?);
+      }
+      Ok(())
+    })?;
+    event!(Level::DEBUG, linked_count, "Linked versions of tracks");
+    Ok(linked_count)
+  }
+}
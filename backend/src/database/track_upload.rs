@@ -0,0 +1,77 @@
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{LocalSource, Track};
+use musium_filesystem_sync::FilesystemSyncError;
+
+use crate::database::{DatabaseConnection, DatabaseQueryError};
+use crate::database::sync::local::LocalSyncError;
+
+/// File extensions of files that can be uploaded, which are the files that local synchronization picks up.
+pub const TRACK_UPLOAD_EXTENSIONS: &[&str] = &["mp3"];
+
+#[derive(Debug, Error)]
+pub enum TrackUploadError {
+  #[error("Failed to query for the managed local source")]
+  QuerySourceFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Failed to perform database operation")]
+  DatabaseOperationFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Managed local source '{0}' does not exist or was deleted")]
+  NoManagedLocalSourceFail(i32),
+  #[error("File name '{0}' is not a plain file name")]
+  InvalidFileNameFail(String),
+  #[error("File '{0}' does not have a supported extension")]
+  UnsupportedFileTypeFail(String),
+  #[error("File '{0}' already exists in the managed local source")]
+  FileExistsFail(String),
+  #[error("Failed to read uploaded file")]
+  ReadFail(#[from] FilesystemSyncError, Backtrace),
+  #[error("Uploaded file '{0}' does not have an ID3 tag")]
+  NoTagFail(String),
+  #[error("Failed to synchronize uploaded file")]
+  SyncFail(#[from] LocalSyncError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Gets the managed local source with `local_source_id` into which uploaded tracks are stored, and the path at which
+  /// an uploaded file named `file_name` is stored in its directory. Fails if the source does not exist or was deleted,
+  /// if `file_name` is not a plain file name with a supported extension, or if a file with that name already exists.
+  pub fn get_track_upload_path(&self, local_source_id: i32, file_name: &str) -> Result<(LocalSource, PathBuf), TrackUploadError> {
+    use TrackUploadError::*;
+    let local_source = self.get_local_source_by_id(local_source_id)?
+      .filter(|local_source| local_source.deleted_at.is_none())
+      .ok_or(NoManagedLocalSourceFail(local_source_id))?;
+    if file_name.is_empty() || file_name.starts_with('.') || file_name.contains(|c| c == '/' || c == '\\' || c == ':' || char::is_control(c)) {
+      return Err(InvalidFileNameFail(file_name.to_string()));
+    }
+    let path = Path::new(&local_source.directory).join(file_name);
+    let supported = path.extension()
+      .and_then(|extension| extension.to_str())
+      .map_or(false, |extension| TRACK_UPLOAD_EXTENSIONS.contains(&extension));
+    if !supported {
+      return Err(UnsupportedFileTypeFail(file_name.to_string()));
+    }
+    if path.exists() {
+      return Err(FileExistsFail(file_name.to_string()));
+    }
+    Ok((local_source, path))
+  }
+
+  /// Synchronizes the single uploaded file at `path`, which must be inside the directory of `local_source`, without
+  /// synchronizing the rest of the source. Returns the created track, or the existing track if the file is a copy of a
+  /// track that was moved or removed.
+  #[instrument(skip(self, local_source))]
+  pub fn import_uploaded_track(&self, local_source: &LocalSource, path: &Path) -> Result<Track, TrackUploadError> {
+    use TrackUploadError::*;
+    let local_sync_track = musium_filesystem_sync::sync_file(&local_source.directory, path)
+      .ok_or_else(|| NoTagFail(path.display().to_string()))??;
+    let track = self.connection.transaction::<_, TrackUploadError, _>(|| {
+      Ok(self.sync_local_sync_track(local_source.id, &local_sync_track)?)
+    })?;
+    event!(Level::INFO, ?track, "Imported uploaded track");
+    Ok(track)
+  }
+}
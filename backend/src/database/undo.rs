@@ -0,0 +1,139 @@
+use std::backtrace::Backtrace;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::model::{NewUndoLogEntry, NewUserAlbumRating, NewUserArtistRating, NewUserTrackRating, UndoKind, UndoLogEntry};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Time window, in minutes, in which destructive operations can be undone.
+pub const UNDO_RETENTION_MINUTES: i64 = 30;
+
+// Logging
+
+impl DatabaseConnection {
+  /// Logs how to undo a destructive operation of the user with `input_user_id`, and purges expired undo log entries.
+  pub(crate) fn log_undo(&self, input_user_id: i32, input_kind: UndoKind, input_target_id: i32, input_previous_rating: Option<i32>, input_description: String) -> Result<(), diesel::result::Error> {
+    self.purge_expired_undo_log()?;
+    let new_entry = NewUndoLogEntry {
+      user_id: input_user_id,
+      kind: input_kind,
+      target_id: input_target_id,
+      previous_rating: input_previous_rating,
+      description: input_description,
+      created_at: Utc::now().naive_utc(),
+    };
+    event!(Level::DEBUG, ?new_entry, "Inserting undo log entry");
+    use schema::undo_log::dsl::*;
+    time!("log_undo.insert", diesel::insert_into(undo_log).values(new_entry).execute(&self.connection)?);
+    Ok(())
+  }
+
+  /// Deletes undo log entries that are older than the retention window, permanently deleting playlists whose deletion
+  /// can no longer be undone.
+  fn purge_expired_undo_log(&self) -> Result<(), diesel::result::Error> {
+    let expired_before = Utc::now().naive_utc() - Duration::minutes(UNDO_RETENTION_MINUTES);
+    let expired_entries: Vec<UndoLogEntry> = {
+      use schema::undo_log::dsl::*;
+      time!("purge_expired_undo_log.select", undo_log.filter(created_at.lt(expired_before)).load(&self.connection)?)
+    };
+    for entry in expired_entries {
+      if entry.kind == UndoKind::PlaylistDeletion {
+        self.purge_deleted_playlist(entry.target_id)?;
+      }
+      use schema::undo_log::dsl::*;
+      time!("purge_expired_undo_log.delete", diesel::delete(undo_log.find(entry.id)).execute(&self.connection)?);
+    }
+    Ok(())
+  }
+}
+
+// Querying
+
+impl DatabaseConnection {
+  /// Lists the undo log entries of the user with `input_user_id` that can still be undone, most recent first.
+  pub fn list_undo_log(&self, input_user_id: i32) -> Result<Vec<UndoLogEntry>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      self.purge_expired_undo_log()?;
+      use schema::undo_log::dsl::*;
+      Ok(time!("list_undo_log.select", undo_log.filter(user_id.eq(input_user_id)).order(created_at.desc()).load::<UndoLogEntry>(&self.connection)?))
+    })
+  }
+}
+
+// Undoing
+
+#[derive(Debug, Error)]
+pub enum UndoError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to restore a source")]
+  RestoreSourceFail(#[from] DatabaseQueryError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Undoes the operation of the undo log entry with `input_id` of the user with `input_user_id`, and removes the entry.
+  /// Returns the undone entry, or `None` if it does not exist or has expired.
+  pub fn undo(&self, input_id: i32, input_user_id: i32) -> Result<Option<UndoLogEntry>, UndoError> {
+    self.connection.transaction::<_, UndoError, _>(|| {
+      self.purge_expired_undo_log()?;
+      let entry = {
+        use schema::undo_log::dsl::*;
+        time!("undo.select", undo_log.find(input_id).filter(user_id.eq(input_user_id)).first::<UndoLogEntry>(&self.connection).optional()?)
+      };
+      let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(None),
+      };
+      event!(Level::DEBUG, ?entry, "Undoing operation");
+      match entry.kind {
+        UndoKind::AlbumRatingChange => {
+          use schema::user_album_rating::dsl::*;
+          let query = user_album_rating.filter(user_id.eq(entry.user_id)).filter(album_id.eq(entry.target_id));
+          if let Some(previous_rating) = entry.previous_rating {
+            time!("undo.replace_album_rating", diesel::replace_into(user_album_rating).values(NewUserAlbumRating { user_id: entry.user_id, album_id: entry.target_id, rating: previous_rating }).execute(&self.connection)?);
+          } else {
+            time!("undo.delete_album_rating", diesel::delete(query).execute(&self.connection)?);
+          }
+        }
+        UndoKind::TrackRatingChange => {
+          use schema::user_track_rating::dsl::*;
+          let query = user_track_rating.filter(user_id.eq(entry.user_id)).filter(track_id.eq(entry.target_id));
+          if let Some(previous_rating) = entry.previous_rating {
+            time!("undo.replace_track_rating", diesel::replace_into(user_track_rating).values(NewUserTrackRating { user_id: entry.user_id, track_id: entry.target_id, rating: previous_rating }).execute(&self.connection)?);
+          } else {
+            time!("undo.delete_track_rating", diesel::delete(query).execute(&self.connection)?);
+          }
+        }
+        UndoKind::ArtistRatingChange => {
+          use schema::user_artist_rating::dsl::*;
+          let query = user_artist_rating.filter(user_id.eq(entry.user_id)).filter(artist_id.eq(entry.target_id));
+          if let Some(previous_rating) = entry.previous_rating {
+            time!("undo.replace_artist_rating", diesel::replace_into(user_artist_rating).values(NewUserArtistRating { user_id: entry.user_id, artist_id: entry.target_id, rating: previous_rating }).execute(&self.connection)?);
+          } else {
+            time!("undo.delete_artist_rating", diesel::delete(query).execute(&self.connection)?);
+          }
+        }
+        UndoKind::PlaylistDeletion => {
+          use schema::playlist::dsl::*;
+          time!("undo.restore_playlist", diesel::update(playlist.find(entry.target_id)).set(deleted_at.eq::<Option<chrono::NaiveDateTime>>(None)).execute(&self.connection)?);
+        }
+        UndoKind::LocalSourceRemoval => {
+          self.restore_local_source_by_id(entry.target_id)?;
+        }
+        UndoKind::SpotifySourceRemoval => {
+          self.restore_spotify_source_by_id(entry.target_id)?;
+        }
+      }
+      {
+        use schema::undo_log::dsl::*;
+        time!("undo.delete", diesel::delete(undo_log.find(entry.id)).execute(&self.connection)?);
+      }
+      Ok(Some(entry))
+    })
+  }
+}
@@ -1,12 +1,18 @@
 use std::backtrace::Backtrace;
 
+use chrono::{Duration, Utc};
 use diesel::prelude::*;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use thiserror::Error;
+use tracing::{event, Level};
 
-use musium_core::model::{NewUser, NewUserAlbumRating, NewUserArtistRating, NewUserTrackRating, User, UserAlbumRating, UserArtistRating, UserLogin, UserTrackRating};
+use musium_core::api::UserRegistration;
+use musium_core::model::{NewUser, NewUserAlbumRating, NewUserArtistRating, NewUserInvite, NewUserTrackRating, UndoKind, User, UserAlbumRating, UserArtistRating, UserInvite, UserLogin, UserTrackRating};
 use musium_core::schema;
 
 use crate::model::{InternalNewUser, InternalUser};
+use crate::password::PasswordHashParams;
 
 use super::{DatabaseConnection, DatabaseQueryError};
 
@@ -31,6 +37,11 @@ impl DatabaseConnection {
     Ok(user.select((id, name)).find(input_id).first::<User>(&self.connection).optional()?)
   }
 
+  pub fn get_user_by_name(&self, input_name: &str) -> Result<Option<User>, DatabaseQueryError> {
+    use schema::user::dsl::*;
+    Ok(user.select((id, name)).filter(name.eq(input_name)).first::<User>(&self.connection).optional()?)
+  }
+
   pub fn verify_user(&self, user_login: &UserLogin) -> Result<Option<User>, UserAddVerifyError> {
     let user: Option<InternalUser> = {
       use schema::user::dsl::*;
@@ -39,8 +50,20 @@ impl DatabaseConnection {
         .first::<InternalUser>(&self.connection)
         .optional()?
     };
-    if let Some(user) = user {
-      if self.inner.password_hasher.verify(&user_login.password, &user.salt, &user.hash)? {
+    if let Some(mut user) = user {
+      let params: PasswordHashParams = user.hash_params.parse()?;
+      if self.inner.password_hasher.verify(&user_login.password, &user.salt, &user.hash, &params)? {
+        if self.inner.password_hasher.needs_rehash(&params) && !self.is_quiesced() {
+          // Transparently upgrade the hash to the current parameters, now that the password is known. Skipped while the
+          // database is quiesced, keeping the outdated hash until the next login.
+          let salt = self.inner.password_hasher.generate_salt();
+          let (hash, new_params) = self.inner.password_hasher.hash(&user_login.password, &salt)?;
+          user.hash = hash;
+          user.salt = salt;
+          user.hash_params = new_params.to_string();
+          user = time!("verify_user.rehash", user.save_changes::<InternalUser>(&*self.connection)?);
+          event!(Level::INFO, user.name = %user.name, old_params = %params, new_params = %user.hash_params, "Re-hashed password of user with current parameters");
+        }
         Ok(Some(user.into()))
       } else {
         Ok(None)
@@ -53,11 +76,12 @@ impl DatabaseConnection {
   pub fn create_user(&self, new_user: NewUser) -> Result<User, UserAddVerifyError> {
     use schema::user;
     let salt = self.inner.password_hasher.generate_salt();
-    let hash = self.inner.password_hasher.hash(new_user.password, &salt)?;
+    let (hash, hash_params) = self.inner.password_hasher.hash(new_user.password, &salt)?;
     let internal_new_user = InternalNewUser {
       name: new_user.name.clone(),
       hash,
       salt,
+      hash_params: hash_params.to_string(),
     };
     time!("create_user.insert", diesel::insert_into(user::table)
       .values(internal_new_user)
@@ -68,6 +92,26 @@ impl DatabaseConnection {
     Ok(time!("create_user.select", select_query.first::<User>(&self.connection)?))
   }
 
+  /// Returns whether any user exists.
+  pub fn has_users(&self) -> Result<bool, DatabaseQueryError> {
+    use schema::user::dsl::*;
+    Ok(time!("has_users.select", diesel::select(diesel::dsl::exists(user)).get_result(&self.connection)?))
+  }
+
+  /// Creates `new_user` if no users exist yet, for setting up a new server. Returns `None` if a user already exists.
+  pub fn create_first_user(&self, new_user: NewUser) -> Result<Option<User>, UserAddVerifyError> {
+    self.connection.transaction::<_, UserAddVerifyError, _>(|| {
+      let has_users = {
+        use schema::user::dsl::*;
+        time!("create_first_user.select", diesel::select(diesel::dsl::exists(user)).get_result::<bool>(&self.connection)?)
+      };
+      if has_users {
+        return Ok(None);
+      }
+      Ok(Some(self.create_user(new_user)?))
+    })
+  }
+
   pub fn delete_user_by_name<S: AsRef<str>>(&self, name: S) -> Result<bool, DatabaseQueryError> {
     use schema::user;
     let name = name.as_ref();
@@ -84,6 +128,90 @@ impl DatabaseConnection {
   }
 }
 
+// User invite database queries
+
+/// Number of characters of invite codes.
+const INVITE_CODE_LENGTH: usize = 16;
+/// Default number of hours an invite code can be redeemed for.
+pub const DEFAULT_INVITE_VALIDITY_HOURS: u32 = 7 * 24;
+/// Maximum number of hours an invite code can be redeemed for.
+pub const MAX_INVITE_VALIDITY_HOURS: u32 = 30 * 24;
+
+#[derive(Debug, Error)]
+pub enum RegisterUserError {
+  #[error("Invite code is invalid, has expired, or has already been redeemed")]
+  InvalidInviteFail,
+  #[error("User with name '{0}' already exists")]
+  NameTakenFail(String),
+  #[error("Failed to create user")]
+  UserCreateFail(#[from] UserAddVerifyError, Backtrace),
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Creates a single-use invite code on behalf of the user with `input_created_by_user_id`, which can be redeemed for
+  /// `valid_for_hours` hours, clamped to `MAX_INVITE_VALIDITY_HOURS`.
+  pub fn create_user_invite(&self, input_created_by_user_id: i32, valid_for_hours: u32) -> Result<UserInvite, DatabaseQueryError> {
+    let input_code: String = rand::thread_rng().sample_iter(&Alphanumeric).take(INVITE_CODE_LENGTH).map(char::from).collect();
+    let now = Utc::now().naive_utc();
+    let valid_for_hours = valid_for_hours.min(MAX_INVITE_VALIDITY_HOURS);
+    let new_user_invite = NewUserInvite {
+      code: input_code.clone(),
+      created_by_user_id: input_created_by_user_id,
+      created_at: now,
+      expires_at: now + Duration::hours(valid_for_hours as i64),
+    };
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::user_invite::dsl::*;
+      time!("create_user_invite.insert", diesel::insert_into(user_invite).values(new_user_invite).execute(&self.connection)?);
+      Ok(time!("create_user_invite.select", user_invite.filter(code.eq(&input_code)).first::<UserInvite>(&self.connection)?))
+    })
+  }
+
+  pub fn list_user_invites(&self) -> Result<Vec<UserInvite>, DatabaseQueryError> {
+    use schema::user_invite::dsl::*;
+    Ok(time!("list_user_invites.select", user_invite.order(created_at.desc()).load::<UserInvite>(&self.connection)?))
+  }
+
+  /// Registers a new user by redeeming the invite code of `registration`, which must exist, must not have expired, and
+  /// must not have been redeemed yet.
+  pub fn register_user(&self, registration: UserRegistration) -> Result<User, RegisterUserError> {
+    self.connection.transaction::<_, RegisterUserError, _>(|| {
+      let now = Utc::now().naive_utc();
+      let invite_id = {
+        use schema::user_invite::dsl::*;
+        let query = user_invite
+          .filter(code.eq(&registration.invite_code))
+          .filter(expires_at.gt(now))
+          .filter(redeemed_at.is_null());
+        let invite_id = time!("register_user.select_invite", query.select(id).first::<i32>(&self.connection).optional()?)
+          .ok_or(RegisterUserError::InvalidInviteFail)?;
+        // Mark as redeemed before creating the user, so that concurrent registrations cannot redeem the same invite.
+        let updated = time!("register_user.redeem_invite", diesel::update(query.filter(id.eq(invite_id))).set(redeemed_at.eq(now)).execute(&self.connection)?);
+        if updated != 1 {
+          return Err(RegisterUserError::InvalidInviteFail);
+        }
+        invite_id
+      };
+      let name_taken = {
+        use schema::user::dsl::*;
+        time!("register_user.select_name", diesel::select(diesel::dsl::exists(user.filter(name.eq(&registration.name)))).get_result::<bool>(&self.connection)?)
+      };
+      if name_taken {
+        return Err(RegisterUserError::NameTakenFail(registration.name));
+      }
+      let new_user = self.create_user(NewUser { name: registration.name, password: registration.password })?;
+      {
+        use schema::user_invite::dsl::*;
+        time!("register_user.update_invite", diesel::update(user_invite.find(invite_id)).set(redeemed_by_user_id.eq(new_user.id)).execute(&self.connection)?);
+      }
+      event!(Level::INFO, user.name = %new_user.name, invite_id, "Registered user with invite");
+      Ok(new_user)
+    })
+  }
+}
+
 
 // User data database queries
 
@@ -94,6 +222,7 @@ impl DatabaseConnection {
       .filter(user_album_rating::user_id.eq(user_id))
       .filter(user_album_rating::album_id.eq(album_id));
     let db_user_album_rating = time!("set_user_album_rating.select", select_query.first::<UserAlbumRating>(&self.connection).optional()?);
+    self.log_undo(user_id, UndoKind::AlbumRatingChange, album_id, db_user_album_rating.as_ref().map(|r: &UserAlbumRating| r.rating), format!("Set rating of album with ID '{}' to {}", album_id, rating))?;
     if let Some(db_user_album_rating) = db_user_album_rating {
       let mut db_user_album_rating: UserAlbumRating = db_user_album_rating;
       db_user_album_rating.rating = rating;
@@ -112,6 +241,7 @@ impl DatabaseConnection {
       .filter(user_track_rating::user_id.eq(user_id))
       .filter(user_track_rating::track_id.eq(track_id));
     let db_user_track_rating = time!("set_user_track_rating.select", select_query.first::<UserTrackRating>(&self.connection).optional()?);
+    self.log_undo(user_id, UndoKind::TrackRatingChange, track_id, db_user_track_rating.as_ref().map(|r: &UserTrackRating| r.rating), format!("Set rating of track with ID '{}' to {}", track_id, rating))?;
     if let Some(db_user_track_rating) = db_user_track_rating {
       let mut db_user_track_rating: UserTrackRating = db_user_track_rating;
       db_user_track_rating.rating = rating;
@@ -124,12 +254,22 @@ impl DatabaseConnection {
     }
   }
 
+  /// Sets the rating of the tracks with `track_ids` to `rating`. Tracks that do not exist are skipped.
+  pub fn set_user_track_ratings(&self, user_id: i32, track_ids: &[i32], rating: i32) -> Result<Vec<UserTrackRating>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      self.filter_existing_track_ids(track_ids)?.into_iter()
+        .map(|track_id| self.set_user_track_rating(user_id, track_id, rating))
+        .collect()
+    })
+  }
+
   pub fn set_user_artist_rating(&self, user_id: i32, artist_id: i32, rating: i32) -> Result<UserArtistRating, DatabaseQueryError> {
     use schema::user_artist_rating;
     let select_query = user_artist_rating::table
       .filter(user_artist_rating::user_id.eq(user_id))
       .filter(user_artist_rating::artist_id.eq(artist_id));
     let db_user_artist_rating = time!("set_user_artist_rating.select", select_query.first::<UserArtistRating>(&self.connection).optional()?);
+    self.log_undo(user_id, UndoKind::ArtistRatingChange, artist_id, db_user_artist_rating.as_ref().map(|r: &UserArtistRating| r.rating), format!("Set rating of artist with ID '{}' to {}", artist_id, rating))?;
     if let Some(db_user_artist_rating) = db_user_artist_rating {
       let mut db_user_artist_rating: UserArtistRating = db_user_artist_rating;
       db_user_artist_rating.rating = rating;
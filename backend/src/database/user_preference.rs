@@ -0,0 +1,111 @@
+use std::backtrace::Backtrace;
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::api::UserPreferences;
+use musium_core::model::{PlaybackPreference, UserPreference};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+/// Maximum number of preferences of a user.
+pub const MAX_USER_PREFERENCES: usize = 128;
+/// Maximum length in bytes of keys and values of preferences.
+pub const MAX_USER_PREFERENCE_LENGTH: usize = 4096;
+
+#[derive(Debug, Error)]
+pub enum UserPreferencesError {
+  #[error("Default volume {0} is not between 0.0 and 1.0")]
+  InvalidDefaultVolume(f32),
+  #[error("Channel balance {0} is not between -1.0 and 1.0")]
+  InvalidChannelBalance(f32),
+  #[error("Crossfeed {0} is not between 0.0 and 1.0")]
+  InvalidCrossfeed(f32),
+  #[error("Cannot store {0} preferences; at most {1} preferences can be stored")]
+  TooManyPreferences(usize, usize),
+  #[error("Key or value of preference '{0}' is longer than {1} bytes")]
+  PreferenceTooLong(String, usize),
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to get or set playback preference")]
+  PlaybackPreferenceFail(#[from] DatabaseQueryError, Backtrace),
+}
+
+impl DatabaseConnection {
+  pub fn get_user_preferences(&self, input_user_id: i32) -> Result<UserPreferences, DatabaseQueryError> {
+    let user_preferences = {
+      use schema::user_preference::dsl::*;
+      time!("get_user_preferences.select", user_preference.filter(user_id.eq(input_user_id)).load::<UserPreference>(&self.connection)?)
+    };
+    let mut preferences = UserPreferences::default();
+    for UserPreference { key, value, .. } in user_preferences {
+      match key.as_str() {
+        UserPreferences::THEME_KEY => preferences.theme = Some(value),
+        UserPreferences::DEFAULT_VOLUME_KEY => preferences.default_volume = value.parse().ok(),
+        UserPreferences::LOCALE_KEY => preferences.locale = Some(value),
+        UserPreferences::MONO_DOWNMIX_KEY => preferences.mono_downmix = value.parse().ok(),
+        UserPreferences::CHANNEL_BALANCE_KEY => preferences.channel_balance = value.parse().ok(),
+        UserPreferences::CROSSFEED_KEY => preferences.crossfeed = value.parse().ok(),
+        _ => { preferences.other.insert(key, value); }
+      }
+    }
+    preferences.preferred_playback_source = Some(self.get_playback_preference(input_user_id)?.preferred_source);
+    Ok(preferences)
+  }
+
+  /// Replaces the preferences of the user with `input_user_id` with `preferences`, returning the stored preferences.
+  pub fn set_user_preferences(&self, input_user_id: i32, preferences: UserPreferences) -> Result<UserPreferences, UserPreferencesError> {
+    if let Some(default_volume) = preferences.default_volume {
+      if !(0.0..=1.0).contains(&default_volume) {
+        return Err(UserPreferencesError::InvalidDefaultVolume(default_volume));
+      }
+    }
+    if let Some(channel_balance) = preferences.channel_balance {
+      if !(-1.0..=1.0).contains(&channel_balance) {
+        return Err(UserPreferencesError::InvalidChannelBalance(channel_balance));
+      }
+    }
+    if let Some(crossfeed) = preferences.crossfeed {
+      if !(0.0..=1.0).contains(&crossfeed) {
+        return Err(UserPreferencesError::InvalidCrossfeed(crossfeed));
+      }
+    }
+    let typed = [
+      (UserPreferences::THEME_KEY, preferences.theme),
+      (UserPreferences::DEFAULT_VOLUME_KEY, preferences.default_volume.map(|v| v.to_string())),
+      (UserPreferences::LOCALE_KEY, preferences.locale),
+      (UserPreferences::MONO_DOWNMIX_KEY, preferences.mono_downmix.map(|v| v.to_string())),
+      (UserPreferences::CHANNEL_BALANCE_KEY, preferences.channel_balance.map(|v| v.to_string())),
+      (UserPreferences::CROSSFEED_KEY, preferences.crossfeed.map(|v| v.to_string())),
+    ];
+    let is_typed_key = |key: &str| key == UserPreferences::PREFERRED_PLAYBACK_SOURCE_KEY || typed.iter().any(|(typed_key, _)| *typed_key == key);
+    let other: Vec<_> = preferences.other.into_iter().filter(|(key, _)| !is_typed_key(key)).collect();
+    let new_user_preferences: Vec<_> = typed.iter()
+      .filter_map(|(key, value)| value.clone().map(|value| (key.to_string(), value)))
+      .chain(other)
+      .map(|(key, value)| UserPreference { user_id: input_user_id, key, value })
+      .collect();
+    if new_user_preferences.len() > MAX_USER_PREFERENCES {
+      return Err(UserPreferencesError::TooManyPreferences(new_user_preferences.len(), MAX_USER_PREFERENCES));
+    }
+    if let Some(too_long) = new_user_preferences.iter().find(|p| p.key.len() > MAX_USER_PREFERENCE_LENGTH || p.value.len() > MAX_USER_PREFERENCE_LENGTH) {
+      let key: String = too_long.key.chars().take(64).collect();
+      return Err(UserPreferencesError::PreferenceTooLong(key, MAX_USER_PREFERENCE_LENGTH));
+    }
+    self.connection.transaction::<_, UserPreferencesError, _>(|| {
+      {
+        use schema::user_preference::dsl::*;
+        event!(Level::DEBUG, user_id = input_user_id, count = new_user_preferences.len(), "Setting user preferences");
+        time!("set_user_preferences.delete", diesel::delete(user_preference.filter(user_id.eq(input_user_id))).execute(&self.connection)?);
+        time!("set_user_preferences.insert", diesel::insert_into(user_preference).values(&new_user_preferences).execute(&self.connection)?);
+      }
+      if let Some(preferred_source) = preferences.preferred_playback_source {
+        let playback_preference = self.get_playback_preference(input_user_id)?;
+        self.set_playback_preference(PlaybackPreference { preferred_source, ..playback_preference })?;
+      }
+      Ok(self.get_user_preferences(input_user_id)?)
+    })
+  }
+}
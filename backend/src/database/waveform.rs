@@ -0,0 +1,67 @@
+use std::backtrace::Backtrace;
+
+use diesel::prelude::*;
+use thiserror::Error;
+use tracing::{event, instrument, Level};
+
+use musium_core::model::{CachedTrackWaveform, LocalSource, LocalTrack, MediaKind, TrackWaveform};
+use musium_core::schema;
+
+use crate::model::LocalSourceEx;
+use crate::waveform::{generate_waveform, WaveformGenerateError};
+
+use super::DatabaseConnection;
+
+#[derive(Debug, Error)]
+pub enum TrackWaveformError {
+  #[error("Failed to execute a database query")]
+  DatabaseQueryFail(#[from] diesel::result::Error, Backtrace),
+  #[error("Failed to generate waveform")]
+  GenerateFail(#[from] WaveformGenerateError, Backtrace),
+}
+
+impl DatabaseConnection {
+  /// Gets the waveform of the track with `input_track_id`, generating and caching it if it was not yet generated, or if
+  /// the local audio file has changed since it was generated. Waveforms are not cached while the database is quiesced.
+  /// Returns `Ok(None)` if the track has no local audio file.
+  #[instrument(skip(self))]
+  pub fn get_or_generate_track_waveform(&self, input_track_id: i32) -> Result<Option<TrackWaveform>, TrackWaveformError> {
+    let data: Option<(LocalTrack, LocalSource)> = {
+      use schema::local_track::dsl::*;
+      time!("get_or_generate_track_waveform.select_local_track", local_track
+        .filter(track_id.eq(input_track_id))
+        .inner_join(schema::local_source::table)
+        .first::<(LocalTrack, LocalSource)>(&self.connection)
+        .optional()?)
+    };
+    let (local_track, path) = match data {
+      Some((local_track, local_source)) => match local_source.track_file_path(&local_track) {
+        Some(path) => (local_track, path),
+        None => return Ok(None),
+      },
+      None => return Ok(None),
+    };
+
+    use schema::track_waveform::dsl::*;
+    let cached = time!("get_or_generate_track_waveform.select", track_waveform.find(input_track_id).first::<CachedTrackWaveform>(&self.connection).optional()?);
+    if let Some(cached) = cached {
+      if cached.local_hash == local_track.hash {
+        // The peaks may have been evicted from the media cache, in which case they are generated again.
+        if let Some(blob) = self.get_media_blob(&cached.peaks_hash)? {
+          return Ok(Some(TrackWaveform { track_id: input_track_id, local_hash: cached.local_hash, peaks: blob.data }));
+        }
+      }
+    }
+
+    event!(Level::DEBUG, ?path, "Generating waveform");
+    let waveform = TrackWaveform { track_id: input_track_id, local_hash: local_track.hash, peaks: generate_waveform(&path)? };
+    if self.is_quiesced() { return Ok(Some(waveform)); }
+    let cached = CachedTrackWaveform {
+      track_id: input_track_id,
+      local_hash: local_track.hash,
+      peaks_hash: self.put_media_blob(MediaKind::Waveform, "application/octet-stream", &waveform.peaks, false)?,
+    };
+    time!("get_or_generate_track_waveform.replace", diesel::replace_into(track_waveform).values(&cached).execute(&self.connection)?);
+    Ok(Some(waveform))
+  }
+}
@@ -0,0 +1,53 @@
+use diesel::prelude::*;
+use tracing::{event, Level};
+
+use musium_core::model::{NewWebhook, Webhook};
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+impl DatabaseConnection {
+  pub fn list_webhooks(&self) -> Result<Vec<Webhook>, DatabaseQueryError> {
+    use schema::webhook::dsl::*;
+    Ok(time!("list_webhooks.select", webhook.order(id.asc()).load::<Webhook>(&self.connection)?))
+  }
+
+  pub fn list_enabled_webhooks(&self) -> Result<Vec<Webhook>, DatabaseQueryError> {
+    use schema::webhook::dsl::*;
+    Ok(time!("list_enabled_webhooks.select", webhook.filter(enabled.eq(true)).order(id.asc()).load::<Webhook>(&self.connection)?))
+  }
+
+  pub fn create_webhook(&self, new_webhook: &NewWebhook) -> Result<Webhook, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::webhook::dsl::*;
+      event!(Level::DEBUG, url = %new_webhook.url, "Inserting webhook");
+      time!("create_webhook.insert", diesel::insert_into(webhook).values(new_webhook).execute(&self.connection)?);
+      Ok(time!("create_webhook.select_inserted", webhook.order(id.desc()).first::<Webhook>(&self.connection)?))
+    })
+  }
+
+  pub fn set_webhook_enabled_by_id(&self, webhook_id: i32, input_enabled: bool) -> Result<Option<Webhook>, DatabaseQueryError> {
+    let db_webhook = {
+      use schema::webhook::dsl::*;
+      time!("set_webhook_enabled_by_id.select", webhook.find(webhook_id).first::<Webhook>(&self.connection).optional()?)
+    };
+    if let Some(mut db_webhook) = db_webhook {
+      db_webhook.enabled = input_enabled;
+      time!("set_webhook_enabled_by_id.update", db_webhook.save_changes::<Webhook>(&*self.connection)?);
+      Ok(Some(db_webhook))
+    } else {
+      Ok(None)
+    }
+  }
+
+  pub fn delete_webhook_by_id(&self, webhook_id: i32) -> Result<Option<Webhook>, DatabaseQueryError> {
+    self.connection.transaction::<_, DatabaseQueryError, _>(|| {
+      use schema::webhook::dsl::*;
+      let db_webhook = time!("delete_webhook_by_id.select", webhook.find(webhook_id).first::<Webhook>(&self.connection).optional()?);
+      if db_webhook.is_some() {
+        time!("delete_webhook_by_id.delete", diesel::delete(webhook.find(webhook_id)).execute(&self.connection)?);
+      }
+      Ok(db_webhook)
+    })
+  }
+}
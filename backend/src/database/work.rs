@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use itertools::Itertools;
+use tracing::{event, Level};
+
+use musium_core::api::{ComposerInfo, WorkInfo};
+use musium_core::collation::{name_collation_key, normalize_name};
+use musium_core::model::{Artist, NewWork, Track, TrackArtistRole, Work};
+use musium_core::model::collection::TracksRaw;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+
+// Querying
+
+impl DatabaseConnection {
+  /// Lists the artists that composed tracks, along with the number of their works that have tracks, ordered by sort name.
+  pub fn list_composers(&self) -> Result<Vec<ComposerInfo>, DatabaseQueryError> {
+    let work_counts = self.count_works_with_tracks()?;
+    let track_composer_ids: Vec<i32> = {
+      use schema::track_artist::dsl::*;
+      time!("list_composers.select_track_composers", track_artist.select(artist_id).filter(role.eq(TrackArtistRole::Composer)).distinct().load(&self.connection)?)
+    };
+    let composer_ids: Vec<i32> = track_composer_ids.into_iter().chain(work_counts.keys().copied()).unique().collect();
+    let composers: Vec<Artist> = {
+      use schema::artist::dsl::*;
+      time!("list_composers.select_artists", artist.filter(id.eq_any(composer_ids)).order(sort_name.asc()).load(&self.connection)?)
+    };
+    Ok(composers.into_iter()
+      .map(|artist| {
+        let work_count = work_counts.get(&artist.id).map_or(0, |work_ids| work_ids.len() as i64);
+        ComposerInfo { artist, work_count }
+      })
+      .collect())
+  }
+
+  /// Lists the works composed by the artist with `input_composer_id` that have tracks, along with their number of
+  /// tracks, ordered by name.
+  pub fn list_composer_works(&self, input_composer_id: i32) -> Result<Vec<WorkInfo>, DatabaseQueryError> {
+    let works: Vec<Work> = {
+      use schema::work::dsl::*;
+      time!("list_composer_works.select_works", work.filter(composer_id.eq(input_composer_id)).order(name_key.asc()).load(&self.connection)?)
+    };
+    let work_ids: Vec<i32> = works.iter().map(|work| work.id).collect();
+    let track_work_ids: Vec<Option<i32>> = {
+      use schema::track::dsl::*;
+      time!("list_composer_works.select_tracks", track.select(work_id).filter(work_id.eq_any(work_ids)).load(&self.connection)?)
+    };
+    let track_counts = track_work_ids.into_iter().flatten().counts();
+    Ok(works.into_iter()
+      .filter_map(|work| {
+        let track_count = *track_counts.get(&work.id)? as i64;
+        Some(WorkInfo { work, track_count })
+      })
+      .collect())
+  }
+
+  /// Lists the tracks of the work with `input_work_id` that pass the content filter of the user with `user_id`, ordered by
+  /// movement number, and then by disc and track number. Tracks of the same movement on different albums are all
+  /// included, as they are usually different recordings of the work.
+  pub fn list_work_tracks(&self, user_id: i32, input_work_id: i32) -> Result<TracksRaw, DatabaseQueryError> {
+    let content_filter = self.get_user_content_filter(user_id)?.filter;
+    let work_track_ids = || {
+      use schema::track::dsl::*;
+      filter_content!(track.select(id).filter(work_id.eq(input_work_id)).into_boxed(), content_filter)
+    };
+    let mut tracks: Vec<Track> = {
+      use schema::track::dsl::*;
+      time!("list_work_tracks.select", track.filter(id.eq_any(work_track_ids())).load(&self.connection)?)
+    };
+    // Sort in Rust, as tracks without a number must be sorted last, while SQLite sorts NULL first.
+    let key = |number: Option<i32>| (number.is_none(), number);
+    tracks.sort_by_key(|track| (key(track.movement_number), track.album_id, key(track.disc_number), key(track.track_number)));
+    self.select_tracks_raw(tracks, work_track_ids)
+  }
+
+  /// Counts the works that have tracks, as IDs of works by composer ID.
+  fn count_works_with_tracks(&self) -> Result<HashMap<i32, HashSet<i32>>, DatabaseQueryError> {
+    let works: Vec<(i32, Option<i32>)> = time!("count_works_with_tracks.select", schema::work::table
+      .inner_join(schema::track::table)
+      .select((schema::work::id, schema::work::composer_id))
+      .load(&self.connection)?);
+    let mut work_ids_by_composer: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for (work_id, composer_id) in works {
+      if let Some(composer_id) = composer_id {
+        work_ids_by_composer.entry(composer_id).or_default().insert(work_id);
+      }
+    }
+    Ok(work_ids_by_composer)
+  }
+}
+
+// Synchronization
+
+impl DatabaseConnection {
+  /// Selects the work named `input_name` composed by the artist with `input_composer_id`, inserting it if it does not
+  /// exist. Works with the same name by different composers, such as "Symphony No. 5", are different works.
+  pub(crate) fn select_or_insert_work(&self, input_name: &str, input_composer_id: Option<i32>) -> Result<Work, diesel::result::Error> {
+    use schema::work::dsl::*;
+    let input_name_key = name_collation_key(input_name);
+    let mut select_query = work.filter(name_key.eq(&input_name_key)).into_boxed();
+    select_query = match input_composer_id {
+      Some(input_composer_id) => select_query.filter(composer_id.eq(input_composer_id)),
+      None => select_query.filter(composer_id.is_null()),
+    };
+    if let Some(db_work) = time!("select_or_insert_work.select", select_query.first::<Work>(&self.connection).optional()?) {
+      return Ok(db_work);
+    }
+    let new_work = NewWork { name: normalize_name(input_name), name_key: input_name_key, composer_id: input_composer_id };
+    event!(Level::DEBUG, ?new_work, "Inserting work");
+    time!("select_or_insert_work.insert", diesel::insert_into(work).values(new_work).execute(&self.connection)?);
+    // NOTE: must be executed in a transaction for consistency
+    Ok(time!("select_or_insert_work.select_inserted", work.order(id.desc()).first(&self.connection)?))
+  }
+}
@@ -0,0 +1,52 @@
+use chrono::{Duration, NaiveDate, Utc};
+use diesel::prelude::*;
+
+use musium_core::api::{YearReview, YearReviewQuery};
+use musium_core::model::PlayHistory;
+use musium_core::schema;
+
+use super::{DatabaseConnection, DatabaseQueryError};
+use super::play_history::{listening_seconds, track_play_counts, track_skip_counts};
+
+impl DatabaseConnection {
+  /// Summarizes the listening of the user with `input_user_id` in `year` from their play history. Returns `None` if
+  /// `year` is out of range.
+  pub fn get_year_review(&self, input_user_id: i32, year: i32, query: YearReviewQuery) -> Result<Option<YearReview>, DatabaseQueryError> {
+    let utc_offset = Duration::minutes(query.utc_offset_minutes as i64);
+    let (start, end) = match (NaiveDate::from_ymd_opt(year, 1, 1), NaiveDate::from_ymd_opt(year + 1, 1, 1)) {
+      (Some(start), Some(end)) => (start.and_hms(0, 0, 0) - utc_offset, end.and_hms(0, 0, 0) - utc_offset),
+      _ => return Ok(None),
+    };
+    let plays: Vec<PlayHistory> = {
+      use schema::play_history::dsl::*;
+      time!("get_year_review.select_plays", play_history
+        .filter(user_id.eq(input_user_id))
+        .filter(played_at.lt(end))
+        .order(played_at.asc())
+        .load(&self.connection)?)
+    };
+    let (plays_before, plays): (Vec<_>, Vec<_>) = plays.into_iter().partition(|p| p.played_at < start);
+    // The last play of a past year can last at most until the end of the year.
+    let now = Utc::now().naive_utc().min(end);
+    let track_play_counts = track_play_counts(&plays);
+    let artist_play_counts = self.artist_play_counts(&track_play_counts)?;
+    let album_play_counts = self.album_play_counts(&track_play_counts)?;
+    let new_artist_play_counts = {
+      let artist_play_counts_before = self.artist_play_counts(&track_play_counts(&plays_before))?;
+      artist_play_counts.iter()
+        .filter(|(artist_id, _)| !artist_play_counts_before.contains_key(artist_id))
+        .map(|(artist_id, count)| (*artist_id, *count))
+        .collect()
+    };
+    Ok(Some(YearReview {
+      year,
+      play_count: plays.len() as i64,
+      minutes_listened: listening_seconds(&plays, now) / 60,
+      top_artists: self.top_artists(&artist_play_counts)?,
+      top_albums: self.top_albums(&album_play_counts)?,
+      top_tracks: self.top_tracks(&track_play_counts)?,
+      most_skipped_tracks: self.top_tracks(&track_skip_counts(&plays))?,
+      new_discoveries: self.top_artists(&new_artist_play_counts)?,
+    }))
+  }
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{event, Level};
+
+use musium_core::api::{Event, EventsPoll};
+
+/// Broadcasts events to all subscribers, such as WebSocket connections of clients. Also keeps a history of the most
+/// recent events, numbered in order of publication, for clients that poll for events.
+#[derive(Clone)]
+pub struct EventBus {
+  tx: broadcast::Sender<Event>,
+  history: Arc<Mutex<EventHistory>>,
+}
+
+struct EventHistory {
+  events: VecDeque<Event>,
+  capacity: usize,
+  /// Sequence number of the next event to be published.
+  next: u64,
+}
+
+impl EventBus {
+  pub fn new(capacity: usize) -> Self {
+    let (tx, _) = broadcast::channel(capacity);
+    let history = EventHistory { events: VecDeque::with_capacity(capacity), capacity, next: 0 };
+    Self { tx, history: Arc::new(Mutex::new(history)) }
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+    self.tx.subscribe()
+  }
+
+  pub fn publish(&self, event: Event) {
+    event!(Level::TRACE, ?event, "Publishing event");
+    {
+      // UNWRAP: errors if a holder of the lock has panicked -> we panic as well.
+      let mut history = self.history.lock().unwrap();
+      if history.events.len() == history.capacity {
+        history.events.pop_front();
+      }
+      history.events.push_back(event.clone());
+      history.next += 1;
+    }
+    self.tx.send(event).ok(); // OK: no subscribers -> we don't care.
+  }
+
+  /// Gets the events in the history that were published after the event with sequence number `since`.
+  pub fn poll(&self, since: Option<u64>) -> EventsPoll {
+    // UNWRAP: errors if a holder of the lock has panicked -> we panic as well.
+    let history = self.history.lock().unwrap();
+    let next = history.next;
+    let since = match since {
+      Some(since) => since.min(next),
+      None => return EventsPoll { events: Vec::new(), next, missed: false },
+    };
+    let first = next - history.events.len() as u64;
+    let events = history.events.iter().skip(since.saturating_sub(first) as usize).cloned().collect();
+    EventsPoll { events, next, missed: since < first }
+  }
+
+  /// Gets the events that were published after the event with sequence number `since`, waiting at most `wait` for an
+  /// event to be published if there are none yet.
+  pub async fn wait_poll(&self, since: Option<u64>, wait: Duration) -> EventsPoll {
+    // Subscribe before polling, so that events published in between are not missed.
+    let mut rx = self.subscribe();
+    let poll = self.poll(since);
+    if since.is_none() || !poll.events.is_empty() || poll.missed {
+      return poll;
+    }
+    wait_for_event(&mut rx, wait, |_| Some(())).await;
+    self.poll(since)
+  }
+}
+
+impl Default for EventBus {
+  fn default() -> Self { Self::new(64) }
+}
+
+/// Waits at most `wait` for an event received by `rx` that `select` maps to `Some`, returning that mapping. Returns
+/// `None` when `wait` has passed.
+pub async fn wait_for_event<T>(rx: &mut broadcast::Receiver<Event>, wait: Duration, mut select: impl FnMut(Event) -> Option<T>) -> Option<T> {
+  let wait_for = async {
+    loop {
+      match rx.recv().await {
+        Ok(event) => if let Some(selected) = select(event) { return Some(selected); }
+        Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => return None,
+      }
+    }
+  };
+  tokio::time::timeout(wait, wait_for).await.ok().flatten()
+}
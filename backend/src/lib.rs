@@ -3,7 +3,29 @@
 #[macro_use] // extern crate with #[macro_use] because diesel does not fully support Rust 2018 yet.
 extern crate diesel;
 
+pub mod artist_info;
+pub mod artwork;
+pub mod audio_features;
+pub mod beets;
+pub mod cover;
+pub mod credits;
 pub mod database;
+pub mod event;
+pub mod listening_session;
+pub mod lyrics;
+pub mod managed_library;
+pub mod mix;
 pub mod model;
+pub mod mood;
+pub mod party;
 pub mod password;
+pub mod release_check;
 pub mod sync;
+pub mod tagger_hook;
+pub mod track_inspection;
+pub mod track_validation;
+pub mod track_version;
+pub mod waveform;
+pub mod webhook;
+pub mod worker;
+pub mod year_review;
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::api::{Event, ListeningSession, ListeningSessionCommand, NewListeningSession};
+
+use crate::event::EventBus;
+
+/// In-memory registry of listening sessions. Changes to sessions are published on the event bus, so that members can
+/// synchronize their playback.
+#[derive(Clone)]
+pub struct ListeningSessions {
+  inner: Arc<Mutex<Inner>>,
+  event_bus: EventBus,
+}
+
+struct Inner {
+  sessions: HashMap<i32, ListeningSession>,
+  next_id: i32,
+}
+
+#[derive(Debug, Error)]
+pub enum ListeningSessionError {
+  #[error("User with ID '{0}' is not the host of listening session with ID '{1}'")]
+  NotHost(i32, i32),
+}
+
+impl ListeningSessions {
+  pub fn new(event_bus: EventBus) -> Self {
+    let inner = Arc::new(Mutex::new(Inner { sessions: HashMap::new(), next_id: 1 }));
+    Self { inner, event_bus }
+  }
+
+  pub fn list(&self) -> Vec<ListeningSession> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    self.inner.lock().unwrap().sessions.values().cloned().collect()
+  }
+
+  pub fn get(&self, id: i32) -> Option<ListeningSession> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    self.inner.lock().unwrap().sessions.get(&id).cloned()
+  }
+
+  /// Finds the listening session hosted by the user with `user_id`, or otherwise the listening session with the lowest
+  /// ID that the user is a member of.
+  pub fn find_by_user(&self, user_id: i32) -> Option<ListeningSession> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let inner = self.inner.lock().unwrap();
+    inner.sessions.values().find(|session| session.host_user_id == user_id)
+      .or_else(|| inner.sessions.values().filter(|session| session.member_user_ids.contains(&user_id)).min_by_key(|session| session.id))
+      .cloned()
+  }
+
+  pub fn create(&self, host_user_id: i32, new_session: NewListeningSession) -> ListeningSession {
+    let session = {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      let id = inner.next_id;
+      inner.next_id += 1;
+      let session = ListeningSession {
+        id,
+        host_user_id,
+        member_user_ids: vec![host_user_id],
+        drift_tolerance_ms: new_session.drift_tolerance_ms,
+        track_id: None,
+        playing: false,
+        position_ms: 0,
+        updated_at: Utc::now().naive_utc(),
+      };
+      inner.sessions.insert(id, session.clone());
+      session
+    };
+    event!(Level::DEBUG, ?session, "Created listening session");
+    self.event_bus.publish(Event::ListeningSessionChanged(session.clone()));
+    session
+  }
+
+  pub fn join(&self, id: i32, user_id: i32) -> Option<ListeningSession> {
+    self.modify(id, |session| {
+      if !session.member_user_ids.contains(&user_id) {
+        session.member_user_ids.push(user_id);
+      }
+    })
+  }
+
+  /// Leaves the listening session with `id`. When the host leaves, the session is ended. Returns `false` if the session
+  /// does not exist.
+  pub fn leave(&self, id: i32, user_id: i32) -> bool {
+    let ended = {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      match inner.sessions.get(&id).map(|session| session.host_user_id) {
+        None => return false,
+        Some(host_user_id) if host_user_id == user_id => {
+          inner.sessions.remove(&id);
+          true
+        }
+        Some(_) => false,
+      }
+    };
+    if ended {
+      event!(Level::DEBUG, id, "Ended listening session");
+      self.event_bus.publish(Event::ListeningSessionEnded(id));
+    } else {
+      self.modify(id, |session| session.member_user_ids.retain(|u| *u != user_id));
+    }
+    true
+  }
+
+  /// Applies `command` sent by the user with `user_id` to the listening session with `id`, and fans out the new state to
+  /// all members. Only the host may send commands.
+  pub fn command(&self, id: i32, user_id: i32, command: ListeningSessionCommand) -> Result<Option<ListeningSession>, ListeningSessionError> {
+    if let Some(session) = self.get(id) {
+      if session.host_user_id != user_id {
+        return Err(ListeningSessionError::NotHost(user_id, id));
+      }
+    }
+    Ok(self.modify(id, |session| {
+      match command {
+        ListeningSessionCommand::Play => session.playing = true,
+        ListeningSessionCommand::Pause => session.playing = false,
+        ListeningSessionCommand::Seek { position_ms } => session.position_ms = position_ms,
+        ListeningSessionCommand::ChangeTrack { track_id } => {
+          session.track_id = Some(track_id);
+          session.position_ms = 0;
+        }
+      }
+    }))
+  }
+
+  fn modify(&self, id: i32, f: impl FnOnce(&mut ListeningSession)) -> Option<ListeningSession> {
+    let session = {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      let session = inner.sessions.get_mut(&id)?;
+      // Rebase the position to now, so that the position stays correct for a playing session.
+      let now = Utc::now().naive_utc();
+      session.position_ms = session.expected_position_ms(now);
+      session.updated_at = now;
+      f(session);
+      session.clone()
+    };
+    self.event_bus.publish(Event::ListeningSessionChanged(session.clone()));
+    Some(session)
+  }
+}
@@ -0,0 +1,75 @@
+use std::backtrace::Backtrace;
+use std::cmp::Ordering;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use thiserror::Error;
+
+use musium_core::api::{Lyrics, LyricsLine};
+
+#[derive(Debug, Error)]
+pub enum LyricsReadError {
+  #[error("Failed to read lyrics file")]
+  ReadFail(#[from] std::io::Error, Backtrace),
+}
+
+/// Reads the lyrics of the track with `track_id` from a file next to its audio file at `track_path`, with the same name
+/// but a different extension. Synced lyrics in an LRC file (`.lrc`) are preferred over plain lyrics in a text file
+/// (`.txt`). Returns `Ok(None)` if there is no lyrics file.
+pub fn read_lyrics(track_id: i32, track_path: impl AsRef<Path>) -> Result<Option<Lyrics>, LyricsReadError> {
+  let track_path = track_path.as_ref();
+  if let Some(source) = read_if_exists(&track_path.with_extension("lrc"))? {
+    let lines = parse_lrc(&source);
+    return Ok(Some(Lyrics { track_id, synced: true, lines }));
+  }
+  if let Some(source) = read_if_exists(&track_path.with_extension("txt"))? {
+    let lines = source.lines().map(|line| LyricsLine { time: None, text: line.trim_end().to_string() }).collect();
+    return Ok(Some(Lyrics { track_id, synced: false, lines }));
+  }
+  Ok(None)
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>, std::io::Error> {
+  match std::fs::read(path) {
+    // Lyrics files are not always UTF-8 encoded; rather show a few garbled characters than no lyrics at all.
+    Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(e),
+  }
+}
+
+/// Parses the lines of LRC `source`, such as `[01:23.45]Some lyrics`, ordered by time. Lines with multiple time tags are
+/// repeated at each of those times, and the `[offset:<milliseconds>]` tag is applied to all times. Other tags such as
+/// `[ar:<artist>]` and lines without time tags are ignored.
+fn parse_lrc(source: &str) -> Vec<LyricsLine> {
+  let mut offset = 0f64;
+  let mut lines = Vec::new();
+  for line in source.lines() {
+    let mut rest = line.trim();
+    let mut times = Vec::new();
+    while let Some((tag, after_tag)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+      rest = after_tag;
+      if let Some(time) = parse_lrc_time(tag) {
+        times.push(time);
+      } else if let Some(milliseconds) = tag.strip_prefix("offset:").and_then(|ms| ms.trim().parse::<f64>().ok()) {
+        offset = milliseconds / 1000f64;
+      }
+    }
+    let text = rest.trim();
+    lines.extend(times.into_iter().map(|time| LyricsLine { time: Some(time), text: text.to_string() }));
+  }
+  // A positive offset shows the lyrics earlier.
+  for line in &mut lines {
+    line.time = line.time.map(|time| (time - offset).max(0f64));
+  }
+  lines.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+  lines
+}
+
+/// Parses an LRC time tag such as `01:23.45` into seconds.
+fn parse_lrc_time(tag: &str) -> Option<f64> {
+  let (minutes, seconds) = tag.split_once(':')?;
+  let minutes: u32 = minutes.trim().parse().ok()?;
+  let seconds: f64 = seconds.trim().parse().ok()?;
+  Some(minutes as f64 * 60f64 + seconds)
+}
@@ -0,0 +1,188 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Default template of the paths of files in the managed library, which results in paths such as
+/// `Artist/Album/01 Title.mp3`.
+pub const DEFAULT_LIBRARY_TEMPLATE: &str = "{album_artist}/{album}/{track} {title}";
+
+/// Maximum length in characters of a single component of a path in the managed library, as most filesystems do not
+/// support file names longer than 255 bytes.
+const MAX_COMPONENT_LENGTH: usize = 120;
+
+/// Settings of the managed library: the local source with `local_source_id` of which musium owns the directory, and
+/// organizes the files into paths given by `template`.
+#[derive(Clone, Debug)]
+pub struct ManagedLibrarySettings {
+  pub local_source_id: i32,
+  pub template: LibraryTemplate,
+}
+
+/// Template of the path of a file in the managed library, relative to the directory of the managed local source and
+/// without the file extension. Components are separated by `/`, and may contain the following placeholders:
+///
+/// - `{album_artist}`: names of the album artists, or of the track artists if the album has no artists.
+/// - `{artist}`: names of the track artists.
+/// - `{album}`: name of the album.
+/// - `{title}`: title of the track.
+/// - `{track}`: track number, padded with zeroes to two digits, or nothing if unknown.
+/// - `{disc}`: disc number, or nothing if unknown.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LibraryTemplate {
+  template: String,
+  components: Vec<Vec<Segment>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Segment {
+  Literal(String),
+  Field(Field),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Field {
+  AlbumArtist,
+  Artist,
+  Album,
+  Title,
+  Track,
+  Disc,
+}
+
+impl Field {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "album_artist" => Some(Field::AlbumArtist),
+      "artist" => Some(Field::Artist),
+      "album" => Some(Field::Album),
+      "title" => Some(Field::Title),
+      "track" => Some(Field::Track),
+      "disc" => Some(Field::Disc),
+      _ => None,
+    }
+  }
+}
+
+/// Metadata of a track from which its path in the managed library is created.
+#[derive(Clone, Debug)]
+pub struct LibraryPathFields<'a> {
+  pub album_artists: &'a [String],
+  pub artists: &'a [String],
+  pub album: &'a str,
+  pub title: &'a str,
+  pub track_number: Option<i32>,
+  pub disc_number: Option<i32>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseLibraryTemplateError {
+  #[error("Library template is empty")]
+  EmptyFail,
+  #[error("Library template '{0}' must be a relative path without empty components")]
+  EmptyComponentFail(String),
+  #[error("Library template '{0}' has an unclosed placeholder")]
+  UnclosedPlaceholderFail(String),
+  #[error("Library template '{0}' has unknown placeholder '{{{1}}}'")]
+  UnknownPlaceholderFail(String, String),
+  #[error("Library template '{0}' must not contain '.' or '..' components")]
+  RelativeComponentFail(String),
+}
+
+impl FromStr for LibraryTemplate {
+  type Err = ParseLibraryTemplateError;
+
+  fn from_str(template: &str) -> Result<Self, Self::Err> {
+    use ParseLibraryTemplateError::*;
+    if template.trim().is_empty() { return Err(EmptyFail); }
+    let mut components = Vec::new();
+    for component in template.split('/') {
+      if component.trim().is_empty() { return Err(EmptyComponentFail(template.to_string())); }
+      if component == "." || component == ".." { return Err(RelativeComponentFail(template.to_string())); }
+      let mut segments = Vec::new();
+      let mut rest = component;
+      while let Some(start) = rest.find('{') {
+        if start > 0 {
+          segments.push(Segment::Literal(rest[..start].to_string()));
+        }
+        let end = rest[start..].find('}').ok_or_else(|| UnclosedPlaceholderFail(template.to_string()))? + start;
+        let name = &rest[start + 1..end];
+        let field = Field::from_name(name).ok_or_else(|| UnknownPlaceholderFail(template.to_string(), name.to_string()))?;
+        segments.push(Segment::Field(field));
+        rest = &rest[end + 1..];
+      }
+      if !rest.is_empty() {
+        segments.push(Segment::Literal(rest.to_string()));
+      }
+      components.push(segments);
+    }
+    Ok(Self { template: template.to_string(), components })
+  }
+}
+
+impl Display for LibraryTemplate {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(&self.template) }
+}
+
+impl Default for LibraryTemplate {
+  fn default() -> Self {
+    // UNWRAP: default template is valid.
+    DEFAULT_LIBRARY_TEMPLATE.parse().unwrap()
+  }
+}
+
+impl LibraryTemplate {
+  /// Renders the path of a file with `fields` and `extension`, relative to the directory of the managed local source.
+  /// Characters that are not allowed in file names on common filesystems are replaced with `_`, and components that
+  /// end up empty are replaced with `_`.
+  pub fn render(&self, fields: &LibraryPathFields, extension: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+    let last = self.components.len() - 1;
+    for (i, segments) in self.components.iter().enumerate() {
+      let mut component = String::new();
+      for segment in segments {
+        match segment {
+          Segment::Literal(literal) => component.push_str(literal),
+          Segment::Field(field) => component.push_str(&sanitize(&fields.value(*field))),
+        }
+      }
+      let mut component: String = component.trim().chars().take(MAX_COMPONENT_LENGTH).collect();
+      // Trailing dots and spaces are stripped by Windows, and leading dots hide files.
+      component = component.trim_end_matches(|c| c == '.' || c == ' ').trim_start_matches('.').to_string();
+      if component.is_empty() {
+        component.push('_');
+      }
+      if i == last && !extension.is_empty() {
+        component.push('.');
+        component.push_str(extension);
+      }
+      path.push(component);
+    }
+    path
+  }
+}
+
+impl LibraryPathFields<'_> {
+  fn value(&self, field: Field) -> String {
+    match field {
+      Field::AlbumArtist if !self.album_artists.is_empty() => self.album_artists.join(", "),
+      Field::AlbumArtist | Field::Artist => self.artists.join(", "),
+      Field::Album => self.album.to_string(),
+      Field::Title => self.title.to_string(),
+      Field::Track => self.track_number.map(|track_number| format!("{:02}", track_number)).unwrap_or_default(),
+      Field::Disc => self.disc_number.map(|disc_number| disc_number.to_string()).unwrap_or_default(),
+    }
+  }
+}
+
+/// Replaces characters in `value` that are not allowed in file names on common filesystems with `_`.
+fn sanitize(value: &str) -> String {
+  value.chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_control() => '_',
+      c => c,
+    })
+    .collect()
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::{self, task, time};
+use tracing::{event, instrument, Level};
+
+use musium_core::format_error::FormatError;
+
+use crate::database::Database;
+use crate::worker::WorkerState;
+
+/// Periodically regenerates the auto-generated mixes of all users whose mixes are stale. Paused while the database
+/// is quiesced.
+pub struct MixGenerationTask {
+  handle: task::JoinHandle<()>,
+  state: WorkerState,
+}
+
+impl MixGenerationTask {
+  pub fn spawn(database: Arc<Database>, interval: Duration) -> Self {
+    let state = WorkerState::default();
+    let task_state = state.clone();
+    let handle = tokio::spawn(async move {
+      let _stopped = task_state.stopped_on_drop();
+      let mut interval = time::interval(interval);
+      loop {
+        interval.tick().await;
+        if database.is_quiesced() { continue; }
+        let _busy = task_state.busy();
+        Self::generate(database.clone()).await;
+      }
+    });
+    Self { handle, state }
+  }
+
+  pub fn state(&self) -> WorkerState { self.state.clone() }
+
+  #[instrument(skip(database))]
+  async fn generate(database: Arc<Database>) {
+    let result = task::spawn_blocking(move || {
+      let connection = database.connect().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))?;
+      connection.regenerate_stale_playlist_mixes().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+    }).await;
+    match result {
+      Ok(Ok(regenerated)) => event!(Level::DEBUG, "Regenerated {} mix(es)", regenerated),
+      Ok(Err(())) => {} // Error was already logged.
+      Err(e) => event!(Level::ERROR, "Generating mixes panicked: {:?}", e),
+    }
+  }
+}
+
+impl Drop for MixGenerationTask {
+  fn drop(&mut self) {
+    self.handle.abort();
+  }
+}
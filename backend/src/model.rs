@@ -74,6 +74,17 @@ impl UpdateFrom<musium_spotify_client::Album> for Album {
 
 // Track
 
+/// Merges whether a track has explicit content according to a source, `new`, into `current`. Sources that do not say
+/// leave it unchanged, and a track stays explicit once any source says so, as leaving out clean tracks is better than
+/// letting explicit tracks through content filters.
+pub fn merge_explicit(current: Option<bool>, new: Option<bool>) -> Option<bool> {
+  match (current, new) {
+    (current, None) => current,
+    (Some(true), _) => Some(true),
+    (_, new) => new,
+  }
+}
+
 pub trait TrackEx {
   fn check_metadata_changed(&self, album: &Album, filesystem_sync_track: &FilesystemSyncTrack) -> bool;
 }
@@ -86,6 +97,7 @@ impl TrackEx for Track {
     if self.track_number != filesystem_sync_track.track_number { return true; }
     if self.track_total != filesystem_sync_track.track_total { return true; }
     if self.title != filesystem_sync_track.title { return true; }
+    if self.explicit != merge_explicit(self.explicit, filesystem_sync_track.explicit) { return true; }
     return false;
   }
 }
@@ -99,6 +111,7 @@ impl UpdateTrackFrom<FilesystemSyncTrack> for Track {
     update!(self.track_number, source.track_number, changed);
     update!(self.track_total, source.track_total, changed);
     update!(self.title, source.title.clone(), changed);
+    update!(self.explicit, merge_explicit(self.explicit, source.explicit), changed);
     changed
   }
 }
@@ -110,6 +123,7 @@ impl UpdateTrackFrom<musium_spotify_client::TrackSimple> for Track {
     update!(self.disc_number, Some(source.disc_number), changed);
     update!(self.track_number, Some(source.track_number), changed);
     update!(self.title, source.name.clone(), changed);
+    update!(self.explicit, merge_explicit(self.explicit, Some(source.explicit)), changed);
     changed
   }
 }
@@ -119,6 +133,7 @@ impl UpdateTrackFrom<musium_spotify_client::TrackSimple> for Track {
 pub trait LocalTrackEx {
   fn check_hash_changed(&self, filesystem_sync_track: &FilesystemSyncTrack) -> bool;
   fn update_from(&mut self, filesystem_sync_track: &FilesystemSyncTrack) -> bool;
+  fn update_replay_gain_from(&mut self, filesystem_sync_track: &FilesystemSyncTrack) -> bool;
 }
 
 impl LocalTrackEx for LocalTrack {
@@ -137,7 +152,23 @@ impl LocalTrackEx for LocalTrack {
       self.file_path = Some(filesystem_sync_track.file_path.clone());
       changed = true;
     }
+    if self.file_path_key.as_ref() != Some(&filesystem_sync_track.file_path_key) {
+      self.file_path_key = Some(filesystem_sync_track.file_path_key.clone());
+      changed = true;
+    }
     update!(self.hash, filesystem_sync_track.hash as i64, changed);
+    update!(self.bitrate, filesystem_sync_track.bitrate, changed);
+    if self.update_replay_gain_from(filesystem_sync_track) { changed = true; }
+    changed
+  }
+
+  fn update_replay_gain_from(&mut self, filesystem_sync_track: &FilesystemSyncTrack) -> bool {
+    let mut changed = false;
+    let replay_gain = &filesystem_sync_track.replay_gain;
+    update!(self.track_gain, replay_gain.track_gain, changed);
+    update!(self.track_peak, replay_gain.track_peak, changed);
+    update!(self.album_gain, replay_gain.album_gain, changed);
+    update!(self.album_peak, replay_gain.album_peak, changed);
     changed
   }
 }
@@ -170,6 +201,7 @@ pub(crate) struct InternalUser {
   pub name: String,
   pub hash: Vec<u8>,
   pub salt: Vec<u8>,
+  pub hash_params: String,
 }
 
 impl Into<User> for InternalUser {
@@ -187,4 +219,5 @@ pub struct InternalNewUser {
   pub name: String,
   pub hash: Vec<u8>,
   pub salt: Vec<u8>,
+  pub hash_params: String,
 }
@@ -0,0 +1,30 @@
+use crate::audio_features::AudioFeatures;
+
+/// Mood tags that are assigned to tracks based on their audio features.
+pub const MOOD_TAG_CHILL: &str = "chill";
+pub const MOOD_TAG_ENERGETIC: &str = "energetic";
+pub const MOOD_TAG_DARK: &str = "dark";
+
+const ENERGETIC_MIN_BPM: f32 = 118.0;
+const ENERGETIC_MIN_ENERGY: f32 = 0.2;
+const CHILL_MAX_BPM: f32 = 100.0;
+const CHILL_MAX_ENERGY: f32 = 0.12;
+const DARK_MAX_BRIGHTNESS: f32 = 0.06;
+
+/// Classifies the mood of a track with a heuristic over its audio `features`, returning the mood tags that apply.
+/// Energetic tracks are fast and loud, chill tracks are slow and quiet, and dark tracks are in a minor key and have
+/// little high-frequency content.
+pub fn classify_mood(features: &AudioFeatures) -> Vec<&'static str> {
+  let mut tags = Vec::new();
+  match (features.bpm, features.energy) {
+    (Some(bpm), Some(energy)) if bpm >= ENERGETIC_MIN_BPM && energy >= ENERGETIC_MIN_ENERGY => tags.push(MOOD_TAG_ENERGETIC),
+    (Some(bpm), Some(energy)) if bpm <= CHILL_MAX_BPM && energy <= CHILL_MAX_ENERGY => tags.push(MOOD_TAG_CHILL),
+    _ => {}
+  }
+  if let (Some(true), Some(brightness)) = (features.key_minor, features.brightness) {
+    if brightness <= DARK_MAX_BRIGHTNESS {
+      tags.push(MOOD_TAG_DARK);
+    }
+  }
+  tags
+}
@@ -0,0 +1,167 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_core::api::{Event, NewParty, NewPartyGuest, Party, PartyGuest, PartyQueueItem};
+
+use crate::event::EventBus;
+
+/// In-memory registry of parties and their guests. Changes to party queues are published on the event bus, so that the
+/// host can see requests of guests.
+#[derive(Clone)]
+pub struct Parties {
+  inner: Arc<Mutex<Inner>>,
+  event_bus: EventBus,
+}
+
+struct Inner {
+  parties: HashMap<i32, Party>,
+  guests: HashMap<String, Guest>,
+  next_id: i32,
+}
+
+struct Guest {
+  guest: PartyGuest,
+  /// Times of the requests of this guest in the last minute, for rate limiting.
+  request_times: VecDeque<Instant>,
+}
+
+#[derive(Debug, Error)]
+pub enum PartyError {
+  #[error("User with ID '{0}' is not the host of party with ID '{1}'")]
+  NotHost(i32, i32),
+  #[error("Guest token is invalid or has expired")]
+  InvalidGuestToken,
+  #[error("Guest '{0}' made too many requests; try again later")]
+  RateLimited(String),
+}
+
+impl Parties {
+  pub fn new(event_bus: EventBus) -> Self {
+    let inner = Arc::new(Mutex::new(Inner { parties: HashMap::new(), guests: HashMap::new(), next_id: 1 }));
+    Self { inner, event_bus }
+  }
+
+  pub fn get(&self, id: i32) -> Option<Party> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    self.inner.lock().unwrap().parties.get(&id).cloned()
+  }
+
+  pub fn create(&self, host_user_id: i32, new_party: NewParty) -> Party {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let mut inner = self.inner.lock().unwrap();
+    let id = inner.next_id;
+    inner.next_id += 1;
+    let party = Party { id, host_user_id, max_guest_requests_per_minute: new_party.max_guest_requests_per_minute, queue: Vec::new() };
+    event!(Level::DEBUG, ?party, "Created party");
+    inner.parties.insert(id, party.clone());
+    party
+  }
+
+  /// Ends the party with `id`, invalidating the tokens of all its guests. Returns `false` if the party does not exist.
+  pub fn end(&self, id: i32, user_id: i32) -> Result<bool, PartyError> {
+    {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      match inner.parties.get(&id).map(|party| party.host_user_id) {
+        None => return Ok(false),
+        Some(host_user_id) if host_user_id != user_id => return Err(PartyError::NotHost(user_id, id)),
+        Some(_) => {}
+      }
+      inner.parties.remove(&id);
+      inner.guests.retain(|_, guest| guest.guest.party_id != id);
+    }
+    self.event_bus.publish(Event::PartyEnded(id));
+    Ok(true)
+  }
+
+  /// Invites a guest to the party with `id`, returning the guest with its secret token.
+  pub fn invite_guest(&self, id: i32, user_id: i32, new_guest: NewPartyGuest) -> Result<Option<PartyGuest>, PartyError> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let mut inner = self.inner.lock().unwrap();
+    match inner.parties.get(&id).map(|party| party.host_user_id) {
+      None => return Ok(None),
+      Some(host_user_id) if host_user_id != user_id => return Err(PartyError::NotHost(user_id, id)),
+      Some(_) => {}
+    }
+    let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+    let expires_at = Utc::now().naive_utc() + chrono::Duration::seconds(new_guest.valid_for_secs as i64);
+    let guest = PartyGuest { token: token.clone(), party_id: id, name: new_guest.name, expires_at };
+    inner.guests.insert(token, Guest { guest: guest.clone(), request_times: VecDeque::new() });
+    Ok(Some(guest))
+  }
+
+  /// Removes and returns the next item of the queue of the party with `id`.
+  pub fn dequeue(&self, id: i32, user_id: i32) -> Result<Option<PartyQueueItem>, PartyError> {
+    let (item, party) = {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      let party = match inner.parties.get_mut(&id) {
+        None => return Ok(None),
+        Some(party) if party.host_user_id != user_id => return Err(PartyError::NotHost(user_id, id)),
+        Some(party) => party,
+      };
+      if party.queue.is_empty() { return Ok(None); }
+      (party.queue.remove(0), party.clone())
+    };
+    self.event_bus.publish(Event::PartyChanged(party));
+    Ok(Some(item))
+  }
+}
+
+// Guest requests
+
+impl Parties {
+  /// Checks that `token` belongs to a guest whose token has not expired and that has not exceeded the rate limit of
+  /// its party, and records the request. Returns the guest.
+  pub fn authorize_guest_request(&self, token: &str) -> Result<PartyGuest, PartyError> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let mut inner = self.inner.lock().unwrap();
+    let max_requests_per_minute = {
+      let guest = inner.guests.get(token).ok_or(PartyError::InvalidGuestToken)?;
+      if guest.guest.expires_at < Utc::now().naive_utc() {
+        inner.guests.remove(token);
+        return Err(PartyError::InvalidGuestToken);
+      }
+      let party = inner.parties.get(&guest.guest.party_id).ok_or(PartyError::InvalidGuestToken)?;
+      party.max_guest_requests_per_minute
+    };
+    // UNWRAP: guest exists as checked above.
+    let guest = inner.guests.get_mut(token).unwrap();
+    let now = Instant::now();
+    while let Some(time) = guest.request_times.front() {
+      if now.duration_since(*time) > Duration::from_secs(60) {
+        guest.request_times.pop_front();
+      } else {
+        break;
+      }
+    }
+    if let Some(max_requests_per_minute) = max_requests_per_minute {
+      if guest.request_times.len() >= max_requests_per_minute as usize {
+        return Err(PartyError::RateLimited(guest.guest.name.clone()));
+      }
+    }
+    guest.request_times.push_back(now);
+    Ok(guest.guest.clone())
+  }
+
+  /// Adds the track with `track_id` to the end of the queue of the party of `guest`.
+  pub fn enqueue(&self, guest: &PartyGuest, track_id: i32) -> Result<Party, PartyError> {
+    let party = {
+      // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+      let mut inner = self.inner.lock().unwrap();
+      let party = inner.parties.get_mut(&guest.party_id).ok_or(PartyError::InvalidGuestToken)?;
+      party.queue.push(PartyQueueItem { track_id, guest_name: guest.name.clone() });
+      party.clone()
+    };
+    event!(Level::DEBUG, ?guest, track_id, "Guest enqueued track");
+    self.event_bus.publish(Event::PartyChanged(party.clone()));
+    Ok(party)
+  }
+}
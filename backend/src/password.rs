@@ -1,4 +1,7 @@
-use argon2::Config;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use argon2::{Config, Variant, Version};
 use rand::RngCore;
 use thiserror::Error;
 
@@ -11,6 +14,59 @@ pub struct PasswordHasher {
 pub enum HashError {
   #[error(transparent)]
   HashFail(#[from] argon2::Error),
+  #[error("Invalid password hash parameters '{0}'")]
+  InvalidParamsFail(String),
+}
+
+/// Parameters a password hash was created with, stored alongside the hash so that hashes created with older parameters
+/// can still be verified, and can be recognized to re-hash them with the current parameters. Formatted like the
+/// parameters of a PHC string, such as `argon2id$v=19$m=19456,t=2,p=1`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PasswordHashParams {
+  pub variant: Variant,
+  pub version: Version,
+  /// Memory cost in KiB.
+  pub mem_cost: u32,
+  pub time_cost: u32,
+  pub lanes: u32,
+}
+
+impl PasswordHashParams {
+  /// Parameters of hashes created before parameters were stored: the rust-argon2 defaults.
+  pub const LEGACY: Self = Self { variant: Variant::Argon2i, version: Version::Version13, mem_cost: 4096, time_cost: 3, lanes: 1 };
+  /// Parameters of newly created hashes: Argon2id with the minimum memory and time costs recommended by OWASP.
+  pub const CURRENT: Self = Self { variant: Variant::Argon2id, version: Version::Version13, mem_cost: 19456, time_cost: 2, lanes: 1 };
+}
+
+impl Display for PasswordHashParams {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}$v={}$m={},t={},p={}", self.variant.as_lowercase_str(), self.version.as_u32(), self.mem_cost, self.time_cost, self.lanes)
+  }
+}
+
+impl FromStr for PasswordHashParams {
+  type Err = HashError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || HashError::InvalidParamsFail(s.to_string());
+    let mut parts = s.split('$');
+    let variant = Variant::from_str(parts.next().ok_or_else(invalid)?).map_err(|_| invalid())?;
+    let version = parts.next().and_then(|v| v.strip_prefix("v=")).and_then(|v| v.parse().ok()).ok_or_else(invalid)?;
+    let version = Version::from_u32(version).map_err(|_| invalid())?;
+    let (mut mem_cost, mut time_cost, mut lanes) = (None, None, None);
+    for param in parts.next().ok_or_else(invalid)?.split(',') {
+      let (key, value) = param.split_once('=').ok_or_else(invalid)?;
+      let value: u32 = value.parse().map_err(|_| invalid())?;
+      match key {
+        "m" => mem_cost = Some(value),
+        "t" => time_cost = Some(value),
+        "p" => lanes = Some(value),
+        _ => return Err(invalid()),
+      }
+    }
+    if parts.next().is_some() { return Err(invalid()); }
+    Ok(Self { variant, version, mem_cost: mem_cost.ok_or_else(invalid)?, time_cost: time_cost.ok_or_else(invalid)?, lanes: lanes.ok_or_else(invalid)? })
+  }
 }
 
 impl PasswordHasher {
@@ -19,12 +75,21 @@ impl PasswordHasher {
     Self { secret_key }
   }
 
-  pub fn hash<P: AsRef<[u8]>, S: AsRef<[u8]>>(&self, password: P, salt: S) -> Result<Vec<u8>, HashError> {
-    Ok(argon2::hash_raw(password.as_ref(), salt.as_ref(), &self.config())?)
+  /// Hashes `password` with `salt` using the current parameters, returning the hash and the parameters to store
+  /// alongside it.
+  pub fn hash<P: AsRef<[u8]>, S: AsRef<[u8]>>(&self, password: P, salt: S) -> Result<(Vec<u8>, PasswordHashParams), HashError> {
+    let params = PasswordHashParams::CURRENT;
+    Ok((argon2::hash_raw(password.as_ref(), salt.as_ref(), &self.config(&params))?, params))
+  }
+
+  /// Verifies `password` against `hash`, which was created with `salt` and `params`.
+  pub fn verify<P: AsRef<[u8]>, S: AsRef<[u8]>, H: AsRef<[u8]>>(&self, password: P, salt: S, hash: H, params: &PasswordHashParams) -> Result<bool, HashError> {
+    Ok(argon2::verify_raw(password.as_ref(), salt.as_ref(), hash.as_ref(), &self.config(params))?)
   }
 
-  pub fn verify<P: AsRef<[u8]>, S: AsRef<[u8]>, H: AsRef<[u8]>>(&self, password: P, salt: S, hash: H) -> Result<bool, HashError> {
-    Ok(argon2::verify_raw(password.as_ref(), salt.as_ref(), hash.as_ref(), &self.config())?)
+  /// Returns whether a hash created with `params` should be re-hashed with the current parameters.
+  pub fn needs_rehash(&self, params: &PasswordHashParams) -> bool {
+    *params != PasswordHashParams::CURRENT
   }
 
   const SALT_SIZE: usize = 32;
@@ -36,10 +101,15 @@ impl PasswordHasher {
     salt
   }
 
-  fn config(&self) -> Config {
+  fn config<'a>(&'a self, params: &PasswordHashParams) -> Config<'a> {
     // OPTO: prevent recreating the config all the time, without adding a lifetime to PasswordHasher, as Config takes
     // the secret key by reference, and Rust does not allow self-referential structs.
     Config {
+      variant: params.variant,
+      version: params.version,
+      mem_cost: params.mem_cost,
+      time_cost: params.time_cost,
+      lanes: params.lanes,
       secret: &self.secret_key,
       ..Config::default()
     }
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::{self, task, time};
+use tracing::{event, instrument, Level};
+
+use musium_core::api::Event;
+use musium_core::format_error::FormatError;
+
+use crate::database::Database;
+use crate::event::EventBus;
+use crate::worker::WorkerState;
+
+/// Periodically checks for new releases of followed artists, publishing an event for each discovered release.
+/// Paused while the database is quiesced.
+pub struct ReleaseCheckTask {
+  handle: task::JoinHandle<()>,
+  state: WorkerState,
+}
+
+impl ReleaseCheckTask {
+  pub fn spawn(database: Arc<Database>, event_bus: EventBus, interval: Duration) -> Self {
+    let state = WorkerState::default();
+    let task_state = state.clone();
+    let handle = tokio::spawn(async move {
+      let _stopped = task_state.stopped_on_drop();
+      let mut interval = time::interval(interval);
+      loop {
+        interval.tick().await;
+        if database.is_quiesced() { continue; }
+        let _busy = task_state.busy();
+        Self::check(database.clone(), &event_bus).await;
+      }
+    });
+    Self { handle, state }
+  }
+
+  pub fn state(&self) -> WorkerState { self.state.clone() }
+
+  #[instrument(skip(database, event_bus))]
+  async fn check(database: Arc<Database>, event_bus: &EventBus) {
+    let result = task::spawn_blocking(move || {
+      let connection = database.connect().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))?;
+      connection.check_new_releases().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+    }).await;
+    match result {
+      Ok(Ok(check)) => {
+        event!(Level::DEBUG, "Discovered {} new release(s)", check.new_releases.len());
+        for new_release in check.new_releases {
+          event_bus.publish(Event::NewRelease(new_release));
+        }
+        for notification in check.notifications {
+          event_bus.publish(Event::Notification(notification));
+        }
+      }
+      Ok(Err(())) => {} // Error was already logged.
+      Err(e) => event!(Level::ERROR, "Checking for new releases panicked: {:?}", e),
+    }
+  }
+}
+
+impl Drop for ReleaseCheckTask {
+  fn drop(&mut self) {
+    self.handle.abort();
+  }
+}
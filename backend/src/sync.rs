@@ -1,15 +1,29 @@
 use std::error::Error as StdError;
 use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicI32, Ordering};
 
+use chrono::{NaiveDateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use thiserror::Error;
-use tokio::{self, sync::{mpsc, oneshot, watch}, task};
+use tokio::{self, sync::{mpsc, oneshot}, task};
 use tracing::{event, instrument, Level};
 
-use musium_core::api::SyncStatus;
+use musium_core::api::{Event, SyncStatus, SyncSummary};
 use musium_core::format_error::FormatError;
+use musium_core::model::NotificationKind;
 use musium_core::panic::try_panic_into_string;
 
+use crate::artist_info::ArtistEnrichmentSettings;
 use crate::database::{Database, DatabaseConnection};
+use crate::database::managed_library::OrganizeScope;
+use crate::database::sync::{error_chain_message, SyncProgress};
+use crate::database::sync_lock::{SyncLockError, SyncLockHeartbeat};
+use crate::database::sync_run::{SyncRunCounts, SyncScope};
+use crate::event::EventBus;
+use crate::managed_library::ManagedLibrarySettings;
+use crate::tagger_hook::TaggerHookSettings;
+use crate::worker::WorkerState;
 
 // Creation
 
@@ -17,16 +31,31 @@ use crate::database::{Database, DatabaseConnection};
 pub struct SyncClient {
   tx: mpsc::Sender<Request>,
   worker_task: Arc<task::JoinHandle<()>>,
+  worker_state: WorkerState,
 }
 
 impl SyncClient {
-  pub fn new() -> Self {
+  /// Creates a sync client. When `artist_enrichment` is given, images and biographies of artists are fetched after
+  /// each successful sync. When `managed_library` is given, the files of the managed library are organized after each
+  /// successful sync. When `tagger_hook` is given, it is run for each album that a successful sync added, before the
+  /// managed library is organized.
+  pub fn new(event_bus: EventBus, artist_enrichment: Option<ArtistEnrichmentSettings>, managed_library: Option<ManagedLibrarySettings>, tagger_hook: Option<TaggerHookSettings>) -> Self {
     let (tx, rx) = mpsc::channel(32);
+    // Identifies this server instance as the holder of the sync lock, which prevents multiple server instances that use
+    // the same database from syncing concurrently.
+    let random: String = rand::thread_rng().sample_iter(&Alphanumeric).take(8).map(char::from).collect();
+    let instance_id = format!("{}-{}", std::process::id(), random);
+    let worker_state = WorkerState::default();
+    let task_worker_state = worker_state.clone();
     let worker_task = Arc::new(tokio::spawn(async move {
-      WorkerTask::new(rx).run().await;
+      let _stopped = task_worker_state.stopped_on_drop();
+      WorkerTask::new(rx, event_bus, instance_id, task_worker_state.clone(), artist_enrichment, managed_library, tagger_hook).run().await;
     }));
-    Self { tx, worker_task }
+    Self { tx, worker_task, worker_state }
   }
+
+  /// Gets the state of the worker task, which is busy while a sync is running.
+  pub fn worker_state(&self) -> WorkerState { self.worker_state.clone() }
 }
 
 // Destruction
@@ -49,7 +78,7 @@ impl SyncClient {
   /// worker task (if any), and does not wait for the worker task to complete first.
   pub async fn destroy(self) -> Result<(), SyncClientDestroyError> {
     use SyncClientDestroyError::*;
-    let SyncClient { tx, worker_task } = self;
+    let SyncClient { tx, worker_task, .. } = self;
     drop(tx); // Dropping sender will cause the worker task to break out of the loop and stop.
     let worker_task = Arc::try_unwrap(worker_task).map_err(|_| ClonesStillExist)?;
     worker_task.abort(); // Also abort the worker task just in case.
@@ -147,18 +176,18 @@ impl Request {
 
 struct WorkerTask {
   rx: mpsc::Receiver<Request>,
-  sync_task: Arc<RwLock<Option<SyncTask>>>,
-}
-
-struct SyncTask {
-  // TODO: do we need to keep on to this handle?
-  _handle: task::JoinHandle<()>,
-  rx: watch::Receiver<SyncStatus>,
+  status: Arc<RwLock<SyncStatus>>,
+  event_bus: EventBus,
+  instance_id: String,
+  worker_state: WorkerState,
+  artist_enrichment: Option<ArtistEnrichmentSettings>,
+  managed_library: Option<ManagedLibrarySettings>,
+  tagger_hook: Option<TaggerHookSettings>,
 }
 
 impl WorkerTask {
-  fn new(rx: mpsc::Receiver<Request>) -> Self {
-    WorkerTask { rx, sync_task: Arc::new(RwLock::new(None)) }
+  fn new(rx: mpsc::Receiver<Request>, event_bus: EventBus, instance_id: String, worker_state: WorkerState, artist_enrichment: Option<ArtistEnrichmentSettings>, managed_library: Option<ManagedLibrarySettings>, tagger_hook: Option<TaggerHookSettings>) -> Self {
+    WorkerTask { rx, status: Arc::new(RwLock::new(SyncStatus::Idle)), event_bus, instance_id, worker_state, artist_enrichment, managed_library, tagger_hook }
   }
 
   #[instrument(skip(self))]
@@ -166,78 +195,239 @@ impl WorkerTask {
     while let Some(request) = self.rx.recv().await { // Loop until all senders disconnect.
       let tx = request.tx;
       let db = request.database;
-      match request.command {
-        Command::GetStatus => {
-          // OK: receiver hung up -> we don't care.
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or(SyncStatus::Idle)).ok();
-        }
-        Command::SyncAll => {
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or_else(
-            || Self::do_sync(self.sync_task.clone(), db, move |c| c.sync_all_sources())
-          )).ok(); // OK: receiver hung up -> we don't care.
-        }
-        Command::SyncLocalSources => {
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or_else(
-            || Self::do_sync(self.sync_task.clone(), db, move |c| c.sync_local_sources())
-          )).ok(); // OK: receiver hung up -> we don't care.
-        }
-        Command::SyncLocalSource(local_source_id) => {
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or_else(
-            || Self::do_sync(self.sync_task.clone(), db, move |c| c.sync_local_source(local_source_id))
-          )).ok(); // OK: receiver hung up -> we don't care.
-        }
-        Command::SyncSpotifySources => {
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or_else(
-            || Self::do_sync(self.sync_task.clone(), db, move |c| c.sync_spotify_sources())
-          )).ok(); // OK: receiver hung up -> we don't care.
-        }
-        Command::SyncSpotifySource(spotify_source_id) => {
-          tx.send(Self::get_sync_status(&self.sync_task).unwrap_or_else(
-            || Self::do_sync(self.sync_task.clone(), db, move |c| c.sync_spotify_source(spotify_source_id))
-          )).ok(); // OK: receiver hung up -> we don't care.
-        }
+      let status = match request.command {
+        Command::GetStatus => Self::get_sync_status(&self.status),
+        Command::SyncAll => self.start_sync(db, SyncScope::All, move |c, p| c.sync_all_sources(p)),
+        Command::SyncLocalSources => self.start_sync(db, SyncScope::LocalSources, move |c, p| c.sync_local_sources(p)),
+        Command::SyncLocalSource(local_source_id) => self.start_sync(db, SyncScope::LocalSource(local_source_id), move |c, p| c.sync_local_source(local_source_id, p)),
+        Command::SyncSpotifySources => self.start_sync(db, SyncScope::SpotifySources, move |c, p| c.sync_spotify_sources(p).map(|_| Vec::new())),
+        Command::SyncSpotifySource(spotify_source_id) => self.start_sync(db, SyncScope::SpotifySource(spotify_source_id), move |c, p| c.sync_spotify_source(spotify_source_id, p).map(|_| Vec::new())),
       };
+      tx.send(status).ok(); // OK: receiver hung up -> we don't care.
     };
   }
 
-  #[instrument(skip(sync_task))]
-  fn get_sync_status(sync_task: &Arc<RwLock<Option<SyncTask>>>) -> Option<SyncStatus> {
+  #[instrument(skip(status))]
+  fn get_sync_status(status: &Arc<RwLock<SyncStatus>>) -> SyncStatus {
+    // UNWRAP: errors if writer has panicked -> we panic as well.
+    status.read().unwrap().clone()
+  }
+
+  fn set_sync_status(status: &Arc<RwLock<SyncStatus>>, event_bus: &EventBus, new_status: SyncStatus) {
     // UNWRAP: errors if writer has panicked -> we panic as well.
-    sync_task.clone().read().unwrap().as_ref().map(|st| *st.rx.borrow())
+    *status.write().unwrap() = new_status.clone();
+    event_bus.publish(Event::SyncStatusChanged(new_status));
+  }
+
+  /// Starts a sync with `sync` unless a sync is already running, returning the status of the running sync in that case.
+  /// Commands are handled one at a time, so no other sync can start between checking and starting.
+  fn start_sync<E: StdError>(
+    &self,
+    db: Arc<Database>,
+    scope: SyncScope,
+    sync: impl 'static + Send + FnOnce(DatabaseConnection, &SyncProgress) -> Result<Vec<i32>, E>,
+  ) -> SyncStatus {
+    let status = Self::get_sync_status(&self.status);
+    if status.is_running() {
+      return status;
+    }
+    Self::do_sync(self.status.clone(), db, self.event_bus.clone(), self.instance_id.clone(), &self.worker_state, self.artist_enrichment.clone(), self.managed_library.clone(), self.tagger_hook.clone(), scope, sync)
   }
 
-  #[instrument(skip(sync_task, db, sync))]
+  #[instrument(skip(status, db, event_bus, worker_state, artist_enrichment, managed_library, tagger_hook, sync))]
   fn do_sync<E: StdError>(
-    sync_task: Arc<RwLock<Option<SyncTask>>>,
+    status: Arc<RwLock<SyncStatus>>,
     db: Arc<Database>,
-    sync: impl 'static + Send + FnOnce(DatabaseConnection) -> Result<(), E>,
+    event_bus: EventBus,
+    instance_id: String,
+    worker_state: &WorkerState,
+    artist_enrichment: Option<ArtistEnrichmentSettings>,
+    managed_library: Option<ManagedLibrarySettings>,
+    tagger_hook: Option<TaggerHookSettings>,
+    scope: SyncScope,
+    sync: impl 'static + Send + FnOnce(DatabaseConnection, &SyncProgress) -> Result<Vec<i32>, E>,
   ) -> SyncStatus {
-    let sync_status = SyncStatus::Started(None);
-    let (progress_tx, rx) = watch::channel(sync_status);
-    let set_sync_task = sync_task.clone();
-    // Lock before spawning to ensure we have the write lock, so we can set the sync task to Some value before the task
-    // sets it to None again when it is finished.
-    // UNWRAP: errors if writer has panicked -> we panic as well.
-    let mut set_sync_task_lock = set_sync_task.write().unwrap();
-    let handle = task::spawn_blocking(move || {
-      progress_tx.send(SyncStatus::Busy(None)).ok(); // OK: receiver hung up -> we don't care.
-      match db.connect() {
-        Ok(c) => match sync(c) {
-          Ok(_) => { progress_tx.send(SyncStatus::Completed).ok(); } // OK: receiver hung up -> we don't care.
-          Err(e) => {
-            event!(Level::ERROR, "{:?}", FormatError::new(&e));
-            progress_tx.send(SyncStatus::Failed).ok(); // OK: receiver hung up -> we don't care.
-          }
+    let started_at = Utc::now().naive_utc();
+    let new_status = match Self::acquire_sync_lock(&db, &instance_id) {
+      Ok(_) => SyncStatus::Running { started_at, progress: None },
+      Err(error) => SyncStatus::Failed { started_at, ended_at: Utc::now().naive_utc(), errors: vec![error] },
+    };
+    Self::set_sync_status(&status, &event_bus, new_status.clone());
+    if !new_status.is_running() {
+      return new_status;
+    }
+    let heartbeat = SyncLockHeartbeat::spawn(db.as_ref().clone(), instance_id.clone());
+    let progress = Self::create_sync_progress(status.clone(), event_bus.clone(), started_at);
+    let busy = worker_state.busy();
+    task::spawn_blocking(move || {
+      let result = match db.connect() {
+        Ok(c) => Self::sync_and_record(&db, c, scope, &progress, sync).map_err(|e| Self::handle_sync_failure(&db, &event_bus, &e)),
+        Err(e) => Err(Self::handle_sync_failure(&db, &event_bus, &e)),
+      };
+      if let (Ok(summary), Some(tagger_hook)) = (&result, &tagger_hook) {
+        Self::run_tagger_hook(&db, tagger_hook, &summary.added_album_ids);
+      }
+      if result.is_ok() {
+        if let Some(managed_library) = &managed_library {
+          Self::organize_managed_library(&db, managed_library);
         }
-        Err(e) => {
-          event!(Level::ERROR, "{:?}", FormatError::new(&e));
-          progress_tx.send(SyncStatus::Failed).ok(); // OK: receiver hung up -> we don't care.
+        if let Some(artist_enrichment) = &artist_enrichment {
+          Self::enrich_artists(&db, artist_enrichment);
         }
+      }
+      drop(heartbeat);
+      Self::release_sync_lock(&db, &instance_id);
+      let ended_at = Utc::now().naive_utc();
+      let end_status = match result {
+        Ok(summary) => SyncStatus::Completed { started_at, ended_at, summary },
+        Err(error) => SyncStatus::Failed { started_at, ended_at, errors: vec![error] },
       };
-      // UNWRAP: errors if writer has panicked -> we panic as well.
-      *sync_task.write().unwrap() = None;
+      Self::set_sync_status(&status, &event_bus, end_status);
+      drop(busy);
     });
-    *set_sync_task_lock = Some(SyncTask { _handle: handle, rx });
-    sync_status
+    new_status
+  }
+
+  /// Creates a sync progress that sets the progress of the running sync that started at `started_at` in `status`. Only
+  /// changes of at least a tenth of a percent are set, so that a status change is not published for every synchronized
+  /// track.
+  fn create_sync_progress(status: Arc<RwLock<SyncStatus>>, event_bus: EventBus, started_at: NaiveDateTime) -> SyncProgress {
+    let reported_permille = AtomicI32::new(0);
+    SyncProgress::new(move |progress| {
+      let permille = (progress * 1000.0) as i32;
+      if reported_permille.swap(permille, Ordering::Relaxed) != permille {
+        Self::set_sync_status(&status, &event_bus, SyncStatus::Running { started_at, progress: Some(progress) });
+      }
+    })
+  }
+
+  /// Runs `sync` on `c` and records its runs in the sync history, returning a summary of the sync with the numbers of
+  /// tracks that the recorded runs added, updated, and removed. Added tracks are linked to other versions of the same
+  /// track. Failures to record, to find added albums, or to link versions are logged but do not fail the sync, as these
+  /// are only used for diagnosis, webhooks, and browsing.
+  fn sync_and_record<E: StdError>(
+    db: &Database,
+    c: DatabaseConnection,
+    scope: SyncScope,
+    progress: &SyncProgress,
+    sync: impl FnOnce(DatabaseConnection, &SyncProgress) -> Result<Vec<i32>, E>,
+  ) -> Result<SyncSummary, E> {
+    let snapshot = c.snapshot_sync_sources(scope).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).ok();
+    let max_album_id = c.get_max_album_id().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).ok();
+    let max_track_id = c.get_max_track_id().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).ok();
+    let result = sync(c, progress);
+    let connection = db.connect().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).ok();
+    let counts = match (snapshot, &connection) {
+      (Some(snapshot), Some(c)) => {
+        let run_result = result.as_ref().map(|skipped_local_source_ids| skipped_local_source_ids.as_slice()).map_err(|e| error_chain_message(e));
+        c.record_sync_runs(snapshot, run_result).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).unwrap_or_default()
+      }
+      _ => SyncRunCounts::default(),
+    };
+    let skipped_local_source_ids = result?;
+    let added_album_ids = match (max_album_id, &connection) {
+      (Some(max_album_id), Some(c)) => c.list_album_ids_after(max_album_id).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).unwrap_or_default(),
+      _ => Vec::new(),
+    };
+    if let (Some(max_track_id), Some(c)) = (max_track_id, &connection) {
+      c.link_track_versions(max_track_id).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))).ok(); // OK: errors are logged.
+    }
+    Ok(SyncSummary {
+      skipped_local_source_ids,
+      added_album_ids,
+      added_track_count: counts.added,
+      updated_track_count: counts.updated,
+      removed_track_count: counts.removed,
+    })
+  }
+
+  /// Logs `error` and notifies users of the failed sync. Returns the message of `error` to put in the sync status.
+  fn handle_sync_failure<E: StdError>(db: &Database, event_bus: &EventBus, error: &E) -> String {
+    event!(Level::ERROR, "{:?}", FormatError::new(error));
+    Self::notify_sync_failure(db, event_bus, error);
+    error_chain_message(error)
+  }
+
+  /// Acquires the sync lock for this server instance. Returns the message of the error to put in the sync status if it
+  /// was not acquired.
+  fn acquire_sync_lock(db: &Database, instance_id: &str) -> Result<(), String> {
+    let result = match db.connect() {
+      Ok(c) => c.acquire_sync_lock(instance_id),
+      Err(e) => {
+        event!(Level::ERROR, "{:?}", FormatError::new(&e));
+        return Err(error_chain_message(&e));
+      }
+    };
+    match result {
+      Ok(_) => Ok(()),
+      Err(SyncLockError::HeldByOtherInstance(holder, expires_at)) => {
+        event!(Level::WARN, %holder, %expires_at, "Not syncing because another server instance holds the sync lock");
+        Err(format!("Not started, sync lock is held by another server instance '{}' until {}", holder, expires_at))
+      }
+      Err(e) => {
+        event!(Level::ERROR, "{:?}", FormatError::new(&e));
+        Err(error_chain_message(&e))
+      }
+    }
+  }
+
+  fn release_sync_lock(db: &Database, instance_id: &str) {
+    let result = db.connect()
+      .map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+      .and_then(|c| c.release_sync_lock(instance_id).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))));
+    if let Ok(false) = result {
+      event!(Level::WARN, "Sync lock was released by another server instance while syncing");
+    }
+  }
+
+  /// Runs the tagger hook for each album in `album_ids`, and reads the tags of their files again. Failures are logged but
+  /// do not fail the sync, so that a failing hook for one album does not prevent running it for other albums.
+  fn run_tagger_hook(db: &Database, tagger_hook: &TaggerHookSettings, album_ids: &[i32]) {
+    let c = match db.connect() {
+      Ok(c) => c,
+      Err(e) => {
+        event!(Level::ERROR, "{:?}", FormatError::new(&e));
+        return;
+      }
+    };
+    for album_id in album_ids {
+      match c.run_tagger_hook(tagger_hook, *album_id) {
+        Ok(count) => event!(Level::DEBUG, album_id, count, "Ran tagger hook for added album"),
+        Err(e) => event!(Level::ERROR, "{:?}", FormatError::new(&e)),
+      }
+    }
+  }
+
+  /// Organizes the files of the managed library, such that files that were added to its directory, or of which the
+  /// metadata changed, are moved to the path given by the template. Failures are logged but do not fail the sync.
+  fn organize_managed_library(db: &Database, managed_library: &ManagedLibrarySettings) {
+    let result = db.connect()
+      .map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+      .and_then(|c| c.organize_managed_library(managed_library, OrganizeScope::All).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))));
+    if let Ok(count) = result {
+      event!(Level::DEBUG, count, "Organized managed library");
+    }
+  }
+
+  /// Fetches artist images and biographies. Failures are logged but do not fail the sync, as enrichment is optional.
+  fn enrich_artists(db: &Database, artist_enrichment: &ArtistEnrichmentSettings) {
+    let result = db.connect()
+      .map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+      .and_then(|c| c.enrich_artists(artist_enrichment).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))));
+    if let Ok(count) = result {
+      event!(Level::DEBUG, count, "Enriched artists");
+    }
+  }
+
+  fn notify_sync_failure(db: &Database, event_bus: &EventBus, error: &dyn StdError) {
+    let message = format!("Synchronization failed: {}", error);
+    let notifications = db.connect()
+      .map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))
+      .and_then(|c| c.notify_all_users(NotificationKind::SyncFailure, message).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e))));
+    if let Ok(notifications) = notifications {
+      for notification in notifications {
+        event_bus.publish(Event::Notification(notification));
+      }
+    }
   }
 }
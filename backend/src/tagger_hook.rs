@@ -0,0 +1,44 @@
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+use std::process::Command;
+
+use thiserror::Error;
+use tracing::{event, Level};
+
+/// Settings of the tagger hook: an external command, such as `beet import`, that is run for each album that a sync
+/// added. The command is run with `args`, followed by the directories that contain the files of the album, and with the
+/// `MUSIUM_ALBUM_ID` and `MUSIUM_ALBUM_NAME` environment variables set. The tags of the files are read again after the
+/// command finishes, so that changes made by the command are picked up.
+#[derive(Clone, Default, Debug)]
+pub struct TaggerHookSettings {
+  pub program: PathBuf,
+  pub args: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum RunTaggerHookError {
+  #[error("Failed to run tagger hook '{0}'")]
+  SpawnFail(PathBuf, #[source] std::io::Error, Backtrace),
+  #[error("Tagger hook '{0}' failed with {1}")]
+  ExitFail(PathBuf, std::process::ExitStatus),
+}
+
+impl TaggerHookSettings {
+  /// Runs the command for the album with `album_id` and `album_name`, of which the files are in `directories`, and waits
+  /// for it to finish.
+  pub fn run(&self, album_id: i32, album_name: &str, directories: &[PathBuf]) -> Result<(), RunTaggerHookError> {
+    use RunTaggerHookError::*;
+    let status = Command::new(&self.program)
+      .args(&self.args)
+      .args(directories)
+      .env("MUSIUM_ALBUM_ID", album_id.to_string())
+      .env("MUSIUM_ALBUM_NAME", album_name)
+      .status()
+      .map_err(|e| SpawnFail(self.program.clone(), e, Backtrace::capture()))?;
+    if !status.success() {
+      return Err(ExitFail(self.program.clone(), status));
+    }
+    event!(Level::DEBUG, album_id, album_name, "Ran tagger hook");
+    Ok(())
+  }
+}
@@ -0,0 +1,35 @@
+use std::backtrace::Backtrace;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use rodio::{Decoder, Source};
+use thiserror::Error;
+
+use musium_core::api::{AudioCodec, AudioFileInfo, TagFrame};
+use musium_filesystem_sync::{read_tag_frames, ReadTagFramesError};
+
+#[derive(Debug, Error)]
+pub enum TrackInspectError {
+  #[error("Failed to read metadata of audio file")]
+  MetadataFail(#[from] std::io::Error, Backtrace),
+  #[error("Failed to read tag of audio file")]
+  ReadTagFail(#[from] ReadTagFramesError, Backtrace),
+}
+
+/// Inspects the audio file at `path`, reading its size, codec, audio properties, and tag. Audio properties are `None`
+/// when the file cannot be decoded, as inspecting such files is useful for finding out what is wrong with them.
+pub fn inspect_track_file(path: impl AsRef<Path>) -> Result<AudioFileInfo, TrackInspectError> {
+  let path = path.as_ref();
+  let size = std::fs::metadata(path)?.len();
+  let decoder = File::open(path).ok().and_then(|file| Decoder::new(BufReader::new(file)).ok());
+  let tags = read_tag_frames(path)?.into_iter().map(|(id, value)| TagFrame { id, value }).collect();
+  Ok(AudioFileInfo {
+    size,
+    codec: AudioCodec::from_path(path),
+    sample_rate: decoder.as_ref().map(|d| d.sample_rate()),
+    channels: decoder.as_ref().map(|d| d.channels()),
+    duration: decoder.as_ref().and_then(|d| d.total_duration()).map(|d| d.as_secs_f64()),
+    tags,
+  })
+}
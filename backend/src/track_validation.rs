@@ -0,0 +1,63 @@
+use std::backtrace::Backtrace;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+
+use rodio::{Decoder, Source};
+use thiserror::Error;
+
+use musium_core::model::TrackIssueKind;
+
+/// Duration of audio that is decoded from the start of each audio file. Enough to find files that are corrupt or in an
+/// unsupported format, while keeping validation of a large library cheap.
+const VALIDATION_DURATION: Duration = Duration::from_secs(5);
+/// Amount of audio that may be missing before the stated duration of an audio file is reached, before considering the
+/// file truncated. Absorbs rounding in the stated duration and decoder padding.
+const TRUNCATION_TOLERANCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum TrackValidateError {
+  #[error("Failed to open audio file")]
+  OpenFail(#[from] std::io::Error, Backtrace),
+  #[error("Failed to decode audio file")]
+  DecodeFail(#[from] rodio::decoder::DecoderError, Backtrace),
+  #[error("Audio file contains no audio")]
+  NoAudio,
+  #[error("Audio file ends after {decoded:?} while it states a duration of {stated:?}")]
+  Truncated { decoded: Duration, stated: Duration },
+}
+
+impl TrackValidateError {
+  /// Gets the kind of track issue this error represents.
+  pub fn kind(&self) -> TrackIssueKind {
+    match self {
+      TrackValidateError::OpenFail(..) => TrackIssueKind::Unreadable,
+      TrackValidateError::DecodeFail(..) | TrackValidateError::NoAudio => TrackIssueKind::Undecodable,
+      TrackValidateError::Truncated { .. } => TrackIssueKind::Truncated,
+    }
+  }
+}
+
+/// Decodes the first `VALIDATION_DURATION` of the audio file at `path`, checking that it can be decoded and that it does
+/// not end before its stated duration. Files that are truncated after the decoded part are not detected.
+pub fn validate_track_file(path: impl AsRef<Path>) -> Result<(), TrackValidateError> {
+  let file = File::open(path)?;
+  let decoder = Decoder::new(BufReader::new(file))?;
+  let stated = decoder.total_duration();
+  let samples_per_second = decoder.sample_rate() as u64 * decoder.channels().max(1) as u64;
+  if samples_per_second == 0 { return Err(TrackValidateError::NoAudio); }
+  let max_samples = samples_per_second * VALIDATION_DURATION.as_millis() as u64 / 1000;
+
+  let decoded_samples = decoder.take(max_samples as usize).count() as u64;
+  if decoded_samples == 0 { return Err(TrackValidateError::NoAudio); }
+  if decoded_samples < max_samples {
+    let decoded = Duration::from_millis(decoded_samples * 1000 / samples_per_second);
+    if let Some(stated) = stated {
+      if decoded + TRUNCATION_TOLERANCE < stated {
+        return Err(TrackValidateError::Truncated { decoded, stated });
+      }
+    }
+  }
+  Ok(())
+}
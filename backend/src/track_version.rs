@@ -0,0 +1,63 @@
+use musium_core::model::TrackRelationKind;
+
+/// Qualifier of a track title that tells which version of a track it is, such as `(Live)` or `- 2011 Remaster`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Qualifier {
+  /// Qualifier of the original version, such as `(Original Mix)` or `(Album Version)`.
+  Original,
+  Version(TrackRelationKind),
+}
+
+/// Splits the collation key of a track title, such as `song (live) [2011 remaster]`, into the collation key of the title
+/// of the original track, such as `song`, and the kind of version, or `None` if the title has no version qualifiers.
+/// Qualifiers are recognized in trailing parentheses or brackets, and after a trailing ` - `. When a title has multiple
+/// qualifiers, the innermost one determines the kind, as it describes the recording rather than the release.
+pub fn parse_version_title(title_key: &str) -> (&str, Option<TrackRelationKind>) {
+  let mut base = title_key.trim();
+  let mut kind = None;
+  while let Some((rest, qualifier)) = split_qualifier(base) {
+    match qualifier_kind(qualifier) {
+      Some(Qualifier::Original) => {}
+      Some(Qualifier::Version(version_kind)) => kind = Some(version_kind),
+      None => break,
+    }
+    base = rest;
+  }
+  if base.is_empty() {
+    return (title_key.trim(), None);
+  }
+  (base, kind)
+}
+
+/// Splits the last qualifier off `title`, returning the rest of the title and the text of the qualifier.
+fn split_qualifier(title: &str) -> Option<(&str, &str)> {
+  let (open, close) = match title.chars().last()? {
+    ')' => ('(', ')'),
+    ']' => ('[', ']'),
+    _ => {
+      let (rest, qualifier) = title.rsplit_once(" - ")?;
+      return Some((rest.trim_end(), qualifier.trim()));
+    }
+  };
+  let start = title.rfind(open)?;
+  let qualifier = &title[start + 1..title.len() - close.len_utf8()];
+  Some((title[..start].trim_end_matches(|c: char| c.is_whitespace() || c == '-'), qualifier.trim()))
+}
+
+fn qualifier_kind(qualifier: &str) -> Option<Qualifier> {
+  let words: Vec<&str> = qualifier.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+  let has = |word: &str| words.contains(&word);
+  if words.iter().any(|word| word.starts_with("remaster")) {
+    Some(Qualifier::Version(TrackRelationKind::Remaster))
+  } else if has("live") {
+    Some(Qualifier::Version(TrackRelationKind::Live))
+  } else if has("original") || (has("album") && has("version")) {
+    Some(Qualifier::Original)
+  } else if has("remix") || has("rmx") || has("mix") {
+    Some(Qualifier::Version(TrackRelationKind::Remix))
+  } else if ["edit", "demo", "acoustic", "instrumental", "unplugged", "version", "mono", "stereo"].iter().any(|word| has(word)) {
+    Some(Qualifier::Version(TrackRelationKind::Other))
+  } else {
+    None
+  }
+}
@@ -0,0 +1,55 @@
+use std::backtrace::Backtrace;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use rodio::{Decoder, Source};
+use thiserror::Error;
+
+/// Number of peaks in a generated waveform.
+pub const WAVEFORM_PEAK_COUNT: usize = 1000;
+
+/// Number of frames that are reduced into a single intermediate peak while decoding, to keep memory usage bounded for
+/// long audio files.
+const FRAMES_PER_INTERMEDIATE_PEAK: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum WaveformGenerateError {
+  #[error("Failed to open audio file")]
+  OpenFail(#[from] std::io::Error, Backtrace),
+  #[error("Failed to decode audio file")]
+  DecodeFail(#[from] rodio::decoder::DecoderError, Backtrace),
+}
+
+/// Decodes the audio file at `path` and downsamples it into `WAVEFORM_PEAK_COUNT` peaks, each being the maximum absolute
+/// amplitude of its segment of the audio, scaled to `0..=255`. Returns fewer peaks if the audio is very short.
+pub fn generate_waveform(path: impl AsRef<Path>) -> Result<Vec<u8>, WaveformGenerateError> {
+  let file = File::open(path)?;
+  let decoder = Decoder::new(BufReader::new(file))?;
+  let samples_per_intermediate_peak = decoder.channels().max(1) as usize * FRAMES_PER_INTERMEDIATE_PEAK;
+
+  let mut intermediate_peaks: Vec<u16> = Vec::new();
+  let mut peak = 0u16;
+  let mut count = 0;
+  for sample in decoder {
+    peak = peak.max(sample.unsigned_abs());
+    count += 1;
+    if count == samples_per_intermediate_peak {
+      intermediate_peaks.push(peak);
+      peak = 0;
+      count = 0;
+    }
+  }
+  if count > 0 {
+    intermediate_peaks.push(peak);
+  }
+
+  let peak_count = WAVEFORM_PEAK_COUNT.min(intermediate_peaks.len());
+  let peaks = (0..peak_count).map(|i| {
+    let start = i * intermediate_peaks.len() / peak_count;
+    let end = ((i + 1) * intermediate_peaks.len() / peak_count).max(start + 1);
+    let peak = intermediate_peaks[start..end].iter().copied().max().unwrap_or(0);
+    (peak as u32 * 255 / i16::MAX as u32).min(255) as u8
+  }).collect();
+  Ok(peaks)
+}
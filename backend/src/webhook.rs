@@ -0,0 +1,223 @@
+use std::backtrace::Backtrace;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac, NewMac};
+use reqwest::Client;
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::{self, sync::broadcast::error::RecvError, task, time};
+use tracing::{event, instrument, Level};
+use url::Url;
+
+use musium_core::api::{Event, SyncStatus, WEBHOOK_SIGNATURE_HEADER, WEBHOOK_TIMESTAMP_HEADER, WebhookPayload};
+use musium_core::format_error::FormatError;
+use musium_core::model::Webhook;
+
+use crate::database::Database;
+use crate::event::EventBus;
+use crate::worker::WorkerState;
+
+/// Maximum number of attempts to deliver a payload to a webhook.
+const MAX_ATTEMPTS: u32 = 4;
+/// Delay before retrying to deliver a payload for the first time, doubled for each following retry.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Time after which an attempt to deliver a payload is considered failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Calls the enabled webhooks on sync completion, new albums, and sync errors, by listening to events on the event bus.
+/// Paused while the database is quiesced.
+pub struct WebhookDispatchTask {
+  handle: task::JoinHandle<()>,
+  state: WorkerState,
+}
+
+impl WebhookDispatchTask {
+  pub fn spawn(database: Arc<Database>, event_bus: &EventBus) -> Self {
+    let mut rx = event_bus.subscribe();
+    let state = WorkerState::default();
+    let task_state = state.clone();
+    let handle = tokio::spawn(async move {
+      let _stopped = task_state.stopped_on_drop();
+      let client = Client::new();
+      loop {
+        match rx.recv().await {
+          Ok(Event::SyncStatusChanged(_)) if database.is_quiesced() => {}
+          Ok(Event::SyncStatusChanged(sync_status)) => {
+            let _busy = task_state.busy();
+            Self::dispatch(database.clone(), &client, sync_status).await
+          }
+          Ok(_) => {}
+          Err(RecvError::Lagged(count)) => event!(Level::WARN, "Webhook dispatch lagged behind, skipped {} event(s)", count),
+          Err(RecvError::Closed) => break,
+        }
+      }
+    });
+    Self { handle, state }
+  }
+
+  pub fn state(&self) -> WorkerState { self.state.clone() }
+
+  #[instrument(skip(database, client))]
+  async fn dispatch(database: Arc<Database>, client: &Client, sync_status: SyncStatus) {
+    let result = task::spawn_blocking(move || {
+      let connection = database.connect().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))?;
+      let payloads = match sync_status {
+        SyncStatus::Completed { started_at, ended_at, summary } => {
+          let albums = connection.list_albums_by_ids(&summary.added_album_ids).map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))?;
+          let mut payloads = vec![WebhookPayload::SyncCompleted { started_at, ended_at, summary }];
+          if !albums.is_empty() {
+            payloads.push(WebhookPayload::NewAlbums { albums });
+          }
+          payloads
+        }
+        SyncStatus::Failed { errors, .. } => vec![WebhookPayload::Error { errors }],
+        _ => return Ok((Vec::new(), Vec::new())),
+      };
+      let webhooks = connection.list_enabled_webhooks().map_err(|e| event!(Level::ERROR, "{:?}", FormatError::new(&e)))?;
+      Ok((webhooks, payloads))
+    }).await;
+    let (webhooks, payloads) = match result {
+      Ok(Ok(webhooks_and_payloads)) => webhooks_and_payloads,
+      Ok(Err(())) => return, // Error was already logged.
+      Err(e) => {
+        event!(Level::ERROR, "Preparing webhook payloads panicked: {:?}", e);
+        return;
+      }
+    };
+    for payload in payloads {
+      let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+          event!(Level::ERROR, ?payload, "Failed to serialize webhook payload: {:?}", e);
+          continue;
+        }
+      };
+      for webhook in webhooks.iter().filter(|webhook| is_enabled_for(webhook, &payload)) {
+        // Deliver concurrently, so that retries of one webhook do not delay the others.
+        tokio::spawn(deliver(client.clone(), webhook.clone(), body.clone()));
+      }
+    }
+  }
+}
+
+impl Drop for WebhookDispatchTask {
+  fn drop(&mut self) {
+    self.handle.abort();
+  }
+}
+
+fn is_enabled_for(webhook: &Webhook, payload: &WebhookPayload) -> bool {
+  match payload {
+    WebhookPayload::SyncCompleted { .. } => webhook.on_sync_completed,
+    WebhookPayload::NewAlbums { .. } => webhook.on_new_albums,
+    WebhookPayload::Error { .. } => webhook.on_errors,
+  }
+}
+
+/// Posts `body` to `webhook`, retrying with exponential backoff when the webhook cannot be reached or does not respond
+/// with a success status. Each attempt is signed with the time of the attempt, so that receivers can reject replayed
+/// deliveries. Gives up when the URL of the webhook resolves to a forbidden address.
+#[instrument(skip(client, webhook, body), fields(webhook_id = webhook.id, url = %webhook.url))]
+async fn deliver(client: Client, webhook: Webhook, body: Vec<u8>) {
+  let mut retry_delay = INITIAL_RETRY_DELAY;
+  for attempt in 1..=MAX_ATTEMPTS {
+    // Check the URL on each attempt, as the addresses that its host resolves to may have changed since.
+    let url = webhook.url.clone();
+    match task::spawn_blocking(move || check_webhook_url(&url)).await {
+      Ok(Ok(())) => {}
+      Ok(Err(e)) => {
+        event!(Level::ERROR, "Not delivering webhook payload: {:?}", FormatError::new(&e));
+        return;
+      }
+      Err(e) => {
+        event!(Level::ERROR, "Checking webhook URL panicked: {:?}", e);
+        return;
+      }
+    }
+    let timestamp = Utc::now().timestamp().to_string();
+    let signature = sign(&webhook.secret, &timestamp, &body);
+    let result = client.post(&webhook.url)
+      .header(reqwest::header::CONTENT_TYPE, "application/json")
+      .header(WEBHOOK_TIMESTAMP_HEADER, &timestamp)
+      .header(WEBHOOK_SIGNATURE_HEADER, &signature)
+      .timeout(REQUEST_TIMEOUT)
+      .body(body.clone())
+      .send()
+      .await;
+    match result {
+      Ok(response) if response.status().is_success() => {
+        event!(Level::DEBUG, attempt, "Delivered webhook payload");
+        return;
+      }
+      Ok(response) => event!(Level::WARN, attempt, status = %response.status(), "Webhook responded with an unsuccessful status"),
+      Err(e) => event!(Level::WARN, attempt, "Failed to call webhook: {:?}", FormatError::new(&e)),
+    }
+    if attempt < MAX_ATTEMPTS {
+      time::sleep(retry_delay).await;
+      retry_delay *= 2;
+    }
+  }
+  event!(Level::ERROR, "Giving up on delivering webhook payload after {} attempts", MAX_ATTEMPTS);
+}
+
+/// Signs `timestamp`, followed by a `.` and `body`, with HMAC-SHA256 keyed by `secret`, returning the signature encoded
+/// as lowercase hex.
+fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+  let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("[BUG] HMAC rejected key, but it accepts keys of any length");
+  mac.update(timestamp.as_bytes());
+  mac.update(b".");
+  mac.update(body);
+  mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Checking URLs
+
+#[derive(Debug, Error)]
+pub enum WebhookUrlError {
+  #[error("Failed to parse webhook URL")]
+  ParseFail(#[from] url::ParseError, Backtrace),
+  #[error("Webhook URL '{0}' must use the http or https scheme")]
+  SchemeFail(String),
+  #[error("Webhook URL '{0}' has no host")]
+  NoHostFail(String),
+  #[error("Failed to resolve the host of webhook URL '{0}'")]
+  ResolveFail(String, #[source] std::io::Error, Backtrace),
+  #[error("Webhook URL '{0}' resolves to loopback, link-local, or unspecified address '{1}', which webhooks may not call")]
+  ForbiddenAddressFail(String, IpAddr),
+}
+
+/// Checks that `url` is an HTTP(S) URL of which the host does not resolve to a loopback, link-local, or unspecified
+/// address, so that webhooks cannot be used to call services of the server itself or of its cloud provider.
+pub fn check_webhook_url(url: &str) -> Result<(), WebhookUrlError> {
+  use WebhookUrlError::*;
+  let parsed = Url::parse(url)?;
+  if parsed.scheme() != "http" && parsed.scheme() != "https" {
+    return Err(SchemeFail(url.to_string()));
+  }
+  let host = parsed.host_str().ok_or_else(|| NoHostFail(url.to_string()))?;
+  // IPv6 addresses are enclosed in brackets in URLs, which resolving does not accept.
+  let host = host.trim_start_matches('[').trim_end_matches(']');
+  // UNWRAP: URLs with the http or https scheme always have a known default port.
+  let port = parsed.port_or_known_default().unwrap();
+  let addresses = (host, port).to_socket_addrs().map_err(|e| ResolveFail(url.to_string(), e, Backtrace::capture()))?;
+  for address in addresses {
+    if is_forbidden_address(address.ip()) {
+      return Err(ForbiddenAddressFail(url.to_string(), address.ip()));
+    }
+  }
+  Ok(())
+}
+
+fn is_forbidden_address(address: IpAddr) -> bool {
+  match address {
+    IpAddr::V4(address) => address.is_loopback() || address.is_link_local() || address.is_unspecified(),
+    IpAddr::V6(address) => {
+      let is_link_local = (address.segments()[0] & 0xffc0) == 0xfe80;
+      let is_mapped_forbidden = address.to_ipv4().map_or(false, |address| is_forbidden_address(IpAddr::V4(address)));
+      address.is_loopback() || is_link_local || address.is_unspecified() || is_mapped_forbidden
+    }
+  }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use musium_core::api::BackgroundWorkerState;
+
+/// State of a background worker, shared between the worker that updates it and the health probe that reports it.
+#[derive(Clone, Default)]
+pub struct WorkerState {
+  inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+  /// Number of jobs that the worker is currently running.
+  busy: AtomicUsize,
+  stopped: AtomicBool,
+}
+
+impl WorkerState {
+  pub fn get(&self) -> BackgroundWorkerState {
+    if self.inner.stopped.load(Ordering::SeqCst) {
+      BackgroundWorkerState::Stopped
+    } else if self.inner.busy.load(Ordering::SeqCst) > 0 {
+      BackgroundWorkerState::Busy
+    } else {
+      BackgroundWorkerState::Idle
+    }
+  }
+
+  /// Marks the worker as busy until the returned guard is dropped.
+  pub fn busy(&self) -> WorkerBusyGuard {
+    self.inner.busy.fetch_add(1, Ordering::SeqCst);
+    WorkerBusyGuard { inner: self.inner.clone() }
+  }
+
+  /// Returns a guard that marks the worker as stopped when dropped. The task of the worker must hold on to the guard, so
+  /// that the worker is marked as stopped when its task ends, panics, or is aborted.
+  pub fn stopped_on_drop(&self) -> WorkerStoppedGuard {
+    WorkerStoppedGuard { inner: self.inner.clone() }
+  }
+}
+
+pub struct WorkerBusyGuard {
+  inner: Arc<Inner>,
+}
+
+impl Drop for WorkerBusyGuard {
+  fn drop(&mut self) {
+    self.inner.busy.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+pub struct WorkerStoppedGuard {
+  inner: Arc<Inner>,
+}
+
+impl Drop for WorkerStoppedGuard {
+  fn drop(&mut self) {
+    self.inner.stopped.store(true, Ordering::SeqCst);
+  }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+use musium_core::api::{PlayCount, YearReview};
+
+/// Renders `review` of the user named `user_name` as a standalone HTML page for sharing, with inline styles so that it
+/// does not depend on any other files.
+pub fn render_year_review_html(review: &YearReview, user_name: &str) -> String {
+  let mut html = String::new();
+  // UNWRAP: writing to a String never fails.
+  writeln!(html, r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{year} in review &ndash; {user}</title>
+<style>
+body {{ margin: 0; padding: 2em; font-family: sans-serif; color: #f5f5f5; background: linear-gradient(135deg, #3a1c71, #d76d77, #ffaf7b); min-height: 100vh; box-sizing: border-box; }}
+main {{ max-width: 48em; margin: 0 auto; }}
+h1 {{ font-size: 3em; margin-bottom: 0; }}
+.numbers {{ display: flex; gap: 2em; margin: 2em 0; }}
+.number strong {{ display: block; font-size: 2.5em; }}
+.lists {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(14em, 1fr)); gap: 1em; }}
+section {{ background: rgba(0, 0, 0, 0.25); border-radius: 0.5em; padding: 1em; }}
+ol {{ padding-left: 1.5em; }}
+.count {{ opacity: 0.7; }}
+</style>
+</head>
+<body>
+<main>
+<h1>{year} in review</h1>
+<p>{user}'s year in music</p>
+<div class="numbers">
+<div class="number"><strong>{minutes}</strong>minutes listened</div>
+<div class="number"><strong>{plays}</strong>plays</div>
+<div class="number"><strong>{discoveries}</strong>new artists</div>
+</div>
+<div class="lists">"#,
+    year = review.year,
+    user = escape(user_name),
+    minutes = review.minutes_listened,
+    plays = review.play_count,
+    discoveries = review.new_discoveries.len(),
+  ).unwrap();
+  write_list(&mut html, "Top artists", review.top_artists.iter().map(|PlayCount { item, play_count }| (item.name.as_str(), *play_count)));
+  write_list(&mut html, "Top albums", review.top_albums.iter().map(|PlayCount { item, play_count }| (item.name.as_str(), *play_count)));
+  write_list(&mut html, "Top tracks", review.top_tracks.iter().map(|PlayCount { item, play_count }| (item.title.as_str(), *play_count)));
+  write_list_with_unit(&mut html, "Most skipped", "skips", review.most_skipped_tracks.iter().map(|PlayCount { item, play_count }| (item.title.as_str(), *play_count)));
+  write_list(&mut html, "New discoveries", review.new_discoveries.iter().map(|PlayCount { item, play_count }| (item.name.as_str(), *play_count)));
+  html.push_str("</div>\n</main>\n</body>\n</html>\n");
+  html
+}
+
+fn write_list<'a>(html: &mut String, title: &str, entries: impl Iterator<Item=(&'a str, i64)>) {
+  write_list_with_unit(html, title, "plays", entries)
+}
+
+fn write_list_with_unit<'a>(html: &mut String, title: &str, unit: &str, entries: impl Iterator<Item=(&'a str, i64)>) {
+  // UNWRAP: writing to a String never fails.
+  writeln!(html, "<section>\n<h2>{}</h2>\n<ol>", escape(title)).unwrap();
+  for (name, count) in entries {
+    writeln!(html, "<li>{} <span class=\"count\">{} {}</span></li>", escape(name), count, unit).unwrap();
+  }
+  html.push_str("</ol>\n</section>\n");
+}
+
+/// Escapes `text` for use in HTML text and attribute values.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
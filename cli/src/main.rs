@@ -1,16 +1,27 @@
-use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
 use dotenv;
 use metrics_core::{Builder, Drain, Observe};
 use metrics_observer_yaml::{YamlBuilder, YamlObserver};
 use metrics_runtime::{Controller, Receiver};
 use structopt::StructOpt;
-use tracing::trace;
+use tracing::{debug, trace};
 use tracing_subscriber::{EnvFilter, fmt};
 use tracing_subscriber::prelude::*;
 
+use musium_core::api::{AlbumCoverFetch, AlbumCoverUpload, AlbumSplit, AlphabetSection, AudioFeaturesFilter, ComposerInfo, ListeningSessionCommand, LocalSourceRelocation, MaintenanceStatus, MetadataConflictResolution, NewListeningSession, NewParty, NewPartyGuest, NewTrackRelation, NewUserInviteProperties, PlayCount, PlaylistProperties, PlayTracksQuery, SortNameOverride, StatsPeriod, TrackFilter, TrackRecommendationRequest, TrackSearch, TrackTechnicalInfo, TrackUpload, TrackVersion, UserPreferences, UserRegistration, UserStatsQuery, WorkInfo, YearReviewQuery};
+use musium_client_settings::{AudioOutputKind, Cache, ClientDirs, ClientProfile, ClientSettings, parse_profile_name};
+use musium_core::collation::alphabet_initial;
+use musium_core::format_error::FormatError;
 use musium_core::model::*;
 use musium_core::model::collection::{Albums, Tracks};
+use musium_i18n::{FALLBACK_LOCALE, FluentArgs, Localizer};
 use musium_player::{Client, create_default_player, Player, Url};
+use musium_player::output::AudioOutputSelection;
+use musium_player::remote::{default_remote_socket_path, RemoteCommand, send_remote_command};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "cli", about = "Musium CLI")]
@@ -18,15 +29,36 @@ struct Opt {
   #[structopt(subcommand)]
   command: Command,
 
-  /// Base URL to use for sending HTTP requests to the server
+  /// Name of the client settings profile to use, with its own server URL, login name, and caches. Defaults to the
+  /// current profile in the client settings file, or the default profile
+  #[structopt(long, env = "MUSIUM_PROFILE", parse(try_from_str = parse_profile_name))]
+  profile: Option<String>,
+  /// Base URL to use for sending HTTP requests to the server. Defaults to the URL of the profile in the client settings
+  /// file
   #[structopt(long, env = "MUSIUM_URL_BASE")]
-  url_base: Url,
-  /// Username for logging into the server
+  url_base: Option<Url>,
+  /// Username for logging into the server. Defaults to the login name of the profile in the client settings file
   #[structopt(long, env = "MUSIUM_LOGIN_NAME")]
-  name: String,
+  name: Option<String>,
   /// Password for logging into the server
   #[structopt(long, env = "MUSIUM_LOGIN_PASSWORD")]
-  password: String,
+  password: Option<String>,
+  /// Locale of messages, such as `nl-NL`. Defaults to the locale in the client settings file, or the locale in the
+  /// preferences of the user
+  #[structopt(long, env = "MUSIUM_LOCALE")]
+  locale: Option<String>,
+  /// Audio output to play audio with: `kira`, which supports seeking and fading, or `rodio`, which supports playing at
+  /// the native sample rate and decoding ahead. Defaults to the audio output in the client settings file, or `kira`
+  #[structopt(long, env = "MUSIUM_AUDIO_OUTPUT", possible_values = AudioOutputKind::NAMES)]
+  audio_output: Option<AudioOutputKind>,
+  /// Whether to play audio at its native sample rate instead of resampling it, such as `true`. Only supported by the
+  /// `rodio` audio output. Defaults to the setting in the client settings file
+  #[structopt(long, env = "MUSIUM_NATIVE_SAMPLE_RATE")]
+  native_sample_rate: Option<bool>,
+  /// Milliseconds of audio to decode ahead of playback, which prevents stutters on slow or busy machines. Only supported
+  /// by the `rodio` audio output. Defaults to the setting in the client settings file
+  #[structopt(long, env = "MUSIUM_DECODE_AHEAD")]
+  decode_ahead: Option<u64>,
 
   /// Whether to print metrics to stderr before the program exits
   #[structopt(long, env = "MUSIUM_PRINT_METRICS")]
@@ -35,6 +67,13 @@ struct Opt {
 
 #[derive(Debug, StructOpt)]
 enum Command {
+  /// Shows the version and capabilities of the server
+  ShowServerInfo,
+  /// Shows which optional subsystems are enabled on the server
+  ShowCapabilities,
+  /// Sets up a new server by creating its first user with the login name and password. Fails if the server has already
+  /// been set up
+  Setup,
   /// Lists all local sources
   ListLocalSources,
   /// Shows a local source, found by id
@@ -46,6 +85,10 @@ enum Command {
   CreateOrEnableLocalSource {
     /// Directory of the local source to create
     directory: String,
+    /// Whether to synchronize the local source even when its directory is unavailable, marking all its tracks as
+    /// removed
+    #[structopt(long)]
+    sync_if_unavailable: bool,
   },
   /// Enables or disables a local source, found by id
   SetLocalSourceEnabledById {
@@ -55,42 +98,414 @@ enum Command {
     #[structopt(short, long)]
     enabled: bool,
   },
+  /// Sets whether a local source, found by id, is skipped during synchronization when its directory is unavailable
+  SetLocalSourceSkipIfUnavailableById {
+    /// Id of the local source
+    id: i32,
+    /// Whether to skip the local source when its directory is unavailable
+    #[structopt(short, long)]
+    skip_if_unavailable: bool,
+  },
+  /// Relocates a local source, found by id, to a new directory
+  RelocateLocalSourceById {
+    /// Id of the local source to relocate
+    id: i32,
+    /// New directory of the local source
+    new_directory: String,
+    /// Only preview the relocation, without changing anything
+    #[structopt(long)]
+    dry_run: bool,
+  },
+  /// Moves the files of the managed local source to the paths given by the managed library template of the server
+  OrganizeManagedLibrary,
+  /// Lists all deleted local sources
+  ListDeletedLocalSources,
+  /// Deletes a local source, found by id, preserving its links so that it can be restored
+  DeleteLocalSourceById {
+    /// Id of the local source to delete
+    id: i32,
+  },
+  /// Restores a deleted local source, found by id
+  RestoreLocalSourceById {
+    /// Id of the local source to restore
+    id: i32,
+  },
 
+  /// Lists all Spotify sources, with the time of their last synchronization and its error
+  ListSpotifySources,
+  /// Shows a Spotify source, found by id
+  ShowSpotifySourceById {
+    /// Id of the Spotify source to show
+    id: i32,
+  },
   /// Creates a new Spotify source by requesting authorization with Spotify
   CreateSpotifySource,
   /// Shows me-info for my Spotify source
   ShowSpotifyMe,
+  /// Lists all deleted Spotify sources
+  ListDeletedSpotifySources,
+  /// Deletes a Spotify source, found by id, preserving its links so that it can be restored
+  DeleteSpotifySourceById {
+    /// Id of the Spotify source to delete
+    id: i32,
+  },
+  /// Restores a deleted Spotify source, found by id
+  RestoreSpotifySourceById {
+    /// Id of the Spotify source to restore
+    id: i32,
+  },
+  /// Saves an album, found by id, to my Spotify library
+  SaveSpotifyAlbum {
+    id: i32,
+  },
+  /// Removes an album, found by id, from my Spotify library
+  RemoveSavedSpotifyAlbum {
+    id: i32,
+  },
+  /// Follows an artist, found by id, on Spotify
+  FollowSpotifyArtist {
+    id: i32,
+  },
+  /// Adds tracks, found by id, to one of my Spotify playlists
+  AddTracksToSpotifyPlaylist {
+    /// Spotify ID of the playlist to add the tracks to
+    spotify_playlist_id: String,
+    track_ids: Vec<i32>,
+  },
 
-  /// Lists all albums
-  ListAlbums,
+  /// Lists all albums, sorted by sort name
+  ListAlbums {
+    /// Only list albums whose sort name starts with this letter, or with `#` for albums whose sort name does not
+    /// start with a letter
+    #[structopt(long)]
+    initial: Option<char>,
+    /// Only list albums of which the artist with this id is an album artist
+    #[structopt(long)]
+    artist_id: Option<i32>,
+  },
+  /// Shows how many albums there are per initial of their sort name, in the order in which albums are listed
+  ShowAlbumAlphabetIndex,
   /// Shows an album, found by id
   ShowAlbumById {
     id: i32,
   },
+  /// Sets the artists of an album, found by id
+  SetAlbumArtists {
+    id: i32,
+    artist_ids: Vec<i32>,
+  },
+  /// Splits tracks off an album, found by id, into a new album
+  SplitAlbum {
+    id: i32,
+    /// Name of the new album
+    #[structopt(long)]
+    name: String,
+    /// IDs of the tracks to move to the new album
+    #[structopt(long)]
+    track_ids: Vec<i32>,
+    /// IDs of the artists of the new album. Copies the artists of the original album when not given
+    #[structopt(long)]
+    artist_ids: Vec<i32>,
+  },
+  /// Overrides the sort name of an album, found by id
+  SetAlbumSortName {
+    id: i32,
+    /// Sort name of the album. Removes the override when not given
+    sort_name: Option<String>,
+  },
+  /// Sets the cover of an album, found by id, to a JPEG or PNG image file
+  SetAlbumCover {
+    id: i32,
+    /// Path to the image file
+    path: String,
+    /// Also embed the cover into the tags of the local files of the album
+    #[structopt(long)]
+    embed: bool,
+  },
+  /// Fetches the cover of an album, found by id, from the Cover Art Archive
+  FetchAlbumCover {
+    id: i32,
+    /// MusicBrainz release ID to fetch the front cover of
+    release_id: String,
+    /// Also embed the cover into the tags of the local files of the album
+    #[structopt(long)]
+    embed: bool,
+  },
 
-  /// Lists all tracks
-  ListTracks,
+  /// Lists all tracks in album order, or the tracks of an album or artist in the order they were added
+  ListTracks {
+    /// Only list tracks of the album with this id
+    #[structopt(long)]
+    album_id: Option<i32>,
+    /// Only list tracks of which the artist with this id is a main artist
+    #[structopt(long)]
+    artist_id: Option<i32>,
+  },
+  /// Lists random tracks
+  ListRandomTracks {
+    /// Number of tracks to list. The server picks a default when not set
+    #[structopt(long)]
+    count: Option<i64>,
+    /// Only list tracks of the album with this id
+    #[structopt(long)]
+    album_id: Option<i32>,
+  },
+  /// Lists the tracks to play of an album and/or artist, in album order, or in random order when shuffling
+  ListTracksToPlay {
+    /// Only list tracks of the album with this id
+    #[structopt(long)]
+    album_id: Option<i32>,
+    /// Only list tracks of the artist with this id
+    #[structopt(long)]
+    artist_id: Option<i32>,
+    /// Whether to list the tracks in random order
+    #[structopt(long)]
+    shuffle: bool,
+    /// Whether to list tracks you often skip further back when listing in random order
+    #[structopt(long)]
+    downrank_skipped: bool,
+  },
+  /// Lists tracks similar to tracks, found by id
+  RecommendTracks {
+    /// Ids of the tracks to list similar tracks of
+    seed_track_ids: Vec<i32>,
+    /// Number of tracks to list. The server picks a default when not set
+    #[structopt(long)]
+    count: Option<i64>,
+    /// Whether to avoid listing tracks you often skip
+    #[structopt(long)]
+    downrank_skipped: bool,
+  },
+  /// Reports that you skipped a track, found by id, at a relative position between 0.0 and 1.0
+  ReportSkip {
+    id: i32,
+    position_relative: f64,
+  },
   /// Shows a track, found by id
   ShowTrackById {
     id: i32,
+    /// Show technical details of the track and its files instead, such as file paths, codecs, and tags
+    #[structopt(long)]
+    technical: bool,
+  },
+  /// Moves a track, found by id, to another album
+  MoveTrackToAlbum {
+    id: i32,
+    album_id: i32,
+  },
+  /// Sets the artists of a track, found by id, credited in a role
+  SetTrackArtists {
+    id: i32,
+    artist_ids: Vec<i32>,
+    /// Role of the artists: main, featured, remixer, composer, conductor, or performer
+    #[structopt(long, default_value = "main")]
+    role: TrackArtistRole,
+  },
+  /// Shows the waveform of a track, found by id, generating it if needed
+  ShowTrackWaveform {
+    id: i32,
+  },
+  /// Shows the detected BPM and key of a track, found by id
+  ShowTrackAudioFeatures {
+    id: i32,
+  },
+  /// Lists tracks with detected BPM and key matching given criteria
+  FilterTracksByAudioFeatures {
+    /// Minimum BPM
+    #[structopt(long)]
+    min_bpm: Option<f32>,
+    /// Maximum BPM
+    #[structopt(long)]
+    max_bpm: Option<f32>,
+    /// Pitch class of the key, where 0 is C, 1 is C#, and so forth
+    #[structopt(long)]
+    key_pitch_class: Option<i32>,
+    /// Whether the key is minor (true) or major (false)
+    #[structopt(long)]
+    key_minor: Option<bool>,
+  },
+  /// Starts detecting BPM, key, and mood tags of local tracks in the background
+  AnalyzeAudioFeatures,
+  /// Searches for tracks
+  SearchTracks {
+    /// Text that the title of tracks must contain
+    #[structopt(default_value = "")]
+    query: String,
+    /// Tag that tracks must be tagged with
+    #[structopt(long)]
+    tag: Option<String>,
+    /// Only take tags assigned by users into account, ignoring machine-generated mood tags
+    #[structopt(long)]
+    exclude_machine_generated_tags: bool,
+  },
+  /// Uploads an MP3 file into the managed local source of the server, and prints the created track
+  UploadTrack {
+    /// Path to the MP3 file
+    path: String,
+    /// File name to store the file under. Defaults to the file name of the path
+    #[structopt(long)]
+    file_name: Option<String>,
+  },
+  /// Lists the other versions of a track, such as its original track, remasters, live recordings, and remixes
+  ListTrackVersions {
+    /// Id of the track
+    id: i32,
+  },
+  /// Links a track as a version of an original track
+  LinkTrackVersion {
+    /// Id of the original track
+    original_id: i32,
+    /// Id of the track that is a version of the original track
+    version_id: i32,
+    /// Kind of version: remaster, live, remix, or other
+    kind: TrackRelationKind,
+  },
+  /// Removes the link between a version of a track and its original track
+  UnlinkTrackVersion {
+    /// Id of the original track
+    original_id: i32,
+    /// Id of the track that is a version of the original track
+    version_id: i32,
+  },
+  /// Lists all composers, along with their number of works
+  ListComposers,
+  /// Lists the works of a composer
+  ListComposerWorks {
+    /// Id of the composer
+    id: i32,
+  },
+  /// Lists the tracks of a work, ordered by movement
+  ListWorkTracks {
+    /// Id of the work
+    id: i32,
+  },
+  /// Lists all tags
+  ListTags,
+  /// Lists the tags of a track
+  ListTrackTags {
+    /// Id of the track
+    id: i32,
+  },
+  /// Tags a track
+  AddTrackTag {
+    /// Id of the track
+    id: i32,
+    /// Name of the tag
+    name: String,
+  },
+  /// Removes a tag from a track
+  RemoveTrackTag {
+    /// Id of the track
+    id: i32,
+    /// Name of the tag
+    name: String,
   },
   /// Plays a track
   PlayTrack {
     /// ID of the track to play
     id: i32,
   },
+  /// Shows a short-lived URL that plays a track without logging in, for external players
+  ShowSignedTrackUrl {
+    /// ID of the track to create the URL for
+    id: i32,
+  },
+  /// Shows your playback preferences
+  ShowPlaybackPreference,
+  /// Sets your playback preferences
+  SetPlaybackPreference {
+    /// Whether to prefer streaming services over local files when a track is available from both
+    #[structopt(long)]
+    prefer_streaming: bool,
+    /// Minimum bitrate in kbit/s of local files to prefer them over streaming services
+    #[structopt(long)]
+    min_local_bitrate: Option<i32>,
+  },
+  /// Shows your preferences that roam across devices
+  ShowUserPreferences,
+  /// Sets one of your preferences that roam across devices, such as `theme`, `default_volume`, `locale`, `mono_downmix`, `channel_balance`, or `crossfeed`
+  SetUserPreference {
+    /// Key of the preference to set
+    key: String,
+    /// Value to set the preference to. Removes the preference when not given
+    value: Option<String>,
+  },
+  /// Lists the tracks, albums, and artists you hid, which are never played when shuffling or in radio, and are left out
+  /// of lists
+  ListHiddenItems,
+  /// Hides a track, album, or artist, so that it is never played when shuffling or in radio, and is left out of lists
+  HideItem {
+    /// Kind of the item to hide: track, album, or artist
+    kind: HiddenItemKind,
+    /// Id of the item to hide
+    id: i32,
+  },
+  /// Unhides a track, album, or artist that you hid
+  UnhideItem {
+    /// Kind of the item to unhide: track, album, or artist
+    kind: HiddenItemKind,
+    /// Id of the item to unhide
+    id: i32,
+  },
+  /// Shows your content filter, which leaves out explicit tracks when listing, shuffling, and recommending tracks
+  ShowMyContentFilter,
+  /// Sets your content filter, unless an admin pinned it
+  SetMyContentFilter {
+    /// Content filter to set: off, exclude_explicit, or only_clean
+    filter: ContentFilter,
+  },
+  /// Shows the content filter of a user, found by id. Only admins may do this
+  ShowUserContentFilter {
+    id: i32,
+  },
+  /// Sets the content filter of a user, found by id. Only admins may do this
+  SetUserContentFilter {
+    id: i32,
+    /// Content filter to set: off, exclude_explicit, or only_clean
+    filter: ContentFilter,
+    /// Whether to pin the content filter, so that the user cannot change it
+    #[structopt(long)]
+    pinned: bool,
+  },
 
   /// Lists all artists
-  ListArtists,
-  /// Shows an artist, found by id
+  ListArtists {
+    /// Only list artists whose sort name starts with this letter, or with `#` for artists whose sort name does not
+    /// start with a letter
+    #[structopt(long)]
+    initial: Option<char>,
+  },
+  /// Shows how many artists there are per initial of their sort name, in the order in which artists are listed
+  ShowArtistAlphabetIndex,
+  /// Shows an artist, found by id, along with its biography if it was fetched
   ShowArtistById {
     id: i32,
   },
+  /// Overrides the sort name of an artist, found by id
+  SetArtistSortName {
+    id: i32,
+    /// Sort name of the artist. Removes the override when not given
+    sort_name: Option<String>,
+  },
 
   /// Lists all users
   ListUsers,
   /// Shows your (logged-in) user
   ShowMyUser,
+  /// Shows your listening statistics, computed from your play history
+  MyStats {
+    /// Period to compute statistics over: week, month, year, or all_time
+    #[structopt(long, default_value = "month")]
+    period: StatsPeriod,
+  },
+  /// Shows your year in review: top artists, minutes listened, and new discoveries of a year
+  MyYearReview {
+    /// Year to review
+    year: i32,
+    /// Whether to print the year in review as a standalone HTML page for sharing instead
+    #[structopt(long)]
+    html: bool,
+  },
   /// Shows a user, found by id
   ShowUserById {
     id: i32,
@@ -112,6 +527,19 @@ enum Command {
     /// Id of the user to delete
     id: i32,
   },
+  /// Creates a single-use invite code with which a new user can register
+  CreateUserInvite {
+    /// Number of hours the invite code can be redeemed for. Defaults to the default of the server
+    #[structopt(long)]
+    valid_for_hours: Option<u32>,
+  },
+  /// Lists all invite codes
+  ListUserInvites,
+  /// Registers a new user with the login name and password by redeeming an invite code, instead of logging in
+  Register {
+    /// Invite code to redeem
+    invite_code: String,
+  },
 
   /// Sets the user-rating for an album
   SetUserAlbumRating {
@@ -135,8 +563,245 @@ enum Command {
     rating: i32,
   },
 
+  /// Lists playlists that you own, collaborate on, or that are public
+  ListPlaylists,
+  /// Lists your daily and weekly mixes, which are regenerated periodically
+  ListPlaylistMixes,
+  /// Shows a playlist, found by id
+  ShowPlaylistById {
+    id: i32,
+  },
+  /// Creates a new playlist
+  CreatePlaylist {
+    /// Name of the playlist to create
+    name: String,
+    /// Whether the playlist can be viewed by all users
+    #[structopt(short, long)]
+    public: bool,
+  },
+  /// Renames or (un)publishes a playlist, found by id
+  UpdatePlaylist {
+    /// Id of the playlist to update
+    id: i32,
+    /// New name of the playlist
+    name: String,
+    /// Whether the playlist can be viewed by all users
+    #[structopt(short, long)]
+    public: bool,
+  },
+  /// Deletes a playlist, found by id
+  DeletePlaylist {
+    /// Id of the playlist to delete
+    id: i32,
+  },
+  /// Appends a track to a playlist
+  AddPlaylistTrack {
+    /// Id of the playlist
+    id: i32,
+    /// Id of the track to append
+    track_id: i32,
+  },
+  /// Removes a track from a playlist
+  RemovePlaylistTrack {
+    /// Id of the playlist
+    id: i32,
+    /// Id of the playlist track (not the track) to remove
+    playlist_track_id: i32,
+  },
+  /// Allows a user to add and remove tracks of a playlist
+  AddPlaylistCollaborator {
+    /// Id of the playlist
+    id: i32,
+    /// Id of the user to add as a collaborator
+    user_id: i32,
+  },
+  /// Disallows a user to add and remove tracks of a playlist
+  RemovePlaylistCollaborator {
+    /// Id of the playlist
+    id: i32,
+    /// Id of the user to remove as a collaborator
+    user_id: i32,
+  },
+
+  /// Lists all listening sessions
+  ListListeningSessions,
+  /// Shows a listening session, found by id
+  ShowListeningSessionById {
+    id: i32,
+  },
+  /// Creates and hosts a new listening session
+  CreateListeningSession {
+    /// Maximum playback drift in milliseconds before members should resynchronize
+    #[structopt(long, default_value = "500")]
+    drift_tolerance_ms: u32,
+  },
+  /// Joins a listening session, found by id
+  JoinListeningSession {
+    id: i32,
+  },
+  /// Leaves a listening session, found by id. Ends the session if you are the host
+  LeaveListeningSession {
+    id: i32,
+  },
+  /// Starts playback in a listening session that you host
+  PlayListeningSession {
+    id: i32,
+  },
+  /// Pauses playback in a listening session that you host
+  PauseListeningSession {
+    id: i32,
+  },
+  /// Seeks playback in a listening session that you host
+  SeekListeningSession {
+    id: i32,
+    /// Position to seek to in milliseconds
+    position_ms: u64,
+  },
+  /// Changes the track of a listening session that you host
+  ChangeListeningSessionTrack {
+    id: i32,
+    /// ID of the track to play
+    track_id: i32,
+  },
+
+  /// Shows a party, found by id
+  ShowPartyById {
+    id: i32,
+  },
+  /// Creates and hosts a new party, where guests can request tracks
+  CreateParty {
+    /// Maximum number of requests a guest may make per minute. No limit if not set
+    #[structopt(long)]
+    max_guest_requests_per_minute: Option<u32>,
+  },
+  /// Ends a party that you host, found by id
+  EndParty {
+    id: i32,
+  },
+  /// Invites a guest to a party that you host, printing the guest token
+  InvitePartyGuest {
+    /// Id of the party
+    id: i32,
+    /// Name of the guest
+    name: String,
+    /// Number of seconds the guest token stays valid
+    #[structopt(long, default_value = "14400")]
+    valid_for_secs: u32,
+  },
+  /// Removes and prints the next requested track of a party that you host
+  DequeuePartyTrack {
+    id: i32,
+  },
+
+  /// Lists albums by artists in the library that exist on Spotify, but have no local files
+  ListMissingAlbums,
+  /// Shows the number of tracks, albums, artists, and playlists in the library
+  ShowLibrarySummary,
+  /// Lists new releases of followed artists
+  ListNewReleases,
+  /// Polls for events published after the event with sequence number `since`, or shows the sequence number to start
+  /// polling from if it is not given
+  PollEvents {
+    since: Option<u64>,
+    /// Number of seconds to wait for an event to be published if there are none yet
+    #[structopt(short, long)]
+    wait: Option<u64>,
+  },
+  /// Lists your notifications
+  ListNotifications {
+    /// Whether to only list unread notifications
+    #[structopt(short, long)]
+    unread_only: bool,
+  },
+  /// Marks one of your notifications as read, found by id
+  MarkNotificationRead {
+    /// Id of the notification to mark as read
+    id: i32,
+  },
+  /// Marks all your notifications as read
+  MarkAllNotificationsRead,
+  /// Shows your notification preferences
+  ShowNotificationPreference,
+  /// Sets your notification preferences
+  SetNotificationPreference {
+    /// Whether to be notified of new releases of followed artists
+    #[structopt(long, parse(try_from_str))]
+    new_releases: bool,
+    /// Whether to be notified of synchronization failures
+    #[structopt(long, parse(try_from_str))]
+    sync_failures: bool,
+    /// Whether to be notified of changes to shared playlists
+    #[structopt(long, parse(try_from_str))]
+    shared_playlist_changes: bool,
+  },
+
+  /// Shows whether the server is in read-only maintenance mode
+  ShowMaintenanceStatus,
+  /// Enables or disables read-only maintenance mode of the server, in which requests that modify the database are rejected
+  SetMaintenanceStatus {
+    /// Whether to enable maintenance mode. Maintenance mode is disabled when not set
+    #[structopt(long)]
+    enabled: bool,
+    /// Reason for the maintenance, shown to rejected requests
+    #[structopt(long)]
+    reason: Option<String>,
+  },
+  /// Shows the number and size of images and waveforms in the media cache of the server
+  ShowMediaCacheStatus,
+  /// Lists local tracks whose file could not be read, could not be decoded, or is truncated
+  ListTrackIssues,
+  /// Starts validating the files of local tracks in the background, by decoding their first seconds
+  ValidateLocalTracks,
+
+  /// Lists your operations that can still be undone
+  ListUndoLog,
+  /// Undoes an operation, found by the id of its undo log entry
+  Undo {
+    /// Id of the undo log entry
+    id: i32,
+  },
+
+  /// Lists the metadata merge policies that are set
+  ListMetadataMergePolicies,
+  /// Sets the policy for merging a metadata field when sources disagree on its value
+  SetMetadataMergePolicy {
+    /// Metadata field: album-name, track-title, track-number, disc-number, artist-name, or track-artists
+    field: MetadataField,
+    /// Merge policy: prefer-local, prefer-spotify, prefer-musicbrainz, or manual
+    policy: MetadataMergePolicy,
+  },
+  /// Removes the merge policy of a metadata field, so that whichever source is synchronized last wins
+  RemoveMetadataMergePolicy {
+    /// Metadata field: album-name, track-title, track-number, disc-number, artist-name, or track-artists
+    field: MetadataField,
+  },
+  /// Lists metadata conflicts between sources that were not resolved by a merge policy
+  ListMetadataConflicts,
+  /// Resolves a metadata conflict by setting its field to a value
+  ResolveMetadataConflict {
+    /// Id of the metadata conflict
+    id: i32,
+    /// Value to set. Track artists are separated by '; ', and an empty value unsets a track or disc number
+    value: String,
+  },
+  /// Dismisses a metadata conflict, keeping the current value
+  DismissMetadataConflict {
+    /// Id of the metadata conflict
+    id: i32,
+  },
+  /// Lists the articles that are moved to the end of album and artist names for sorting
+  ListSortArticles,
+  /// Sets the articles that are moved to the end of album and artist names for sorting
+  SetSortArticles {
+    articles: Vec<String>,
+  },
+
   /// Shows the status of the current synchronization task (if any).
-  ShowSyncStatus,
+  ShowSyncStatus {
+    /// Number of seconds to wait for the status to change before showing it
+    #[structopt(short, long)]
+    wait: Option<u64>,
+  },
   /// Attempts to start a synchronization task with all sources if no synchronization task is currently running.
   /// Shows the status of the current synchronization task otherwise.
   SyncAllSources,
@@ -158,6 +823,86 @@ enum Command {
     /// ID of the Spotify source to synchronize.
     spotify_source_id: i32,
   },
+  /// Previews the changes that synchronizing with all local sources would make, without making them.
+  PreviewSync,
+  /// Shows which server instance holds the sync lock (if any).
+  ShowSyncLock,
+  /// Forcibly releases the sync lock, regardless of which server instance holds it. Only use this to recover from a
+  /// stale lock of a server instance that crashed.
+  ForceReleaseSyncLock,
+  /// Lists the sync runs of each source of recent synchronizations, most recent first.
+  SyncHistory,
+
+  /// Lists all webhooks
+  ListWebhooks,
+  /// Creates a webhook that is called with a JSON payload, signed with HMAC-SHA256 keyed by `secret`
+  CreateWebhook {
+    /// URL to post payloads to
+    url: String,
+    /// Key of the signature of payloads
+    secret: String,
+    /// Whether to call the webhook when a synchronization completes
+    #[structopt(long)]
+    on_sync_completed: bool,
+    /// Whether to call the webhook when a synchronization adds albums
+    #[structopt(long)]
+    on_new_albums: bool,
+    /// Whether to call the webhook when a synchronization fails
+    #[structopt(long)]
+    on_errors: bool,
+  },
+  /// Enables or disables a webhook, found by id
+  SetWebhookEnabledById {
+    /// Id of the webhook
+    id: i32,
+    /// Whether to enable or disable the webhook
+    #[structopt(short, long)]
+    enabled: bool,
+  },
+  /// Deletes a webhook, found by id
+  DeleteWebhookById {
+    /// Id of the webhook to delete
+    id: i32,
+  },
+
+  /// Sends a command to a running player instance, such as the GUI started with `--remote-control`, through its local
+  /// control socket. Does not log into the server
+  Remote {
+    /// Command to send
+    #[structopt(possible_values = RemoteCommand::NAMES)]
+    command: RemoteCommand,
+    /// Path of the local control socket of the player instance. Defaults to `musium-player.sock` in `$XDG_RUNTIME_DIR`
+    /// or the temporary directory
+    #[structopt(long, env = "MUSIUM_REMOTE_CONTROL_SOCKET")]
+    socket: Option<PathBuf>,
+  },
+
+  /// Shows the size of the client caches, or clears them
+  Cache {
+    /// Cache to show or clear. Defaults to all caches
+    #[structopt(possible_values = Cache::NAMES)]
+    cache: Option<Cache>,
+    /// Clear the cache instead of showing its size
+    #[structopt(long)]
+    clear: bool,
+  },
+  /// Saves the server URL and login name into the profile, and the locale and audio output as client settings, which are
+  /// used when they are not given as arguments or environment variables. Passwords are never saved
+  SaveSettings,
+  /// Lists the profiles in the client settings, marking the current profile with `*`
+  ListProfiles,
+  /// Switches the current profile, which is used when no profile is given
+  SwitchProfile {
+    /// Name of the profile to switch to. Switches to the default profile when not given
+    #[structopt(parse(try_from_str = parse_profile_name))]
+    name: Option<String>,
+  },
+  /// Removes a profile from the client settings. Does not clear its caches
+  RemoveProfile {
+    /// Name of the profile to remove
+    #[structopt(parse(try_from_str = parse_profile_name))]
+    name: String,
+  },
 }
 
 fn main() -> Result<()> {
@@ -181,22 +926,138 @@ fn main() -> Result<()> {
   let controller: Controller = metrics_receiver.controller();
   let mut observer: YamlObserver = YamlBuilder::new().build();
   metrics_receiver.install();
+  // Send remote commands to the running player instance, instead of creating a player with its own audio output.
+  if let Command::Remote { command, socket } = opt.command {
+    let socket = socket.unwrap_or_else(default_remote_socket_path);
+    println!("{}", send_remote_command(&socket, command)?);
+    return Ok(());
+  }
+  // Manage client caches and settings without connecting to the server.
+  let client_dirs = ClientDirs::new()?;
+  let mut settings = ClientSettings::load(&client_dirs)?;
+  let profile_name = opt.profile.or_else(|| settings.current_profile.clone());
+  let profile_dirs = client_dirs.for_profile(profile_name.as_deref());
+  // Localize output with the locale given as argument or in the client settings. When neither is given, the locale of
+  // the preferences of the user is used after logging in.
+  let locale = opt.locale.clone().or_else(|| settings.locale.clone());
+  let mut localizer = Localizer::new(locale.as_deref().unwrap_or(FALLBACK_LOCALE));
+  if let Command::Cache { cache, clear } = opt.command {
+    let caches = cache.map_or_else(|| Cache::ALL.to_vec(), |cache| vec![cache]);
+    for cache in caches {
+      if clear {
+        profile_dirs.clear_cache(cache)?;
+        let mut args = FluentArgs::new();
+        args.set("cache", cache.to_string());
+        println!("{}", localizer.text_with("cli-cache-cleared", &args));
+      } else {
+        let size = profile_dirs.cache_size(cache)?;
+        let mut args = FluentArgs::new();
+        args.set("cache", cache.to_string());
+        args.set("size", format!("{:.1}", size as f64 / (1024.0 * 1024.0)));
+        args.set("directory", format!("{:?}", profile_dirs.cache_dir(cache)));
+        println!("{}", localizer.text_with("cli-cache-size", &args));
+      }
+    }
+    return Ok(());
+  }
+  match opt.command {
+    Command::SaveSettings => {
+      let profile = settings.profile(profile_name.as_deref());
+      let profile = ClientProfile {
+        url_base: opt.url_base.map(|url_base| url_base.to_string()).or(profile.url_base),
+        login_name: opt.name.or(profile.login_name),
+      };
+      settings.set_profile(profile_name.as_deref(), profile);
+      settings.locale = opt.locale.or(settings.locale);
+      settings.audio_output.override_with(opt.audio_output, opt.native_sample_rate, opt.decode_ahead);
+      settings.save(&client_dirs)?;
+      let mut args = FluentArgs::new();
+      args.set("file", format!("{:?}", client_dirs.settings_file()));
+      println!("{}", localizer.text_with("cli-settings-saved", &args));
+      return Ok(());
+    }
+    Command::ListProfiles => {
+      let current = settings.current_profile.as_deref();
+      let default_profile = settings.profile(None);
+      let default_name = localizer.text("cli-default-profile");
+      println!("{} {}: {:?}", if current.is_none() { "*" } else { " " }, default_name, default_profile);
+      for (name, profile) in &settings.profiles {
+        println!("{} {}: {:?}", if current == Some(name.as_str()) { "*" } else { " " }, name, profile);
+      }
+      return Ok(());
+    }
+    Command::SwitchProfile { name } => {
+      if let Some(name) = &name {
+        if !settings.profiles.contains_key(name) {
+          anyhow::bail!(localizer.text_with("cli-no-profile-to-switch-to", &profile_args(name)));
+        }
+      }
+      settings.current_profile = name;
+      settings.save(&client_dirs)?;
+      let name = match &settings.current_profile {
+        Some(name) => name.clone(),
+        None => localizer.text("cli-default-profile"),
+      };
+      println!("{}", localizer.text_with("cli-profile-switched", &profile_args(&name)));
+      return Ok(());
+    }
+    Command::RemoveProfile { name } => {
+      if settings.remove_profile(&name) {
+        settings.save(&client_dirs)?;
+        println!("{}", localizer.text_with("cli-profile-removed", &profile_args(&name)));
+      } else {
+        anyhow::bail!(localizer.text_with("cli-no-profile", &profile_args(&name)));
+      }
+      return Ok(());
+    }
+    _ => {}
+  }
+  let profile = settings.profile(profile_name.as_deref());
+  let url_base = match opt.url_base {
+    Some(url_base) => url_base,
+    None => profile.url_base.as_deref()
+      .ok_or_else(|| anyhow!(localizer.text("cli-no-server-url")))?
+      .parse()
+      .with_context(|| localizer.text("cli-parse-server-url-failed"))?,
+  };
+  let name = opt.name.or(profile.login_name)
+    .ok_or_else(|| anyhow!(localizer.text("cli-no-login-name")))?;
+  let password = opt.password
+    .ok_or_else(|| anyhow!(localizer.text("cli-no-password")))?;
   // Create an async runtime
   let runtime = tokio::runtime::Builder::new_current_thread()
     .enable_all()
     .build()
     .unwrap();
   // Create player
-  let mut player = create_default_player(opt.url_base)?;
-  // Login
-  let user_login = UserLogin { name: opt.name, password: opt.password };
-  runtime.block_on(async { player.login(&user_login).await })
-    .with_context(|| "Failed to login to server")?;
-  // Run command
+  let mut audio_output_settings = settings.audio_output.clone();
+  audio_output_settings.override_with(opt.audio_output, opt.native_sample_rate, opt.decode_ahead);
+  let mut player = create_default_player(url_base, AudioOutputSelection::from(&audio_output_settings))?;
+  // Login, or create a user with the login name and password when setting up the server or registering.
   let command = opt.command;
-  let result = runtime.block_on(async {
-    run(command, &mut player).await
-  });
+  let result = if let Command::Setup = command {
+    let new_user = NewUser { name, password };
+    runtime.block_on(async { setup(&new_user, &player, &localizer).await })
+  } else if let Command::Register { invite_code } = command {
+    let registration = UserRegistration { invite_code, name, password };
+    runtime.block_on(async { register(&registration, &player).await })
+  } else {
+    let user_login = UserLogin { name, password };
+    runtime.block_on(async { player.login(&user_login).await })
+      .with_context(|| localizer.text("cli-login-failed"))?;
+    // Use the locale of the preferences of the user when no locale was given.
+    if locale.is_none() {
+      match runtime.block_on(async { player.get_client().get_user_preferences().await }) {
+        Ok(UserPreferences { locale: Some(locale), .. }) => localizer = Localizer::new(&locale),
+        Ok(_) => {}
+        Err(e) => debug!("Failed to get user preferences; using default locale: {:?}", FormatError::new(&e)),
+      }
+    }
+    // Run command
+    runtime.block_on(async {
+      run(command, &mut player, &localizer).await
+    })
+  };
   // Print metrics
   if opt.print_metrics {
     controller.observe(&mut observer);
@@ -207,8 +1068,114 @@ fn main() -> Result<()> {
   Ok(result?)
 }
 
-async fn run(command: Command, player: &mut impl Player) -> Result<()> {
+/// Prints the localized message with `title_id`, followed by each of the named `counts`, formatted with the message with
+/// `entry_id`.
+fn print_play_counts(localizer: &Localizer, title_id: &str, entry_id: &str, counts: impl IntoIterator<Item=(String, i64)>) {
+  println!("{}:", localizer.text(title_id));
+  for (name, count) in counts {
+    let mut args = FluentArgs::new();
+    args.set("name", name);
+    args.set("count", count);
+    println!("  {}", localizer.text_with(entry_id, &args));
+  }
+}
+
+/// Creates the arguments of a message about the client profile named `name`.
+fn profile_args(name: &str) -> FluentArgs<'_> {
+  let mut args = FluentArgs::new();
+  args.set("name", name);
+  args
+}
+
+/// Creates the arguments of a message about the file at `path`.
+fn path_args(path: &str) -> FluentArgs<'_> {
+  let mut args = FluentArgs::new();
+  args.set("path", path);
+  args
+}
+
+/// Creates the arguments of a message about `year`.
+fn year_args(year: i32) -> FluentArgs<'static> {
+  let mut args = FluentArgs::new();
+  args.set("year", year);
+  args
+}
+
+/// Prints each section of `index` with its count and the position in the list at which it starts.
+fn print_alphabet_index(index: &[AlphabetSection], localizer: &Localizer) {
+  let mut start = 0;
+  for AlphabetSection { initial, count } in index {
+    let mut args = FluentArgs::new();
+    args.set("initial", initial.to_string());
+    args.set("count", *count);
+    args.set("start", start);
+    println!("{}", localizer.text_with("cli-alphabet-section", &args));
+    start += count;
+  }
+}
+
+fn print_track_technical_info(technical_info: &TrackTechnicalInfo, localizer: &Localizer) {
+  println!("{:?}", technical_info.track);
+  for local_file in &technical_info.local_files {
+    let local_track = &local_file.local_track;
+    let mut args = FluentArgs::new();
+    args.set("id", local_track.local_source_id);
+    args.set("path", local_file.path.clone().unwrap_or_else(|| localizer.text("cli-technical-info-no-file")));
+    println!("{}", localizer.text_with("cli-technical-info-local-source", &args));
+    let mut args = FluentArgs::new();
+    args.set("hash", format!("{:08x}", local_track.hash));
+    args.set("bitrate", format!("{:?}", local_track.bitrate));
+    println!("  {}", localizer.text_with("cli-technical-info-hash", &args));
+    if let Some(file) = &local_file.file {
+      let mut args = FluentArgs::new();
+      args.set("size", file.size);
+      args.set("codec", format!("{:?}", file.codec));
+      args.set("sample-rate", format!("{:?}", file.sample_rate));
+      args.set("channels", format!("{:?}", file.channels));
+      args.set("duration", format!("{:?}", file.duration));
+      println!("  {}", localizer.text_with("cli-technical-info-file", &args));
+      for tag in &file.tags {
+        println!("  {}: {}", tag.id, tag.value);
+      }
+    }
+    if let Some(error) = &local_file.error {
+      let mut args = FluentArgs::new();
+      args.set("error", error.as_str());
+      println!("  {}", localizer.text_with("cli-technical-info-error", &args));
+    }
+  }
+  for spotify_track in &technical_info.spotify_tracks {
+    let mut args = FluentArgs::new();
+    args.set("id", spotify_track.spotify_id.as_str());
+    println!("{}", localizer.text_with("cli-technical-info-spotify-track", &args));
+  }
+}
+
+async fn setup(new_user: &NewUser, player: &impl Player, localizer: &Localizer) -> Result<()> {
+  match player.get_client().setup(new_user).await? {
+    Some(user) => println!("{:?}", user),
+    None => anyhow::bail!(localizer.text("cli-setup-already-done")),
+  }
+  Ok(())
+}
+
+async fn register(registration: &UserRegistration, player: &impl Player) -> Result<()> {
+  let user = player.get_client().register(registration).await?;
+  println!("{:?}", user);
+  Ok(())
+}
+
+async fn run(command: Command, player: &mut impl Player, localizer: &Localizer) -> Result<()> {
   match command {
+    Command::ShowServerInfo => {
+      println!("{:?}", player.get_client().get_server_info().await?);
+    }
+    Command::ShowCapabilities => {
+      println!("{:?}", player.get_client().get_capabilities().await?);
+    }
+    Command::Setup => {
+      unreachable!("setup is run instead of logging in");
+    }
     Command::ListLocalSources => {
       for local_source in player.get_client().list_local_sources().await? {
         println!("{:?}", local_source);
@@ -218,14 +1185,56 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
       let local_source = player.get_client().get_local_source_by_id(id).await?;
       println!("{:?}", local_source);
     }
-    Command::CreateOrEnableLocalSource { directory } => {
-      let local_source = player.get_client().create_or_enable_local_source(&NewLocalSource { enabled: true, directory }).await?;
+    Command::CreateOrEnableLocalSource { directory, sync_if_unavailable } => {
+      let local_source = player.get_client().create_or_enable_local_source(&NewLocalSource { enabled: true, directory, skip_if_unavailable: !sync_if_unavailable }).await?;
       println!("{:?}", local_source);
     }
     Command::SetLocalSourceEnabledById { id, enabled } => {
       player.get_client().set_local_source_enabled_by_id(id, enabled).await?;
     }
+    Command::SetLocalSourceSkipIfUnavailableById { id, skip_if_unavailable } => {
+      player.get_client().set_local_source_skip_if_unavailable_by_id(id, skip_if_unavailable).await?;
+    }
+    Command::RelocateLocalSourceById { id, new_directory, dry_run } => {
+      if let Some(result) = player.get_client().relocate_local_source_by_id(id, &LocalSourceRelocation { new_directory, dry_run }).await? {
+        println!("{:?}", result.local_source);
+        for track in result.tracks {
+          println!("- {:?}", track);
+        }
+      } else {
+        let mut args = FluentArgs::new();
+        args.set("id", id);
+        println!("{}", localizer.text_with("cli-no-local-source", &args));
+      }
+    }
+    Command::OrganizeManagedLibrary => {
+      let mut args = FluentArgs::new();
+      args.set("count", player.get_client().organize_managed_library().await?);
+      println!("{}", localizer.text_with("cli-files-moved", &args));
+    }
+    Command::ListDeletedLocalSources => {
+      for local_source in player.get_client().list_deleted_local_sources().await? {
+        println!("{:?}", local_source);
+      }
+    }
+    Command::DeleteLocalSourceById { id } => {
+      let local_source = player.get_client().delete_local_source_by_id(id).await?;
+      println!("{:?}", local_source);
+    }
+    Command::RestoreLocalSourceById { id } => {
+      let local_source = player.get_client().restore_local_source_by_id(id).await?;
+      println!("{:?}", local_source);
+    }
 
+    Command::ListSpotifySources => {
+      for spotify_source in player.get_client().list_spotify_sources().await? {
+        println!("{:?}", spotify_source);
+      }
+    }
+    Command::ShowSpotifySourceById { id } => {
+      let spotify_source = player.get_client().get_spotify_source_by_id(id).await?;
+      println!("{:?}", spotify_source);
+    }
     Command::CreateSpotifySource => {
       let url = player.get_client().create_spotify_source_authorization_url().await?;
       open::that(url)?;
@@ -234,54 +1243,327 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
       let me_info = player.get_client().show_spotify_me().await?;
       println!("{:?}", me_info);
     }
+    Command::ListDeletedSpotifySources => {
+      for spotify_source in player.get_client().list_deleted_spotify_sources().await? {
+        println!("{:?}", spotify_source);
+      }
+    }
+    Command::DeleteSpotifySourceById { id } => {
+      let spotify_source = player.get_client().delete_spotify_source_by_id(id).await?;
+      println!("{:?}", spotify_source);
+    }
+    Command::RestoreSpotifySourceById { id } => {
+      let spotify_source = player.get_client().restore_spotify_source_by_id(id).await?;
+      println!("{:?}", spotify_source);
+    }
+    Command::SaveSpotifyAlbum { id } => {
+      println!("{:?}", player.get_client().save_spotify_album(id).await?);
+    }
+    Command::RemoveSavedSpotifyAlbum { id } => {
+      println!("{:?}", player.get_client().remove_saved_spotify_album(id).await?);
+    }
+    Command::FollowSpotifyArtist { id } => {
+      println!("{:?}", player.get_client().follow_spotify_artist(id).await?);
+    }
+    Command::AddTracksToSpotifyPlaylist { spotify_playlist_id, track_ids } => {
+      println!("{:?}", player.get_client().add_tracks_to_spotify_playlist(&spotify_playlist_id, &track_ids).await?);
+    }
 
-    Command::ListAlbums => {
+    Command::ListAlbums { initial, artist_id } => {
       let albums_raw = player.get_client().list_albums().await?;
       let albums: Albums = albums_raw.into();
-      for (album, album_artists) in albums.iter() {
-        println!("{:?}", album);
+      let initial = initial.map(|c| c.to_ascii_uppercase());
+      let artist_album_ids: Option<HashSet<i32>> = artist_id.map(|artist_id| albums.albums_of_artist(artist_id).map(|album| album.id).collect());
+      let albums_to_list = albums.sorted_by_sort_name().into_iter()
+        .filter(|(album, _)| initial.map_or(true, |c| alphabet_initial(&album.sort_name) == c))
+        .filter(|(album, _)| artist_album_ids.as_ref().map_or(true, |album_ids| album_ids.contains(&album.id)));
+      for (album, album_artists) in albums_to_list {
+        println!("{:?} ({:?})", album, albums.availability(album.id));
         for artist in album_artists {
           println!("- {:?}", artist);
         }
       }
     }
+    Command::ShowAlbumAlphabetIndex => {
+      print_alphabet_index(&player.get_client().get_album_alphabet_index().await?, localizer);
+    }
     Command::ShowAlbumById { id } => {
       let album = player.get_client().get_album_by_id(id).await?;
       println!("{:?}", album);
     }
+    Command::SetAlbumArtists { id, artist_ids } => {
+      println!("{:?}", player.get_client().set_album_artists(id, &artist_ids).await?);
+    }
+    Command::SplitAlbum { id, name, track_ids, artist_ids } => {
+      let split = AlbumSplit { name, track_ids, artist_ids };
+      println!("{:?}", player.get_client().split_album(id, &split).await?);
+    }
+    Command::SetAlbumSortName { id, sort_name } => {
+      println!("{:?}", player.get_client().set_album_sort_name(id, &SortNameOverride { sort_name }).await?);
+    }
+    Command::SetAlbumCover { id, path, embed } => {
+      let mime_type = match std::path::Path::new(&path).extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+      };
+      let data = std::fs::read(&path).with_context(|| localizer.text_with("cli-read-cover-failed", &path_args(&path)))?;
+      println!("{:?}", player.get_client().set_album_cover(id, mime_type, data, &AlbumCoverUpload { embed }).await?);
+    }
+    Command::FetchAlbumCover { id, release_id, embed } => {
+      println!("{:?}", player.get_client().fetch_album_cover(id, &AlbumCoverFetch { release_id, embed }).await?);
+    }
 
-    Command::ListTracks => {
+    Command::ListTracks { album_id, artist_id } => {
       let tracks_raw = player.get_client().list_tracks().await?;
       let tracks: Tracks = tracks_raw.into();
-      for info in tracks.iter() {
-        println!("- {:?}", info.track);
+      let infos: Vec<_> = match (album_id, artist_id) {
+        (Some(album_id), artist_id) => tracks.tracks_of_album(album_id)
+          .filter(|info| artist_id.map_or(true, |artist_id| info.track_artists().any(|artist| artist.id == artist_id)))
+          .collect(),
+        (None, Some(artist_id)) => tracks.tracks_of_artist(artist_id).collect(),
+        (None, None) => tracks.sorted_by_album(),
+      };
+      for info in infos {
+        println!("- {:?} ({:?})", info.track, info.availability());
         for artist in info.track_artists() {
           println!("  * {:?}", artist);
         }
+        for (role, artist) in info.track_credits() {
+          println!("  * {:?} ({})", artist, role);
+        }
         println!("  * {:?}", info.album());
         for artist in info.album_artists() {
           println!("    - {:?}", artist);
         }
       }
     }
-    Command::ShowTrackById { id } => {
+    Command::ListRandomTracks { count, album_id } => {
+      for track in player.get_client().sample_random_tracks(count, &TrackFilter { album_id, ..TrackFilter::default() }).await?.tracks {
+        println!("{}", track);
+      }
+    }
+    Command::ListTracksToPlay { album_id, artist_id, shuffle, downrank_skipped } => {
+      for track in player.get_client().list_tracks_to_play(&PlayTracksQuery { album_id, artist_id, shuffle, downrank_skipped }).await?.tracks {
+        println!("{}", track);
+      }
+    }
+    Command::RecommendTracks { seed_track_ids, count, downrank_skipped } => {
+      let request = TrackRecommendationRequest { seed_track_ids, exclude_track_ids: Vec::new(), count, downrank_skipped };
+      for track in player.get_client().recommend_tracks(&request).await?.tracks {
+        println!("{}", track);
+      }
+    }
+    Command::ReportSkip { id, position_relative } => {
+      if player.get_client().report_skip(id, position_relative).await? {
+        println!("{}", localizer.text("cli-skip-recorded"));
+      } else {
+        println!("{}", localizer.text("cli-skip-not-recorded"));
+      }
+    }
+    Command::ShowTrackById { id, technical: false } => {
       let track = player.get_client().get_track_by_id(id).await?;
       println!("{:?}", track);
     }
+    Command::ShowTrackById { id, technical: true } => {
+      match player.get_client().get_track_technical_info(id).await? {
+        Some(technical_info) => print_track_technical_info(&technical_info, localizer),
+        None => println!("{:?}", None::<TrackTechnicalInfo>),
+      }
+    }
+    Command::MoveTrackToAlbum { id, album_id } => {
+      println!("{:?}", player.get_client().move_track_to_album(id, album_id).await?);
+    }
+    Command::SetTrackArtists { id, artist_ids, role } => {
+      println!("{:?}", player.get_client().set_track_artists(id, role, &artist_ids).await?);
+    }
+    Command::ShowTrackWaveform { id } => {
+      println!("{:?}", player.get_client().get_track_waveform(id).await?);
+    }
+    Command::ShowTrackAudioFeatures { id } => {
+      println!("{:?}", player.get_client().get_track_audio_features(id).await?);
+    }
+    Command::FilterTracksByAudioFeatures { min_bpm, max_bpm, key_pitch_class, key_minor } => {
+      let filter = AudioFeaturesFilter { min_bpm, max_bpm, key_pitch_class, key_minor };
+      for audio_features in player.get_client().filter_tracks_by_audio_features(&filter).await? {
+        println!("{:?} ({})", audio_features, audio_features.key_name().unwrap_or_default());
+      }
+    }
+    Command::AnalyzeAudioFeatures => {
+      player.get_client().analyze_audio_features().await?;
+    }
+    Command::SearchTracks { query, tag, exclude_machine_generated_tags } => {
+      let search = TrackSearch { query, tag, include_machine_generated_tags: !exclude_machine_generated_tags };
+      for track in player.get_client().search_tracks(&search).await? {
+        println!("{}", track);
+      }
+    }
+    Command::UploadTrack { path, file_name } => {
+      let file_name = match file_name {
+        Some(file_name) => file_name,
+        None => std::path::Path::new(&path).file_name()
+          .with_context(|| localizer.text_with("cli-path-without-file-name", &path_args(&path)))?
+          .to_string_lossy()
+          .to_string(),
+      };
+      let data = std::fs::read(&path).with_context(|| localizer.text_with("cli-read-track-file-failed", &path_args(&path)))?;
+      println!("{}", player.get_client().upload_track(&TrackUpload { file_name }, data).await?);
+    }
+    Command::ListTrackVersions { id } => {
+      for TrackVersion { relation, track } in player.get_client().list_track_versions(id).await? {
+        let version = if relation.original_track_id == track.id {
+          "track-version-original"
+        } else {
+          match relation.kind {
+            TrackRelationKind::Remaster => "track-version-remaster",
+            TrackRelationKind::Live => "track-version-live",
+            TrackRelationKind::Remix => "track-version-remix",
+            TrackRelationKind::Other => "track-version-other",
+          }
+        };
+        let mut args = FluentArgs::new();
+        args.set("track", track.to_string());
+        args.set("version", localizer.text(version));
+        let text = if relation.machine_generated { "cli-track-version-machine-generated" } else { "cli-track-version" };
+        println!("{}", localizer.text_with(text, &args));
+      }
+    }
+    Command::LinkTrackVersion { original_id, version_id, kind } => {
+      let new_relation = NewTrackRelation { original_track_id: original_id, version_track_id: version_id, kind };
+      println!("{:?}", player.get_client().add_track_relation(&new_relation).await?);
+    }
+    Command::UnlinkTrackVersion { original_id, version_id } => {
+      player.get_client().remove_track_relation(original_id, version_id).await?;
+    }
+    Command::ListComposers => {
+      for ComposerInfo { artist, work_count } in player.get_client().list_composers().await? {
+        let mut args = FluentArgs::new();
+        args.set("name", format!("{:?}", artist));
+        args.set("count", work_count);
+        println!("{}", localizer.text_with("classical-composer", &args));
+      }
+    }
+    Command::ListComposerWorks { id } => {
+      for WorkInfo { work, track_count } in player.get_client().list_composer_works(id).await? {
+        let mut args = FluentArgs::new();
+        args.set("name", format!("{:?}", work));
+        args.set("count", track_count);
+        println!("{}", localizer.text_with("classical-work", &args));
+      }
+    }
+    Command::ListWorkTracks { id } => {
+      let tracks: Tracks = player.get_client().list_work_tracks(id).await?.into();
+      for info in tracks.iter() {
+        let movement = info.track.movement.as_deref().unwrap_or(&info.track.title);
+        match info.track.movement_number {
+          Some(movement_number) => println!("{:>3}. {}", movement_number, movement),
+          None => println!("     {}", movement),
+        }
+        let artists = info.track_artists().map(|artist| artist.name.as_str()).collect::<Vec<_>>().join(", ");
+        println!("     {} - {}", artists, info.album().map_or("", |album| album.name.as_str()));
+      }
+    }
+    Command::ListTags => {
+      for tag in player.get_client().list_tags().await? {
+        println!("{:?}", tag);
+      }
+    }
+    Command::ListTrackTags { id } => {
+      for assigned_tag in player.get_client().list_track_tags(id).await? {
+        println!("{:?}", assigned_tag);
+      }
+    }
+    Command::AddTrackTag { id, name } => {
+      println!("{:?}", player.get_client().add_track_tag(id, &name).await?);
+    }
+    Command::RemoveTrackTag { id, name } => {
+      player.get_client().remove_track_tag(id, &name).await?;
+    }
     Command::PlayTrack { id } => {
       player.play_track_by_id(id).await
-        .with_context(|| "Failed to play audio track")?;
+        .with_context(|| localizer.text("cli-play-track-failed"))?;
+    }
+    Command::ShowSignedTrackUrl { id } => {
+      println!("{:?}", player.get_client().get_signed_track_url(id).await?);
+    }
+    Command::ShowPlaybackPreference => {
+      println!("{:?}", player.get_client().get_playback_preference().await?);
+    }
+    Command::SetPlaybackPreference { prefer_streaming, min_local_bitrate } => {
+      let preferred_source = if prefer_streaming { PreferredPlaybackSource::Streaming } else { PreferredPlaybackSource::Local };
+      // User ID is ignored by the server, which always sets the preference of the logged-in user.
+      let preference = PlaybackPreference { user_id: 0, preferred_source, min_local_bitrate };
+      println!("{:?}", player.get_client().set_playback_preference(&preference).await?);
+    }
+    Command::ShowUserPreferences => {
+      println!("{:?}", player.get_client().get_user_preferences().await?);
+    }
+    Command::SetUserPreference { key, value } => {
+      let mut preferences = player.get_client().get_user_preferences().await?;
+      // Leave the preferred playback source unchanged, as it is set with the playback preferences.
+      preferences.preferred_playback_source = None;
+      match key.as_str() {
+        UserPreferences::THEME_KEY => preferences.theme = value,
+        UserPreferences::DEFAULT_VOLUME_KEY => preferences.default_volume = value.map(|v| v.parse()).transpose()?,
+        UserPreferences::LOCALE_KEY => preferences.locale = value,
+        UserPreferences::MONO_DOWNMIX_KEY => preferences.mono_downmix = value.map(|v| v.parse()).transpose()?,
+        UserPreferences::CHANNEL_BALANCE_KEY => preferences.channel_balance = value.map(|v| v.parse()).transpose()?,
+        UserPreferences::CROSSFEED_KEY => preferences.crossfeed = value.map(|v| v.parse()).transpose()?,
+        UserPreferences::PREFERRED_PLAYBACK_SOURCE_KEY => anyhow::bail!(localizer.text("cli-set-preferred-playback-source")),
+        _ => match value {
+          Some(value) => { preferences.other.insert(key, value); }
+          None => { preferences.other.remove(&key); }
+        },
+      }
+      println!("{:?}", player.get_client().set_user_preferences(&preferences).await?);
+    }
+    Command::ListHiddenItems => {
+      for hidden_item in player.get_client().list_hidden_items().await? {
+        println!("{:?}", hidden_item);
+      }
+    }
+    Command::HideItem { kind, id } => {
+      println!("{:?}", player.get_client().hide_item(kind, id).await?);
+    }
+    Command::UnhideItem { kind, id } => {
+      if !player.get_client().unhide_item(kind, id).await? {
+        let mut args = FluentArgs::new();
+        args.set("kind", kind.to_string());
+        args.set("id", id);
+        eprintln!("{}", localizer.text_with("cli-item-not-hidden", &args));
+      }
+    }
+    Command::ShowMyContentFilter => {
+      println!("{:?}", player.get_client().get_my_content_filter().await?);
+    }
+    Command::SetMyContentFilter { filter } => {
+      println!("{:?}", player.get_client().set_my_content_filter(filter).await?);
+    }
+    Command::ShowUserContentFilter { id } => {
+      println!("{:?}", player.get_client().get_user_content_filter(id).await?);
+    }
+    Command::SetUserContentFilter { id, filter, pinned } => {
+      let content_filter = UserContentFilter { user_id: id, filter, pinned };
+      println!("{:?}", player.get_client().set_user_content_filter(&content_filter).await?);
     }
 
-    Command::ListArtists => {
+    Command::ListArtists { initial } => {
+      let initial = initial.map(|c| c.to_ascii_uppercase());
       for artist in player.get_client().list_artists().await? {
-        println!("{:?}", artist);
+        if initial.map_or(true, |c| alphabet_initial(&artist.sort_name) == c) {
+          println!("{:?}", artist);
+        }
       }
     }
+    Command::ShowArtistAlphabetIndex => {
+      print_alphabet_index(&player.get_client().get_artist_alphabet_index().await?, localizer);
+    }
     Command::ShowArtistById { id } => {
       let artist = player.get_client().get_artist_by_id(id).await?;
       println!("{:?}", artist);
     }
+    Command::SetArtistSortName { id, sort_name } => {
+      println!("{:?}", player.get_client().set_artist_sort_name(id, &SortNameOverride { sort_name }).await?);
+    }
 
     Command::ListUsers => {
       for user in player.get_client().list_users().await? {
@@ -292,6 +1574,51 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
       let user = player.get_client().get_my_user().await?;
       println!("{:?}", user);
     }
+    Command::MyYearReview { year, html: true } => {
+      match player.get_client().get_my_year_review_html(year, YearReviewQuery::local()).await? {
+        Some(html) => print!("{}", html),
+        None => eprintln!("{}", localizer.text_with("cli-year-out-of-range", &year_args(year))),
+      }
+    }
+    Command::MyYearReview { year, html: false } => {
+      let review = match player.get_client().get_my_year_review(year, YearReviewQuery::local()).await? {
+        Some(review) => review,
+        None => {
+          eprintln!("{}", localizer.text_with("cli-year-out-of-range", &year_args(year)));
+          return Ok(());
+        }
+      };
+      let mut args = FluentArgs::new();
+      args.set("plays", review.play_count);
+      args.set("minutes", review.minutes_listened);
+      println!("{}", localizer.text_with("cli-year-review-summary", &args));
+      print_play_counts(localizer, "stats-top-artists", "cli-play-count-entry", review.top_artists.into_iter().map(|PlayCount { item, play_count }| (item.name, play_count)));
+      print_play_counts(localizer, "stats-top-albums", "cli-play-count-entry", review.top_albums.into_iter().map(|PlayCount { item, play_count }| (item.name, play_count)));
+      print_play_counts(localizer, "stats-top-tracks", "cli-play-count-entry", review.top_tracks.into_iter().map(|PlayCount { item, play_count }| (item.title, play_count)));
+      print_play_counts(localizer, "stats-most-skipped-tracks", "cli-skip-count-entry", review.most_skipped_tracks.into_iter().map(|PlayCount { item, play_count }| (item.title, play_count)));
+      print_play_counts(localizer, "cli-new-discoveries", "cli-play-count-entry", review.new_discoveries.into_iter().map(|PlayCount { item, play_count }| (item.name, play_count)));
+    }
+    Command::MyStats { period } => {
+      let stats = player.get_client().get_my_stats(UserStatsQuery::local(period)).await?;
+      let mut args = FluentArgs::new();
+      args.set("plays", stats.play_count);
+      args.set("hours", stats.listening_seconds / 3600);
+      args.set("minutes", stats.listening_seconds % 3600 / 60);
+      args.set("skips", stats.skip_count);
+      println!("{}", localizer.text_with("stats-summary", &args));
+      let mut args = FluentArgs::new();
+      args.set("current", stats.current_streak_days);
+      args.set("longest", stats.longest_streak_days);
+      println!("{}", localizer.text_with("stats-streaks", &args));
+      print_play_counts(localizer, "stats-top-artists", "cli-play-count-entry", stats.top_artists.into_iter().map(|PlayCount { item, play_count }| (item.name, play_count)));
+      print_play_counts(localizer, "stats-top-albums", "cli-play-count-entry", stats.top_albums.into_iter().map(|PlayCount { item, play_count }| (item.name, play_count)));
+      print_play_counts(localizer, "stats-top-tracks", "cli-play-count-entry", stats.top_tracks.into_iter().map(|PlayCount { item, play_count }| (item.title, play_count)));
+      print_play_counts(localizer, "stats-most-skipped-tracks", "cli-skip-count-entry", stats.most_skipped_tracks.into_iter().map(|PlayCount { item, play_count }| (item.title, play_count)));
+      println!("{}:", localizer.text("stats-hours"));
+      for (hour, plays) in stats.hour_histogram.iter().enumerate() {
+        println!("  {:02}:00 {}", hour, plays);
+      }
+    }
     Command::ShowUserById { id } => {
       let user = player.get_client().get_user_by_id(id).await?;
       println!("{:?}", user);
@@ -306,6 +1633,18 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
     Command::DeleteUserById { id } => {
       player.get_client().delete_user_by_id(id).await?;
     }
+    Command::CreateUserInvite { valid_for_hours } => {
+      let user_invite = player.get_client().create_user_invite(&NewUserInviteProperties { valid_for_hours }).await?;
+      println!("{:?}", user_invite);
+    }
+    Command::ListUserInvites => {
+      for user_invite in player.get_client().list_user_invites().await? {
+        println!("{:?}", user_invite);
+      }
+    }
+    Command::Register { .. } => {
+      unreachable!("registration is run instead of logging in");
+    }
 
     Command::SetUserAlbumRating { album_id, rating } => {
       let rating = player.get_client().set_user_album_rating(album_id, rating).await?;
@@ -320,8 +1659,193 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
       println!("{:?}", rating);
     }
 
-    Command::ShowSyncStatus => {
-      let status = player.get_client().get_sync_status().await?;
+    Command::ListPlaylists => {
+      for playlist in player.get_client().list_playlists().await? {
+        println!("{:?}", playlist);
+      }
+    }
+    Command::ListPlaylistMixes => {
+      for mix in player.get_client().list_playlist_mixes().await? {
+        println!("{:?}", mix);
+      }
+    }
+    Command::ShowPlaylistById { id } => {
+      println!("{:?}", player.get_client().get_playlist_by_id(id).await?);
+    }
+    Command::CreatePlaylist { name, public } => {
+      println!("{:?}", player.get_client().create_playlist(&PlaylistProperties { name, public }).await?);
+    }
+    Command::UpdatePlaylist { id, name, public } => {
+      println!("{:?}", player.get_client().update_playlist(id, &PlaylistProperties { name, public }).await?);
+    }
+    Command::DeletePlaylist { id } => {
+      player.get_client().delete_playlist(id).await?;
+    }
+    Command::AddPlaylistTrack { id, track_id } => {
+      println!("{:?}", player.get_client().add_playlist_track(id, track_id).await?);
+    }
+    Command::RemovePlaylistTrack { id, playlist_track_id } => {
+      player.get_client().remove_playlist_track(id, playlist_track_id).await?;
+    }
+    Command::AddPlaylistCollaborator { id, user_id } => {
+      println!("{:?}", player.get_client().add_playlist_collaborator(id, user_id).await?);
+    }
+    Command::RemovePlaylistCollaborator { id, user_id } => {
+      player.get_client().remove_playlist_collaborator(id, user_id).await?;
+    }
+
+    Command::ListListeningSessions => {
+      for listening_session in player.get_client().list_listening_sessions().await? {
+        println!("{:?}", listening_session);
+      }
+    }
+    Command::ShowListeningSessionById { id } => {
+      println!("{:?}", player.get_client().get_listening_session_by_id(id).await?);
+    }
+    Command::CreateListeningSession { drift_tolerance_ms } => {
+      println!("{:?}", player.get_client().create_listening_session(&NewListeningSession { drift_tolerance_ms }).await?);
+    }
+    Command::JoinListeningSession { id } => {
+      println!("{:?}", player.get_client().join_listening_session(id).await?);
+    }
+    Command::LeaveListeningSession { id } => {
+      player.get_client().leave_listening_session(id).await?;
+    }
+    Command::PlayListeningSession { id } => {
+      println!("{:?}", player.get_client().command_listening_session(id, &ListeningSessionCommand::Play).await?);
+    }
+    Command::PauseListeningSession { id } => {
+      println!("{:?}", player.get_client().command_listening_session(id, &ListeningSessionCommand::Pause).await?);
+    }
+    Command::SeekListeningSession { id, position_ms } => {
+      println!("{:?}", player.get_client().command_listening_session(id, &ListeningSessionCommand::Seek { position_ms }).await?);
+    }
+    Command::ChangeListeningSessionTrack { id, track_id } => {
+      println!("{:?}", player.get_client().command_listening_session(id, &ListeningSessionCommand::ChangeTrack { track_id }).await?);
+    }
+
+    Command::ShowPartyById { id } => {
+      println!("{:?}", player.get_client().get_party_by_id(id).await?);
+    }
+    Command::CreateParty { max_guest_requests_per_minute } => {
+      println!("{:?}", player.get_client().create_party(&NewParty { max_guest_requests_per_minute }).await?);
+    }
+    Command::EndParty { id } => {
+      player.get_client().end_party(id).await?;
+    }
+    Command::InvitePartyGuest { id, name, valid_for_secs } => {
+      println!("{:?}", player.get_client().invite_party_guest(id, &NewPartyGuest { name, valid_for_secs }).await?);
+    }
+    Command::DequeuePartyTrack { id } => {
+      println!("{:?}", player.get_client().dequeue_party_track(id).await?);
+    }
+
+    Command::ListMissingAlbums => {
+      let albums_raw = player.get_client().list_missing_albums().await?;
+      let albums: Albums = albums_raw.into();
+      for (album, album_artists) in albums.iter() {
+        println!("{:?}", album);
+        for artist in album_artists {
+          println!("- {:?}", artist);
+        }
+      }
+    }
+    Command::ShowLibrarySummary => {
+      println!("{:?}", player.get_client().get_library_summary().await?);
+    }
+
+    Command::ListNewReleases => {
+      for new_release in player.get_client().list_new_releases().await? {
+        println!("{:?}", new_release);
+      }
+    }
+
+    Command::PollEvents { since, wait } => {
+      println!("{:?}", player.get_client().poll_events(since, wait.map(Duration::from_secs)).await?);
+    }
+    Command::ListNotifications { unread_only } => {
+      for notification in player.get_client().list_notifications(unread_only).await? {
+        println!("{:?}", notification);
+      }
+    }
+    Command::MarkNotificationRead { id } => {
+      println!("{:?}", player.get_client().mark_notification_read(id).await?);
+    }
+    Command::MarkAllNotificationsRead => {
+      println!("{:?}", player.get_client().mark_all_notifications_read().await?);
+    }
+    Command::ShowNotificationPreference => {
+      println!("{:?}", player.get_client().get_notification_preference().await?);
+    }
+    Command::SetNotificationPreference { new_releases, sync_failures, shared_playlist_changes } => {
+      // User ID is ignored by the server, which always sets the preference of the logged-in user.
+      let preference = NotificationPreference { user_id: 0, new_releases, sync_failures, shared_playlist_changes };
+      println!("{:?}", player.get_client().set_notification_preference(&preference).await?);
+    }
+
+    Command::ShowMaintenanceStatus => {
+      println!("{:?}", player.get_client().get_maintenance_status().await?);
+    }
+    Command::SetMaintenanceStatus { enabled, reason } => {
+      println!("{:?}", player.get_client().set_maintenance_status(&MaintenanceStatus { enabled, reason }).await?);
+    }
+    Command::ShowMediaCacheStatus => {
+      println!("{:?}", player.get_client().get_media_cache_status().await?);
+    }
+    Command::ListTrackIssues => {
+      for issue in player.get_client().list_track_issues().await? {
+        println!("{:?}", issue);
+      }
+    }
+    Command::ValidateLocalTracks => {
+      player.get_client().validate_local_tracks().await?;
+    }
+
+    Command::ListUndoLog => {
+      for entry in player.get_client().list_undo_log().await? {
+        println!("{:?}", entry);
+      }
+    }
+    Command::Undo { id } => {
+      println!("{:?}", player.get_client().undo(id).await?);
+    }
+
+    Command::ListMetadataMergePolicies => {
+      for setting in player.get_client().list_metadata_merge_policies().await? {
+        println!("{:?}", setting);
+      }
+    }
+    Command::SetMetadataMergePolicy { field, policy } => {
+      println!("{:?}", player.get_client().set_metadata_merge_policy(&MetadataMergePolicySetting { field, policy }).await?);
+    }
+    Command::RemoveMetadataMergePolicy { field } => {
+      println!("{:?}", player.get_client().remove_metadata_merge_policy(field).await?);
+    }
+    Command::ListMetadataConflicts => {
+      for conflict in player.get_client().list_metadata_conflicts().await? {
+        println!("{:?}", conflict);
+      }
+    }
+    Command::ResolveMetadataConflict { id, value } => {
+      println!("{:?}", player.get_client().resolve_metadata_conflict(id, &MetadataConflictResolution { value }).await?);
+    }
+    Command::DismissMetadataConflict { id } => {
+      println!("{:?}", player.get_client().dismiss_metadata_conflict(id).await?);
+    }
+    Command::ListSortArticles => {
+      for article in player.get_client().list_sort_articles().await? {
+        println!("{}", article);
+      }
+    }
+    Command::SetSortArticles { articles } => {
+      println!("{:?}", player.get_client().set_sort_articles(&articles).await?);
+    }
+
+    Command::ShowSyncStatus { wait } => {
+      let status = match wait {
+        Some(wait) => player.get_client().wait_for_sync_status(Duration::from_secs(wait)).await?,
+        None => player.get_client().get_sync_status().await?,
+      };
       println!("{}", status);
     }
     Command::SyncAllSources => {
@@ -344,6 +1868,43 @@ async fn run(command: Command, player: &mut impl Player) -> Result<()> {
       let status = player.get_client().sync_spotify_source(spotify_source_id).await?;
       println!("{}", status);
     }
+    Command::PreviewSync => {
+      println!("{:?}", player.get_client().preview_sync().await?);
+    }
+    Command::ShowSyncLock => {
+      println!("{:?}", player.get_client().show_sync_lock().await?);
+    }
+    Command::ForceReleaseSyncLock => {
+      println!("{:?}", player.get_client().force_release_sync_lock().await?);
+    }
+    Command::SyncHistory => {
+      for sync_run in player.get_client().list_sync_history().await? {
+        println!("{:?}", sync_run);
+      }
+    }
+
+    Command::ListWebhooks => {
+      for webhook in player.get_client().list_webhooks().await? {
+        println!("{:?}", webhook);
+      }
+    }
+    Command::CreateWebhook { url, secret, on_sync_completed, on_new_albums, on_errors } => {
+      let new_webhook = NewWebhook { url, secret, on_sync_completed, on_new_albums, on_errors };
+      println!("{:?}", player.get_client().create_webhook(&new_webhook).await?);
+    }
+    Command::SetWebhookEnabledById { id, enabled } => {
+      println!("{:?}", player.get_client().set_webhook_enabled_by_id(id, enabled).await?);
+    }
+    Command::DeleteWebhookById { id } => {
+      println!("{:?}", player.get_client().delete_webhook_by_id(id).await?);
+    }
+
+    Command::Remote { .. } => {
+      unreachable!("remote commands are sent instead of logging in");
+    }
+    Command::Cache { .. } | Command::SaveSettings | Command::ListProfiles | Command::SwitchProfile { .. } | Command::RemoveProfile { .. } => {
+      unreachable!("client caches and settings are managed instead of logging in");
+    }
   }
   Ok(())
 }
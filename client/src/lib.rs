@@ -1,89 +1,362 @@
 use std::fmt::Debug;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
 use musium_core::{
-  api::SpotifyMeInfo,
+  api::{SpotifyMeInfo, SpotifyPlaylistAddResult},
   model::{
+    Album,
     Artist,
     collection::{
       AlbumsRaw,
+      PlaylistRaw,
       TracksRaw,
     },
+    ContentFilter,
+    HiddenItem,
+    HiddenItemKind,
     LocalAlbum,
     LocalSource,
     LocalTrack,
+    MetadataConflict,
+    MetadataField,
+    MetadataMergePolicySetting,
     NewLocalSource,
+    NewRelease,
     NewUser,
+    NewWebhook,
+    Notification,
+    NotificationPreference,
+    PlaybackPreference,
+    Playlist,
+    PlaylistCollaborator,
+    PlaylistMix,
+    PlaylistTrack,
+    SyncLock,
+    SyncRun,
+    Tag,
+    Track,
+    TrackArtistRole,
+    TrackAudioFeatures,
+    TrackIssue,
+    TrackRelation,
+    TrackTag,
+    TrackWaveform,
+    UndoLogEntry,
     User,
     UserAlbumRating,
     UserArtistRating,
+    UserContentFilter,
+    UserInvite,
     UserLogin,
     UserTrackRating,
   },
 };
-use musium_core::api::{PlaySource, PlaySourceKind, SyncStatus};
+use musium_core::api::{AlbumCoverFetch, AlbumCoverResult, AlbumCoverUpload, AlbumSplit, AlphabetSection, ArtistDetail, Artwork, AssignedTag, AudioCodec, AudioFeaturesFilter, Capabilities, ComposerInfo, KioskLogin, EventsPoll, HiddenItemInfo, LibrarySummary, ListeningSession, ListeningSessionCommand, LocalSourceRelocation, LocalSourceRelocationResult, Lyrics, MaintenanceStatus, MediaCacheStatus, MetadataConflictResolution, NewListeningSession, NewParty, NewPartyGuest, NewTrackRelation, NewUserInviteProperties, PageQuery, Party, PartyGuest, PartyQueueItem, PlaylistProperties, PlaySource, PlayTracksQuery, PlaySourceKind, ReplayGain, ServerInfo, SignedTrackUrl, SkipReport, SortNameOverride, SpotifySourceInfo, SyncPreview, SyncStatus, TrackFilter, TrackRatings, TrackRecommendationRequest, TrackSearch, TrackSort, TracksPage, TrackTechnicalInfo, TrackUpload, TrackVersion, UserPreferences, UserRegistration, UserStats, UserStatsQuery, WebhookInfo, WorkInfo, YearReview, YearReviewQuery};
 use musium_core::error::SyncError;
-use musium_core::model::SpotifySource;
 
-#[async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 pub trait Client: 'static + Send + Sync + Clone + Debug {
+  type ServerInfoError: SyncError;
+  async fn get_server_info(&self) -> Result<ServerInfo, Self::ServerInfoError>;
+  async fn get_capabilities(&self) -> Result<Capabilities, Self::ServerInfoError>;
+
+
   type LoginError: SyncError;
   async fn login(&self, user_login: &UserLogin) -> Result<User, Self::LoginError>;
+  /// Creates the first user of a new server and logs in as that user. Returns `None` if the server has already been
+  /// set up.
+  async fn setup(&self, new_user: &NewUser) -> Result<Option<User>, Self::LoginError>;
+  /// Registers a new user by redeeming an invite code, and logs in as that user.
+  async fn register(&self, registration: &UserRegistration) -> Result<User, Self::LoginError>;
+  /// Logs in as the kiosk user of the server with its kiosk token. Kiosk logins can browse and play, but not modify
+  /// data.
+  async fn kiosk_login(&self, kiosk_login: &KioskLogin) -> Result<User, Self::LoginError>;
 
 
   type LocalSourceError: SyncError;
   async fn list_local_sources(&self) -> Result<Vec<LocalSource>, Self::LocalSourceError>;
+  async fn list_deleted_local_sources(&self) -> Result<Vec<LocalSource>, Self::LocalSourceError>;
   async fn get_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError>;
   async fn create_or_enable_local_source(&self, new_local_source: &NewLocalSource) -> Result<LocalSource, Self::LocalSourceError>;
   async fn set_local_source_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<LocalSource>, Self::LocalSourceError>;
+  async fn set_local_source_skip_if_unavailable_by_id(&self, id: i32, skip_if_unavailable: bool) -> Result<Option<LocalSource>, Self::LocalSourceError>;
+  async fn delete_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError>;
+  async fn restore_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError>;
+  async fn relocate_local_source_by_id(&self, id: i32, relocation: &LocalSourceRelocation) -> Result<Option<LocalSourceRelocationResult>, Self::LocalSourceError>;
+  /// Moves the files of the managed local source to the paths given by the managed library template of the server.
+  /// Returns the number of files that were moved.
+  async fn organize_managed_library(&self) -> Result<usize, Self::LocalSourceError>;
 
   type SpotifySourceError: SyncError;
-  async fn list_spotify_sources(&self) -> Result<Vec<SpotifySource>, Self::SpotifySourceError>;
-  async fn get_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySource>, Self::SpotifySourceError>;
+  async fn list_spotify_sources(&self) -> Result<Vec<SpotifySourceInfo>, Self::SpotifySourceError>;
+  async fn list_deleted_spotify_sources(&self) -> Result<Vec<SpotifySourceInfo>, Self::SpotifySourceError>;
+  async fn get_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError>;
   async fn create_spotify_source_authorization_url(&self) -> Result<String, Self::SpotifySourceError>;
-  async fn set_spotify_source_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<SpotifySource>, Self::SpotifySourceError>;
+  async fn set_spotify_source_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError>;
+  async fn delete_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError>;
+  async fn restore_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError>;
   async fn show_spotify_me(&self) -> Result<SpotifyMeInfo, Self::SpotifySourceError>;
+  /// Saves the album with `album_id` to the Spotify library of the logged-in user. Returns `false` if the album is not
+  /// available on Spotify. Fails if the server does not support `spotify_write_back`.
+  async fn save_spotify_album(&self, album_id: i32) -> Result<bool, Self::SpotifySourceError>;
+  /// Removes the album with `album_id` from the Spotify library of the logged-in user. Returns `false` if the album is
+  /// not available on Spotify.
+  async fn remove_saved_spotify_album(&self, album_id: i32) -> Result<bool, Self::SpotifySourceError>;
+  /// Follows the artist with `artist_id` on Spotify for the logged-in user. Returns `false` if the artist is not
+  /// available on Spotify.
+  async fn follow_spotify_artist(&self, artist_id: i32) -> Result<bool, Self::SpotifySourceError>;
+  /// Appends the tracks with `track_ids` to the Spotify playlist with `spotify_playlist_id` of the logged-in user.
+  async fn add_tracks_to_spotify_playlist(&self, spotify_playlist_id: &str, track_ids: &[i32]) -> Result<SpotifyPlaylistAddResult, Self::SpotifySourceError>;
 
 
   type AlbumError: SyncError;
   async fn list_albums(&self) -> Result<AlbumsRaw, Self::AlbumError>;
+  /// Gets the alphabet index of the albums in the order of `list_albums`, for jumping to albums by initial.
+  async fn get_album_alphabet_index(&self) -> Result<Vec<AlphabetSection>, Self::AlbumError>;
   async fn get_album_by_id(&self, id: i32) -> Result<Option<LocalAlbum>, Self::AlbumError>;
+  async fn set_album_artists(&self, id: i32, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, Self::AlbumError>;
+  async fn split_album(&self, id: i32, split: &AlbumSplit) -> Result<Option<Album>, Self::AlbumError>;
+  async fn set_album_sort_name(&self, id: i32, sort_name_override: &SortNameOverride) -> Result<Option<Album>, Self::AlbumError>;
+  async fn set_album_cover(&self, id: i32, mime_type: &str, data: Vec<u8>, upload: &AlbumCoverUpload) -> Result<Option<AlbumCoverResult>, Self::AlbumError>;
+  async fn fetch_album_cover(&self, id: i32, fetch: &AlbumCoverFetch) -> Result<Option<AlbumCoverResult>, Self::AlbumError>;
+  async fn get_album_cover(&self, id: i32, size: Option<u32>) -> Result<Option<Artwork>, Self::AlbumError>;
 
   type TrackError: SyncError;
   async fn list_tracks(&self) -> Result<TracksRaw, Self::TrackError>;
+  async fn list_tracks_page(&self, page: &PageQuery, sort: &TrackSort, filter: &TrackFilter) -> Result<TracksPage, Self::TrackError>;
+  /// Lists tracks like `list_tracks_page`, but sends `filter` in the body of the request instead of in its URL.
+  async fn query_tracks_page(&self, page: &PageQuery, sort: &TrackSort, filter: &TrackFilter) -> Result<TracksPage, Self::TrackError>;
+  /// Samples at most `count` random tracks that match `filter`, in random order.
+  async fn sample_random_tracks(&self, count: Option<i64>, filter: &TrackFilter) -> Result<TracksRaw, Self::TrackError>;
+  /// Lists the tracks to play that match `query`, in play order.
+  async fn list_tracks_to_play(&self, query: &PlayTracksQuery) -> Result<TracksRaw, Self::TrackError>;
+  /// Recommends tracks similar to the seed tracks of `request`, in order of descending similarity.
+  async fn recommend_tracks(&self, request: &TrackRecommendationRequest) -> Result<TracksRaw, Self::TrackError>;
+  /// Reports that playback of the track with `id` was stopped at `position_relative` to play another track, which
+  /// counts as skipping it when before `SKIP_THRESHOLD`. Returns whether a skip was recorded.
+  async fn report_skip(&self, id: i32, position_relative: f64) -> Result<bool, Self::TrackError>;
   async fn get_track_by_id(&self, id: i32) -> Result<Option<LocalTrack>, Self::TrackError>;
+  /// Gets technical details of the track with `id` and its files, or `None` if the track does not exist.
+  async fn get_track_technical_info(&self, id: i32) -> Result<Option<TrackTechnicalInfo>, Self::TrackError>;
+  async fn get_track_waveform(&self, id: i32) -> Result<Option<TrackWaveform>, Self::TrackError>;
+  async fn get_track_lyrics(&self, id: i32) -> Result<Option<Lyrics>, Self::TrackError>;
+  async fn get_track_audio_features(&self, id: i32) -> Result<Option<TrackAudioFeatures>, Self::TrackError>;
+  async fn filter_tracks_by_audio_features(&self, filter: &AudioFeaturesFilter) -> Result<Vec<TrackAudioFeatures>, Self::TrackError>;
+  async fn analyze_audio_features(&self) -> Result<(), Self::TrackError>;
+  async fn move_track_to_album(&self, id: i32, album_id: i32) -> Result<Option<Track>, Self::TrackError>;
+  /// Sets the artists credited in `role` on the track with `id` to the artists with `artist_ids`, returning the artists,
+  /// or `None` if the track does not exist.
+  async fn set_track_artists(&self, id: i32, role: TrackArtistRole, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, Self::TrackError>;
+  async fn search_tracks(&self, search: &TrackSearch) -> Result<Vec<Track>, Self::TrackError>;
+  /// Uploads audio file `data` into the managed local source of the server under the file name of `upload`, and
+  /// returns the track that was created for it.
+  async fn upload_track(&self, upload: &TrackUpload, data: Vec<u8>) -> Result<Track, Self::TrackError>;
+  /// Lists all links between versions of tracks.
+  async fn list_track_relations(&self) -> Result<Vec<TrackRelation>, Self::TrackError>;
+  /// Lists the other versions of the track with `id`, such as its original track, remasters, live recordings, and
+  /// remixes.
+  async fn list_track_versions(&self, id: i32) -> Result<Vec<TrackVersion>, Self::TrackError>;
+  /// Links a track as a version of an original track, or returns `None` if either track does not exist.
+  async fn add_track_relation(&self, new_relation: &NewTrackRelation) -> Result<Option<TrackRelation>, Self::TrackError>;
+  async fn remove_track_relation(&self, original_track_id: i32, version_track_id: i32) -> Result<(), Self::TrackError>;
+  /// Lists the artists that composed tracks, for browsing classical music by composer and then by work.
+  async fn list_composers(&self) -> Result<Vec<ComposerInfo>, Self::TrackError>;
+  async fn list_composer_works(&self, composer_id: i32) -> Result<Vec<WorkInfo>, Self::TrackError>;
+  /// Lists the tracks of the work with `work_id`, ordered by movement.
+  async fn list_work_tracks(&self, work_id: i32) -> Result<TracksRaw, Self::TrackError>;
+
+  type TagError: SyncError;
+  async fn list_tags(&self) -> Result<Vec<Tag>, Self::TagError>;
+  async fn list_track_tags(&self, track_id: i32) -> Result<Vec<AssignedTag>, Self::TagError>;
+  async fn add_track_tag(&self, track_id: i32, name: &str) -> Result<Option<TrackTag>, Self::TagError>;
+  /// Tags the tracks with `track_ids` with the tag named `name`, skipping tracks that do not exist.
+  async fn add_tracks_tag(&self, track_ids: &[i32], name: &str) -> Result<Vec<TrackTag>, Self::TagError>;
+  async fn remove_track_tag(&self, track_id: i32, name: &str) -> Result<(), Self::TagError>;
 
   type ArtistError: SyncError;
   async fn list_artists(&self) -> Result<Vec<Artist>, Self::ArtistError>;
-  async fn get_artist_by_id(&self, id: i32) -> Result<Option<Artist>, Self::ArtistError>;
+  /// Gets the alphabet index of the artists in the order of `list_artists`, for jumping to artists by initial.
+  async fn get_artist_alphabet_index(&self) -> Result<Vec<AlphabetSection>, Self::ArtistError>;
+  async fn get_artist_by_id(&self, id: i32) -> Result<Option<ArtistDetail>, Self::ArtistError>;
+  async fn set_artist_sort_name(&self, id: i32, sort_name_override: &SortNameOverride) -> Result<Option<Artist>, Self::ArtistError>;
+  async fn get_artist_image(&self, id: i32, size: Option<u32>) -> Result<Option<Artwork>, Self::ArtistError>;
 
 
   type PlaybackError: SyncError;
   async fn get_track_play_source_kind_by_id(&self, id: i32) -> Result<Option<PlaySourceKind>, Self::PlaybackError>;
   async fn play_track_by_id(&self, id: i32) -> Result<Option<PlaySource>, Self::PlaybackError>;
+  type AudioChunks: AudioChunks<Error=Self::PlaybackError>;
+  /// Plays the track with `id` like `play_track_by_id`, but receives its audio data in chunks, so that playback can start
+  /// before the audio data is received entirely.
+  async fn stream_track_by_id(&self, id: i32) -> Result<Option<PlayStream<Self::AudioChunks>>, Self::PlaybackError>;
+  async fn get_signed_track_url(&self, id: i32) -> Result<Option<SignedTrackUrl>, Self::PlaybackError>;
+  async fn get_playback_preference(&self) -> Result<PlaybackPreference, Self::PlaybackError>;
+  async fn set_playback_preference(&self, preference: &PlaybackPreference) -> Result<PlaybackPreference, Self::PlaybackError>;
 
 
   type UserError: SyncError;
   async fn list_users(&self) -> Result<Vec<User>, Self::UserError>;
   async fn get_my_user(&self) -> Result<User, Self::UserError>;
+  async fn get_user_preferences(&self) -> Result<UserPreferences, Self::UserError>;
+  /// Replaces all preferences of the logged-in user, except for the preferred playback source when it is `None`.
+  async fn set_user_preferences(&self, preferences: &UserPreferences) -> Result<UserPreferences, Self::UserError>;
+  /// Gets listening statistics of the logged-in user, computed from their play history.
+  async fn get_my_stats(&self, query: UserStatsQuery) -> Result<UserStats, Self::UserError>;
+  /// Gets the year in review of the logged-in user for `year`, or `None` if `year` is out of range.
+  async fn get_my_year_review(&self, year: i32, query: YearReviewQuery) -> Result<Option<YearReview>, Self::UserError>;
+  /// Gets the year in review of the logged-in user for `year` as a standalone HTML page for sharing, or `None` if `year`
+  /// is out of range.
+  async fn get_my_year_review_html(&self, year: i32, query: YearReviewQuery) -> Result<Option<String>, Self::UserError>;
   async fn get_user_by_id(&self, id: i32) -> Result<Option<User>, Self::UserError>;
   async fn create_user(&self, new_user: &NewUser) -> Result<User, Self::UserError>;
   async fn delete_user_by_name(&self, name: &String) -> Result<(), Self::UserError>;
   async fn delete_user_by_id(&self, id: i32) -> Result<(), Self::UserError>;
+  async fn create_user_invite(&self, properties: &NewUserInviteProperties) -> Result<UserInvite, Self::UserError>;
+  async fn list_user_invites(&self) -> Result<Vec<UserInvite>, Self::UserError>;
+  /// Gets the content filter of the logged-in user.
+  async fn get_my_content_filter(&self) -> Result<UserContentFilter, Self::UserError>;
+  /// Sets the content filter of the logged-in user, which fails when an admin pinned it.
+  async fn set_my_content_filter(&self, filter: ContentFilter) -> Result<UserContentFilter, Self::UserError>;
+  /// Gets the content filter of the user with `user_id`. Only admins may do this.
+  async fn get_user_content_filter(&self, user_id: i32) -> Result<UserContentFilter, Self::UserError>;
+  /// Sets (and possibly pins) the content filter of the user with the user ID in `content_filter`. Only admins may do
+  /// this.
+  async fn set_user_content_filter(&self, content_filter: &UserContentFilter) -> Result<UserContentFilter, Self::UserError>;
 
 
   type UserDataError: SyncError;
   async fn set_user_album_rating(&self, album_id: i32, rating: i32) -> Result<UserAlbumRating, Self::UserDataError>;
   async fn set_user_track_rating(&self, track_id: i32, rating: i32) -> Result<UserTrackRating, Self::UserDataError>;
+  /// Sets the rating of multiple tracks at once, skipping tracks that do not exist.
+  async fn set_user_track_ratings(&self, ratings: &TrackRatings) -> Result<Vec<UserTrackRating>, Self::UserDataError>;
   async fn set_user_artist_rating(&self, artist_id: i32, rating: i32) -> Result<UserArtistRating, Self::UserDataError>;
+  /// Lists the tracks, albums, and artists that the logged-in user hid, most recently hidden first.
+  async fn list_hidden_items(&self) -> Result<Vec<HiddenItemInfo>, Self::UserDataError>;
+  /// Hides the item of `kind` with `item_id`, so that it is skipped when shuffling, in radio, and in default list views.
+  async fn hide_item(&self, kind: HiddenItemKind, item_id: i32) -> Result<HiddenItem, Self::UserDataError>;
+  /// Unhides the item of `kind` with `item_id`. Returns whether the item was hidden.
+  async fn unhide_item(&self, kind: HiddenItemKind, item_id: i32) -> Result<bool, Self::UserDataError>;
+
+
+  type PlaylistError: SyncError;
+  async fn list_playlists(&self) -> Result<Vec<Playlist>, Self::PlaylistError>;
+  async fn get_playlist_by_id(&self, id: i32) -> Result<Option<PlaylistRaw>, Self::PlaylistError>;
+  async fn create_playlist(&self, properties: &PlaylistProperties) -> Result<Playlist, Self::PlaylistError>;
+  async fn update_playlist(&self, id: i32, properties: &PlaylistProperties) -> Result<Option<Playlist>, Self::PlaylistError>;
+  async fn delete_playlist(&self, id: i32) -> Result<(), Self::PlaylistError>;
+  async fn add_playlist_track(&self, id: i32, track_id: i32) -> Result<Option<PlaylistTrack>, Self::PlaylistError>;
+  /// Appends the tracks with `track_ids` to the playlist with `id`, skipping tracks that do not exist.
+  async fn add_playlist_tracks(&self, id: i32, track_ids: &[i32]) -> Result<Option<Vec<PlaylistTrack>>, Self::PlaylistError>;
+  async fn remove_playlist_track(&self, id: i32, playlist_track_id: i32) -> Result<(), Self::PlaylistError>;
+  async fn add_playlist_collaborator(&self, id: i32, user_id: i32) -> Result<Option<PlaylistCollaborator>, Self::PlaylistError>;
+  async fn remove_playlist_collaborator(&self, id: i32, user_id: i32) -> Result<(), Self::PlaylistError>;
+  /// Lists the daily and weekly mixes generated for the logged-in user. Their tracks are available through the playlists
+  /// they refer to.
+  async fn list_playlist_mixes(&self) -> Result<Vec<PlaylistMix>, Self::PlaylistError>;
+
+
+  type ListeningSessionError: SyncError;
+  async fn list_listening_sessions(&self) -> Result<Vec<ListeningSession>, Self::ListeningSessionError>;
+  async fn get_listening_session_by_id(&self, id: i32) -> Result<Option<ListeningSession>, Self::ListeningSessionError>;
+  async fn create_listening_session(&self, new_listening_session: &NewListeningSession) -> Result<ListeningSession, Self::ListeningSessionError>;
+  async fn join_listening_session(&self, id: i32) -> Result<Option<ListeningSession>, Self::ListeningSessionError>;
+  async fn leave_listening_session(&self, id: i32) -> Result<(), Self::ListeningSessionError>;
+  async fn command_listening_session(&self, id: i32, command: &ListeningSessionCommand) -> Result<Option<ListeningSession>, Self::ListeningSessionError>;
+
+
+  type PartyError: SyncError;
+  async fn get_party_by_id(&self, id: i32) -> Result<Option<Party>, Self::PartyError>;
+  async fn create_party(&self, new_party: &NewParty) -> Result<Party, Self::PartyError>;
+  async fn end_party(&self, id: i32) -> Result<(), Self::PartyError>;
+  async fn invite_party_guest(&self, id: i32, new_guest: &NewPartyGuest) -> Result<Option<PartyGuest>, Self::PartyError>;
+  async fn dequeue_party_track(&self, id: i32) -> Result<Option<PartyQueueItem>, Self::PartyError>;
+
+
+  type ReportError: SyncError;
+  async fn list_missing_albums(&self) -> Result<AlbumsRaw, Self::ReportError>;
+  async fn get_library_summary(&self) -> Result<LibrarySummary, Self::ReportError>;
+
+
+  type NotificationError: SyncError;
+  async fn list_new_releases(&self) -> Result<Vec<NewRelease>, Self::NotificationError>;
+  async fn list_notifications(&self, unread_only: bool) -> Result<Vec<Notification>, Self::NotificationError>;
+  async fn mark_notification_read(&self, id: i32) -> Result<Option<Notification>, Self::NotificationError>;
+  async fn mark_all_notifications_read(&self) -> Result<usize, Self::NotificationError>;
+  async fn get_notification_preference(&self) -> Result<NotificationPreference, Self::NotificationError>;
+  async fn set_notification_preference(&self, preference: &NotificationPreference) -> Result<NotificationPreference, Self::NotificationError>;
+
+
+  type EventError: SyncError;
+  /// Gets the events published after the event with sequence number `since`, waiting at most `wait` for an event to be
+  /// published if there are none yet. Without `since`, only gets the sequence number to start polling from.
+  async fn poll_events(&self, since: Option<u64>, wait: Option<Duration>) -> Result<EventsPoll, Self::EventError>;
+
+
+  type MaintenanceError: SyncError;
+  async fn get_maintenance_status(&self) -> Result<MaintenanceStatus, Self::MaintenanceError>;
+  async fn set_maintenance_status(&self, status: &MaintenanceStatus) -> Result<MaintenanceStatus, Self::MaintenanceError>;
+  async fn get_media_cache_status(&self) -> Result<MediaCacheStatus, Self::MaintenanceError>;
+  async fn list_track_issues(&self) -> Result<Vec<TrackIssue>, Self::MaintenanceError>;
+  /// Starts validating the files of local tracks in the background. Found issues are listed by `list_track_issues`.
+  async fn validate_local_tracks(&self) -> Result<(), Self::MaintenanceError>;
+
+
+  type UndoError: SyncError;
+  async fn list_undo_log(&self) -> Result<Vec<UndoLogEntry>, Self::UndoError>;
+  async fn undo(&self, id: i32) -> Result<Option<UndoLogEntry>, Self::UndoError>;
+
+
+  type MetadataError: SyncError;
+  async fn list_metadata_merge_policies(&self) -> Result<Vec<MetadataMergePolicySetting>, Self::MetadataError>;
+  async fn set_metadata_merge_policy(&self, setting: &MetadataMergePolicySetting) -> Result<MetadataMergePolicySetting, Self::MetadataError>;
+  async fn remove_metadata_merge_policy(&self, field: MetadataField) -> Result<Option<MetadataMergePolicySetting>, Self::MetadataError>;
+  async fn list_metadata_conflicts(&self) -> Result<Vec<MetadataConflict>, Self::MetadataError>;
+  async fn resolve_metadata_conflict(&self, id: i32, resolution: &MetadataConflictResolution) -> Result<Option<MetadataConflict>, Self::MetadataError>;
+  async fn dismiss_metadata_conflict(&self, id: i32) -> Result<Option<MetadataConflict>, Self::MetadataError>;
+  async fn list_sort_articles(&self) -> Result<Vec<String>, Self::MetadataError>;
+  async fn set_sort_articles(&self, articles: &[String]) -> Result<Vec<String>, Self::MetadataError>;
 
 
   type SyncError: SyncError;
   async fn get_sync_status(&self) -> Result<SyncStatus, Self::SyncError>;
+  /// Gets the sync status once it changes, or the current sync status once `wait` has passed.
+  async fn wait_for_sync_status(&self, wait: Duration) -> Result<SyncStatus, Self::SyncError>;
   async fn sync_all_sources(&self) -> Result<SyncStatus, Self::SyncError>;
   async fn sync_local_sources(&self) -> Result<SyncStatus, Self::SyncError>;
   async fn sync_local_source(&self, local_source_id: i32) -> Result<SyncStatus, Self::SyncError>;
   async fn sync_spotify_sources(&self) -> Result<SyncStatus, Self::SyncError>;
   async fn sync_spotify_source(&self, spotify_source_id: i32) -> Result<SyncStatus, Self::SyncError>;
+  async fn preview_sync(&self) -> Result<SyncPreview, Self::SyncError>;
+  async fn show_sync_lock(&self) -> Result<Option<SyncLock>, Self::SyncError>;
+  async fn force_release_sync_lock(&self) -> Result<Option<SyncLock>, Self::SyncError>;
+  /// Lists the sync runs of each source of recent synchronizations, most recent first.
+  async fn list_sync_history(&self) -> Result<Vec<SyncRun>, Self::SyncError>;
+
+
+  type WebhookError: SyncError;
+  async fn list_webhooks(&self) -> Result<Vec<WebhookInfo>, Self::WebhookError>;
+  async fn create_webhook(&self, new_webhook: &NewWebhook) -> Result<WebhookInfo, Self::WebhookError>;
+  async fn set_webhook_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<WebhookInfo>, Self::WebhookError>;
+  async fn delete_webhook_by_id(&self, id: i32) -> Result<Option<WebhookInfo>, Self::WebhookError>;
+}
+
+/// Audio data of a track that is received from the server in chunks.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait AudioChunks: 'static + Send {
+  type Error: SyncError;
+  /// Receives the next chunk of audio data, or `None` when all audio data was received.
+  async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Self::Error>;
+}
+
+/// Source to play a track from, like `PlaySource`, but with its audio data received in `chunks`.
+#[derive(Debug)]
+pub enum PlayStream<C> {
+  /// Audio data to play, received in `chunks`, along with its length in bytes if known, the album of the track, and the
+  /// ReplayGain values of the file if it has them.
+  AudioData { codec: Option<AudioCodec>, length: Option<u64>, album_id: Option<i32>, replay_gain: Option<ReplayGain>, chunks: C },
+  ExternallyPlayedOnSpotify,
 }
@@ -0,0 +1,72 @@
+use std::backtrace::Backtrace;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use reqwest::Url;
+use thiserror::Error;
+
+use musium_core::api::{API_PATH_PREFIX, ZEROCONF_SERVICE_TYPE};
+
+/// Server that announced itself on the local network through zeroconf (mDNS-SD).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DiscoveredServer {
+  /// Instance name the server announced itself under, such as `musium`.
+  pub name: String,
+  /// Base URL of the server, suitable for `HttpClient::set_url`.
+  pub url: Url,
+  /// Version of the server, if it announced one.
+  pub version: Option<String>,
+  /// Version of the HTTP API the server serves, if it announced one.
+  pub api_version: Option<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum DiscoverServersError {
+  #[error("Failed to browse the local network through zeroconf")]
+  BrowseFail(#[from] mdns_sd::Error, Backtrace),
+}
+
+/// Discovers servers on the local network by browsing for `ZEROCONF_SERVICE_TYPE` for `timeout`, returning the servers
+/// that were resolved in that time, ordered by name. Blocks the current thread for `timeout`.
+pub fn discover_servers(timeout: Duration) -> Result<Vec<DiscoveredServer>, DiscoverServersError> {
+  let daemon = ServiceDaemon::new()?;
+  let receiver = daemon.browse(ZEROCONF_SERVICE_TYPE)?;
+  let deadline = Instant::now() + timeout;
+  let mut servers: Vec<DiscoveredServer> = Vec::new();
+  loop {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() { break; }
+    let service_info = match receiver.recv_timeout(remaining) {
+      Ok(ServiceEvent::ServiceResolved(service_info)) => service_info,
+      Ok(_) => continue,
+      Err(_) => break, // Timed out or daemon stopped -> done browsing.
+    };
+    // Prefer the lowest address, so that the same server resolves to the same URL each time.
+    let address = match service_info.get_addresses().iter().min() {
+      Some(address) => IpAddr::from(*address),
+      None => continue,
+    };
+    // Servers served under a path prefix announce the API path under that prefix, such as `/musium/api/v1`.
+    let base_path = service_info.get_property_val_str("path")
+      .and_then(|path| path.trim_end_matches('/').strip_suffix(API_PATH_PREFIX))
+      .unwrap_or("/");
+    let url = match Url::parse(&format!("http://{}:{}{}", address, service_info.get_port(), base_path)) {
+      Ok(url) => url,
+      Err(_) => continue,
+    };
+    let name = service_info.get_fullname()
+      .strip_suffix(ZEROCONF_SERVICE_TYPE)
+      .map(|name| name.trim_end_matches('.'))
+      .unwrap_or(service_info.get_fullname())
+      .to_string();
+    let version = service_info.get_property_val_str("version").map(|version| version.to_string());
+    let api_version = service_info.get_property_val_str("api_version").and_then(|api_version| api_version.parse().ok());
+    // Servers may be resolved multiple times, for example when they announce themselves again.
+    servers.retain(|server| server.name != name);
+    servers.push(DiscoveredServer { name, url, version, api_version });
+  }
+  daemon.shutdown().ok(); // OK: browsing is done -> we don't care whether the daemon shut down cleanly.
+  servers.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(servers)
+}
@@ -1,28 +1,39 @@
 #![feature(backtrace)]
 
 use std::backtrace::Backtrace;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use reqwest::{Client as ReqwestHttpClient, header::CONTENT_TYPE, header::ToStrError, Method, redirect, RequestBuilder, Response, StatusCode};
+use reqwest::{Client as ReqwestHttpClient, header::CONTENT_TYPE, header::ETAG, header::HeaderMap, header::IF_NONE_MATCH, header::HeaderValue, header::ToStrError, Method, RequestBuilder, Response, StatusCode};
 pub use reqwest::Url;
 use serde::Serialize;
 use thiserror::Error;
 
-pub use musium_client::Client;
+pub use musium_client::{AudioChunks, Client, PlayStream};
 use musium_core::{
-  api::{InternalServerError, SpotifyMeInfo},
+  api::{InternalServerError, SpotifyMeInfo, SpotifyPlaylistAddResult},
   model::{
     *,
-    collection::{AlbumsRaw, TracksRaw},
+    collection::{AlbumsRaw, PlaylistRaw, TracksRaw},
   },
 };
-use musium_core::api::{AudioCodec, PlaySource, PlaySourceKind, SyncStatus};
+use musium_core::api::{ALBUM_ID_HEADER, AlbumCoverFetch, API_PATH_PREFIX, API_VERSION, API_VERSION_HEADER, AlbumCoverResult, AlbumCoverUpload, AlbumSplit, AlphabetSection, ArtistDetail, Artwork, AssignedTag, AudioCodec, AudioFeaturesFilter, Capabilities, ComposerInfo, KioskLogin, EventsPoll, EventsQuery, HiddenItemInfo, LibrarySummary, ListeningSession, ListeningSessionCommand, LocalSourceRelocation, LocalSourceRelocationResult, Lyrics, MaintenanceStatus, MediaCacheStatus, MetadataConflictResolution, NewListeningSession, NewParty, NewPartyGuest, NewTrackRelation, NewUserInviteProperties, PageQuery, Party, PartyGuest, PartyQueueItem, PlaylistProperties, PlaySource, PlayTracksQuery, PlaySourceKind, RandomTracksQuery, ReplayGain, ServerInfo, SignedTrackUrl, SkipReport, SortNameOverride, SpotifySourceInfo, SyncPreview, SyncStatus, TrackFilter, TrackRatings, TrackRecommendationRequest, TrackArtistsQuery, TrackSearch, TrackSort, TracksPage, TrackTechnicalInfo, TrackUpload, TrackVersion, UserPreferences, UserRegistration, UserStats, UserStatsQuery, WaitQuery, WebhookInfo, WorkInfo, YearReview, YearReviewQuery};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use discover::{discover_servers, DiscoveredServer, DiscoverServersError};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod discover;
 
 #[derive(Clone)]
 pub struct HttpClient {
   client: ReqwestHttpClient,
   url: Url,
+  capabilities: Arc<Mutex<Option<Capabilities>>>,
+  artwork_cache: Arc<Mutex<ArtworkCache>>,
 }
 
 // Creation
@@ -35,15 +46,85 @@ pub enum HttpClientCreateError {
 
 impl HttpClient {
   pub fn new(url: Url) -> Result<Self, HttpClientCreateError> {
-    let client: ReqwestHttpClient = ReqwestHttpClient::builder()
+    let mut headers = HeaderMap::new();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from(API_VERSION));
+    let builder = ReqwestHttpClient::builder()
+      .default_headers(headers);
+    #[cfg(not(target_arch = "wasm32"))] let builder = builder
       .cookie_store(true)
-      .redirect(redirect::Policy::none())
-      .build()?;
-    Ok(Self { client, url })
+      .redirect(reqwest::redirect::Policy::none());
+    let client: ReqwestHttpClient = builder.build()?;
+    Ok(Self { client, url: with_trailing_slash(url), capabilities: Default::default(), artwork_cache: Default::default() })
   }
 
+  #[inline]
+  pub fn url(&self) -> &Url { &self.url }
+
   pub fn set_url(&mut self, url: Url) {
-    self.url = url;
+    self.url = with_trailing_slash(url);
+    // Capabilities and artwork are cached per server.
+    self.capabilities = Default::default();
+    self.artwork_cache = Default::default();
+  }
+}
+
+/// Appends a trailing slash to the path of `url` if it has none, so that joining API paths onto it keeps its path, such
+/// as for servers served under a path prefix like `https://example.com/musium`.
+fn with_trailing_slash(mut url: Url) -> Url {
+  if !url.path().ends_with('/') {
+    let path = format!("{}/", url.path());
+    url.set_path(&path);
+  }
+  url
+}
+
+// Artwork cache
+
+/// Maximum total size in bytes of the album covers and artist images in the artwork cache.
+const ARTWORK_CACHE_SIZE_LIMIT: usize = 64 * 1024 * 1024;
+
+/// In-memory cache of album covers and artist images by URL, along with their ETags, so that unchanged artwork is not
+/// transferred again. Least recently used artwork is evicted when the cache exceeds `ARTWORK_CACHE_SIZE_LIMIT`.
+#[derive(Default)]
+struct ArtworkCache {
+  entries: HashMap<String, (String, Artwork)>,
+  order: VecDeque<String>,
+  size: usize,
+}
+
+impl ArtworkCache {
+  fn get(&mut self, url: &str) -> Option<(String, Artwork)> {
+    let entry = self.entries.get(url)?.clone();
+    self.touch(url);
+    Some(entry)
+  }
+
+  fn insert(&mut self, url: String, etag: String, artwork: Artwork) {
+    self.remove(&url);
+    self.size += artwork.data.len();
+    self.entries.insert(url.clone(), (etag, artwork));
+    self.order.push_back(url);
+    while self.size > ARTWORK_CACHE_SIZE_LIMIT {
+      match self.order.pop_front() {
+        Some(url) => self.remove(&url),
+        None => break,
+      }
+    }
+  }
+
+  fn remove(&mut self, url: &str) {
+    if let Some((_, artwork)) = self.entries.remove(url) {
+      self.size -= artwork.data.len();
+      self.order.retain(|u| u != url);
+    }
+  }
+
+  fn touch(&mut self, url: &str) {
+    if let Some(position) = self.order.iter().position(|u| u == url) {
+      if let Some(url) = self.order.remove(position) {
+        self.order.push_back(url);
+      }
+    }
   }
 }
 
@@ -57,6 +138,10 @@ pub enum HttpRequestError {
   RequestFail(#[from] reqwest::Error),
   #[error("Server responded with an internal error")]
   InternalServerFail(#[from] InternalServerError, Backtrace),
+  #[error("Server does not support the API version of this client: {0}")]
+  UnsupportedApiVersionFail(String, Backtrace),
+  #[error("Server rejected the request with status code {0}: {1}")]
+  RequestRejectedFail(StatusCode, String, Backtrace),
   #[error("Server responded with unexpected status code: {0}")]
   UnexpectedStatusCode(StatusCode, Backtrace),
 }
@@ -85,8 +170,30 @@ pub enum CreateSpotifySourceAuthorizationUrlError {
   LocationHeaderToStringFail(#[from] ToStrError),
 }
 
-#[async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 impl Client for HttpClient {
+  // Server info
+
+  type ServerInfoError = HttpRequestError;
+
+  async fn get_server_info(&self) -> Result<ServerInfo, Self::ServerInfoError> {
+    let response = self.get_simple("info").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_capabilities(&self) -> Result<Capabilities, Self::ServerInfoError> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    if let Some(capabilities) = self.capabilities.lock().unwrap().clone() {
+      return Ok(capabilities);
+    }
+    let response = self.get_simple("capabilities").await?;
+    let capabilities: Capabilities = response.json().await?;
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+    Ok(capabilities)
+  }
+
   // Login
 
   type LoginError = HttpRequestError;
@@ -96,6 +203,22 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn setup(&self, new_user: &NewUser) -> Result<Option<User>, Self::LoginError> {
+    let response = self.post("setup", |r| r.json(new_user), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn register(&self, registration: &UserRegistration) -> Result<User, Self::LoginError> {
+    let response = self.post_simple_with_json("register", registration).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn kiosk_login(&self, kiosk_login: &KioskLogin) -> Result<User, Self::LoginError> {
+    let response = self.post_simple_with_json("kiosk_login", kiosk_login).await?;
+    Ok(response.json().await?)
+  }
+
   // Local source
 
   type LocalSourceError = HttpRequestError;
@@ -105,6 +228,11 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn list_deleted_local_sources(&self) -> Result<Vec<LocalSource>, Self::LocalSourceError> {
+    let response = self.get_simple("source/local/deleted").await?;
+    Ok(response.json().await?)
+  }
+
   async fn get_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError> {
     let response = self.get_simple(format!("source/local/{}", id)).await?;
     Ok(response.json().await?)
@@ -120,20 +248,58 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn set_local_source_skip_if_unavailable_by_id(&self, id: i32, skip_if_unavailable: bool) -> Result<Option<LocalSource>, Self::LocalSourceError> {
+    let response = self.post_simple_with_json(format!("source/local/set_skip_if_unavailable/{}", id), &skip_if_unavailable).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn delete_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError> {
+    let response = self.delete_simple(format!("source/local/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn restore_local_source_by_id(&self, id: i32) -> Result<Option<LocalSource>, Self::LocalSourceError> {
+    let response = self.post_simple(format!("source/local/restore/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn relocate_local_source_by_id(&self, id: i32, relocation: &LocalSourceRelocation) -> Result<Option<LocalSourceRelocationResult>, Self::LocalSourceError> {
+    let response = self.post_simple_with_json(format!("source/local/{}/relocate", id), relocation).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn organize_managed_library(&self) -> Result<usize, Self::LocalSourceError> {
+    let response = self.post("managed_library/organize", |r| r, &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
   // Spotify source
 
   type SpotifySourceError = SpotifySourceError;
 
-  async fn list_spotify_sources(&self) -> Result<Vec<SpotifySource>, Self::SpotifySourceError> {
+  async fn list_spotify_sources(&self) -> Result<Vec<SpotifySourceInfo>, Self::SpotifySourceError> {
     let response = self.get_simple("source/spotify").await?;
     Ok(response.json().await?)
   }
 
-  async fn get_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySource>, Self::SpotifySourceError> {
+  async fn list_deleted_spotify_sources(&self) -> Result<Vec<SpotifySourceInfo>, Self::SpotifySourceError> {
+    let response = self.get_simple("source/spotify/deleted").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError> {
     let response = self.get_simple(format!("source/spotify/{}", id)).await?;
     Ok(response.json().await?)
   }
 
+  #[cfg(target_arch = "wasm32")]
+  async fn create_spotify_source_authorization_url(&self) -> Result<String, Self::SpotifySourceError> {
+    // The browser follows redirects without exposing the redirect location, so instead return the URL of the endpoint
+    // itself, which redirects the browser to the Spotify authorization page when navigated to.
+    Ok(self.url.join(&format!("{}/source/spotify/request_authorization", API_PATH_PREFIX))?.to_string())
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
   async fn create_spotify_source_authorization_url(&self) -> Result<String, Self::SpotifySourceError> {
     use CreateSpotifySourceAuthorizationUrlError::*;
     use SpotifySourceError::*;
@@ -145,16 +311,46 @@ impl Client for HttpClient {
     }
   }
 
-  async fn set_spotify_source_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<SpotifySource>, Self::SpotifySourceError> {
+  async fn set_spotify_source_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError> {
     let response = self.post_simple_with_json(format!("source/spotify/set_enabled/{}", id), &enabled).await?;
     Ok(response.json().await?)
   }
 
+  async fn delete_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError> {
+    let response = self.delete_simple(format!("source/spotify/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn restore_spotify_source_by_id(&self, id: i32) -> Result<Option<SpotifySourceInfo>, Self::SpotifySourceError> {
+    let response = self.post_simple(format!("source/spotify/restore/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
   async fn show_spotify_me(&self) -> Result<SpotifyMeInfo, Self::SpotifySourceError> {
     let response = self.get_simple("source/spotify/me").await?;
     Ok(response.json().await.map_err(|e| HttpRequestError::RequestFail(e))?)
   }
 
+  async fn save_spotify_album(&self, album_id: i32) -> Result<bool, Self::SpotifySourceError> {
+    let response = self.put(format!("album/{}/spotify/saved", album_id), |r| r, &[StatusCode::NO_CONTENT, StatusCode::NOT_FOUND]).await?;
+    Ok(response.status() == StatusCode::NO_CONTENT)
+  }
+
+  async fn remove_saved_spotify_album(&self, album_id: i32) -> Result<bool, Self::SpotifySourceError> {
+    let response = self.delete(format!("album/{}/spotify/saved", album_id), |r| r, &[StatusCode::NO_CONTENT, StatusCode::NOT_FOUND]).await?;
+    Ok(response.status() == StatusCode::NO_CONTENT)
+  }
+
+  async fn follow_spotify_artist(&self, artist_id: i32) -> Result<bool, Self::SpotifySourceError> {
+    let response = self.put(format!("artist/{}/spotify/followed", artist_id), |r| r, &[StatusCode::NO_CONTENT, StatusCode::NOT_FOUND]).await?;
+    Ok(response.status() == StatusCode::NO_CONTENT)
+  }
+
+  async fn add_tracks_to_spotify_playlist(&self, spotify_playlist_id: &str, track_ids: &[i32]) -> Result<SpotifyPlaylistAddResult, Self::SpotifySourceError> {
+    let response = self.post_simple_with_json(format!("spotify/playlist/{}/tracks", spotify_playlist_id), track_ids).await?;
+    Ok(response.json().await?)
+  }
+
   // Album
 
   type AlbumError = HttpRequestError;
@@ -165,11 +361,50 @@ impl Client for HttpClient {
     Ok(albums_raw)
   }
 
+  async fn get_album_alphabet_index(&self) -> Result<Vec<AlphabetSection>, Self::AlbumError> {
+    let response = self.get_simple("album/alphabet_index").await?;
+    Ok(response.json().await?)
+  }
+
   async fn get_album_by_id(&self, id: i32) -> Result<Option<LocalAlbum>, Self::AlbumError> {
     let response = self.get_simple(format!("album/{}", id)).await?;
     Ok(response.json().await?)
   }
 
+  async fn set_album_artists(&self, id: i32, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, Self::AlbumError> {
+    let response = self.put(format!("album/{}/artists", id), |r| r.json(artist_ids), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn split_album(&self, id: i32, split: &AlbumSplit) -> Result<Option<Album>, Self::AlbumError> {
+    let response = self.post(format!("album/{}/split", id), |r| r.json(split), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn set_album_sort_name(&self, id: i32, sort_name_override: &SortNameOverride) -> Result<Option<Album>, Self::AlbumError> {
+    let response = self.put(format!("album/{}/sort_name", id), |r| r.json(sort_name_override), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn set_album_cover(&self, id: i32, mime_type: &str, data: Vec<u8>, upload: &AlbumCoverUpload) -> Result<Option<AlbumCoverResult>, Self::AlbumError> {
+    let response = self.put(format!("album/{}/cover", id), |r| r.query(upload).header(CONTENT_TYPE, mime_type).body(data), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn fetch_album_cover(&self, id: i32, fetch: &AlbumCoverFetch) -> Result<Option<AlbumCoverResult>, Self::AlbumError> {
+    let response = self.post(format!("album/{}/cover/fetch", id), |r| r.json(fetch), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_album_cover(&self, id: i32, size: Option<u32>) -> Result<Option<Artwork>, Self::AlbumError> {
+    self.get_artwork(format!("album/{}/cover", id), size).await
+  }
+
   // Track
 
   type TrackError = HttpRequestError;
@@ -180,11 +415,164 @@ impl Client for HttpClient {
     Ok(tracks_raw)
   }
 
+  async fn list_tracks_page(&self, page: &PageQuery, sort: &TrackSort, filter: &TrackFilter) -> Result<TracksPage, Self::TrackError> {
+    let response = self.get("track/page", |r| r.query(page).query(sort).query(filter), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn query_tracks_page(&self, page: &PageQuery, sort: &TrackSort, filter: &TrackFilter) -> Result<TracksPage, Self::TrackError> {
+    let response = self.post("track/query", |r| r.query(page).query(sort).json(filter), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn sample_random_tracks(&self, count: Option<i64>, filter: &TrackFilter) -> Result<TracksRaw, Self::TrackError> {
+    let response = self.get("track/random", |r| r.query(&RandomTracksQuery { count }).query(filter), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_tracks_to_play(&self, query: &PlayTracksQuery) -> Result<TracksRaw, Self::TrackError> {
+    let response = self.get("track/play", |r| r.query(query), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn recommend_tracks(&self, request: &TrackRecommendationRequest) -> Result<TracksRaw, Self::TrackError> {
+    let response = self.post("track/recommend", |r| r.json(request), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn report_skip(&self, id: i32, position_relative: f64) -> Result<bool, Self::TrackError> {
+    let report = SkipReport { position_relative };
+    let response = self.post(format!("track/{}/skip", id), |r| r.json(&report), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
   async fn get_track_by_id(&self, id: i32) -> Result<Option<LocalTrack>, Self::TrackError> {
     let response = self.get_simple(format!("track/{}", id)).await?;
     Ok(response.json().await?)
   }
 
+  async fn get_track_technical_info(&self, id: i32) -> Result<Option<TrackTechnicalInfo>, Self::TrackError> {
+    let response = self.get(format!("track/{}/technical", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_track_waveform(&self, id: i32) -> Result<Option<TrackWaveform>, Self::TrackError> {
+    let response = self.get(format!("track/{}/waveform", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_track_lyrics(&self, id: i32) -> Result<Option<Lyrics>, Self::TrackError> {
+    let response = self.get(format!("track/{}/lyrics", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_track_audio_features(&self, id: i32) -> Result<Option<TrackAudioFeatures>, Self::TrackError> {
+    let response = self.get_simple(format!("track/{}/audio_features", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn filter_tracks_by_audio_features(&self, filter: &AudioFeaturesFilter) -> Result<Vec<TrackAudioFeatures>, Self::TrackError> {
+    let response = self.get("audio_features", |r| r.query(filter), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn analyze_audio_features(&self) -> Result<(), Self::TrackError> {
+    self.post("audio_features/analyze", |r| r, &[StatusCode::ACCEPTED]).await?;
+    Ok(())
+  }
+
+  async fn move_track_to_album(&self, id: i32, album_id: i32) -> Result<Option<Track>, Self::TrackError> {
+    let response = self.put(format!("track/{}/album/{}", id, album_id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn set_track_artists(&self, id: i32, role: TrackArtistRole, artist_ids: &[i32]) -> Result<Option<Vec<Artist>>, Self::TrackError> {
+    let query = TrackArtistsQuery { role };
+    let response = self.put(format!("track/{}/artists", id), |r| r.query(&query).json(artist_ids), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn search_tracks(&self, search: &TrackSearch) -> Result<Vec<Track>, Self::TrackError> {
+    let response = self.get("search/track", |r| r.query(search), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn upload_track(&self, upload: &TrackUpload, data: Vec<u8>) -> Result<Track, Self::TrackError> {
+    let response = self.post("track/upload", |r| r.query(upload).header(CONTENT_TYPE, "application/octet-stream").body(data), &[StatusCode::CREATED]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_track_relations(&self) -> Result<Vec<TrackRelation>, Self::TrackError> {
+    let response = self.get_simple("track_relation").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_track_versions(&self, id: i32) -> Result<Vec<TrackVersion>, Self::TrackError> {
+    let response = self.get_simple(format!("track/{}/versions", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn add_track_relation(&self, new_relation: &NewTrackRelation) -> Result<Option<TrackRelation>, Self::TrackError> {
+    let response = self.post("track_relation", |r| r.json(new_relation), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn remove_track_relation(&self, original_track_id: i32, version_track_id: i32) -> Result<(), Self::TrackError> {
+    self.delete_simple(format!("track_relation/{}/{}", original_track_id, version_track_id)).await?;
+    Ok(())
+  }
+
+  async fn list_composers(&self) -> Result<Vec<ComposerInfo>, Self::TrackError> {
+    let response = self.get_simple("composer").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_composer_works(&self, composer_id: i32) -> Result<Vec<WorkInfo>, Self::TrackError> {
+    let response = self.get_simple(format!("composer/{}/works", composer_id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_work_tracks(&self, work_id: i32) -> Result<TracksRaw, Self::TrackError> {
+    let response = self.get_simple(format!("work/{}/tracks", work_id)).await?;
+    Ok(response.json().await?)
+  }
+
+  // Tag
+
+  type TagError = HttpRequestError;
+
+  async fn list_tags(&self) -> Result<Vec<Tag>, Self::TagError> {
+    let response = self.get_simple("tag").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_track_tags(&self, track_id: i32) -> Result<Vec<AssignedTag>, Self::TagError> {
+    let response = self.get_simple(format!("track/{}/tag", track_id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn add_track_tag(&self, track_id: i32, name: &str) -> Result<Option<TrackTag>, Self::TagError> {
+    let response = self.put(format!("track/{}/tag/{}", track_id, name), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn add_tracks_tag(&self, track_ids: &[i32], name: &str) -> Result<Vec<TrackTag>, Self::TagError> {
+    let response = self.put_simple_with_json(format!("tag/{}/tracks", name), track_ids).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn remove_track_tag(&self, track_id: i32, name: &str) -> Result<(), Self::TagError> {
+    self.delete_simple(format!("track/{}/tag/{}", track_id, name)).await?;
+    Ok(())
+  }
+
   // Artist
 
   type ArtistError = HttpRequestError;
@@ -194,11 +582,26 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
-  async fn get_artist_by_id(&self, id: i32) -> Result<Option<Artist>, Self::ArtistError> {
+  async fn get_artist_alphabet_index(&self) -> Result<Vec<AlphabetSection>, Self::ArtistError> {
+    let response = self.get_simple("artist/alphabet_index").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_artist_by_id(&self, id: i32) -> Result<Option<ArtistDetail>, Self::ArtistError> {
     let response = self.get_simple(format!("artist/{}", id)).await?;
     Ok(response.json().await?)
   }
 
+  async fn set_artist_sort_name(&self, id: i32, sort_name_override: &SortNameOverride) -> Result<Option<Artist>, Self::ArtistError> {
+    let response = self.put(format!("artist/{}/sort_name", id), |r| r.json(sort_name_override), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_artist_image(&self, id: i32, size: Option<u32>) -> Result<Option<Artwork>, Self::ArtistError> {
+    self.get_artwork(format!("artist/{}/image", id), size).await
+  }
+
   // Playback
 
   type PlaybackError = HttpRequestError;
@@ -217,9 +620,9 @@ impl Client for HttpClient {
     ).await?;
     let play_source = match response.status() {
       StatusCode::OK => {
-        let codec = response.headers().get(CONTENT_TYPE).and_then(|mime| mime.to_str().map_or(None, |str| AudioCodec::from_mime(str)));
+        let (codec, album_id, replay_gain) = audio_data_headers(response.headers());
         let data = response.bytes().await?.to_vec();
-        Some(PlaySource::AudioData { codec, data })
+        Some(PlaySource::AudioData { codec, data, album_id, replay_gain })
       }
       StatusCode::ACCEPTED => Some(PlaySource::ExternallyPlayedOnSpotify),
       StatusCode::NOT_FOUND => None,
@@ -228,6 +631,44 @@ impl Client for HttpClient {
     Ok(play_source)
   }
 
+  type AudioChunks = HttpAudioChunks;
+
+  async fn stream_track_by_id(&self, id: i32) -> Result<Option<PlayStream<Self::AudioChunks>>, Self::PlaybackError> {
+    let response = self.get(
+      format!("track/play/{}", id),
+      |r| r,
+      &[StatusCode::OK, StatusCode::ACCEPTED, StatusCode::NOT_FOUND],
+    ).await?;
+    let play_stream = match response.status() {
+      StatusCode::OK => {
+        let (codec, album_id, replay_gain) = audio_data_headers(response.headers());
+        let length = response.content_length();
+        let chunks = HttpAudioChunks::new(response).await?;
+        Some(PlayStream::AudioData { codec, length, album_id, replay_gain, chunks })
+      }
+      StatusCode::ACCEPTED => Some(PlayStream::ExternallyPlayedOnSpotify),
+      StatusCode::NOT_FOUND => None,
+      _ => unreachable!()
+    };
+    Ok(play_stream)
+  }
+
+  async fn get_signed_track_url(&self, id: i32) -> Result<Option<SignedTrackUrl>, Self::PlaybackError> {
+    let response = self.get(format!("track/{}/signed_url", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn get_playback_preference(&self) -> Result<PlaybackPreference, Self::PlaybackError> {
+    let response = self.get_simple("playback/preferences").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_playback_preference(&self, preference: &PlaybackPreference) -> Result<PlaybackPreference, Self::PlaybackError> {
+    let response = self.put_simple_with_json("playback/preferences", preference).await?;
+    Ok(response.json().await?)
+  }
+
   // User
 
   type UserError = HttpRequestError;
@@ -242,6 +683,33 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn get_user_preferences(&self) -> Result<UserPreferences, Self::UserError> {
+    let response = self.get_simple("user/me/preferences").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_user_preferences(&self, preferences: &UserPreferences) -> Result<UserPreferences, Self::UserError> {
+    let response = self.put_simple_with_json("user/me/preferences", preferences).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_my_stats(&self, query: UserStatsQuery) -> Result<UserStats, Self::UserError> {
+    let response = self.get("user/me/stats", |r| r.query(&query), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_my_year_review(&self, year: i32, query: YearReviewQuery) -> Result<Option<YearReview>, Self::UserError> {
+    let response = self.get(format!("user/me/year_review/{}", year), |r| r.query(&query), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(Some(response.json().await?))
+  }
+
+  async fn get_my_year_review_html(&self, year: i32, query: YearReviewQuery) -> Result<Option<String>, Self::UserError> {
+    let response = self.get(format!("user/me/year_review/{}/html", year), |r| r.query(&query), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(Some(response.text().await?))
+  }
+
   async fn get_user_by_id(&self, id: i32) -> Result<Option<User>, Self::UserError> {
     let response = self.get_simple(format!("user/{}", id)).await?;
     Ok(response.json().await?)
@@ -262,6 +730,36 @@ impl Client for HttpClient {
     Ok(())
   }
 
+  async fn create_user_invite(&self, properties: &NewUserInviteProperties) -> Result<UserInvite, Self::UserError> {
+    let response = self.post_simple_with_json("admin/invites", properties).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_user_invites(&self) -> Result<Vec<UserInvite>, Self::UserError> {
+    let response = self.get_simple("admin/invites").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_my_content_filter(&self) -> Result<UserContentFilter, Self::UserError> {
+    let response = self.get_simple("user/me/content_filter").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_my_content_filter(&self, filter: ContentFilter) -> Result<UserContentFilter, Self::UserError> {
+    let response = self.put_simple_with_json("user/me/content_filter", &filter).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_user_content_filter(&self, user_id: i32) -> Result<UserContentFilter, Self::UserError> {
+    let response = self.get_simple(format!("admin/user/{}/content_filter", user_id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_user_content_filter(&self, content_filter: &UserContentFilter) -> Result<UserContentFilter, Self::UserError> {
+    let response = self.put_simple_with_json(format!("admin/user/{}/content_filter", content_filter.user_id), content_filter).await?;
+    Ok(response.json().await?)
+  }
+
   // User data
 
   type UserDataError = HttpRequestError;
@@ -276,11 +774,311 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn set_user_track_ratings(&self, ratings: &TrackRatings) -> Result<Vec<UserTrackRating>, Self::UserDataError> {
+    let response = self.put_simple_with_json("user/data/track/rating", ratings).await?;
+    Ok(response.json().await?)
+  }
+
   async fn set_user_artist_rating(&self, artist_id: i32, rating: i32) -> Result<UserArtistRating, Self::UserDataError> {
     let response = self.put_simple(format!("user/data/artist/{}/rating/{}", artist_id, rating)).await?;
     Ok(response.json().await?)
   }
 
+  async fn list_hidden_items(&self) -> Result<Vec<HiddenItemInfo>, Self::UserDataError> {
+    let response = self.get_simple("user/data/hidden").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn hide_item(&self, kind: HiddenItemKind, item_id: i32) -> Result<HiddenItem, Self::UserDataError> {
+    let response = self.put_simple(format!("user/data/hidden/{}/{}", kind, item_id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn unhide_item(&self, kind: HiddenItemKind, item_id: i32) -> Result<bool, Self::UserDataError> {
+    let response = self.delete(format!("user/data/hidden/{}/{}", kind, item_id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    Ok(response.status() == StatusCode::OK)
+  }
+
+  // Playlist
+
+  type PlaylistError = HttpRequestError;
+
+  async fn list_playlists(&self) -> Result<Vec<Playlist>, Self::PlaylistError> {
+    let response = self.get_simple("playlist").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_playlist_by_id(&self, id: i32) -> Result<Option<PlaylistRaw>, Self::PlaylistError> {
+    let response = self.get_simple(format!("playlist/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn create_playlist(&self, properties: &PlaylistProperties) -> Result<Playlist, Self::PlaylistError> {
+    let response = self.post_simple_with_json("playlist", properties).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn update_playlist(&self, id: i32, properties: &PlaylistProperties) -> Result<Option<Playlist>, Self::PlaylistError> {
+    let response = self.put(format!("playlist/{}", id), |r| r.json(properties), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn delete_playlist(&self, id: i32) -> Result<(), Self::PlaylistError> {
+    self.delete_simple(format!("playlist/{}", id)).await?;
+    Ok(())
+  }
+
+  async fn add_playlist_track(&self, id: i32, track_id: i32) -> Result<Option<PlaylistTrack>, Self::PlaylistError> {
+    let response = self.post(format!("playlist/{}/track/{}", id, track_id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn add_playlist_tracks(&self, id: i32, track_ids: &[i32]) -> Result<Option<Vec<PlaylistTrack>>, Self::PlaylistError> {
+    let response = self.post(format!("playlist/{}/tracks", id), |r| r.json(track_ids), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn remove_playlist_track(&self, id: i32, playlist_track_id: i32) -> Result<(), Self::PlaylistError> {
+    self.delete_simple(format!("playlist/{}/playlist_track/{}", id, playlist_track_id)).await?;
+    Ok(())
+  }
+
+  async fn add_playlist_collaborator(&self, id: i32, user_id: i32) -> Result<Option<PlaylistCollaborator>, Self::PlaylistError> {
+    let response = self.put(format!("playlist/{}/collaborator/{}", id, user_id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn remove_playlist_collaborator(&self, id: i32, user_id: i32) -> Result<(), Self::PlaylistError> {
+    self.delete_simple(format!("playlist/{}/collaborator/{}", id, user_id)).await?;
+    Ok(())
+  }
+
+  async fn list_playlist_mixes(&self) -> Result<Vec<PlaylistMix>, Self::PlaylistError> {
+    let response = self.get_simple("playlist/mix").await?;
+    Ok(response.json().await?)
+  }
+
+  // Listening session
+
+  type ListeningSessionError = HttpRequestError;
+
+  async fn list_listening_sessions(&self) -> Result<Vec<ListeningSession>, Self::ListeningSessionError> {
+    let response = self.get_simple("session").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_listening_session_by_id(&self, id: i32) -> Result<Option<ListeningSession>, Self::ListeningSessionError> {
+    let response = self.get_simple(format!("session/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn create_listening_session(&self, new_listening_session: &NewListeningSession) -> Result<ListeningSession, Self::ListeningSessionError> {
+    let response = self.post_simple_with_json("session", new_listening_session).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn join_listening_session(&self, id: i32) -> Result<Option<ListeningSession>, Self::ListeningSessionError> {
+    let response = self.post(format!("session/{}/join", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn leave_listening_session(&self, id: i32) -> Result<(), Self::ListeningSessionError> {
+    self.post_simple(format!("session/{}/leave", id)).await?;
+    Ok(())
+  }
+
+  async fn command_listening_session(&self, id: i32, command: &ListeningSessionCommand) -> Result<Option<ListeningSession>, Self::ListeningSessionError> {
+    let response = self.post(format!("session/{}/command", id), |r| r.json(command), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  // Party
+
+  type PartyError = HttpRequestError;
+
+  async fn get_party_by_id(&self, id: i32) -> Result<Option<Party>, Self::PartyError> {
+    let response = self.get_simple(format!("party/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn create_party(&self, new_party: &NewParty) -> Result<Party, Self::PartyError> {
+    let response = self.post_simple_with_json("party", new_party).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn end_party(&self, id: i32) -> Result<(), Self::PartyError> {
+    self.delete_simple(format!("party/{}", id)).await?;
+    Ok(())
+  }
+
+  async fn invite_party_guest(&self, id: i32, new_guest: &NewPartyGuest) -> Result<Option<PartyGuest>, Self::PartyError> {
+    let response = self.post(format!("party/{}/guest", id), |r| r.json(new_guest), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn dequeue_party_track(&self, id: i32) -> Result<Option<PartyQueueItem>, Self::PartyError> {
+    let response = self.post_simple(format!("party/{}/dequeue", id)).await?;
+    Ok(response.json().await?)
+  }
+
+  // Reports
+
+  type ReportError = HttpRequestError;
+
+  async fn list_missing_albums(&self) -> Result<AlbumsRaw, Self::ReportError> {
+    let response = self.get_simple("reports/missing_albums").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_library_summary(&self) -> Result<LibrarySummary, Self::ReportError> {
+    let response = self.get_simple("home_assistant/library").await?;
+    Ok(response.json().await?)
+  }
+
+  // Notifications
+
+  type NotificationError = HttpRequestError;
+
+  async fn list_new_releases(&self) -> Result<Vec<NewRelease>, Self::NotificationError> {
+    let response = self.get_simple("notifications").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_notifications(&self, unread_only: bool) -> Result<Vec<Notification>, Self::NotificationError> {
+    let response = self.get("notifications/inbox", |r| r.query(&[("unread_only", unread_only)]), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn mark_notification_read(&self, id: i32) -> Result<Option<Notification>, Self::NotificationError> {
+    let response = self.put(format!("notifications/inbox/{}/read", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn mark_all_notifications_read(&self) -> Result<usize, Self::NotificationError> {
+    let response = self.put_simple("notifications/inbox/read").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_notification_preference(&self) -> Result<NotificationPreference, Self::NotificationError> {
+    let response = self.get_simple("notifications/preferences").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_notification_preference(&self, preference: &NotificationPreference) -> Result<NotificationPreference, Self::NotificationError> {
+    let response = self.put_simple_with_json("notifications/preferences", preference).await?;
+    Ok(response.json().await?)
+  }
+
+  // Events
+
+  type EventError = HttpRequestError;
+
+  /// Polls for events by long-polling, which works through proxies that do not support WebSockets.
+  async fn poll_events(&self, since: Option<u64>, wait: Option<Duration>) -> Result<EventsPoll, Self::EventError> {
+    let wait = wait.map_or_else(WaitQuery::default, WaitQuery::new);
+    let response = self.get("events/poll", |r| r.query(&EventsQuery { since }).query(&wait), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
+  // Maintenance
+
+  type MaintenanceError = HttpRequestError;
+
+  async fn get_maintenance_status(&self) -> Result<MaintenanceStatus, Self::MaintenanceError> {
+    let response = self.get_simple("maintenance").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_maintenance_status(&self, status: &MaintenanceStatus) -> Result<MaintenanceStatus, Self::MaintenanceError> {
+    let response = self.put_simple_with_json("maintenance", status).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn get_media_cache_status(&self) -> Result<MediaCacheStatus, Self::MaintenanceError> {
+    let response = self.get_simple("media_cache").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn list_track_issues(&self) -> Result<Vec<TrackIssue>, Self::MaintenanceError> {
+    let response = self.get_simple("maintenance/issues").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn validate_local_tracks(&self) -> Result<(), Self::MaintenanceError> {
+    self.post("maintenance/issues/validate", |r| r, &[StatusCode::ACCEPTED]).await?;
+    Ok(())
+  }
+
+  // Undo
+
+  type UndoError = HttpRequestError;
+
+  async fn list_undo_log(&self) -> Result<Vec<UndoLogEntry>, Self::UndoError> {
+    let response = self.get_simple("undo").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn undo(&self, id: i32) -> Result<Option<UndoLogEntry>, Self::UndoError> {
+    let response = self.post(format!("undo/{}", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  // Metadata merging
+
+  type MetadataError = HttpRequestError;
+
+  async fn list_metadata_merge_policies(&self) -> Result<Vec<MetadataMergePolicySetting>, Self::MetadataError> {
+    let response = self.get_simple("metadata/merge_policy").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_metadata_merge_policy(&self, setting: &MetadataMergePolicySetting) -> Result<MetadataMergePolicySetting, Self::MetadataError> {
+    let response = self.put_simple_with_json("metadata/merge_policy", setting).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn remove_metadata_merge_policy(&self, field: MetadataField) -> Result<Option<MetadataMergePolicySetting>, Self::MetadataError> {
+    let response = self.delete(format!("metadata/merge_policy/{:?}", field), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn list_metadata_conflicts(&self) -> Result<Vec<MetadataConflict>, Self::MetadataError> {
+    let response = self.get_simple("metadata/conflict").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn resolve_metadata_conflict(&self, id: i32, resolution: &MetadataConflictResolution) -> Result<Option<MetadataConflict>, Self::MetadataError> {
+    let response = self.post(format!("metadata/conflict/{}/resolve", id), |r| r.json(resolution), &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn dismiss_metadata_conflict(&self, id: i32) -> Result<Option<MetadataConflict>, Self::MetadataError> {
+    let response = self.delete(format!("metadata/conflict/{}", id), |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn list_sort_articles(&self) -> Result<Vec<String>, Self::MetadataError> {
+    let response = self.get_simple("metadata/sort_article").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_sort_articles(&self, articles: &[String]) -> Result<Vec<String>, Self::MetadataError> {
+    let response = self.put_simple_with_json("metadata/sort_article", articles).await?;
+    Ok(response.json().await?)
+  }
+
   // Sync
 
   type SyncError = HttpRequestError;
@@ -290,6 +1088,11 @@ impl Client for HttpClient {
     Ok(response.json().await?)
   }
 
+  async fn wait_for_sync_status(&self, wait: Duration) -> Result<SyncStatus, Self::SyncError> {
+    let response = self.get("sync", |r| r.query(&WaitQuery::new(wait)), &[StatusCode::OK]).await?;
+    Ok(response.json().await?)
+  }
+
   async fn sync_all_sources(&self) -> Result<SyncStatus, Self::SyncError> {
     let response = self.post_simple("sync").await?;
     Ok(response.json().await?)
@@ -314,12 +1117,149 @@ impl Client for HttpClient {
     let response = self.post_simple(format!("sync/spotify/{}", spotify_source_id)).await?;
     Ok(response.json().await?)
   }
+
+  async fn preview_sync(&self) -> Result<SyncPreview, Self::SyncError> {
+    let response = self.post_simple("sync/preview").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn show_sync_lock(&self) -> Result<Option<SyncLock>, Self::SyncError> {
+    let response = self.get("sync/lock", |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn force_release_sync_lock(&self) -> Result<Option<SyncLock>, Self::SyncError> {
+    let response = self.delete("sync/lock", |r| r, &[StatusCode::OK, StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND { return Ok(None); }
+    Ok(response.json().await?)
+  }
+
+  async fn list_sync_history(&self) -> Result<Vec<SyncRun>, Self::SyncError> {
+    let response = self.get_simple("sync/history").await?;
+    Ok(response.json().await?)
+  }
+
+  // Webhooks
+
+  type WebhookError = HttpRequestError;
+
+  async fn list_webhooks(&self) -> Result<Vec<WebhookInfo>, Self::WebhookError> {
+    let response = self.get_simple("webhook").await?;
+    Ok(response.json().await?)
+  }
+
+  async fn create_webhook(&self, new_webhook: &NewWebhook) -> Result<WebhookInfo, Self::WebhookError> {
+    let response = self.post_simple_with_json("webhook", new_webhook).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn set_webhook_enabled_by_id(&self, id: i32, enabled: bool) -> Result<Option<WebhookInfo>, Self::WebhookError> {
+    let response = self.post_simple_with_json(format!("webhook/set_enabled/{}", id), &enabled).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn delete_webhook_by_id(&self, id: i32) -> Result<Option<WebhookInfo>, Self::WebhookError> {
+    let response = self.delete_simple(format!("webhook/{}", id)).await?;
+    Ok(response.json().await?)
+  }
+}
+
+// Audio chunks
+
+/// Audio data of a track that is received in chunks from the body of an HTTP response.
+pub struct HttpAudioChunks {
+  #[cfg(not(target_arch = "wasm32"))]
+  response: Response,
+  // The body of a response cannot be received in chunks on wasm32, so it is received entirely as a single chunk.
+  #[cfg(target_arch = "wasm32")]
+  data: Option<Vec<u8>>,
+}
+
+impl HttpAudioChunks {
+  #[cfg(not(target_arch = "wasm32"))]
+  async fn new(response: Response) -> Result<Self, HttpRequestError> {
+    Ok(Self { response })
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  async fn new(response: Response) -> Result<Self, HttpRequestError> {
+    Ok(Self { data: Some(response.bytes().await?.to_vec()) })
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl AudioChunks for HttpAudioChunks {
+  type Error = HttpRequestError;
+
+  #[cfg(not(target_arch = "wasm32"))]
+  async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Self::Error> {
+    Ok(self.response.chunk().await?.map(|chunk| chunk.to_vec()))
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, Self::Error> {
+    Ok(self.data.take())
+  }
+}
+
+impl Debug for HttpAudioChunks {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("HttpAudioChunks").finish()
+  }
+}
+
+/// Gets the codec, album ID, and ReplayGain values of the audio data in a response from `headers`.
+fn audio_data_headers(headers: &HeaderMap) -> (Option<AudioCodec>, Option<i32>, Option<ReplayGain>) {
+  let codec = headers.get(CONTENT_TYPE).and_then(|mime| mime.to_str().map_or(None, |str| AudioCodec::from_mime(str)));
+  let album_id = headers.get(ALBUM_ID_HEADER).and_then(|value| value.to_str().ok()).and_then(|value| value.parse().ok());
+  let replay_gain = ReplayGain::from_headers(|name| headers.get(name).and_then(|value| value.to_str().ok()));
+  (codec, album_id, replay_gain)
 }
 
 // Internals
 
 #[allow(dead_code)]
 impl HttpClient {
+  /// Gets artwork at `url_suffix`, resized to `size` if set, revalidating artwork in the artwork cache with its ETag.
+  async fn get_artwork(&self, url_suffix: String, size: Option<u32>) -> Result<Option<Artwork>, HttpRequestError> {
+    let url_suffix = match size {
+      Some(size) => format!("{}?size={}", url_suffix, size),
+      None => url_suffix,
+    };
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let cached = self.artwork_cache.lock().unwrap().get(&url_suffix);
+    let response = self.get(
+      &url_suffix,
+      |r| match &cached {
+        Some((etag, _)) => r.header(IF_NONE_MATCH, etag),
+        None => r,
+      },
+      &[StatusCode::OK, StatusCode::NOT_MODIFIED, StatusCode::NOT_FOUND],
+    ).await?;
+    match response.status() {
+      StatusCode::NOT_MODIFIED if cached.is_some() => Ok(cached.map(|(_, artwork)| artwork)),
+      StatusCode::OK => {
+        let headers = response.headers();
+        let mime_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+        let etag = headers.get(ETAG).and_then(|value| value.to_str().ok()).map(|etag| etag.to_string());
+        let artwork = Artwork { mime_type, data: response.bytes().await?.to_vec() };
+        if let Some(etag) = etag {
+          // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+          self.artwork_cache.lock().unwrap().insert(url_suffix, etag, artwork.clone());
+        }
+        Ok(Some(artwork))
+      }
+      c @ StatusCode::NOT_MODIFIED => Err(HttpRequestError::UnexpectedStatusCode(c, Backtrace::capture())),
+      _ => {
+        // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+        self.artwork_cache.lock().unwrap().remove(&url_suffix);
+        Ok(None)
+      }
+    }
+  }
+
   async fn request(
     &self,
     method: Method,
@@ -328,9 +1268,21 @@ impl HttpClient {
     expected_status_codes: impl AsRef<[StatusCode]>,
   ) -> Result<Response, HttpRequestError> {
     use HttpRequestError::*;
-    let url = self.url.join(url_suffix.as_ref())?;
-    let response = f_request(self.client.request(method, url)).send().await?;
+    let url = self.url.join(&format!("{}/{}", API_PATH_PREFIX, url_suffix.as_ref()))?;
+    let request = self.client.request(method, url);
+    // Include the identity cookie in requests from the browser, which are cross-origin when the web client is not
+    // served by the server itself.
+    #[cfg(target_arch = "wasm32")] let request = request.fetch_credentials_include();
+    let response = f_request(request).send().await?;
     match response.status() {
+      c @ StatusCode::NOT_ACCEPTABLE => {
+        let json: Result<InternalServerError, _> = response.json().await;
+        return Err(if let Ok(internal_server_error) = json {
+          UnsupportedApiVersionFail(internal_server_error.message, Backtrace::capture())
+        } else {
+          UnexpectedStatusCode(c, Backtrace::capture())
+        });
+      }
       c @ StatusCode::INTERNAL_SERVER_ERROR => {
         let json: Result<InternalServerError, _> = response.json().await;
         return Err(if let Ok(internal_server_error) = json {
@@ -339,6 +1291,14 @@ impl HttpClient {
           UnexpectedStatusCode(c, Backtrace::capture())
         });
       }
+      c @ (StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS) if !expected_status_codes.as_ref().contains(&c) => {
+        let json: Result<InternalServerError, _> = response.json().await;
+        return Err(if let Ok(internal_server_error) = json {
+          RequestRejectedFail(c, internal_server_error.message, Backtrace::capture())
+        } else {
+          UnexpectedStatusCode(c, Backtrace::capture())
+        });
+      }
       c if !expected_status_codes.as_ref().contains(&c) => {
         return Err(UnexpectedStatusCode(c, Backtrace::capture()));
       }
@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Name of the settings file in the configuration directory.
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+/// Name of the directory in the cache and data directories that contains the caches of named profiles.
+const PROFILES_DIR_NAME: &str = "profiles";
+
+/// Directories in which clients store their settings and caches, following the conventions of the platform, such as
+/// `$XDG_CONFIG_HOME/musium` and `$XDG_CACHE_HOME/musium` on Linux.
+#[derive(Clone, Debug)]
+pub struct ClientDirs {
+  config_dir: PathBuf,
+  cache_dir: PathBuf,
+  data_dir: PathBuf,
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to determine the client settings and cache directories; no home directory was found")]
+pub struct NoHomeDirectoryError;
+
+impl ClientDirs {
+  pub fn new() -> Result<Self, NoHomeDirectoryError> {
+    let project_dirs = ProjectDirs::from("", "", "musium").ok_or(NoHomeDirectoryError)?;
+    Ok(Self {
+      config_dir: project_dirs.config_dir().to_path_buf(),
+      cache_dir: project_dirs.cache_dir().to_path_buf(),
+      data_dir: project_dirs.data_dir().to_path_buf(),
+    })
+  }
+
+  #[inline]
+  pub fn config_dir(&self) -> &Path { &self.config_dir }
+
+  #[inline]
+  pub fn settings_file(&self) -> PathBuf { self.config_dir.join(SETTINGS_FILE_NAME) }
+
+  /// Gets the directories of the profile with `profile_name`, which have the same configuration directory, but separate
+  /// caches. The default profile, when `profile_name` is `None`, uses the caches of these directories.
+  pub fn for_profile(&self, profile_name: Option<&str>) -> Self {
+    match profile_name {
+      Some(profile_name) => Self {
+        config_dir: self.config_dir.clone(),
+        cache_dir: self.cache_dir.join(PROFILES_DIR_NAME).join(profile_name),
+        data_dir: self.data_dir.join(PROFILES_DIR_NAME).join(profile_name),
+      },
+      None => self.clone(),
+    }
+  }
+
+  /// Gets the directory of `cache`. The directory may not exist yet.
+  pub fn cache_dir(&self, cache: Cache) -> PathBuf {
+    match cache {
+      Cache::Token => self.cache_dir.join("token"),
+      Cache::Artwork => self.cache_dir.join("artwork"),
+      // Offline tracks are stored as data, as they are downloaded on purpose and should not be cleaned up by the
+      // platform like caches may be.
+      Cache::OfflineTracks => self.data_dir.join("offline_tracks"),
+    }
+  }
+
+  /// Gets the directory of `cache`, creating it if it does not exist.
+  pub fn create_cache_dir(&self, cache: Cache) -> Result<PathBuf, CacheError> {
+    let dir = self.cache_dir(cache);
+    fs::create_dir_all(&dir).map_err(|e| CacheError::CreateDirFail(dir.clone(), e))?;
+    Ok(dir)
+  }
+
+  /// Gets the total size in bytes of the files in `cache`.
+  pub fn cache_size(&self, cache: Cache) -> Result<u64, CacheError> {
+    let dir = self.cache_dir(cache);
+    dir_size(&dir).map_err(|e| CacheError::InspectFail(dir, e))
+  }
+
+  /// Removes all files in `cache`.
+  pub fn clear_cache(&self, cache: Cache) -> Result<(), CacheError> {
+    let dir = self.cache_dir(cache);
+    match fs::remove_dir_all(&dir) {
+      Err(e) if e.kind() != io::ErrorKind::NotFound => Err(CacheError::ClearFail(dir, e)),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Gets the total size in bytes of the files in `dir` and its subdirectories, or 0 if `dir` does not exist.
+fn dir_size(dir: &Path) -> io::Result<u64> {
+  let entries = match fs::read_dir(dir) {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+    r => r?,
+  };
+  let mut size = 0;
+  for entry in entries {
+    let entry = entry?;
+    let metadata = entry.metadata()?;
+    size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+  }
+  Ok(size)
+}
+
+// Settings
+
+/// Settings shared by all clients, persisted in the settings file of the configuration directory. Command-line
+/// arguments and environment variables take precedence over these settings.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSettings {
+  /// Base URL of the server to connect to in the default profile.
+  pub url_base: Option<String>,
+  /// Name of the user to log in as in the default profile. Passwords are never stored.
+  pub login_name: Option<String>,
+  /// Locale of the user interface, such as `nl-NL`.
+  pub locale: Option<String>,
+  /// Name of the profile that is used when no profile is given, or `None` to use the default profile.
+  pub current_profile: Option<String>,
+  /// Named profiles, such as for a home server and a remote server, in addition to the default profile.
+  pub profiles: BTreeMap<String, ClientProfile>,
+  /// Audio output that players play audio with.
+  pub audio_output: AudioOutputSettings,
+}
+
+/// Server and account to connect with, so that clients can switch between servers and accounts.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientProfile {
+  /// Base URL of the server to connect to.
+  pub url_base: Option<String>,
+  /// Name of the user to log in as. Passwords are never stored.
+  pub login_name: Option<String>,
+}
+
+/// Audio output that players play audio with, and its options. Changes take effect when the player is started again.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioOutputSettings {
+  /// Audio output to play audio with.
+  pub kind: AudioOutputKind,
+  /// Whether to play audio at its native sample rate instead of resampling it to the sample rate of the output device.
+  /// Only supported by the Rodio audio output.
+  pub native_sample_rate: bool,
+  /// Milliseconds of audio to decode ahead of playback, or `None` to decode audio while playing it. Only supported by
+  /// the Rodio audio output.
+  pub decode_ahead_milliseconds: Option<u64>,
+}
+
+impl AudioOutputSettings {
+  /// Overrides these settings with those of `kind`, `native_sample_rate`, and `decode_ahead_milliseconds` that are set,
+  /// such as settings given as command-line arguments.
+  pub fn override_with(&mut self, kind: Option<AudioOutputKind>, native_sample_rate: Option<bool>, decode_ahead_milliseconds: Option<u64>) {
+    if let Some(kind) = kind {
+      self.kind = kind;
+    }
+    if let Some(native_sample_rate) = native_sample_rate {
+      self.native_sample_rate = native_sample_rate;
+    }
+    if decode_ahead_milliseconds.is_some() {
+      self.decode_ahead_milliseconds = decode_ahead_milliseconds;
+    }
+  }
+}
+
+/// Audio output that players play audio with.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioOutputKind {
+  /// Kira audio output, which supports seeking and fading.
+  Kira,
+  /// Rodio audio output, which supports playing at the native sample rate, decoding ahead of playback, and streaming.
+  Rodio,
+}
+
+impl AudioOutputKind {
+  pub const NAMES: &'static [&'static str] = &["kira", "rodio"];
+}
+
+impl Default for AudioOutputKind {
+  fn default() -> Self { AudioOutputKind::Kira }
+}
+
+impl Display for AudioOutputKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      AudioOutputKind::Kira => "kira",
+      AudioOutputKind::Rodio => "rodio",
+    };
+    f.write_str(name)
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown audio output '{0}'; expected one of: kira, rodio")]
+pub struct ParseAudioOutputKindError(String);
+
+impl FromStr for AudioOutputKind {
+  type Err = ParseAudioOutputKindError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "kira" => Ok(AudioOutputKind::Kira),
+      "rodio" => Ok(AudioOutputKind::Rodio),
+      _ => Err(ParseAudioOutputKindError(s.to_string())),
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid profile name '{0}'; profile names may only contain letters, digits, '-', and '_'")]
+pub struct ParseProfileNameError(String);
+
+/// Parses `name` as a profile name, which is used as a directory name for the caches of the profile, and therefore may
+/// only contain letters, digits, '-', and '_'.
+pub fn parse_profile_name(name: &str) -> Result<String, ParseProfileNameError> {
+  if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+    return Err(ParseProfileNameError(name.to_string()));
+  }
+  Ok(name.to_string())
+}
+
+#[derive(Debug, Error)]
+pub enum ClientSettingsError {
+  #[error("Failed to read client settings file {0:?}")]
+  ReadFail(PathBuf, #[source] io::Error),
+  #[error("Failed to parse client settings file {0:?}")]
+  ParseFail(PathBuf, #[source] toml::de::Error),
+  #[error("Failed to serialize client settings")]
+  SerializeFail(#[from] toml::ser::Error),
+  #[error("Failed to create client configuration directory {0:?}")]
+  CreateDirFail(PathBuf, #[source] io::Error),
+  #[error("Failed to write client settings file {0:?}")]
+  WriteFail(PathBuf, #[source] io::Error),
+}
+
+impl ClientSettings {
+  /// Loads the settings from the settings file in `dirs`, or returns the default settings if there is no settings file.
+  pub fn load(dirs: &ClientDirs) -> Result<Self, ClientSettingsError> {
+    let file = dirs.settings_file();
+    let text = match fs::read_to_string(&file) {
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+      r => r.map_err(|e| ClientSettingsError::ReadFail(file.clone(), e))?,
+    };
+    toml::from_str(&text).map_err(|e| ClientSettingsError::ParseFail(file, e))
+  }
+
+  /// Saves the settings to the settings file in `dirs`, creating the configuration directory if it does not exist.
+  pub fn save(&self, dirs: &ClientDirs) -> Result<(), ClientSettingsError> {
+    let text = toml::to_string_pretty(self)?;
+    fs::create_dir_all(dirs.config_dir()).map_err(|e| ClientSettingsError::CreateDirFail(dirs.config_dir().to_path_buf(), e))?;
+    let file = dirs.settings_file();
+    fs::write(&file, text).map_err(|e| ClientSettingsError::WriteFail(file, e))
+  }
+
+  /// Gets the profile with `profile_name`, or the default profile when `profile_name` is `None`. Returns an empty profile
+  /// if there is no profile with `profile_name`.
+  pub fn profile(&self, profile_name: Option<&str>) -> ClientProfile {
+    match profile_name {
+      Some(profile_name) => self.profiles.get(profile_name).cloned().unwrap_or_default(),
+      None => ClientProfile { url_base: self.url_base.clone(), login_name: self.login_name.clone() },
+    }
+  }
+
+  /// Sets the profile with `profile_name`, or the default profile when `profile_name` is `None`, to `profile`.
+  pub fn set_profile(&mut self, profile_name: Option<&str>, profile: ClientProfile) {
+    match profile_name {
+      Some(profile_name) => { self.profiles.insert(profile_name.to_string(), profile); }
+      None => {
+        self.url_base = profile.url_base;
+        self.login_name = profile.login_name;
+      }
+    }
+  }
+
+  /// Removes the profile with `profile_name`, also unsetting it as the current profile. Returns whether the profile
+  /// existed.
+  pub fn remove_profile(&mut self, profile_name: &str) -> bool {
+    if self.current_profile.as_deref() == Some(profile_name) {
+      self.current_profile = None;
+    }
+    self.profiles.remove(profile_name).is_some()
+  }
+}
+
+// Caches
+
+/// Cache of files stored by clients.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Cache {
+  /// Authentication tokens, so that clients do not need to log in again.
+  Token,
+  /// Album covers and artist images.
+  Artwork,
+  /// Audio of tracks made available for playing without a connection to the server.
+  OfflineTracks,
+}
+
+impl Cache {
+  pub const ALL: [Cache; 3] = [Cache::Token, Cache::Artwork, Cache::OfflineTracks];
+  pub const NAMES: &'static [&'static str] = &["token", "artwork", "offline-tracks"];
+}
+
+impl Display for Cache {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      Cache::Token => "token",
+      Cache::Artwork => "artwork",
+      Cache::OfflineTracks => "offline-tracks",
+    };
+    f.write_str(name)
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown cache '{0}'; expected one of: token, artwork, offline-tracks")]
+pub struct ParseCacheError(String);
+
+impl FromStr for Cache {
+  type Err = ParseCacheError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "token" => Ok(Cache::Token),
+      "artwork" => Ok(Cache::Artwork),
+      "offline-tracks" => Ok(Cache::OfflineTracks),
+      _ => Err(ParseCacheError(s.to_string())),
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+  #[error("Failed to create cache directory {0:?}")]
+  CreateDirFail(PathBuf, #[source] io::Error),
+  #[error("Failed to inspect the size of cache directory {0:?}")]
+  InspectFail(PathBuf, #[source] io::Error),
+  #[error("Failed to clear cache directory {0:?}")]
+  ClearFail(PathBuf, #[source] io::Error),
+}
@@ -1,11 +1,18 @@
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
 use std::path::Path;
+use std::time::Duration;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use chrono::NaiveDateTime;
+
+use crate::model::{Album, Artist, Availability, HiddenItem, LocalSource, LocalTrack, NewRelease, Notification, PreferredPlaybackSource, SpotifySource, SpotifyTrack, Tag, Track, TrackArtistRole, TrackRelation, TrackRelationKind, Webhook, Work};
+use crate::model::collection::TracksRaw;
+
 #[derive(Debug, Error)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[error("Internal server error")]
@@ -13,6 +20,59 @@ pub struct InternalServerError {
   pub message: String,
 }
 
+// API versioning
+
+/// Version of the HTTP API. Incremented on breaking changes to endpoints or their request and response bodies.
+pub const API_VERSION: u32 = 1;
+
+/// Path prefix under which version `API_VERSION` of the HTTP API is served, without leading or trailing slashes.
+pub const API_PATH_PREFIX: &'static str = "api/v1";
+
+/// Header with the version of the HTTP API a client expects. The server rejects requests with a version it does not
+/// support with a 406 Not Acceptable response, and sets this header on its responses to the version it serves.
+pub const API_VERSION_HEADER: &'static str = "x-musium-api-version";
+
+/// Service type under which servers announce themselves on the local network through zeroconf (mDNS-SD), with the
+/// `API_PATH_PREFIX`, under the path prefix of the server if any, in the `path` property and the `API_VERSION` in the `api_version` property.
+pub const ZEROCONF_SERVICE_TYPE: &'static str = "_musium._tcp.local.";
+
+/// Version and capabilities of the server, so that clients can check compatibility before using the API. Capabilities
+/// are names of optional or later-added features, such as `album_cover` or `media_cache`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct ServerInfo {
+  pub version: String,
+  pub api_version: u32,
+  pub min_api_version: u32,
+  pub capabilities: Vec<String>,
+}
+
+/// Optional subsystems of the server and whether they are enabled, so that front-ends can hide the UI of disabled
+/// subsystems instead of failing when it is used.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct Capabilities {
+  pub spotify: bool,
+  /// Whether saving albums, following artists, and adding tracks to playlists can be written back to Spotify.
+  pub spotify_write_back: bool,
+  pub transcoding: bool,
+  pub lyrics: bool,
+  pub scrobbling: bool,
+  pub websocket_events: bool,
+  pub artist_enrichment: bool,
+  /// Whether new users can register themselves with an invite code.
+  pub registration: bool,
+  /// ID of the local source into which uploaded tracks are stored, or `None` if uploading tracks is disabled.
+  pub managed_local_source_id: Option<i32>,
+  /// Template by which the files of the managed local source are organized, or `None` if they are not organized.
+  pub managed_library_template: Option<String>,
+  /// Whether a read-only kiosk login with the kiosk token of the server is available.
+  pub kiosk: bool,
+  /// URI that Spotify redirects to after authorizing a Spotify source, which must be registered as a redirect URI of the
+  /// Spotify app of the server, or `None` if it is derived from the URL at which the server is reached.
+  pub spotify_redirect_uri: Option<String>,
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub enum AudioCodec {
@@ -57,10 +117,70 @@ impl AudioCodec {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub enum PlaySource {
-  AudioData { codec: Option<AudioCodec>, data: Vec<u8> },
+  /// Audio data to play, along with the album of the track, and the ReplayGain values of the file if it has them.
+  AudioData { codec: Option<AudioCodec>, data: Vec<u8>, album_id: Option<i32>, replay_gain: Option<ReplayGain> },
   ExternallyPlayedOnSpotify,
 }
 
+/// Header containing the album ID of a track, in responses with audio data.
+pub const ALBUM_ID_HEADER: &'static str = "x-musium-album-id";
+
+/// ReplayGain values of a local track. Gains are in dB relative to the ReplayGain reference level, and peaks are linear
+/// sample amplitudes where 1.0 is full scale.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Copy, Clone, PartialEq, Debug)]
+pub struct ReplayGain {
+  pub track_gain: Option<f32>,
+  pub track_peak: Option<f32>,
+  pub album_gain: Option<f32>,
+  pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+  pub const TRACK_GAIN_HEADER: &'static str = "x-replaygain-track-gain";
+  pub const TRACK_PEAK_HEADER: &'static str = "x-replaygain-track-peak";
+  pub const ALBUM_GAIN_HEADER: &'static str = "x-replaygain-album-gain";
+  pub const ALBUM_PEAK_HEADER: &'static str = "x-replaygain-album-peak";
+
+  /// Returns `None` if no gain is known, because peaks are meaningless without a gain.
+  pub fn new(track_gain: Option<f32>, track_peak: Option<f32>, album_gain: Option<f32>, album_peak: Option<f32>) -> Option<Self> {
+    if track_gain.is_none() && album_gain.is_none() { return None; }
+    Some(Self { track_gain, track_peak, album_gain, album_peak })
+  }
+
+  /// Gets the header names and values to send these values along with audio data.
+  pub fn to_headers(&self) -> Vec<(&'static str, String)> {
+    [
+      (Self::TRACK_GAIN_HEADER, self.track_gain),
+      (Self::TRACK_PEAK_HEADER, self.track_peak),
+      (Self::ALBUM_GAIN_HEADER, self.album_gain),
+      (Self::ALBUM_PEAK_HEADER, self.album_peak),
+    ].iter().filter_map(|(name, value)| value.map(|value| (*name, value.to_string()))).collect()
+  }
+
+  /// Reads these values from headers, using `get_header` to get the value of a header by name.
+  pub fn from_headers<'a>(get_header: impl Fn(&'static str) -> Option<&'a str>) -> Option<Self> {
+    let parse = |name| get_header(name).and_then(|value| value.parse().ok());
+    Self::new(parse(Self::TRACK_GAIN_HEADER), parse(Self::TRACK_PEAK_HEADER), parse(Self::ALBUM_GAIN_HEADER), parse(Self::ALBUM_PEAK_HEADER))
+  }
+
+  /// Gets the linear factor to multiply the volume with. Uses the album gain when `prefer_album` is true and the album
+  /// gain is known, and the track gain otherwise. The factor is reduced to prevent clipping when the peak is known.
+  pub fn gain_factor(&self, prefer_album: bool) -> Option<f64> {
+    let (gain, peak) = match (prefer_album, self.album_gain, self.track_gain) {
+      (true, Some(album_gain), _) => (album_gain, self.album_peak),
+      (_, _, Some(track_gain)) => (track_gain, self.track_peak),
+      (_, Some(album_gain), None) => (album_gain, self.album_peak),
+      _ => return None,
+    };
+    let factor = 10f64.powf(gain as f64 / 20.0);
+    Some(match peak {
+      Some(peak) if peak > 0.0 => factor.min(1.0 / peak as f64),
+      _ => factor,
+    })
+  }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub enum PlaySourceKind {
@@ -68,36 +188,550 @@ pub enum PlaySourceKind {
   ExternalOnSpotify,
 }
 
+/// Status of synchronizing sources. Goes from `Idle` to `Running` when a sync is started, and from `Running` to
+/// `Completed` or `Failed` when it ends. A sync that cannot be started, for example because another server instance
+/// holds the sync lock, goes to `Failed` directly. The last `Completed` or `Failed` status is kept until the next sync is
+/// started.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum SyncStatus {
+  /// No sync was started since the server started.
   Idle,
-  Started(Option<f32>),
-  Busy(Option<f32>),
-  Completed,
-  Failed,
+  Running {
+    started_at: NaiveDateTime,
+    /// Progress as a fraction between 0 and 1, or `None` if unknown.
+    progress: Option<f32>,
+  },
+  Completed {
+    started_at: NaiveDateTime,
+    ended_at: NaiveDateTime,
+    summary: SyncSummary,
+  },
+  Failed {
+    started_at: NaiveDateTime,
+    ended_at: NaiveDateTime,
+    /// Messages of the errors that caused the sync to fail, including their causes.
+    errors: Vec<String>,
+  },
+}
+
+impl SyncStatus {
+  #[inline]
+  pub fn is_running(&self) -> bool { matches!(self, SyncStatus::Running { .. }) }
 }
 
 impl Display for SyncStatus {
   fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
     match self {
       SyncStatus::Idle => f.write_str("idle"),
-      SyncStatus::Started(progress) => {
-        f.write_str("started")?;
+      SyncStatus::Running { started_at, progress } => {
+        f.write_str(&format!("running since {}", started_at))?;
         if let Some(progress) = progress {
-          f.write_str(&format!(" {0:.1}", progress * 100f32))?;
+          f.write_str(&format!(", {0:.1}%", progress * 100f32))?;
         }
         Ok(())
       }
-      SyncStatus::Busy(progress) => {
-        f.write_str("busy")?;
-        if let Some(progress) = progress {
-          f.write_str(&format!(" {0:.1}", progress * 100f32))?;
+      SyncStatus::Completed { ended_at, summary, .. } => {
+        f.write_str(&format!("completed at {}", ended_at))?;
+        if !summary.added_album_ids.is_empty() {
+          f.write_str(&format!(", added {} album(s)", summary.added_album_ids.len()))?;
+        }
+        f.write_str(&format!(", {} track(s) added, {} updated, {} removed", summary.added_track_count, summary.updated_track_count, summary.removed_track_count))?;
+        if !summary.skipped_local_source_ids.is_empty() {
+          f.write_str(&format!(", but skipped unavailable local sources {:?}", summary.skipped_local_source_ids))?;
+        }
+        Ok(())
+      }
+      SyncStatus::Failed { ended_at, errors, .. } => {
+        f.write_str(&format!("failed at {}", ended_at))?;
+        for error in errors {
+          f.write_str(&format!("\n- {}", error))?;
         }
         Ok(())
       }
-      SyncStatus::Completed => f.write_str("completed"),
-      SyncStatus::Failed => f.write_str("failed"),
+    }
+  }
+}
+
+/// Summary of a completed sync.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct SyncSummary {
+  /// IDs of local sources that were skipped because their directory is unavailable.
+  pub skipped_local_source_ids: Vec<i32>,
+  /// IDs of albums that were added to the library.
+  pub added_album_ids: Vec<i32>,
+  /// Number of tracks that were added to the synchronized sources, summed over all sources.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub added_track_count: i32,
+  /// Number of tracks of the synchronized sources of which the metadata changed, summed over all sources.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub updated_track_count: i32,
+  /// Number of tracks that were removed from the synchronized sources, summed over all sources.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub removed_track_count: i32,
+}
+
+/// Preview of the changes that synchronizing local sources would make to the database, without making them.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct SyncPreview {
+  pub additions: SyncPreviewChanges,
+  pub removals: SyncPreviewChanges,
+  pub moves: SyncPreviewChanges,
+  pub metadata_changes: SyncPreviewChanges,
+  /// IDs of local sources that would be skipped because their directory is unavailable.
+  pub skipped_local_source_ids: Vec<i32>,
+  /// Errors that occurred while scanning, for files that would not be synchronized.
+  pub errors: Vec<String>,
+}
+
+/// Number of changes of one kind in a sync preview, along with a sample of at most a fixed number of those changes.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct SyncPreviewChanges {
+  pub count: usize,
+  pub sample: Vec<SyncPreviewChange>,
+}
+
+/// Change to a track in a sync preview. `track_id` is `None` for tracks that would be added, and `previous_file_path` is
+/// only set for tracks that would be moved.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct SyncPreviewChange {
+  pub local_source_id: i32,
+  pub track_id: Option<i32>,
+  pub file_path: String,
+  pub previous_file_path: Option<String>,
+  pub description: String,
+}
+
+/// Resolution of a metadata conflict, setting the conflicting field to `value`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct MetadataConflictResolution {
+  pub value: String,
+}
+
+/// Override of the sort name of an album or artist. When `sort_name` is `None`, the override is removed and the sort
+/// name is computed from the name again.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct SortNameOverride {
+  pub sort_name: Option<String>,
+}
+
+/// Section of a list of albums or artists sorted by sort name, with the `count` consecutive entries whose sort name
+/// starts with `initial`. Sections are in the order of the list, so clients can jump to a section by summing the counts
+/// of the sections before it.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AlphabetSection {
+  /// Uppercase letter from A to Z, or `#` for sort names that do not start with such a letter.
+  pub initial: char,
+  pub count: i64,
+}
+
+/// Status of the media cache on the server. Only unpinned blobs count towards `size_limit`, as pinned blobs are never
+/// evicted.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct MediaCacheStatus {
+  pub blob_count: i64,
+  pub total_size: i64,
+  pub unpinned_size: i64,
+  pub size_limit: i64,
+}
+
+/// Short-lived URL that plays a track without an authenticated session, for external players such as Chromecast, UPnP
+/// renderers, or HTML5 audio elements. Anyone with the URL can play the track until `expires_at`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct SignedTrackUrl {
+  pub url: String,
+  pub expires_at: NaiveDateTime,
+}
+
+/// Artist with its biography, and whether it has an image that can be retrieved from `GET /artist/{id}/image`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct ArtistDetail {
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  pub artist: Artist,
+  pub biography: Option<String>,
+  pub biography_source_url: Option<String>,
+  pub has_image: bool,
+}
+
+/// Options for uploading the cover of an album. When `embed` is true, the cover is also embedded into the tags of the
+/// local files of the album.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Clone, Debug)]
+pub struct AlbumCoverUpload {
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub embed: bool,
+}
+
+/// Options for uploading a track into the managed local source of the server, where `file_name` is the name under which
+/// the uploaded file is stored.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct TrackUpload {
+  pub file_name: String,
+}
+
+/// Request to fetch the front cover of the MusicBrainz release with `release_id` from the Cover Art Archive, and set it
+/// as the cover of an album.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AlbumCoverFetch {
+  pub release_id: String,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub embed: bool,
+}
+
+/// Result of setting the cover of an album. `embed_errors` describes the local files the cover could not be embedded
+/// into, which do not prevent the cover from being set.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AlbumCoverResult {
+  pub album_id: i32,
+  pub mime_type: String,
+  pub embedded_file_count: usize,
+  pub embed_errors: Vec<String>,
+}
+
+/// Sizes in pixels that album covers and artist images can be requested in, with the `size` query parameter. The image
+/// is resized to fit within a square of that size.
+pub const ARTWORK_SIZES: &[u32] = &[64, 256, 1024];
+
+/// Query of requests for album covers and artist images. Returns the original image when `size` is not set.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct ArtworkQuery {
+  pub size: Option<u32>,
+}
+
+/// Album cover or artist image.
+#[derive(Clone, Debug)]
+pub struct Artwork {
+  pub mime_type: String,
+  pub data: Vec<u8>,
+}
+
+/// Status of the read-only maintenance mode of the server. While `enabled`, requests that may modify the database are
+/// rejected with `reason`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct MaintenanceStatus {
+  pub enabled: bool,
+  pub reason: Option<String>,
+}
+
+/// Readiness of the server to serve requests, as reported by the readiness health probe. The server is `ready` when
+/// the database is connected, no migrations are pending, enough disk space is available on the volume of the database,
+/// and no background worker has stopped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct ReadinessStatus {
+  pub ready: bool,
+  pub database_connected: bool,
+  /// Versions of the database migrations that have not been applied yet, or empty if the database is not connected.
+  pub pending_migrations: Vec<String>,
+  /// Number of bytes available on the volume of the database, or `None` if it could not be determined.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub available_disk_space: Option<u64>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub background_workers: Vec<BackgroundWorkerStatus>,
+}
+
+/// Status of a background worker of the server, such as the sync worker or the webhook dispatcher.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct BackgroundWorkerStatus {
+  pub name: String,
+  pub state: BackgroundWorkerState,
+}
+
+/// State of a background worker. A worker that has `Stopped` no longer does its work, for example because it panicked.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BackgroundWorkerState {
+  Idle,
+  Busy,
+  Stopped,
+}
+
+/// Properties of an invite code, used when creating an invite code.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NewUserInviteProperties {
+  /// Number of hours the invite code can be redeemed for, or `None` for the default of the server.
+  pub valid_for_hours: Option<u32>,
+}
+
+/// Registration of a new user with `name` and `password`, redeeming `invite_code`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct UserRegistration {
+  pub invite_code: String,
+  pub name: String,
+  pub password: String,
+}
+
+/// Login as the kiosk user of the server with its kiosk `token`, instead of with a password. Kiosk logins are read-only:
+/// they can browse and play, but not modify ratings, playlists, sources, or other data.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct KioskLogin {
+  pub token: String,
+}
+
+/// Preferences of a user that roam across the devices of the user. Preferences that clients share are typed fields,
+/// while other preferences are kept as key/value pairs in `other`, so that clients can store their own preferences.
+/// Setting preferences replaces all preferences, except for `preferred_playback_source`, which is left unchanged when
+/// `None`, as it is the preferred source of the playback preferences of the user.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct UserPreferences {
+  pub theme: Option<String>,
+  /// Default volume between 0.0 and 1.0.
+  pub default_volume: Option<f32>,
+  pub preferred_playback_source: Option<PreferredPlaybackSource>,
+  /// Locale as a BCP 47 language tag, such as `en-US`.
+  pub locale: Option<String>,
+  /// Whether to downmix stereo audio to mono.
+  pub mono_downmix: Option<bool>,
+  /// Balance between the left (-1.0) and right (1.0) audio channel.
+  pub channel_balance: Option<f32>,
+  /// Amount of headphone crossfeed between 0.0 and 1.0.
+  pub crossfeed: Option<f32>,
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  pub other: BTreeMap<String, String>,
+}
+
+impl UserPreferences {
+  pub const THEME_KEY: &'static str = "theme";
+  pub const DEFAULT_VOLUME_KEY: &'static str = "default_volume";
+  pub const PREFERRED_PLAYBACK_SOURCE_KEY: &'static str = "preferred_playback_source";
+  pub const LOCALE_KEY: &'static str = "locale";
+  pub const MONO_DOWNMIX_KEY: &'static str = "mono_downmix";
+  pub const CHANNEL_BALANCE_KEY: &'static str = "channel_balance";
+  pub const CROSSFEED_KEY: &'static str = "crossfeed";
+}
+
+/// Daily window of local time during which players limit their volume, such as at night. The window wraps around
+/// midnight when `end` is before `start`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct QuietHours {
+  pub start: chrono::NaiveTime,
+  pub end: chrono::NaiveTime,
+}
+
+impl QuietHours {
+  pub fn contains(&self, time: chrono::NaiveTime) -> bool {
+    if self.start <= self.end {
+      self.start <= time && time < self.end
+    } else {
+      self.start <= time || time < self.end
+    }
+  }
+
+  /// Returns whether the current local time is within these quiet hours.
+  pub fn is_active_now(&self) -> bool {
+    self.contains(chrono::Local::now().time())
+  }
+}
+
+impl Display for QuietHours {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid quiet hours '{0}'; expected a start and end time such as 22:00-07:00")]
+pub struct ParseQuietHoursError(String);
+
+impl std::str::FromStr for QuietHours {
+  type Err = ParseQuietHoursError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parse_time = |time: &str| chrono::NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+    s.split_once('-')
+      .and_then(|(start, end)| Some(QuietHours { start: parse_time(start)?, end: parse_time(end)? }))
+      .ok_or_else(|| ParseQuietHoursError(s.to_string()))
+  }
+}
+
+/// Period over which listening statistics are computed, ending now.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum StatsPeriod {
+  Week,
+  Month,
+  Year,
+  AllTime,
+}
+
+impl StatsPeriod {
+  pub const ALL: [StatsPeriod; 4] = [StatsPeriod::Week, StatsPeriod::Month, StatsPeriod::Year, StatsPeriod::AllTime];
+
+  /// Gets the duration of this period, or `None` if it is unbounded.
+  pub fn duration(&self) -> Option<chrono::Duration> {
+    match self {
+      StatsPeriod::Week => Some(chrono::Duration::weeks(1)),
+      StatsPeriod::Month => Some(chrono::Duration::days(30)),
+      StatsPeriod::Year => Some(chrono::Duration::days(365)),
+      StatsPeriod::AllTime => None,
+    }
+  }
+}
+
+impl Default for StatsPeriod {
+  fn default() -> Self { Self::Month }
+}
+
+impl Display for StatsPeriod {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      StatsPeriod::Week => f.write_str("week"),
+      StatsPeriod::Month => f.write_str("month"),
+      StatsPeriod::Year => f.write_str("year"),
+      StatsPeriod::AllTime => f.write_str("all_time"),
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown statistics period '{0}'; expected one of: week, month, year, all_time")]
+pub struct ParseStatsPeriodError(String);
+
+impl std::str::FromStr for StatsPeriod {
+  type Err = ParseStatsPeriodError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "week" => Ok(StatsPeriod::Week),
+      "month" => Ok(StatsPeriod::Month),
+      "year" => Ok(StatsPeriod::Year),
+      "all_time" => Ok(StatsPeriod::AllTime),
+      s => Err(ParseStatsPeriodError(s.to_string())),
+    }
+  }
+}
+
+/// Query for the listening statistics of the logged-in user over `period`. Hours and days are in the time zone that is
+/// `utc_offset_minutes` ahead of UTC, as the server does not know the time zone of the user.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct UserStatsQuery {
+  pub period: StatsPeriod,
+  pub utc_offset_minutes: i32,
+}
+
+impl UserStatsQuery {
+  /// Creates a query over `period` in the local time zone of this device.
+  pub fn local(period: StatsPeriod) -> Self {
+    let utc_offset_minutes = chrono::Local::now().offset().local_minus_utc() / 60;
+    Self { period, utc_offset_minutes }
+  }
+}
+
+/// Listening statistics of a user, computed from their play history.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct UserStats {
+  pub period: StatsPeriod,
+  pub play_count: i64,
+  /// Estimated listening time in seconds. The server does not know how long each play lasted, so plays are assumed to
+  /// last until the next play, up to a maximum.
+  pub listening_seconds: i64,
+  /// Most played artists, most played first.
+  pub top_artists: Vec<PlayCount<Artist>>,
+  /// Most played albums, most played first.
+  pub top_albums: Vec<PlayCount<Album>>,
+  /// Most played tracks, most played first.
+  pub top_tracks: Vec<PlayCount<Track>>,
+  /// Number of plays that were skipped.
+  pub skip_count: i64,
+  /// Most skipped tracks, most skipped first, where the play count is the number of skips.
+  pub most_skipped_tracks: Vec<PlayCount<Track>>,
+  /// Number of plays in each hour of the day, starting at midnight.
+  pub hour_histogram: [i64; 24],
+  /// Number of consecutive days up to and including today, or up to yesterday if nothing was played yet today, with at
+  /// least one play. Not limited to `period`.
+  pub current_streak_days: u32,
+  /// Largest number of consecutive days with at least one play in `period`.
+  pub longest_streak_days: u32,
+}
+
+/// Number of times `item` was played.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct PlayCount<T> {
+  pub item: T,
+  pub play_count: i64,
+}
+
+/// Query for the year in review of the logged-in user. The year starts and ends in the time zone that is
+/// `utc_offset_minutes` ahead of UTC.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct YearReviewQuery {
+  pub utc_offset_minutes: i32,
+}
+
+impl YearReviewQuery {
+  /// Creates a query in the local time zone of this device.
+  pub fn local() -> Self {
+    Self { utc_offset_minutes: chrono::Local::now().offset().local_minus_utc() / 60 }
+  }
+}
+
+/// Summary of the listening of a user in a year, computed from their play history.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, Debug)]
+pub struct YearReview {
+  pub year: i32,
+  pub play_count: i64,
+  /// Estimated number of minutes listened, estimated like `UserStats::listening_seconds`.
+  pub minutes_listened: i64,
+  /// Most played artists, most played first.
+  pub top_artists: Vec<PlayCount<Artist>>,
+  /// Most played albums, most played first.
+  pub top_albums: Vec<PlayCount<Album>>,
+  /// Most played tracks, most played first.
+  pub top_tracks: Vec<PlayCount<Track>>,
+  /// Most skipped tracks, most skipped first, where the play count is the number of skips.
+  pub most_skipped_tracks: Vec<PlayCount<Track>>,
+  /// Most played artists that were played for the first time in the year, most played first.
+  pub new_discoveries: Vec<PlayCount<Artist>>,
+}
+
+/// Spotify source as exposed by the API, without the OAuth tokens of the Spotify account.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpotifySourceInfo {
+  pub id: i32,
+  pub user_id: i32,
+  pub enabled: bool,
+  /// Time at which this source was (soft-)deleted, or `None` if it has not been deleted.
+  pub deleted_at: Option<NaiveDateTime>,
+  /// Time at which this source was last successfully synchronized, or `None` if it has not been synchronized yet.
+  pub last_synced_at: Option<NaiveDateTime>,
+  /// Error of the last synchronization of this source, or `None` if it succeeded.
+  pub last_error: Option<String>,
+}
+
+impl From<SpotifySource> for SpotifySourceInfo {
+  fn from(source: SpotifySource) -> Self {
+    Self {
+      id: source.id,
+      user_id: source.user_id,
+      enabled: source.enabled,
+      deleted_at: source.deleted_at,
+      last_synced_at: source.last_synced_at,
+      last_error: source.last_error,
     }
   }
 }
@@ -108,3 +742,645 @@ pub struct SpotifyMeInfo {
   pub display_name: String,
 }
 
+/// Result of adding tracks to a Spotify playlist. Tracks that are not available on Spotify are skipped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct SpotifyPlaylistAddResult {
+  pub added: usize,
+  pub skipped_track_ids: Vec<i32>,
+}
+
+/// Relocation of a local source to `new_directory`, for example after moving a music folder to a new disk. When
+/// `dry_run` is set, nothing is changed, and only a preview of the relocation is returned.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct LocalSourceRelocation {
+  pub new_directory: String,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub dry_run: bool,
+}
+
+/// Result, or preview for a dry run, of relocating a local source.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct LocalSourceRelocationResult {
+  pub local_source: LocalSource,
+  pub old_directory: String,
+  pub dry_run: bool,
+  pub tracks: Vec<RelocatedTrack>,
+}
+
+/// Track of a relocated local source, with its old and new path, and whether a file exists at its new path.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct RelocatedTrack {
+  pub track_id: i32,
+  pub old_path: String,
+  pub new_path: String,
+  pub found: bool,
+}
+
+
+/// Split of an incorrectly merged album into a new album with `name`, moving the tracks with `track_ids` to it. The new
+/// album gets the artists with `artist_ids`, or the artists of the original album if empty.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AlbumSplit {
+  pub name: String,
+  pub track_ids: Vec<i32>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub artist_ids: Vec<i32>,
+}
+
+/// Query for setting the artists of a track credited in `role`, which are the main artists by default.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TrackArtistsQuery {
+  pub role: TrackArtistRole,
+}
+
+/// Criteria for filtering tracks by their detected audio features, for example to build DJ sets. Criteria that are
+/// `None` are not applied.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct AudioFeaturesFilter {
+  pub min_bpm: Option<f32>,
+  pub max_bpm: Option<f32>,
+  pub key_pitch_class: Option<i32>,
+  pub key_minor: Option<bool>,
+}
+
+/// Track search criteria. Tracks match when their title contains `query`, and when `tag` is set, when they are tagged
+/// with that tag. Machine-generated tags are only taken into account if `include_machine_generated_tags` is set.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Clone, Debug)]
+pub struct TrackSearch {
+  pub query: String,
+  pub tag: Option<String>,
+  pub include_machine_generated_tags: bool,
+}
+
+impl Default for TrackSearch {
+  fn default() -> Self { Self { query: String::new(), tag: None, include_machine_generated_tags: true } }
+}
+
+/// Page of a list: at most `limit` items starting at index `offset`. The server caps `limit`, and picks a default when
+/// it is `None`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct PageQuery {
+  pub offset: i64,
+  pub limit: Option<i64>,
+}
+
+/// Query for a random sample of `count` tracks. The server caps `count`, and picks a default when it is `None`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct RandomTracksQuery {
+  pub count: Option<i64>,
+}
+
+/// Query for the tracks to play of the album with `album_id` and of the artist with `artist_id`, or of all tracks when
+/// neither is set. Tracks are in album order (sort name of the album, then disc number, then track number), or in random
+/// order when `shuffle` is set. When `downrank_skipped` is also set, tracks the user often skips tend to be shuffled
+/// towards the end.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct PlayTracksQuery {
+  pub album_id: Option<i32>,
+  pub artist_id: Option<i32>,
+  pub shuffle: bool,
+  pub downrank_skipped: bool,
+}
+
+/// Request for at most `count` tracks similar to the tracks with `seed_track_ids`, such as for continuing playback when
+/// the queue runs out. Tracks that share artists or tags with the seed tracks are recommended, preferring tracks that
+/// the user rated highly, and avoiding tracks the user often skips when `downrank_skipped` is set. Seed tracks and
+/// tracks with `exclude_track_ids` are not recommended. The server caps `count`, and picks a default when it is `None`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone, Debug)]
+pub struct TrackRecommendationRequest {
+  pub seed_track_ids: Vec<i32>,
+  pub exclude_track_ids: Vec<i32>,
+  pub count: Option<i64>,
+  pub downrank_skipped: bool,
+}
+
+/// Report that the user stopped playing a track at `position_relative`, between 0.0 and 1.0, to play another track.
+/// Only counts as skipping the track when `position_relative` is before `SKIP_THRESHOLD`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct SkipReport {
+  pub position_relative: f64,
+}
+
+/// Column by which tracks are sorted on the server. Columns that are not listed here can only be sorted by clients.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TrackSortColumn {
+  Id,
+  Title,
+  /// Sort name of the album, then disc number, then track number.
+  Album,
+}
+
+impl Default for TrackSortColumn {
+  fn default() -> Self { Self::Id }
+}
+
+/// Sort order of tracks. Tracks that are equal in `sort` are sorted by ID.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TrackSort {
+  pub sort: TrackSortColumn,
+  pub descending: bool,
+}
+
+/// Filter of albums and artists in list views.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct HiddenFilter {
+  /// Also include albums and artists that the user hid, which are excluded by default.
+  pub include_hidden: bool,
+}
+
+/// Item that a user hid, along with the title of the track or the name of the album or artist, for managing hidden
+/// items.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct HiddenItemInfo {
+  pub item: HiddenItem,
+  /// Title of the track or name of the album or artist, or `None` if the item no longer exists.
+  pub name: Option<String>,
+}
+
+/// Filter of tracks, as the basis for listing, sampling, smart playlists, and advanced search. Tracks are only filtered by
+/// the fields that are set, and must match all of them.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone, Eq, PartialEq, Debug)]
+pub struct TrackFilter {
+  /// Only include tracks of the artist with this ID.
+  pub artist_id: Option<i32>,
+  /// Only include tracks of the album with this ID.
+  pub album_id: Option<i32>,
+  /// Only include tracks that have the tag with this name, such as a genre.
+  pub tag: Option<String>,
+  /// Only include tracks that the logged-in user rated at least this rating.
+  pub min_rating: Option<i32>,
+  /// Only include tracks that the logged-in user rated at most this rating.
+  pub max_rating: Option<i32>,
+  /// Only include tracks that have a file in the local source with this ID.
+  pub local_source_id: Option<i32>,
+  /// Only include tracks that are linked to the Spotify source with this ID.
+  pub spotify_source_id: Option<i32>,
+  /// Only include tracks that are playable from at least the kinds of sources of this availability, or that are not
+  /// playable at all if `Unavailable`.
+  pub availability: Option<Availability>,
+  /// Only include tracks that were added at or after this time.
+  pub added_after: Option<NaiveDateTime>,
+  /// Only include tracks that were added before this time.
+  pub added_before: Option<NaiveDateTime>,
+  /// Also include tracks that the user hid, which are excluded by default.
+  pub include_hidden: bool,
+}
+
+/// Page of sorted tracks, starting at index `offset` of the `total` tracks, along with the albums and artists of the
+/// tracks in the page.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Clone, Debug)]
+pub struct TracksPage {
+  pub offset: i64,
+  pub total: i64,
+  pub tracks: TracksRaw,
+}
+
+impl TracksPage {
+  /// Returns the query for the page after this page with `limit`, or `None` if this is the last page.
+  pub fn next_page(&self, limit: Option<i64>) -> Option<PageQuery> {
+    let offset = self.offset + self.tracks.tracks.len() as i64;
+    if self.tracks.tracks.is_empty() || offset >= self.total { return None; }
+    Some(PageQuery { offset, limit })
+  }
+}
+
+/// Lyrics of a track, read from a lyrics file next to the local audio file of the track.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Lyrics {
+  pub track_id: i32,
+  /// Whether all lines have a time, so that the lyrics can be shown in sync with playback.
+  pub synced: bool,
+  /// Lines of the lyrics, ordered by time if the lyrics are synced.
+  pub lines: Vec<LyricsLine>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct LyricsLine {
+  /// Time in seconds from the start of the track at which this line is sung, or `None` if the lyrics are not synced.
+  pub time: Option<f64>,
+  pub text: String,
+}
+
+impl Lyrics {
+  /// Returns the index of the line that is sung at `position` in seconds, or `None` if the lyrics are not synced or no
+  /// line is sung yet.
+  pub fn line_index_at(&self, position: f64) -> Option<usize> {
+    if !self.synced { return None; }
+    self.lines.iter().rposition(|line| line.time.map_or(false, |time| time <= position))
+  }
+}
+
+/// Technical details of a track and the files and sources it is linked to, for debugging mis-tagged or duplicated
+/// files.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct TrackTechnicalInfo {
+  pub track: Track,
+  /// Local files of the track, one for each local source that has a file of the track.
+  pub local_files: Vec<LocalTrackFileInfo>,
+  /// Spotify tracks the track is linked to.
+  pub spotify_tracks: Vec<SpotifyTrack>,
+  /// Whether the paths of the local files were hidden, because the requesting user may not see paths.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub paths_hidden: bool,
+}
+
+impl TrackTechnicalInfo {
+  /// Removes the paths of the local files, for users that may not see where files are stored on the server.
+  pub fn hide_paths(&mut self) {
+    for local_file in &mut self.local_files {
+      local_file.local_track.file_path = None;
+      local_file.local_track.file_path_key = None;
+      local_file.path = None;
+    }
+    self.paths_hidden = true;
+  }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct LocalTrackFileInfo {
+  /// Link of the track to the local source, with the file path relative to the directory of the source, the hash of
+  /// the audio data, and the bitrate found when synchronizing.
+  pub local_track: LocalTrack,
+  /// Full path of the file on the server, or `None` if the link has no file path or paths are hidden.
+  pub path: Option<String>,
+  /// Details read from the file, or `None` if the file could not be inspected.
+  pub file: Option<AudioFileInfo>,
+  /// Why the file could not be inspected, or `None` if it was inspected.
+  pub error: Option<String>,
+}
+
+/// Details read from an audio file.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AudioFileInfo {
+  /// Size of the file in bytes.
+  pub size: u64,
+  pub codec: Option<AudioCodec>,
+  /// Sample rate in Hz, or `None` if the audio could not be decoded.
+  pub sample_rate: Option<u32>,
+  /// Number of channels, or `None` if the audio could not be decoded.
+  pub channels: Option<u16>,
+  /// Duration in seconds, or `None` if it is unknown.
+  pub duration: Option<f64>,
+  /// Snapshot of the frames of the tag of the file, as frame IDs such as `TIT2` with their value as text.
+  pub tags: Vec<TagFrame>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct TagFrame {
+  pub id: String,
+  pub value: String,
+}
+
+/// Rating of multiple tracks at once.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct TrackRatings {
+  pub track_ids: Vec<i32>,
+  pub rating: i32,
+}
+
+/// Tag assigned to a track.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct AssignedTag {
+  pub tag: Tag,
+  pub machine_generated: bool,
+}
+
+/// Other version of a track: `track` along with the `relation` that links it to the original track, or to the version
+/// when `track` is the original.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct TrackVersion {
+  pub relation: TrackRelation,
+  pub track: Track,
+}
+
+/// Link to create from the track with `version_track_id` to the original track with `original_track_id`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug)]
+pub struct NewTrackRelation {
+  pub original_track_id: i32,
+  pub version_track_id: i32,
+  pub kind: TrackRelationKind,
+}
+
+/// Artist that composed tracks, along with the number of works they composed, for browsing classical music.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct ComposerInfo {
+  pub artist: Artist,
+  pub work_count: i64,
+}
+
+/// Work along with the number of its tracks, for browsing classical music.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct WorkInfo {
+  pub work: Work,
+  pub track_count: i64,
+}
+
+/// Properties of a playlist, used when creating or updating a playlist.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct PlaylistProperties {
+  pub name: String,
+  pub public: bool,
+}
+
+/// Properties of a listening session, used when creating a listening session.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug)]
+pub struct NewListeningSession {
+  /// Maximum difference in milliseconds between the playback position of a client and the position of the session,
+  /// before the client should seek to the position of the session.
+  pub drift_tolerance_ms: u32,
+}
+
+impl Default for NewListeningSession {
+  fn default() -> Self { Self { drift_tolerance_ms: 500 } }
+}
+
+/// Synchronized group playback session, hosted by one user and joined by other users.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct ListeningSession {
+  pub id: i32,
+  pub host_user_id: i32,
+  pub member_user_ids: Vec<i32>,
+  pub drift_tolerance_ms: u32,
+  pub track_id: Option<i32>,
+  pub playing: bool,
+  /// Playback position in milliseconds at `updated_at`.
+  pub position_ms: u64,
+  pub updated_at: NaiveDateTime,
+}
+
+impl ListeningSession {
+  /// Gets the expected playback position in milliseconds at `now`, taking into account the time that has passed since
+  /// the session was last updated if it is playing.
+  pub fn expected_position_ms(&self, now: NaiveDateTime) -> u64 {
+    if self.playing {
+      let elapsed = (now - self.updated_at).num_milliseconds().max(0) as u64;
+      self.position_ms + elapsed
+    } else {
+      self.position_ms
+    }
+  }
+
+  /// Checks whether a client at `position_ms` at `now` has drifted too far from the session.
+  pub fn is_drifted(&self, position_ms: u64, now: NaiveDateTime) -> bool {
+    let expected = self.expected_position_ms(now);
+    let drift = if expected > position_ms { expected - position_ms } else { position_ms - expected };
+    drift > self.drift_tolerance_ms as u64
+  }
+}
+
+/// Playback command sent by the host of a listening session, which is fanned out to all members.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug)]
+pub enum ListeningSessionCommand {
+  Play,
+  Pause,
+  Seek { position_ms: u64 },
+  ChangeTrack { track_id: i32 },
+}
+
+/// Playback state of a user in the shape of the media player integration of Home Assistant. The server only knows about
+/// playback in listening sessions, so this reflects the listening session hosted by the user, or otherwise the first
+/// listening session the user is a member of.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct HomeAssistantState {
+  pub state: HomeAssistantPlayerState,
+  pub listening_session_id: Option<i32>,
+  pub media: Option<HomeAssistantMedia>,
+  /// Playback position in seconds at `media_position_updated_at`.
+  pub media_position: Option<f64>,
+  pub media_position_updated_at: Option<NaiveDateTime>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HomeAssistantPlayerState {
+  Idle,
+  Playing,
+  Paused,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct HomeAssistantMedia {
+  pub track_id: i32,
+  pub title: String,
+  /// Names of the artists of the track, joined with commas.
+  pub artist: Option<String>,
+  pub album_id: i32,
+  pub album_name: String,
+}
+
+/// Control verb sent by Home Assistant, applied to the listening session hosted by the user.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "verb", rename_all = "snake_case"))]
+#[derive(Copy, Clone, Debug)]
+pub enum HomeAssistantCommand {
+  Play,
+  Pause,
+  PlayPause,
+  /// Seeks to `position` in seconds.
+  Seek { position: f64 },
+  PlayMedia { track_id: i32 },
+}
+
+/// Number of items in the library, for showing in Home Assistant.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct LibrarySummary {
+  pub track_count: i64,
+  pub album_count: i64,
+  pub artist_count: i64,
+  pub playlist_count: i64,
+}
+
+/// Properties of a party, used when creating a party.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NewParty {
+  /// Maximum number of requests (searches and enqueues) a guest may make per minute, or `None` for no limit.
+  pub max_guest_requests_per_minute: Option<u32>,
+}
+
+/// Party hosted by a user, where guests without an account can search for tracks and request them by adding them to a
+/// queue controlled by the host.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct Party {
+  pub id: i32,
+  pub host_user_id: i32,
+  pub max_guest_requests_per_minute: Option<u32>,
+  pub queue: Vec<PartyQueueItem>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct PartyQueueItem {
+  pub track_id: i32,
+  pub guest_name: String,
+}
+
+/// Properties of a party guest, used when inviting a guest.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct NewPartyGuest {
+  pub name: String,
+  /// Number of seconds the guest token stays valid.
+  pub valid_for_secs: u32,
+}
+
+/// Guest of a party, identified by a secret token that is only valid until `expires_at`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct PartyGuest {
+  pub token: String,
+  pub party_id: i32,
+  pub name: String,
+  pub expires_at: NaiveDateTime,
+}
+
+/// Event pushed from the server to connected clients.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub enum Event {
+  NewRelease(NewRelease),
+  Notification(Notification),
+  /// Tracks, collaborators, or properties of the playlist with given ID were changed.
+  PlaylistChanged(i32),
+  /// The playlist with given ID was deleted.
+  PlaylistDeleted(i32),
+  /// The state or members of a listening session changed; members should synchronize their playback to it.
+  ListeningSessionChanged(ListeningSession),
+  /// The listening session with given ID was ended by its host.
+  ListeningSessionEnded(i32),
+  /// The queue of a party changed.
+  PartyChanged(Party),
+  /// The party with given ID was ended by its host.
+  PartyEnded(i32),
+  /// The status of synchronizing sources changed.
+  SyncStatusChanged(SyncStatus),
+}
+
+/// Query for events published after the event with sequence number `since`. Without `since`, no events are returned,
+/// only the sequence number to start polling from.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct EventsQuery {
+  pub since: Option<u64>,
+}
+
+/// Events polled from the server, for clients that cannot receive events over a WebSocket.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug)]
+pub struct EventsPoll {
+  pub events: Vec<Event>,
+  /// Sequence number to pass as `since` in the next poll.
+  pub next: u64,
+  /// Whether events were missed because they were dropped from the history of the server before they were polled.
+  /// Clients should then refresh the state they keep up to date with events.
+  pub missed: bool,
+}
+
+/// Webhook as exposed by the API, without its secret.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WebhookInfo {
+  pub id: i32,
+  pub url: String,
+  pub on_sync_completed: bool,
+  pub on_new_albums: bool,
+  pub on_errors: bool,
+  pub enabled: bool,
+}
+
+impl From<Webhook> for WebhookInfo {
+  fn from(webhook: Webhook) -> Self {
+    Self {
+      id: webhook.id,
+      url: webhook.url,
+      on_sync_completed: webhook.on_sync_completed,
+      on_new_albums: webhook.on_new_albums,
+      on_errors: webhook.on_errors,
+      enabled: webhook.enabled,
+    }
+  }
+}
+
+/// Name of the header of webhook calls that contains the hex-encoded HMAC-SHA256 signature of the timestamp, followed by
+/// a `.` and the payload, keyed by the secret of the webhook.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Musium-Signature";
+
+/// Name of the header of webhook calls that contains the time of the call, in seconds since the Unix epoch. Receivers
+/// should reject calls of which the timestamp is too old, so that calls cannot be replayed.
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Musium-Timestamp";
+
+/// Payload that webhooks are called with, as JSON.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "kind", rename_all = "snake_case"))]
+#[derive(Clone, Debug)]
+pub enum WebhookPayload {
+  SyncCompleted {
+    started_at: NaiveDateTime,
+    ended_at: NaiveDateTime,
+    summary: SyncSummary,
+  },
+  NewAlbums {
+    albums: Vec<Album>,
+  },
+  Error {
+    errors: Vec<String>,
+  },
+}
+
+/// Query of long-polling requests: the server holds the request until there is something new to respond with, or until
+/// `wait` has passed. Formatted as a number of seconds optionally followed by `s`, such as `30s`. The server caps `wait`,
+/// and responds immediately when it is `None`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Default, Clone, Debug)]
+pub struct WaitQuery {
+  pub wait: Option<String>,
+}
+
+impl WaitQuery {
+  pub fn new(wait: Duration) -> Self { Self { wait: Some(format!("{}s", wait.as_secs())) } }
+
+  /// Parses `wait` into a duration, or returns `None` if it is not set.
+  pub fn duration(&self) -> Result<Option<Duration>, std::num::ParseIntError> {
+    self.wait.as_ref().map(|wait| {
+      let seconds = wait.strip_suffix('s').unwrap_or(wait);
+      seconds.parse::<u64>().map(Duration::from_secs)
+    }).transpose()
+  }
+}
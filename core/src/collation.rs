@@ -0,0 +1,63 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::api::AlphabetSection;
+
+// Normalization, collation, and sorting of names of albums, tracks, and artists.
+
+/// Normalizes `name` to Unicode Normalization Form C (NFC), so that names that only differ in how they are encoded, for
+/// example a precomposed "ó" versus an "o" followed by a combining acute accent, are stored the same.
+pub fn normalize_name(name: &str) -> String {
+  name.nfc().collect()
+}
+
+/// Creates the collation key of `name`, which is equal for names that only differ in encoding, case, or accents. For
+/// example, "Sigur Rós", "sigur ros", and "SIGUR RO\u{301}S" all have collation key "sigur ros". Names are compared by
+/// their collation key during synchronization and search.
+pub fn name_collation_key(name: &str) -> String {
+  name.trim()
+    .nfkd()
+    .filter(|c| !is_combining_mark(*c))
+    .flat_map(char::to_lowercase)
+    .collect()
+}
+
+/// Creates the sort name of `name` by moving a leading article of `articles` to the end, so that for example "The
+/// Beatles" becomes "Beatles, The". Articles are matched regardless of case, and only when followed by whitespace and
+/// more text, so that "Theatre" and "The" are left alone.
+pub fn sort_name(name: &str, articles: &[String]) -> String {
+  let name = name.trim();
+  for article in articles {
+    if let (Some(prefix), Some(rest)) = (name.get(..article.len()), name.get(article.len()..)) {
+      if prefix.to_lowercase() == article.to_lowercase() && rest.starts_with(char::is_whitespace) && !rest.trim_start().is_empty() {
+        return format!("{}, {}", rest.trim_start(), prefix);
+      }
+    }
+  }
+  name.to_string()
+}
+
+/// Initial of names that do not start with a letter from A to Z in an alphabet index.
+pub const OTHER_INITIAL: char = '#';
+
+/// Gets the initial of `name` in an alphabet index: its first letter without accents in uppercase, so that "Émilie"
+/// is listed under E, or `OTHER_INITIAL` if it does not start with a letter from A to Z.
+pub fn alphabet_initial(name: &str) -> char {
+  name_collation_key(name).chars().next()
+    .filter(|c| c.is_ascii_alphabetic())
+    .map_or(OTHER_INITIAL, |c| c.to_ascii_uppercase())
+}
+
+/// Creates the alphabet index of `names` in the order they are listed, merging consecutive names with the same initial
+/// into one section.
+pub fn alphabet_index<'a>(names: impl IntoIterator<Item=&'a str>) -> Vec<AlphabetSection> {
+  let mut sections: Vec<AlphabetSection> = Vec::new();
+  for name in names {
+    let initial = alphabet_initial(name);
+    match sections.last_mut() {
+      Some(section) if section.initial == initial => section.count += 1,
+      _ => sections.push(AlphabetSection { initial, count: 1 }),
+    }
+  }
+  sections
+}
@@ -13,3 +13,4 @@ pub mod error;
 pub mod format_error;
 pub mod untagged_result;
 pub mod panic;
+pub mod collation;
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use itertools::Itertools;
@@ -16,14 +17,24 @@ pub struct AlbumsRaw {
   pub albums: Vec<Album>,
   pub artists: Vec<Artist>,
   pub album_artists: Vec<AlbumArtist>,
+  /// Availability of albums, by album ID. Albums that are missing are unavailable.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub availability: HashMap<i32, Availability>,
 }
 
+/// Albums with indexed lookups by album and artist ID. The indices are built on creation, so fields must not be modified
+/// directly.
 #[derive(Default, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(from = "AlbumsRaw", into = "AlbumsRaw"))]
 pub struct Albums {
   pub albums: Vec<Album>,
   pub artists: HashMap<i32, Artist>,
   pub album_artists: HashMap<i32, Vec<i32>>,
+  pub availability: HashMap<i32, Availability>,
+  /// Index into `albums`, by album ID.
+  album_indices: HashMap<i32, usize>,
+  /// IDs of albums, by album artist ID.
+  artist_albums: HashMap<i32, Vec<i32>>,
 }
 
 impl Albums {
@@ -31,29 +42,64 @@ impl Albums {
     albums: Vec<Album>,
     artists: Vec<Artist>,
     album_artists: Vec<AlbumArtist>,
+    availability: HashMap<i32, Availability>,
   ) -> Self {
     let artists = artists.into_iter().map(|a| (a.id, a)).collect();
-    let album_artists = album_artists.into_iter().map(|aa| (aa.album_id, aa.artist_id)).into_group_map();
-    Self { albums, artists, album_artists }
+    let album_artists: HashMap<i32, Vec<i32>> = album_artists.into_iter().map(|aa| (aa.album_id, aa.artist_id)).into_group_map();
+    let album_indices = index_by_id(&albums, |a| a.id);
+    let artist_albums = invert(&album_artists);
+    Self { albums, artists, album_artists, availability, album_indices, artist_albums }
   }
 
   pub fn iter(&self) -> impl Iterator<Item=(&Album, impl Iterator<Item=&Artist>)> + '_ {
-    let Albums { albums, artists, album_artists } = &self;
-    albums.into_iter().filter_map(move |album| {
-      let album_artists: &Vec<i32> = album_artists.get(&album.id)?;
-      let album_artists: Vec<&Artist> = album_artists.into_iter().filter_map(|aa| artists.get(aa)).collect();
-      return Some((album, album_artists.into_iter()));
-    })
+    self.albums.iter().filter_map(move |album| self.with_artists(album))
   }
 
   pub fn len(&self) -> usize {
     self.albums.len()
   }
+
+  pub fn availability(&self, album_id: i32) -> Availability {
+    self.availability.get(&album_id).copied().unwrap_or_default()
+  }
+
+  pub fn get_album(&self, album_id: i32) -> Option<&Album> {
+    self.album_indices.get(&album_id).and_then(|i| self.albums.get(*i))
+  }
+
+  pub fn get_artist(&self, artist_id: i32) -> Option<&Artist> {
+    self.artists.get(&artist_id)
+  }
+
+  /// Gets the albums of which the artist with `artist_id` is an album artist.
+  pub fn albums_of_artist(&self, artist_id: i32) -> impl Iterator<Item=&Album> + '_ {
+    self.artist_albums.get(&artist_id).into_iter().flatten().filter_map(move |album_id| self.get_album(*album_id))
+  }
+
+  /// Gets the albums with their album artists, sorted by sort name.
+  pub fn sorted_by_sort_name(&self) -> Vec<(&Album, impl Iterator<Item=&Artist>)> {
+    let mut albums: Vec<&Album> = self.albums.iter().collect();
+    albums.sort_by(|a, b| a.sort_name.cmp(&b.sort_name).then(a.id.cmp(&b.id)));
+    albums.into_iter().filter_map(|album| self.with_artists(album)).collect()
+  }
+
+  fn with_artists<'a>(&'a self, album: &'a Album) -> Option<(&'a Album, impl Iterator<Item=&'a Artist>)> {
+    let album_artists: &Vec<i32> = self.album_artists.get(&album.id)?;
+    let album_artists: Vec<&Artist> = album_artists.iter().filter_map(|aa| self.artists.get(aa)).collect();
+    Some((album, album_artists.into_iter()))
+  }
 }
 
 impl From<AlbumsRaw> for Albums {
   fn from(albums: AlbumsRaw) -> Self {
-    Albums::from(albums.albums, albums.artists, albums.album_artists)
+    Albums::from(albums.albums, albums.artists, albums.album_artists, albums.availability)
+  }
+}
+
+impl From<Albums> for AlbumsRaw {
+  fn from(albums: Albums) -> Self {
+    let album_artists = flatten_group_map(albums.album_artists, |album_id, artist_id| AlbumArtist { album_id, artist_id });
+    AlbumsRaw { albums: albums.albums, artists: albums.artists.into_values().collect(), album_artists, availability: albums.availability }
   }
 }
 
@@ -69,16 +115,32 @@ pub struct TracksRaw {
   pub artists: Vec<Artist>,
   pub album_artists: Vec<AlbumArtist>,
   pub track_artists: Vec<TrackArtist>,
+  /// Availability of tracks, by track ID. Tracks that are missing are unavailable.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub availability: HashMap<i32, Availability>,
 }
 
+/// Tracks with indexed lookups by track, album, and artist ID. The indices are built on creation, so fields must not be
+/// modified directly.
 #[derive(Default, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(from = "TracksRaw", into = "TracksRaw"))]
 pub struct Tracks {
   pub albums: HashMap<i32, Album>,
   pub tracks: Vec<Track>,
   pub artists: HashMap<i32, Artist>,
   pub album_artists: HashMap<i32, Vec<i32>>,
+  /// IDs of main artists, by track ID.
   pub track_artists: HashMap<i32, Vec<i32>>,
+  /// Roles and IDs of artists credited in other roles than the main artist, such as featured artists and remixers, by
+  /// track ID.
+  pub track_credits: HashMap<i32, Vec<(TrackArtistRole, i32)>>,
+  pub availability: HashMap<i32, Availability>,
+  /// Index into `tracks`, by track ID.
+  track_indices: HashMap<i32, usize>,
+  /// IDs of tracks, by album ID.
+  album_tracks: HashMap<i32, Vec<i32>>,
+  /// IDs of tracks, by main track artist ID.
+  artist_tracks: HashMap<i32, Vec<i32>>,
 }
 
 impl<'a> Tracks {
@@ -88,17 +150,20 @@ impl<'a> Tracks {
     artists: Vec<Artist>,
     album_artists: Vec<AlbumArtist>,
     track_artists: Vec<TrackArtist>,
+    availability: HashMap<i32, Availability>,
   ) -> Self {
     let albums = albums.into_iter().map(|a| (a.id, a)).collect();
     let artists = artists.into_iter().map(|a| (a.id, a)).collect();
-    let track_artists = track_artists.into_iter().map(|ta| (ta.track_id, ta.artist_id)).into_group_map();
+    let (track_artists, track_credits) = group_track_artists(track_artists);
     let album_artists = album_artists.into_iter().map(|aa| (aa.album_id, aa.artist_id)).into_group_map();
-    Self { tracks, albums, artists, track_artists, album_artists }
+    let track_indices = index_by_id(&tracks, |t| t.id);
+    let album_tracks = tracks.iter().map(|t| (t.album_id, t.id)).into_group_map();
+    let artist_tracks = invert(&track_artists);
+    Self { tracks, albums, artists, track_artists, track_credits, album_artists, availability, track_indices, album_tracks, artist_tracks }
   }
 
   pub fn iter(&'a self) -> impl Iterator<Item=TrackInfo<'a>> + ExactSizeIterator + Clone + 'a {
-    let Tracks { tracks, albums, artists, track_artists, album_artists } = &self;
-    tracks.into_iter().map(move |track| { TrackInfo { track, albums, artists, track_artists, album_artists } })
+    self.tracks.iter().map(move |track| self.info(track))
   }
 
   pub fn len(&self) -> usize {
@@ -108,11 +173,66 @@ impl<'a> Tracks {
   pub fn get_track(&self, index: usize) -> Option<&Track> {
     self.tracks.get(index)
   }
+
+  pub fn get_track_by_id(&'a self, track_id: i32) -> Option<TrackInfo<'a>> {
+    self.track_indices.get(&track_id).and_then(|i| self.tracks.get(*i)).map(|track| self.info(track))
+  }
+
+  /// Gets the tracks of the album with `album_id`, in the order they were added.
+  pub fn tracks_of_album(&'a self, album_id: i32) -> impl Iterator<Item=TrackInfo<'a>> + 'a {
+    self.album_tracks.get(&album_id).into_iter().flatten().filter_map(move |track_id| self.get_track_by_id(*track_id))
+  }
+
+  /// Gets the tracks of which the artist with `artist_id` is a main track artist, in the order they were added.
+  pub fn tracks_of_artist(&'a self, artist_id: i32) -> impl Iterator<Item=TrackInfo<'a>> + 'a {
+    self.artist_tracks.get(&artist_id).into_iter().flatten().filter_map(move |track_id| self.get_track_by_id(*track_id))
+  }
+
+  /// Gets the tracks sorted by `compare`.
+  pub fn sorted_by(&'a self, mut compare: impl FnMut(&TrackInfo<'a>, &TrackInfo<'a>) -> Ordering) -> Vec<TrackInfo<'a>> {
+    let mut tracks: Vec<_> = self.iter().collect();
+    tracks.sort_by(|a, b| compare(a, b));
+    tracks
+  }
+
+  /// Gets the tracks sorted by the sort name of their album, then disc number, then track number.
+  pub fn sorted_by_album(&'a self) -> Vec<TrackInfo<'a>> {
+    self.sorted_by(|a, b| {
+      let album_sort_name = |t: &TrackInfo| t.album().map(|album| album.sort_name.clone());
+      album_sort_name(a).cmp(&album_sort_name(b))
+        .then(a.track.disc_number.cmp(&b.track.disc_number))
+        .then(a.track.track_number.cmp(&b.track.track_number))
+        .then(a.track.id.cmp(&b.track.id))
+    })
+  }
+
+  fn info(&'a self, track: &'a Track) -> TrackInfo<'a> {
+    let Tracks { albums, artists, track_artists, track_credits, album_artists, availability, .. } = &self;
+    TrackInfo { track, albums, artists, track_artists, track_credits, album_artists, availability }
+  }
 }
 
 impl From<TracksRaw> for Tracks {
   fn from(tracks: TracksRaw) -> Self {
-    Tracks::from(tracks.albums, tracks.tracks, tracks.artists, tracks.album_artists, tracks.track_artists)
+    Tracks::from(tracks.albums, tracks.tracks, tracks.artists, tracks.album_artists, tracks.track_artists, tracks.availability)
+  }
+}
+
+impl From<Tracks> for TracksRaw {
+  fn from(tracks: Tracks) -> Self {
+    let album_artists = flatten_group_map(tracks.album_artists, |album_id, artist_id| AlbumArtist { album_id, artist_id });
+    let track_artists = flatten_group_map(tracks.track_artists, |track_id, artist_id| TrackArtist { track_id, artist_id, role: TrackArtistRole::Main })
+      .into_iter()
+      .chain(flatten_group_map(tracks.track_credits, |track_id, (role, artist_id)| TrackArtist { track_id, artist_id, role }))
+      .collect();
+    TracksRaw {
+      albums: tracks.albums.into_values().collect(),
+      tracks: tracks.tracks,
+      artists: tracks.artists.into_values().collect(),
+      album_artists,
+      track_artists,
+      availability: tracks.availability,
+    }
   }
 }
 
@@ -122,6 +242,8 @@ pub struct TrackInfo<'a> {
   artists: &'a HashMap<i32, Artist>,
   album_artists: &'a HashMap<i32, Vec<i32>>,
   track_artists: &'a HashMap<i32, Vec<i32>>,
+  track_credits: &'a HashMap<i32, Vec<(TrackArtistRole, i32)>>,
+  availability: &'a HashMap<i32, Availability>,
 }
 
 impl<'a> TrackInfo<'a> {
@@ -135,6 +257,18 @@ impl<'a> TrackInfo<'a> {
     self.track_artists.get(&self.track.id).into_iter().flat_map(move |ids| ids.into_iter()).filter_map(move |ta| self.artists.get(ta))
   }
 
+  /// Gets the artists credited in other roles than the main artist, along with their role.
+  #[inline]
+  pub fn track_credits(&self) -> impl Iterator<Item=(TrackArtistRole, &Artist)> {
+    self.track_credits.get(&self.track.id).into_iter().flat_map(move |credits| credits.into_iter()).filter_map(move |(role, artist_id)| self.artists.get(artist_id).map(|artist| (*role, artist)))
+  }
+
+  /// Gets the artists credited in `role` on this track.
+  #[inline]
+  pub fn track_artists_with_role(&self, role: TrackArtistRole) -> impl Iterator<Item=&Artist> {
+    self.track_credits().filter(move |(r, _)| *r == role).map(|(_, artist)| artist)
+  }
+
   #[inline]
   pub fn album(&self) -> Option<&Album> {
     self.albums.get(&self.track.album_id)
@@ -144,4 +278,47 @@ impl<'a> TrackInfo<'a> {
   pub fn album_artists(&self) -> impl Iterator<Item=&Artist> {
     self.album_artists.get(&self.track.album_id).into_iter().flat_map(move |ids| ids.into_iter()).filter_map(move |ta| self.artists.get(ta))
   }
+
+  #[inline]
+  pub fn availability(&self) -> Availability {
+    self.availability.get(&self.track.id).copied().unwrap_or_default()
+  }
+}
+
+//
+// Indices
+//
+
+/// Creates an index of the position of each of `items` in `items`, by the ID that `id` gets from it.
+fn index_by_id<T>(items: &[T], id: impl Fn(&T) -> i32) -> HashMap<i32, usize> {
+  items.iter().enumerate().map(|(i, item)| (id(item), i)).collect()
+}
+
+/// Inverts a one-to-many relation, such as album ID to artist IDs into artist ID to album IDs.
+fn invert(relation: &HashMap<i32, Vec<i32>>) -> HashMap<i32, Vec<i32>> {
+  relation.iter().flat_map(|(from, tos)| tos.iter().map(move |to| (*to, *from))).into_group_map()
+}
+
+/// Groups `track_artists` into IDs of main artists by track ID, and roles and IDs of artists in other roles by track ID.
+fn group_track_artists(track_artists: Vec<TrackArtist>) -> (HashMap<i32, Vec<i32>>, HashMap<i32, Vec<(TrackArtistRole, i32)>>) {
+  let (main, credits): (Vec<TrackArtist>, Vec<TrackArtist>) = track_artists.into_iter().partition(|ta| ta.role == TrackArtistRole::Main);
+  let main = main.into_iter().map(|ta| (ta.track_id, ta.artist_id)).into_group_map();
+  let credits = credits.into_iter().map(|ta| (ta.track_id, (ta.role, ta.artist_id))).into_group_map();
+  (main, credits)
+}
+
+fn flatten_group_map<V, T>(group_map: HashMap<i32, Vec<V>>, f: impl Fn(i32, V) -> T) -> Vec<T> {
+  group_map.into_iter().flat_map(|(from, tos)| tos.into_iter().map(move |to| (from, to))).map(|(from, to)| f(from, to)).collect()
+}
+
+//
+// Playlist
+//
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlaylistRaw {
+  pub playlist: Playlist,
+  pub playlist_tracks: Vec<PlaylistTrack>,
+  pub collaborators: Vec<PlaylistCollaborator>,
 }
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Error, Formatter};
+use std::str::FromStr;
 
 use chrono::NaiveDateTime;
 
@@ -21,17 +22,36 @@ pub mod collection;
 pub struct Album {
   pub id: i32,
   pub name: String,
+  /// Collation key of `name`, for comparing names regardless of encoding, case, and accents.
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub name_key: String,
+  /// Name for sorting, such as "Beatles, The" for "The Beatles".
+  pub sort_name: String,
+  /// Whether `sort_name` was set manually, instead of computed from `name`.
+  pub sort_name_overridden: bool,
 }
 
 #[derive(Default, Debug)]
 #[cfg_attr(feature = "diesel", derive(Insertable), table_name = "album")]
 pub struct NewAlbum {
   pub name: String,
+  pub name_key: String,
+  pub sort_name: String,
+}
+
+/// Front cover of an album, uploaded by a user or fetched from the Cover Art Archive. The image is stored as a pinned
+/// blob in the media cache.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(album_id), table_name = "album_cover", belongs_to(Album))]
+pub struct AlbumCover {
+  pub album_id: i32,
+  pub blob_hash: String,
+  pub updated_at: NaiveDateTime,
 }
 
 // Track
 
-#[derive(Default, Clone, PartialOrd, PartialEq, Debug)]
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations, AsChangeset), belongs_to(Album), table_name = "track", changeset_options(treat_none_as_null = "true"))]
 pub struct Track {
@@ -42,6 +62,23 @@ pub struct Track {
   pub track_number: Option<i32>,
   pub track_total: Option<i32>,
   pub title: String,
+  /// Collation key of `title`, for comparing titles regardless of encoding, case, and accents.
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub title_key: String,
+  /// When the track was first synchronized from any source.
+  pub added_at: NaiveDateTime,
+  /// Whether the track has explicit content, or `None` if none of its sources say.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub explicit: Option<bool>,
+  /// ID of the classical work the track is a movement of, or `None` if it is not part of a work.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub work_id: Option<i32>,
+  /// Name of the movement within the work, such as "I. Allegro".
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub movement: Option<String>,
+  /// Number of the movement within the work.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub movement_number: Option<i32>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -53,6 +90,49 @@ pub struct NewTrack {
   pub track_number: Option<i32>,
   pub track_total: Option<i32>,
   pub title: String,
+  pub title_key: String,
+  pub explicit: Option<bool>,
+}
+
+// Availability
+
+/// Whether an album or track can be played, and from which kinds of sources. Tracks are available locally when they
+/// have a file in an enabled local source, and available for streaming when they are linked to an enabled Spotify
+/// source. Albums are available from a kind of source when any of their tracks are.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Availability {
+  Unavailable,
+  Local,
+  Streaming,
+  LocalAndStreaming,
+}
+
+impl Default for Availability {
+  fn default() -> Self { Availability::Unavailable }
+}
+
+impl Availability {
+  pub fn new(local: bool, streaming: bool) -> Self {
+    match (local, streaming) {
+      (false, false) => Availability::Unavailable,
+      (true, false) => Availability::Local,
+      (false, true) => Availability::Streaming,
+      (true, true) => Availability::LocalAndStreaming,
+    }
+  }
+
+  #[inline]
+  pub fn is_local(&self) -> bool { matches!(self, Availability::Local | Availability::LocalAndStreaming) }
+  #[inline]
+  pub fn is_streaming(&self) -> bool { matches!(self, Availability::Streaming | Availability::LocalAndStreaming) }
+  #[inline]
+  pub fn is_playable(&self) -> bool { *self != Availability::Unavailable }
+
+  /// Combines two availabilities into one that is available from the sources of both.
+  pub fn union(self, other: Self) -> Self {
+    Self::new(self.is_local() || other.is_local(), self.is_streaming() || other.is_streaming())
+  }
 }
 
 // Artist
@@ -63,22 +143,95 @@ pub struct NewTrack {
 pub struct Artist {
   pub id: i32,
   pub name: String,
+  /// Collation key of `name`, for comparing names regardless of encoding, case, and accents.
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub name_key: String,
+  /// Name for sorting, such as "Beatles, The" for "The Beatles".
+  pub sort_name: String,
+  /// Whether `sort_name` was set manually, instead of computed from `name`.
+  pub sort_name_overridden: bool,
 }
 
 #[derive(Default, Debug)]
 #[cfg_attr(feature = "diesel", derive(Insertable), table_name = "artist")]
 pub struct NewArtist {
   pub name: String,
+  pub name_key: String,
+  pub sort_name: String,
+}
+
+/// Image and short biography of an artist, fetched from TheAudioDB or Wikipedia. All fields except `fetched_at` are
+/// `None` when nothing was found. The image is stored as an unpinned blob in the media cache, and may have been evicted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(artist_id), table_name = "artist_info", belongs_to(Artist), changeset_options(treat_none_as_null = "true"))]
+pub struct ArtistInfo {
+  pub artist_id: i32,
+  pub biography: Option<String>,
+  pub image_hash: Option<String>,
+  /// URL of the page the biography and image were taken from.
+  pub source_url: Option<String>,
+  pub fetched_at: NaiveDateTime,
 }
 
 // Track-artist
 
+/// Role in which an artist is credited on a track. Stored in the `role` column of `track_artist` as its discriminant,
+/// so existing discriminants must never change; new roles get the next unused integer.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum TrackArtistRole {
+  /// Main artist of the track, which is listed as the track artist.
+  Main = 0,
+  /// Artist featured on the track, such as "B" in "A feat. B".
+  Featured = 1,
+  /// Artist that remixed the track.
+  Remixer = 2,
+  /// Composer of the track, which is usually only credited on classical music.
+  Composer = 3,
+  /// Conductor of the orchestra performing the track.
+  Conductor = 4,
+  /// Performer of the track other than the main artist, such as a soloist or an orchestra.
+  Performer = 5,
+}
+
+impl TrackArtistRole {
+  pub const ALL: [TrackArtistRole; 6] = [TrackArtistRole::Main, TrackArtistRole::Featured, TrackArtistRole::Remixer, TrackArtistRole::Composer, TrackArtistRole::Conductor, TrackArtistRole::Performer];
+}
+
+impl Default for TrackArtistRole {
+  fn default() -> Self { TrackArtistRole::Main }
+}
+
+impl Display for TrackArtistRole {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      TrackArtistRole::Main => "main",
+      TrackArtistRole::Featured => "featured",
+      TrackArtistRole::Remixer => "remixer",
+      TrackArtistRole::Composer => "composer",
+      TrackArtistRole::Conductor => "conductor",
+      TrackArtistRole::Performer => "performer",
+    })
+  }
+}
+
+impl FromStr for TrackArtistRole {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    TrackArtistRole::ALL.iter().copied().find(|role| role.to_string() == s).ok_or_else(|| format!("Unrecognized track artist role '{}'", s))
+  }
+}
+
+/// Credit of the artist with `artist_id` on the track with `track_id` in `role`.
 #[derive(Default, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), primary_key(track_id, artist_id), table_name = "track_artist", belongs_to(Track), belongs_to(Artist))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), primary_key(track_id, artist_id, role), table_name = "track_artist", belongs_to(Track), belongs_to(Artist))]
 pub struct TrackArtist {
   pub track_id: i32,
   pub artist_id: i32,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub role: TrackArtistRole,
 }
 
 #[derive(Default, Copy, Clone, Debug)]
@@ -86,6 +239,40 @@ pub struct TrackArtist {
 pub struct NewTrackArtist {
   pub track_id: i32,
   pub artist_id: i32,
+  pub role: TrackArtistRole,
+}
+
+/// Maps `TrackArtistRole` to and from its integer discriminant in the database.
+#[cfg(feature = "diesel")]
+mod track_artist_role_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::TrackArtistRole;
+
+  impl<DB: Backend> ToSql<Integer, DB> for TrackArtistRole where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for TrackArtistRole where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(TrackArtistRole::Main),
+        1 => Ok(TrackArtistRole::Featured),
+        2 => Ok(TrackArtistRole::Remixer),
+        3 => Ok(TrackArtistRole::Composer),
+        4 => Ok(TrackArtistRole::Conductor),
+        5 => Ok(TrackArtistRole::Performer),
+        v => Err(format!("Unrecognized track artist role {}", v).into()),
+      }
+    }
+  }
 }
 
 // Album-artist
@@ -106,6 +293,30 @@ pub struct NewAlbumArtist {
 }
 
 
+// Work
+
+/// Classical work, such as a symphony, of which tracks are the movements.
+#[derive(Default, Clone, PartialOrd, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, AsChangeset), table_name = "work", changeset_options(treat_none_as_null = "true"))]
+pub struct Work {
+  pub id: i32,
+  pub name: String,
+  /// Collation key of `name`, for comparing names regardless of encoding, case, and accents.
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub name_key: String,
+  /// ID of the artist that composed the work, or `None` if unknown.
+  pub composer_id: Option<i32>,
+}
+
+#[derive(Default, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "work")]
+pub struct NewWork {
+  pub name: String,
+  pub name_key: String,
+  pub composer_id: Option<i32>,
+}
+
 //
 // Local source and linked data
 //
@@ -117,6 +328,12 @@ pub struct LocalSource {
   pub id: i32,
   pub enabled: bool,
   pub directory: String,
+  /// Whether to skip synchronizing this source when its directory is unavailable (e.g., an unmounted network share),
+  /// instead of marking all its tracks as removed.
+  pub skip_if_unavailable: bool,
+  /// Time at which this source was (soft-)deleted, or `None` if it has not been deleted. Links of a deleted source are
+  /// preserved, so that they can be reused when the source is restored or its directory is added again.
+  pub deleted_at: Option<NaiveDateTime>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -125,8 +342,13 @@ pub struct LocalSource {
 pub struct NewLocalSource {
   pub enabled: bool,
   pub directory: String,
+  #[cfg_attr(feature = "serde", serde(default = "default_skip_if_unavailable"))]
+  pub skip_if_unavailable: bool,
 }
 
+#[cfg(feature = "serde")]
+fn default_skip_if_unavailable() -> bool { true }
+
 #[derive(Default, Copy, Clone, PartialOrd, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), primary_key(album_id, local_source_id), table_name = "local_album", belongs_to(Album), belongs_to(LocalSource))]
@@ -149,7 +371,20 @@ pub struct LocalTrack {
   pub track_id: i32,
   pub local_source_id: i32,
   pub file_path: Option<String>,
+  /// Normalized `file_path` for comparing paths independent of platform-specific path semantics, such as path
+  /// separators and case-insensitivity.
+  pub file_path_key: Option<String>,
   pub hash: i64,
+  /// Bitrate in kbit/s, or `None` if unknown.
+  pub bitrate: Option<i32>,
+  /// ReplayGain track gain in dB, imported from the tags of the file.
+  pub track_gain: Option<f32>,
+  /// ReplayGain track peak as a linear sample amplitude, where 1.0 is full scale.
+  pub track_peak: Option<f32>,
+  /// ReplayGain album gain in dB, imported from the tags of the file.
+  pub album_gain: Option<f32>,
+  /// ReplayGain album peak as a linear sample amplitude, where 1.0 is full scale.
+  pub album_peak: Option<f32>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -158,7 +393,13 @@ pub struct NewLocalTrack {
   pub track_id: i32,
   pub local_source_id: i32,
   pub file_path: Option<String>,
+  pub file_path_key: Option<String>,
   pub hash: i64,
+  pub bitrate: Option<i32>,
+  pub track_gain: Option<f32>,
+  pub track_peak: Option<f32>,
+  pub album_gain: Option<f32>,
+  pub album_peak: Option<f32>,
 }
 
 #[derive(Default, Copy, Clone, PartialOrd, PartialEq, Debug)]
@@ -180,8 +421,9 @@ pub struct NewLocalArtist {
 // Spotify data
 //
 
+/// Spotify account of a user that is synchronized as a source. Contains the OAuth tokens of the account, and is
+/// therefore not serializable: API responses use `api::SpotifySourceInfo` instead.
 #[derive(Clone, PartialOrd, PartialEq, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations, AsChangeset), table_name = "spotify_source", belongs_to(User), changeset_options(treat_none_as_null = "true"))]
 pub struct SpotifySource {
   pub id: i32,
@@ -190,10 +432,15 @@ pub struct SpotifySource {
   pub refresh_token: String,
   pub access_token: String,
   pub expiry_date: NaiveDateTime,
+  /// Time at which this source was (soft-)deleted, or `None` if it has not been deleted.
+  pub deleted_at: Option<NaiveDateTime>,
+  /// Time at which this source was last successfully synchronized, or `None` if it has not been synchronized yet.
+  pub last_synced_at: Option<NaiveDateTime>,
+  /// Error of the last synchronization of this source, or `None` if it succeeded.
+  pub last_error: Option<String>,
 }
 
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "diesel", derive(Insertable), table_name = "spotify_source")]
 pub struct NewSpotifySource {
   pub user_id: i32,
@@ -203,6 +450,31 @@ pub struct NewSpotifySource {
   pub expiry_date: NaiveDateTime,
 }
 
+/// State of the last synchronization of a Spotify source.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(spotify_source_id), table_name = "spotify_sync_state", belongs_to(SpotifySource))]
+pub struct SpotifySyncState {
+  pub spotify_source_id: i32,
+  /// Time of the last full synchronization, which refetched all albums of the followed artists, the library, and the
+  /// playlists of the source.
+  pub full_synced_at: NaiveDateTime,
+  /// Time at which the most recently saved album in the library of the source was saved, or `None` if the library was
+  /// empty. Incremental synchronizations only fetch albums saved after this time.
+  pub saved_albums_added_at: Option<NaiveDateTime>,
+}
+
+/// Snapshot of a playlist of a Spotify source as of the last synchronization. Incremental synchronizations only fetch
+/// the tracks of playlists of which the snapshot changed.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations), primary_key(spotify_source_id, playlist_id), table_name = "spotify_playlist_snapshot", belongs_to(SpotifySource))]
+pub struct SpotifyPlaylistSnapshot {
+  pub spotify_source_id: i32,
+  pub playlist_id: String,
+  pub snapshot_id: String,
+}
+
 #[derive(Default, Clone, PartialOrd, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), primary_key(album_id, spotify_id), table_name = "spotify_album", belongs_to(Album))]
@@ -297,6 +569,34 @@ pub struct NewSpotifyArtistSource {
   pub spotify_source_id: i32,
 }
 
+//
+// New releases
+//
+
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), table_name = "new_release", belongs_to(SpotifySource))]
+pub struct NewRelease {
+  pub id: i32,
+  pub spotify_source_id: i32,
+  pub spotify_id: String,
+  pub name: String,
+  pub artist_name: String,
+  pub release_date: String,
+  pub discovered_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "new_release")]
+pub struct NewNewRelease {
+  pub spotify_source_id: i32,
+  pub spotify_id: String,
+  pub name: String,
+  pub artist_name: String,
+  pub release_date: String,
+  pub discovered_at: NaiveDateTime,
+}
+
 
 //
 // User and user data
@@ -326,6 +626,40 @@ pub struct NewUser {
   pub password: String,
 }
 
+// User invite
+
+/// Single-use invite code with which a new user can register, until it expires.
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable), table_name = "user_invite")]
+pub struct UserInvite {
+  pub id: i32,
+  pub code: String,
+  pub created_by_user_id: i32,
+  pub created_at: NaiveDateTime,
+  pub expires_at: NaiveDateTime,
+  /// ID of the user that redeemed this invite, or `None` if it has not been redeemed, or if that user was deleted.
+  pub redeemed_by_user_id: Option<i32>,
+  /// Time at which this invite was redeemed, or `None` if it has not been redeemed.
+  pub redeemed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "user_invite")]
+pub struct NewUserInvite {
+  pub code: String,
+  pub created_by_user_id: i32,
+  pub created_at: NaiveDateTime,
+  pub expires_at: NaiveDateTime,
+}
+
+// User ratings
+
+/// Lowest rating a user can give to an album, track, or artist.
+pub const MIN_RATING: i32 = 0;
+/// Highest rating a user can give to an album, track, or artist.
+pub const MAX_RATING: i32 = 10;
+
 // User-album rating
 
 #[derive(Default, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
@@ -386,27 +720,1196 @@ pub struct NewUserArtistRating {
   pub rating: i32,
 }
 
+// User hidden item
+
+/// Kind of item that a user can hide.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum HiddenItemKind {
+  Track,
+  Album,
+  Artist,
+}
+
+impl HiddenItemKind {
+  pub const ALL: [HiddenItemKind; 3] = [HiddenItemKind::Track, HiddenItemKind::Album, HiddenItemKind::Artist];
+}
+
+impl Display for HiddenItemKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      HiddenItemKind::Track => "track",
+      HiddenItemKind::Album => "album",
+      HiddenItemKind::Artist => "artist",
+    })
+  }
+}
+
+impl FromStr for HiddenItemKind {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    HiddenItemKind::ALL.iter().copied().find(|kind| kind.to_string() == s).ok_or_else(|| format!("Unrecognized hidden item kind '{}'", s))
+  }
+}
+
+/// Track, album, or artist that a user never wants to play. Hidden items are skipped when shuffling, in radio, and in
+/// default list views, but remain in the library. Hiding an album or artist hides all their tracks.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations), primary_key(user_id, kind, item_id), table_name = "user_hidden_item", belongs_to(User))]
+pub struct HiddenItem {
+  pub user_id: i32,
+  pub kind: HiddenItemKind,
+  pub item_id: i32,
+  pub hidden_at: NaiveDateTime,
+}
+
+#[cfg(feature = "diesel")]
+mod hidden_item_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::HiddenItemKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for HiddenItemKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for HiddenItemKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(HiddenItemKind::Track),
+        1 => Ok(HiddenItemKind::Album),
+        2 => Ok(HiddenItemKind::Artist),
+        v => Err(format!("Unrecognized hidden item kind {}", v).into()),
+      }
+    }
+  }
+}
+
+// User content filter
+
+/// Filter of explicit content. Tracks are only known to be clean when a source says so, so `OnlyClean` also leaves out
+/// tracks for which this is unknown.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum ContentFilter {
+  /// Do not filter tracks.
+  Off,
+  /// Leave out tracks that are known to have explicit content.
+  ExcludeExplicit,
+  /// Only include tracks that are known to be clean.
+  OnlyClean,
+}
+
+impl ContentFilter {
+  pub const ALL: [ContentFilter; 3] = [ContentFilter::Off, ContentFilter::ExcludeExplicit, ContentFilter::OnlyClean];
+
+  /// Returns whether a track with `explicit` passes this filter.
+  pub fn allows(&self, explicit: Option<bool>) -> bool {
+    match self {
+      ContentFilter::Off => true,
+      ContentFilter::ExcludeExplicit => explicit != Some(true),
+      ContentFilter::OnlyClean => explicit == Some(false),
+    }
+  }
+}
+
+impl Default for ContentFilter {
+  fn default() -> Self { ContentFilter::Off }
+}
+
+impl Display for ContentFilter {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      ContentFilter::Off => "off",
+      ContentFilter::ExcludeExplicit => "exclude_explicit",
+      ContentFilter::OnlyClean => "only_clean",
+    })
+  }
+}
+
+impl FromStr for ContentFilter {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    ContentFilter::ALL.iter().copied().find(|filter| filter.to_string() == s).ok_or_else(|| format!("Unrecognized content filter '{}'", s))
+  }
+}
+
+/// Content filter of a user, enforced when listing, shuffling, and recommending tracks. A pinned content filter can only
+/// be changed by admins, such as for profiles of children.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(user_id), table_name = "user_content_filter", belongs_to(User))]
+pub struct UserContentFilter {
+  pub user_id: i32,
+  pub filter: ContentFilter,
+  pub pinned: bool,
+}
+
+#[cfg(feature = "diesel")]
+mod content_filter_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::ContentFilter;
+
+  impl<DB: Backend> ToSql<Integer, DB> for ContentFilter where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for ContentFilter where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(ContentFilter::Off),
+        1 => Ok(ContentFilter::ExcludeExplicit),
+        2 => Ok(ContentFilter::OnlyClean),
+        v => Err(format!("Unrecognized content filter {}", v).into()),
+      }
+    }
+  }
+}
+
+// Playback preference
+
+/// Which source to play a track from, when it is available from both a local source and a streaming service.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum PreferredPlaybackSource {
+  Local,
+  Streaming,
+}
+
+/// Playback preferences of a user. Local tracks with a bitrate below `min_local_bitrate` (in kbit/s) are only played
+/// when the track is not available from a streaming service.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(user_id), table_name = "playback_preference", belongs_to(User), changeset_options(treat_none_as_null = "true"))]
+pub struct PlaybackPreference {
+  pub user_id: i32,
+  pub preferred_source: PreferredPlaybackSource,
+  pub min_local_bitrate: Option<i32>,
+}
+
+impl PlaybackPreference {
+  /// Default preferences of a user that has not set any preferences yet: prefer local tracks of any bitrate.
+  pub fn default_for_user(user_id: i32) -> Self {
+    Self { user_id, preferred_source: PreferredPlaybackSource::Local, min_local_bitrate: None }
+  }
+}
+
+#[cfg(feature = "diesel")]
+mod preferred_playback_source_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::PreferredPlaybackSource;
+
+  impl<DB: Backend> ToSql<Integer, DB> for PreferredPlaybackSource where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for PreferredPlaybackSource where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(PreferredPlaybackSource::Local),
+        1 => Ok(PreferredPlaybackSource::Streaming),
+        v => Err(format!("Unrecognized preferred playback source {}", v).into()),
+      }
+    }
+  }
+}
+
+// User preference
+
+/// Generic key/value preference of a user, stored on behalf of clients.
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Queryable, Insertable), table_name = "user_preference")]
+pub struct UserPreference {
+  pub user_id: i32,
+  pub key: String,
+  pub value: String,
+}
+
 //
-// Display implementations
+// Playlists
 //
 
-impl Display for Track {
-  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-    write!(f, "{:>6}:", self.id)?;
-    match (self.disc_number, self.disc_total) {
-      (Some(number), Some(total)) => write!(f, " ({}/{})", number, total)?,
-      (Some(number), _) => write!(f, "   ({})", number)?,
-      _ => write!(f, "      ")?,
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, AsChangeset), table_name = "playlist", changeset_options(treat_none_as_null = "true"))]
+pub struct Playlist {
+  pub id: i32,
+  pub owner_user_id: i32,
+  pub name: String,
+  pub public: bool,
+  /// Time at which this playlist was deleted, or `None` if it has not been deleted. Deleted playlists are kept until
+  /// their deletion can no longer be undone.
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "playlist")]
+pub struct NewPlaylist {
+  pub owner_user_id: i32,
+  pub name: String,
+  pub public: bool,
+}
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations, AsChangeset), table_name = "playlist_track", belongs_to(Playlist), belongs_to(Track))]
+pub struct PlaylistTrack {
+  pub id: i32,
+  pub playlist_id: i32,
+  pub track_id: i32,
+  pub position: i32,
+  pub added_by: i32,
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "playlist_track")]
+pub struct NewPlaylistTrack {
+  pub playlist_id: i32,
+  pub track_id: i32,
+  pub position: i32,
+  pub added_by: i32,
+}
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations), primary_key(playlist_id, user_id), table_name = "playlist_collaborator", belongs_to(Playlist), belongs_to(User))]
+pub struct PlaylistCollaborator {
+  pub playlist_id: i32,
+  pub user_id: i32,
+}
+
+/// Kind of playlist that is generated for a user and regenerated periodically.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum PlaylistMixKind {
+  /// Favorite and unheard tracks of the user's first group of top artists, regenerated daily.
+  DailyMix1,
+  /// Like `DailyMix1`, for the second group of top artists.
+  DailyMix2,
+  /// Like `DailyMix1`, for the third group of top artists.
+  DailyMix3,
+  /// Tracks the user has not played or rated yet that resemble their favorite tracks, regenerated weekly.
+  DiscoveryWeekly,
+}
+
+impl PlaylistMixKind {
+  pub const ALL: [PlaylistMixKind; 4] = [PlaylistMixKind::DailyMix1, PlaylistMixKind::DailyMix2, PlaylistMixKind::DailyMix3, PlaylistMixKind::DiscoveryWeekly];
+
+  /// Gets the name of playlists of this kind.
+  pub fn name(&self) -> &'static str {
+    match self {
+      PlaylistMixKind::DailyMix1 => "Daily Mix 1",
+      PlaylistMixKind::DailyMix2 => "Daily Mix 2",
+      PlaylistMixKind::DailyMix3 => "Daily Mix 3",
+      PlaylistMixKind::DiscoveryWeekly => "Discovery Weekly",
     }
-    match (self.track_number, self.track_total) {
-      (Some(number), Some(total)) => write!(f, " {:>3}/{:>3}.", number, total)?,
-      (Some(number), _) => write!(f, "     {:>3}.", number)?,
-      _ => write!(f, "         ")?,
+  }
+
+  /// Gets the duration after which playlists of this kind are regenerated.
+  pub fn regeneration_interval(&self) -> chrono::Duration {
+    match self {
+      PlaylistMixKind::DiscoveryWeekly => chrono::Duration::weeks(1),
+      _ => chrono::Duration::days(1),
+    }
+  }
+}
+
+/// Playlist that was generated for a user, which is regenerated periodically.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(playlist_id), table_name = "playlist_mix", belongs_to(Playlist), belongs_to(User))]
+pub struct PlaylistMix {
+  pub playlist_id: i32,
+  pub user_id: i32,
+  pub kind: PlaylistMixKind,
+  pub generated_at: NaiveDateTime,
+}
+
+#[cfg(feature = "diesel")]
+mod playlist_mix_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::PlaylistMixKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for PlaylistMixKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for PlaylistMixKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(PlaylistMixKind::DailyMix1),
+        1 => Ok(PlaylistMixKind::DailyMix2),
+        2 => Ok(PlaylistMixKind::DailyMix3),
+        3 => Ok(PlaylistMixKind::DiscoveryWeekly),
+        v => Err(format!("Unrecognized playlist mix kind {}", v).into()),
+      }
+    }
+  }
+}
+
+
+//
+// Notifications
+//
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum NotificationKind {
+  NewRelease,
+  SyncFailure,
+  SharedPlaylistChange,
+}
+
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations, AsChangeset), table_name = "notification", belongs_to(User), changeset_options(treat_none_as_null = "true"))]
+pub struct Notification {
+  pub id: i32,
+  pub user_id: i32,
+  pub kind: NotificationKind,
+  pub message: String,
+  pub created_at: NaiveDateTime,
+  pub is_read: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "notification")]
+pub struct NewNotification {
+  pub user_id: i32,
+  pub kind: NotificationKind,
+  pub message: String,
+  pub created_at: NaiveDateTime,
+  pub is_read: bool,
+}
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(user_id), table_name = "notification_preference", belongs_to(User))]
+pub struct NotificationPreference {
+  pub user_id: i32,
+  pub new_releases: bool,
+  pub sync_failures: bool,
+  pub shared_playlist_changes: bool,
+}
+
+impl NotificationPreference {
+  /// Default preferences of a user that has not set any preferences yet: all notifications are enabled.
+  pub fn default_for_user(user_id: i32) -> Self {
+    Self { user_id, new_releases: true, sync_failures: true, shared_playlist_changes: true }
+  }
+
+  pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+    match kind {
+      NotificationKind::NewRelease => self.new_releases,
+      NotificationKind::SyncFailure => self.sync_failures,
+      NotificationKind::SharedPlaylistChange => self.shared_playlist_changes,
+    }
+  }
+}
+
+#[cfg(feature = "diesel")]
+mod notification_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::NotificationKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for NotificationKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for NotificationKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(NotificationKind::NewRelease),
+        1 => Ok(NotificationKind::SyncFailure),
+        2 => Ok(NotificationKind::SharedPlaylistChange),
+        v => Err(format!("Unrecognized notification kind {}", v).into()),
+      }
+    }
+  }
+}
+
+//
+// Audio analysis
+
+/// Downsampled waveform of the local audio file of a track, for rendering seek bars. Each peak is the maximum absolute
+/// amplitude of a segment of the audio, scaled to `0..=255`. `local_hash` is the hash of the local track the waveform
+/// was generated from, so that the waveform can be regenerated when the file changes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrackWaveform {
+  pub track_id: i32,
+  pub local_hash: i64,
+  pub peaks: Vec<u8>,
+}
+
+/// Reference to the peaks of a generated waveform in the media cache.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(track_id), table_name = "track_waveform", belongs_to(Track))]
+pub struct CachedTrackWaveform {
+  pub track_id: i32,
+  pub local_hash: i64,
+  pub peaks_hash: String,
+}
+
+/// Detected tempo and key of the local audio file of a track. `local_hash` is the hash of the local track the features
+/// were detected from, so that they can be detected again when the file changes.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(track_id), table_name = "track_audio_features", belongs_to(Track), changeset_options(treat_none_as_null = "true"))]
+pub struct TrackAudioFeatures {
+  pub track_id: i32,
+  pub local_hash: i64,
+  pub bpm: Option<f32>,
+  /// Pitch class of the tonic of the key, where 0 is C, 1 is C#, and so forth.
+  pub key_pitch_class: Option<i32>,
+  pub key_minor: Option<bool>,
+  pub energy: Option<f32>,
+  pub brightness: Option<f32>,
+}
+
+impl TrackAudioFeatures {
+  /// Gets the name of the key, such as `C# minor`, or `None` if the key was not detected.
+  pub fn key_name(&self) -> Option<String> {
+    const PITCH_CLASS_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let pitch_class = self.key_pitch_class?;
+    let name = PITCH_CLASS_NAMES.get(pitch_class as usize)?;
+    Some(format!("{} {}", name, if self.key_minor.unwrap_or(false) { "minor" } else { "major" }))
+  }
+}
+
+/// Kind of problem found when decoding the local audio file of a track.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum TrackIssueKind {
+  /// The file could not be opened or read.
+  Unreadable,
+  /// The file is not in a supported format, or its audio data is corrupt.
+  Undecodable,
+  /// The audio ends before the duration stated in the file.
+  Truncated,
+}
+
+/// Problem found when decoding the local audio file of a track, which would interrupt playback. `local_hash` is the
+/// hash of the local track the problem was found in, so that the file can be checked again when it changes.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations, AsChangeset), primary_key(track_id), table_name = "track_issue", belongs_to(Track))]
+pub struct TrackIssue {
+  pub track_id: i32,
+  pub local_hash: i64,
+  pub kind: TrackIssueKind,
+  pub message: String,
+  pub detected_at: NaiveDateTime,
+}
+
+#[cfg(feature = "diesel")]
+mod track_issue_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::TrackIssueKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for TrackIssueKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for TrackIssueKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(TrackIssueKind::Unreadable),
+        1 => Ok(TrackIssueKind::Undecodable),
+        2 => Ok(TrackIssueKind::Truncated),
+        v => Err(format!("Unrecognized track issue kind {}", v).into()),
+      }
+    }
+  }
+}
+
+
+//
+// Track relations
+//
+
+/// Kind of version of an original track.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum TrackRelationKind {
+  /// Remastered release of the original recording.
+  Remaster,
+  /// Live recording of the original track.
+  Live,
+  /// Remix of the original track.
+  Remix,
+  /// Any other version, such as an edit, a demo, or an acoustic version.
+  Other,
+}
+
+impl TrackRelationKind {
+  pub const ALL: [TrackRelationKind; 4] = [TrackRelationKind::Remaster, TrackRelationKind::Live, TrackRelationKind::Remix, TrackRelationKind::Other];
+}
+
+impl Display for TrackRelationKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      TrackRelationKind::Remaster => "remaster",
+      TrackRelationKind::Live => "live",
+      TrackRelationKind::Remix => "remix",
+      TrackRelationKind::Other => "other",
+    })
+  }
+}
+
+impl FromStr for TrackRelationKind {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    TrackRelationKind::ALL.iter().copied().find(|kind| kind.to_string() == s).ok_or_else(|| format!("Unrecognized track relation kind '{}'", s))
+  }
+}
+
+/// Link from the track with `version_track_id` to the original track with `original_track_id` of which it is a version
+/// of `kind`. Links are either created by users, or `machine_generated` from the titles of tracks during sync.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable), primary_key(original_track_id, version_track_id), table_name = "track_relation")]
+pub struct TrackRelation {
+  pub original_track_id: i32,
+  pub version_track_id: i32,
+  pub kind: TrackRelationKind,
+  pub machine_generated: bool,
+}
+
+#[cfg(feature = "diesel")]
+mod track_relation_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::TrackRelationKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for TrackRelationKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for TrackRelationKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(TrackRelationKind::Remaster),
+        1 => Ok(TrackRelationKind::Live),
+        2 => Ok(TrackRelationKind::Remix),
+        3 => Ok(TrackRelationKind::Other),
+        v => Err(format!("Unrecognized track relation kind {}", v).into()),
+      }
+    }
+  }
+}
+
+
+//
+// Tags
+
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable), table_name = "tag")]
+pub struct Tag {
+  pub id: i32,
+  pub name: String,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "tag")]
+pub struct NewTag {
+  pub name: String,
+}
+
+/// Tag assigned to a track, either by a user, or `machine_generated` from audio analysis.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, Associations), primary_key(track_id, tag_id), table_name = "track_tag", belongs_to(Track), belongs_to(Tag))]
+pub struct TrackTag {
+  pub track_id: i32,
+  pub tag_id: i32,
+  pub machine_generated: bool,
+}
+
+
+//
+// Undo log
+//
+
+/// Kind of destructive operation that can be undone.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum UndoKind {
+  AlbumRatingChange,
+  TrackRatingChange,
+  ArtistRatingChange,
+  PlaylistDeletion,
+  LocalSourceRemoval,
+  SpotifySourceRemoval,
+}
+
+/// Entry in the undo log of a user, describing how to undo a destructive operation on the entity with `target_id`. For
+/// rating changes, `previous_rating` is the rating before the change, or `None` if there was no rating.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), table_name = "undo_log", belongs_to(User))]
+pub struct UndoLogEntry {
+  pub id: i32,
+  pub user_id: i32,
+  pub kind: UndoKind,
+  pub target_id: i32,
+  pub previous_rating: Option<i32>,
+  pub description: String,
+  pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "undo_log")]
+pub struct NewUndoLogEntry {
+  pub user_id: i32,
+  pub kind: UndoKind,
+  pub target_id: i32,
+  pub previous_rating: Option<i32>,
+  pub description: String,
+  pub created_at: NaiveDateTime,
+}
+
+#[cfg(feature = "diesel")]
+mod undo_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::UndoKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for UndoKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for UndoKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(UndoKind::AlbumRatingChange),
+        1 => Ok(UndoKind::TrackRatingChange),
+        2 => Ok(UndoKind::ArtistRatingChange),
+        3 => Ok(UndoKind::PlaylistDeletion),
+        4 => Ok(UndoKind::LocalSourceRemoval),
+        5 => Ok(UndoKind::SpotifySourceRemoval),
+        v => Err(format!("Unrecognized undo kind {}", v).into()),
+      }
+    }
+  }
+}
+
+
+//
+// Metadata merging
+//
+
+/// Metadata field that can be provided by multiple sources, and for which a merge policy can be set.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum MetadataField {
+  AlbumName,
+  TrackTitle,
+  TrackNumber,
+  DiscNumber,
+  ArtistName,
+  TrackArtists,
+}
+
+impl MetadataField {
+  pub const ALL: [MetadataField; 6] = [MetadataField::AlbumName, MetadataField::TrackTitle, MetadataField::TrackNumber, MetadataField::DiscNumber, MetadataField::ArtistName, MetadataField::TrackArtists];
+}
+
+impl Display for MetadataField {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      MetadataField::AlbumName => "album-name",
+      MetadataField::TrackTitle => "track-title",
+      MetadataField::TrackNumber => "track-number",
+      MetadataField::DiscNumber => "disc-number",
+      MetadataField::ArtistName => "artist-name",
+      MetadataField::TrackArtists => "track-artists",
+    })
+  }
+}
+
+impl FromStr for MetadataField {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    MetadataField::ALL.iter().copied().find(|field| field.to_string() == s).ok_or_else(|| format!("Unrecognized metadata field '{}'", s))
+  }
+}
+
+/// Policy for merging a metadata field when sources disagree on its value. Values from local sources and Spotify
+/// sources are preferred with `PreferLocal` and `PreferSpotify` respectively. MusicBrainz metadata is not synchronized
+/// yet, so with `PreferMusicBrainz` no source overwrites the current value, and disagreements are recorded as conflicts
+/// as with `Manual`.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum MetadataMergePolicy {
+  PreferLocal,
+  PreferSpotify,
+  PreferMusicBrainz,
+  Manual,
+}
+
+impl Display for MetadataMergePolicy {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    f.write_str(match self {
+      MetadataMergePolicy::PreferLocal => "prefer-local",
+      MetadataMergePolicy::PreferSpotify => "prefer-spotify",
+      MetadataMergePolicy::PreferMusicBrainz => "prefer-musicbrainz",
+      MetadataMergePolicy::Manual => "manual",
+    })
+  }
+}
+
+impl FromStr for MetadataMergePolicy {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "prefer-local" => Ok(MetadataMergePolicy::PreferLocal),
+      "prefer-spotify" => Ok(MetadataMergePolicy::PreferSpotify),
+      "prefer-musicbrainz" => Ok(MetadataMergePolicy::PreferMusicBrainz),
+      "manual" => Ok(MetadataMergePolicy::Manual),
+      _ => Err(format!("Unrecognized metadata merge policy '{}'", s)),
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable), primary_key(field), table_name = "metadata_merge_policy")]
+pub struct MetadataMergePolicySetting {
+  pub field: MetadataField,
+  pub policy: MetadataMergePolicy,
+}
+
+/// Conflict between the values of `field` of the album, track, or artist with `entity_id` from different sources, that
+/// was not resolved by a merge policy. `current_value` is the value in the database, which is kept until the conflict
+/// is resolved. Track artists are represented as names separated by `; `.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, AsChangeset), table_name = "metadata_conflict", changeset_options(treat_none_as_null = "true"))]
+pub struct MetadataConflict {
+  pub id: i32,
+  pub field: MetadataField,
+  pub entity_id: i32,
+  pub current_value: Option<String>,
+  pub local_value: Option<String>,
+  pub spotify_value: Option<String>,
+  pub detected_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "metadata_conflict")]
+pub struct NewMetadataConflict {
+  pub field: MetadataField,
+  pub entity_id: i32,
+  pub current_value: Option<String>,
+  pub local_value: Option<String>,
+  pub spotify_value: Option<String>,
+  pub detected_at: NaiveDateTime,
+}
+
+#[cfg(feature = "diesel")]
+mod metadata_merge_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::{MetadataField, MetadataMergePolicy};
+
+  impl<DB: Backend> ToSql<Integer, DB> for MetadataField where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for MetadataField where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(MetadataField::AlbumName),
+        1 => Ok(MetadataField::TrackTitle),
+        2 => Ok(MetadataField::TrackNumber),
+        3 => Ok(MetadataField::DiscNumber),
+        4 => Ok(MetadataField::ArtistName),
+        5 => Ok(MetadataField::TrackArtists),
+        v => Err(format!("Unrecognized metadata field {}", v).into()),
+      }
+    }
+  }
+
+  impl<DB: Backend> ToSql<Integer, DB> for MetadataMergePolicy where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for MetadataMergePolicy where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(MetadataMergePolicy::PreferLocal),
+        1 => Ok(MetadataMergePolicy::PreferSpotify),
+        2 => Ok(MetadataMergePolicy::PreferMusicBrainz),
+        3 => Ok(MetadataMergePolicy::Manual),
+        v => Err(format!("Unrecognized metadata merge policy {}", v).into()),
+      }
+    }
+  }
+}
+
+
+//
+// Sync lock
+//
+
+/// Lease on the sync subsystem held by the server instance identified by `holder`, until `expires_at`.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable), table_name = "sync_lock")]
+pub struct SyncLock {
+  pub id: i32,
+  pub holder: String,
+  pub acquired_at: NaiveDateTime,
+  pub expires_at: NaiveDateTime,
+}
+
+
+//
+// Sync history
+//
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum SyncSourceKind {
+  Local,
+  Spotify,
+}
+
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum SyncRunOutcome {
+  Completed,
+  /// The source was skipped, for example because its directory was unavailable.
+  Skipped,
+  Failed,
+}
+
+/// Record of synchronizing the source with `source_id` of `source_kind`. `added`, `updated`, and `removed` are numbers
+/// of tracks of the source.
+#[derive(Clone, PartialOrd, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable), table_name = "sync_run")]
+pub struct SyncRun {
+  pub id: i32,
+  pub source_kind: SyncSourceKind,
+  pub source_id: i32,
+  pub started_at: NaiveDateTime,
+  pub ended_at: NaiveDateTime,
+  pub added: i32,
+  pub updated: i32,
+  pub removed: i32,
+  pub outcome: SyncRunOutcome,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "sync_run")]
+pub struct NewSyncRun {
+  pub source_kind: SyncSourceKind,
+  pub source_id: i32,
+  pub started_at: NaiveDateTime,
+  pub ended_at: NaiveDateTime,
+  pub added: i32,
+  pub updated: i32,
+  pub removed: i32,
+  pub outcome: SyncRunOutcome,
+  pub error: Option<String>,
+}
+
+#[cfg(feature = "diesel")]
+mod sync_source_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::SyncSourceKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for SyncSourceKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for SyncSourceKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(SyncSourceKind::Local),
+        1 => Ok(SyncSourceKind::Spotify),
+        v => Err(format!("Unrecognized sync source kind {}", v).into()),
+      }
+    }
+  }
+}
+
+#[cfg(feature = "diesel")]
+mod sync_run_outcome_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::SyncRunOutcome;
+
+  impl<DB: Backend> ToSql<Integer, DB> for SyncRunOutcome where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for SyncRunOutcome where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(SyncRunOutcome::Completed),
+        1 => Ok(SyncRunOutcome::Skipped),
+        2 => Ok(SyncRunOutcome::Failed),
+        v => Err(format!("Unrecognized sync run outcome {}", v).into()),
+      }
+    }
+  }
+}
+
+//
+// Webhooks
+//
+
+/// Outgoing webhook that is called on the kinds of events it is enabled for. Not serializable so that its secret is not
+/// sent to clients; the API exposes webhooks as `WebhookInfo` instead.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, AsChangeset), table_name = "webhook")]
+pub struct Webhook {
+  pub id: i32,
+  pub url: String,
+  /// Key of the HMAC-SHA256 signature of payloads, with which receivers verify that a payload was sent by this server.
+  pub secret: String,
+  pub on_sync_completed: bool,
+  pub on_new_albums: bool,
+  pub on_errors: bool,
+  pub enabled: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "webhook")]
+pub struct NewWebhook {
+  pub url: String,
+  pub secret: String,
+  pub on_sync_completed: bool,
+  pub on_new_albums: bool,
+  pub on_errors: bool,
+}
+
+
+//
+// Play history
+//
+
+/// Play of a track by a user, recorded when the server serves the track for playback.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Associations), table_name = "play_history", belongs_to(User), belongs_to(Track))]
+pub struct PlayHistory {
+  pub id: i32,
+  pub user_id: i32,
+  pub track_id: i32,
+  pub played_at: NaiveDateTime,
+  /// Whether the track was skipped before `SKIP_THRESHOLD` of it was played.
+  pub skipped: bool,
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "diesel", derive(Insertable), table_name = "play_history")]
+pub struct NewPlayHistory {
+  pub user_id: i32,
+  pub track_id: i32,
+  pub played_at: NaiveDateTime,
+  pub skipped: bool,
+}
+
+/// Relative position in a track before which stopping playback of the track counts as skipping it.
+pub const SKIP_THRESHOLD: f64 = 0.3;
+
+
+//
+// Display implementations
+//
+
+impl Display for Track {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+    write!(f, "{:>6}:", self.id)?;
+    match (self.disc_number, self.disc_total) {
+      (Some(number), Some(total)) => write!(f, " ({}/{})", number, total)?,
+      (Some(number), _) => write!(f, "   ({})", number)?,
+      _ => write!(f, "      ")?,
+    }
+    match (self.track_number, self.track_total) {
+      (Some(number), Some(total)) => write!(f, " {:>3}/{:>3}.", number, total)?,
+      (Some(number), _) => write!(f, "     {:>3}.", number)?,
+      _ => write!(f, "         ")?,
+    }
+    write!(f, " {:<50}", self.title)?;
+    // if let Some(file_path) = &self.file_path {
+    //   write!(f, " - {}", file_path)?;
+    // }
+    Ok(())
+  }
+}
+
+//
+// Media cache
+//
+
+/// Kind of media stored in the media cache.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow), sql_type = "diesel::sql_types::Integer")]
+pub enum MediaKind {
+  AlbumCover,
+  ArtistImage,
+  Waveform,
+  ResizedImage,
+}
+
+/// Blob of media in the media cache, keyed by the hex-encoded BLAKE3 `hash` of its `data`. Pinned blobs cannot be
+/// derived again and are never evicted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, AsChangeset), primary_key(hash), table_name = "media_blob")]
+pub struct MediaBlob {
+  pub hash: String,
+  pub kind: MediaKind,
+  pub mime_type: String,
+  pub data: Vec<u8>,
+  pub size: i64,
+  pub pinned: bool,
+  pub last_accessed_at: NaiveDateTime,
+}
+
+/// Resized variant of image blob `source_hash` in the media cache, fitting within `size` by `size` pixels.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "diesel", derive(Identifiable, Queryable, Insertable, AsChangeset), primary_key(source_hash, size), table_name = "media_variant")]
+pub struct MediaVariant {
+  pub source_hash: String,
+  pub size: i32,
+  pub variant_hash: String,
+}
+
+#[cfg(feature = "diesel")]
+mod media_kind_sql {
+  use std::io::Write;
+
+  use diesel::backend::Backend;
+  use diesel::deserialize::{self, FromSql};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Integer;
+
+  use super::MediaKind;
+
+  impl<DB: Backend> ToSql<Integer, DB> for MediaKind where i32: ToSql<Integer, DB> {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      (*self as i32).to_sql(out)
+    }
+  }
+
+  impl<DB: Backend> FromSql<Integer, DB> for MediaKind where i32: FromSql<Integer, DB> {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      match i32::from_sql(bytes)? {
+        0 => Ok(MediaKind::AlbumCover),
+        1 => Ok(MediaKind::ArtistImage),
+        2 => Ok(MediaKind::Waveform),
+        3 => Ok(MediaKind::ResizedImage),
+        v => Err(format!("Unrecognized media kind {}", v).into()),
+      }
     }
-    write!(f, " {:<50}", self.title)?;
-    // if let Some(file_path) = &self.file_path {
-    //   write!(f, " - {}", file_path)?;
-    // }
-    Ok(())
   }
 }
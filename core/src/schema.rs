@@ -2,6 +2,9 @@ table! {
     album (id) {
         id -> Integer,
         name -> Text,
+        name_key -> Text,
+        sort_name -> Text,
+        sort_name_overridden -> Bool,
     }
 }
 
@@ -12,10 +15,31 @@ table! {
     }
 }
 
+table! {
+    album_cover (album_id) {
+        album_id -> Integer,
+        blob_hash -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     artist (id) {
         id -> Integer,
         name -> Text,
+        name_key -> Text,
+        sort_name -> Text,
+        sort_name_overridden -> Bool,
+    }
+}
+
+table! {
+    artist_info (artist_id) {
+        artist_id -> Integer,
+        biography -> Nullable<Text>,
+        image_hash -> Nullable<Text>,
+        source_url -> Nullable<Text>,
+        fetched_at -> Timestamp,
     }
 }
 
@@ -38,6 +62,8 @@ table! {
         id -> Integer,
         enabled -> Bool,
         directory -> Text,
+        skip_if_unavailable -> Bool,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -46,7 +72,144 @@ table! {
         track_id -> Integer,
         local_source_id -> Integer,
         file_path -> Nullable<Text>,
+        file_path_key -> Nullable<Text>,
         hash -> BigInt,
+        bitrate -> Nullable<Integer>,
+        track_gain -> Nullable<Float>,
+        track_peak -> Nullable<Float>,
+        album_gain -> Nullable<Float>,
+        album_peak -> Nullable<Float>,
+    }
+}
+
+table! {
+    media_blob (hash) {
+        hash -> Text,
+        kind -> Integer,
+        mime_type -> Text,
+        data -> Binary,
+        size -> BigInt,
+        pinned -> Bool,
+        last_accessed_at -> Timestamp,
+    }
+}
+
+table! {
+    media_variant (source_hash, size) {
+        source_hash -> Text,
+        size -> Integer,
+        variant_hash -> Text,
+    }
+}
+
+table! {
+    metadata_conflict (id) {
+        id -> Integer,
+        field -> Integer,
+        entity_id -> Integer,
+        current_value -> Nullable<Text>,
+        local_value -> Nullable<Text>,
+        spotify_value -> Nullable<Text>,
+        detected_at -> Timestamp,
+    }
+}
+
+table! {
+    metadata_merge_policy (field) {
+        field -> Integer,
+        policy -> Integer,
+    }
+}
+
+table! {
+    new_release (id) {
+        id -> Integer,
+        spotify_source_id -> Integer,
+        spotify_id -> Text,
+        name -> Text,
+        artist_name -> Text,
+        release_date -> Text,
+        discovered_at -> Timestamp,
+    }
+}
+
+table! {
+    notification (id) {
+        id -> Integer,
+        user_id -> Integer,
+        kind -> Integer,
+        message -> Text,
+        created_at -> Timestamp,
+        is_read -> Bool,
+    }
+}
+
+table! {
+    notification_preference (user_id) {
+        user_id -> Integer,
+        new_releases -> Bool,
+        sync_failures -> Bool,
+        shared_playlist_changes -> Bool,
+    }
+}
+
+table! {
+    play_history (id) {
+        id -> Integer,
+        user_id -> Integer,
+        track_id -> Integer,
+        played_at -> Timestamp,
+        skipped -> Bool,
+    }
+}
+
+table! {
+    playback_preference (user_id) {
+        user_id -> Integer,
+        preferred_source -> Integer,
+        min_local_bitrate -> Nullable<Integer>,
+    }
+}
+
+table! {
+    playlist (id) {
+        id -> Integer,
+        owner_user_id -> Integer,
+        name -> Text,
+        public -> Bool,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    playlist_collaborator (playlist_id, user_id) {
+        playlist_id -> Integer,
+        user_id -> Integer,
+    }
+}
+
+table! {
+    playlist_mix (playlist_id) {
+        playlist_id -> Integer,
+        user_id -> Integer,
+        kind -> Integer,
+        generated_at -> Timestamp,
+    }
+}
+
+table! {
+    playlist_track (id) {
+        id -> Integer,
+        playlist_id -> Integer,
+        track_id -> Integer,
+        position -> Integer,
+        added_by -> Integer,
+    }
+}
+
+table! {
+    sort_article (article) {
+        article -> Text,
     }
 }
 
@@ -86,6 +249,25 @@ table! {
         refresh_token -> Text,
         access_token -> Text,
         expiry_date -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        last_synced_at -> Nullable<Timestamp>,
+        last_error -> Nullable<Text>,
+    }
+}
+
+table! {
+    spotify_playlist_snapshot (spotify_source_id, playlist_id) {
+        spotify_source_id -> Integer,
+        playlist_id -> Text,
+        snapshot_id -> Text,
+    }
+}
+
+table! {
+    spotify_sync_state (spotify_source_id) {
+        spotify_source_id -> Integer,
+        full_synced_at -> Timestamp,
+        saved_albums_added_at -> Nullable<Timestamp>,
     }
 }
 
@@ -103,6 +285,37 @@ table! {
     }
 }
 
+table! {
+    sync_lock (id) {
+        id -> Integer,
+        holder -> Text,
+        acquired_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+table! {
+    sync_run (id) {
+        id -> Integer,
+        source_kind -> Integer,
+        source_id -> Integer,
+        started_at -> Timestamp,
+        ended_at -> Timestamp,
+        added -> Integer,
+        updated -> Integer,
+        removed -> Integer,
+        outcome -> Integer,
+        error -> Nullable<Text>,
+    }
+}
+
+table! {
+    tag (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
 table! {
     track (id) {
         id -> Integer,
@@ -112,13 +325,79 @@ table! {
         track_number -> Nullable<Integer>,
         track_total -> Nullable<Integer>,
         title -> Text,
+        title_key -> Text,
+        added_at -> Timestamp,
+        explicit -> Nullable<Bool>,
+        work_id -> Nullable<Integer>,
+        movement -> Nullable<Text>,
+        movement_number -> Nullable<Integer>,
     }
 }
 
 table! {
-    track_artist (track_id, artist_id) {
+    track_artist (track_id, artist_id, role) {
         track_id -> Integer,
         artist_id -> Integer,
+        role -> Integer,
+    }
+}
+
+table! {
+    track_audio_features (track_id) {
+        track_id -> Integer,
+        local_hash -> BigInt,
+        bpm -> Nullable<Float>,
+        key_pitch_class -> Nullable<Integer>,
+        key_minor -> Nullable<Bool>,
+        energy -> Nullable<Float>,
+        brightness -> Nullable<Float>,
+    }
+}
+
+table! {
+    track_issue (track_id) {
+        track_id -> Integer,
+        local_hash -> BigInt,
+        kind -> Integer,
+        message -> Text,
+        detected_at -> Timestamp,
+    }
+}
+
+table! {
+    track_relation (original_track_id, version_track_id) {
+        original_track_id -> Integer,
+        version_track_id -> Integer,
+        kind -> Integer,
+        machine_generated -> Bool,
+    }
+}
+
+table! {
+    track_tag (track_id, tag_id) {
+        track_id -> Integer,
+        tag_id -> Integer,
+        machine_generated -> Bool,
+    }
+}
+
+table! {
+    track_waveform (track_id) {
+        track_id -> Integer,
+        local_hash -> BigInt,
+        peaks_hash -> Text,
+    }
+}
+
+table! {
+    undo_log (id) {
+        id -> Integer,
+        user_id -> Integer,
+        kind -> Integer,
+        target_id -> Integer,
+        previous_rating -> Nullable<Integer>,
+        description -> Text,
+        created_at -> Timestamp,
     }
 }
 
@@ -128,6 +407,7 @@ table! {
         name -> Text,
         hash -> Binary,
         salt -> Binary,
+        hash_params -> Text,
     }
 }
 
@@ -147,6 +427,43 @@ table! {
     }
 }
 
+table! {
+    user_content_filter (user_id) {
+        user_id -> Integer,
+        filter -> Integer,
+        pinned -> Bool,
+    }
+}
+
+table! {
+    user_hidden_item (user_id, kind, item_id) {
+        user_id -> Integer,
+        kind -> Integer,
+        item_id -> Integer,
+        hidden_at -> Timestamp,
+    }
+}
+
+table! {
+    user_invite (id) {
+        id -> Integer,
+        code -> Text,
+        created_by_user_id -> Integer,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        redeemed_by_user_id -> Nullable<Integer>,
+        redeemed_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    user_preference (user_id, key) {
+        user_id -> Integer,
+        key -> Text,
+        value -> Text,
+    }
+}
+
 table! {
     user_track_rating (user_id, track_id) {
         user_id -> Integer,
@@ -155,53 +472,137 @@ table! {
     }
 }
 
+table! {
+    webhook (id) {
+        id -> Integer,
+        url -> Text,
+        secret -> Text,
+        on_sync_completed -> Bool,
+        on_new_albums -> Bool,
+        on_errors -> Bool,
+        enabled -> Bool,
+    }
+}
+
+table! {
+    work (id) {
+        id -> Integer,
+        name -> Text,
+        name_key -> Text,
+        composer_id -> Nullable<Integer>,
+    }
+}
+
 joinable!(album_artist -> album (album_id));
 joinable!(album_artist -> artist (artist_id));
+joinable!(album_cover -> album (album_id));
+joinable!(artist_info -> artist (artist_id));
 joinable!(local_album -> album (album_id));
 joinable!(local_album -> local_source (local_source_id));
 joinable!(local_artist -> artist (artist_id));
 joinable!(local_artist -> local_source (local_source_id));
 joinable!(local_track -> local_source (local_source_id));
 joinable!(local_track -> track (track_id));
+joinable!(new_release -> spotify_source (spotify_source_id));
+joinable!(notification -> user (user_id));
+joinable!(notification_preference -> user (user_id));
+joinable!(play_history -> track (track_id));
+joinable!(play_history -> user (user_id));
+joinable!(playback_preference -> user (user_id));
+joinable!(playlist -> user (owner_user_id));
+joinable!(playlist_collaborator -> playlist (playlist_id));
+joinable!(playlist_collaborator -> user (user_id));
+joinable!(playlist_mix -> playlist (playlist_id));
+joinable!(playlist_mix -> user (user_id));
+joinable!(playlist_track -> playlist (playlist_id));
+joinable!(playlist_track -> track (track_id));
+joinable!(playlist_track -> user (added_by));
 joinable!(spotify_album -> album (album_id));
 joinable!(spotify_album_source -> album (album_id));
 joinable!(spotify_album_source -> spotify_source (spotify_source_id));
 joinable!(spotify_artist -> artist (artist_id));
 joinable!(spotify_artist_source -> artist (artist_id));
 joinable!(spotify_artist_source -> spotify_source (spotify_source_id));
+joinable!(spotify_playlist_snapshot -> spotify_source (spotify_source_id));
 joinable!(spotify_source -> user (user_id));
+joinable!(spotify_sync_state -> spotify_source (spotify_source_id));
 joinable!(spotify_track -> track (track_id));
 joinable!(spotify_track_source -> spotify_source (spotify_source_id));
 joinable!(spotify_track_source -> track (track_id));
 joinable!(track -> album (album_id));
 joinable!(track_artist -> artist (artist_id));
 joinable!(track_artist -> track (track_id));
+joinable!(track -> work (work_id));
+joinable!(track_audio_features -> track (track_id));
+joinable!(track_issue -> track (track_id));
+joinable!(track_tag -> tag (tag_id));
+joinable!(track_tag -> track (track_id));
+joinable!(track_waveform -> track (track_id));
+joinable!(undo_log -> user (user_id));
 joinable!(user_album_rating -> album (album_id));
 joinable!(user_album_rating -> user (user_id));
 joinable!(user_artist_rating -> artist (artist_id));
 joinable!(user_artist_rating -> user (user_id));
+joinable!(user_content_filter -> user (user_id));
+joinable!(user_hidden_item -> user (user_id));
+joinable!(user_invite -> user (created_by_user_id));
+joinable!(user_preference -> user (user_id));
 joinable!(user_track_rating -> track (track_id));
 joinable!(user_track_rating -> user (user_id));
+joinable!(work -> artist (composer_id));
 
 allow_tables_to_appear_in_same_query!(
     album,
     album_artist,
+    album_cover,
     artist,
+    artist_info,
     local_album,
     local_artist,
     local_source,
     local_track,
+    media_blob,
+    media_variant,
+    metadata_conflict,
+    metadata_merge_policy,
+    new_release,
+    notification,
+    notification_preference,
+    play_history,
+    playback_preference,
+    playlist,
+    playlist_collaborator,
+    playlist_mix,
+    playlist_track,
+    sort_article,
     spotify_album,
     spotify_album_source,
     spotify_artist,
     spotify_artist_source,
+    spotify_playlist_snapshot,
     spotify_source,
+    spotify_sync_state,
     spotify_track,
     spotify_track_source,
+    sync_lock,
+    sync_run,
+    tag,
     track,
     track_artist,
+    track_audio_features,
+    track_issue,
+    track_relation,
+    track_tag,
+    track_waveform,
+    undo_log,
     user,
     user_album_rating,
     user_artist_rating,
+    user_content_filter,
+    user_hidden_item,
+    user_invite,
+    user_preference,
     user_track_rating,
+    webhook,
+    work,
 );
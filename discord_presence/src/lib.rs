@@ -0,0 +1,157 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use discord_rich_presence::activity::{Activity, Assets, Timestamps};
+use tracing::{debug, warn};
+
+/// Key of the art asset of the Discord application that is shown when no artwork URL is configured.
+const DEFAULT_LARGE_IMAGE: &str = "musium";
+/// Interval between attempts to connect to Discord, for example when Discord is not running.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+/// Number of seconds the start of the playing track may shift before the elapsed time is published again. Keeps the
+/// presence from being published on every position update, as Discord rate limits presence updates.
+const MAX_START_DRIFT: i64 = 2;
+
+/// Configuration of the Discord Rich Presence integration.
+#[derive(Clone, Debug)]
+pub struct DiscordPresenceConfig {
+  /// ID of the Discord application to publish the presence as.
+  pub application_id: String,
+  /// URL of the artwork to show, where `{album_id}` is replaced by the ID of the album of the playing track. Discord
+  /// downloads the artwork itself, so this URL must be publicly reachable. Shows the `musium` art asset of the Discord
+  /// application when `None`.
+  pub artwork_url: Option<String>,
+}
+
+/// Track to publish as the presence of the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresenceTrack {
+  pub id: i32,
+  pub title: String,
+  pub artists: Option<String>,
+  pub album_id: i32,
+  pub album: Option<String>,
+  /// Position of playback in seconds, from which the elapsed time is shown.
+  pub position: Option<f64>,
+  pub is_paused: bool,
+}
+
+/// Publishes the playing track to Discord Rich Presence. Presence updates are sent to a background thread which
+/// (re)connects to Discord when needed, so that updating the presence never blocks. The presence is cleared when this
+/// is dropped.
+pub struct DiscordPresence {
+  sender: Sender<Option<Presence>>,
+  published: Option<Presence>,
+}
+
+#[derive(Clone, Debug)]
+struct Presence {
+  track: PresenceTrack,
+  /// Unix timestamp in seconds of when playback of the track started, or `None` when paused.
+  start: Option<i64>,
+}
+
+impl DiscordPresence {
+  pub fn spawn(config: DiscordPresenceConfig) -> Self {
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+      .name("Discord presence".to_string())
+      .spawn(move || run(config, receiver))
+      .expect("Failed to spawn Discord presence thread");
+    Self { sender, published: None }
+  }
+
+  /// Publishes `track` as the presence, or clears the presence if `track` is `None`. Does nothing if the presence would
+  /// not change noticeably.
+  pub fn set_track(&mut self, track: Option<PresenceTrack>) {
+    let presence = track.map(|track| {
+      let start = match (track.is_paused, track.position) {
+        (false, Some(position)) => Some(unix_timestamp() - position as i64),
+        _ => None,
+      };
+      Presence { track, start }
+    });
+    let unchanged = match (&self.published, &presence) {
+      (None, None) => true,
+      (Some(published), Some(presence)) => {
+        let start_unchanged = match (published.start, presence.start) {
+          (Some(published_start), Some(start)) => (published_start - start).abs() <= MAX_START_DRIFT,
+          (None, None) => true,
+          _ => false,
+        };
+        start_unchanged && PresenceTrack { position: None, ..published.track.clone() } == PresenceTrack { position: None, ..presence.track.clone() }
+      }
+      _ => false,
+    };
+    if unchanged { return; }
+    self.published = presence.clone();
+    self.sender.send(presence).ok(); // OK: thread only stops when this is dropped -> cannot fail.
+  }
+}
+
+fn run(config: DiscordPresenceConfig, receiver: Receiver<Option<Presence>>) {
+  let mut client: Option<DiscordIpcClient> = None;
+  // Presence that still has to be published, for example because Discord was not running.
+  let mut pending: Option<Option<Presence>> = None;
+  loop {
+    let received = if pending.is_some() { receiver.recv_timeout(RECONNECT_INTERVAL) } else { receiver.recv().map_err(|_| RecvTimeoutError::Disconnected) };
+    match received {
+      Ok(presence) => pending = Some(presence),
+      Err(RecvTimeoutError::Timeout) => {}
+      Err(RecvTimeoutError::Disconnected) => break,
+    }
+    if let Some(presence) = &pending {
+      match publish(&config, &mut client, presence.as_ref()) {
+        Ok(_) => pending = None,
+        Err(e) => {
+          debug!("Failed to publish Discord presence, retrying in {} seconds: {}", RECONNECT_INTERVAL.as_secs(), e);
+          client = None;
+        }
+      }
+    }
+  }
+  if let Some(mut client) = client {
+    if let Err(e) = client.clear_activity().and_then(|_| client.close()) {
+      warn!("Failed to clear Discord presence: {}", e);
+    }
+  }
+}
+
+fn publish(config: &DiscordPresenceConfig, client: &mut Option<DiscordIpcClient>, presence: Option<&Presence>) -> Result<(), Box<dyn std::error::Error>> {
+  if client.is_none() {
+    let mut new_client = DiscordIpcClient::new(&config.application_id)?;
+    new_client.connect()?;
+    debug!("Connected to Discord");
+    *client = Some(new_client);
+  }
+  let client = client.as_mut().expect("[BUG] No Discord client after connecting");
+  let presence = match presence {
+    Some(presence) => presence,
+    None => return client.clear_activity(),
+  };
+  let track = &presence.track;
+  let large_image = match &config.artwork_url {
+    Some(artwork_url) => artwork_url.replace("{album_id}", &track.album_id.to_string()),
+    None => DEFAULT_LARGE_IMAGE.to_string(),
+  };
+  let mut assets = Assets::new().large_image(&large_image);
+  if let Some(album) = &track.album {
+    assets = assets.large_text(album);
+  }
+  let mut activity = Activity::new()
+    .details(&track.title)
+    .assets(assets);
+  if let Some(artists) = &track.artists {
+    activity = activity.state(artists);
+  }
+  if let Some(start) = presence.start {
+    activity = activity.timestamps(Timestamps::new().start(start));
+  }
+  client.set_activity(activity)
+}
+
+fn unix_timestamp() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
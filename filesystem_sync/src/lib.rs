@@ -1,7 +1,10 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek};
+use std::path::Path;
 
 use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
 
 #[derive(Clone, Debug)]
@@ -18,7 +21,61 @@ pub struct FilesystemSyncTrack {
   pub album_artists: Vec<String>,
   // OPTO: smallstring?
   pub file_path: String,
+  /// Normalized version of `file_path` for comparing paths, see [path_key].
+  pub file_path_key: String,
   pub hash: u32,
+  /// Bitrate in kbit/s, taken from the first MPEG audio frame header. For variable bitrate files this is only an
+  /// approximation.
+  pub bitrate: Option<i32>,
+  pub replay_gain: FilesystemSyncReplayGain,
+  /// Whether the track has explicit content, or `None` if the tag does not say.
+  pub explicit: Option<bool>,
+  /// Artists featured on the track. Tags do not store these separately, so they are empty until they are split off the
+  /// track artists and title during synchronization.
+  pub featured_artists: Vec<String>,
+  /// Artists that remixed the track, read from the remixer (`TPE4`) frame.
+  pub remixers: Vec<String>,
+  /// Conductors of the track, read from the conductor (`TPE3`) frame.
+  pub conductors: Vec<String>,
+  pub composers: Vec<String>,
+  /// Performers of the track, such as soloists and orchestras, as credited separately from the track artists.
+  pub performers: Vec<String>,
+  /// Classical work the track is a movement of, or `None` if the tag does not say.
+  pub work: Option<String>,
+  pub movement: Option<String>,
+  pub movement_number: Option<i32>,
+}
+
+/// ReplayGain values read from the tags of a file. Gains are in dB relative to the ReplayGain reference level, and peaks
+/// are linear sample amplitudes where 1.0 is full scale.
+#[derive(Default, Copy, Clone, PartialEq, Debug)]
+pub struct FilesystemSyncReplayGain {
+  pub track_gain: Option<f32>,
+  pub track_peak: Option<f32>,
+  pub album_gain: Option<f32>,
+  pub album_peak: Option<f32>,
+}
+
+impl FilesystemSyncTrack {
+  /// Normalizes the title, album, artist, work, and movement names to Unicode Normalization Form C (NFC), as tags may
+  /// store the same name in different encodings, for example with decomposed accents.
+  fn normalize_names(self) -> Self {
+    let normalize = |name: String| -> String { name.nfc().collect() };
+    Self {
+      title: normalize(self.title),
+      album: normalize(self.album),
+      track_artists: self.track_artists.into_iter().map(normalize).collect(),
+      album_artists: self.album_artists.into_iter().map(normalize).collect(),
+      featured_artists: self.featured_artists.into_iter().map(normalize).collect(),
+      remixers: self.remixers.into_iter().map(normalize).collect(),
+      conductors: self.conductors.into_iter().map(normalize).collect(),
+      composers: self.composers.into_iter().map(normalize).collect(),
+      performers: self.performers.into_iter().map(normalize).collect(),
+      work: self.work.map(normalize),
+      movement: self.movement.map(normalize),
+      ..self
+    }
+  }
 }
 
 #[derive(Debug, Error)]
@@ -47,6 +104,140 @@ pub enum FilesystemSyncError {
   NoAlbumFail(String),
 }
 
+/// Creates a key from `path` for comparing paths independent of platform-specific path semantics. Backslashes are
+/// converted to forward slashes, verbatim prefixes (`\\?\` and `\\?\UNC\`) are removed, repeated and trailing slashes
+/// are collapsed, and drive letters are lowercased. On platforms with case-insensitive filesystems by default (Windows
+/// and macOS), the entire key is lowercased, so that paths that only differ in case map to the same key.
+pub fn path_key(path: &str) -> String {
+  let path = path.replace('\\', "/");
+  let (unc, path) = if let Some(path) = path.strip_prefix("//?/UNC/") {
+    (true, path)
+  } else if let Some(path) = path.strip_prefix("//?/") {
+    (false, path)
+  } else if let Some(path) = path.strip_prefix("//") {
+    (true, path)
+  } else {
+    (false, path.as_str())
+  };
+  let mut key = String::with_capacity(path.len() + 2);
+  if unc {
+    key.push_str("//");
+  } else if path.starts_with('/') {
+    key.push('/');
+  }
+  key.push_str(&path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("/"));
+  let mut chars = key.chars();
+  if let (Some(drive), Some(':')) = (chars.next(), chars.next()) {
+    if drive.is_ascii_alphabetic() {
+      key.replace_range(0..1, &drive.to_ascii_lowercase().to_string());
+    }
+  }
+  if cfg!(any(windows, target_os = "macos")) {
+    key = key.to_lowercase();
+  }
+  key
+}
+
+/// Checks whether `directory` is reachable: it must be a readable directory that is not empty. An empty directory is
+/// considered unreachable, as it usually is the mount point of an unmounted network share.
+pub fn is_reachable(directory: impl AsRef<Path>) -> bool {
+  match std::fs::read_dir(directory) {
+    Ok(mut entries) => entries.next().is_some(),
+    Err(_) => false,
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum EmbedCoverError {
+  #[error("Failed to read ID3v2 tag")]
+  Id3v2ReadFail(id3::Error),
+  #[error("File '{0}' does not have an ID3v2 tag to embed the cover into")]
+  NoId3v2TagFail(String),
+  #[error("Failed to write ID3v2 tag")]
+  Id3v2WriteFail(id3::Error),
+}
+
+/// Embeds image `data` of `mime_type` as the front cover into the ID3v2 tag of the file at `path`, replacing any
+/// existing front cover. Only the tag is rewritten, so the hash of the audio data does not change. Files without an
+/// ID3v2 tag are not changed, as adding a tag without a title and album would make them fail to synchronize.
+pub fn embed_cover(path: impl AsRef<Path>, mime_type: &str, data: Vec<u8>) -> Result<(), EmbedCoverError> {
+  use EmbedCoverError::*;
+  use id3::frame::{Picture, PictureType};
+  let path = path.as_ref();
+  let mut tag = match id3::Tag::read_from_path(path) {
+    Ok(tag) => tag,
+    Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => return Err(NoId3v2TagFail(path.display().to_string())),
+    Err(e) => return Err(Id3v2ReadFail(e)),
+  };
+  tag.remove_picture_by_type(PictureType::CoverFront);
+  tag.add_picture(Picture { mime_type: mime_type.to_string(), picture_type: PictureType::CoverFront, description: String::new(), data });
+  tag.write_to_path(path, id3::Version::Id3v24).map_err(Id3v2WriteFail)
+}
+
+#[derive(Debug, Error)]
+pub enum ReadTagFramesError {
+  #[error("Failed to open file for reading")]
+  FileOpenFail(std::io::Error),
+  #[error("Failed to check for ID3v1 tag")]
+  Id3v1CheckFail(id3::Error),
+  #[error("Failed to read ID3v2 tag")]
+  Id3v2ReadFail(id3::Error),
+  #[error("Failed to read ID3v1 tag")]
+  Id3v1ReadFail(id3::Error),
+}
+
+/// Reads the frames of the tag of the file at `path` as pairs of frame ID and value as text, for inspecting the tag.
+/// The ID3v2 tag is preferred over the ID3v1 tag, of which the fields are returned with the ID of their ID3v2 frame.
+/// Frames without a textual value, such as pictures, are returned with a description of their content. Returns no
+/// frames if the file has no tag.
+pub fn read_tag_frames(path: impl AsRef<Path>) -> Result<Vec<(String, String)>, ReadTagFramesError> {
+  use ReadTagFramesError::*;
+  let path = path.as_ref();
+  match id3::Tag::read_from_path(path) {
+    Ok(tag) => {
+      let frames = tag.frames().map(|frame| {
+        let content = frame.content();
+        let value = if let Some(text) = content.text() {
+          text.to_string()
+        } else if let Some(extended_text) = content.extended_text() {
+          format!("{}={}", extended_text.description, extended_text.value)
+        } else if let Some(link) = content.link() {
+          link.to_string()
+        } else if let Some(comment) = content.comment() {
+          comment.text.clone()
+        } else if let Some(lyrics) = content.lyrics() {
+          lyrics.text.clone()
+        } else if let Some(picture) = content.picture() {
+          format!("{:?} picture ({}, {} bytes)", picture.picture_type, picture.mime_type, picture.data.len())
+        } else {
+          "(binary data)".to_string()
+        };
+        (frame.id().to_string(), value)
+      }).collect();
+      return Ok(frames);
+    }
+    Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => {}
+    Err(e) => return Err(Id3v2ReadFail(e)),
+  }
+  let mut buf_reader = BufReader::new(File::open(path).map_err(FileOpenFail)?);
+  if !id3::v1::Tag::is_candidate(&mut buf_reader).map_err(Id3v1CheckFail)? {
+    return Ok(Vec::new());
+  }
+  let tag = id3::v1::Tag::read_from(&mut buf_reader).map_err(Id3v1ReadFail)?;
+  let mut frames = vec![
+    ("TIT2".to_string(), tag.title),
+    ("TPE1".to_string(), tag.artist),
+    ("TALB".to_string(), tag.album),
+    ("TYER".to_string(), tag.year),
+    ("COMM".to_string(), tag.comment),
+  ];
+  if let Some(track) = tag.track {
+    frames.push(("TRCK".to_string(), track.to_string()));
+  }
+  frames.retain(|(_, value)| !value.is_empty());
+  Ok(frames)
+}
+
 pub fn sync<S: Into<String>>(directory: S) -> impl Iterator<Item=Result<FilesystemSyncTrack, FilesystemSyncError>> {
   use FilesystemSyncError::*;
   let directory = directory.into();
@@ -58,126 +249,284 @@ pub fn sync<S: Into<String>>(directory: S) -> impl Iterator<Item=Result<Filesyst
         Err(e) => return Some(Err(WalkDirFail(e))),
       };
       if !entry.file_type().is_file() { return None; }
-      let file_name = entry.file_name().to_string_lossy();
-      if file_name.ends_with(".mp3") {
-        // Open the MP3 file for reading.
-        let mut buf_reader = {
-          let file = match File::open(entry.path()) {
-            Ok(file) => file,
-            Err(e) => return Some(Err(FileReadFail(e))),
-          };
-          BufReader::new(file) // Buffer file reads to reduce number of system calls.
-        };
+      sync_file(&directory, entry.path())
+    })
+}
 
-        // Check which ID3 tags are present in the file.
-        let has_id3v2_tag = match id3::Tag::is_candidate(&mut buf_reader) {
-          Ok(b) => b,
-          Err(e) => return Some(Err(Id3v2CheckFail(e))),
-        };
-        let has_id3v1_tag = match id3::v1::Tag::is_candidate(&mut buf_reader) {
-          Ok(b) => b,
-          Err(e) => return Some(Err(Id3v1CheckFail(e))),
-        };
-        if !has_id3v2_tag && !has_id3v1_tag {
-          return None;
-        }
-
-        // Create file path, relative to scan directory.
-        let file_path = entry.path()
-          .strip_prefix(&directory)
-          .unwrap_or_else(|_| panic!("BUG: cannot strip prefix, path '{}' is not prefixed by '{}'", entry.path().display(), directory))
-          .to_string_lossy()
-          .to_string();
-
-        // Create hasher for hashing the audio data of the file.
-        let mut hasher = crc32fast::Hasher::new();
-
-        // Create scanned track from the ID3v1/2 tag.
-        let scanned_track = if has_id3v2_tag {
-          // Prefer ID3v2 tag, over ID3v1.
-          let tag = match id3::Tag::read_from(&mut buf_reader) {
-            Ok(tag) => tag,
-            Err(e) => return Some(Err(Id3v2ReadFail(e))),
-          };
-
-          // Title and album must be present.
-          let title = if let Some(title) = tag.title() {
-            title.to_string()
-          } else {
-            return Some(Err(NoTitleFail(file_path.clone())));
-          };
-          let album = if let Some(album) = tag.album() {
-            album.to_string()
-          } else {
-            return Some(Err(NoAlbumFail(file_path.clone())));
-          };
-
-          // Reset reader to start and skip the ID3v2 tag to get to the audio data.
-          match buf_reader.seek(std::io::SeekFrom::Start(0)) {
-            Err(e) => return Some(Err(FileSeekFail(e))),
-            _ => {}
-          }
-          match id3::Tag::skip(&mut buf_reader) {
-            Err(e) => return Some(Err(Id3v2SkipFail(e))),
-            _ => {}
-          }
-          // Read file to buffer.
-          let mut buffer = Vec::new();
-          if let Err(e) = buf_reader.read_to_end(&mut buffer) {
-            return Some(Err(FileReadFail(e)));
-          }
-          // Calculate hash over the audio data.
-          hasher.update(skip_id3v1(&buffer)); // Possibly skip the ID3v1 tag which is at the end of the file.
-          let hash = hasher.finalize();
-
-          FilesystemSyncTrack {
-            disc_number: tag.disc().map(|u| u as i32),
-            disc_total: tag.total_discs().map(|u| u as i32),
-            track_number: tag.track().map(|u| u as i32),
-            track_total: tag.total_tracks().map(|u| u as i32),
-            title,
-            album,
-            track_artists: tag.artist().map_or(vec![], |a| vec![a.to_string()]), // TODO: support multiple artists.
-            album_artists: tag.album_artist().map_or(vec![], |a| vec![a.to_string()]), // TODO: support multiple artists.
-            file_path,
-            hash,
-          }
-        } else if has_id3v1_tag {
-          let tag = match id3::v1::Tag::read_from(&mut buf_reader) {
-            Ok(tag) => tag,
-            Err(e) => return Some(Err(Id3v1ReadFail(e))),
-          };
-
-          // Read file to buffer.
-          let mut buffer = Vec::new();
-          if let Err(e) = buf_reader.read_to_end(&mut buffer) {
-            return Some(Err(FileReadFail(e)));
-          }
-          // Calculate hash over the audio data.
-          hasher.update(skip_id3v1(&buffer)); // Skip the ID3v1 tag which is at the end of the file.
-          let hash = hasher.finalize();
-
-          FilesystemSyncTrack {
-            disc_number: None,
-            disc_total: None,
-            track_number: tag.track.map(|u| u as i32),
-            track_total: None,
-            title: tag.title,
-            album: tag.album,
-            track_artists: vec![tag.artist], // TODO: support multiple artists.
-            album_artists: vec![],
-            file_path,
-            hash,
-          }
-        } else {
-          return None;
-        };
+/// Scans the file at `path`, which must be inside `directory`, into a track. Returns `None` if the file is not an MP3
+/// file or has no ID3 tag.
+pub fn sync_file(directory: &str, path: &Path) -> Option<Result<FilesystemSyncTrack, FilesystemSyncError>> {
+  use FilesystemSyncError::*;
+  let file_name = path.file_name()?.to_string_lossy();
+  if !file_name.ends_with(".mp3") { return None; }
 
-        Some(Ok(scanned_track))
-      } else {
-        None
-      }
-    })
+  // Open the MP3 file for reading.
+  let mut buf_reader = {
+    let file = match File::open(path) {
+      Ok(file) => file,
+      Err(e) => return Some(Err(FileReadFail(e))),
+    };
+    BufReader::new(file) // Buffer file reads to reduce number of system calls.
+  };
+
+  // Check which ID3 tags are present in the file.
+  let has_id3v2_tag = match id3::Tag::is_candidate(&mut buf_reader) {
+    Ok(b) => b,
+    Err(e) => return Some(Err(Id3v2CheckFail(e))),
+  };
+  let has_id3v1_tag = match id3::v1::Tag::is_candidate(&mut buf_reader) {
+    Ok(b) => b,
+    Err(e) => return Some(Err(Id3v1CheckFail(e))),
+  };
+  if !has_id3v2_tag && !has_id3v1_tag {
+    return None;
+  }
+
+  // Create file path, relative to scan directory.
+  let file_path = path
+    .strip_prefix(directory)
+    .unwrap_or_else(|_| panic!("BUG: cannot strip prefix, path '{}' is not prefixed by '{}'", path.display(), directory))
+    .to_string_lossy()
+    .to_string();
+
+  // Create hasher for hashing the audio data of the file.
+  let mut hasher = crc32fast::Hasher::new();
+
+  // Create scanned track from the ID3v1/2 tag.
+  let scanned_track = if has_id3v2_tag {
+    // Prefer ID3v2 tag, over ID3v1.
+    let tag = match id3::Tag::read_from(&mut buf_reader) {
+      Ok(tag) => tag,
+      Err(e) => return Some(Err(Id3v2ReadFail(e))),
+    };
+
+    // Title and album must be present.
+    let title = if let Some(title) = tag.title() {
+      title.to_string()
+    } else {
+      return Some(Err(NoTitleFail(file_path.clone())));
+    };
+    let album = if let Some(album) = tag.album() {
+      album.to_string()
+    } else {
+      return Some(Err(NoAlbumFail(file_path.clone())));
+    };
+
+    // Reset reader to start and skip the ID3v2 tag to get to the audio data.
+    match buf_reader.seek(std::io::SeekFrom::Start(0)) {
+      Err(e) => return Some(Err(FileSeekFail(e))),
+      _ => {}
+    }
+    match id3::Tag::skip(&mut buf_reader) {
+      Err(e) => return Some(Err(Id3v2SkipFail(e))),
+      _ => {}
+    }
+    // Read file to buffer.
+    let mut buffer = Vec::new();
+    if let Err(e) = buf_reader.read_to_end(&mut buffer) {
+      return Some(Err(FileReadFail(e)));
+    }
+    // Calculate hash over the audio data.
+    hasher.update(skip_id3v1(&buffer)); // Possibly skip the ID3v1 tag which is at the end of the file.
+    let hash = hasher.finalize();
+    let bitrate = mp3_bitrate(&buffer);
+    let replay_gain = read_replay_gain(&tag);
+    let explicit = read_explicit(&tag);
+    let classical = read_classical(&tag);
+
+    FilesystemSyncTrack {
+      disc_number: tag.disc().map(|u| u as i32),
+      disc_total: tag.total_discs().map(|u| u as i32),
+      track_number: tag.track().map(|u| u as i32),
+      track_total: tag.total_tracks().map(|u| u as i32),
+      title,
+      album,
+      track_artists: tag.artist().map_or(vec![], |a| vec![a.to_string()]), // TODO: support multiple artists.
+      album_artists: tag.album_artist().map_or(vec![], |a| vec![a.to_string()]), // TODO: support multiple artists.
+      file_path_key: path_key(&file_path),
+      file_path,
+      hash,
+      bitrate,
+      replay_gain,
+      explicit,
+      featured_artists: vec![],
+      remixers: read_names(&tag, "TPE4"),
+      conductors: read_names(&tag, "TPE3"),
+      composers: classical.composers,
+      performers: classical.performers,
+      work: classical.work,
+      movement: classical.movement,
+      movement_number: classical.movement_number,
+    }
+  } else if has_id3v1_tag {
+    let tag = match id3::v1::Tag::read_from(&mut buf_reader) {
+      Ok(tag) => tag,
+      Err(e) => return Some(Err(Id3v1ReadFail(e))),
+    };
+
+    // Read file to buffer.
+    let mut buffer = Vec::new();
+    if let Err(e) = buf_reader.read_to_end(&mut buffer) {
+      return Some(Err(FileReadFail(e)));
+    }
+    // Calculate hash over the audio data.
+    hasher.update(skip_id3v1(&buffer)); // Skip the ID3v1 tag which is at the end of the file.
+    let hash = hasher.finalize();
+    let bitrate = mp3_bitrate(&buffer);
+
+    FilesystemSyncTrack {
+      disc_number: None,
+      disc_total: None,
+      track_number: tag.track.map(|u| u as i32),
+      track_total: None,
+      title: tag.title,
+      album: tag.album,
+      track_artists: vec![tag.artist], // TODO: support multiple artists.
+      album_artists: vec![],
+      file_path_key: path_key(&file_path),
+      file_path,
+      hash,
+      bitrate,
+      replay_gain: FilesystemSyncReplayGain::default(), // ID3v1 tags cannot store ReplayGain values.
+      explicit: None, // ID3v1 tags cannot store whether a track has explicit content.
+      // ID3v1 tags cannot store remixers, conductors, composers, performers, works, or movements.
+      featured_artists: vec![],
+      remixers: vec![],
+      conductors: vec![],
+      composers: vec![],
+      performers: vec![],
+      work: None,
+      movement: None,
+      movement_number: None,
+    }
+  } else {
+    return None;
+  };
+
+  Some(Ok(scanned_track.normalize_names()))
+}
+
+/// Reads ReplayGain values from the user-defined text (`TXXX`) frames of `tag`. R128 gains, which are stored in 1/256 dB
+/// relative to -23 LUFS, are converted to ReplayGain gains relative to -18 LUFS, and are only used when there is no
+/// ReplayGain gain.
+fn read_replay_gain(tag: &id3::Tag) -> FilesystemSyncReplayGain {
+  let mut replay_gain = FilesystemSyncReplayGain::default();
+  let mut r128_track_gain = None;
+  let mut r128_album_gain = None;
+  for extended_text in tag.extended_texts() {
+    let value = extended_text.value.trim();
+    match extended_text.description.to_ascii_uppercase().as_str() {
+      "REPLAYGAIN_TRACK_GAIN" => replay_gain.track_gain = parse_replay_gain(value),
+      "REPLAYGAIN_TRACK_PEAK" => replay_gain.track_peak = value.parse().ok(),
+      "REPLAYGAIN_ALBUM_GAIN" => replay_gain.album_gain = parse_replay_gain(value),
+      "REPLAYGAIN_ALBUM_PEAK" => replay_gain.album_peak = value.parse().ok(),
+      "R128_TRACK_GAIN" => r128_track_gain = parse_r128_gain(value),
+      "R128_ALBUM_GAIN" => r128_album_gain = parse_r128_gain(value),
+      _ => {}
+    }
+  }
+  replay_gain.track_gain = replay_gain.track_gain.or(r128_track_gain);
+  replay_gain.album_gain = replay_gain.album_gain.or(r128_album_gain);
+  replay_gain
+}
+
+/// Reads whether the track has explicit content from the user-defined text (`TXXX`) frames of `tag`. The iTunes advisory
+/// rating is preferred, where 1 (or 4) means explicit, 2 means clean, and 0 means not rated. Otherwise, an `EXPLICIT`
+/// frame as written by other taggers is used.
+fn read_explicit(tag: &id3::Tag) -> Option<bool> {
+  let mut advisory = None;
+  let mut explicit = None;
+  for extended_text in tag.extended_texts() {
+    let value = extended_text.value.trim();
+    match extended_text.description.to_ascii_uppercase().as_str() {
+      "ITUNESADVISORY" => advisory = match value {
+        "1" | "4" => Some(true),
+        "2" => Some(false),
+        _ => None,
+      },
+      "EXPLICIT" => explicit = match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+      },
+      _ => {}
+    }
+  }
+  advisory.or(explicit)
+}
+
+/// Composer, performer, work, and movement metadata of classical music read from a tag.
+#[derive(Default)]
+struct ClassicalTags {
+  composers: Vec<String>,
+  performers: Vec<String>,
+  work: Option<String>,
+  movement: Option<String>,
+  movement_number: Option<i32>,
+}
+
+/// Reads classical music metadata from `tag`. Composers are read from the composer (`TCOM`) frame, and performers from
+/// the musician credits (`TMCL`) frame and `PERFORMER` user-defined text frames. Movements are read from the movement
+/// name (`MVNM`) and number (`MVIN`) frames. The work is read from a `WORK` user-defined text frame, or otherwise from
+/// the content group (`TIT1`) frame, but only when the track has a movement, as most taggers use that frame for other
+/// kinds of grouping.
+fn read_classical(tag: &id3::Tag) -> ClassicalTags {
+  let text = |id: &str| -> Option<&str> {
+    tag.get(id).and_then(|frame| frame.content().text()).map(|text| text.trim()).filter(|text| !text.is_empty())
+  };
+  let mut classical = ClassicalTags::default();
+  if let Some(composers) = text("TCOM") {
+    classical.composers = split_names(composers);
+  }
+  if let Some(musician_credits) = text("TMCL") {
+    // Musician credits alternate between the instrument or role, and the name of the musician.
+    classical.performers = musician_credits.split('\0').skip(1).step_by(2)
+      .map(|name| name.trim())
+      .filter(|name| !name.is_empty())
+      .map(|name| name.to_string())
+      .collect();
+  }
+  let mut work = None;
+  for extended_text in tag.extended_texts() {
+    let value = extended_text.value.trim();
+    match extended_text.description.to_ascii_uppercase().as_str() {
+      "WORK" if !value.is_empty() => work = Some(value.to_string()),
+      "PERFORMER" => classical.performers.extend(split_names(value)),
+      _ => {}
+    }
+  }
+  classical.movement = text("MVNM").map(|movement| movement.to_string());
+  classical.movement_number = text("MVIN").and_then(|movement_number| movement_number.split('/').next()?.trim().parse().ok());
+  let has_movement = classical.movement.is_some() || classical.movement_number.is_some();
+  classical.work = work.or_else(|| text("TIT1").filter(|_| has_movement).map(|work| work.to_string()));
+  let mut seen_performers = HashSet::new();
+  classical.performers.retain(|performer| seen_performers.insert(performer.clone()));
+  classical
+}
+
+/// Reads the names in the text frame with `id` of `tag`, or no names if the frame does not exist.
+fn read_names(tag: &id3::Tag, id: &str) -> Vec<String> {
+  tag.get(id).and_then(|frame| frame.content().text()).map_or(vec![], split_names)
+}
+
+/// Splits multiple names in a text frame, which ID3v2.4 separates with null characters, and earlier versions commonly
+/// with slashes or semicolons.
+fn split_names(names: &str) -> Vec<String> {
+  names.split(|c: char| c == '\0' || c == '/' || c == ';')
+    .map(|name| name.trim())
+    .filter(|name| !name.is_empty())
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Parses a ReplayGain gain such as "-6.54 dB".
+fn parse_replay_gain(value: &str) -> Option<f32> {
+  value.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace()).parse().ok()
+}
+
+/// Parses an R128 gain such as "-1234", and converts it to a ReplayGain gain in dB.
+fn parse_r128_gain(value: &str) -> Option<f32> {
+  value.parse::<i32>().ok().map(|gain| gain as f32 / 256.0 + 5.0)
 }
 
 fn skip_id3v1(buffer: &[u8]) -> &[u8] {
@@ -190,3 +539,33 @@ fn skip_id3v1(buffer: &[u8]) -> &[u8] {
     buffer
   }
 }
+
+/// Maximum number of bytes to search for the first MPEG audio frame header.
+const MP3_FRAME_SEARCH_LIMIT: usize = 64 * 1024;
+
+/// Gets the bitrate in kbit/s from the first MPEG audio frame header in `buffer`, or `None` if no valid frame header was
+/// found.
+fn mp3_bitrate(buffer: &[u8]) -> Option<i32> {
+  const V1_L1: [i32; 15] = [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448];
+  const V1_L2: [i32; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384];
+  const V1_L3: [i32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+  const V2_L1: [i32; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256];
+  const V2_L2_L3: [i32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+  let search_len = buffer.len().min(MP3_FRAME_SEARCH_LIMIT);
+  buffer[..search_len].windows(3).find_map(|header| {
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 { return None; }
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+    let bitrate_index = (header[2] >> 4) as usize;
+    // Version 0b01 and layer 0b00 are reserved, and bitrate index 0 (free) and 15 (bad) do not specify a bitrate.
+    if version == 0b01 || layer == 0b00 || bitrate_index == 0 || bitrate_index == 15 { return None; }
+    let table = match (version, layer) {
+      (0b11, 0b11) => &V1_L1,
+      (0b11, 0b10) => &V1_L2,
+      (0b11, _) => &V1_L3,
+      (_, 0b11) => &V2_L1,
+      _ => &V2_L2_L3,
+    };
+    Some(table[bitrate_index])
+  })
+}
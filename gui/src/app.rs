@@ -1,22 +1,75 @@
-use iced::{Application, Command, Element, Subscription};
-use tracing::error;
+use std::path::PathBuf;
+
+use iced::{Application, Color, Command, Container, Element, Length, Subscription};
+use tracing::{error, warn};
 use url::Url;
 
+use musium_client_settings::{ClientDirs, ClientProfile, ClientSettings};
+use musium_core::api::UserPreferences;
+use musium_core::format_error::FormatError;
 use musium_core::model::UserLogin;
-use musium_player::{HttpClient, Player};
+#[cfg(feature = "discord_presence")]
+use musium_discord_presence::{DiscordPresence, DiscordPresenceConfig};
+use musium_i18n::{FALLBACK_LOCALE, Localizer};
+use musium_player::{Client, HttpClient, Player};
+use musium_player::remote::{RemoteCommand, RemoteControlServer};
 
+use crate::hotkey::{HotkeyAction, HotkeyBindings, Hotkeys};
 use crate::page::{login, main};
+use crate::remote;
+use crate::theme::{Theme, ThemeVariant};
+use crate::tray::{Tray, TrayAction, TrayLabels};
 use crate::util::Update;
 
 pub struct Flags<P: Player> {
   pub initial_url: Url,
   pub initial_user_login: UserLogin,
+  /// Kiosk token to log in with instead of `initial_user_login`, or `None` to log in with a name and password.
+  pub kiosk_token: Option<String>,
+  /// Directories to save the client settings in after logging in, or `None` to not save client settings.
+  pub client_dirs: Option<ClientDirs>,
+  /// Client settings loaded at startup, updated with the server URL and login name after logging in.
+  pub client_settings: ClientSettings,
+  /// Name of the profile to log in with, or `None` to log in with the default profile.
+  pub profile_name: Option<String>,
+  /// Locale of the user interface, or `None` to use the locale in the preferences of the user after logging in.
+  pub locale: Option<String>,
+  /// Theme of the user interface, or `None` to use the theme in the preferences of the user after logging in.
+  pub theme: Option<ThemeVariant>,
+  /// Accent color of the user interface, or `None` to use the accent color of the theme.
+  pub accent_color: Option<Color>,
+  /// Whether to show a tray icon with playback controls.
+  pub tray: bool,
+  /// Global hotkeys that control playback while another application is focused.
+  pub hotkey_bindings: HotkeyBindings,
+  /// Path of the local control socket to listen on for remote commands, or `None` to not listen for remote commands.
+  pub remote_control_socket: Option<PathBuf>,
+  /// Configuration for publishing the playing track to Discord Rich Presence, or `None` to not publish it.
+  #[cfg(feature = "discord_presence")]
+  pub discord_presence: Option<DiscordPresenceConfig>,
   pub player: P,
 }
 
 pub struct App<P: Player<Client=HttpClient>> {
   player: P,
   current_page: Page<P>,
+  client_dirs: Option<ClientDirs>,
+  client_settings: ClientSettings,
+  /// Name of the profile that was used to log in, or `None` for the default profile.
+  profile_name: Option<String>,
+  /// Whether the GUI runs in read-only kiosk mode.
+  kiosk: bool,
+  localizer: Localizer,
+  locale_from_preferences: bool,
+  theme: Theme,
+  theme_from_preferences: bool,
+  accent_color: Option<Color>,
+  tray: Option<Tray>,
+  hotkeys: Option<Hotkeys>,
+  remote_control: Option<RemoteControlServer>,
+  #[cfg(feature = "discord_presence")]
+  discord_presence: Option<DiscordPresence>,
+  should_exit: bool,
 }
 
 #[derive(Debug)]
@@ -29,6 +82,10 @@ enum Page<P: Player<Client=HttpClient>> {
 pub enum Message<P: Player> {
   LoginPage(login::Message<P>),
   MainPage(main::Message<P>),
+  ReceiveUserPreferences(Result<UserPreferences, <P::Client as Client>::UserError>),
+  Tray(TrayAction),
+  Hotkey(HotkeyAction),
+  Remote(RemoteCommand),
 }
 
 impl<P: Player<Client=HttpClient>> Application for App<P> {
@@ -37,30 +94,224 @@ impl<P: Player<Client=HttpClient>> Application for App<P> {
   type Flags = Flags<P>;
 
   fn new(flags: Flags<P>) -> (Self, Command<Message<P>>) {
-    let current_page = Page::Login(login::Page::new(flags.initial_url, flags.initial_user_login));
-    let app = Self { player: flags.player, current_page };
-    (app, Command::none())
+    let kiosk = flags.kiosk_token.is_some();
+    let (login_page, login_command) = match flags.kiosk_token {
+      Some(kiosk_token) => login::Page::new_kiosk(flags.initial_url, kiosk_token, &flags.player),
+      None => {
+        let profiles = Self::selectable_profiles(&flags.client_settings);
+        (login::Page::new(flags.initial_url, flags.initial_user_login, flags.profile_name.clone(), profiles), Command::none())
+      }
+    };
+    let current_page = Page::Login(login_page);
+    let localizer = Localizer::new(flags.locale.as_deref().unwrap_or(FALLBACK_LOCALE));
+    let locale_from_preferences = flags.locale.is_none();
+    let accent_color = flags.accent_color;
+    let theme = Self::create_theme(flags.theme.unwrap_or_default(), accent_color);
+    let theme_from_preferences = flags.theme.is_none();
+    let tray = if flags.tray { Tray::spawn(Self::tray_labels(&localizer)) } else { None };
+    let hotkeys = if flags.hotkey_bindings.is_empty() { None } else { Hotkeys::register(flags.hotkey_bindings) };
+    let remote_control = flags.remote_control_socket.and_then(|path| match RemoteControlServer::listen(path) {
+      Ok(server) => Some(server),
+      Err(e) => {
+        error!("Failed to listen for remote commands: {:?}", FormatError::new(&e));
+        None
+      }
+    });
+    let app = Self {
+      player: flags.player,
+      current_page,
+      client_dirs: flags.client_dirs,
+      client_settings: flags.client_settings,
+      profile_name: flags.profile_name,
+      kiosk,
+      localizer,
+      locale_from_preferences,
+      theme,
+      theme_from_preferences,
+      accent_color,
+      tray,
+      hotkeys,
+      remote_control,
+      #[cfg(feature = "discord_presence")]
+      discord_presence: flags.discord_presence.map(DiscordPresence::spawn),
+      should_exit: false,
+    };
+    (app, login_command.map(|m| Message::LoginPage(m)))
   }
 
   fn title(&self) -> String {
-    "Musium".to_string()
+    self.localizer.text("app-title")
   }
 
   fn update(&mut self, message: Message<P>) -> Command<Message<P>> {
+    let command = self.update_page(message);
+    if let (Some(tray), Page::Main(p)) = (&mut self.tray, &self.current_page) {
+      tray.set_status(p.tray_status());
+    }
+    if let (Some(remote_control), Page::Main(p)) = (&self.remote_control, &self.current_page) {
+      remote_control.set_status(p.remote_status());
+    }
+    #[cfg(feature = "discord_presence")] {
+      if let (Some(discord_presence), Page::Main(p)) = (&mut self.discord_presence, &self.current_page) {
+        discord_presence.set_track(p.presence_track());
+      }
+    }
+    command
+  }
+
+  fn subscription(&self) -> Subscription<Message<P>> {
+    let page_subscription = match &self.current_page {
+      Page::Login(_) => { Subscription::none() }
+      Page::Main(p) => { p.subscription(&self.player).map(|m| Message::MainPage(m)) }
+    };
+    let tray_subscription = match &self.tray {
+      Some(tray) => tray.subscription().map(|a| Message::Tray(a)),
+      None => Subscription::none(),
+    };
+    let hotkey_subscription = match &self.hotkeys {
+      Some(hotkeys) => hotkeys.subscription().map(|a| Message::Hotkey(a)),
+      None => Subscription::none(),
+    };
+    let remote_subscription = match &self.remote_control {
+      Some(remote_control) => remote::subscription(remote_control).map(|c| Message::Remote(c)),
+      None => Subscription::none(),
+    };
+    Subscription::batch([page_subscription, tray_subscription, hotkey_subscription, remote_subscription])
+  }
+
+  fn should_exit(&self) -> bool {
+    self.should_exit
+  }
+
+  fn background_color(&self) -> Color {
+    self.theme.palette.background
+  }
+
+  fn view(&mut self) -> Element<'_, Message<P>> {
+    let page = match &mut self.current_page {
+      Page::Login(p) => p.view(&self.localizer, self.theme).map(|m| Message::LoginPage(m)),
+      Page::Main(p) => p.view(&self.localizer, self.theme).map(|m| Message::MainPage(m)),
+    };
+    Container::new(page)
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .style(self.theme)
+      .into()
+  }
+}
+
+impl<P: Player<Client=HttpClient>> App<P> {
+  /// Saves the server URL and login name that were used to log in into the profile with `profile_name`, and makes it
+  /// the current profile, so that they are used the next time.
+  fn save_client_settings(&mut self, profile_name: Option<String>, url_base: Option<String>, login_name: &str) {
+    self.profile_name = profile_name.clone();
+    let client_dirs = match &self.client_dirs {
+      Some(client_dirs) => client_dirs,
+      None => return,
+    };
+    let mut client_settings = self.client_settings.clone();
+    let profile = client_settings.profile(profile_name.as_deref());
+    client_settings.set_profile(profile_name.as_deref(), ClientProfile {
+      url_base: url_base.or(profile.url_base),
+      login_name: Some(login_name.to_string()),
+    });
+    client_settings.current_profile = profile_name;
+    if client_settings == self.client_settings { return; }
+    if let Err(e) = client_settings.save(client_dirs) {
+      warn!("Failed to save client settings: {:?}", FormatError::new(&e));
+    }
+    self.client_settings = client_settings;
+  }
+
+  fn update_page(&mut self, message: Message<P>) -> Command<Message<P>> {
     match (&mut self.current_page, message) {
       (Page::Login(p), Message::LoginPage(m)) => {
         let Update { action, command } = p.update(&mut self.player, m);
         let command = command.map(|m| Message::LoginPage(m));
         if let Some(login::Action::LoggedIn(user)) = action {
-          let (main_page, main_command) = main::Page::new(user, &mut self.player);
+          // Do not remember the kiosk user as login name, as kiosk mode logs in without a name.
+          if !self.kiosk {
+            let profile_name = p.profile_name().map(|name| name.to_string());
+            let url_base = p.url().map(|url| url.to_string());
+            self.save_client_settings(profile_name, url_base, &user.name);
+          }
+          let (main_page, main_command) = main::Page::new(user, self.kiosk, &mut self.player);
           let main_command = main_command.map(|m| Message::MainPage(m));
           self.current_page = Page::Main(main_page);
-          Command::batch(vec![command, main_command])
+          let mut commands = vec![command, main_command];
+          if self.locale_from_preferences || self.theme_from_preferences {
+            let player = self.player.clone();
+            commands.push(Command::perform(
+              async move { player.get_client().get_user_preferences().await },
+              |r| Message::ReceiveUserPreferences(r),
+            ));
+          }
+          Command::batch(commands)
         } else {
           command
         }
       }
+      (Page::Main(_), Message::MainPage(main::Message::SwitchProfile)) => {
+        // Return to the login page with the current profile, where another profile can be selected to log in with.
+        let profile = self.client_settings.profile(self.profile_name.as_deref());
+        let url = self.player.get_client().url().clone();
+        let user_login = UserLogin { name: profile.login_name.unwrap_or_default(), password: String::new() };
+        let profiles = Self::selectable_profiles(&self.client_settings);
+        self.current_page = Page::Login(login::Page::new(url, user_login, self.profile_name.clone(), profiles));
+        let player = self.player.clone();
+        Command::perform(
+          async move { player.stop().await },
+          |r| {
+            if let Err(e) = r {
+              error!("Failed to stop playback when switching profiles: {:?}", FormatError::new(&e));
+            }
+            Message::LoginPage(login::Message::Return)
+          },
+        )
+      }
       (Page::Main(p), Message::MainPage(m)) => p.update(&mut self.player, m).map(|m| Message::MainPage(m)),
+      (_, Message::Tray(TrayAction::Quit)) => {
+        self.should_exit = true;
+        Command::none()
+      }
+      (Page::Main(p), Message::Tray(TrayAction::TogglePlay)) => p.update(&mut self.player, main::Message::RequestTogglePlay).map(|m| Message::MainPage(m)),
+      (Page::Main(p), Message::Tray(TrayAction::NextTrack)) => p.update(&mut self.player, main::Message::RequestNextTrack).map(|m| Message::MainPage(m)),
+      (Page::Login(_), Message::Tray(_)) => Command::none(), // Nothing is playing before logging in -> ignore action.
+      (Page::Main(p), Message::Hotkey(action)) => {
+        let m = match action {
+          HotkeyAction::TogglePlay => main::Message::RequestTogglePlay,
+          HotkeyAction::NextTrack => main::Message::RequestNextTrack,
+          HotkeyAction::VolumeUp => main::Message::RequestChangeVolume(main::VOLUME_STEP),
+          HotkeyAction::VolumeDown => main::Message::RequestChangeVolume(-main::VOLUME_STEP),
+        };
+        p.update(&mut self.player, m).map(|m| Message::MainPage(m))
+      }
+      (Page::Login(_), Message::Hotkey(_)) => Command::none(), // Nothing is playing before logging in -> ignore action.
+      (Page::Main(p), Message::Remote(command)) => match p.remote_message(command) {
+        Some(m) => p.update(&mut self.player, m).map(|m| Message::MainPage(m)),
+        None => Command::none(),
+      },
+      (Page::Login(_), Message::Remote(_)) => Command::none(), // Nothing is playing before logging in -> ignore command.
+      (_, Message::ReceiveUserPreferences(r)) => {
+        match r {
+          Ok(UserPreferences { locale, theme, .. }) => {
+            if let (true, Some(locale)) = (self.locale_from_preferences, locale) {
+              self.localizer = Localizer::new(&locale);
+              if let Some(tray) = &mut self.tray {
+                tray.set_labels(Self::tray_labels(&self.localizer));
+              }
+            }
+            if let (true, Some(theme)) = (self.theme_from_preferences, theme) {
+              match theme.parse() {
+                Ok(variant) => self.theme = Self::create_theme(variant, self.accent_color),
+                Err(e) => error!("Failed to apply theme from user preferences: {:?}", FormatError::new(&e)),
+              }
+            }
+          }
+          Err(e) => error!("Failed to receive user preferences: {:?}", FormatError::new(&e)),
+        }
+        Command::none()
+      }
       (p, m) => {
         error!("[BUG] Requested update with message '{:?}', but that message cannot be handled by the current page '{:?}' or the application itself", m, p);
         Command::none()
@@ -68,17 +319,29 @@ impl<P: Player<Client=HttpClient>> Application for App<P> {
     }
   }
 
-  fn subscription(&self) -> Subscription<Message<P>> {
-    match &self.current_page {
-      Page::Login(_) => { Subscription::none() }
-      Page::Main(p) => { p.subscription(&self.player).map(|m| Message::MainPage(m)) }
+  /// Gets the profiles that can be selected on the login page, starting with the default profile, or no profiles when
+  /// there are no named profiles to switch between.
+  fn selectable_profiles(client_settings: &ClientSettings) -> Vec<(Option<String>, ClientProfile)> {
+    if client_settings.profiles.is_empty() { return Vec::new(); }
+    std::iter::once((None, client_settings.profile(None)))
+      .chain(client_settings.profiles.iter().map(|(name, profile)| (Some(name.clone()), profile.clone())))
+      .collect()
+  }
+
+  fn tray_labels(localizer: &Localizer) -> TrayLabels {
+    TrayLabels {
+      title: localizer.text("app-title"),
+      toggle_play: localizer.text("player-toggle-play"),
+      next_track: localizer.text("player-next-track"),
+      quit: localizer.text("tray-quit"),
     }
   }
 
-  fn view(&mut self) -> Element<'_, Message<P>> {
-    match &mut self.current_page {
-      Page::Login(p) => p.view().map(|m| Message::LoginPage(m)),
-      Page::Main(p) => p.view().map(|m| Message::MainPage(m)),
+  fn create_theme(variant: ThemeVariant, accent_color: Option<Color>) -> Theme {
+    let theme = Theme::new(variant);
+    match accent_color {
+      Some(accent_color) => theme.with_accent(accent_color),
+      None => theme,
     }
   }
 }
@@ -0,0 +1,107 @@
+use std::hash::{Hash, Hasher};
+
+use iced::futures::{self, stream::BoxStream};
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+pub use livesplit_hotkey::Hotkey;
+use livesplit_hotkey::Hook;
+use tokio::sync::broadcast;
+use tracing::{debug, error, warn};
+
+use musium_core::format_error::FormatError;
+
+/// Action that is triggered by pressing a global hotkey.
+#[derive(Copy, Clone, Debug)]
+pub enum HotkeyAction {
+  TogglePlay,
+  NextTrack,
+  VolumeUp,
+  VolumeDown,
+}
+
+/// Global hotkeys bound to actions, where `None` leaves an action unbound.
+#[derive(Clone, Default, Debug)]
+pub struct HotkeyBindings {
+  pub toggle_play: Option<Hotkey>,
+  pub next_track: Option<Hotkey>,
+  pub volume_up: Option<Hotkey>,
+  pub volume_down: Option<Hotkey>,
+}
+
+impl HotkeyBindings {
+  pub fn is_empty(&self) -> bool {
+    self.toggle_play.is_none() && self.next_track.is_none() && self.volume_up.is_none() && self.volume_down.is_none()
+  }
+}
+
+/// Hotkeys that are registered with the operating system, so that they are triggered even when another application is
+/// focused. Triggered actions are received through `subscription`. Hotkeys are unregistered when this is dropped.
+pub struct Hotkeys {
+  actions: broadcast::Sender<HotkeyAction>,
+  _hook: Hook,
+}
+
+impl Hotkeys {
+  /// Registers the hotkeys of `bindings`, returning `None` if global hotkeys are not supported on this platform.
+  /// Hotkeys that fail to register, for example because another application already registered them, are skipped.
+  pub fn register(bindings: HotkeyBindings) -> Option<Self> {
+    let hook = match Hook::new() {
+      Ok(hook) => hook,
+      Err(e) => {
+        warn!("Global hotkeys are not supported on this platform; not registering hotkeys: {:?}", FormatError::new(&e));
+        return None;
+      }
+    };
+    let (actions, _) = broadcast::channel(16);
+    let bindings = [
+      (bindings.toggle_play, HotkeyAction::TogglePlay),
+      (bindings.next_track, HotkeyAction::NextTrack),
+      (bindings.volume_up, HotkeyAction::VolumeUp),
+      (bindings.volume_down, HotkeyAction::VolumeDown),
+    ];
+    for (hotkey, action) in bindings {
+      if let Some(hotkey) = hotkey {
+        let actions = actions.clone();
+        match hook.register(hotkey, move || { actions.send(action).ok(); }) { // OK: no receivers when the application is not subscribed -> ignore action.
+          Ok(_) => debug!("Registered global hotkey '{}' for {:?}", hotkey, action),
+          Err(e) => error!("Failed to register global hotkey '{}' for {:?}: {:?}", hotkey, action, FormatError::new(&e)),
+        }
+      }
+    }
+    Some(Self { actions, _hook: hook })
+  }
+
+  pub fn subscription(&self) -> Subscription<HotkeyAction> {
+    Subscription::from_recipe(HotkeyActionSubscription { actions: self.actions.clone() })
+  }
+}
+
+// Hotkey action subscription
+
+struct HotkeyActionSubscription {
+  actions: broadcast::Sender<HotkeyAction>,
+}
+
+impl<H, I> Recipe<H, I> for HotkeyActionSubscription where
+  H: Hasher
+{
+  type Output = HotkeyAction;
+
+  fn hash(&self, state: &mut H) {
+    // Only one set of hotkeys is registered, so hash just the marker struct.
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+  }
+
+  fn stream(self: Box<Self>, _input: BoxStream<I>) -> BoxStream<Self::Output> {
+    Box::pin(futures::stream::unfold(self.actions.subscribe(), |mut receiver| async move {
+      loop {
+        match receiver.recv().await {
+          Ok(action) => return Some((action, receiver)),
+          Err(broadcast::error::RecvError::Lagged(skipped)) => warn!("Skipped {} hotkey actions", skipped),
+          Err(broadcast::error::RecvError::Closed) => return None,
+        }
+      }
+    }))
+  }
+}
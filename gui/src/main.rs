@@ -1,38 +1,145 @@
 #![feature(deadline_api)]
 
-use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
 use dotenv;
 use iced::Application;
 use metrics_core::{Builder, Drain, Observe};
 use metrics_observer_yaml::{YamlBuilder, YamlObserver};
 use metrics_runtime::{Controller, Receiver};
 use structopt::StructOpt;
-use tracing::trace;
+use tracing::{trace, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 use tracing_subscriber::prelude::*;
 use url::Url;
 
 use app::{App, Flags};
+use hotkey::{Hotkey, HotkeyBindings};
+use musium_client_settings::{AudioOutputKind, ClientDirs, ClientSettings, parse_profile_name};
+use musium_core::api::QuietHours;
+use musium_core::format_error::FormatError;
 use musium_core::model::*;
-use musium_player::create_default_player;
+#[cfg(feature = "discord_presence")]
+use musium_discord_presence::DiscordPresenceConfig;
+use musium_player::{create_default_player, Player};
+use musium_player::output::AudioOutputSelection;
+use musium_player::remote::default_remote_socket_path;
+use musium_player::volume::{OutputMaxVolume, VolumeLimits};
+use theme::{parse_color, ThemeVariant};
 
 mod app;
+mod hotkey;
 mod page;
+mod remote;
+mod reveal;
+mod theme;
+mod tray;
 mod util;
 mod widget;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "cli", about = "Musium CLI")]
 struct Opt {
-  /// Base URL to use for sending HTTP requests to the server
+  /// Name of the client settings profile to use, with its own server URL, login name, and caches. Defaults to the
+  /// current profile in the client settings file, which is set after logging in. Can also be switched on the login page
+  #[structopt(long, env = "MUSIUM_PROFILE", parse(try_from_str = parse_profile_name))]
+  profile: Option<String>,
+  /// Base URL to use for sending HTTP requests to the server. Defaults to the URL of the profile in the client settings
+  /// file, which is saved after logging in
   #[structopt(long, env = "MUSIUM_URL_BASE")]
-  url_base: Url,
-  /// Username for logging into the server
+  url_base: Option<Url>,
+  /// Username for logging into the server. Defaults to the login name of the profile in the client settings file, which
+  /// is saved after logging in
   #[structopt(long, env = "MUSIUM_LOGIN_NAME")]
-  name: String,
-  /// Password for logging into the server
+  name: Option<String>,
+  /// Password for logging into the server. Can also be entered on the login page
   #[structopt(long, env = "MUSIUM_LOGIN_PASSWORD")]
-  password: String,
+  password: Option<String>,
+  /// Kiosk token of the server, to log in as its kiosk user without a name and password. Kiosk mode can browse and
+  /// play, but not modify ratings, playlists, sources, or other data, such as for a shared living-room machine
+  #[structopt(long, env = "MUSIUM_KIOSK_TOKEN", hide_env_values = true)]
+  kiosk_token: Option<String>,
+  /// Locale of the user interface, such as `nl-NL`. Defaults to the locale in the client settings file, or the locale
+  /// in the preferences of the user after logging in
+  #[structopt(long, env = "MUSIUM_LOCALE")]
+  locale: Option<String>,
+  /// Theme of the user interface: `light` or `dark`. Defaults to the theme in the preferences of the user after logging
+  /// in
+  #[structopt(long, env = "MUSIUM_THEME")]
+  theme: Option<ThemeVariant>,
+  /// Accent color of the user interface, as a hexadecimal RGB color such as `#3273dc`
+  #[structopt(long, env = "MUSIUM_ACCENT_COLOR", parse(try_from_str = parse_color))]
+  accent_color: Option<iced::Color>,
+  /// Whether to show a tray icon with playback controls and the playing track
+  #[structopt(long, env = "MUSIUM_TRAY")]
+  tray: bool,
+  /// Global hotkey that toggles playback, even when another application is focused, such as `MediaPlayPause` or
+  /// `Ctrl + Alt + KeyP`
+  #[structopt(long, env = "MUSIUM_HOTKEY_TOGGLE_PLAY")]
+  hotkey_toggle_play: Option<Hotkey>,
+  /// Global hotkey that plays the next track in the queue, even when another application is focused
+  #[structopt(long, env = "MUSIUM_HOTKEY_NEXT_TRACK")]
+  hotkey_next_track: Option<Hotkey>,
+  /// Global hotkey that raises the volume, even when another application is focused
+  #[structopt(long, env = "MUSIUM_HOTKEY_VOLUME_UP")]
+  hotkey_volume_up: Option<Hotkey>,
+  /// Global hotkey that lowers the volume, even when another application is focused
+  #[structopt(long, env = "MUSIUM_HOTKEY_VOLUME_DOWN")]
+  hotkey_volume_down: Option<Hotkey>,
+  /// Whether to continue with tracks similar to the recently played tracks when the queue runs out, instead of stopping
+  /// playback. Can also be toggled in the player controls
+  #[structopt(long, env = "MUSIUM_ALBUM_RADIO")]
+  album_radio: bool,
+  /// Whether to play tracks that you often skip less when shuffling and in the album radio. Can also be toggled in the
+  /// player controls
+  #[structopt(long, env = "MUSIUM_DOWNRANK_SKIPPED")]
+  downrank_skipped: bool,
+  /// Maximum volume of an output device, as the name of the output device and a volume between 0.0 and 1.0, such as
+  /// `Speakers=0.5`. Can be given multiple times for different output devices
+  #[structopt(long, number_of_values = 1)]
+  output_max_volume: Vec<OutputMaxVolume>,
+  /// Daily window of local time during which the volume is limited and fades are lengthened, such as `22:00-07:00`
+  #[structopt(long, env = "MUSIUM_QUIET_HOURS")]
+  quiet_hours: Option<QuietHours>,
+  /// Maximum volume between 0.0 and 1.0 during quiet hours
+  #[structopt(long, env = "MUSIUM_QUIET_HOURS_MAX_VOLUME", default_value = "0.3")]
+  quiet_hours_max_volume: f64,
+  /// Duration in seconds of fades when pausing, resuming, and stopping playback
+  #[structopt(long, env = "MUSIUM_FADE_DURATION", default_value = "0")]
+  fade_duration: f64,
+  /// Duration in seconds of fades when pausing, resuming, and stopping playback during quiet hours
+  #[structopt(long, env = "MUSIUM_QUIET_HOURS_FADE_DURATION", default_value = "3")]
+  quiet_hours_fade_duration: f64,
+  /// Audio output to play audio with: `kira`, which supports seeking and fading, or `rodio`, which supports playing at
+  /// the native sample rate and decoding ahead. Defaults to the audio output in the client settings file, or `kira`
+  #[structopt(long, env = "MUSIUM_AUDIO_OUTPUT", possible_values = AudioOutputKind::NAMES)]
+  audio_output: Option<AudioOutputKind>,
+  /// Whether to play audio at its native sample rate instead of resampling it, such as `true`. Only supported by the
+  /// `rodio` audio output. Defaults to the setting in the client settings file
+  #[structopt(long, env = "MUSIUM_NATIVE_SAMPLE_RATE")]
+  native_sample_rate: Option<bool>,
+  /// Milliseconds of audio to decode ahead of playback, which prevents stutters on slow or busy machines. Only supported
+  /// by the `rodio` audio output. Defaults to the setting in the client settings file
+  #[structopt(long, env = "MUSIUM_DECODE_AHEAD")]
+  decode_ahead: Option<u64>,
+  /// Whether to listen on a local control socket for remote commands, such as those sent by `musium-cli remote`
+  #[structopt(long, env = "MUSIUM_REMOTE_CONTROL")]
+  remote_control: bool,
+  /// Path of the local control socket to listen on for remote commands. Defaults to `musium-player.sock` in
+  /// `$XDG_RUNTIME_DIR` or the temporary directory
+  #[structopt(long, env = "MUSIUM_REMOTE_CONTROL_SOCKET")]
+  remote_control_socket: Option<PathBuf>,
+  /// ID of the Discord application to publish the playing track to Discord Rich Presence as. Does not publish to
+  /// Discord Rich Presence when not set
+  #[cfg(feature = "discord_presence")]
+  #[structopt(long, env = "MUSIUM_DISCORD_APPLICATION_ID")]
+  discord_application_id: Option<String>,
+  /// URL of the artwork shown in Discord Rich Presence, where `{album_id}` is replaced by the ID of the album of the
+  /// playing track. Must be reachable by Discord. Defaults to the `musium` art asset of the Discord application
+  #[cfg(feature = "discord_presence")]
+  #[structopt(long, env = "MUSIUM_DISCORD_ARTWORK_URL")]
+  discord_artwork_url: Option<String>,
 
   /// Whether to print metrics to stderr before the program exits
   #[structopt(long, env = "MUSIUM_PRINT_METRICS")]
@@ -60,11 +167,50 @@ fn main() -> Result<()> {
   let controller: Controller = metrics_receiver.controller();
   let mut observer: YamlObserver = YamlBuilder::new().build();
   metrics_receiver.install();
+  // Load client settings, which are used when not given as arguments.
+  let client_dirs = match ClientDirs::new() {
+    Ok(client_dirs) => Some(client_dirs),
+    Err(e) => {
+      warn!("Not loading or saving client settings: {:?}", FormatError::new(&e));
+      None
+    }
+  };
+  let client_settings = match client_dirs.as_ref().map(ClientSettings::load).transpose() {
+    Ok(client_settings) => client_settings.unwrap_or_default(),
+    Err(e) => {
+      warn!("Failed to load client settings; using defaults: {:?}", FormatError::new(&e));
+      ClientSettings::default()
+    }
+  };
+  let profile_name = opt.profile.or_else(|| client_settings.current_profile.clone());
+  let profile = client_settings.profile(profile_name.as_deref());
+  let url_base = match opt.url_base {
+    Some(url_base) => url_base,
+    None => profile.url_base.as_deref()
+      .ok_or_else(|| anyhow!("No server URL given as argument or in the profile of the client settings file"))?
+      .parse()
+      .with_context(|| "Failed to parse server URL of the profile of the client settings file")?,
+  };
   // Create player
-  let player = create_default_player(opt.url_base.clone())
+  let mut audio_output_settings = client_settings.audio_output.clone();
+  audio_output_settings.override_with(opt.audio_output, opt.native_sample_rate, opt.decode_ahead);
+  let player = create_default_player(url_base.clone(), AudioOutputSelection::from(&audio_output_settings))
     .with_context(|| "Failed to create player")?;
+  player.set_album_radio_enabled(opt.album_radio);
+  player.set_downrank_skipped_enabled(opt.downrank_skipped);
+  player.set_volume_limits(VolumeLimits {
+    output_max_volumes: opt.output_max_volume.into_iter().map(|o| (o.output_device_name, o.max_volume)).collect(),
+    quiet_hours: opt.quiet_hours,
+    quiet_hours_max_volume: opt.quiet_hours_max_volume,
+    fade_duration: opt.fade_duration,
+    quiet_hours_fade_duration: opt.quiet_hours_fade_duration,
+  });
   // Run GUI
-  let user_login = UserLogin { name: opt.name, password: opt.password };
+  let user_login = UserLogin {
+    name: opt.name.or(profile.login_name).unwrap_or_default(),
+    password: opt.password.unwrap_or_default(),
+  };
+  let locale = opt.locale.or_else(|| client_settings.locale.clone());
   let app_settings = iced::Settings {
     window: iced::window::Settings {
       min_size: Some((800, 600)),
@@ -72,8 +218,32 @@ fn main() -> Result<()> {
     },
     flags: Flags {
       player,
-      initial_url: opt.url_base,
+      initial_url: url_base,
       initial_user_login: user_login,
+      kiosk_token: opt.kiosk_token,
+      client_dirs,
+      client_settings,
+      profile_name,
+      locale,
+      theme: opt.theme,
+      accent_color: opt.accent_color,
+      tray: opt.tray,
+      hotkey_bindings: HotkeyBindings {
+        toggle_play: opt.hotkey_toggle_play,
+        next_track: opt.hotkey_next_track,
+        volume_up: opt.hotkey_volume_up,
+        volume_down: opt.hotkey_volume_down,
+      },
+      remote_control_socket: if opt.remote_control {
+        Some(opt.remote_control_socket.unwrap_or_else(default_remote_socket_path))
+      } else {
+        None
+      },
+      #[cfg(feature = "discord_presence")]
+      discord_presence: opt.discord_application_id.map(|application_id| DiscordPresenceConfig {
+        application_id,
+        artwork_url: opt.discord_artwork_url,
+      }),
     },
     default_font: None,
     default_text_size: 20,
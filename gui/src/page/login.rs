@@ -1,30 +1,50 @@
 #![allow(dead_code, unused_imports, unused_variables)]
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use iced::{Align, Button, button, Column, Command, Element, HorizontalAlignment, Length, Row, Text, text_input, TextInput};
 use tracing::{debug, error};
 use url::Url;
 
 use derivative::Derivative;
+use musium_client_settings::{ClientProfile, parse_profile_name};
 use musium_core::format_error::FormatError;
 use musium_core::model::{User, UserLogin};
+use musium_core::panic::panic_into_string;
+use musium_i18n::Localizer;
 use musium_player::*;
 
+use crate::theme::Theme;
+use crate::util::ButtonEx;
 use crate::util::Update;
 
+/// Duration for which the local network is browsed for servers.
+const DISCOVER_SERVERS_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[derive(Debug, Derivative)]
 #[derivative(Default(bound = ""))]
 pub struct Page<P: Player<Client=HttpClient>> {
+  profile_input: text_input::State,
   url_input: text_input::State,
   name_input: text_input::State,
   password_input: text_input::State,
   login_button: button::State,
+  discover_button: button::State,
 
   url: String,
   parsed_url: Option<Url>,
   url_parse_error: Option<url::ParseError>,
   user_login: UserLogin,
+  /// Kiosk token to log in with instead of a name and password, or `None` to log in with a name and password.
+  kiosk_token: Option<String>,
+  discovering: bool,
+  discovered_servers: Vec<(DiscoveredServer, button::State)>,
+
+  /// Name of the profile to save the server URL and login name into after logging in, or empty for the default profile.
+  profile_name: String,
+  /// Saved profiles that can be selected, along with the button state of their select button.
+  profiles: Vec<(Option<String>, ClientProfile, button::State)>,
 
   state: State<P>,
 }
@@ -32,10 +52,15 @@ pub struct Page<P: Player<Client=HttpClient>> {
 #[derive(Debug, Derivative)]
 #[derivative(Clone)]
 pub enum Message<P: Player> {
+  SetProfileName(String),
+  SelectProfile(Option<String>, ClientProfile),
   SetUrl(String),
+  DiscoverServers,
+  ReceiveDiscoveredServers(Result<Vec<DiscoveredServer>, Arc<DiscoverServersError>>),
   SetName(String),
   SetPassword(String),
   SendLoginRequest(UserLogin),
+  SendKioskLoginRequest(String),
   LoginResponseReceived(Result<User, Arc<P::LoginError>>),
   Return,
 }
@@ -49,17 +74,49 @@ enum State<P: Player> { Idle, Busy, Failed(Arc<P::LoginError>) }
 impl<P: Player> Default for State<P> { fn default() -> Self { Self::Idle } }
 
 impl<P: Player<Client=HttpClient>> Page<P> {
-  pub fn new(url: Url, user_login: UserLogin) -> Self {
+  /// Creates a page that saves into the profile with `profile_name` after logging in, where `profiles` can be selected.
+  pub fn new(url: Url, user_login: UserLogin, profile_name: Option<String>, profiles: Vec<(Option<String>, ClientProfile)>) -> Self {
     Self {
       url: url.to_string(),
       parsed_url: Some(url),
       user_login,
+      profile_name: profile_name.unwrap_or_default(),
+      profiles: profiles.into_iter().map(|(name, profile)| (name, profile, button::State::default())).collect(),
       ..Self::default()
     }
   }
 
+  /// Creates a page that logs in with `kiosk_token` instead of a name and password, starting to log in immediately.
+  pub fn new_kiosk(url: Url, kiosk_token: String, player: &P) -> (Self, Command<Message<P>>) {
+    let mut page = Self {
+      url: url.to_string(),
+      parsed_url: Some(url),
+      kiosk_token: Some(kiosk_token.clone()),
+      ..Self::default()
+    };
+    let command = page.send_kiosk_login_request(player, kiosk_token);
+    (page, command)
+  }
+
+  /// Gets the server URL entered on this page, or `None` if it is not a valid URL.
+  pub fn url(&self) -> Option<&Url> { self.parsed_url.as_ref() }
+
+  /// Gets the name of the profile entered on this page, or `None` for the default profile.
+  pub fn profile_name(&self) -> Option<&str> {
+    if self.profile_name.is_empty() { None } else { Some(&self.profile_name) }
+  }
+
   pub fn update(&mut self, player: &mut P, message: Message<P>) -> Update<Message<P>, Action> {
     match message {
+      Message::SetProfileName(profile_name) => self.profile_name = profile_name,
+      Message::SelectProfile(profile_name, profile) => {
+        self.profile_name = profile_name.unwrap_or_default();
+        self.user_login.name = profile.login_name.unwrap_or_default();
+        self.user_login.password = String::new();
+        if let Some(url) = profile.url_base {
+          return self.update(player, Message::SetUrl(url));
+        }
+      }
       Message::SetUrl(url) => {
         self.url = url.clone();
         match Url::parse(&url) {
@@ -74,6 +131,29 @@ impl<P: Player<Client=HttpClient>> Page<P> {
           }
         }
       }
+      Message::DiscoverServers => {
+        self.discovering = true;
+        let command = Command::perform(
+          async move {
+            tokio::task::spawn_blocking(|| discover_servers(DISCOVER_SERVERS_TIMEOUT)).await.unwrap_or_else(|e| {
+              error!("Server discovery task panicked; returning empty list of servers. Panic was: {:?}", e.try_into_panic().map(|p| panic_into_string(p)));
+              Ok(Vec::new())
+            })
+          },
+          |r| Message::ReceiveDiscoveredServers(r.map_err(|e| Arc::new(e))),
+        );
+        return Update::command(command);
+      }
+      Message::ReceiveDiscoveredServers(result) => {
+        self.discovering = false;
+        match result {
+          Ok(servers) => {
+            debug!("Discovered {} servers", servers.len());
+            self.discovered_servers = servers.into_iter().map(|s| (s, button::State::default())).collect();
+          }
+          Err(e) => error!("Failed to discover servers: {:?}", FormatError::new(e.as_ref())),
+        }
+      }
       Message::SetName(name) => self.user_login.name = name,
       Message::SetPassword(password) => self.user_login.password = password,
       Message::SendLoginRequest(user_login) => {
@@ -85,6 +165,7 @@ impl<P: Player<Client=HttpClient>> Page<P> {
         self.state = State::Busy;
         return Update::command(command);
       }
+      Message::SendKioskLoginRequest(kiosk_token) => return Update::command(self.send_kiosk_login_request(player, kiosk_token)),
       Message::LoginResponseReceived(result) => match result {
         Ok(user) => {
           debug!("Logged in as: {}", user.name);
@@ -102,11 +183,20 @@ impl<P: Player<Client=HttpClient>> Page<P> {
     Update::none()
   }
 
-  pub fn view(&mut self) -> Element<'_, Message<P>> {
-    let title = Text::new("Musium")
+  fn send_kiosk_login_request(&mut self, player: &P, kiosk_token: String) -> Command<Message<P>> {
+    let player = player.clone();
+    self.state = State::Busy;
+    Command::perform(
+      async move { player.kiosk_login(&kiosk_token).await },
+      |r| Message::LoginResponseReceived(r.map_err(|e| Arc::new(e))),
+    )
+  }
+
+  pub fn view(&mut self, localizer: &Localizer, theme: Theme) -> Element<'_, Message<P>> {
+    let title = Text::new(localizer.text("app-title"))
       .width(Length::Fill)
       .size(100)
-      .color([0.5, 0.5, 0.5])
+      .color(theme.palette.text_muted)
       .horizontal_alignment(HorizontalAlignment::Center);
 
     let content: Element<_> = match &self.state {
@@ -118,53 +208,113 @@ impl<P: Player<Client=HttpClient>> Page<P> {
         let input_size = 30;
         let input_width = Length::Units(400);
         let input_padding = 5;
-        Column::new().spacing(spacing).align_items(align)
+        let profile_name_valid = self.profile_name.is_empty() || parse_profile_name(&self.profile_name).is_ok();
+        let column = Column::new().spacing(spacing).align_items(align)
+          .push(if self.kiosk_token.is_some() {
+            // Kiosk logins are not saved into profiles, so do not show them at all.
+            Row::new()
+          } else {
+            Row::new().spacing(spacing).align_items(align)
+              .push(Text::new(localizer.text("login-profile"))
+                .size(label_size)
+                .width(label_width)
+              )
+              .push(TextInput::new(&mut self.profile_input, &localizer.text("login-profile-default"), &self.profile_name, Message::SetProfileName)
+                .size(input_size)
+                .width(input_width)
+                .padding(input_padding)
+                .style(theme)
+              )
+          })
+          .push(self.profiles.iter_mut().fold(Row::new().spacing(spacing).align_items(align), |row, (name, profile, state)| {
+            let label = name.clone().unwrap_or_else(|| localizer.text("login-profile-default"));
+            let message = Message::SelectProfile(name.clone(), profile.clone());
+            row.push(Button::new(state, Text::new(label))
+              .style(theme)
+              .on_press(message)
+            )
+          }))
           .push(Row::new().spacing(spacing).align_items(align)
-            .push(Text::new("Server URL")
+            .push(Text::new(localizer.text("login-server-url"))
               .size(label_size)
               .width(label_width)
             )
-            .push(TextInput::new(&mut self.url_input, "Server URL", &self.url, Message::SetUrl)
+            .push(TextInput::new(&mut self.url_input, &localizer.text("login-server-url"), &self.url, Message::SetUrl)
               .size(input_size)
               .width(input_width)
               .padding(input_padding)
+              .style(theme)
             )
-          )
-          .push(Row::new().spacing(spacing).align_items(align)
-            .push(Text::new("Name")
-              .size(label_size)
-              .width(label_width)
-            )
-            .push(TextInput::new(&mut self.name_input, "Name", &self.user_login.name, Message::SetName)
-              .size(input_size)
-              .width(input_width)
+            .push(Button::new(&mut self.discover_button, Text::new(localizer.text(if self.discovering { "login-discovering" } else { "login-discover" })))
               .padding(input_padding)
+              .style(theme)
+              .on_press_into(|| Message::DiscoverServers, !self.discovering)
             )
           )
-          .push(Row::new().spacing(spacing).align_items(align)
-            .push(Text::new("Password")
-              .size(label_size)
-              .width(label_width)
+          .push(self.discovered_servers.iter_mut().fold(Column::new().spacing(spacing).align_items(align), |column, (server, state)| {
+            let url = server.url.to_string();
+            let label = format!("{} ({})", server.name, url);
+            column.push(Button::new(state, Text::new(label))
+              .style(theme)
+              .on_press(Message::SetUrl(url))
             )
-            .push(TextInput::new(&mut self.password_input, "Password", &self.user_login.password, Message::SetPassword)
-              .size(input_size)
-              .width(input_width)
-              .padding(input_padding)
-              .password()
+          }));
+        if let Some(kiosk_token) = &self.kiosk_token {
+          // Kiosk logins need no name and password, as the kiosk token identifies the kiosk user.
+          column
+            .push(Button::new(&mut self.login_button, Text::new(localizer.text("login-kiosk-button")).size(30).horizontal_alignment(HorizontalAlignment::Center))
+              .style(theme.accent_button())
+              .on_press(Message::SendKioskLoginRequest(kiosk_token.clone()))
             )
-          )
-          .push(Button::new(&mut self.login_button, Text::new("Login").size(30).width(label_width).horizontal_alignment(HorizontalAlignment::Center))
-            .on_press(Message::SendLoginRequest(self.user_login.clone()))
-          )
-          .into()
+            .into()
+        } else {
+          let user_login = self.user_login.clone();
+          column
+            .push(Row::new().spacing(spacing).align_items(align)
+              .push(Text::new(localizer.text("login-name"))
+                .size(label_size)
+                .width(label_width)
+              )
+              .push(TextInput::new(&mut self.name_input, &localizer.text("login-name"), &self.user_login.name, Message::SetName)
+                .size(input_size)
+                .width(input_width)
+                .padding(input_padding)
+                .style(theme)
+              )
+            )
+            .push(Row::new().spacing(spacing).align_items(align)
+              .push(Text::new(localizer.text("login-password"))
+                .size(label_size)
+                .width(label_width)
+              )
+              .push(TextInput::new(&mut self.password_input, &localizer.text("login-password"), &self.user_login.password, Message::SetPassword)
+                .size(input_size)
+                .width(input_width)
+                .padding(input_padding)
+                .password()
+                .style(theme)
+              )
+            )
+            .push(if profile_name_valid {
+              Row::new()
+            } else {
+              Row::new().push(Text::new(localizer.text("login-profile-invalid")).color(theme.palette.text_muted))
+            })
+            .push(Button::new(&mut self.login_button, Text::new(localizer.text("login-button")).size(30).width(label_width).horizontal_alignment(HorizontalAlignment::Center))
+              .style(theme.accent_button())
+              .on_press_into(move || Message::SendLoginRequest(user_login.clone()), profile_name_valid)
+            )
+            .into()
+        }
       }
       State::Busy => Row::new()
-        .push(Text::new("Logging in..."))
+        .push(Text::new(localizer.text("login-busy")))
         .into(),
       State::Failed(e) => Column::new()
-        .push(Text::new("Logging in failed sadface"))
+        .push(Text::new(localizer.text("login-failed")))
         .push(Text::new(format!("{:?}", e)))
-        .push(Button::new(&mut self.login_button, Text::new("Return"))
+        .push(Button::new(&mut self.login_button, Text::new(localizer.text("login-return")))
+          .style(theme)
           .on_press(Message::Return)
         )
         .into(),
@@ -0,0 +1,224 @@
+use iced::{Align, button, Button, Column, Command, Element, Length, Row, scrollable, Scrollable};
+use itertools::Itertools;
+use tracing::error;
+
+use musium_core::api::{ComposerInfo, WorkInfo};
+use musium_core::format_error::FormatError;
+use musium_core::model::collection::{Tracks, TracksRaw};
+use musium_i18n::{FluentArgs, Localizer};
+use musium_player::{Client, Player};
+
+use crate::page::main::{cell_button, h1, h3, PlayingTrack, txt};
+use crate::theme::Theme;
+use crate::util::{ButtonEx, Update};
+
+/// Tab for browsing classical music by composer, then by work, and then by movement.
+#[derive(Default, Debug)]
+pub struct Tab {
+  /// Composers, along with the button state of their select button.
+  composers: Vec<(ComposerInfo, button::State)>,
+  selected_composer_id: Option<i32>,
+  /// Works of the selected composer, along with the button state of their select button.
+  works: Vec<(WorkInfo, button::State)>,
+  selected_work_id: Option<i32>,
+  /// Tracks of the selected work in movement order.
+  work_tracks: Vec<WorkTrackViewModel>,
+  loading: bool,
+  refresh_button_state: button::State,
+  play_work_button_state: button::State,
+  composers_scrollable_state: scrollable::State,
+  works_scrollable_state: scrollable::State,
+  work_tracks_scrollable_state: scrollable::State,
+}
+
+#[derive(Debug)]
+pub enum Message<P: Player> {
+  RequestComposers,
+  ReceiveComposers(Result<Vec<ComposerInfo>, <P::Client as Client>::TrackError>),
+  SelectComposer(i32),
+  ReceiveWorks(i32, Result<Vec<WorkInfo>, <P::Client as Client>::TrackError>),
+  SelectWork(i32),
+  ReceiveWorkTracks(i32, Result<TracksRaw, <P::Client as Client>::TrackError>),
+  /// Plays all playable tracks of the selected work.
+  PlayWork,
+  /// Plays the playable tracks of the selected work, starting at the track with the given ID.
+  PlayFrom(i32),
+}
+
+impl<'a> Tab {
+  /// Requests the composers, such as when this tab is shown.
+  pub fn request_composers<P: Player>(&mut self, player: &P) -> Command<Message<P>> {
+    self.loading = true;
+    let player = player.clone();
+    Command::perform(
+      async move { player.get_client().list_composers().await },
+      |r| Message::ReceiveComposers(r),
+    )
+  }
+
+  pub fn update<P: Player>(&mut self, player: &P, message: Message<P>) -> Update<Message<P>, super::Action> {
+    match message {
+      Message::RequestComposers => return Update::command(self.request_composers(player)),
+      Message::ReceiveComposers(r) => {
+        self.loading = false;
+        match r {
+          Ok(composers) => self.composers = composers.into_iter().map(|c| (c, button::State::default())).collect(),
+          Err(e) => error!("Failed to receive composers: {:?}", FormatError::new(&e)),
+        }
+      }
+      Message::SelectComposer(composer_id) => {
+        self.selected_composer_id = Some(composer_id);
+        self.selected_work_id = None;
+        self.works.clear();
+        self.work_tracks.clear();
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.get_client().list_composer_works(composer_id).await },
+          move |r| Message::ReceiveWorks(composer_id, r),
+        ));
+      }
+      Message::ReceiveWorks(composer_id, r) => match r {
+        // Ignore works of a composer that is no longer selected, as the user selected another one in the meantime.
+        Ok(works) if self.selected_composer_id == Some(composer_id) => self.works = works.into_iter().map(|w| (w, button::State::default())).collect(),
+        Ok(_) => {}
+        Err(e) => error!("Failed to receive works of composer {}: {:?}", composer_id, FormatError::new(&e)),
+      }
+      Message::SelectWork(work_id) => {
+        self.selected_work_id = Some(work_id);
+        self.work_tracks.clear();
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.get_client().list_work_tracks(work_id).await },
+          move |r| Message::ReceiveWorkTracks(work_id, r),
+        ));
+      }
+      Message::ReceiveWorkTracks(work_id, r) => match r {
+        Ok(tracks) if self.selected_work_id == Some(work_id) => self.work_tracks = work_track_view_models(tracks),
+        Ok(_) => {}
+        Err(e) => error!("Failed to receive tracks of work {}: {:?}", work_id, FormatError::new(&e)),
+      }
+      Message::PlayWork => {
+        let tracks = self.playable_tracks_from(None);
+        if !tracks.is_empty() {
+          return Update::action(super::Action::PlayTracks(tracks));
+        }
+      }
+      Message::PlayFrom(track_id) => {
+        let tracks = self.playable_tracks_from(Some(track_id));
+        if !tracks.is_empty() {
+          return Update::action(super::Action::PlayTracks(tracks));
+        }
+      }
+    }
+    Update::none()
+  }
+
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
+    let has_playable_tracks = self.work_tracks.iter().any(|t| t.playable);
+    let header = Row::new()
+      .spacing(8)
+      .width(Length::Fill)
+      .align_items(Align::Center)
+      .push(Row::new().width(Length::Fill).push(h1(localizer.text("classical-title"))))
+      .push(Button::new(&mut self.play_work_button_state, txt(localizer.text("classical-play-work"))).style(theme.accent_button())
+        .on_press_into(|| Message::PlayWork, has_playable_tracks))
+      .push(Button::new(&mut self.refresh_button_state, txt(localizer.text("refresh"))).style(theme)
+        .on_press_into(|| Message::RequestComposers, !self.loading));
+    let column = Column::new()
+      .spacing(4)
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .push(header);
+    if self.composers.is_empty() {
+      let text = if self.loading { "classical-loading" } else { "classical-none" };
+      return column.push(txt(localizer.text(text))).into();
+    }
+
+    let selected_composer_id = self.selected_composer_id;
+    let composers = self.composers.iter_mut()
+      .fold(Column::new().spacing(2).width(Length::Fill).push(h3(localizer.text("classical-composers"))), |column, (info, state)| {
+        let composer_id = info.artist.id;
+        let mut args = FluentArgs::new();
+        args.set("name", info.artist.name.clone());
+        args.set("count", info.work_count);
+        let label = localizer.text_with("classical-composer", &args);
+        column.push(cell_button(theme, state, label, selected_composer_id != Some(composer_id), move || Message::SelectComposer(composer_id)))
+      });
+    let selected_work_id = self.selected_work_id;
+    let works = self.works.iter_mut()
+      .fold(Column::new().spacing(2).width(Length::Fill).push(h3(localizer.text("classical-works"))), |column, (info, state)| {
+        let work_id = info.work.id;
+        let mut args = FluentArgs::new();
+        args.set("name", info.work.name.clone());
+        args.set("count", info.track_count);
+        let label = localizer.text_with("classical-work", &args);
+        column.push(cell_button(theme, state, label, selected_work_id != Some(work_id), move || Message::SelectWork(work_id)))
+      });
+    let play_label = localizer.text("classical-play-from");
+    let work_tracks = self.work_tracks.iter_mut()
+      .fold(Column::new().spacing(2).width(Length::Fill).push(h3(localizer.text("classical-movements"))), |column, track| {
+        let track_id = track.playing_track.id;
+        let movement = match track.movement_number {
+          Some(movement_number) => format!("{}. {}", movement_number, track.movement),
+          None => track.movement.clone(),
+        };
+        let details = [track.playing_track.track_artists.clone(), track.playing_track.album.clone()].into_iter().flatten().join(" - ");
+        column.push(Row::new()
+          .spacing(8)
+          .align_items(Align::Center)
+          .push(cell_button(theme, &mut track.play_button_state, play_label.clone(), track.playable, move || Message::PlayFrom(track_id)))
+          .push(Column::new().push(txt(movement)).push(txt(details).size(12)))
+        )
+      });
+    let browser = Row::new()
+      .spacing(8)
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .push(Scrollable::new(&mut self.composers_scrollable_state).width(Length::FillPortion(1)).height(Length::Fill).push(composers))
+      .push(Scrollable::new(&mut self.works_scrollable_state).width(Length::FillPortion(1)).height(Length::Fill).push(works))
+      .push(Scrollable::new(&mut self.work_tracks_scrollable_state).width(Length::FillPortion(2)).height(Length::Fill).push(work_tracks));
+    column.push(browser).into()
+  }
+
+  /// Gets the playable tracks of the selected work, starting at the track with `track_id`, or at the first track if it
+  /// is `None`.
+  fn playable_tracks_from(&self, track_id: Option<i32>) -> Vec<PlayingTrack> {
+    self.work_tracks.iter()
+      .skip_while(|t| track_id.map_or(false, |track_id| t.playing_track.id != track_id))
+      .filter(|t| t.playable)
+      .map(|t| t.playing_track.clone())
+      .collect()
+  }
+}
+
+#[derive(Debug)]
+struct WorkTrackViewModel {
+  playing_track: PlayingTrack,
+  playable: bool,
+  /// Name of the movement, or the title of the track if the movement has no name.
+  movement: String,
+  movement_number: Option<i32>,
+  play_button_state: button::State,
+}
+
+/// Converts `tracks` into view models, keeping their movement order.
+fn work_track_view_models(tracks: TracksRaw) -> Vec<WorkTrackViewModel> {
+  let tracks: Tracks = tracks.into();
+  tracks.iter().map(|track_info| {
+    let track_artists = track_info.track_artists().map(|a| a.name.clone()).join(", ");
+    let track = track_info.track;
+    WorkTrackViewModel {
+      playing_track: PlayingTrack {
+        id: track.id,
+        title: track.title.clone(),
+        track_artists: if track_artists.is_empty() { None } else { Some(track_artists) },
+        album_id: track.album_id,
+        album: track_info.album().map(|a| a.name.clone()),
+      },
+      playable: track_info.availability().is_playable(),
+      movement: track.movement.clone().unwrap_or_else(|| track.title.clone()),
+      movement_number: track.movement_number,
+      play_button_state: button::State::default(),
+    }
+  }).collect()
+}
@@ -0,0 +1,165 @@
+use iced::{Align, button, Button, Column, Command, Element, Length, Row, scrollable, Scrollable};
+use tracing::{debug, error};
+
+use musium_core::api::HiddenItemInfo;
+use musium_core::format_error::FormatError;
+use musium_core::model::{ContentFilter, HiddenItemKind, UserContentFilter};
+use musium_i18n::{FluentArgs, Localizer};
+use musium_player::{Client, Player};
+
+use crate::page::main::{cell_button, h1, txt};
+use crate::theme::Theme;
+use crate::util::ButtonEx;
+
+#[derive(Default, Debug)]
+pub struct Tab {
+  /// Hidden items, along with the button state of their unhide button.
+  hidden_items: Vec<(HiddenItemInfo, button::State)>,
+  loading: bool,
+  /// Content filter of the user, or `None` if it has not been received yet.
+  content_filter: Option<UserContentFilter>,
+  content_filter_button_states: [button::State; ContentFilter::ALL.len()],
+  refresh_button_state: button::State,
+  scrollable_state: scrollable::State,
+}
+
+#[derive(Debug)]
+pub enum Message<P: Player> {
+  RequestHiddenItems,
+  ReceiveHiddenItems(Result<Vec<HiddenItemInfo>, <P::Client as Client>::UserDataError>),
+  Unhide(HiddenItemKind, i32),
+  ReceiveUnhide(HiddenItemKind, i32, Result<bool, <P::Client as Client>::UserDataError>),
+  ReceiveContentFilter(Result<UserContentFilter, <P::Client as Client>::UserError>),
+  SetContentFilter(ContentFilter),
+}
+
+impl<'a> Tab {
+  /// Requests the hidden items and content filter of the user, such as when this tab is shown.
+  pub fn request_hidden_items<P: Player>(&mut self, player: &P) -> Command<Message<P>> {
+    self.loading = true;
+    let hidden_items_player = player.clone();
+    let content_filter_player = player.clone();
+    Command::batch(vec![
+      Command::perform(
+        async move { hidden_items_player.get_client().list_hidden_items().await },
+        |r| Message::ReceiveHiddenItems(r),
+      ),
+      Command::perform(
+        async move { content_filter_player.get_client().get_my_content_filter().await },
+        |r| Message::ReceiveContentFilter(r),
+      ),
+    ])
+  }
+
+  pub fn update<P: Player>(&mut self, player: &P, message: Message<P>) -> Command<Message<P>> {
+    match message {
+      Message::RequestHiddenItems => return self.request_hidden_items(player),
+      Message::ReceiveHiddenItems(r) => {
+        self.loading = false;
+        match r {
+          Ok(hidden_items) => self.hidden_items = hidden_items.into_iter().map(|i| (i, button::State::default())).collect(),
+          Err(e) => error!("Failed to receive hidden items: {:?}", FormatError::new(&e)),
+        }
+      }
+      Message::Unhide(kind, item_id) => {
+        let player = player.clone();
+        return Command::perform(
+          async move { player.get_client().unhide_item(kind, item_id).await },
+          move |r| Message::ReceiveUnhide(kind, item_id, r),
+        );
+      }
+      Message::ReceiveUnhide(kind, item_id, r) => match r {
+        Ok(unhidden) => {
+          debug!("Unhid {} {}, was hidden: {}", kind, item_id, unhidden);
+          self.hidden_items.retain(|(i, _)| i.item.kind != kind || i.item.item_id != item_id);
+        }
+        Err(e) => error!("Failed to unhide {} {}: {:?}", kind, item_id, FormatError::new(&e)),
+      }
+      Message::ReceiveContentFilter(r) => match r {
+        Ok(content_filter) => self.content_filter = Some(content_filter),
+        Err(e) => error!("Failed to receive or set content filter: {:?}", FormatError::new(&e)),
+      }
+      Message::SetContentFilter(filter) => {
+        let player = player.clone();
+        return Command::perform(
+          async move { player.get_client().set_my_content_filter(filter).await },
+          |r| Message::ReceiveContentFilter(r),
+        );
+      }
+    }
+    Command::none()
+  }
+
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
+    let header = Row::new()
+      .spacing(8)
+      .width(Length::Fill)
+      .align_items(Align::Center)
+      .push(Row::new().width(Length::Fill).push(h1(localizer.text("hidden-title"))))
+      .push(Button::new(&mut self.refresh_button_state, txt(localizer.text("refresh"))).style(theme)
+        .on_press_into(|| Message::RequestHiddenItems, !self.loading));
+    let mut column = Column::new()
+      .spacing(4)
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .push(header);
+    if let Some(content_filter) = self.content_filter {
+      // Only admins can change pinned content filters, so disable all buttons when pinned.
+      let filters = ContentFilter::ALL.iter().zip(self.content_filter_button_states.iter_mut())
+        .fold(Row::new().spacing(2).align_items(Align::Center).push(txt(localizer.text("content-filter"))), |row, (filter, state)| {
+          let filter = *filter;
+          let enabled = !content_filter.pinned && filter != content_filter.filter;
+          row.push(Button::new(state, txt(localizer.text(content_filter_text_id(filter)))).style(theme)
+            .on_press_into(move || Message::SetContentFilter(filter), enabled))
+        });
+      column = column.push(filters);
+      if content_filter.pinned {
+        column = column.push(txt(localizer.text("content-filter-pinned")));
+      }
+    }
+    let column = column.push(txt(localizer.text("hidden-description")));
+    if self.hidden_items.is_empty() {
+      let text = if self.loading { "hidden-loading" } else { "hidden-none" };
+      return column.push(txt(localizer.text(text))).into();
+    }
+    let unhide_label = localizer.text("hidden-unhide");
+    let items = self.hidden_items.iter_mut()
+      .fold(Column::new().spacing(2).width(Length::Fill), |column, (info, state)| {
+        let kind = info.item.kind;
+        let item_id = info.item.item_id;
+        let mut args = FluentArgs::new();
+        args.set("id", item_id);
+        let name = info.name.clone().unwrap_or_else(|| localizer.text_with("hidden-unknown-item", &args));
+        column.push(Row::new()
+          .spacing(8)
+          .align_items(Align::Center)
+          .push(cell_button(theme, state, unhide_label.clone(), true, move || Message::Unhide(kind, item_id)))
+          .push(txt(localizer.text(kind_text_id(kind))).width(Length::Units(80)))
+          .push(txt(name))
+        )
+      });
+    column
+      .push(Scrollable::new(&mut self.scrollable_state)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .push(items)
+      )
+      .into()
+  }
+}
+
+fn kind_text_id(kind: HiddenItemKind) -> &'static str {
+  match kind {
+    HiddenItemKind::Track => "hidden-kind-track",
+    HiddenItemKind::Album => "hidden-kind-album",
+    HiddenItemKind::Artist => "hidden-kind-artist",
+  }
+}
+
+fn content_filter_text_id(filter: ContentFilter) -> &'static str {
+  match filter {
+    ContentFilter::Off => "content-filter-off",
+    ContentFilter::ExcludeExplicit => "content-filter-exclude-explicit",
+    ContentFilter::OnlyClean => "content-filter-only-clean",
+  }
+}
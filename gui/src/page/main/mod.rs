@@ -2,13 +2,13 @@
 
 use std::borrow::BorrowMut;
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use iced::{self, button, Button, Checkbox, Color, Column, Command, Element, futures, Length, Row, Rule, rule, scrollable, Slider, slider, Subscription, Text};
+use iced::{self, button, Button, Checkbox, Color, Column, Command, Container, Element, futures, Length, Row, Rule, rule, scrollable, Slider, slider, Subscription, Text};
 use iced::futures::stream::BoxStream;
 use iced_native::{Align, HorizontalAlignment, Space, VerticalAlignment};
 use iced_native::subscription::Recipe;
@@ -16,46 +16,107 @@ use itertools::Itertools;
 use thiserror::Error;
 use tracing::{debug, error, info};
 
+use musium_core::api::UserPreferences;
 use musium_core::format_error::FormatError;
-use musium_core::model::{Album, Track, User};
+use musium_core::model::{Album, SKIP_THRESHOLD, Track, User};
 use musium_core::model::collection::{TrackInfo, Tracks};
+use musium_i18n::{FluentArgs, Localizer};
 use musium_player::*;
+use musium_player::remote::{RemoteCommand, RemoteStatus};
 
 use crate::page::main::track::TrackViewModel;
+use crate::theme::Theme;
+use crate::tray::TrayStatus;
 use crate::util::{ButtonEx, Update};
 use crate::widget::table::TableBuilder;
 
 mod track;
 mod source;
+mod now_playing;
+mod stats;
+mod hidden;
+mod classical;
+
+/// Number of stars that tracks can be rated with, each star being worth an equal part of the maximum rating.
+const RATING_STARS: i32 = 5;
+/// Amount by which the volume is changed when raising or lowering it.
+pub const VOLUME_STEP: f64 = 0.05;
+/// Duration that toasts are shown before they are dismissed automatically.
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+/// Number of recently played tracks that are remembered, for seeding the album radio and not playing them again.
+const PLAYED_TRACKS_HISTORY: usize = 50;
+/// Interval at which the volume limits of the player are enforced, starting and ending quiet hours on time.
+const VOLUME_LIMITS_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Default, Debug)]
 pub struct Page {
   logged_in_user: User,
+  /// Whether the user is logged in through the kiosk login, and may therefore only browse and play.
+  read_only: bool,
 
   track_tab: track::Tab,
   track_tab_button_state: button::State,
   source_tab: source::Tab,
   source_tab_button_state: button::State,
+  now_playing_tab: now_playing::Tab,
+  now_playing_tab_button_state: button::State,
+  stats_tab: stats::Tab,
+  stats_tab_button_state: button::State,
+  hidden_tab: hidden::Tab,
+  hidden_tab_button_state: button::State,
+  classical_tab: classical::Tab,
+  classical_tab_button_state: button::State,
+  switch_profile_button_state: button::State,
   current_tab: Tab,
 
   is_paused: bool,
   is_stopped: bool,
   track_position_relative: f64,
-  player_status_subscription_active: bool,
+  /// Whether the player is getting the audio of a track before playing it.
+  is_buffering: bool,
+  /// Tracks to play after the current track, in order.
+  queue: VecDeque<PlayingTrack>,
+  /// IDs of recently played tracks, most recent last.
+  played_track_ids: VecDeque<i32>,
+  /// Whether the album radio of the player is enabled, mirrored here for showing it.
+  album_radio_enabled: bool,
+  /// Whether downranking of skipped tracks by the player is enabled, mirrored here for showing it.
+  downrank_skipped_enabled: bool,
+  /// Channel processing of the player, loaded from and saved to the preferences of the user.
+  channel_processing: ChannelProcessing,
+  /// Whether quiet hours were active when the volume limits of the player were last enforced.
+  quiet_hours_active: bool,
 
   prev_track_button_state: button::State,
   stop_button_state: button::State,
   toggle_play_button_state: button::State,
   next_track_button_state: button::State,
   track_position_slider_state: slider::State,
+  balance_slider_state: slider::State,
+  crossfeed_slider_state: slider::State,
 
+  /// Notifications shown above the player controls, oldest first.
+  toasts: Vec<Toast>,
+}
+
+#[derive(Debug)]
+struct Toast {
+  message: String,
+  shown_at: Instant,
+  dismiss_button_state: button::State,
 }
 
 #[derive(Debug)]
 pub enum Message<P: Player> {
   TrackTab(track::Message<P>),
   SourceTab(source::Message<P>),
+  NowPlayingTab(now_playing::Message<P>),
+  StatsTab(stats::Message<P>),
+  HiddenTab(hidden::Message<P>),
+  ClassicalTab(classical::Message<P>),
   SetCurrentTab(Tab),
+  /// Returns to the login page to log in with another profile. Handled by the application, as it replaces this page.
+  SwitchProfile,
   RequestPrevTrack,
   RequestStop,
   ReceiveStop(Result<(), <P::AudioOutput as AudioOutput>::StopError>),
@@ -64,13 +125,44 @@ pub enum Message<P: Player> {
   RequestNextTrack,
   RequestSeek(f64),
   ReceiveSeek(Result<(), <P::AudioOutput as AudioOutput>::SeekToRelativeError>),
-  ReceivePlayerStatus(Result<PlayerStatus, PlayerStatusError<P>>),
+  /// Changes the volume by the given amount, clamped between 0 and 1.
+  RequestChangeVolume(f64),
+  ReceiveChangeVolume(Result<f64, ChangeVolumeError<P>>),
+  ReceivePlayerEvent(PlayerEvent),
+  ReceivePlayQueued(PlayingTrack, Result<(), P::PlayError>),
+  SetAlbumRadioEnabled(bool),
+  SetDownrankSkippedEnabled(bool),
+  ReceiveReportSkip(Result<bool, <P::Client as Client>::TrackError>),
+  ChannelControl(ChannelControlMessage),
+  ReceiveChannelProcessingPreferences(Result<UserPreferences, <P::Client as Client>::UserError>),
+  ReceiveSetChannelProcessing(Result<(), <P::AudioOutput as AudioOutput>::SetChannelProcessingError>),
+  ReceiveSaveChannelProcessing(Result<(), <P::Client as Client>::UserError>),
+  EnforceVolumeLimits,
+  ReceiveEnforceVolumeLimits(Result<bool, P::EnforceVolumeLimitsError>),
+  ReceiveAlbumRadioTracks(Result<Vec<PlayingTrack>, <P::Client as Client>::TrackError>),
+  ReceivePlaybackError(PlaybackError),
+  DismissToast(usize),
+  ExpireToasts(Instant),
+}
+
+/// Message of the channel processing controls, separate from `Message` because sliders require messages to be `Clone`.
+#[derive(Clone, Debug)]
+pub enum ChannelControlMessage {
+  SetMonoDownmix(bool),
+  SetBalance(f32),
+  SetCrossfeed(f32),
+  /// Saves the channel processing to the preferences of the user, such as when a slider is released.
+  Save,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Tab {
   Track,
   Source,
+  NowPlaying,
+  Stats,
+  Hidden,
+  Classical,
 }
 
 impl Default for Tab {
@@ -78,22 +170,54 @@ impl Default for Tab {
 }
 
 pub enum Action {
-  ReceivePlay
+  ReceivePlay(PlayingTrack),
+  /// Adds tracks to the end of the queue, starting playback of the queue if nothing is playing.
+  Enqueue(Vec<PlayingTrack>),
+  /// Adds tracks to the front of the queue, so that they play after the current track, starting playback of the queue
+  /// if nothing is playing.
+  EnqueueNext(Vec<PlayingTrack>),
+  /// Replaces the queue with tracks, and plays the first track.
+  PlayTracks(Vec<PlayingTrack>),
+}
+
+/// Track that is playing or queued, with the information that is shown while it plays.
+#[derive(Clone, Debug)]
+pub struct PlayingTrack {
+  pub id: i32,
+  pub title: String,
+  pub track_artists: Option<String>,
+  pub album_id: i32,
+  pub album: Option<String>,
 }
 
 impl<'a> Page {
-  pub fn new<P: Player>(logged_in_user: User, player: &P) -> (Self, Command<Message<P>>) {
-    let (track_tab, track_tab_command) = track::Tab::new(player);
+  pub fn new<P: Player>(logged_in_user: User, read_only: bool, player: &P) -> (Self, Command<Message<P>>) {
+    let (track_tab, track_tab_command) = track::Tab::new(player, read_only);
     let (source_tab, source_tab_command) = source::Tab::new(player);
     let page = Self {
       logged_in_user,
+      read_only,
+      track_tab,
+      source_tab,
+      now_playing_tab: now_playing::Tab::new(read_only),
       is_paused: false,
       is_stopped: true,
+      album_radio_enabled: player.is_album_radio_enabled(),
+      downrank_skipped_enabled: player.is_downrank_skipped_enabled(),
       ..Self::default()
     };
+    let channel_processing_command = {
+      let player = player.clone();
+      Command::perform(
+        async move { player.get_client().get_user_preferences().await },
+        |r| Message::ReceiveChannelProcessingPreferences(r),
+      )
+    };
     let command = Command::batch(vec![
       track_tab_command.map(|m| Message::TrackTab(m)),
       source_tab_command.map(|m| Message::SourceTab(m)),
+      channel_processing_command,
+      Self::enforce_volume_limits(player),
     ]);
     (page, command)
   }
@@ -103,15 +227,41 @@ impl<'a> Page {
     match message {
       TrackTab(m) => {
         let (command, action) = self.track_tab.update(player, m).unwrap();
-        self.handle_action(action);
-        return command.map(|m| TrackTab(m));
+        let action_command = self.handle_action(player, action);
+        return Command::batch(vec![command.map(|m| TrackTab(m)), action_command]);
       }
       SourceTab(m) => {
         let (command, action) = self.source_tab.update(player, m).unwrap();
-        self.handle_action(action);
-        return command.map(|m| SourceTab(m));
+        let action_command = self.handle_action(player, action);
+        return Command::batch(vec![command.map(|m| SourceTab(m)), action_command]);
+      }
+      NowPlayingTab(m) => {
+        return self.now_playing_tab.update(player, m).map(|m| NowPlayingTab(m));
+      }
+      StatsTab(m) => {
+        return self.stats_tab.update(player, m).map(|m| StatsTab(m));
+      }
+      HiddenTab(m) => {
+        return self.hidden_tab.update(player, m).map(|m| HiddenTab(m));
+      }
+      ClassicalTab(m) => {
+        let (command, action) = self.classical_tab.update(player, m).unwrap();
+        let action_command = self.handle_action(player, action);
+        return Command::batch(vec![command.map(|m| ClassicalTab(m)), action_command]);
+      }
+      SetCurrentTab(tab) => {
+        // Refresh statistics whenever they are shown, as they change with every play, hidden items, as they change when
+        // hiding items from the track tab, and composers, as they change when synchronizing.
+        let command = match tab {
+          Tab::Stats => self.stats_tab.request_stats(player).map(|m| StatsTab(m)),
+          Tab::Hidden => self.hidden_tab.request_hidden_items(player).map(|m| HiddenTab(m)),
+          Tab::Classical => self.classical_tab.request_composers(player).map(|m| ClassicalTab(m)),
+          _ => Command::none(),
+        };
+        self.current_tab = tab;
+        return command;
       }
-      SetCurrentTab(tab) => self.current_tab = tab,
+      SwitchProfile => error!("[BUG] Requested to switch profiles, but that is handled by the application"),
 
       RequestStop => {
         let player = player.clone();
@@ -124,7 +274,6 @@ impl<'a> Page {
         Ok(_) => {
           self.is_paused = false;
           self.is_stopped = true;
-          self.player_status_subscription_active = false;
         }
         Err(e) => error!("Failed to stop playback: {:?}", FormatError::new(&e)),
       }
@@ -139,7 +288,6 @@ impl<'a> Page {
         Ok(is_playing) => {
           self.is_paused = !is_playing;
           self.is_stopped = false;
-          self.player_status_subscription_active = is_playing;
         }
         Err(e) => error!("Failed to toggle playback: {:?}", FormatError::new(&e)),
       }
@@ -156,69 +304,393 @@ impl<'a> Page {
           error!("Failed to seek: {:?}", FormatError::new(&e));
         }
       }
-      ReceivePlayerStatus(r) => match r {
-        Ok(PlayerStatus { is_stopped, position_relative }) => {
-          self.is_stopped = is_stopped;
+      RequestChangeVolume(delta) => {
+        let player = player.clone();
+        return Command::perform(
+          async move {
+            let volume = player.get_volume().await.map_err(ChangeVolumeError::GetVolumeFail)?;
+            let volume = (volume + delta).clamp(0.0, 1.0);
+            player.set_volume(volume).await.map_err(ChangeVolumeError::SetVolumeFail)?;
+            Ok(volume)
+          },
+          |r| ReceiveChangeVolume(r),
+        );
+      }
+      ReceiveChangeVolume(r) => match r {
+        Ok(volume) => debug!("Changed volume to {}", volume),
+        Err(e) => error!("Failed to change volume: {:?}", FormatError::new(&e)),
+      }
+      RequestNextTrack => {
+        let next_command = self.play_next_queued(player);
+        // Report playing the next track early on in the current track as skipping it.
+        let skipped_track_id = self.now_playing_tab.track().map(|track| track.id)
+          .filter(|_| !self.read_only && !self.is_stopped && self.track_position_relative < SKIP_THRESHOLD);
+        if let Some(track_id) = skipped_track_id {
+          let position_relative = self.track_position_relative;
+          let player = player.clone();
+          let report_command = Command::perform(
+            async move { player.get_client().report_skip(track_id, position_relative).await },
+            |r| ReceiveReportSkip(r),
+          );
+          return Command::batch(vec![next_command, report_command]);
+        }
+        return next_command;
+      }
+      ReceivePlayQueued(track, r) => match r {
+        Ok(_) => return self.set_playing(player, track),
+        Err(e) => error!("Failed to play queued track: {:?}", FormatError::new(&e)),
+      }
+      SetAlbumRadioEnabled(enabled) => {
+        player.set_album_radio_enabled(enabled);
+        self.album_radio_enabled = enabled;
+      }
+      SetDownrankSkippedEnabled(enabled) => {
+        player.set_downrank_skipped_enabled(enabled);
+        self.downrank_skipped_enabled = enabled;
+      }
+      ReceiveReportSkip(r) => match r {
+        Ok(recorded) => debug!("Reported skip, recorded: {}", recorded),
+        Err(e) => error!("Failed to report skip: {:?}", FormatError::new(&e)),
+      }
+      ChannelControl(m) => {
+        match m {
+          ChannelControlMessage::SetMonoDownmix(enabled) => {
+            self.channel_processing.mono_downmix = enabled;
+            return Command::batch(vec![self.apply_channel_processing(player), self.save_channel_processing(player)]);
+          }
+          ChannelControlMessage::SetBalance(balance) => self.channel_processing.balance = balance,
+          ChannelControlMessage::SetCrossfeed(crossfeed) => self.channel_processing.crossfeed = crossfeed,
+          ChannelControlMessage::Save => return self.save_channel_processing(player),
+        }
+        return self.apply_channel_processing(player);
+      }
+      ReceiveChannelProcessingPreferences(r) => match r {
+        Ok(preferences) => {
+          self.channel_processing = ChannelProcessing::from_user_preferences(&preferences);
+          return self.apply_channel_processing(player);
+        }
+        Err(e) => error!("Failed to receive channel processing preferences: {:?}", FormatError::new(&e)),
+      }
+      ReceiveSetChannelProcessing(r) => if let Err(e) = r {
+        error!("Failed to set channel processing: {:?}", FormatError::new(&e));
+      }
+      ReceiveSaveChannelProcessing(r) => if let Err(e) = r {
+        error!("Failed to save channel processing: {:?}", FormatError::new(&e));
+      }
+      EnforceVolumeLimits => return Self::enforce_volume_limits(player),
+      ReceiveEnforceVolumeLimits(r) => match r {
+        Ok(quiet_hours_active) => self.quiet_hours_active = quiet_hours_active,
+        Err(e) => error!("Failed to enforce volume limits: {:?}", FormatError::new(&e)),
+      }
+      ReceiveAlbumRadioTracks(r) => match r {
+        Ok(tracks) => {
+          debug!("Continuing with {} album radio tracks", tracks.len());
+          self.queue.extend(tracks);
+          self.announce_queue(player);
+          if self.is_stopped {
+            return self.play_next_queued(player);
+          }
+        }
+        Err(e) => error!("Failed to receive album radio tracks: {:?}", FormatError::new(&e)),
+      }
+      ReceivePlayerEvent(event) => match event {
+        PlayerEvent::Progress { position, duration, position_relative } => {
           self.track_position_relative = position_relative.unwrap_or(0.0f64);
-          self.player_status_subscription_active = !is_stopped;
+          self.now_playing_tab.set_position(position, duration);
+        }
+        PlayerEvent::TrackEnded { finished: true, .. } => {
+          self.is_stopped = true;
+          return self.play_next_queued(player);
+        }
+        PlayerEvent::Buffering(is_buffering) => self.is_buffering = is_buffering,
+        event => debug!("Player event: {:?}", event),
+      }
+      ReceivePlaybackError(e) => {
+        error!("Playback error: {:?}", FormatError::new(&e));
+        self.toasts.push(Toast { message: e.to_string(), shown_at: Instant::now(), dismiss_button_state: Default::default() });
+        // Continue with the next track in the queue when the current track stopped playing.
+        if e.is_fatal() && !self.is_stopped {
+          return if self.queue.is_empty() {
+            self.update(player, RequestStop)
+          } else {
+            self.play_next_queued(player)
+          };
         }
-        Err(e) => error!("Failed to receive player status: {:?}", FormatError::new(&e)),
       }
+      DismissToast(index) => if index < self.toasts.len() {
+        self.toasts.remove(index);
+      }
+      ExpireToasts(now) => self.toasts.retain(|toast| now.duration_since(toast.shown_at) < TOAST_DURATION),
       m => debug!("Unhandled message: {:?}", m)
     };
     Command::none()
   }
 
-  pub fn handle_action(&mut self, action: Option<Action>) {
+  pub fn handle_action<P: Player>(&mut self, player: &P, action: Option<Action>) -> Command<Message<P>> {
     if let Some(action) = action {
       match action {
-        Action::ReceivePlay => {
-          self.is_paused = false;
-          self.is_stopped = false;
-          self.player_status_subscription_active = true;
+        Action::ReceivePlay(track) => return self.set_playing(player, track),
+        Action::Enqueue(tracks) => {
+          debug!("Adding {} tracks to the queue", tracks.len());
+          self.queue.extend(tracks);
+          self.announce_queue(player);
+          if self.is_stopped {
+            return self.play_next_queued(player);
+          }
+        }
+        Action::EnqueueNext(tracks) => {
+          debug!("Adding {} tracks to the front of the queue", tracks.len());
+          for track in tracks.into_iter().rev() {
+            self.queue.push_front(track);
+          }
+          self.announce_queue(player);
+          if self.is_stopped {
+            return self.play_next_queued(player);
+          }
+        }
+        Action::PlayTracks(tracks) => {
+          debug!("Replacing the queue with {} tracks", tracks.len());
+          self.queue = tracks.into();
+          self.announce_queue(player);
+          return self.play_next_queued(player);
         }
       }
     }
+    Command::none()
   }
 
-  pub fn subscription<P: Player>(&self, player: &P) -> Subscription<Message<P>> {
-    let player_status_subscription = if self.player_status_subscription_active {
+  /// Returns the playback status to show in the tray icon.
+  pub fn tray_status(&self) -> TrayStatus {
+    let playing_track = if self.is_stopped { None } else {
+      self.now_playing_tab.track().map(|track| match &track.track_artists {
+        Some(track_artists) => format!("{} - {}", track.title, track_artists),
+        None => track.title.clone(),
+      })
+    };
+    TrayStatus { playing_track, can_play_next: !self.queue.is_empty() }
+  }
+
+  /// Returns the playback status to reply to the `status` remote command.
+  pub fn remote_status(&self) -> RemoteStatus {
+    RemoteStatus {
+      playing_track: self.tray_status().playing_track,
+      is_paused: self.is_paused,
+      queue_length: self.queue.len(),
+    }
+  }
+
+  /// Returns the message that executes remote `command`, or `None` if there is nothing to do, such as pausing while
+  /// playback is already paused.
+  pub fn remote_message<P: Player>(&self, command: RemoteCommand) -> Option<Message<P>> {
+    match command {
+      RemoteCommand::Play if self.is_stopped && !self.queue.is_empty() => Some(Message::RequestNextTrack),
+      RemoteCommand::Play if !self.is_stopped && self.is_paused => Some(Message::RequestTogglePlay),
+      RemoteCommand::Pause if !self.is_stopped && !self.is_paused => Some(Message::RequestTogglePlay),
+      RemoteCommand::Next => Some(Message::RequestNextTrack),
+      _ => None,
+    }
+  }
+
+  /// Returns the track to publish to Discord Rich Presence, or `None` when playback is stopped.
+  #[cfg(feature = "discord_presence")]
+  pub fn presence_track(&self) -> Option<musium_discord_presence::PresenceTrack> {
+    if self.is_stopped { return None; }
+    self.now_playing_tab.track().map(|track| musium_discord_presence::PresenceTrack {
+      id: track.id,
+      title: track.title.clone(),
+      artists: track.track_artists.clone(),
+      album_id: track.album_id,
+      album: track.album.clone(),
+      position: self.now_playing_tab.position(),
+      is_paused: self.is_paused,
+    })
+  }
+
+  fn set_playing<P: Player>(&mut self, player: &P, track: PlayingTrack) -> Command<Message<P>> {
+    if self.played_track_ids.len() == PLAYED_TRACKS_HISTORY {
+      self.played_track_ids.pop_front();
+    }
+    self.played_track_ids.push_back(track.id);
+    self.is_paused = false;
+    self.is_stopped = false;
+    self.now_playing_tab.set_track(player, track).map(|m| Message::NowPlayingTab(m))
+  }
+
+  /// Announces the queued tracks to subscribers of player events, as the player does not keep a queue.
+  fn announce_queue<P: Player>(&self, player: &P) {
+    player.announce_queue_changed(self.queue.iter().map(|track| track.id).collect());
+  }
+
+  fn apply_channel_processing<P: Player>(&self, player: &P) -> Command<Message<P>> {
+    let processing = self.channel_processing;
+    let player = player.clone();
+    Command::perform(
+      async move { player.set_channel_processing(processing).await },
+      |r| Message::ReceiveSetChannelProcessing(r),
+    )
+  }
+
+  fn save_channel_processing<P: Player>(&self, player: &P) -> Command<Message<P>> {
+    // Channel processing still applies in read-only mode, but only until the GUI is closed.
+    if self.read_only { return Command::none(); }
+    let processing = self.channel_processing;
+    let player = player.clone();
+    Command::perform(
+      async move {
+        let client = player.get_client();
+        let mut preferences = client.get_user_preferences().await?;
+        processing.store_in_user_preferences(&mut preferences);
+        // Leave the preferred playback source unchanged.
+        preferences.preferred_playback_source = None;
+        client.set_user_preferences(&preferences).await?;
+        Ok(())
+      },
+      |r| Message::ReceiveSaveChannelProcessing(r),
+    )
+  }
+
+  fn enforce_volume_limits<P: Player>(player: &P) -> Command<Message<P>> {
+    let player = player.clone();
+    Command::perform(
+      async move { player.enforce_volume_limits().await },
+      |r| Message::ReceiveEnforceVolumeLimits(r),
+    )
+  }
+
+  fn play_next_queued<P: Player>(&mut self, player: &P) -> Command<Message<P>> {
+    if let Some(track) = self.queue.pop_front() {
+      self.announce_queue(player);
+      let player = player.clone();
+      Command::perform(
+        async move {
+          let result = player.play_track_by_id(track.id).await;
+          (track, result)
+        },
+        |(track, r)| Message::ReceivePlayQueued(track, r),
+      )
+    } else if player.is_album_radio_enabled() && !self.played_track_ids.is_empty() {
+      // Continue with tracks similar to the recently played tracks, instead of stopping.
+      let played_track_ids: Vec<i32> = self.played_track_ids.iter().copied().collect();
       let player = player.clone();
-      Subscription::from_recipe(PlayerStatusSubscription { player }).map(|r| Message::ReceivePlayerStatus(r))
+      Command::perform(
+        async move { player.get_album_radio_tracks(&played_track_ids).await.map(track::playable_tracks) },
+        |r| Message::ReceiveAlbumRadioTracks(r),
+      )
     } else {
+      Command::none()
+    }
+  }
+
+  pub fn subscription<P: Player>(&self, player: &P) -> Subscription<Message<P>> {
+    let player_event_subscription = Subscription::from_recipe(PlayerEventSubscription { player: player.clone() })
+      .map(|e| Message::ReceivePlayerEvent(e));
+    let source_subscription = self.source_tab.subscription(player).map(|m| Message::SourceTab(m));
+    let playback_error_subscription = Subscription::from_recipe(PlaybackErrorSubscription { player: player.clone() })
+      .map(|e| Message::ReceivePlaybackError(e));
+    let toast_subscription = if self.toasts.is_empty() {
       Subscription::none()
+    } else {
+      iced::time::every(Duration::from_secs(1)).map(|now| Message::ExpireToasts(now))
     };
-    let source_subscription = self.source_tab.subscription(player).map(|m| Message::SourceTab(m));
-    Subscription::batch([player_status_subscription, source_subscription])
+    let volume_limits_subscription = iced::time::every(VOLUME_LIMITS_INTERVAL).map(|_| Message::EnforceVolumeLimits);
+    Subscription::batch([player_event_subscription, source_subscription, playback_error_subscription, toast_subscription, volume_limits_subscription])
   }
 
-  pub fn view<P: Player>(&'a mut self) -> Element<'a, Message<P>> {
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
     let tabs = Row::new()
       .spacing(2)
       .align_items(Align::Center)
-      .push(Button::new(&mut self.track_tab_button_state, Text::new("Tracks"))
+      .push(Button::new(&mut self.track_tab_button_state, Text::new(localizer.text("tab-tracks"))).style(theme)
         .on_press_into(|| Message::SetCurrentTab(Tab::Track), self.current_tab != Tab::Track))
-      .push(Button::new(&mut self.source_tab_button_state, Text::new("Sources"))
-        .on_press_into(|| Message::SetCurrentTab(Tab::Source), self.current_tab != Tab::Source))
+      .push(if self.read_only {
+        // Sources cannot be modified in read-only mode, so do not show them at all.
+        empty()
+      } else {
+        Button::new(&mut self.source_tab_button_state, Text::new(localizer.text("tab-sources"))).style(theme)
+          .on_press_into(|| Message::SetCurrentTab(Tab::Source), self.current_tab != Tab::Source).into()
+      })
+      .push(Button::new(&mut self.now_playing_tab_button_state, Text::new(localizer.text("tab-now-playing"))).style(theme)
+        .on_press_into(|| Message::SetCurrentTab(Tab::NowPlaying), self.current_tab != Tab::NowPlaying))
+      .push(Button::new(&mut self.stats_tab_button_state, Text::new(localizer.text("tab-stats"))).style(theme)
+        .on_press_into(|| Message::SetCurrentTab(Tab::Stats), self.current_tab != Tab::Stats))
+      .push(if self.read_only {
+        // Hidden items cannot be unhidden in read-only mode, so do not show them at all.
+        empty()
+      } else {
+        Button::new(&mut self.hidden_tab_button_state, Text::new(localizer.text("tab-hidden"))).style(theme)
+          .on_press_into(|| Message::SetCurrentTab(Tab::Hidden), self.current_tab != Tab::Hidden).into()
+      })
+      .push(Button::new(&mut self.classical_tab_button_state, Text::new(localizer.text("tab-classical"))).style(theme)
+        .on_press_into(|| Message::SetCurrentTab(Tab::Classical), self.current_tab != Tab::Classical))
+      .push(if self.read_only {
+        // Kiosk mode logs in with its kiosk token instead of a profile, so do not allow switching profiles.
+        empty()
+      } else {
+        Button::new(&mut self.switch_profile_button_state, Text::new(localizer.text("switch-profile"))).style(theme)
+          .on_press_into(|| Message::SwitchProfile, true).into()
+      })
       ;
     let current_tab = match self.current_tab {
-      Tab::Track => self.track_tab.view().map(|m| Message::TrackTab(m)),
-      Tab::Source => self.source_tab.view().map(|m| Message::SourceTab(m)),
+      Tab::Track => self.track_tab.view(localizer, theme).map(|m| Message::TrackTab(m)),
+      Tab::Source => self.source_tab.view(localizer, theme).map(|m| Message::SourceTab(m)),
+      Tab::NowPlaying => self.now_playing_tab.view(localizer, theme).map(|m| Message::NowPlayingTab(m)),
+      Tab::Stats => self.stats_tab.view(localizer, theme).map(|m| Message::StatsTab(m)),
+      Tab::Hidden => self.hidden_tab.view(localizer, theme).map(|m| Message::HiddenTab(m)),
+      Tab::Classical => self.classical_tab.view(localizer, theme).map(|m| Message::ClassicalTab(m)),
     };
+    let mut queue_args = FluentArgs::new();
+    queue_args.set("count", self.queue.len());
     let player_controls = Row::new()
       .spacing(2)
       .align_items(Align::Center)
-      .push(Button::new(&mut self.prev_track_button_state, Text::new("Prev track"))
+      .push(Button::new(&mut self.prev_track_button_state, Text::new(localizer.text("player-prev-track"))).style(theme)
         .on_press_into(move || Message::RequestPrevTrack, !self.is_stopped))
-      .push(Button::new(&mut self.stop_button_state, Text::new("Stop"))
+      .push(Button::new(&mut self.stop_button_state, Text::new(localizer.text("player-stop"))).style(theme)
         .on_press_into(move || Message::RequestStop, !self.is_stopped))
-      .push(Button::new(&mut self.toggle_play_button_state, Text::new("Play/pause"))
+      .push(Button::new(&mut self.toggle_play_button_state, Text::new(localizer.text("player-toggle-play"))).style(theme.accent_button())
         .on_press_into(move || Message::RequestTogglePlay, !self.is_stopped))
-      .push(Button::new(&mut self.next_track_button_state, Text::new("Next track"))
-        .on_press_into(move || Message::RequestNextTrack, !self.is_stopped))
+      .push(Button::new(&mut self.next_track_button_state, Text::new(localizer.text("player-next-track"))).style(theme)
+        .on_press_into(move || Message::RequestNextTrack, !self.queue.is_empty()))
+      .push(txt(localizer.text_with("player-queue", &queue_args)))
+      .push(Checkbox::new(self.album_radio_enabled, localizer.text("player-album-radio"), Message::SetAlbumRadioEnabled)
+        .style(theme))
+      .push(Checkbox::new(self.downrank_skipped_enabled, localizer.text("player-downrank-skipped"), Message::SetDownrankSkippedEnabled)
+        .style(theme))
+      .push(if self.quiet_hours_active { txt(localizer.text("player-quiet-hours")).into() } else { empty() })
+      .push(if self.is_buffering { txt(localizer.text("player-buffering")).into() } else { empty() })
       ;
+    let channel_controls: Element<_> = Row::new()
+      .spacing(4)
+      .align_items(Align::Center)
+      .push(Checkbox::new(self.channel_processing.mono_downmix, localizer.text("player-mono-downmix"), ChannelControlMessage::SetMonoDownmix)
+        .style(theme))
+      .push(txt(localizer.text("player-balance")))
+      .push(Slider::new(&mut self.balance_slider_state, -1.0..=1.0, self.channel_processing.balance, ChannelControlMessage::SetBalance)
+        .step(0.05)
+        .on_release(ChannelControlMessage::Save)
+        .width(Length::Units(120))
+        .style(theme))
+      .push(txt(localizer.text("player-crossfeed")))
+      .push(Slider::new(&mut self.crossfeed_slider_state, 0.0..=1.0, self.channel_processing.crossfeed, ChannelControlMessage::SetCrossfeed)
+        .step(0.05)
+        .on_release(ChannelControlMessage::Save)
+        .width(Length::Units(120))
+        .style(theme))
+      .into();
+    let toasts = self.toasts.iter_mut().enumerate().fold(Column::new().spacing(2).width(Length::Fill).align_items(Align::Center), |column, (i, toast)| {
+      let mut args = FluentArgs::new();
+      args.set("error", toast.message.clone());
+      column.push(Container::new(Row::new()
+        .spacing(4)
+        .align_items(Align::Center)
+        .push(txt(localizer.text_with("playback-error", &args)))
+        .push(Button::new(&mut toast.dismiss_button_state, txt("×")).padding(1).style(theme)
+          .on_press_into(move || Message::DismissToast(i), true))
+      ).padding(4).style(theme.menu()))
+    });
     let seek_controls: Element<_> = Slider::new(&mut self.track_position_slider_state, 0.0..=1.0, self.track_position_relative, move |v| v)
       .step(0.001)
+      .style(theme)
       .into();
     let content: Element<_> = Column::new()
       .width(Length::Fill)
@@ -226,10 +698,12 @@ impl<'a> Page {
       .padding(4)
       .spacing(4)
       .push(tabs)
-      .push(horizontal_line())
+      .push(horizontal_line(theme))
       .push(current_tab)
-      .push(horizontal_line())
+      .push(toasts)
+      .push(horizontal_line(theme))
       .push(Column::new().width(Length::Fill).align_items(Align::Center).push(player_controls))
+      .push(Column::new().width(Length::Fill).align_items(Align::Center).push(channel_controls.map(|m| Message::ChannelControl(m))))
       .push(seek_controls.map(|v| Message::RequestSeek(v)))
       .into();
     content//.explain([0.5, 0.5, 0.5])
@@ -266,89 +740,95 @@ fn cell_text<'a, M>(label: impl Into<String>) -> Element<'a, M> {
     .into()
 }
 
-fn cell_checkbox<'a, M: 'a>(is_checked: bool, message_fn: impl 'static + Fn(bool) -> M) -> Element<'a, M> {
+fn cell_checkbox<'a, M: 'a>(theme: Theme, is_checked: bool, message_fn: impl 'static + Fn(bool) -> M) -> Element<'a, M> {
   Checkbox::new(is_checked, "", message_fn)
+    .style(theme)
     .into()
 }
 
-fn cell_button<'a, M: 'static>(state: &'a mut button::State, label: impl Into<String>, enabled: bool, message_fn: impl 'static + Fn() -> M) -> Element<'a, M> {
+fn cell_button<'a, M: 'static>(theme: Theme, state: &'a mut button::State, label: impl Into<String>, enabled: bool, message_fn: impl 'static + Fn() -> M) -> Element<'a, M> {
   Button::new(state, txt(label))
     .padding(1)
+    .style(theme)
     .on_press_into(message_fn, enabled)
 }
 
-fn horizontal_line<M: 'static>() -> Element<'static, M> {
+fn horizontal_line<M: 'static>(theme: Theme) -> Element<'static, M> {
   Rule::horizontal(1)
-    .style(HorizontalLine)
+    .style(theme)
     .into()
 }
 
-struct HorizontalLine;
-
-impl rule::StyleSheet for HorizontalLine {
-  fn style(&self) -> rule::Style {
-    rule::Style {
-      color: [0.6, 0.6, 0.6, 0.6].into(),
-      width: 1,
-      radius: 0.0,
-      fill_mode: rule::FillMode::Full,
-    }
-  }
-}
-
 fn empty<'a, M: 'a>() -> Element<'a, M> {
   Space::new(Length::Shrink, Length::Shrink).into()
 }
 
-// Player status subscription
+#[derive(Debug, Error)]
+pub enum ChangeVolumeError<P: Player> {
+  #[error(transparent)]
+  GetVolumeFail(<P::AudioOutput as AudioOutput>::GetVolumeError),
+  #[error(transparent)]
+  SetVolumeFail(<P::AudioOutput as AudioOutput>::SetVolumeError),
+}
+
+// Playback error subscription
 
-struct PlayerStatusSubscription<P: Player> {
+struct PlaybackErrorSubscription<P: Player> {
   player: P,
 }
 
-#[derive(Debug)]
-pub struct PlayerStatus {
-  pub is_stopped: bool,
-  pub position_relative: Option<f64>,
+impl<H, I, P: Player> Recipe<H, I> for PlaybackErrorSubscription<P> where
+  H: Hasher
+{
+  type Output = PlaybackError;
+
+  fn hash(&self, state: &mut H) {
+    // Only one playback error subscription may be active, so hash just the marker struct.
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+  }
+
+  fn stream(self: Box<Self>, _input: BoxStream<I>) -> BoxStream<Self::Output> {
+    let receiver = self.player.get_audio_output().subscribe_playback_errors();
+    Box::pin(futures::stream::unfold(receiver, |mut receiver| async move {
+      loop {
+        match receiver.recv().await {
+          Ok(error) => return Some((error, receiver)),
+          Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => debug!("Skipped {} playback errors", skipped),
+          Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        }
+      }
+    }))
+  }
 }
 
-#[derive(Debug, Error)]
-pub enum PlayerStatusError<P: Player> {
-  #[error(transparent)]
-  IsStoppedFail(<P::AudioOutput as AudioOutput>::IsStoppedError),
-  #[error(transparent)]
-  GetPositionRelativeFail(<P::AudioOutput as AudioOutput>::GetPositionRelativeError),
+// Player event subscription
+
+struct PlayerEventSubscription<P: Player> {
+  player: P,
 }
 
-impl<H, I, P: Player> Recipe<H, I> for PlayerStatusSubscription<P> where
+impl<H, I, P: Player> Recipe<H, I> for PlayerEventSubscription<P> where
   H: Hasher
 {
-  type Output = Result<PlayerStatus, PlayerStatusError<P>>;
+  type Output = PlayerEvent;
 
   fn hash(&self, state: &mut H) {
-    // Only one player status subscription may be active, so hash just the marker struct.
+    // Only one player event subscription may be active, so hash just the marker struct.
     struct Marker;
     std::any::TypeId::of::<Marker>().hash(state);
   }
 
-  fn stream(self: Box<Self>, input: BoxStream<I>) -> BoxStream<Self::Output> {
-    Box::pin(futures::stream::unfold((self.player, false, false), |(player, stop, delay)| async move {
-      use PlayerStatusError::*;
-      if stop {
-        return None;
-      }
-      if delay {
-        tokio::time::sleep(Duration::from_millis(250)).await;
-      }
-      let is_stopped = match player.get_audio_output().is_stopped().await {
-        Err(e) => return Some((Err(IsStoppedFail(e)), (player, true, true))),
-        Ok(v) => v,
-      };
-      let position_relative = match player.get_audio_output().get_position_relative().await {
-        Err(e) => return Some((Err(GetPositionRelativeFail(e)), (player, true, true))),
-        Ok(v) => v,
-      };
-      Some((Ok(PlayerStatus { is_stopped, position_relative }), (player, is_stopped, true)))
+  fn stream(self: Box<Self>, _input: BoxStream<I>) -> BoxStream<Self::Output> {
+    let receiver = self.player.subscribe_player_events();
+    Box::pin(futures::stream::unfold(receiver, |mut receiver| async move {
+      loop {
+        match receiver.recv().await {
+          Ok(event) => return Some((event, receiver)),
+          Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => debug!("Skipped {} player events", skipped),
+          Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        }
+      }
     }))
   }
 }
@@ -0,0 +1,195 @@
+use iced::{Align, button, Button, Column, Command, Container, Element, image, Image, Length, Row, scrollable, Scrollable, Text};
+use tracing::{debug, error};
+
+use musium_core::api::{Artwork, Lyrics};
+use musium_core::format_error::FormatError;
+use musium_core::model::{MAX_RATING, UserTrackRating};
+use musium_i18n::Localizer;
+use musium_player::{Client, Player};
+
+use crate::page::main::{empty, h1, h3, PlayingTrack, RATING_STARS, txt};
+use crate::theme::Theme;
+use crate::util::ButtonEx;
+
+/// Size in pixels of the album cover that is requested and shown.
+const ARTWORK_SIZE: u32 = 256;
+/// Number of lines of synced lyrics that are shown before the line that is being sung.
+const LYRICS_LINES_BEFORE: usize = 3;
+/// Number of lines of synced lyrics that are shown after the line that is being sung.
+const LYRICS_LINES_AFTER: usize = 8;
+
+#[derive(Default, Debug)]
+pub struct Tab {
+  track: Option<PlayingTrack>,
+  artwork: Option<image::Handle>,
+  lyrics: Option<Lyrics>,
+  /// Position of playback in seconds, updated from player status events.
+  position: Option<f64>,
+  /// Duration of the track in seconds, updated from player status events.
+  duration: Option<f64>,
+  /// Rating the user gave to the track while it was playing.
+  rating: Option<i32>,
+  rating_button_states: [button::State; RATING_STARS as usize],
+  lyrics_scrollable_state: scrollable::State,
+  /// Whether the user may only browse and play, hiding the rating of the track.
+  read_only: bool,
+}
+
+#[derive(Debug)]
+pub enum Message<P: Player> {
+  ReceiveArtwork(i32, Result<Option<Artwork>, <P::Client as Client>::AlbumError>),
+  ReceiveLyrics(i32, Result<Option<Lyrics>, <P::Client as Client>::TrackError>),
+  RequestRate(i32),
+  ReceiveRate(Result<UserTrackRating, <P::Client as Client>::UserDataError>),
+}
+
+impl<'a> Tab {
+  pub fn new(read_only: bool) -> Self {
+    Self { read_only, ..Self::default() }
+  }
+
+  /// Shows `track` as the playing track, requesting its album cover and lyrics.
+  pub fn set_track<P: Player>(&mut self, player: &P, track: PlayingTrack) -> Command<Message<P>> {
+    let (track_id, album_id) = (track.id, track.album_id);
+    *self = Self { track: Some(track), read_only: self.read_only, ..Self::default() };
+    let player_clone = player.clone();
+    let artwork_command = Command::perform(
+      async move { player_clone.get_client().get_album_cover(album_id, Some(ARTWORK_SIZE)).await },
+      move |r| Message::ReceiveArtwork(track_id, r),
+    );
+    let player = player.clone();
+    let lyrics_command = Command::perform(
+      async move { player.get_client().get_track_lyrics(track_id).await },
+      move |r| Message::ReceiveLyrics(track_id, r),
+    );
+    Command::batch(vec![artwork_command, lyrics_command])
+  }
+
+  pub fn track(&self) -> Option<&PlayingTrack> { self.track.as_ref() }
+
+  pub fn position(&self) -> Option<f64> { self.position }
+
+  pub fn set_position(&mut self, position: Option<f64>, duration: Option<f64>) {
+    self.position = position;
+    self.duration = duration;
+  }
+
+  pub fn update<P: Player>(&mut self, player: &P, message: Message<P>) -> Command<Message<P>> {
+    let track_id = self.track.as_ref().map(|t| t.id);
+    match message {
+      Message::ReceiveArtwork(id, _) | Message::ReceiveLyrics(id, _) if Some(id) != track_id => {
+        debug!("Discarding artwork or lyrics of a track that is no longer playing");
+      }
+      Message::ReceiveArtwork(_, r) => match r {
+        Ok(artwork) => self.artwork = artwork.map(|a| image::Handle::from_memory(a.data)),
+        Err(e) => error!("Failed to receive album cover: {:?}", FormatError::new(&e)),
+      }
+      Message::ReceiveLyrics(_, r) => match r {
+        Ok(lyrics) => self.lyrics = lyrics,
+        Err(e) => error!("Failed to receive lyrics: {:?}", FormatError::new(&e)),
+      }
+      Message::RequestRate(rating) => if let Some(track_id) = track_id {
+        self.rating = Some(rating);
+        let player = player.clone();
+        return Command::perform(
+          async move { player.get_client().set_user_track_rating(track_id, rating).await },
+          |r| Message::ReceiveRate(r),
+        );
+      }
+      Message::ReceiveRate(r) => if let Err(e) = r {
+        error!("Failed to rate track: {:?}", FormatError::new(&e));
+      }
+    }
+    Command::none()
+  }
+
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
+    let track = match &self.track {
+      Some(track) => track,
+      None => return Container::new(txt(localizer.text("now-playing-nothing")))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into(),
+    };
+    let artwork: Element<_> = match &self.artwork {
+      Some(handle) => Image::new(handle.clone()).width(Length::Units(ARTWORK_SIZE as u16)).height(Length::Units(ARTWORK_SIZE as u16)).into(),
+      None => Container::new(txt(localizer.text("now-playing-no-artwork")))
+        .width(Length::Units(ARTWORK_SIZE as u16))
+        .height(Length::Units(ARTWORK_SIZE as u16))
+        .center_x()
+        .center_y()
+        .into(),
+    };
+    let rating = self.rating;
+    let rating_row = self.rating_button_states.iter_mut().enumerate()
+      .fold(Row::new().spacing(2).align_items(Align::Center).push(txt(localizer.text("track-menu-rate"))), |row, (i, state)| {
+        let stars = i as i32 + 1;
+        let star_rating = stars * MAX_RATING / RATING_STARS;
+        // Highlight the button of the current rating.
+        let style: Box<dyn button::StyleSheet> = if rating == Some(star_rating) { Box::new(theme.accent_button()) } else { Box::new(theme) };
+        row.push(Button::new(state, txt("★".repeat(stars as usize)))
+          .padding(1)
+          .style(style)
+          .on_press_into(move || Message::RequestRate(star_rating), true))
+      });
+    let time = match (self.position, self.duration) {
+      (Some(position), Some(duration)) => format!("{} / -{}", format_time(position), format_time((duration - position).max(0f64))),
+      (Some(position), None) => format_time(position),
+      _ => String::new(),
+    };
+    let info = Column::new()
+      .spacing(4)
+      .push(h1(track.title.clone()))
+      .push(h3(track.track_artists.clone().unwrap_or_default()))
+      .push(txt(track.album.clone().unwrap_or_default()))
+      .push(txt(time))
+      .push(if self.read_only { empty() } else { rating_row.into() });
+    let lyrics = lyrics_view(localizer, theme, self.lyrics.as_ref(), self.position);
+    Column::new()
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .spacing(8)
+      .push(Row::new()
+        .spacing(8)
+        .push(artwork)
+        .push(info)
+      )
+      .push(Scrollable::new(&mut self.lyrics_scrollable_state)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .push(lyrics)
+      )
+      .into()
+  }
+}
+
+fn lyrics_view<'a, P: Player>(localizer: &Localizer, theme: Theme, lyrics: Option<&Lyrics>, position: Option<f64>) -> Element<'a, Message<P>> {
+  let lyrics = match lyrics {
+    Some(lyrics) if !lyrics.lines.is_empty() => lyrics,
+    _ => return txt(localizer.text("now-playing-no-lyrics")).into(),
+  };
+  let column = Column::new().spacing(2).width(Length::Fill);
+  if !lyrics.synced {
+    return lyrics.lines.iter().fold(column, |column, line| column.push(txt(line.text.clone()))).into();
+  }
+  // Show the lines around the line that is being sung, so that the sung line stays in view without scrolling.
+  let current = position.and_then(|position| lyrics.line_index_at(position));
+  let start = current.map_or(0, |current| current.saturating_sub(LYRICS_LINES_BEFORE));
+  let end = (current.unwrap_or(0) + LYRICS_LINES_AFTER + 1).min(lyrics.lines.len());
+  lyrics.lines[start..end].iter().enumerate().fold(column, |column, (i, line)| {
+    let text: Text = if Some(start + i) == current {
+      h3(line.text.clone()).color(theme.palette.accent)
+    } else {
+      txt(line.text.clone()).color(theme.palette.text_muted)
+    };
+    column.push(text)
+  }).into()
+}
+
+/// Formats `seconds` as minutes and seconds, such as `3:07`.
+fn format_time(seconds: f64) -> String {
+  let seconds = seconds.max(0f64) as u64;
+  format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -10,15 +10,20 @@ use iced_native::subscription::Recipe;
 use itertools::Itertools;
 use tracing::{debug, error};
 
-use musium_core::api::SyncStatus;
+use musium_core::api::{Capabilities, SpotifySourceInfo, SyncStatus};
 use musium_core::format_error::FormatError;
-use musium_core::model::{LocalSource, SpotifySource};
+use musium_core::model::LocalSource;
+use musium_i18n::{FluentArgs, Localizer};
 use musium_player::{Client, HttpRequestError, Player};
 
 use crate::page::main::{cell_button, cell_checkbox, cell_text, h1, h2, header_text, horizontal_line};
+use crate::theme::Theme;
 use crate::util::{ButtonEx, Update};
 use crate::widget::table::TableBuilder;
 
+/// Time the server may hold a request for a change of the sync status.
+const SYNC_STATUS_WAIT: Duration = Duration::from_secs(30);
+
 #[derive(Default, Debug)]
 pub struct Tab {
   local_sources: LocalSources,
@@ -40,7 +45,7 @@ pub enum Message<P: Player> {
   RequestSetLocalSourceEnabled(i32, bool),
   ReceiveSetLocalSourceEnabled(Result<Option<LocalSource>, <P::Client as Client>::LocalSourceError>, i32, bool),
   RequestSetSpotifySourceEnabled(i32, bool),
-  ReceiveSetSpotifySourceEnabled(Result<Option<SpotifySource>, <P::Client as Client>::SpotifySourceError>, i32, bool),
+  ReceiveSetSpotifySourceEnabled(Result<Option<SpotifySourceInfo>, <P::Client as Client>::SpotifySourceError>, i32, bool),
 
   RequestSync,
   RequestLocalSourcesSync,
@@ -48,6 +53,8 @@ pub enum Message<P: Player> {
   RequestSpotifySourcesSync,
   RequestSpotifySourceSync(i32),
   ReceiveSyncStatus(Result<SyncStatus, <P::Client as Client>::SyncError>),
+
+  ReceiveCapabilities(Result<Capabilities, <P::Client as Client>::ServerInfoError>),
 }
 
 impl<'a> Tab {
@@ -55,7 +62,12 @@ impl<'a> Tab {
     let mut tab = Self {
       ..Self::default()
     };
-    let command = tab.refresh(player);
+    let capabilities_player = player.clone();
+    let capabilities_command = Command::perform(
+      async move { capabilities_player.get_client().get_capabilities().await },
+      |r| Message::ReceiveCapabilities(r),
+    );
+    let command = Command::batch(vec![tab.refresh(player), capabilities_command]);
     (tab, command)
   }
 
@@ -168,12 +180,11 @@ impl<'a> Tab {
         match result {
           Ok(sync_status) => {
             debug!("Received sync status: {}", sync_status);
-            match sync_status {
-              SyncStatus::Idle | SyncStatus::Completed | SyncStatus::Failed => {
-                self.syncing = false;
-                self.sync_subscription_active = false;
-              }
-              _ => self.syncing = true,
+            if sync_status.is_running() {
+              self.syncing = true;
+            } else {
+              self.syncing = false;
+              self.sync_subscription_active = false;
             }
           }
           Err(e) => {
@@ -183,6 +194,11 @@ impl<'a> Tab {
           }
         };
       }
+
+      ReceiveCapabilities(result) => match result {
+        Ok(capabilities) => self.spotify_sources.capabilities = Some(capabilities),
+        Err(e) => error!("Receiving capabilities failed: {:?}", FormatError::new(&e)),
+      }
     }
     Update::none()
   }
@@ -196,7 +212,7 @@ impl<'a> Tab {
     }
   }
 
-  pub fn view<P: Player>(&'a mut self) -> Element<'a, Message<P>> {
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
     let header = Row::new()
       .spacing(2)
       .width(Length::Fill)
@@ -204,24 +220,24 @@ impl<'a> Tab {
       .push(Row::new()
         .width(Length::Fill)
         .align_items(Align::Center)
-        .push(h1("Sources"))
+        .push(h1(localizer.text("sources-title")))
       )
       .push(Row::new()
-        .push(Button::new(&mut self.refresh_button_state, Text::new("Refresh")).on_press_into(|| Message::RequestRefresh, !self.refreshing))
-        .push(Button::new(&mut self.sync_all_button_state, Text::new("Sync all")).on_press_into(|| Message::RequestSync, !self.syncing))
+        .push(Button::new(&mut self.refresh_button_state, Text::new(localizer.text("refresh"))).style(theme).on_press_into(|| Message::RequestRefresh, !self.refreshing))
+        .push(Button::new(&mut self.sync_all_button_state, Text::new(localizer.text("sources-sync-all"))).style(theme).on_press_into(|| Message::RequestSync, !self.syncing))
       )
       ;
-    let local_sources = self.local_sources.view(self.syncing);
-    let spotify_sources = self.spotify_sources.view(self.syncing);
+    let local_sources = self.local_sources.view(self.syncing, localizer, theme);
+    let spotify_sources = self.spotify_sources.view(self.syncing, localizer, theme);
     Column::new()
       .width(Length::Fill)
       .height(Length::Fill)
       .spacing(4)
       .align_items(Align::Center)
       .push(header)
-      .push(horizontal_line())
+      .push(horizontal_line(theme))
       .push(local_sources)
-      .push(horizontal_line())
+      .push(horizontal_line(theme))
       .push(spotify_sources)
       .into()
   }
@@ -256,7 +272,7 @@ impl<'a> LocalSources {
     self.sources = Rc::new(RefCell::new(sources));
   }
 
-  fn view<P: Player>(&'a mut self, syncing: bool) -> Element<'a, Message<P>> {
+  fn view<P: Player>(&'a mut self, syncing: bool, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
     let header = Row::new()
       .spacing(2)
       .width(Length::Fill)
@@ -264,31 +280,32 @@ impl<'a> LocalSources {
       .push(Row::new()
         .width(Length::Fill)
         .align_items(Align::Center)
-        .push(h2("Local sources"))
+        .push(h2(localizer.text("local-sources-title")))
       )
       .push(Row::new()
-        .push(Button::new(&mut self.sync_button_state, Text::new("Sync all local sources"))
+        .push(Button::new(&mut self.sync_button_state, Text::new(localizer.text("local-sources-sync-all"))).style(theme)
           .on_press_into(move || Message::RequestLocalSourcesSync, !syncing)
         )
       )
       ;
+    let sync_label = localizer.text("source-sync");
     let table: Element<_> = TableBuilder::new(self.sources.clone())
       .spacing(2)
       .header_row_height(26)
       .row_height(16)
-      .push_column(5, header_text("ID"), Box::new(|t| {
+      .push_column(5, header_text(localizer.text("column-id")), Box::new(|t| {
         cell_text(t.source.id.to_string())
       }))
-      .push_column(25, header_text("Directory"), Box::new(|t|
+      .push_column(25, header_text(localizer.text("column-directory")), Box::new(|t|
         cell_text(t.source.directory.clone())
       ))
-      .push_column(5, header_text("Enabled"), Box::new(|t| {
+      .push_column(5, header_text(localizer.text("column-enabled")), Box::new(move |t| {
         let id = t.source.id;
-        cell_checkbox(t.source.enabled, move |e| Message::RequestSetLocalSourceEnabled(id, e))
+        cell_checkbox(theme, t.source.enabled, move |e| Message::RequestSetLocalSourceEnabled(id, e))
       }))
-      .push_column(5, header_text("Sync"), Box::new(move |t| {
+      .push_column(5, header_text(localizer.text("column-sync")), Box::new(move |t| {
         let id = t.source.id;
-        cell_button(&mut t.sync_button_state, "Sync", !syncing, move || Message::RequestLocalSourceSync(id))
+        cell_button(theme, &mut t.sync_button_state, sync_label.clone(), !syncing, move || Message::RequestLocalSourceSync(id))
       }))
       .build(&mut self.rows_scrollable_state)
       .into();
@@ -298,7 +315,7 @@ impl<'a> LocalSources {
       .spacing(4)
       .align_items(Align::Center)
       .push(header)
-      .push(horizontal_line())
+      .push(horizontal_line(theme))
       .push(table)
       .into()
   }
@@ -319,6 +336,9 @@ impl<'a> From<LocalSource> for LocalSourceViewModel {
 #[derive(Default, Debug)]
 struct SpotifySources {
   sources: Rc<RefCell<Vec<SpotifySourceViewModel>>>,
+  /// Capabilities of the server, for showing the Spotify redirect URI to register in the Spotify app, or `None` if they
+  /// have not been received yet.
+  capabilities: Option<Capabilities>,
   rows_scrollable_state: scrollable::State,
   sync_button_state: button::State,
 }
@@ -328,7 +348,7 @@ impl<'a> SpotifySources {
     self.sources = Rc::new(RefCell::new(sources));
   }
 
-  fn view<P: Player>(&'a mut self, syncing: bool) -> Element<'a, Message<P>> {
+  fn view<P: Player>(&'a mut self, syncing: bool, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
     let header = Row::new()
       .spacing(2)
       .width(Length::Fill)
@@ -336,41 +356,54 @@ impl<'a> SpotifySources {
       .push(Row::new()
         .width(Length::Fill)
         .align_items(Align::Center)
-        .push(h2("Spotify sources"))
+        .push(h2(localizer.text("spotify-sources-title")))
       )
       .push(Row::new()
-        .push(Button::new(&mut self.sync_button_state, Text::new("Sync all Spotify sources"))
+        .push(Button::new(&mut self.sync_button_state, Text::new(localizer.text("spotify-sources-sync-all"))).style(theme)
           .on_press_into(move || Message::RequestSpotifySourcesSync, !syncing)
         )
       )
       ;
+    let sync_label = localizer.text("source-sync");
     let table: Element<_> = TableBuilder::new(self.sources.clone())
       .spacing(2)
       .header_row_height(26)
       .row_height(16)
-      .push_column(5, header_text("ID"), Box::new(|t| {
+      .push_column(5, header_text(localizer.text("column-id")), Box::new(|t| {
         cell_text(t.source.id.to_string())
       }))
-      .push_column(25, header_text("User ID"), Box::new(|t|
+      .push_column(25, header_text(localizer.text("column-user-id")), Box::new(|t|
         cell_text(t.source.user_id.to_string())
       ))
-      .push_column(5, header_text("Enabled"), Box::new(|t| {
+      .push_column(5, header_text(localizer.text("column-enabled")), Box::new(move |t| {
         let id = t.source.id;
-        cell_checkbox(t.source.enabled, move |e| Message::RequestSetSpotifySourceEnabled(id, e))
+        cell_checkbox(theme, t.source.enabled, move |e| Message::RequestSetSpotifySourceEnabled(id, e))
       }))
-      .push_column(5, header_text("Sync"), Box::new(move |t| {
+      .push_column(5, header_text(localizer.text("column-sync")), Box::new(move |t| {
         let id = t.source.id;
-        cell_button(&mut t.sync_button_state, "Sync", !syncing, move || Message::RequestSpotifySourceSync(id))
+        cell_button(theme, &mut t.sync_button_state, sync_label.clone(), !syncing, move || Message::RequestSpotifySourceSync(id))
       }))
       .build(&mut self.rows_scrollable_state)
       .into();
+    // Setup instructions, as authorizing a Spotify source fails when the redirect URI is not registered in the Spotify
+    // app of the server.
+    let redirect_uri = match self.capabilities.as_ref().map(|c| &c.spotify_redirect_uri) {
+      Some(Some(redirect_uri)) => {
+        let mut args = FluentArgs::new();
+        args.set("uri", redirect_uri.clone());
+        Text::new(localizer.text_with("spotify-sources-redirect-uri", &args))
+      }
+      Some(None) => Text::new(localizer.text("spotify-sources-redirect-uri-derived")),
+      None => Text::new(""),
+    };
     Column::new()
       .width(Length::Fill)
       .height(Length::Fill)
       .spacing(4)
       .align_items(Align::Center)
       .push(header)
-      .push(horizontal_line())
+      .push(redirect_uri.color(theme.palette.text_muted))
+      .push(horizontal_line(theme))
       .push(table)
       .into()
   }
@@ -378,12 +411,12 @@ impl<'a> SpotifySources {
 
 #[derive(Debug)]
 pub struct SpotifySourceViewModel {
-  source: SpotifySource,
+  source: SpotifySourceInfo,
   sync_button_state: button::State,
 }
 
-impl<'a> From<SpotifySource> for SpotifySourceViewModel {
-  fn from(source: SpotifySource) -> Self { Self { source, sync_button_state: button::State::default() } }
+impl<'a> From<SpotifySourceInfo> for SpotifySourceViewModel {
+  fn from(source: SpotifySourceInfo) -> Self { Self { source, sync_button_state: button::State::default() } }
 }
 
 // Sync subscription
@@ -404,17 +437,19 @@ impl<H, I, P: Player> Recipe<H, I> for SyncSubscription<P> where
   }
 
   fn stream(self: Box<Self>, input: BoxStream<I>) -> BoxStream<Self::Output> {
-    Box::pin(futures::stream::unfold((self.player, false, false), |(player, stop, delay)| async move {
+    Box::pin(futures::stream::unfold((self.player, false, false), |(player, stop, wait)| async move {
       if stop {
         return None;
       }
-      if delay {
-        tokio::time::sleep(Duration::from_millis(1000)).await;
-      }
-      let sync_status_result = player.clone().get_client().get_sync_status().await;
-      let stop = match sync_status_result {
-        Ok(SyncStatus::Idle) | Ok(SyncStatus::Completed) | Ok(SyncStatus::Failed) | Err(_) => true,
-        _ => false,
+      // Long-poll for changes after getting the initial status, instead of repeatedly requesting the status.
+      let sync_status_result = if wait {
+        player.clone().get_client().wait_for_sync_status(SYNC_STATUS_WAIT).await
+      } else {
+        player.clone().get_client().get_sync_status().await
+      };
+      let stop = match &sync_status_result {
+        Ok(sync_status) => !sync_status.is_running(),
+        Err(_) => true,
       };
       Some((sync_status_result, (player, stop, true)))
     }))
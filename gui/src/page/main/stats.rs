@@ -0,0 +1,140 @@
+use iced::{Align, button, Button, Column, Command, Element, Length, Row, scrollable, Scrollable};
+use tracing::error;
+
+use musium_core::api::{PlayCount, StatsPeriod, UserStats, UserStatsQuery};
+use musium_core::format_error::FormatError;
+use musium_i18n::{FluentArgs, Localizer};
+use musium_player::{Client, Player};
+
+use crate::page::main::{h1, h3, txt};
+use crate::theme::Theme;
+use crate::util::ButtonEx;
+
+/// Number of characters of the bar of the hour with the most plays in the hour-of-day histogram.
+const HISTOGRAM_WIDTH: i64 = 40;
+
+#[derive(Default, Debug)]
+pub struct Tab {
+  period: StatsPeriod,
+  stats: Option<UserStats>,
+  loading: bool,
+  period_button_states: [button::State; StatsPeriod::ALL.len()],
+  refresh_button_state: button::State,
+  scrollable_state: scrollable::State,
+}
+
+#[derive(Debug)]
+pub enum Message<P: Player> {
+  SetPeriod(StatsPeriod),
+  RequestStats,
+  ReceiveStats(StatsPeriod, Result<UserStats, <P::Client as Client>::UserError>),
+}
+
+impl<'a> Tab {
+  /// Requests the statistics of the current period, such as when this tab is shown.
+  pub fn request_stats<P: Player>(&mut self, player: &P) -> Command<Message<P>> {
+    self.loading = true;
+    let period = self.period;
+    let player = player.clone();
+    Command::perform(
+      async move { player.get_client().get_my_stats(UserStatsQuery::local(period)).await },
+      move |r| Message::ReceiveStats(period, r),
+    )
+  }
+
+  pub fn update<P: Player>(&mut self, player: &P, message: Message<P>) -> Command<Message<P>> {
+    match message {
+      Message::SetPeriod(period) => {
+        self.period = period;
+        return self.request_stats(player);
+      }
+      Message::RequestStats => return self.request_stats(player),
+      // Discard statistics of a period that is no longer selected.
+      Message::ReceiveStats(period, _) if period != self.period => {}
+      Message::ReceiveStats(_, r) => {
+        self.loading = false;
+        match r {
+          Ok(stats) => self.stats = Some(stats),
+          Err(e) => error!("Failed to receive listening statistics: {:?}", FormatError::new(&e)),
+        }
+      }
+    }
+    Command::none()
+  }
+
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
+    let current_period = self.period;
+    let periods = StatsPeriod::ALL.iter().zip(self.period_button_states.iter_mut())
+      .fold(Row::new().spacing(2).align_items(Align::Center), |row, (period, state)| {
+        let period = *period;
+        row.push(Button::new(state, txt(localizer.text(period_text_id(period)))).style(theme)
+          .on_press_into(move || Message::SetPeriod(period), period != current_period))
+      })
+      .push(Button::new(&mut self.refresh_button_state, txt(localizer.text("refresh"))).style(theme)
+        .on_press_into(|| Message::RequestStats, !self.loading));
+    let header = Column::new()
+      .spacing(4)
+      .push(h1(localizer.text("stats-title")))
+      .push(periods);
+    let stats = match &self.stats {
+      Some(stats) => stats,
+      None => {
+        let text = if self.loading { "stats-loading" } else { "stats-none" };
+        return header.push(txt(localizer.text(text))).into();
+      }
+    };
+
+    let mut summary_args = FluentArgs::new();
+    summary_args.set("plays", stats.play_count);
+    summary_args.set("hours", stats.listening_seconds / 3600);
+    summary_args.set("minutes", stats.listening_seconds % 3600 / 60);
+    summary_args.set("skips", stats.skip_count);
+    let mut streak_args = FluentArgs::new();
+    streak_args.set("current", stats.current_streak_days);
+    streak_args.set("longest", stats.longest_streak_days);
+    let top_list = |title: String, entries: Vec<(String, i64)>| {
+      entries.into_iter().fold(Column::new().spacing(2).width(Length::Fill).push(h3(title)), |column, (name, play_count)| {
+        let mut args = FluentArgs::new();
+        args.set("name", name);
+        args.set("count", play_count);
+        column.push(txt(localizer.text_with("stats-top-entry", &args)))
+      })
+    };
+    let top_lists = Row::new()
+      .spacing(8)
+      .push(top_list(localizer.text("stats-top-artists"), stats.top_artists.iter().map(|PlayCount { item, play_count }| (item.name.clone(), *play_count)).collect()))
+      .push(top_list(localizer.text("stats-top-albums"), stats.top_albums.iter().map(|PlayCount { item, play_count }| (item.name.clone(), *play_count)).collect()))
+      .push(top_list(localizer.text("stats-top-tracks"), stats.top_tracks.iter().map(|PlayCount { item, play_count }| (item.title.clone(), *play_count)).collect()))
+      .push(top_list(localizer.text("stats-most-skipped-tracks"), stats.most_skipped_tracks.iter().map(|PlayCount { item, play_count }| (item.title.clone(), *play_count)).collect()));
+    let max_hour_plays = stats.hour_histogram.iter().copied().max().unwrap_or(0).max(1);
+    let histogram = stats.hour_histogram.iter().enumerate()
+      .fold(Column::new().spacing(0).push(h3(localizer.text("stats-hours"))), |column, (hour, plays)| {
+        let bar = "█".repeat((plays * HISTOGRAM_WIDTH / max_hour_plays) as usize);
+        column.push(txt(format!("{:02}:00 {} {}", hour, bar, plays)).color(theme.palette.accent))
+      });
+    let content = Column::new()
+      .spacing(8)
+      .push(txt(localizer.text_with("stats-summary", &summary_args)))
+      .push(txt(localizer.text_with("stats-streaks", &streak_args)))
+      .push(top_lists)
+      .push(histogram);
+    header
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .push(Scrollable::new(&mut self.scrollable_state)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .push(content)
+      )
+      .into()
+  }
+}
+
+fn period_text_id(period: StatsPeriod) -> &'static str {
+  match period {
+    StatsPeriod::Week => "stats-period-week",
+    StatsPeriod::Month => "stats-period-month",
+    StatsPeriod::Year => "stats-period-year",
+    StatsPeriod::AllTime => "stats-period-all-time",
+  }
+}
@@ -1,75 +1,479 @@
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::rc::Rc;
 
-use iced::{Align, button, Button, Column, Command, Element, HorizontalAlignment, Length, Row, Rule, scrollable, Space, Text, VerticalAlignment};
+use iced::{Align, button, Button, Checkbox, Column, Command, Container, Element, HorizontalAlignment, Length, mouse, Row, Rule, scrollable, Scrollable, Space, Text, text_input, TextInput, VerticalAlignment};
 use itertools::Itertools;
 use tracing::{debug, error};
 
+use musium_core::api::{AlphabetSection, PageQuery, TrackFilter, TrackRatings, TrackSort, TrackSortColumn, TrackTechnicalInfo, TrackVersion, UserPreferences};
+use musium_core::collation::alphabet_index;
 use musium_core::format_error::FormatError;
-use musium_core::model::collection::{TrackInfo, Tracks};
+use musium_core::model::{HiddenItem, HiddenItemKind, MAX_RATING, Playlist, PlaylistTrack, TrackArtistRole, TrackRelation, TrackRelationKind, TrackTag, UserTrackRating};
+use musium_core::model::collection::{TrackInfo, Tracks, TracksRaw};
 use musium_core::panic::panic_into_string;
+use musium_i18n::{FluentArgs, Localizer};
 use musium_player::{Client, Player, PlayError};
 
-use crate::page::main::{cell_button, cell_text, empty, h1, header_text, horizontal_line};
+use crate::page::main::{cell_button, cell_text, empty, h1, header_text, horizontal_line, PlayingTrack, RATING_STARS, txt};
+use crate::reveal::reveal_in_file_manager;
+use crate::theme::Theme;
 use crate::util::{ButtonEx, Update};
-use crate::widget::table::TableBuilder;
+use crate::widget::context_menu::{self, ContextMenu};
+use crate::widget::table::{ColumnResize, ResizeState, RowPress, RowPressState, scroll_to_row, TableBuilder};
+
+/// Number of tracks requested per page. Tracks are loaded page by page, so that the first tracks are shown while the
+/// rest of the tracks are still loading.
+const TRACKS_PAGE_LIMIT: i64 = 500;
+
+/// Key of the user preference that stores the width and visibility of the columns of the track table.
+const COLUMNS_PREFERENCE_KEY: &str = "gui_track_table_columns";
+
+/// Height of the rows of the track table, and the spacing between them.
+const ROW_HEIGHT: u32 = 17;
+const ROW_SPACING: u32 = 1;
 
 #[derive(Default, Debug)]
 pub struct Tab {
   tracks: Rc<RefCell<Vec<TrackViewModel>>>,
   rows_scrollable_state: scrollable::State,
+  columns: Vec<ColumnSetting>,
+  column_resize_state: ResizeState,
+  /// Column the tracks are sorted by, and whether they are sorted in descending order.
+  sort: Option<(TrackColumn, bool)>,
+  /// Sort of the tracks on the server, which determines the order in which pages of tracks are loaded.
+  server_sort: TrackSort,
+  /// Album the tracks are filtered by, as its ID and name.
+  album_filter: Option<(i32, String)>,
+  show_all_button_state: button::State,
+  shuffle_all_button_state: button::State,
+  /// Whether to leave out tracks that are a version of another track, such as remasters and live recordings.
+  collapse_versions: bool,
+  /// IDs of the tracks that are a version of another track.
+  version_track_ids: HashSet<i32>,
+  /// Buttons that jump to the first row of each initial of the sorted text column, as the initial and its row index.
+  jump_buttons: Vec<(char, usize, button::State)>,
+
+  row_press_state: RowPressState,
+  /// Index of the row that selecting a range of rows starts from.
+  selection_anchor: Option<usize>,
+  context_menu_state: context_menu::State,
+  /// Context menu with actions on the selected tracks, or `None` if it is closed.
+  track_menu: Option<TrackMenu>,
+  /// Playlists that selected tracks can be added to.
+  playlists: Vec<Playlist>,
+  /// Panel with technical details of a track, or `None` if it is closed.
+  inspector: Option<Inspector>,
 
   refreshing: bool,
+  /// Incremented on each refresh, to discard pages of tracks that were requested by an earlier refresh.
+  refresh_generation: u64,
   refresh_button_state: button::State,
+
+  /// Whether the user may only browse and play, hiding actions that modify data and not saving column settings.
+  read_only: bool,
 }
 
 #[derive(Debug)]
 pub enum Message<P: Player> {
   RequestRefresh,
-  ReceiveRefresh(Result<Vec<TrackViewModel>, <P::Client as Client>::TrackError>),
+  ReceiveTracksPage(u64, Result<TracksPageViewModel, <P::Client as Client>::TrackError>),
   RequestPlayTrack(i32),
-  ReceivePlayResult(Result<(), P::PlayError>),
+  ReceivePlayResult(PlayingTrack, Result<(), P::PlayError>),
+  SortBy(usize),
+  ResizeColumn(ColumnResize),
+  SetColumnVisible(TrackColumn, bool),
+  ReceiveColumnSettings(Result<UserPreferences, <P::Client as Client>::UserError>),
+  ReceiveSaveColumnSettings(Result<(), <P::Client as Client>::UserError>),
+  PressRow(RowPress),
+  CloseTrackMenu,
+  EnqueueSelection,
+  EnqueueSelectionNext,
+  PlaySelectionAlbumShuffled,
+  PlaySelectionArtist,
+  ReceivePlayTracks(Result<Vec<PlayingTrack>, <P::Client as Client>::TrackError>),
+  AddSelectionToPlaylist(i32),
+  ReceiveAddSelectionToPlaylist(Result<Option<Vec<PlaylistTrack>>, <P::Client as Client>::PlaylistError>),
+  RateSelection(i32),
+  ReceiveRateSelection(Result<Vec<UserTrackRating>, <P::Client as Client>::UserDataError>),
+  SetSelectionTag(String),
+  TagSelection,
+  ReceiveTagSelection(Result<Vec<TrackTag>, <P::Client as Client>::TagError>),
+  ShowSelectionAlbum,
+  InspectSelection,
+  ReceiveTechnicalInfo(Result<Option<TrackTechnicalInfo>, <P::Client as Client>::TrackError>),
+  CloseInspector,
+  RevealSelection,
+  ReceiveRevealTechnicalInfo(Result<Option<TrackTechnicalInfo>, <P::Client as Client>::TrackError>),
+  RevealFile(String),
+  HideSelection(HiddenItemKind),
+  ReceiveHideSelection(Result<Vec<HiddenItem>, <P::Client as Client>::UserDataError>),
+  ShowAllTracks,
+  ShuffleAll,
+  SetCollapseVersions(bool),
+  ReceiveTrackRelations(Result<Vec<TrackRelation>, <P::Client as Client>::TrackError>),
+  ReceiveTrackVersions(i32, Result<Vec<TrackVersion>, <P::Client as Client>::TrackError>),
+  PlayVersion(i32),
+  JumpToRow(usize),
+  ReceivePlaylists(Result<Vec<Playlist>, <P::Client as Client>::PlaylistError>),
 }
 
 impl<'a> Tab {
-  pub fn new<P: Player>(player: &P) -> (Self, Command<Message<P>>) {
+  pub fn new<P: Player>(player: &P, read_only: bool) -> (Self, Command<Message<P>>) {
     let mut tab = Self {
+      columns: TrackColumn::ALL.iter().map(|c| ColumnSetting::new(*c)).collect(),
+      read_only,
       ..Self::default()
     };
-    let command = tab.refresh(player);
+    let player_clone = player.clone();
+    let load_column_settings_command = Command::perform(
+      async move { player_clone.get_client().get_user_preferences().await },
+      |r| Message::ReceiveColumnSettings(r),
+    );
+    let command = Command::batch(vec![tab.refresh(player), load_column_settings_command, Self::request_playlists(player), Self::request_track_relations(player)]);
     (tab, command)
   }
 
   pub fn update<P: Player>(&mut self, player: &P, message: Message<P>) -> Update<Message<P>, super::Action> {
     match message {
       Message::RequestRefresh => {
-        return Update::command(self.refresh(player));
+        return Update::command(Command::batch(vec![self.refresh(player), Self::request_playlists(player), Self::request_track_relations(player)]));
       }
-      Message::ReceiveRefresh(r) => {
-        self.refreshing = false;
+      Message::ReceiveTracksPage(generation, _) if generation != self.refresh_generation => {
+        debug!("Discarding page of tracks of an earlier refresh");
+      }
+      Message::ReceiveTracksPage(_, r) => {
         match r {
-          Ok(tracks) => {
-            debug!("Received {} tracks", tracks.len());
-            self.tracks = Rc::new(RefCell::new(tracks))
+          Ok(TracksPageViewModel { offset, mut tracks, next_page }) => {
+            debug!("Received {} tracks starting at {}", tracks.len(), offset);
+            if self.collapse_versions {
+              tracks.retain(|t| !self.version_track_ids.contains(&t.id));
+            }
+            if offset == 0 {
+              self.tracks = Rc::new(RefCell::new(tracks));
+              self.selection_anchor = None;
+              self.track_menu = None;
+            } else {
+              self.tracks.borrow_mut().extend(tracks);
+            }
+            self.sort_tracks_locally();
+            self.update_jump_buttons();
+            if let Some(next_page) = next_page {
+              return Update::command(self.request_tracks_page(player, next_page));
+            }
           }
           Err(e) => error!("Receiving tracks failed: {:?}", FormatError::new(&e)),
         };
+        self.refreshing = false;
       }
       Message::RequestPlayTrack(track_id) => {
-        return Update::command(Self::play_track(track_id, player));
+        let track = self.tracks.borrow().iter().find(|t| t.id == track_id).map(|t| t.playing_track());
+        if let Some(track) = track {
+          return Update::command(Self::play_track(track, player));
+        }
       }
-      Message::ReceivePlayResult(r) => match r {
+      Message::ReceivePlayResult(track, r) => match r {
         r @ Ok(_) => {
           debug!("Track played successfully");
-          return Update::action(super::Action::ReceivePlay);
+          return Update::action(super::Action::ReceivePlay(track));
         }
         Err(e) => error!("Playing track failed: {:?}", FormatError::new(&e)),
       }
+      Message::SortBy(column_index) => {
+        let column = self.visible_columns().nth(column_index).map(|s| s.column);
+        if let Some(column) = column {
+          if column == TrackColumn::Play { return Update::none(); }
+          let descending = match self.sort {
+            Some((sorted_column, descending)) if sorted_column == column => !descending,
+            _ => false,
+          };
+          self.sort = Some((column, descending));
+          if let Some(server_sort_column) = column.server_sort_column() {
+            // Sort on the server, so that pages of tracks that are still loading are loaded in sorted order.
+            self.server_sort = TrackSort { sort: server_sort_column, descending };
+            return Update::command(self.refresh(player));
+          }
+          self.sort_tracks_locally();
+          self.update_jump_buttons();
+          self.selection_anchor = None;
+        }
+      }
+      Message::ResizeColumn(ColumnResize { column_index, fill_portion, next_fill_portion, finished }) => {
+        let mut visible_columns = self.columns.iter_mut().filter(|s| s.visible).skip(column_index);
+        if let (Some(column), Some(next_column)) = (visible_columns.next(), visible_columns.next()) {
+          column.fill_portion = fill_portion;
+          next_column.fill_portion = next_fill_portion;
+        }
+        if finished {
+          return Update::command(self.save_column_settings(player));
+        }
+      }
+      Message::SetColumnVisible(column, visible) => {
+        if let Some(setting) = self.columns.iter_mut().find(|s| s.column == column) {
+          setting.visible = visible;
+        }
+        return Update::command(self.save_column_settings(player));
+      }
+      Message::ReceiveColumnSettings(r) => match r {
+        Ok(preferences) => if let Some(value) = preferences.other.get(COLUMNS_PREFERENCE_KEY) {
+          ColumnSetting::apply_preference(&mut self.columns, value);
+        }
+        Err(e) => error!("Failed to receive column settings: {:?}", FormatError::new(&e)),
+      }
+      Message::ReceiveSaveColumnSettings(r) => if let Err(e) = r {
+        error!("Failed to save column settings: {:?}", FormatError::new(&e));
+      }
+      Message::PressRow(RowPress { row_index, button, modifiers }) => {
+        if button == mouse::Button::Right {
+          let is_selected = self.tracks.borrow().get(row_index).map_or(false, |t| t.selected);
+          if !is_selected {
+            self.select_only(row_index);
+          }
+          self.track_menu = Some(TrackMenu::new(&self.playlists, self.read_only));
+        } else if modifiers.shift {
+          self.select_range(row_index, modifiers.is_command_pressed());
+        } else if modifiers.is_command_pressed() {
+          if let Some(track) = self.tracks.borrow_mut().get_mut(row_index) {
+            track.selected = !track.selected;
+          }
+          self.selection_anchor = Some(row_index);
+        } else {
+          self.select_only(row_index);
+        }
+      }
+      Message::CloseTrackMenu => self.track_menu = None,
+      Message::EnqueueSelection => {
+        self.track_menu = None;
+        let tracks = self.tracks.borrow().iter().filter(|t| t.selected).map(|t| t.playing_track()).collect();
+        return Update::action(super::Action::Enqueue(tracks));
+      }
+      Message::EnqueueSelectionNext => {
+        self.track_menu = None;
+        let tracks = self.tracks.borrow().iter().filter(|t| t.selected).map(|t| t.playing_track()).collect();
+        return Update::action(super::Action::EnqueueNext(tracks));
+      }
+      Message::PlaySelectionAlbumShuffled => {
+        self.track_menu = None;
+        let album_id = self.tracks.borrow().iter().find(|t| t.selected).map(|t| t.album_id);
+        if let Some(album_id) = album_id {
+          let player = player.clone();
+          return Update::command(Command::perform(
+            async move { player.play_album_shuffled(album_id).await.map(playable_tracks) },
+            |r| Message::ReceivePlayTracks(r),
+          ));
+        }
+      }
+      Message::PlaySelectionArtist => {
+        self.track_menu = None;
+        let artist_id = self.tracks.borrow().iter().find(|t| t.selected).and_then(|t| t.artist_id);
+        if let Some(artist_id) = artist_id {
+          let player = player.clone();
+          return Update::command(Command::perform(
+            async move { player.play_artist(artist_id).await.map(playable_tracks) },
+            |r| Message::ReceivePlayTracks(r),
+          ));
+        }
+      }
+      Message::ReceivePlayTracks(r) => match r {
+        Ok(tracks) => return Update::action(super::Action::PlayTracks(tracks)),
+        Err(e) => error!("Failed to get tracks to play: {:?}", FormatError::new(&e)),
+      }
+      Message::AddSelectionToPlaylist(playlist_id) => {
+        self.track_menu = None;
+        let track_ids = self.selected_track_ids();
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.get_client().add_playlist_tracks(playlist_id, &track_ids).await },
+          |r| Message::ReceiveAddSelectionToPlaylist(r),
+        ));
+      }
+      Message::ReceiveAddSelectionToPlaylist(r) => match r {
+        Ok(Some(playlist_tracks)) => debug!("Added {} tracks to playlist", playlist_tracks.len()),
+        Ok(None) => error!("Failed to add tracks to playlist: playlist does not exist or cannot be edited"),
+        Err(e) => error!("Failed to add tracks to playlist: {:?}", FormatError::new(&e)),
+      }
+      Message::RateSelection(rating) => {
+        self.track_menu = None;
+        let ratings = TrackRatings { track_ids: self.selected_track_ids(), rating };
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.get_client().set_user_track_ratings(&ratings).await },
+          |r| Message::ReceiveRateSelection(r),
+        ));
+      }
+      Message::ReceiveRateSelection(r) => match r {
+        Ok(ratings) => debug!("Rated {} tracks", ratings.len()),
+        Err(e) => error!("Failed to rate tracks: {:?}", FormatError::new(&e)),
+      }
+      Message::SetSelectionTag(tag) => if let Some(track_menu) = &mut self.track_menu {
+        track_menu.tag = tag;
+      }
+      Message::TagSelection => {
+        let tag = match self.track_menu.take() {
+          Some(track_menu) if !track_menu.tag.trim().is_empty() => track_menu.tag.trim().to_string(),
+          _ => return Update::none(),
+        };
+        let track_ids = self.selected_track_ids();
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.get_client().add_tracks_tag(&track_ids, &tag).await },
+          |r| Message::ReceiveTagSelection(r),
+        ));
+      }
+      Message::ReceiveTagSelection(r) => match r {
+        Ok(track_tags) => debug!("Tagged {} tracks", track_tags.len()),
+        Err(e) => error!("Failed to tag tracks: {:?}", FormatError::new(&e)),
+      }
+      Message::ShowSelectionAlbum => {
+        self.track_menu = None;
+        let album = self.tracks.borrow().iter()
+          .find(|t| t.selected)
+          .map(|t| (t.album_id, t.album.clone().unwrap_or_default()));
+        if let Some(album) = album {
+          self.album_filter = Some(album);
+          return Update::command(self.refresh(player));
+        }
+      }
+      Message::InspectSelection => {
+        self.track_menu = None;
+        let track_id = self.tracks.borrow().iter().find(|t| t.selected).map(|t| t.id);
+        if let Some(track_id) = track_id {
+          let player = player.clone();
+          return Update::command(Command::perform(
+            async move { player.get_client().get_track_technical_info(track_id).await },
+            |r| Message::ReceiveTechnicalInfo(r),
+          ));
+        }
+      }
+      Message::ReceiveTechnicalInfo(r) => match r {
+        Ok(Some(technical_info)) => {
+          let track_id = technical_info.track.id;
+          let credits = self.tracks.borrow().iter().find(|t| t.id == track_id).map(|t| t.credits.clone()).unwrap_or_default();
+          self.inspector = Some(Inspector::new(technical_info, credits));
+          let player = player.clone();
+          return Update::command(Command::perform(
+            async move { player.get_client().list_track_versions(track_id).await },
+            move |r| Message::ReceiveTrackVersions(track_id, r),
+          ));
+        }
+        Ok(None) => error!("Failed to inspect track: track does not exist"),
+        Err(e) => error!("Failed to inspect track: {:?}", FormatError::new(&e)),
+      }
+      Message::CloseInspector => self.inspector = None,
+      Message::RevealSelection => {
+        self.track_menu = None;
+        let track_id = self.tracks.borrow().iter().find(|t| t.selected).map(|t| t.id);
+        if let Some(track_id) = track_id {
+          let player = player.clone();
+          return Update::command(Command::perform(
+            async move { player.get_client().get_track_technical_info(track_id).await },
+            |r| Message::ReceiveRevealTechnicalInfo(r),
+          ));
+        }
+      }
+      Message::ReceiveRevealTechnicalInfo(r) => match r {
+        Ok(Some(technical_info)) => {
+          let path = technical_info.local_files.into_iter().filter_map(|f| f.path).next();
+          match path {
+            Some(path) => reveal_file(&path),
+            None if technical_info.paths_hidden => error!("Failed to reveal track file: the server hides file paths"),
+            None => error!("Failed to reveal track file: track has no local file"),
+          }
+        }
+        Ok(None) => error!("Failed to reveal track file: track does not exist"),
+        Err(e) => error!("Failed to reveal track file: {:?}", FormatError::new(&e)),
+      }
+      Message::RevealFile(path) => reveal_file(&path),
+      Message::HideSelection(kind) => {
+        self.track_menu = None;
+        let item_ids: Vec<i32> = {
+          let tracks = self.tracks.borrow();
+          let selected = tracks.iter().filter(|t| t.selected);
+          match kind {
+            HiddenItemKind::Track => selected.map(|t| t.id).collect(),
+            HiddenItemKind::Album => selected.map(|t| t.album_id).unique().collect(),
+            HiddenItemKind::Artist => selected.filter_map(|t| t.artist_id).unique().collect(),
+          }
+        };
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move {
+            let mut hidden_items = Vec::with_capacity(item_ids.len());
+            for item_id in item_ids {
+              hidden_items.push(player.get_client().hide_item(kind, item_id).await?);
+            }
+            Ok(hidden_items)
+          },
+          |r| Message::ReceiveHideSelection(r),
+        ));
+      }
+      Message::ReceiveHideSelection(r) => match r {
+        Ok(hidden_items) => {
+          debug!("Hid {} items", hidden_items.len());
+          // Refresh to leave out the tracks that are now hidden.
+          return Update::command(self.refresh(player));
+        }
+        Err(e) => error!("Failed to hide items: {:?}", FormatError::new(&e)),
+      }
+      Message::ShowAllTracks => {
+        self.album_filter = None;
+        return Update::command(self.refresh(player));
+      }
+      Message::ShuffleAll => {
+        let filter = self.track_filter();
+        let player = player.clone();
+        return Update::command(Command::perform(
+          async move { player.shuffle_all(&filter).await.map(playable_tracks) },
+          |r| Message::ReceivePlayTracks(r),
+        ));
+      }
+      Message::SetCollapseVersions(collapse_versions) => {
+        self.collapse_versions = collapse_versions;
+        // Refresh to show the versions again, or to leave out versions of tracks in pages that are still loading.
+        return Update::command(self.refresh(player));
+      }
+      Message::ReceiveTrackRelations(r) => match r {
+        Ok(relations) => {
+          self.version_track_ids = relations.into_iter().map(|r| r.version_track_id).collect();
+          if self.collapse_versions {
+            self.tracks.borrow_mut().retain(|t| !self.version_track_ids.contains(&t.id));
+            self.selection_anchor = None;
+            self.update_jump_buttons();
+          }
+        }
+        Err(e) => error!("Failed to receive track relations: {:?}", FormatError::new(&e)),
+      }
+      Message::ReceiveTrackVersions(track_id, r) => match r {
+        Ok(versions) => if let Some(inspector) = self.inspector.as_mut().filter(|i| i.technical_info.track.id == track_id) {
+          inspector.set_versions(versions);
+        }
+        Err(e) => error!("Failed to receive track versions: {:?}", FormatError::new(&e)),
+      }
+      Message::PlayVersion(track_id) => {
+        let track = self.inspector.as_ref().and_then(|i| i.versions.iter().find(|(v, _)| v.track.id == track_id)).map(|(v, _)| PlayingTrack {
+          id: v.track.id,
+          title: v.track.title.clone(),
+          track_artists: None,
+          album_id: v.track.album_id,
+          album: None,
+        });
+        if let Some(track) = track {
+          return Update::command(Self::play_track(track, player));
+        }
+      }
+      Message::JumpToRow(row_index) => {
+        scroll_to_row(&mut self.rows_scrollable_state, row_index, ROW_HEIGHT, ROW_SPACING);
+      }
+      Message::ReceivePlaylists(r) => match r {
+        Ok(playlists) => self.playlists = playlists,
+        Err(e) => error!("Failed to receive playlists: {:?}", FormatError::new(&e)),
+      }
     }
     Update::none()
   }
 
-  pub fn view<P: Player>(&'a mut self) -> Element<'a, Message<P>> {
+  pub fn view<P: Player>(&'a mut self, localizer: &Localizer, theme: Theme) -> Element<'a, Message<P>> {
     let header = Row::new()
       .spacing(2)
       .width(Length::Fill)
@@ -77,87 +481,616 @@ impl<'a> Tab {
       .push(Row::new()
         .width(Length::Fill)
         .align_items(Align::Center)
-        .push(h1("Tracks"))
+        .spacing(8)
+        .push(h1(localizer.text("tracks-title")))
+        .push(txt(self.album_filter.as_ref().map(|(_, name)| name.clone()).unwrap_or_default()))
       )
       .push(Row::new()
-        .push(Button::new(&mut self.refresh_button_state, Text::new("Refresh")).on_press_into(|| Message::RequestRefresh, !self.refreshing))
+        .spacing(2)
+        .align_items(Align::Center)
+        .push(Checkbox::new(self.collapse_versions, localizer.text("tracks-collapse-versions"), |v| Message::SetCollapseVersions(v))
+          .size(14)
+          .text_size(16)
+          .style(theme)
+        )
+        .push(Button::new(&mut self.shuffle_all_button_state, Text::new(localizer.text("tracks-shuffle-all"))).style(theme).on_press_into(|| Message::ShuffleAll, true))
+        .push(Button::new(&mut self.show_all_button_state, Text::new(localizer.text("tracks-show-all"))).style(theme).on_press_into(|| Message::ShowAllTracks, self.album_filter.is_some()))
+        .push(Button::new(&mut self.refresh_button_state, Text::new(localizer.text("refresh"))).style(theme).on_press_into(|| Message::RequestRefresh, !self.refreshing))
       )
       ;
-    let table: Element<_> = TableBuilder::new(self.tracks.clone())
-      .spacing(1)
+    let column_toggles = self.columns.iter()
+      .filter(|s| s.column != TrackColumn::Play)
+      .fold(Row::new().spacing(8).align_items(Align::Center).push(txt(localizer.text("tracks-columns"))), |row, s| {
+        let column = s.column;
+        row.push(Checkbox::new(s.visible, localizer.text(column.label_id()), move |v| Message::SetColumnVisible(column, v))
+          .size(14)
+          .text_size(16)
+          .style(theme)
+        )
+      });
+    let has_jump_bar = !self.jump_buttons.is_empty();
+    let jump_bar = self.jump_buttons.iter_mut()
+      .fold(Row::new().spacing(2).align_items(Align::Center), |row, (initial, row_index, state)| {
+        let row_index = *row_index;
+        row.push(cell_button(theme, state, initial.to_string(), true, move || Message::JumpToRow(row_index)))
+      });
+    let play_label = localizer.text("track-play");
+    let mut table = TableBuilder::new(self.tracks.clone())
+      .spacing(ROW_SPACING)
       .header_row_height(27)
-      .row_height(17)
-      .push_column(5, empty(), Box::new(move |t| {
-        play_button(&mut t.play_button_state, t.id)
-      }))
-      .push_column(5, header_text("#"), Box::new(|t|
-        if let Some(track_number) = &t.track_number { cell_text(track_number) } else { empty() }
-      ))
-      .push_column(25, header_text("Title"), Box::new(|t|
-        cell_text(t.title.clone())
-      ))
-      .push_column(25, header_text("Track Artists"), Box::new(|t|
-        if let Some(track_artists) = &t.track_artists { cell_text(track_artists.clone()) } else { empty() }
-      ))
-      .push_column(25, header_text("Album"), Box::new(|t|
-        if let Some(album) = &t.album { cell_text(album.clone()) } else { empty() }
-      ))
-      .push_column(25, header_text("Album Artists"), Box::new(|t|
-        if let Some(album_artists) = &t.album_artists { cell_text(album_artists.clone()) } else { empty() }
-      ))
-      .build(&mut self.rows_scrollable_state)
-      .into();
-    Column::new()
+      .row_height(ROW_HEIGHT);
+    for setting in self.columns.iter().filter(|s| s.visible) {
+      let column = setting.column;
+      let header: Element<_> = match column {
+        TrackColumn::Play => empty(),
+        _ => {
+          let sort_indicator = match self.sort {
+            Some((sorted_column, false)) if sorted_column == column => " ▲",
+            Some((sorted_column, true)) if sorted_column == column => " ▼",
+            _ => "",
+          };
+          header_text(format!("{}{}", localizer.text(column.label_id()), sort_indicator))
+        }
+      };
+      table = table.push_column(setting.fill_portion, header, column.cell(theme, play_label.clone()));
+    }
+    let table = table
+      .on_sort(|column_index| Message::SortBy(column_index))
+      .resizable(&mut self.column_resize_state, |resize| Message::ResizeColumn(resize))
+      .on_row_press(&mut self.row_press_state, |row_press| Message::PressRow(row_press))
+      .build(&mut self.rows_scrollable_state);
+    let num_selected = self.tracks.borrow().iter().filter(|t| t.selected).count();
+    let track_menu = self.track_menu.as_mut().map(|m| m.view(localizer, theme, num_selected));
+    let table: Element<_> = ContextMenu::new(&mut self.context_menu_state, table, track_menu, || Message::CloseTrackMenu).into();
+    let mut column = Column::new()
       .width(Length::Fill)
       .height(Length::Fill)
       .spacing(4)
       .align_items(Align::Center)
       .push(header)
-      .push(horizontal_line())
-      .push(table)
-      .into()
+      .push(column_toggles);
+    if has_jump_bar {
+      column = column.push(jump_bar);
+    }
+    column = column
+      .push(horizontal_line(theme))
+      .push(table);
+    if let Some(inspector) = &mut self.inspector {
+      column = column
+        .push(horizontal_line(theme))
+        .push(inspector.view(localizer, theme));
+    }
+    column.into()
+  }
+
+  fn visible_columns(&self) -> impl Iterator<Item=&ColumnSetting> {
+    self.columns.iter().filter(|s| s.visible)
+  }
+
+  fn selected_track_ids(&self) -> Vec<i32> {
+    self.tracks.borrow().iter().filter(|t| t.selected).map(|t| t.id).collect()
+  }
+
+  fn select_only(&mut self, row_index: usize) {
+    for (i, track) in self.tracks.borrow_mut().iter_mut().enumerate() {
+      track.selected = i == row_index;
+    }
+    self.selection_anchor = Some(row_index);
+  }
+
+  /// Selects the rows from the selection anchor up to and including the row with `row_index`, keeping the current
+  /// selection if `extend` is set.
+  fn select_range(&mut self, row_index: usize, extend: bool) {
+    let anchor = *self.selection_anchor.get_or_insert(row_index);
+    let range = anchor.min(row_index)..=anchor.max(row_index);
+    for (i, track) in self.tracks.borrow_mut().iter_mut().enumerate() {
+      if range.contains(&i) {
+        track.selected = true;
+      } else if !extend {
+        track.selected = false;
+      }
+    }
   }
 
   fn refresh<P: Player>(&mut self, player: &P) -> Command<Message<P>> {
     self.refreshing = true;
+    self.refresh_generation += 1;
+    self.request_tracks_page(player, PageQuery { offset: 0, limit: Some(TRACKS_PAGE_LIMIT) })
+  }
+
+  /// Gets the filter of the tracks that are shown.
+  fn track_filter(&self) -> TrackFilter {
+    TrackFilter { album_id: self.album_filter.as_ref().map(|(album_id, _)| *album_id), ..TrackFilter::default() }
+  }
+
+  fn request_tracks_page<P: Player>(&self, player: &P, page: PageQuery) -> Command<Message<P>> {
+    let generation = self.refresh_generation;
+    let sort = self.server_sort;
+    let filter = self.track_filter();
     let player = player.clone();
     Command::perform(
       async move {
-        let tracks = player.get_client().list_tracks().await?;
+        let tracks_page = player.get_client().list_tracks_page(&page, &sort, &filter).await?;
+        let offset = tracks_page.offset;
+        let next_page = tracks_page.next_page(page.limit);
         let tracks_view_models = tokio::task::spawn_blocking(move || {
-          let tracks: Tracks = tracks.into();
+          let tracks: Tracks = tracks_page.tracks.into();
           let tracks_view_models: Vec<_> = tracks.iter().map(|ti| ti.into()).collect();
           tracks_view_models
         }).await.unwrap_or_else(|e| {
           error!("Tracks view model creation task panicked; returning empty list of tracks. Panic was: {:?}", e.try_into_panic().map(|p| panic_into_string(p)));
           Vec::new()
         });
-        Ok(tracks_view_models)
+        Ok(TracksPageViewModel { offset, tracks: tracks_view_models, next_page })
+      },
+      move |r| Message::ReceiveTracksPage(generation, r),
+    )
+  }
+
+  /// Sorts the loaded tracks when they are sorted by a column that the server cannot sort by.
+  fn sort_tracks_locally(&mut self) {
+    if let Some((column, descending)) = self.sort {
+      if column.server_sort_column().is_some() { return; }
+      self.tracks.borrow_mut().sort_by(|a, b| {
+        let ordering = column.compare(a, b);
+        if descending { ordering.reverse() } else { ordering }
+      });
+    }
+  }
+
+  /// Creates a jump button for each initial of the column the tracks are sorted by, or none if the tracks are not sorted
+  /// by a text column.
+  fn update_jump_buttons(&mut self) {
+    self.jump_buttons.clear();
+    if let Some((column, _)) = self.sort {
+      if !column.is_text() { return; }
+      let tracks = self.tracks.borrow();
+      let mut row_index = 0;
+      for AlphabetSection { initial, count } in alphabet_index(tracks.iter().map(|t| column.text(t))) {
+        self.jump_buttons.push((initial, row_index, button::State::default()));
+        row_index += count as usize;
+      }
+    }
+  }
+
+  fn save_column_settings<P: Player>(&self, player: &P) -> Command<Message<P>> {
+    if self.read_only { return Command::none(); }
+    let value = ColumnSetting::to_preference(&self.columns);
+    let player = player.clone();
+    Command::perform(
+      async move {
+        let client = player.get_client();
+        let mut preferences = client.get_user_preferences().await?;
+        preferences.other.insert(COLUMNS_PREFERENCE_KEY.to_string(), value);
+        // Leave the preferred playback source unchanged.
+        preferences.preferred_playback_source = None;
+        client.set_user_preferences(&preferences).await?;
+        Ok(())
       },
-      |r| Message::ReceiveRefresh(r),
+      |r| Message::ReceiveSaveColumnSettings(r),
     )
   }
 
-  fn play_track<P: Player>(track_id: i32, player: &P) -> Command<Message<P>> {
+  fn request_track_relations<P: Player>(player: &P) -> Command<Message<P>> {
     let player = player.clone();
     Command::perform(
-      async move { player.play_track_by_id(track_id).await },
-      |r| Message::ReceivePlayResult(r),
+      async move { player.get_client().list_track_relations().await },
+      |r| Message::ReceiveTrackRelations(r),
     )
   }
+
+  fn request_playlists<P: Player>(player: &P) -> Command<Message<P>> {
+    let player = player.clone();
+    Command::perform(
+      async move { player.get_client().list_playlists().await },
+      |r| Message::ReceivePlaylists(r),
+    )
+  }
+
+  fn play_track<P: Player>(track: PlayingTrack, player: &P) -> Command<Message<P>> {
+    let player = player.clone();
+    Command::perform(
+      async move {
+        let result = player.play_track_by_id(track.id).await;
+        (track, result)
+      },
+      |(track, r)| Message::ReceivePlayResult(track, r),
+    )
+  }
+}
+
+// Columns
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TrackColumn {
+  Play,
+  TrackNumber,
+  Title,
+  TrackArtists,
+  Album,
+  AlbumArtists,
+}
+
+impl TrackColumn {
+  const ALL: [TrackColumn; 6] = [Self::Play, Self::TrackNumber, Self::Title, Self::TrackArtists, Self::Album, Self::AlbumArtists];
+
+  fn key(self) -> &'static str {
+    match self {
+      Self::Play => "play",
+      Self::TrackNumber => "track_number",
+      Self::Title => "title",
+      Self::TrackArtists => "track_artists",
+      Self::Album => "album",
+      Self::AlbumArtists => "album_artists",
+    }
+  }
+
+  fn label_id(self) -> &'static str {
+    match self {
+      Self::Play => "track-play",
+      Self::TrackNumber => "column-track-number",
+      Self::Title => "column-title",
+      Self::TrackArtists => "column-track-artists",
+      Self::Album => "column-album",
+      Self::AlbumArtists => "column-album-artists",
+    }
+  }
+
+  fn default_fill_portion(self) -> u32 {
+    match self {
+      Self::Play | Self::TrackNumber => 50,
+      _ => 250,
+    }
+  }
+
+  /// Returns the column to sort by on the server when sorting by this column, or `None` if this column must be sorted
+  /// locally.
+  fn server_sort_column(self) -> Option<TrackSortColumn> {
+    match self {
+      Self::Title => Some(TrackSortColumn::Title),
+      Self::Album => Some(TrackSortColumn::Album),
+      _ => None,
+    }
+  }
+
+  /// Returns whether this column contains text, which tracks can be indexed alphabetically by.
+  fn is_text(self) -> bool {
+    match self {
+      Self::Title | Self::TrackArtists | Self::Album | Self::AlbumArtists => true,
+      Self::Play | Self::TrackNumber => false,
+    }
+  }
+
+  /// Gets the text of `track` in this column, or an empty string if this column does not contain text.
+  fn text(self, track: &TrackViewModel) -> &str {
+    match self {
+      Self::Title => &track.title,
+      Self::TrackArtists => track.track_artists.as_deref().unwrap_or(""),
+      Self::Album => track.album.as_deref().unwrap_or(""),
+      Self::AlbumArtists => track.album_artists.as_deref().unwrap_or(""),
+      Self::Play | Self::TrackNumber => "",
+    }
+  }
+
+  fn compare(self, a: &TrackViewModel, b: &TrackViewModel) -> Ordering {
+    match self {
+      Self::Play => a.playable.cmp(&b.playable),
+      Self::TrackNumber => a.track_number.cmp(&b.track_number),
+      Self::Title => a.title.cmp(&b.title),
+      Self::TrackArtists => a.track_artists.cmp(&b.track_artists),
+      Self::Album => a.album.cmp(&b.album),
+      Self::AlbumArtists => a.album_artists.cmp(&b.album_artists),
+    }.then(a.id.cmp(&b.id))
+  }
+
+  fn cell<'a, P: Player>(self, theme: Theme, play_label: String) -> Box<dyn 'a + Fn(&mut TrackViewModel) -> Element<'_, Message<P>>> {
+    let cell = self.unselected_cell(theme, play_label);
+    Box::new(move |t| {
+      if t.selected {
+        Container::new(cell(t)).width(Length::Fill).height(Length::Fill).style(theme.selected_row()).into()
+      } else {
+        cell(t)
+      }
+    })
+  }
+
+  fn unselected_cell<'a, P: Player>(self, theme: Theme, play_label: String) -> Box<dyn 'a + Fn(&mut TrackViewModel) -> Element<'_, Message<P>>> {
+    match self {
+      Self::Play => Box::new(move |t| {
+        play_button(theme, &mut t.play_button_state, play_label.clone(), t.id, t.playable)
+      }),
+      Self::TrackNumber => Box::new(|t|
+        if let Some(track_number) = t.track_number { cell_text(track_number.to_string()) } else { empty() }
+      ),
+      Self::Title => Box::new(|t|
+        cell_text(t.title.clone())
+      ),
+      Self::TrackArtists => Box::new(|t|
+        if let Some(track_artists) = &t.track_artists { cell_text(track_artists.clone()) } else { empty() }
+      ),
+      Self::Album => Box::new(|t|
+        if let Some(album) = &t.album { cell_text(album.clone()) } else { empty() }
+      ),
+      Self::AlbumArtists => Box::new(|t|
+        if let Some(album_artists) = &t.album_artists { cell_text(album_artists.clone()) } else { empty() }
+      ),
+    }
+  }
+}
+
+/// Width and visibility of a column of the track table.
+#[derive(Copy, Clone, Debug)]
+struct ColumnSetting {
+  column: TrackColumn,
+  fill_portion: u32,
+  visible: bool,
+}
+
+impl ColumnSetting {
+  fn new(column: TrackColumn) -> Self {
+    Self { column, fill_portion: column.default_fill_portion(), visible: true }
+  }
+
+  /// Converts `settings` into a preference value, such as `title:250,album:250:hidden`.
+  fn to_preference(settings: &[ColumnSetting]) -> String {
+    settings.iter().map(|s| {
+      if s.visible {
+        format!("{}:{}", s.column.key(), s.fill_portion)
+      } else {
+        format!("{}:{}:hidden", s.column.key(), s.fill_portion)
+      }
+    }).join(",")
+  }
+
+  /// Applies the settings in preference `value` to `settings`, ignoring unknown columns and malformed settings.
+  fn apply_preference(settings: &mut [ColumnSetting], value: &str) {
+    for entry in value.split(',') {
+      let mut parts = entry.split(':');
+      let (key, fill_portion, hidden) = (parts.next(), parts.next().and_then(|p| p.parse::<u32>().ok()), parts.next() == Some("hidden"));
+      if let (Some(setting), Some(fill_portion)) = (settings.iter_mut().find(|s| Some(s.column.key()) == key), fill_portion) {
+        setting.fill_portion = fill_portion.max(1);
+        // The play column cannot be hidden.
+        setting.visible = !hidden || setting.column == TrackColumn::Play;
+      }
+    }
+  }
+}
+
+// Context menu
+
+/// Context menu with actions on the selected tracks.
+#[derive(Default, Debug)]
+struct TrackMenu {
+  enqueue_button_state: button::State,
+  enqueue_next_button_state: button::State,
+  play_album_shuffled_button_state: button::State,
+  play_artist_button_state: button::State,
+  show_album_button_state: button::State,
+  inspect_button_state: button::State,
+  reveal_button_state: button::State,
+  hide_track_button_state: button::State,
+  hide_album_button_state: button::State,
+  hide_artist_button_state: button::State,
+  /// Playlists to add the selected tracks to, as their ID, name, and button state.
+  playlists: Vec<(i32, String, button::State)>,
+  rating_button_states: [button::State; RATING_STARS as usize],
+  tag: String,
+  tag_input_state: text_input::State,
+  tag_button_state: button::State,
+  /// Whether to hide the actions that modify data: hiding, adding to playlists, rating, and tagging.
+  read_only: bool,
+}
+
+impl TrackMenu {
+  fn new(playlists: &[Playlist], read_only: bool) -> Self {
+    let playlists = if read_only { &[][..] } else { playlists };
+    Self {
+      playlists: playlists.iter().map(|p| (p.id, p.name.clone(), button::State::default())).collect(),
+      read_only,
+      ..Self::default()
+    }
+  }
+
+  fn view<P: Player>(&mut self, localizer: &Localizer, theme: Theme, num_selected: usize) -> Element<'_, Message<P>> {
+    let mut selected_args = FluentArgs::new();
+    selected_args.set("count", num_selected);
+    let mut column = Column::new()
+      .width(Length::Units(250))
+      .spacing(2)
+      .push(txt(localizer.text_with("tracks-selected", &selected_args)))
+      .push(horizontal_line(theme))
+      .push(menu_button(theme, &mut self.enqueue_next_button_state, localizer.text("track-menu-play-next"), || Message::EnqueueSelectionNext))
+      .push(menu_button(theme, &mut self.enqueue_button_state, localizer.text("track-menu-add-to-queue"), || Message::EnqueueSelection))
+      .push(menu_button(theme, &mut self.play_album_shuffled_button_state, localizer.text("track-menu-play-album-shuffled"), || Message::PlaySelectionAlbumShuffled))
+      .push(menu_button(theme, &mut self.play_artist_button_state, localizer.text("track-menu-play-artist"), || Message::PlaySelectionArtist))
+      .push(menu_button(theme, &mut self.show_album_button_state, localizer.text("track-menu-show-album"), || Message::ShowSelectionAlbum));
+    if !self.read_only {
+      column = column
+        .push(menu_button(theme, &mut self.inspect_button_state, localizer.text("track-menu-inspect"), || Message::InspectSelection))
+        .push(menu_button(theme, &mut self.reveal_button_state, localizer.text("track-menu-reveal"), || Message::RevealSelection))
+        .push(menu_button(theme, &mut self.hide_track_button_state, localizer.text("track-menu-hide-track"), || Message::HideSelection(HiddenItemKind::Track)))
+        .push(menu_button(theme, &mut self.hide_album_button_state, localizer.text("track-menu-hide-album"), || Message::HideSelection(HiddenItemKind::Album)))
+        .push(menu_button(theme, &mut self.hide_artist_button_state, localizer.text("track-menu-hide-artist"), || Message::HideSelection(HiddenItemKind::Artist)));
+    }
+    if !self.playlists.is_empty() {
+      column = column.push(txt(localizer.text("track-menu-add-to-playlist")));
+      for (playlist_id, name, state) in &mut self.playlists {
+        let playlist_id = *playlist_id;
+        column = column.push(menu_button(theme, state, name.clone(), move || Message::AddSelectionToPlaylist(playlist_id)));
+      }
+    }
+    if self.read_only {
+      return Container::new(column)
+        .padding(4)
+        .style(theme.menu())
+        .into();
+    }
+    let rating_row = self.rating_button_states.iter_mut().enumerate()
+      .fold(Row::new().spacing(2).align_items(Align::Center).push(txt(localizer.text("track-menu-rate"))), |row, (i, state)| {
+        let stars = i as i32 + 1;
+        let rating = stars * MAX_RATING / RATING_STARS;
+        row.push(cell_button(theme, state, "★".repeat(stars as usize), true, move || Message::RateSelection(rating)))
+      });
+    // Text inputs require a `Clone` message, so use an `Option<String>` message that is `None` when submitting.
+    let tag_input: Element<_> = TextInput::new(&mut self.tag_input_state, &localizer.text("track-menu-tag-placeholder"), &self.tag, |tag| Some(tag))
+      .on_submit(None)
+      .size(16)
+      .padding(2)
+      .style(theme)
+      .into();
+    let tag_row = Row::new()
+      .spacing(2)
+      .align_items(Align::Center)
+      .push(tag_input.map(|tag| match tag {
+        Some(tag) => Message::SetSelectionTag(tag),
+        None => Message::TagSelection,
+      }))
+      .push(cell_button(theme, &mut self.tag_button_state, localizer.text("track-menu-tag"), !self.tag.trim().is_empty(), || Message::TagSelection));
+    column = column
+      .push(horizontal_line(theme))
+      .push(rating_row)
+      .push(tag_row);
+    Container::new(column)
+      .padding(4)
+      .style(theme.menu())
+      .into()
+  }
+}
+
+// Inspector
+
+#[derive(Debug)]
+struct Inspector {
+  technical_info: TrackTechnicalInfo,
+  scrollable_state: scrollable::State,
+  close_button_state: button::State,
+  /// Buttons that reveal each local file in the file manager.
+  reveal_button_states: Vec<button::State>,
+  /// Artists credited in other roles than the main artist, along with their role.
+  credits: Vec<(TrackArtistRole, String)>,
+  /// Other versions of the track, along with the state of the button that plays them.
+  versions: Vec<(TrackVersion, button::State)>,
+}
+
+impl Inspector {
+  fn new(technical_info: TrackTechnicalInfo, credits: Vec<(TrackArtistRole, String)>) -> Self {
+    let reveal_button_states = technical_info.local_files.iter().map(|_| button::State::default()).collect();
+    Self { technical_info, scrollable_state: Default::default(), close_button_state: Default::default(), reveal_button_states, credits, versions: Vec::new() }
+  }
+
+  fn set_versions(&mut self, versions: Vec<TrackVersion>) {
+    self.versions = versions.into_iter().map(|v| (v, button::State::default())).collect();
+  }
+
+  fn view<P: Player>(&mut self, localizer: &Localizer, theme: Theme) -> Element<'_, Message<P>> {
+    let track = &self.technical_info.track;
+    let header = Row::new()
+      .width(Length::Fill)
+      .spacing(8)
+      .align_items(Align::Center)
+      .push(h1(localizer.text("track-inspector-title")))
+      .push(Text::new(format!("{} (#{})", track.title, track.id)).width(Length::Fill))
+      .push(Button::new(&mut self.close_button_state, Text::new(localizer.text("track-inspector-close"))).style(theme).on_press_into(|| Message::CloseInspector, true));
+    let mut details = Column::new().width(Length::Fill);
+    if self.technical_info.paths_hidden {
+      details = details.push(txt(localizer.text("track-inspector-paths-hidden")));
+    }
+    for (local_file, reveal_button_state) in self.technical_info.local_files.iter().zip(&mut self.reveal_button_states) {
+      let local_track = &local_file.local_track;
+      let path = local_file.path.clone();
+      let path_row = Row::new()
+        .spacing(8)
+        .align_items(Align::Center)
+        .push(txt(path.clone().unwrap_or_else(|| localizer.text("track-inspector-no-file"))))
+        .push(cell_button(theme, reveal_button_state, localizer.text("track-inspector-reveal"), path.is_some(), move || Message::RevealFile(path.clone().unwrap_or_default())));
+      details = details.push(path_row);
+      let mut lines = vec![format!("  local source: {}, hash: {:08x}, bitrate: {:?} kbit/s", local_track.local_source_id, local_track.hash, local_track.bitrate)];
+      if let Some(file) = &local_file.file {
+        lines.push(format!("  size: {} bytes, codec: {:?}, sample rate: {:?} Hz, channels: {:?}, duration: {:?} s", file.size, file.codec, file.sample_rate, file.channels, file.duration));
+        lines.extend(file.tags.iter().map(|tag| format!("  {}: {}", tag.id, tag.value)));
+      }
+      if let Some(error) = &local_file.error {
+        lines.push(format!("  error: {}", error));
+      }
+      details = lines.into_iter().fold(details, |column, line| column.push(txt(line)));
+    }
+    details = self.technical_info.spotify_tracks.iter().fold(details, |column, t| column.push(txt(format!("Spotify: {}", t.spotify_id))));
+    if !self.credits.is_empty() {
+      details = details.push(txt(localizer.text("track-inspector-credits")));
+      for (role, names) in &self.credits.iter().group_by(|(role, _)| *role) {
+        let names = names.map(|(_, name)| name.as_str()).join(", ");
+        details = details.push(txt(format!("  {}: {}", localizer.text(artist_role_label_id(role)), names)));
+      }
+    }
+    if !self.versions.is_empty() {
+      details = details.push(txt(localizer.text("track-inspector-versions")));
+      let play_label = localizer.text("track-play");
+      for (version, play_button_state) in &mut self.versions {
+        let version_track_id = version.track.id;
+        let kind_label = if version.relation.original_track_id == version_track_id {
+          localizer.text("track-version-original")
+        } else {
+          localizer.text(version_kind_label_id(version.relation.kind))
+        };
+        let version_row = Row::new()
+          .spacing(8)
+          .align_items(Align::Center)
+          .push(cell_button(theme, play_button_state, play_label.clone(), true, move || Message::PlayVersion(version_track_id)))
+          .push(txt(format!("{} ({})", version.track.title, kind_label)));
+        details = details.push(version_row);
+      }
+    }
+    Column::new()
+      .width(Length::Fill)
+      .height(Length::Units(200))
+      .spacing(4)
+      .push(header)
+      .push(Scrollable::new(&mut self.scrollable_state)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .push(details)
+      )
+      .into()
+  }
 }
 
 // View model
 
+#[derive(Debug)]
+pub struct TracksPageViewModel {
+  offset: i64,
+  tracks: Vec<TrackViewModel>,
+  next_page: Option<PageQuery>,
+}
+
 #[derive(Default, Debug)]
 pub struct TrackViewModel {
   id: i32,
+  playable: bool,
   play_button_state: button::State,
-  track_number: Option<String>,
+  selected: bool,
+  track_number: Option<i32>,
   title: String,
   track_artists: Option<String>,
+  album_id: i32,
   album: Option<String>,
   album_artists: Option<String>,
+  /// First artist of the track, for playing all tracks of that artist.
+  artist_id: Option<i32>,
+  /// Artists credited in other roles than the main artist, along with their role, ordered by role.
+  credits: Vec<(TrackArtistRole, String)>,
+}
+
+impl TrackViewModel {
+  fn playing_track(&self) -> PlayingTrack {
+    PlayingTrack {
+      id: self.id,
+      title: self.title.clone(),
+      track_artists: self.track_artists.clone(),
+      album_id: self.album_id,
+      album: self.album.clone(),
+    }
+  }
 }
 
 impl<'a> From<TrackInfo<'a>> for TrackViewModel {
@@ -168,18 +1101,69 @@ impl<'a> From<TrackInfo<'a>> for TrackViewModel {
     let album_artists = if album_artists.is_empty() { None } else { Some(album_artists) };
     Self {
       id: track_info.track.id,
-      track_number: track_info.track.track_number.map(|tn| tn.to_string()),
+      playable: track_info.availability().is_playable(),
+      track_number: track_info.track.track_number,
       title: track_info.track.title.clone(),
       track_artists,
+      album_id: track_info.track.album_id,
       album: track_info.album().map(|a| a.name.clone()),
       album_artists,
+      artist_id: track_info.track_artists().next().map(|a| a.id),
+      credits: track_info.track_credits().map(|(role, a)| (role, a.name.clone())).sorted_by_key(|(role, _)| *role).collect(),
       ..Self::default()
     }
   }
 }
 
+/// Gets the ID of the label of artists credited in `role`.
+fn artist_role_label_id(role: TrackArtistRole) -> &'static str {
+  match role {
+    TrackArtistRole::Main => "track-artist-role-main",
+    TrackArtistRole::Featured => "track-artist-role-featured",
+    TrackArtistRole::Remixer => "track-artist-role-remixer",
+    TrackArtistRole::Composer => "track-artist-role-composer",
+    TrackArtistRole::Conductor => "track-artist-role-conductor",
+    TrackArtistRole::Performer => "track-artist-role-performer",
+  }
+}
+
+/// Gets the ID of the label of versions of `kind`.
+fn version_kind_label_id(kind: TrackRelationKind) -> &'static str {
+  match kind {
+    TrackRelationKind::Remaster => "track-version-remaster",
+    TrackRelationKind::Live => "track-version-live",
+    TrackRelationKind::Remix => "track-version-remix",
+    TrackRelationKind::Other => "track-version-other",
+  }
+}
+
+/// Reveals the file at `path` in the file manager, logging an error if that fails.
+fn reveal_file(path: &str) {
+  if let Err(e) = reveal_in_file_manager(path) {
+    error!("Failed to reveal track file: {:?}", FormatError::new(&e));
+  }
+}
+
+/// Converts `tracks` into the playable tracks among them, keeping their order.
+pub fn playable_tracks(tracks: TracksRaw) -> Vec<PlayingTrack> {
+  let tracks: Tracks = tracks.into();
+  tracks.iter()
+    .map(|ti| TrackViewModel::from(ti))
+    .filter(|t| t.playable)
+    .map(|t| t.playing_track())
+    .collect()
+}
+
 // Widget functions
 
-fn play_button<'a, P: Player>(state: &'a mut button::State, track_id: i32) -> Element<'a, Message<P>> {
-  cell_button(state, "Play", true, move || Message::RequestPlayTrack(track_id))
+fn play_button<'a, P: Player>(theme: Theme, state: &'a mut button::State, label: String, track_id: i32, playable: bool) -> Element<'a, Message<P>> {
+  cell_button(theme, state, label, playable, move || Message::RequestPlayTrack(track_id))
+}
+
+fn menu_button<'a, P: Player>(theme: Theme, state: &'a mut button::State, label: String, message_fn: impl 'static + Fn() -> Message<P>) -> Element<'a, Message<P>> {
+  Button::new(state, txt(label))
+    .width(Length::Fill)
+    .padding(2)
+    .style(theme)
+    .on_press_into(message_fn, true)
 }
@@ -0,0 +1,42 @@
+use std::hash::{Hash, Hasher};
+
+use iced::futures::{self, stream::BoxStream};
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use musium_player::remote::{RemoteCommand, RemoteControlServer};
+
+/// Subscription to the commands received by the local control socket of `server`.
+pub fn subscription(server: &RemoteControlServer) -> Subscription<RemoteCommand> {
+  Subscription::from_recipe(RemoteCommandSubscription { commands: server.subscribe() })
+}
+
+struct RemoteCommandSubscription {
+  commands: broadcast::Receiver<RemoteCommand>,
+}
+
+impl<H, I> Recipe<H, I> for RemoteCommandSubscription where
+  H: Hasher
+{
+  type Output = RemoteCommand;
+
+  fn hash(&self, state: &mut H) {
+    // Only one local control socket is listened on, so hash just the marker struct.
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+  }
+
+  fn stream(self: Box<Self>, _input: BoxStream<I>) -> BoxStream<Self::Output> {
+    Box::pin(futures::stream::unfold(self.commands, |mut receiver| async move {
+      loop {
+        match receiver.recv().await {
+          Ok(command) => return Some((command, receiver)),
+          Err(broadcast::error::RecvError::Lagged(skipped)) => warn!("Skipped {} remote commands", skipped),
+          Err(broadcast::error::RecvError::Closed) => return None,
+        }
+      }
+    }))
+  }
+}
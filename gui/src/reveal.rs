@@ -0,0 +1,58 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RevealError {
+  #[error("File '{0}' does not exist on this machine")]
+  NotFoundFail(PathBuf),
+  #[error("Failed to start the file manager")]
+  StartFileManagerFail(#[from] io::Error),
+}
+
+/// Reveals the file at `path` in the file manager of the operating system, selecting the file where the file manager
+/// supports it, or opening its containing folder otherwise. Paths come from the server, so this only works when the
+/// server runs on this machine or shares its file system.
+pub fn reveal_in_file_manager(path: impl AsRef<Path>) -> Result<(), RevealError> {
+  let path = path.as_ref();
+  if !path.exists() {
+    return Err(RevealError::NotFoundFail(path.to_path_buf()));
+  }
+  reveal(path)?;
+  Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> io::Result<()> {
+  // Explorer requires the path to be part of the `/select,` argument.
+  let mut argument = std::ffi::OsString::from("/select,");
+  argument.push(path);
+  Command::new("explorer").arg(argument).spawn()?;
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> io::Result<()> {
+  Command::new("open").arg("-R").arg(path).spawn()?;
+  Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn reveal(path: &Path) -> io::Result<()> {
+  // Ask the file manager to select the file through the FreeDesktop file manager D-Bus interface, falling back to
+  // opening the containing folder when no file manager implements that interface.
+  let uri = format!("file://{}", path.display());
+  let status = Command::new("dbus-send")
+    .args(&["--session", "--print-reply", "--dest=org.freedesktop.FileManager1", "/org/freedesktop/FileManager1", "org.freedesktop.FileManager1.ShowItems"])
+    .arg(format!("array:string:{}", uri))
+    .arg("string:")
+    .status();
+  if let Ok(status) = status {
+    if status.success() { return Ok(()); }
+  }
+  let folder = path.parent().unwrap_or(path);
+  Command::new("xdg-open").arg(folder).spawn()?;
+  Ok(())
+}
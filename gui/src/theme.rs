@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use iced::{Background, Color, button, checkbox, container, rule, slider, text_input};
+use thiserror::Error;
+
+/// Colors that are applied across all widgets of the GUI.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Palette {
+  /// Color of the window background.
+  pub background: Color,
+  /// Color of the background of widgets that are drawn on top of the window background, such as buttons and inputs.
+  pub surface: Color,
+  /// Color of text.
+  pub text: Color,
+  /// Color of less important text, such as placeholders and text of disabled widgets.
+  pub text_muted: Color,
+  /// Color that highlights active and focused widgets.
+  pub accent: Color,
+  /// Color of text drawn on top of the accent color.
+  pub text_on_accent: Color,
+  /// Color of lines and borders.
+  pub divider: Color,
+}
+
+/// Built-in theme, from which the palette of a theme is derived.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ThemeVariant {
+  Light,
+  Dark,
+}
+
+impl Default for ThemeVariant {
+  fn default() -> Self { Self::Light }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown theme '{0}', expected 'light' or 'dark'")]
+pub struct ParseThemeVariantError(String);
+
+impl FromStr for ThemeVariant {
+  type Err = ParseThemeVariantError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "light" => Ok(Self::Light),
+      "dark" => Ok(Self::Dark),
+      _ => Err(ParseThemeVariantError(s.to_string())),
+    }
+  }
+}
+
+/// Theme of the GUI, implementing the style sheets of the widgets that are used. Pass a theme to the `style` method of
+/// a widget to style it with the palette of the theme.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Theme {
+  pub variant: ThemeVariant,
+  pub palette: Palette,
+}
+
+impl Theme {
+  pub fn new(variant: ThemeVariant) -> Self {
+    let palette = match variant {
+      ThemeVariant::Light => Palette {
+        background: Color::WHITE,
+        surface: Color::from_rgb(0.93, 0.93, 0.94),
+        text: Color::from_rgb(0.1, 0.1, 0.1),
+        text_muted: Color::from_rgb(0.5, 0.5, 0.5),
+        accent: Color::from_rgb(0.2, 0.45, 0.85),
+        text_on_accent: Color::WHITE,
+        divider: Color::from_rgba(0.6, 0.6, 0.6, 0.6),
+      },
+      ThemeVariant::Dark => Palette {
+        background: Color::from_rgb(0.12, 0.12, 0.13),
+        surface: Color::from_rgb(0.2, 0.2, 0.22),
+        text: Color::from_rgb(0.9, 0.9, 0.9),
+        text_muted: Color::from_rgb(0.55, 0.55, 0.55),
+        accent: Color::from_rgb(0.4, 0.62, 1.0),
+        text_on_accent: Color::from_rgb(0.05, 0.05, 0.05),
+        divider: Color::from_rgba(0.45, 0.45, 0.45, 0.6),
+      },
+    };
+    Self { variant, palette }
+  }
+
+  pub fn light() -> Self { Self::new(ThemeVariant::Light) }
+
+  pub fn dark() -> Self { Self::new(ThemeVariant::Dark) }
+
+  /// Returns this theme with its accent color replaced by `accent`.
+  pub fn with_accent(mut self, accent: Color) -> Self {
+    self.palette.accent = accent;
+    // Pick black or white text on the accent color, whichever contrasts most with it.
+    let luminance = 0.2126 * accent.r + 0.7152 * accent.g + 0.0722 * accent.b;
+    self.palette.text_on_accent = if luminance > 0.5 { Color::BLACK } else { Color::WHITE };
+    self
+  }
+
+  /// Returns a style sheet for buttons that stand out by using the accent color, for the primary action of a page.
+  pub fn accent_button(self) -> AccentButton { AccentButton(self) }
+
+  /// Returns a style sheet for containers that float above other widgets, such as context menus.
+  pub fn menu(self) -> Menu { Menu(self) }
+
+  /// Returns a style sheet for containers that highlight selected rows of a table.
+  pub fn selected_row(self) -> SelectedRow { SelectedRow(self) }
+}
+
+impl Default for Theme {
+  fn default() -> Self { Self::new(ThemeVariant::default()) }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid color '{0}', expected a hexadecimal RGB color such as '#3273dc'")]
+pub struct ParseColorError(String);
+
+/// Parses a hexadecimal RGB color such as `#3273dc`, with an optional leading `#`.
+pub fn parse_color(s: &str) -> Result<Color, ParseColorError> {
+  let hex = s.strip_prefix('#').unwrap_or(s);
+  if hex.len() != 6 || !hex.is_ascii() {
+    return Err(ParseColorError(s.to_string()));
+  }
+  let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ParseColorError(s.to_string()));
+  Ok(Color::from_rgb8(component(0)?, component(2)?, component(4)?))
+}
+
+fn mix(a: Color, b: Color, factor: f32) -> Color {
+  Color {
+    r: a.r + (b.r - a.r) * factor,
+    g: a.g + (b.g - a.g) * factor,
+    b: a.b + (b.b - a.b) * factor,
+    a: a.a + (b.a - a.a) * factor,
+  }
+}
+
+// Style sheets
+
+impl container::StyleSheet for Theme {
+  fn style(&self) -> container::Style {
+    container::Style {
+      text_color: Some(self.palette.text),
+      background: Some(Background::Color(self.palette.background)),
+      ..container::Style::default()
+    }
+  }
+}
+
+/// Container style sheet for widgets that float above other widgets.
+#[derive(Copy, Clone, Debug)]
+pub struct Menu(Theme);
+
+impl container::StyleSheet for Menu {
+  fn style(&self) -> container::Style {
+    let palette = &self.0.palette;
+    container::Style {
+      text_color: Some(palette.text),
+      background: Some(Background::Color(palette.background)),
+      border_radius: 2.0,
+      border_width: 1.0,
+      border_color: palette.divider,
+    }
+  }
+}
+
+/// Container style sheet for selected rows of a table.
+#[derive(Copy, Clone, Debug)]
+pub struct SelectedRow(Theme);
+
+impl container::StyleSheet for SelectedRow {
+  fn style(&self) -> container::Style {
+    let palette = &self.0.palette;
+    container::Style {
+      text_color: Some(palette.text),
+      background: Some(Background::Color(mix(palette.background, palette.accent, 0.3))),
+      ..container::Style::default()
+    }
+  }
+}
+
+impl button::StyleSheet for Theme {
+  fn active(&self) -> button::Style {
+    button::Style {
+      background: Some(Background::Color(self.palette.surface)),
+      border_radius: 2.0,
+      border_width: 1.0,
+      border_color: self.palette.divider,
+      text_color: self.palette.text,
+      ..button::Style::default()
+    }
+  }
+
+  fn hovered(&self) -> button::Style {
+    button::Style {
+      background: Some(Background::Color(mix(self.palette.surface, self.palette.accent, 0.25))),
+      border_color: self.palette.accent,
+      ..button::StyleSheet::active(self)
+    }
+  }
+
+  fn pressed(&self) -> button::Style {
+    button::Style {
+      background: Some(Background::Color(mix(self.palette.surface, self.palette.accent, 0.5))),
+      ..button::StyleSheet::hovered(self)
+    }
+  }
+
+  fn disabled(&self) -> button::Style {
+    button::Style {
+      text_color: self.palette.text_muted,
+      ..button::StyleSheet::active(self)
+    }
+  }
+}
+
+/// Button style sheet that uses the accent color of a theme.
+#[derive(Copy, Clone, Debug)]
+pub struct AccentButton(Theme);
+
+impl button::StyleSheet for AccentButton {
+  fn active(&self) -> button::Style {
+    let palette = &self.0.palette;
+    button::Style {
+      background: Some(Background::Color(palette.accent)),
+      border_radius: 2.0,
+      border_width: 1.0,
+      border_color: palette.accent,
+      text_color: palette.text_on_accent,
+      ..button::Style::default()
+    }
+  }
+
+  fn hovered(&self) -> button::Style {
+    let palette = &self.0.palette;
+    button::Style {
+      background: Some(Background::Color(mix(palette.accent, palette.text, 0.15))),
+      ..self.active()
+    }
+  }
+
+  fn disabled(&self) -> button::Style {
+    let palette = &self.0.palette;
+    button::Style {
+      background: Some(Background::Color(mix(palette.accent, palette.background, 0.5))),
+      ..self.active()
+    }
+  }
+}
+
+impl text_input::StyleSheet for Theme {
+  fn active(&self) -> text_input::Style {
+    text_input::Style {
+      background: Background::Color(self.palette.surface),
+      border_radius: 2.0,
+      border_width: 1.0,
+      border_color: self.palette.divider,
+    }
+  }
+
+  fn focused(&self) -> text_input::Style {
+    text_input::Style {
+      border_color: self.palette.accent,
+      ..text_input::StyleSheet::active(self)
+    }
+  }
+
+  fn placeholder_color(&self) -> Color { self.palette.text_muted }
+
+  fn value_color(&self) -> Color { self.palette.text }
+
+  fn selection_color(&self) -> Color { Color { a: 0.4, ..self.palette.accent } }
+}
+
+impl checkbox::StyleSheet for Theme {
+  fn active(&self, _is_checked: bool) -> checkbox::Style {
+    checkbox::Style {
+      background: Background::Color(self.palette.surface),
+      checkmark_color: self.palette.accent,
+      border_radius: 2.0,
+      border_width: 1.0,
+      border_color: self.palette.divider,
+    }
+  }
+
+  fn hovered(&self, is_checked: bool) -> checkbox::Style {
+    checkbox::Style {
+      border_color: self.palette.accent,
+      ..checkbox::StyleSheet::active(self, is_checked)
+    }
+  }
+}
+
+impl slider::StyleSheet for Theme {
+  fn active(&self) -> slider::Style {
+    slider::Style {
+      rail_colors: (self.palette.accent, self.palette.divider),
+      handle: slider::Handle {
+        shape: slider::HandleShape::Circle { radius: 7.0 },
+        color: self.palette.surface,
+        border_width: 1.0,
+        border_color: self.palette.divider,
+      },
+    }
+  }
+
+  fn hovered(&self) -> slider::Style {
+    let style = slider::StyleSheet::active(self);
+    slider::Style {
+      handle: slider::Handle { border_color: self.palette.accent, ..style.handle },
+      ..style
+    }
+  }
+
+  fn dragging(&self) -> slider::Style {
+    let style = slider::StyleSheet::hovered(self);
+    slider::Style {
+      handle: slider::Handle { color: self.palette.accent, ..style.handle },
+      ..style
+    }
+  }
+}
+
+impl rule::StyleSheet for Theme {
+  fn style(&self) -> rule::Style {
+    rule::Style {
+      color: self.palette.divider,
+      width: 1,
+      radius: 0.0,
+      fill_mode: rule::FillMode::Full,
+    }
+  }
+}
@@ -0,0 +1,182 @@
+use std::hash::{Hash, Hasher};
+
+use iced::futures::{self, stream::BoxStream};
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Action that is chosen from the menu of the tray icon.
+#[derive(Copy, Clone, Debug)]
+pub enum TrayAction {
+  TogglePlay,
+  NextTrack,
+  Quit,
+}
+
+/// Localized labels of the tray icon.
+#[derive(Clone, Default, Debug, Eq, PartialEq)]
+pub struct TrayLabels {
+  pub title: String,
+  pub toggle_play: String,
+  pub next_track: String,
+  pub quit: String,
+}
+
+/// Playback status that is shown by the tray icon.
+#[derive(Clone, Default, Debug, Eq, PartialEq)]
+pub struct TrayStatus {
+  /// Description of the track that is playing, or `None` if playback is stopped.
+  pub playing_track: Option<String>,
+  pub can_play_next: bool,
+}
+
+/// Tray icon with a tooltip showing the playing track, and a menu with playback controls. Actions chosen from the menu
+/// are received through `subscription`.
+pub struct Tray {
+  actions: broadcast::Sender<TrayAction>,
+  status: TrayStatus,
+  labels: TrayLabels,
+  #[cfg(target_os = "linux")]
+  handle: ksni::Handle<platform::TrayIcon>,
+}
+
+impl Tray {
+  /// Spawns a tray icon, returning `None` if tray icons are not supported on this platform.
+  pub fn spawn(labels: TrayLabels) -> Option<Self> {
+    let (actions, _) = broadcast::channel(16);
+    Self::spawn_platform(actions, labels)
+  }
+
+  #[cfg(target_os = "linux")]
+  fn spawn_platform(actions: broadcast::Sender<TrayAction>, labels: TrayLabels) -> Option<Self> {
+    let status = TrayStatus::default();
+    let tray_icon = platform::TrayIcon { actions: actions.clone(), labels: labels.clone(), status: status.clone() };
+    let service = ksni::TrayService::new(tray_icon);
+    let handle = service.handle();
+    service.spawn();
+    tracing::debug!("Spawned tray icon");
+    Some(Self { actions, status, labels, handle })
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn spawn_platform(_actions: broadcast::Sender<TrayAction>, _labels: TrayLabels) -> Option<Self> {
+    warn!("Tray icons are not supported on this platform; not showing a tray icon");
+    None
+  }
+
+  /// Shows `status` in the tray icon, if it differs from the shown status.
+  pub fn set_status(&mut self, status: TrayStatus) {
+    if self.status == status { return; }
+    self.status = status;
+    #[cfg(target_os = "linux")] {
+      let status = self.status.clone();
+      self.handle.update(move |tray_icon| tray_icon.status = status);
+    }
+  }
+
+  /// Shows `labels` in the tray icon, if they differ from the shown labels.
+  pub fn set_labels(&mut self, labels: TrayLabels) {
+    if self.labels == labels { return; }
+    self.labels = labels;
+    #[cfg(target_os = "linux")] {
+      let labels = self.labels.clone();
+      self.handle.update(move |tray_icon| tray_icon.labels = labels);
+    }
+  }
+
+  pub fn subscription(&self) -> Subscription<TrayAction> {
+    Subscription::from_recipe(TrayActionSubscription { actions: self.actions.clone() })
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+  use ksni::menu::StandardItem;
+  use ksni::{MenuItem, ToolTip};
+  use tokio::sync::broadcast;
+
+  use super::{TrayAction, TrayLabels, TrayStatus};
+
+  /// Tray icon implemented as a StatusNotifierItem, which is shown by most Linux desktop environments.
+  pub struct TrayIcon {
+    pub actions: broadcast::Sender<TrayAction>,
+    pub labels: TrayLabels,
+    pub status: TrayStatus,
+  }
+
+  impl TrayIcon {
+    fn send(&self, action: TrayAction) {
+      self.actions.send(action).ok(); // OK: no receivers when the application is not subscribed -> ignore action.
+    }
+  }
+
+  impl ksni::Tray for TrayIcon {
+    fn id(&self) -> String { "musium".to_string() }
+
+    fn title(&self) -> String { self.labels.title.clone() }
+
+    fn icon_name(&self) -> String { "audio-x-generic".to_string() }
+
+    fn tool_tip(&self) -> ToolTip {
+      ToolTip {
+        title: self.labels.title.clone(),
+        description: self.status.playing_track.clone().unwrap_or_default(),
+        ..ToolTip::default()
+      }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+      vec![
+        StandardItem {
+          label: self.labels.toggle_play.clone(),
+          enabled: self.status.playing_track.is_some(),
+          activate: Box::new(|tray_icon: &mut Self| tray_icon.send(TrayAction::TogglePlay)),
+          ..StandardItem::default()
+        }.into(),
+        StandardItem {
+          label: self.labels.next_track.clone(),
+          enabled: self.status.can_play_next,
+          activate: Box::new(|tray_icon: &mut Self| tray_icon.send(TrayAction::NextTrack)),
+          ..StandardItem::default()
+        }.into(),
+        MenuItem::Separator,
+        StandardItem {
+          label: self.labels.quit.clone(),
+          activate: Box::new(|tray_icon: &mut Self| tray_icon.send(TrayAction::Quit)),
+          ..StandardItem::default()
+        }.into(),
+      ]
+    }
+  }
+}
+
+// Tray action subscription
+
+struct TrayActionSubscription {
+  actions: broadcast::Sender<TrayAction>,
+}
+
+impl<H, I> Recipe<H, I> for TrayActionSubscription where
+  H: Hasher
+{
+  type Output = TrayAction;
+
+  fn hash(&self, state: &mut H) {
+    // Only one tray icon exists, so hash just the marker struct.
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+  }
+
+  fn stream(self: Box<Self>, _input: BoxStream<I>) -> BoxStream<Self::Output> {
+    Box::pin(futures::stream::unfold(self.actions.subscribe(), |mut receiver| async move {
+      loop {
+        match receiver.recv().await {
+          Ok(action) => return Some((action, receiver)),
+          Err(broadcast::error::RecvError::Lagged(skipped)) => warn!("Skipped {} tray actions", skipped),
+          Err(broadcast::error::RecvError::Closed) => return None,
+        }
+      }
+    }))
+  }
+}
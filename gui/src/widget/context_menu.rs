@@ -0,0 +1,166 @@
+use std::hash::Hash;
+
+use iced_native::{
+  Clipboard, Element, Event, Hasher, keyboard, Layout, Length, mouse, overlay, Point, Rectangle, Renderer, Size, Widget,
+};
+use iced_native::event::Status;
+use iced_native::layout::{Limits, Node};
+
+//
+// Context menu widget
+//
+
+/// Wraps `content`, showing `menu` on top of it at the position where `content` was last right-clicked. The owner of the
+/// context menu decides when the menu is open by passing `Some` menu, and closes it when it receives the message created
+/// by `on_close`, which is sent when pressing outside of the menu or pressing escape.
+pub struct ContextMenu<'a, M, R> {
+  state: &'a mut State,
+  content: Element<'a, M, R>,
+  menu: Option<Element<'a, M, R>>,
+  on_close: Box<dyn 'a + Fn() -> M>,
+}
+
+/// State of a context menu, which must be kept by the owner of the context menu between views.
+#[derive(Default, Debug)]
+pub struct State {
+  /// Position of the last right-click, in window coordinates.
+  position: Point,
+}
+
+impl<'a, M, R> ContextMenu<'a, M, R> {
+  pub fn new(
+    state: &'a mut State,
+    content: impl Into<Element<'a, M, R>>,
+    menu: Option<Element<'a, M, R>>,
+    on_close: impl 'a + Fn() -> M,
+  ) -> Self {
+    Self { state, content: content.into(), menu, on_close: Box::new(on_close) }
+  }
+}
+
+impl<'a, M: 'a, R: 'a + Renderer> Widget<M, R> for ContextMenu<'a, M, R> {
+  fn width(&self) -> Length { self.content.width() }
+
+  fn height(&self) -> Length { self.content.height() }
+
+  fn layout(&self, renderer: &R, limits: &Limits) -> Node {
+    self.content.layout(renderer, limits)
+  }
+
+  fn draw(
+    &self,
+    renderer: &mut R,
+    defaults: &R::Defaults,
+    layout: Layout<'_>,
+    cursor_position: Point,
+    viewport: &Rectangle<f32>,
+  ) -> R::Output {
+    self.content.draw(renderer, defaults, layout, cursor_position, viewport)
+  }
+
+  fn hash_layout(&self, state: &mut Hasher) {
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+    self.content.hash_layout(state);
+  }
+
+  fn on_event(
+    &mut self,
+    event: Event,
+    layout: Layout<'_>,
+    cursor_position: Point,
+    messages: &mut Vec<M>,
+    renderer: &R,
+    clipboard: Option<&dyn Clipboard>,
+  ) -> Status {
+    // Record the position before the content handles the event, as the content may open the menu in response.
+    if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) = &event {
+      if layout.bounds().contains(cursor_position) {
+        self.state.position = cursor_position;
+      }
+    }
+    self.content.on_event(event, layout, cursor_position, messages, renderer, clipboard)
+  }
+
+  fn overlay(&mut self, layout: Layout<'_>) -> Option<overlay::Element<'_, M, R>> {
+    match &mut self.menu {
+      Some(menu) => {
+        let position = self.state.position;
+        Some(overlay::Element::new(position, Box::new(MenuOverlay { menu, on_close: &self.on_close })))
+      }
+      None => self.content.overlay(layout),
+    }
+  }
+}
+
+impl<'a, M: 'a, R: 'a + Renderer> Into<Element<'a, M, R>> for ContextMenu<'a, M, R> {
+  fn into(self) -> Element<'a, M, R> {
+    Element::new(self)
+  }
+}
+
+//
+// Menu overlay
+//
+
+struct MenuOverlay<'b, 'a, M, R> {
+  menu: &'b mut Element<'a, M, R>,
+  on_close: &'b Box<dyn 'a + Fn() -> M>,
+}
+
+impl<'b, 'a, M, R: Renderer> overlay::Overlay<M, R> for MenuOverlay<'b, 'a, M, R> {
+  fn layout(&self, renderer: &R, bounds: Size, position: Point) -> Node {
+    let limits = Limits::new(Size::ZERO, bounds)
+      .width(Length::Shrink)
+      .height(Length::Shrink);
+    let mut node = self.menu.layout(renderer, &limits);
+    let size = node.size();
+    // Open the menu to the left of or above the position when there is no room to the right of or below it.
+    let x = if position.x + size.width > bounds.width { (position.x - size.width).max(0f32) } else { position.x };
+    let y = if position.y + size.height > bounds.height { (position.y - size.height).max(0f32) } else { position.y };
+    node.move_to(Point::new(x, y));
+    node
+  }
+
+  fn draw(
+    &self,
+    renderer: &mut R,
+    defaults: &R::Defaults,
+    layout: Layout<'_>,
+    cursor_position: Point,
+  ) -> R::Output {
+    self.menu.draw(renderer, defaults, layout, cursor_position, &layout.bounds())
+  }
+
+  fn hash_layout(&self, state: &mut Hasher, position: Point) {
+    struct Marker;
+    std::any::TypeId::of::<Marker>().hash(state);
+    (position.x as u32).hash(state);
+    (position.y as u32).hash(state);
+    self.menu.hash_layout(state);
+  }
+
+  fn on_event(
+    &mut self,
+    event: Event,
+    layout: Layout<'_>,
+    cursor_position: Point,
+    messages: &mut Vec<M>,
+    renderer: &R,
+    clipboard: Option<&dyn Clipboard>,
+  ) -> Status {
+    match &event {
+      Event::Mouse(mouse::Event::ButtonPressed(_)) if !layout.bounds().contains(cursor_position) => {
+        // Do not capture the press, so that the content below the menu also handles it.
+        messages.push((self.on_close)());
+        return Status::Ignored;
+      }
+      Event::Keyboard(keyboard::Event::KeyPressed { key_code: keyboard::KeyCode::Escape, .. }) => {
+        messages.push((self.on_close)());
+        return Status::Captured;
+      }
+      _ => {}
+    }
+    self.menu.on_event(event, layout, cursor_position, messages, renderer, clipboard)
+  }
+}
@@ -1 +1,2 @@
+pub mod context_menu;
 pub mod table;
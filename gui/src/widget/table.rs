@@ -2,12 +2,13 @@
 
 use std::cell::RefCell;
 use std::hash::Hash;
+use std::ops::Range;
 use std::rc::Rc;
 
 use iced_graphics::{Backend, Primitive, Renderer as ConcreteRenderer};
 use iced_native::{
-  Clipboard, Element, Event, event, Hasher, Layout, Length, mouse, overlay, Point, Rectangle, Renderer, Scrollable,
-  scrollable, Size, touch, Widget,
+  Clipboard, Element, Event, event, Hasher, keyboard, Layout, Length, mouse, overlay, Point, Rectangle, Renderer,
+  Scrollable, scrollable, Size, touch, Widget,
 };
 use iced_native::event::Status;
 use iced_native::layout::{Limits, Node};
@@ -46,8 +47,8 @@ impl<'a, T, M, R> TableBuilder<'a, T, M, R> where
       max_width: u32::MAX,
       max_height: u32::MAX,
       spacing: 0,
-      header: TableHeader { spacing, row_height, column_fill_portions: Vec::new(), headers: Vec::new() },
-      rows: TableRows { spacing, row_height, column_fill_portions: Vec::new(), mappers: Vec::new(), rows },
+      header: TableHeader { spacing, row_height, column_fill_portions: Vec::new(), headers: Vec::new(), on_sort: None, resize: None },
+      rows: TableRows { spacing, row_height, column_fill_portions: Vec::new(), mappers: Vec::new(), rows, on_row_press: None },
     }
   }
 
@@ -101,6 +102,27 @@ impl<'a, T, M, R> TableBuilder<'a, T, M, R> where
     self
   }
 
+  /// Sends the message created by `on_sort` with the index of a column when its header is clicked.
+  pub fn on_sort(mut self, on_sort: impl 'a + Fn(usize) -> M) -> Self {
+    self.header.on_sort = Some(Box::new(on_sort));
+    self
+  }
+
+  /// Allows resizing columns by dragging the space between column headers, sending the message created by `on_resize`
+  /// while dragging. The owner of the table must apply the resize to the fill portions of the columns it pushes.
+  pub fn resizable(mut self, state: &'a mut ResizeState, on_resize: impl 'a + Fn(ColumnResize) -> M) -> Self {
+    self.header.resize = Some((state, Box::new(on_resize)));
+    self
+  }
+
+  /// Sends the message created by `on_row_press` when a row is pressed with the left or right mouse button, outside of
+  /// the elements of the row that handle the press themselves. The keyboard modifiers that are held while pressing are
+  /// tracked in `state`, which must be kept by the owner of the table between views.
+  pub fn on_row_press(mut self, state: &'a mut RowPressState, on_row_press: impl 'a + Fn(RowPress) -> M) -> Self {
+    self.rows.on_row_press = Some((state, Box::new(on_row_press)));
+    self
+  }
+
 
   pub fn build(
     self,
@@ -122,6 +144,15 @@ impl<'a, T, M, R> TableBuilder<'a, T, M, R> where
   }
 }
 
+/// Scrolls the rows of a table that was built with `rows_scrollable_state` so that the row at `row_index` is at the top,
+/// given the `row_height` and `spacing` the table was built with.
+pub fn scroll_to_row(rows_scrollable_state: &mut scrollable::State, row_index: usize, row_height: u32, spacing: u32) {
+  let y = row_index as f32 * (row_height + spacing) as f32;
+  // HACK: the layout of the rows is not known here, so scroll to 100% of content that is hidden up to `y`. The offset
+  // is clamped to the actually hidden content when the rows are laid out.
+  rows_scrollable_state.scroll_to(1.0, Rectangle::default(), Rectangle { height: y, ..Rectangle::default() });
+}
+
 //
 // Table widget
 //
@@ -265,11 +296,87 @@ impl<'a, M: 'a, R: 'a + TableRenderer> Into<Element<'a, M, R>> for Table<'a, M,
 // Table header
 //
 
+/// Distance in pixels from the space between two column headers within which dragging resizes the columns.
+const RESIZE_HANDLE_DISTANCE: f32 = 4.0;
+
 pub struct TableHeader<'a, M, R> {
   spacing: u32,
   row_height: u32,
   column_fill_portions: Vec<u32>,
   headers: Vec<Element<'a, M, R>>,
+  on_sort: Option<Box<dyn 'a + Fn(usize) -> M>>,
+  resize: Option<(&'a mut ResizeState, Box<dyn 'a + Fn(ColumnResize) -> M>)>,
+}
+
+/// State of resizing columns of a table, which must be kept by the owner of the table between views.
+#[derive(Default, Debug)]
+pub struct ResizeState {
+  /// Index of the column whose right edge is being dragged.
+  dragging: Option<usize>,
+}
+
+/// Resize of the column with `column_index` and the column after it, by changing their fill portions. The sum of their
+/// fill portions stays the same. `finished` is set when the user stops dragging.
+#[derive(Copy, Clone, Debug)]
+pub struct ColumnResize {
+  pub column_index: usize,
+  pub fill_portion: u32,
+  pub next_fill_portion: u32,
+  pub finished: bool,
+}
+
+impl<'a, M, R> TableHeader<'a, M, R> {
+  /// Returns the index of the column whose right edge is near `x`, if any.
+  fn resize_handle_at(&self, x: f32, layout: Layout<'_>) -> Option<usize> {
+    let spacing = self.spacing as f32;
+    let num_columns = self.column_fill_portions.len();
+    layout.children()
+      .take(num_columns.saturating_sub(1)) // Last column has no column after it to resize with.
+      .position(|column_layout| {
+        let bounds = column_layout.bounds();
+        let edge = bounds.x + bounds.width + spacing / 2f32;
+        (x - edge).abs() <= RESIZE_HANDLE_DISTANCE
+      })
+  }
+
+  fn column_at(&self, x: f32, layout: Layout<'_>) -> Option<usize> {
+    layout.children().position(|column_layout| {
+      let bounds = column_layout.bounds();
+      x >= bounds.x && x <= bounds.x + bounds.width
+    })
+  }
+
+  /// Returns the resize of the column with `column_index` when its right edge is dragged to `x`.
+  fn resize_to(&self, column_index: usize, x: f32, layout: Layout<'_>, finished: bool) -> Option<ColumnResize> {
+    let mut children = layout.children().skip(column_index);
+    let (left, right) = (children.next()?.bounds(), children.next()?.bounds());
+    let fill_portion = *self.column_fill_portions.get(column_index)?;
+    let next_fill_portion = *self.column_fill_portions.get(column_index + 1)?;
+    let total_fill_portion = fill_portion + next_fill_portion;
+    let total_width = left.width + right.width;
+    if total_fill_portion < 2 || total_width <= 0f32 {
+      return None;
+    }
+    let width = (x - left.x).clamp(0f32, total_width);
+    let new_fill_portion = ((width / total_width) * total_fill_portion as f32).round() as u32;
+    let new_fill_portion = new_fill_portion.clamp(1, total_fill_portion - 1);
+    Some(ColumnResize { column_index, fill_portion: new_fill_portion, next_fill_portion: total_fill_portion - new_fill_portion, finished })
+  }
+
+  fn mouse_interaction(&self, layout: Layout<'_>, cursor_position: Point) -> Option<mouse::Interaction> {
+    if let Some((state, _)) = &self.resize {
+      if state.dragging.is_some() {
+        return Some(mouse::Interaction::ResizingHorizontally);
+      }
+      if layout.bounds().contains(cursor_position) && self.resize_handle_at(cursor_position.x, layout).is_some() {
+        return Some(mouse::Interaction::ResizingHorizontally);
+      }
+    }
+    if self.on_sort.is_some() && layout.bounds().contains(cursor_position) {
+      return Some(mouse::Interaction::Pointer);
+    }
+    None
+  }
 }
 
 impl<'a, M, R: TableHeaderRenderer> Widget<M, R> for TableHeader<'a, M, R> {
@@ -291,7 +398,8 @@ impl<'a, M, R: TableHeaderRenderer> Widget<M, R> for TableHeader<'a, M, R> {
     cursor_position: Point,
     viewport: &Rectangle<f32>,
   ) -> R::Output {
-    renderer.draw_table_header(defaults, layout, cursor_position, viewport, &self.headers)
+    let mouse_interaction = self.mouse_interaction(layout, cursor_position);
+    renderer.draw_table_header(defaults, layout, cursor_position, viewport, &self.headers, mouse_interaction)
   }
 
   fn hash_layout(&self, state: &mut Hasher) {
@@ -313,7 +421,35 @@ impl<'a, M, R: TableHeaderRenderer> Widget<M, R> for TableHeader<'a, M, R> {
     renderer: &R,
     clipboard: Option<&dyn Clipboard>,
   ) -> Status {
-    self.headers
+    // Resizing columns takes precedence over header elements, as the resize handles are between header elements.
+    let dragging = self.resize.as_ref().and_then(|(state, _)| state.dragging);
+    match (&event, dragging) {
+      (Event::Mouse(mouse::Event::CursorMoved { .. }), Some(column_index)) => {
+        if let (Some(resize), Some((_, on_resize))) = (self.resize_to(column_index, cursor_position.x, layout, false), &self.resize) {
+          messages.push(on_resize(resize));
+        }
+        return Status::Captured;
+      }
+      (Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)), Some(column_index)) => {
+        if let (Some(resize), Some((_, on_resize))) = (self.resize_to(column_index, cursor_position.x, layout, true), &self.resize) {
+          messages.push(on_resize(resize));
+        }
+        if let Some((state, _)) = &mut self.resize {
+          state.dragging = None;
+        }
+        return Status::Captured;
+      }
+      (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), None) if self.resize.is_some() && layout.bounds().contains(cursor_position) => {
+        if let Some(column_index) = self.resize_handle_at(cursor_position.x, layout) {
+          if let Some((state, _)) = &mut self.resize {
+            state.dragging = Some(column_index);
+          }
+          return Status::Captured;
+        }
+      }
+      _ => {}
+    }
+    let status = self.headers
       .iter_mut()
       .zip(layout.children())
       .map(|(header, layout)| {
@@ -326,7 +462,17 @@ impl<'a, M, R: TableHeaderRenderer> Widget<M, R> for TableHeader<'a, M, R> {
           clipboard,
         )
       })
-      .fold(event::Status::Ignored, event::Status::merge)
+      .fold(event::Status::Ignored, event::Status::merge);
+    if status == Status::Captured { return status; }
+    if let (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(on_sort)) = (&event, &self.on_sort) {
+      if layout.bounds().contains(cursor_position) {
+        if let Some(column_index) = self.column_at(cursor_position.x, layout) {
+          messages.push(on_sort(column_index));
+          return Status::Captured;
+        }
+      }
+    }
+    Status::Ignored
   }
 
   fn overlay(&mut self, layout: Layout<'_>) -> Option<overlay::Element<'_, M, R>> {
@@ -346,6 +492,7 @@ pub trait TableHeaderRenderer: Renderer {
     cursor_position: Point,
     viewport: &Rectangle<f32>,
     headers: &[Element<'_, M, Self>],
+    mouse_interaction: Option<mouse::Interaction>,
   ) -> Self::Output;
 }
 
@@ -357,8 +504,9 @@ impl<B: Backend> TableHeaderRenderer for ConcreteRenderer<B> {
     cursor_position: Point,
     viewport: &Rectangle<f32>,
     headers: &[Element<'_, M, Self>],
+    mouse_interaction: Option<mouse::Interaction>,
   ) -> Self::Output {
-    let mut mouse_cursor = mouse::Interaction::default();
+    let mut mouse_cursor = mouse_interaction.unwrap_or_default();
     if headers.is_empty() {
       return (Primitive::None, mouse_cursor);
     }
@@ -399,6 +547,21 @@ struct TableRows<'a, T, M, R> where
   //       Ideally, we want to take something like `T: 'a, I: 'a + IntoIterator, I::Item=&'a mut T,
   //       I::IntoIter='a + ExactSizeIterator`.
   rows: Rc<RefCell<Vec<T>>>,
+  on_row_press: Option<(&'a mut RowPressState, Box<dyn 'a + Fn(RowPress) -> M>)>,
+}
+
+/// State of pressing rows of a table, which must be kept by the owner of the table between views.
+#[derive(Default, Debug)]
+pub struct RowPressState {
+  modifiers: keyboard::Modifiers,
+}
+
+/// Press of the row with `row_index` with mouse `button`, while holding keyboard `modifiers`.
+#[derive(Copy, Clone, Debug)]
+pub struct RowPress {
+  pub row_index: usize,
+  pub button: mouse::Button,
+  pub modifiers: keyboard::Modifiers,
 }
 
 impl<'a, T, M, R: TableRowsRenderer<'a, T, M>> Widget<M, R> for TableRows<'a, T, M, R> where
@@ -451,12 +614,26 @@ impl<'a, T, M, R: TableRowsRenderer<'a, T, M>> Widget<M, R> for TableRows<'a, T,
   ) -> Status {
     let absolute_position = layout.position();
     match &event {
+      Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+        if let Some((state, _)) = &mut self.on_row_press {
+          state.modifiers = *modifiers;
+        }
+        return Status::Ignored;
+      }
       Event::Keyboard(_) | Event::Window(_) => return Status::Ignored,
-      Event::Mouse(_) => {
+      Event::Mouse(mouse_event) => {
         let mouse_position_relative = Point::new(cursor_position.x - absolute_position.x, cursor_position.y - absolute_position.y);
         if self.propagate_event_to_element_at(&event, mouse_position_relative, layout, cursor_position, messages, renderer, clipboard) == Status::Captured {
           return Status::Captured;
         }
+        if let (mouse::Event::ButtonPressed(button @ (mouse::Button::Left | mouse::Button::Right)), Some((state, on_row_press))) = (mouse_event, &self.on_row_press) {
+          if layout.bounds().contains(cursor_position) {
+            if let Some(row_index) = self.get_row_index_at(mouse_position_relative.y).filter(|i| *i < self.rows.borrow().len()) {
+              messages.push(on_row_press(RowPress { row_index, button: *button, modifiers: state.modifiers }));
+              return Status::Captured;
+            }
+          }
+        }
       }
       Event::Touch(touch_event) => {
         let touch_position_absolute = match touch_event {
@@ -571,18 +748,15 @@ impl<'a, T, M, B> TableRowsRenderer<'a, T, M> for ConcreteRenderer<B> where
   ) -> Self::Output {
     let absolute_position = layout.position();
     let mut mouse_cursor = mouse::Interaction::default();
-    let num_rows = rows.len();
-    if num_rows == 0 {
+    let visible_rows = visible_row_range(rows.len(), row_height, spacing, absolute_position.y, viewport);
+    if visible_rows.is_empty() {
       return (Primitive::None, mouse_cursor);
     }
     let mut primitives = Vec::new();
-    let last_row_index = num_rows.saturating_sub(1);
     let row_height_plus_spacing = row_height + spacing;
-    let start_offset = (((viewport.y - absolute_position.y) / row_height_plus_spacing).floor() as usize).min(last_row_index);
-    // NOTE: + 1 on next line to ensure that last partially visible row is not culled.
-    let num_rows_to_render = ((viewport.height / row_height_plus_spacing).ceil() as usize + 1).min(num_rows);
-    let mut y_offset = absolute_position.y + start_offset as f32 * row_height_plus_spacing;
-    for (i, row) in rows.into_iter().skip(start_offset).take(num_rows_to_render).enumerate() {
+    let start_offset = visible_rows.start;
+    for (i, row) in rows[visible_rows].iter_mut().enumerate() {
+      let y_offset = absolute_position.y + (start_offset + i) as f32 * row_height_plus_spacing;
       for (mapper, base_layout) in mappers.iter().zip(layout.children()) {
         let element: Element<'_, M, Self> = mapper(row);
         // HACK: reconstruct layout of element to fix its y position based on `y_offset`.
@@ -592,10 +766,6 @@ impl<'a, T, M, B> TableRowsRenderer<'a, T, M> for ConcreteRenderer<B> where
         if new_mouse_cursor > mouse_cursor { mouse_cursor = new_mouse_cursor; }
         primitives.push(primitive);
       }
-      y_offset += row_height;
-      if i < last_row_index { // Don't add spacing after last row.
-        y_offset += spacing;
-      }
     }
     (Primitive::Group { primitives }, mouse_cursor)
   }
@@ -611,6 +781,22 @@ impl<'a, T, M, R> Into<Element<'a, M, R>> for TableRows<'a, T, M, R> where
   }
 }
 
+/// Returns the range of indices of the rows that are (partially) visible in `viewport`, where the first row starts at
+/// `rows_y`. Only these rows are turned into elements and drawn, so that the cost of drawing a table is independent of
+/// its number of rows.
+fn visible_row_range(num_rows: usize, row_height: f32, spacing: f32, rows_y: f32, viewport: &Rectangle<f32>) -> Range<usize> {
+  let row_height_plus_spacing = row_height + spacing;
+  if num_rows == 0 || row_height_plus_spacing <= 0f32 {
+    return 0..0;
+  }
+  let viewport_top = (viewport.y - rows_y).max(0f32);
+  let viewport_bottom = (viewport.y + viewport.height - rows_y).max(0f32);
+  let start = ((viewport_top / row_height_plus_spacing).floor() as usize).min(num_rows);
+  // NOTE: ceil to ensure that the last partially visible row is not culled.
+  let end = ((viewport_bottom / row_height_plus_spacing).ceil() as usize).clamp(start, num_rows);
+  start..end
+}
+
 //
 // Column layout calculation and reconstruction.
 //
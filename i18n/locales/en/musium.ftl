@@ -0,0 +1,220 @@
+app-title = Musium
+
+## Login page
+
+login-server-url = Server URL
+login-name = Name
+login-password = Password
+login-discover = Discover
+login-discovering = Discovering...
+login-button = Login
+login-busy = Logging in...
+login-failed = Logging in failed
+login-return = Return
+login-kiosk-button = Start kiosk
+login-profile = Profile
+login-profile-default = Default
+login-profile-invalid = Profile names may only contain letters, digits, '-', and '_'
+
+## Main page
+
+tab-tracks = Tracks
+tab-sources = Sources
+tab-now-playing = Now Playing
+tab-stats = Statistics
+tab-hidden = Hidden
+tab-classical = Classical
+switch-profile = Switch profile
+player-prev-track = Prev track
+player-stop = Stop
+player-toggle-play = Play/pause
+player-next-track = Next track
+playback-error = Playback failed: { $error }
+player-queue = Queue: { $count }
+player-album-radio = Album radio
+player-downrank-skipped = Play skipped tracks less
+player-mono-downmix = Mono
+player-balance = Balance
+player-crossfeed = Crossfeed
+player-quiet-hours = Quiet hours
+player-buffering = Buffering…
+refresh = Refresh
+
+## Tracks tab
+
+tracks-title = Tracks
+track-play = Play
+tracks-columns = Columns:
+column-track-number = #
+column-title = Title
+column-track-artists = Track Artists
+column-album = Album
+column-album-artists = Album Artists
+tracks-show-all = Show all tracks
+tracks-shuffle-all = Shuffle
+tracks-collapse-versions = Collapse versions
+tracks-selected = { $count ->
+    [one] 1 track selected
+   *[other] { $count } tracks selected
+}
+track-menu-play-next = Play next
+track-menu-add-to-queue = Add to queue
+track-menu-play-album-shuffled = Play album shuffled
+track-menu-play-artist = Play artist
+track-menu-add-to-playlist = Add to playlist:
+track-menu-rate = Rate:
+track-menu-tag = Tag
+track-menu-tag-placeholder = Tag name
+track-menu-show-album = Show album
+track-menu-inspect = Inspect
+track-menu-reveal = Show in file manager
+track-menu-hide-track = Never play track
+track-menu-hide-album = Never play album
+track-menu-hide-artist = Never play artist
+track-inspector-title = Inspector
+track-inspector-close = Close
+track-inspector-no-file = No file
+track-inspector-reveal = Show in file manager
+track-inspector-paths-hidden = File paths are hidden by the server
+track-inspector-credits = Credits:
+track-artist-role-main = Artist
+track-artist-role-featured = Featuring
+track-artist-role-remixer = Remixed by
+track-artist-role-composer = Composer
+track-artist-role-conductor = Conductor
+track-artist-role-performer = Performers
+track-inspector-versions = Other versions:
+track-version-original = original
+track-version-remaster = remaster
+track-version-live = live
+track-version-remix = remix
+track-version-other = other version
+
+## Now playing tab
+
+now-playing-nothing = Nothing is playing
+now-playing-no-artwork = No album cover
+now-playing-no-lyrics = No lyrics
+
+## Statistics tab
+
+stats-title = Listening statistics
+stats-period-week = Week
+stats-period-month = Month
+stats-period-year = Year
+stats-period-all-time = All time
+stats-loading = Loading statistics...
+stats-none = No statistics
+stats-summary = { $plays } plays, { $hours }h { $minutes }m of listening, { $skips } skips
+stats-streaks = Current streak: { $current } days, longest streak: { $longest } days
+stats-top-artists = Top artists
+stats-top-albums = Top albums
+stats-top-tracks = Top tracks
+stats-most-skipped-tracks = Most skipped tracks
+stats-top-entry = { $name } ({ $count })
+stats-hours = Plays by hour of day
+
+hidden-title = Hidden
+hidden-description = Hidden tracks, albums, and artists are never played when shuffling or in radio, and are left out of lists.
+hidden-loading = Loading hidden items...
+hidden-none = Nothing is hidden
+hidden-unhide = Unhide
+hidden-unknown-item = Unknown item { $id }
+hidden-kind-track = Track
+hidden-kind-album = Album
+hidden-kind-artist = Artist
+content-filter = Content filter:
+content-filter-off = Off
+content-filter-exclude-explicit = No explicit tracks
+content-filter-only-clean = Only clean tracks
+content-filter-pinned = Your content filter was set by an admin, and cannot be changed.
+
+## Classical tab
+
+classical-title = Classical
+classical-loading = Loading composers...
+classical-none = No composers found. Composers, works, and movements are read from the tags of local files.
+classical-composers = Composers
+classical-composer = { $name } ({ $count ->
+    [one] 1 work
+   *[other] { $count } works
+})
+classical-works = Works
+classical-work = { $name } ({ $count ->
+    [one] 1 track
+   *[other] { $count } tracks
+})
+classical-movements = Movements
+classical-play-work = Play work
+classical-play-from = Play
+
+## Sources tab
+
+sources-title = Sources
+sources-sync-all = Sync all
+local-sources-title = Local sources
+local-sources-sync-all = Sync all local sources
+spotify-sources-title = Spotify sources
+spotify-sources-sync-all = Sync all Spotify sources
+spotify-sources-redirect-uri = Register { $uri } as a redirect URI in the settings of the Spotify app of the server to add Spotify sources
+spotify-sources-redirect-uri-derived = Register the Spotify authorization callback of the server as a redirect URI in the settings of the Spotify app, or set the redirect URI of the server with --spotify-redirect-uri
+source-sync = Sync
+column-id = ID
+column-directory = Directory
+column-user-id = User ID
+column-enabled = Enabled
+column-sync = Sync
+
+## Tray icon
+
+tray-quit = Quit
+
+## Command-line interface
+
+cli-login-failed = Failed to login to server
+cli-setup-already-done = Server has already been set up
+cli-set-preferred-playback-source = Set the preferred playback source with the set-playback-preference command
+cli-play-track-failed = Failed to play audio track
+cli-read-cover-failed = Failed to read cover image '{ $path }'
+cli-path-without-file-name = Path '{ $path }' does not have a file name
+cli-read-track-file-failed = Failed to read track file '{ $path }'
+cli-alphabet-section = { $initial } { $count } (from { $start })
+cli-skip-recorded = Recorded skip
+cli-skip-not-recorded = Did not record skip: track was not played or was played too far to count as skipped
+cli-item-not-hidden = The { $kind } with id { $id } was not hidden
+cli-year-out-of-range = Year { $year } is out of range
+cli-year-review-summary = { $plays } plays, { $minutes } minutes listened
+cli-new-discoveries = New discoveries
+cli-play-count-entry = { $name } ({ $count ->
+    [one] 1 play
+   *[other] { $count } plays
+})
+cli-skip-count-entry = { $name } ({ $count ->
+    [one] 1 skip
+   *[other] { $count } skips
+})
+cli-track-version = { $track } ({ $version })
+cli-track-version-machine-generated = { $track } ({ $version }, machine-generated)
+cli-cache-cleared = Cleared { $cache } cache
+cli-cache-size = { $cache }: { $size } MiB ({ $directory })
+cli-settings-saved = Saved client settings to { $file }
+cli-no-server-url = No server URL given as argument or in the profile of the client settings file
+cli-parse-server-url-failed = Failed to parse server URL of the profile of the client settings file
+cli-no-login-name = No login name given as argument or in the profile of the client settings file
+cli-no-password = No password given as argument
+cli-default-profile = (default)
+cli-profile-switched = Switched to profile { $name }
+cli-profile-removed = Removed profile { $name }
+cli-no-profile = No profile named '{ $name }'
+cli-no-profile-to-switch-to = No profile named '{ $name }'; create it with `--profile { $name } save-settings`
+cli-files-moved = { $count ->
+    [one] Moved 1 file
+   *[other] Moved { $count } files
+}
+cli-technical-info-local-source = Local source { $id }: { $path }
+cli-technical-info-no-file = (no file)
+cli-technical-info-hash = hash: { $hash }, bitrate: { $bitrate } kbit/s
+cli-technical-info-file = size: { $size } bytes, codec: { $codec }, sample rate: { $sample-rate } Hz, channels: { $channels }, duration: { $duration } s
+cli-technical-info-error = error: { $error }
+cli-technical-info-spotify-track = Spotify track: { $id }
+cli-no-local-source = No local source with ID { $id }
@@ -0,0 +1,214 @@
+app-title = Musium
+
+## Login page
+
+login-server-url = Server-URL
+login-name = Naam
+login-password = Wachtwoord
+login-discover = Zoeken
+login-discovering = Bezig met zoeken...
+login-button = Inloggen
+login-busy = Bezig met inloggen...
+login-failed = Inloggen mislukt
+login-return = Terug
+login-kiosk-button = Kiosk starten
+login-profile = Profiel
+login-profile-default = Standaard
+login-profile-invalid = Profielnamen mogen alleen letters, cijfers, '-' en '_' bevatten
+
+## Main page
+
+tab-tracks = Nummers
+tab-sources = Bronnen
+tab-now-playing = Nu aan het afspelen
+tab-stats = Statistieken
+tab-hidden = Verborgen
+tab-classical = Klassiek
+switch-profile = Profiel wisselen
+player-prev-track = Vorig nummer
+player-stop = Stop
+player-toggle-play = Afspelen/pauzeren
+player-next-track = Volgend nummer
+playback-error = Afspelen mislukt: { $error }
+player-queue = Wachtrij: { $count }
+player-album-radio = Albumradio
+player-downrank-skipped = Overgeslagen nummers minder afspelen
+player-mono-downmix = Mono
+player-balance = Balans
+player-crossfeed = Crossfeed
+player-quiet-hours = Stille uren
+player-buffering = Bufferen…
+refresh = Vernieuwen
+
+## Tracks tab
+
+tracks-title = Nummers
+track-play = Afspelen
+tracks-columns = Kolommen:
+column-track-number = #
+column-title = Titel
+column-track-artists = Artiesten
+column-album = Album
+column-album-artists = Albumartiesten
+tracks-show-all = Alle nummers tonen
+tracks-shuffle-all = Shuffelen
+tracks-collapse-versions = Versies samenvouwen
+tracks-selected = { $count ->
+    [one] 1 nummer geselecteerd
+   *[other] { $count } nummers geselecteerd
+}
+track-menu-play-next = Hierna afspelen
+track-menu-add-to-queue = Toevoegen aan wachtrij
+track-menu-play-album-shuffled = Album willekeurig afspelen
+track-menu-play-artist = Artiest afspelen
+track-menu-add-to-playlist = Toevoegen aan afspeellijst:
+track-menu-rate = Waarderen:
+track-menu-tag = Taggen
+track-menu-tag-placeholder = Tagnaam
+track-menu-show-album = Album tonen
+track-menu-inspect = Inspecteren
+track-menu-reveal = Tonen in bestandsbeheer
+track-menu-hide-track = Nummer nooit afspelen
+track-menu-hide-album = Album nooit afspelen
+track-menu-hide-artist = Artiest nooit afspelen
+track-inspector-title = Inspecteur
+track-inspector-close = Sluiten
+track-inspector-no-file = Geen bestand
+track-inspector-reveal = Tonen in bestandsbeheer
+track-inspector-paths-hidden = Bestandspaden worden verborgen door de server
+track-inspector-credits = Medewerkers:
+track-artist-role-main = Artiest
+track-artist-role-featured = Met
+track-artist-role-remixer = Geremixt door
+track-artist-role-composer = Componist
+track-artist-role-conductor = Dirigent
+track-artist-role-performer = Uitvoerenden
+track-inspector-versions = Andere versies:
+track-version-original = origineel
+track-version-remaster = remaster
+track-version-live = live
+track-version-remix = remix
+track-version-other = andere versie
+
+## Now playing tab
+
+now-playing-nothing = Er wordt niets afgespeeld
+now-playing-no-artwork = Geen albumhoes
+now-playing-no-lyrics = Geen songtekst
+
+## Statistics tab
+
+stats-title = Luisterstatistieken
+stats-period-week = Week
+stats-period-month = Maand
+stats-period-year = Jaar
+stats-period-all-time = Altijd
+stats-loading = Statistieken laden...
+stats-none = Geen statistieken
+stats-summary = { $plays } keer afgespeeld, { $hours }u { $minutes }m geluisterd, { $skips } keer overgeslagen
+stats-streaks = Huidige reeks: { $current } dagen, langste reeks: { $longest } dagen
+stats-top-artists = Topartiesten
+stats-top-albums = Topalbums
+stats-top-tracks = Topnummers
+stats-most-skipped-tracks = Vaakst overgeslagen nummers
+stats-top-entry = { $name } ({ $count })
+stats-hours = Afspeelbeurten per uur van de dag
+
+hidden-title = Verborgen
+hidden-description = Verborgen nummers, albums en artiesten worden nooit afgespeeld bij shuffelen of in radio, en worden weggelaten uit lijsten.
+hidden-loading = Verborgen items laden...
+hidden-none = Er is niets verborgen
+hidden-unhide = Zichtbaar maken
+hidden-unknown-item = Onbekend item { $id }
+hidden-kind-track = Nummer
+hidden-kind-album = Album
+hidden-kind-artist = Artiest
+content-filter = Inhoudsfilter:
+content-filter-off = Uit
+content-filter-exclude-explicit = Geen expliciete nummers
+content-filter-only-clean = Alleen nette nummers
+content-filter-pinned = Je inhoudsfilter is ingesteld door een beheerder, en kan niet worden gewijzigd.
+
+## Classical tab
+
+classical-title = Klassiek
+classical-loading = Componisten laden...
+classical-none = Geen componisten gevonden. Componisten, werken en delen worden gelezen uit de tags van lokale bestanden.
+classical-composers = Componisten
+classical-composer = { $name } ({ $count ->
+    [one] 1 werk
+   *[other] { $count } werken
+})
+classical-works = Werken
+classical-work = { $name } ({ $count ->
+    [one] 1 nummer
+   *[other] { $count } nummers
+})
+classical-movements = Delen
+classical-play-work = Werk afspelen
+classical-play-from = Afspelen
+
+## Sources tab
+
+sources-title = Bronnen
+sources-sync-all = Alles synchroniseren
+local-sources-title = Lokale bronnen
+local-sources-sync-all = Alle lokale bronnen synchroniseren
+spotify-sources-title = Spotify-bronnen
+spotify-sources-sync-all = Alle Spotify-bronnen synchroniseren
+spotify-sources-redirect-uri = Registreer { $uri } als redirect-URI in de instellingen van de Spotify-app van de server om Spotify-bronnen toe te voegen
+spotify-sources-redirect-uri-derived = Registreer de Spotify-autorisatiecallback van de server als redirect-URI in de instellingen van de Spotify-app, of stel de redirect-URI van de server in met --spotify-redirect-uri
+source-sync = Synchroniseren
+column-id = ID
+column-directory = Map
+column-user-id = Gebruikers-ID
+column-enabled = Ingeschakeld
+column-sync = Synchroniseren
+
+## Tray icon
+
+tray-quit = Afsluiten
+
+## Command-line interface
+
+cli-login-failed = Inloggen op de server mislukt
+cli-setup-already-done = De server is al ingesteld
+cli-set-preferred-playback-source = Stel de voorkeursbron voor afspelen in met het commando set-playback-preference
+cli-play-track-failed = Afspelen van het nummer mislukt
+cli-read-cover-failed = Lezen van albumhoes '{ $path }' mislukt
+cli-path-without-file-name = Pad '{ $path }' heeft geen bestandsnaam
+cli-read-track-file-failed = Lezen van nummerbestand '{ $path }' mislukt
+cli-alphabet-section = { $initial } { $count } (vanaf { $start })
+cli-skip-recorded = Overslaan geregistreerd
+cli-skip-not-recorded = Overslaan niet geregistreerd: het nummer werd niet afgespeeld of werd te ver afgespeeld om als overgeslagen te tellen
+cli-item-not-hidden = { $kind } met id { $id } was niet verborgen
+cli-year-out-of-range = Jaar { $year } valt buiten het bereik
+cli-year-review-summary = { $plays } keer afgespeeld, { $minutes } minuten geluisterd
+cli-new-discoveries = Nieuwe ontdekkingen
+cli-play-count-entry = { $name } ({ $count } keer afgespeeld)
+cli-skip-count-entry = { $name } ({ $count } keer overgeslagen)
+cli-track-version = { $track } ({ $version })
+cli-track-version-machine-generated = { $track } ({ $version }, automatisch gegenereerd)
+cli-cache-cleared = Cache { $cache } gewist
+cli-cache-size = { $cache }: { $size } MiB ({ $directory })
+cli-settings-saved = Clientinstellingen opgeslagen in { $file }
+cli-no-server-url = Geen server-URL opgegeven als argument of in het profiel van het clientinstellingenbestand
+cli-parse-server-url-failed = Verwerken van de server-URL van het profiel van het clientinstellingenbestand mislukt
+cli-no-login-name = Geen inlognaam opgegeven als argument of in het profiel van het clientinstellingenbestand
+cli-no-password = Geen wachtwoord opgegeven als argument
+cli-default-profile = (standaard)
+cli-profile-switched = Overgeschakeld naar profiel { $name }
+cli-profile-removed = Profiel { $name } verwijderd
+cli-no-profile = Geen profiel met de naam '{ $name }'
+cli-no-profile-to-switch-to = Geen profiel met de naam '{ $name }'; maak het aan met `--profile { $name } save-settings`
+cli-files-moved = { $count ->
+    [one] 1 bestand verplaatst
+   *[other] { $count } bestanden verplaatst
+}
+cli-technical-info-local-source = Lokale bron { $id }: { $path }
+cli-technical-info-no-file = (geen bestand)
+cli-technical-info-hash = hash: { $hash }, bitrate: { $bitrate } kbit/s
+cli-technical-info-file = grootte: { $size } bytes, codec: { $codec }, samplefrequentie: { $sample-rate } Hz, kanalen: { $channels }, duur: { $duration } s
+cli-technical-info-error = fout: { $error }
+cli-technical-info-spotify-track = Spotify-nummer: { $id }
+cli-no-local-source = Geen lokale bron met ID { $id }
@@ -0,0 +1,100 @@
+use std::fmt::{Debug, Formatter};
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+use tracing::{event, Level};
+use unic_langid::LanguageIdentifier;
+
+pub use fluent_bundle::FluentArgs;
+
+/// Locale of the catalog that is used for locales and messages without a translation.
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Fluent catalogs of user-facing strings, by locale.
+const CATALOGS: &[(&str, &str)] = &[
+  ("en", include_str!("../locales/en/musium.ftl")),
+  ("nl", include_str!("../locales/nl/musium.ftl")),
+];
+
+/// Localizes user-facing strings of the GUI and CLI, with the catalog of the language of a locale, falling back to the
+/// English catalog for messages that have no translation.
+pub struct Localizer {
+  locale: LanguageIdentifier,
+  /// Bundle of the catalog of the locale, followed by the bundle of the fallback catalog.
+  bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+  /// Creates a localizer for `locale`, a BCP 47 language tag such as `nl-NL`. Falls back to the English catalog when
+  /// `locale` cannot be parsed or has no catalog.
+  pub fn new(locale: &str) -> Self {
+    // UNWRAP: fallback locale is a valid language identifier.
+    let fallback_locale: LanguageIdentifier = FALLBACK_LOCALE.parse().unwrap();
+    let locale: LanguageIdentifier = locale.parse().unwrap_or_else(|e| {
+      event!(Level::WARN, locale, "Invalid locale, falling back to '{}': {}", FALLBACK_LOCALE, e);
+      fallback_locale.clone()
+    });
+    let mut bundles = Vec::new();
+    if locale.language != fallback_locale.language {
+      match CATALOGS.iter().find(|(catalog_locale, _)| *catalog_locale == locale.language.as_str()) {
+        Some((_, source)) => bundles.push(Self::create_bundle(locale.clone(), source)),
+        None => event!(Level::WARN, %locale, "No catalog for locale, falling back to '{}'", FALLBACK_LOCALE),
+      }
+    }
+    // UNWRAP: there is a catalog for the fallback locale.
+    let (_, fallback_source) = CATALOGS.iter().find(|(catalog_locale, _)| *catalog_locale == FALLBACK_LOCALE).unwrap();
+    bundles.push(Self::create_bundle(fallback_locale, fallback_source));
+    Self { locale, bundles }
+  }
+
+  pub fn locale(&self) -> &LanguageIdentifier { &self.locale }
+
+  /// Returns the localized string of the message with `id`.
+  pub fn text(&self, id: &str) -> String {
+    self.format(id, None)
+  }
+
+  /// Returns the localized string of the message with `id`, with `args` substituted for its variables.
+  pub fn text_with(&self, id: &str, args: &FluentArgs) -> String {
+    self.format(id, Some(args))
+  }
+
+  fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+    for bundle in &self.bundles {
+      if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+        let mut errors = Vec::new();
+        let text = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+          event!(Level::WARN, id, ?errors, "Errors while formatting message");
+        }
+        return text.into_owned();
+      }
+    }
+    event!(Level::ERROR, id, "[BUG] Message is not in any catalog");
+    id.to_string()
+  }
+
+  fn create_bundle(locale: LanguageIdentifier, source: &str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+      event!(Level::ERROR, %locale, ?errors, "[BUG] Errors while parsing catalog; ignoring erroneous messages");
+      resource
+    });
+    let mut bundle = FluentBundle::new_concurrent(vec![locale]);
+    // Do not isolate substituted arguments with Unicode directionality markers, as terminals display them.
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+      event!(Level::ERROR, ?errors, "[BUG] Errors while adding catalog to bundle");
+    }
+    bundle
+  }
+}
+
+impl Default for Localizer {
+  fn default() -> Self { Self::new(FALLBACK_LOCALE) }
+}
+
+impl Debug for Localizer {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Localizer").field("locale", &self.locale).finish()
+  }
+}
@@ -1,18 +1,35 @@
 mod worker_task;
+pub mod remote;
+pub mod volume;
+#[cfg(feature = "default_player")]
+pub mod output;
 
 use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::warn;
 
-pub use musium_audio_output::AudioOutput;
+pub use musium_audio_output::{AudioOutput, ChannelProcessing, PlaybackError};
+use musium_audio_output::{AudioSource, ReceivedAudio};
 #[cfg(feature = "default_player")]
 pub use musium_audio_output_kira::KiraAudioOutput;
-pub use musium_client::Client;
 #[cfg(feature = "default_player")]
-pub use musium_client_http::{HttpClient, HttpRequestError, Url};
+pub use musium_audio_output_rodio::{RodioAudioOutput, RodioOptions};
+pub use musium_client::{AudioChunks, Client, PlayStream};
+#[cfg(feature = "default_player")]
+pub use musium_client_http::{discover_servers, DiscoveredServer, DiscoverServersError, HttpClient, HttpRequestError, Url};
+use musium_core::api::{KioskLogin, PlayTracksQuery, TrackFilter, TrackRecommendationRequest};
 use musium_core::error::SyncError;
 use musium_core::model::{User, UserLogin};
+use musium_core::model::collection::TracksRaw;
+
+use crate::volume::VolumeLimits;
 
 // Player trait
 
@@ -35,9 +52,33 @@ pub trait Player: 'static + Send + Sync + Clone + Debug {
 
   type LoginError: SyncError;
   async fn login(&self, user_login: &UserLogin) -> Result<User, Self::LoginError>;
+  /// Logs in as the kiosk user of the server with `kiosk_token`, which can browse and play but not modify data.
+  async fn kiosk_login(&self, kiosk_token: &str) -> Result<User, Self::LoginError>;
 
   type PlayError: SyncError;
   async fn play_track_by_id(&self, id: i32) -> Result<(), Self::PlayError>;
+  /// Gets the tracks of the album with `album_id` in random order, for playing the album shuffled. The player does not
+  /// keep a queue, so callers replace their queue with these tracks.
+  async fn play_album_shuffled(&self, album_id: i32) -> Result<TracksRaw, <Self::Client as Client>::TrackError>;
+  /// Gets all tracks of the artist with `artist_id`, ordered by album, disc number, and track number, for playing the
+  /// artist. The player does not keep a queue, so callers replace their queue with these tracks.
+  async fn play_artist(&self, artist_id: i32) -> Result<TracksRaw, <Self::Client as Client>::TrackError>;
+  /// Gets a random sample of the tracks that match `filter`, for shuffling all tracks. The server samples tracks without
+  /// listing all of them, so that shuffling starts instantly on large libraries. The player does not keep a queue, so
+  /// callers replace their queue with these tracks.
+  async fn shuffle_all(&self, filter: &TrackFilter) -> Result<TracksRaw, <Self::Client as Client>::TrackError>;
+
+  /// Whether to continue with similar tracks when the queue runs out, instead of stopping playback.
+  fn is_album_radio_enabled(&self) -> bool;
+  fn set_album_radio_enabled(&self, enabled: bool);
+  /// Gets tracks similar to the recently played tracks with `played_track_ids`, most recent last, to continue playback
+  /// with when the queue runs out. Played tracks are not recommended again. Continues with random tracks when no similar
+  /// tracks are found, such as when no tracks were played yet.
+  async fn get_album_radio_tracks(&self, played_track_ids: &[i32]) -> Result<TracksRaw, <Self::Client as Client>::TrackError>;
+
+  /// Whether tracks that the user often skips are played less when shuffling and by the album radio.
+  fn is_downrank_skipped_enabled(&self) -> bool;
+  fn set_downrank_skipped_enabled(&self, enabled: bool);
 
   async fn is_paused(&self) -> Result<bool, <Self::AudioOutput as AudioOutput>::IsPausedError>;
   async fn pause(&self) -> Result<(), <Self::AudioOutput as AudioOutput>::PauseError>;
@@ -48,33 +89,239 @@ pub trait Player: 'static + Send + Sync + Clone + Debug {
   async fn seek_to_relative(&self, position_relative: f64) -> Result<(), <Self::AudioOutput as AudioOutput>::SeekToRelativeError>;
   async fn get_volume(&self) -> Result<f64, <Self::AudioOutput as AudioOutput>::GetVolumeError>;
   async fn set_volume(&self, volume: f64) -> Result<(), <Self::AudioOutput as AudioOutput>::SetVolumeError>;
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), <Self::AudioOutput as AudioOutput>::SetChannelProcessingError>;
+
+  /// Gets the limits on the volume, which are enforced regardless of the volume that is set.
+  fn get_volume_limits(&self) -> VolumeLimits;
+  /// Sets the limits on the volume, which take effect when they are next enforced.
+  fn set_volume_limits(&self, limits: VolumeLimits);
+  type EnforceVolumeLimitsError: SyncError;
+  /// Limits the volume to the maximum volume of the current output device and of quiet hours, and sets the fade duration
+  /// of the audio output. Must be called periodically to start and end quiet hours on time and to pick up changes of
+  /// the output device. Returns whether quiet hours are active.
+  async fn enforce_volume_limits(&self) -> Result<bool, Self::EnforceVolumeLimitsError>;
+
+  /// Subscribes to events of this player, such as tracks starting and ending, and playback progressing.
+  fn subscribe_player_events(&self) -> broadcast::Receiver<PlayerEvent>;
+  /// Publishes a `QueueChanged` event with the IDs of the queued tracks. The player does not keep a queue, so callers
+  /// that keep a queue call this whenever they change it.
+  fn announce_queue_changed(&self, queued_track_ids: Vec<i32>);
+}
+
+/// Event of a player.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PlayerEvent {
+  /// Track with ID started playing.
+  TrackStarted(i32),
+  /// Track with `track_id` ended. `finished` is true when the track played until its end, and false when it was
+  /// stopped or replaced by another track.
+  TrackEnded { track_id: i32, finished: bool },
+  /// Queued tracks changed to the tracks with these IDs, in order.
+  QueueChanged(Vec<i32>),
+  /// Volume of the audio output changed to this volume, after applying volume limits.
+  VolumeChanged(f64),
+  /// Whether the player is buffering, which it is while getting the audio of a track before playing it.
+  Buffering(bool),
+  /// Playback of the current track progressed, with the position and duration in seconds if the audio output supports
+  /// them.
+  Progress { position: Option<f64>, duration: Option<f64>, position_relative: Option<f64> },
 }
 
 #[derive(Debug, Error)]
-pub enum PlayError<CP, AOS, AOP> {
+pub enum PlayError<CP, AOS, AOG, AOP> {
   #[error("Failed to get playback data from the client")]
   ClientPlayTrackFail(#[source] CP),
   #[error("Failed to set audio data to the audio output")]
   SetAudioDataFail(#[source] AOS),
+  #[error("Failed to set ReplayGain gain to the audio output")]
+  SetGainFail(#[source] AOG),
   #[error("Failed to play audio with the audio output")]
   AudioOutputPlayFail(#[source] AOP),
 }
 
+#[derive(Debug, Error)]
+pub enum EnforceVolumeLimitsError<AOD, AOV, AOF> {
+  #[error("Failed to get the output device name from the audio output")]
+  GetOutputDeviceNameFail(#[source] AOD),
+  #[error("Failed to set the limited volume to the audio output")]
+  SetVolumeFail(#[source] AOV),
+  #[error("Failed to set the fade duration to the audio output")]
+  SetFadeDurationFail(#[source] AOF),
+}
+
 // Generic player type
 
 #[derive(Clone, Debug)]
 pub struct GenericPlayer<C, AO> {
   client: C,
   audio_output: AO,
+  /// Album of the previously played track, for preferring album gain when playing tracks of the same album in sequence.
+  previous_album_id: Arc<Mutex<Option<i32>>>,
+  album_radio_enabled: Arc<AtomicBool>,
+  downrank_skipped_enabled: Arc<AtomicBool>,
+  volume_limits: Arc<Mutex<VolumeLimits>>,
+  volume: Arc<Mutex<LimitedVolume>>,
+  events: broadcast::Sender<PlayerEvent>,
+  /// Monitor of the track that is playing, or `None` if no track is playing.
+  playback_monitor: Arc<Mutex<Option<PlaybackMonitor>>>,
+  next_playback_monitor_id: Arc<AtomicU64>,
+  /// Task that receives the audio of the track that is playing, or `None` if no audio is being received.
+  audio_receiver: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
+/// Task that monitors playback of a track, publishing progress and the end of the track.
+#[derive(Debug)]
+struct PlaybackMonitor {
+  id: u64,
+  track_id: i32,
+  task: JoinHandle<()>,
+}
+
+/// Volume requested by clients, and the maximum volume it is limited to.
+#[derive(Copy, Clone, Debug)]
+struct LimitedVolume {
+  requested: f64,
+  max: f64,
+}
+
+impl LimitedVolume {
+  fn limited(&self) -> f64 { self.requested.min(self.max) }
+}
+
+/// Number of the most recently played tracks that seed the album radio.
+const ALBUM_RADIO_SEED_TRACKS: usize = 5;
+/// Number of tracks that the album radio continues with each time the queue runs out.
+const ALBUM_RADIO_TRACKS: i64 = 10;
+/// Number of random tracks that shuffling all tracks queues.
+const SHUFFLE_ALL_TRACKS: i64 = 100;
+/// Interval at which playback monitors check the audio output and publish progress.
+const PLAYBACK_MONITOR_INTERVAL: Duration = Duration::from_millis(250);
+
 impl<C: Client, AO: AudioOutput> GenericPlayer<C, AO> {
   pub fn new(client: C, audio_output: AO) -> Self {
     Self {
       client,
       audio_output,
+      previous_album_id: Arc::new(Mutex::new(None)),
+      album_radio_enabled: Arc::new(AtomicBool::new(false)),
+      downrank_skipped_enabled: Arc::new(AtomicBool::new(false)),
+      volume_limits: Arc::new(Mutex::new(VolumeLimits::default())),
+      volume: Arc::new(Mutex::new(LimitedVolume { requested: 1.0, max: 1.0 })),
+      events: broadcast::channel(64).0,
+      playback_monitor: Arc::new(Mutex::new(None)),
+      next_playback_monitor_id: Arc::new(AtomicU64::new(0)),
+      audio_receiver: Arc::new(Mutex::new(None)),
     }
   }
+
+  /// Starts receiving the audio of the track with `id` from the client and sets it to the audio output, without playing
+  /// it. The audio output can start playing the audio before it is received entirely.
+  async fn set_track_audio(&self, id: i32) -> Result<(), PlayError<C::PlaybackError, AO::SetAudioDataError, AO::SetGainError, AO::PlayError>> {
+    use PlayError::*;
+    use PlayStream::*;
+    self.stop_receiving_audio();
+    let play_stream = self.get_client().stream_track_by_id(id).await.map_err(|e| ClientPlayTrackFail(e))?;
+    match play_stream {
+      Some(AudioData { codec, length, album_id, replay_gain, chunks }) => {
+        // Prefer album gain when playing tracks of the same album in sequence, to keep loudness differences between
+        // tracks of an album intact.
+        let prefer_album = {
+          let mut previous_album_id = self.previous_album_id.lock().unwrap();
+          let prefer_album = album_id.is_some() && *previous_album_id == album_id;
+          *previous_album_id = album_id;
+          prefer_album
+        };
+        let gain = replay_gain.and_then(|replay_gain| replay_gain.gain_factor(prefer_album)).unwrap_or(1.0);
+        self.get_audio_output().set_gain(gain).await.map_err(|e| SetGainFail(e))?;
+        let (received_audio, writer) = ReceivedAudio::new(length);
+        let task = tokio::spawn(async move {
+          let mut chunks = chunks;
+          loop {
+            match chunks.next_chunk().await {
+              Ok(Some(chunk)) => writer.push(&chunk),
+              Ok(None) => break writer.finish(),
+              Err(e) => {
+                warn!(error = ?e, "Failed to receive audio of track {}", id);
+                break writer.fail(e.to_string());
+              }
+            }
+          }
+        });
+        self.audio_receiver.lock().unwrap().replace(task);
+        self.get_audio_output().set_audio_source(codec, AudioSource::Received(received_audio)).await.map_err(|e| SetAudioDataFail(e))?
+      }
+      Some(ExternallyPlayedOnSpotify) => {}
+      None => {}
+    };
+    Ok(())
+  }
+
+  /// Stops receiving the audio of the previous track, if it is still being received. Audio outputs that are reading it
+  /// then fail to read past the audio that was received so far.
+  fn stop_receiving_audio(&self) {
+    if let Some(previous) = self.audio_receiver.lock().unwrap().take() {
+      previous.abort();
+    }
+  }
+
+  fn publish(&self, event: PlayerEvent) {
+    self.events.send(event).ok(); // OK: no subscribers -> we don't care.
+  }
+
+  /// Starts monitoring playback of the track with `track_id`, ending the monitoring of the previous track.
+  fn start_playback_monitor(&self, track_id: i32) {
+    let id = self.next_playback_monitor_id.fetch_add(1, Ordering::Relaxed);
+    let task = tokio::spawn(monitor_playback(self.audio_output.clone(), id, track_id, self.playback_monitor.clone(), self.events.clone()));
+    let previous = self.playback_monitor.lock().unwrap().replace(PlaybackMonitor { id, track_id, task });
+    if let Some(previous) = previous {
+      previous.task.abort();
+      self.publish(PlayerEvent::TrackEnded { track_id: previous.track_id, finished: false });
+    }
+    self.publish(PlayerEvent::TrackStarted(track_id));
+  }
+
+  /// Ends monitoring playback, as playback was stopped.
+  fn stop_playback_monitor(&self) {
+    if let Some(previous) = self.playback_monitor.lock().unwrap().take() {
+      previous.task.abort();
+      self.publish(PlayerEvent::TrackEnded { track_id: previous.track_id, finished: false });
+    }
+  }
+}
+
+/// Publishes the progress of playing the track with `track_id` until the audio output stops, then publishes that the
+/// track finished, unless monitoring was ended because another track started or playback was stopped.
+async fn monitor_playback<AO: AudioOutput>(
+  audio_output: AO,
+  id: u64,
+  track_id: i32,
+  playback_monitor: Arc<Mutex<Option<PlaybackMonitor>>>,
+  events: broadcast::Sender<PlayerEvent>,
+) {
+  // OK in sends: no subscribers -> we don't care.
+  let mut interval = tokio::time::interval(PLAYBACK_MONITOR_INTERVAL);
+  interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+  loop {
+    interval.tick().await;
+    match audio_output.is_stopped().await {
+      Ok(true) => break,
+      Ok(false) => {}
+      Err(e) => {
+        warn!(error = ?e, "Failed to check whether the audio output stopped; stopping playback monitoring");
+        break;
+      }
+    }
+    let position = audio_output.get_position().await.unwrap_or_else(|e| { warn!(error = ?e, "Failed to get playback position"); None });
+    let duration = audio_output.get_duration().await.unwrap_or_else(|e| { warn!(error = ?e, "Failed to get playback duration"); None });
+    let position_relative = audio_output.get_position_relative().await.unwrap_or_else(|e| { warn!(error = ?e, "Failed to get relative playback position"); None });
+    events.send(PlayerEvent::Progress { position, duration, position_relative }).ok();
+  }
+  // Only publish that the track finished if this monitor was not replaced in the meantime.
+  let mut playback_monitor = playback_monitor.lock().unwrap();
+  if playback_monitor.as_ref().map_or(false, |monitor| monitor.id == id) {
+    *playback_monitor = None;
+    events.send(PlayerEvent::TrackEnded { track_id, finished: true }).ok();
+  }
 }
 
 #[async_trait]
@@ -97,22 +344,69 @@ impl<C: Client, AO: AudioOutput> Player for GenericPlayer<C, AO> {
   async fn login(&self, user_login: &UserLogin) -> Result<User, Self::LoginError> {
     self.get_client().login(user_login).await
   }
+  async fn kiosk_login(&self, kiosk_token: &str) -> Result<User, Self::LoginError> {
+    self.get_client().kiosk_login(&KioskLogin { token: kiosk_token.to_string() }).await
+  }
 
 
-  type PlayError = PlayError<C::PlaybackError, AO::SetAudioDataError, AO::PlayError>;
+  type PlayError = PlayError<C::PlaybackError, AO::SetAudioDataError, AO::SetGainError, AO::PlayError>;
   async fn play_track_by_id(&self, id: i32) -> Result<(), Self::PlayError> {
     use PlayError::*;
-    use musium_core::api::PlaySource::*;
-    let play_source = self.get_client().play_track_by_id(id).await.map_err(|e| ClientPlayTrackFail(e))?;
-    match play_source {
-      Some(AudioData { codec, data }) => self.get_audio_output().set_audio_data(codec, data).await.map_err(|e| SetAudioDataFail(e))?,
-      Some(ExternallyPlayedOnSpotify) => {}
-      None => {}
-    };
+    // End monitoring the previous track before replacing its audio, so that it is not mistaken to have finished.
+    self.stop_playback_monitor();
+    self.publish(PlayerEvent::Buffering(true));
+    let result = self.set_track_audio(id).await;
+    self.publish(PlayerEvent::Buffering(false));
+    result?;
     self.get_audio_output().play().await.map_err(|e| AudioOutputPlayFail(e))?;
+    self.start_playback_monitor(id);
     Ok(())
   }
 
+  async fn play_album_shuffled(&self, album_id: i32) -> Result<TracksRaw, C::TrackError> {
+    self.get_client().list_tracks_to_play(&PlayTracksQuery { album_id: Some(album_id), artist_id: None, shuffle: true, downrank_skipped: self.is_downrank_skipped_enabled() }).await
+  }
+
+  async fn play_artist(&self, artist_id: i32) -> Result<TracksRaw, C::TrackError> {
+    self.get_client().list_tracks_to_play(&PlayTracksQuery { album_id: None, artist_id: Some(artist_id), shuffle: false, downrank_skipped: false }).await
+  }
+
+  async fn shuffle_all(&self, filter: &TrackFilter) -> Result<TracksRaw, C::TrackError> {
+    self.get_client().sample_random_tracks(Some(SHUFFLE_ALL_TRACKS), filter).await
+  }
+
+
+  fn is_album_radio_enabled(&self) -> bool {
+    self.album_radio_enabled.load(Ordering::Relaxed)
+  }
+
+  fn set_album_radio_enabled(&self, enabled: bool) {
+    self.album_radio_enabled.store(enabled, Ordering::Relaxed)
+  }
+
+  async fn get_album_radio_tracks(&self, played_track_ids: &[i32]) -> Result<TracksRaw, C::TrackError> {
+    let seed_start = played_track_ids.len().saturating_sub(ALBUM_RADIO_SEED_TRACKS);
+    let request = TrackRecommendationRequest {
+      seed_track_ids: played_track_ids[seed_start..].to_vec(),
+      exclude_track_ids: played_track_ids[..seed_start].to_vec(),
+      count: Some(ALBUM_RADIO_TRACKS),
+      downrank_skipped: self.is_downrank_skipped_enabled(),
+    };
+    let tracks = self.get_client().recommend_tracks(&request).await?;
+    if !tracks.tracks.is_empty() { return Ok(tracks); }
+    let mut tracks = self.get_client().sample_random_tracks(Some(ALBUM_RADIO_TRACKS), &TrackFilter::default()).await?;
+    tracks.tracks.retain(|t| !played_track_ids.contains(&t.id));
+    Ok(tracks)
+  }
+
+  fn is_downrank_skipped_enabled(&self) -> bool {
+    self.downrank_skipped_enabled.load(Ordering::Relaxed)
+  }
+
+  fn set_downrank_skipped_enabled(&self, enabled: bool) {
+    self.downrank_skipped_enabled.store(enabled, Ordering::Relaxed)
+  }
+
 
   async fn is_paused(&self) -> Result<bool, AO::IsPausedError> {
     self.get_audio_output().is_paused().await
@@ -131,7 +425,10 @@ impl<C: Client, AO: AudioOutput> Player for GenericPlayer<C, AO> {
   }
 
   async fn stop(&self) -> Result<(), AO::StopError> {
-    self.get_audio_output().stop().await
+    self.get_audio_output().stop().await?;
+    self.stop_playback_monitor();
+    self.stop_receiving_audio();
+    Ok(())
   }
 
   async fn get_position_relative(&self) -> Result<Option<f64>, AO::GetPositionRelativeError> {
@@ -147,25 +444,81 @@ impl<C: Client, AO: AudioOutput> Player for GenericPlayer<C, AO> {
   }
 
   async fn set_volume(&self, volume: f64) -> Result<(), AO::SetVolumeError> {
-    self.get_audio_output().set_volume(volume).await
+    // Limit to the maximum volume of when the volume limits were last enforced, and remember the requested volume so
+    // that it is restored when the limits are lifted, such as when quiet hours end.
+    let volume = {
+      let mut limited_volume = self.volume.lock().unwrap();
+      limited_volume.requested = volume.clamp(0.0, 1.0);
+      limited_volume.limited()
+    };
+    self.get_audio_output().set_volume(volume).await?;
+    self.publish(PlayerEvent::VolumeChanged(volume));
+    Ok(())
+  }
+
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), AO::SetChannelProcessingError> {
+    self.get_audio_output().set_channel_processing(processing).await
+  }
+
+
+  fn get_volume_limits(&self) -> VolumeLimits {
+    self.volume_limits.lock().unwrap().clone()
+  }
+
+  fn set_volume_limits(&self, limits: VolumeLimits) {
+    *self.volume_limits.lock().unwrap() = limits;
+  }
+
+  type EnforceVolumeLimitsError = EnforceVolumeLimitsError<AO::GetOutputDeviceNameError, AO::SetVolumeError, AO::SetFadeDurationError>;
+  async fn enforce_volume_limits(&self) -> Result<bool, Self::EnforceVolumeLimitsError> {
+    use EnforceVolumeLimitsError::*;
+    let output_device_name = self.get_audio_output().get_output_device_name().await.map_err(|e| GetOutputDeviceNameFail(e))?;
+    let (max_volume, fade_duration, is_quiet_hours) = {
+      let limits = self.volume_limits.lock().unwrap();
+      let is_quiet_hours = limits.is_quiet_hours_now();
+      (limits.max_volume(output_device_name.as_deref(), is_quiet_hours), limits.fade_duration(is_quiet_hours), is_quiet_hours)
+    };
+    let (volume, changed) = {
+      let mut limited_volume = self.volume.lock().unwrap();
+      let previous = limited_volume.limited();
+      limited_volume.max = max_volume;
+      (limited_volume.limited(), limited_volume.limited() != previous)
+    };
+    self.get_audio_output().set_volume(volume).await.map_err(|e| SetVolumeFail(e))?;
+    if changed {
+      self.publish(PlayerEvent::VolumeChanged(volume));
+    }
+    self.get_audio_output().set_fade_duration(fade_duration).await.map_err(|e| SetFadeDurationFail(e))?;
+    Ok(is_quiet_hours)
+  }
+
+
+  fn subscribe_player_events(&self) -> broadcast::Receiver<PlayerEvent> {
+    self.events.subscribe()
+  }
+
+  fn announce_queue_changed(&self, queued_track_ids: Vec<i32>) {
+    self.publish(PlayerEvent::QueueChanged(queued_track_ids));
   }
 }
 
 // Default player
 
 #[cfg(feature = "default_player")]
-pub type DefaultPlayer = GenericPlayer<HttpClient, KiraAudioOutput>;
+pub type DefaultPlayer = GenericPlayer<HttpClient, output::SelectedAudioOutput>;
 
 #[cfg(feature = "default_player")]
 #[derive(Debug, Error)]
 pub enum CreateError {
   #[error("Failed to create HTTP client")]
   ClientCreateFail(#[from] musium_client_http::HttpClientCreateError),
-  #[error("Failed to create Kira audio output")]
-  AudioOutputCreateFail(#[from] musium_audio_output_kira::KiraCreateError),
+  #[error("Failed to create audio output")]
+  AudioOutputCreateFail(#[from] output::SelectedAudioOutputCreateError),
 }
 
+/// Creates the default player, connecting to the server at `url`, and playing audio with the audio output of
+/// `audio_output`. Must not be called from an asynchronous context, as creating the audio output may block the thread.
 #[cfg(feature = "default_player")]
-pub fn create_default_player(url: Url) -> Result<DefaultPlayer, CreateError> {
-  Ok(DefaultPlayer::new(musium_client_http::HttpClient::new(url)?, musium_audio_output_kira::KiraAudioOutput::new()?))
+pub fn create_default_player(url: Url, audio_output: output::AudioOutputSelection) -> Result<DefaultPlayer, CreateError> {
+  Ok(DefaultPlayer::new(musium_client_http::HttpClient::new(url)?, output::SelectedAudioOutput::new(audio_output)?))
 }
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+use musium_audio_output::{AudioOutput, AudioSource, ChannelProcessing, PlaybackError};
+use musium_audio_output_kira::{KiraAudioOutput, KiraCreateError};
+use musium_audio_output_rodio::{RodioAudioOutput, RodioCreateError, RodioOptions};
+use musium_client_settings::{AudioOutputKind, AudioOutputSettings};
+use musium_core::api::AudioCodec;
+
+/// Audio output to play audio with, selected when creating the default player.
+#[derive(Copy, Clone, Debug)]
+pub enum AudioOutputSelection {
+  /// Kira audio output, which supports seeking, fading, and getting the playback position, but resamples audio to the
+  /// sample rate of the output device and decodes tracks entirely before playing them.
+  Kira,
+  /// Rodio audio output with options, which supports playing at the native sample rate, decoding ahead of playback, and
+  /// streaming tracks while playing them, but does not support seeking, fading, or getting the playback position.
+  Rodio(RodioOptions),
+}
+
+impl Default for AudioOutputSelection {
+  fn default() -> Self { Self::Kira }
+}
+
+impl From<&AudioOutputSettings> for AudioOutputSelection {
+  fn from(settings: &AudioOutputSettings) -> Self {
+    match settings.kind {
+      AudioOutputKind::Kira => Self::Kira,
+      AudioOutputKind::Rodio => Self::Rodio(RodioOptions {
+        native_sample_rate: settings.native_sample_rate,
+        decode_ahead: settings.decode_ahead_milliseconds.map(Duration::from_millis),
+      }),
+    }
+  }
+}
+
+/// Audio output that is either a Kira or a Rodio audio output, as selected with an `AudioOutputSelection`.
+#[derive(Clone, Debug)]
+pub enum SelectedAudioOutput {
+  Kira(KiraAudioOutput),
+  Rodio(RodioAudioOutput),
+}
+
+#[derive(Debug, Error)]
+pub enum SelectedAudioOutputCreateError {
+  #[error("Failed to create Kira audio output")]
+  KiraCreateFail(#[from] KiraCreateError),
+  #[error("Failed to create Rodio audio output")]
+  RodioCreateFail(#[from] RodioCreateError),
+}
+
+impl SelectedAudioOutput {
+  /// Creates the audio output of `selection`. Must not be called from an asynchronous context, as creating a Rodio audio
+  /// output blocks the thread.
+  pub fn new(selection: AudioOutputSelection) -> Result<Self, SelectedAudioOutputCreateError> {
+    let audio_output = match selection {
+      AudioOutputSelection::Kira => Self::Kira(KiraAudioOutput::new()?),
+      AudioOutputSelection::Rodio(options) => Self::Rodio(RodioAudioOutput::new_blocking_with_options(options)?),
+    };
+    Ok(audio_output)
+  }
+}
+
+/// Error of the Kira audio output, or of the Rodio audio output.
+#[derive(Debug, Error)]
+pub enum SelectedAudioOutputError<K, R> {
+  #[error(transparent)]
+  Kira(K),
+  #[error(transparent)]
+  Rodio(R),
+}
+
+type SelectedError<K, R> = SelectedAudioOutputError<K, R>;
+
+/// Calls `$call` on the selected audio output bound to `$output`, and maps its error into a `SelectedAudioOutputError`.
+macro_rules! delegate {
+  ($self:ident, $output:ident => $call:expr) => {
+    match $self {
+      SelectedAudioOutput::Kira($output) => $call.await.map_err(SelectedAudioOutputError::Kira),
+      SelectedAudioOutput::Rodio($output) => $call.await.map_err(SelectedAudioOutputError::Rodio),
+    }
+  };
+}
+
+#[async_trait]
+impl AudioOutput for SelectedAudioOutput {
+  type SetAudioDataError = SelectedError<<KiraAudioOutput as AudioOutput>::SetAudioDataError, <RodioAudioOutput as AudioOutput>::SetAudioDataError>;
+  async fn set_audio_source(&self, codec: Option<AudioCodec>, source: AudioSource) -> Result<(), Self::SetAudioDataError> {
+    delegate!(self, output => output.set_audio_source(codec, source))
+  }
+
+
+  type IsPlayingError = SelectedError<<KiraAudioOutput as AudioOutput>::IsPlayingError, <RodioAudioOutput as AudioOutput>::IsPlayingError>;
+  async fn is_playing(&self) -> Result<bool, Self::IsPlayingError> {
+    delegate!(self, output => output.is_playing())
+  }
+
+  type PlayError = SelectedError<<KiraAudioOutput as AudioOutput>::PlayError, <RodioAudioOutput as AudioOutput>::PlayError>;
+  async fn play(&self) -> Result<(), Self::PlayError> {
+    delegate!(self, output => output.play())
+  }
+
+
+  type IsPausedError = SelectedError<<KiraAudioOutput as AudioOutput>::IsPausedError, <RodioAudioOutput as AudioOutput>::IsPausedError>;
+  async fn is_paused(&self) -> Result<bool, Self::IsPausedError> {
+    delegate!(self, output => output.is_paused())
+  }
+
+  type PauseError = SelectedError<<KiraAudioOutput as AudioOutput>::PauseError, <RodioAudioOutput as AudioOutput>::PauseError>;
+  async fn pause(&self) -> Result<(), Self::PauseError> {
+    delegate!(self, output => output.pause())
+  }
+
+
+  type TogglePlayError = SelectedError<<KiraAudioOutput as AudioOutput>::TogglePlayError, <RodioAudioOutput as AudioOutput>::TogglePlayError>;
+  async fn toggle_play(&self) -> Result<bool, Self::TogglePlayError> {
+    delegate!(self, output => output.toggle_play())
+  }
+
+
+  type IsStoppedError = SelectedError<<KiraAudioOutput as AudioOutput>::IsStoppedError, <RodioAudioOutput as AudioOutput>::IsStoppedError>;
+  async fn is_stopped(&self) -> Result<bool, Self::IsStoppedError> {
+    delegate!(self, output => output.is_stopped())
+  }
+
+  type StopError = SelectedError<<KiraAudioOutput as AudioOutput>::StopError, <RodioAudioOutput as AudioOutput>::StopError>;
+  async fn stop(&self) -> Result<(), Self::StopError> {
+    delegate!(self, output => output.stop())
+  }
+
+
+  type GetDurationError = SelectedError<<KiraAudioOutput as AudioOutput>::GetDurationError, <RodioAudioOutput as AudioOutput>::GetDurationError>;
+  async fn get_duration(&self) -> Result<Option<f64>, Self::GetDurationError> {
+    delegate!(self, output => output.get_duration())
+  }
+
+  type GetPositionError = SelectedError<<KiraAudioOutput as AudioOutput>::GetPositionError, <RodioAudioOutput as AudioOutput>::GetPositionError>;
+  async fn get_position(&self) -> Result<Option<f64>, Self::GetPositionError> {
+    delegate!(self, output => output.get_position())
+  }
+
+  type SeekToError = SelectedError<<KiraAudioOutput as AudioOutput>::SeekToError, <RodioAudioOutput as AudioOutput>::SeekToError>;
+  async fn seek_to(&self, position: f64) -> Result<(), Self::SeekToError> {
+    delegate!(self, output => output.seek_to(position))
+  }
+
+  type GetPositionRelativeError = SelectedError<<KiraAudioOutput as AudioOutput>::GetPositionRelativeError, <RodioAudioOutput as AudioOutput>::GetPositionRelativeError>;
+  async fn get_position_relative(&self) -> Result<Option<f64>, Self::GetPositionRelativeError> {
+    delegate!(self, output => output.get_position_relative())
+  }
+
+  type SeekToRelativeError = SelectedError<<KiraAudioOutput as AudioOutput>::SeekToRelativeError, <RodioAudioOutput as AudioOutput>::SeekToRelativeError>;
+  async fn seek_to_relative(&self, position_relative: f64) -> Result<(), Self::SeekToRelativeError> {
+    delegate!(self, output => output.seek_to_relative(position_relative))
+  }
+
+
+  type GetVolumeError = SelectedError<<KiraAudioOutput as AudioOutput>::GetVolumeError, <RodioAudioOutput as AudioOutput>::GetVolumeError>;
+  async fn get_volume(&self) -> Result<f64, Self::GetVolumeError> {
+    delegate!(self, output => output.get_volume())
+  }
+
+  type SetVolumeError = SelectedError<<KiraAudioOutput as AudioOutput>::SetVolumeError, <RodioAudioOutput as AudioOutput>::SetVolumeError>;
+  async fn set_volume(&self, volume: f64) -> Result<(), Self::SetVolumeError> {
+    delegate!(self, output => output.set_volume(volume))
+  }
+
+  type SetGainError = SelectedError<<KiraAudioOutput as AudioOutput>::SetGainError, <RodioAudioOutput as AudioOutput>::SetGainError>;
+  async fn set_gain(&self, gain: f64) -> Result<(), Self::SetGainError> {
+    delegate!(self, output => output.set_gain(gain))
+  }
+
+  type SetChannelProcessingError = SelectedError<<KiraAudioOutput as AudioOutput>::SetChannelProcessingError, <RodioAudioOutput as AudioOutput>::SetChannelProcessingError>;
+  async fn set_channel_processing(&self, processing: ChannelProcessing) -> Result<(), Self::SetChannelProcessingError> {
+    delegate!(self, output => output.set_channel_processing(processing))
+  }
+
+  type SetFadeDurationError = SelectedError<<KiraAudioOutput as AudioOutput>::SetFadeDurationError, <RodioAudioOutput as AudioOutput>::SetFadeDurationError>;
+  async fn set_fade_duration(&self, fade_duration: f64) -> Result<(), Self::SetFadeDurationError> {
+    delegate!(self, output => output.set_fade_duration(fade_duration))
+  }
+
+  type GetOutputDeviceNameError = SelectedError<<KiraAudioOutput as AudioOutput>::GetOutputDeviceNameError, <RodioAudioOutput as AudioOutput>::GetOutputDeviceNameError>;
+  async fn get_output_device_name(&self) -> Result<Option<String>, Self::GetOutputDeviceNameError> {
+    delegate!(self, output => output.get_output_device_name())
+  }
+
+  fn subscribe_playback_errors(&self) -> broadcast::Receiver<PlaybackError> {
+    match self {
+      SelectedAudioOutput::Kira(output) => output.subscribe_playback_errors(),
+      SelectedAudioOutput::Rodio(output) => output.subscribe_playback_errors(),
+    }
+  }
+}
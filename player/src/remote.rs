@@ -0,0 +1,206 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{debug, error, warn};
+
+/// Command sent to a running player instance through its local control socket. Commands and replies are single lines
+/// of text, so that the socket can also be used from scripts with tools such as `socat`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RemoteCommand {
+  Play,
+  Pause,
+  Next,
+  Status,
+}
+
+impl RemoteCommand {
+  pub const NAMES: &'static [&'static str] = &["play", "pause", "next", "status"];
+}
+
+impl Display for RemoteCommand {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RemoteCommand::Play => f.write_str("play"),
+      RemoteCommand::Pause => f.write_str("pause"),
+      RemoteCommand::Next => f.write_str("next"),
+      RemoteCommand::Status => f.write_str("status"),
+    }
+  }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown remote command '{0}'; expected one of: play, pause, next, status")]
+pub struct ParseRemoteCommandError(String);
+
+impl FromStr for RemoteCommand {
+  type Err = ParseRemoteCommandError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim() {
+      "play" => Ok(RemoteCommand::Play),
+      "pause" => Ok(RemoteCommand::Pause),
+      "next" => Ok(RemoteCommand::Next),
+      "status" => Ok(RemoteCommand::Status),
+      s => Err(ParseRemoteCommandError(s.to_string())),
+    }
+  }
+}
+
+/// Playback status of a player instance, replied to the `status` command.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct RemoteStatus {
+  /// Description of the track that is playing, or `None` if playback is stopped.
+  pub playing_track: Option<String>,
+  pub is_paused: bool,
+  /// Number of tracks queued after the playing track.
+  pub queue_length: usize,
+}
+
+impl Display for RemoteStatus {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.playing_track {
+      Some(track) if self.is_paused => write!(f, "paused: {}", track)?,
+      Some(track) => write!(f, "playing: {}", track)?,
+      None => f.write_str("stopped")?,
+    }
+    write!(f, " ({} queued)", self.queue_length)
+  }
+}
+
+/// Gets the default path of the local control socket: `musium-player.sock` in `$XDG_RUNTIME_DIR`, or in the temporary
+/// directory if that is not set.
+pub fn default_remote_socket_path() -> PathBuf {
+  std::env::var_os("XDG_RUNTIME_DIR")
+    .map(PathBuf::from)
+    .unwrap_or_else(std::env::temp_dir)
+    .join("musium-player.sock")
+}
+
+#[derive(Debug, Error)]
+pub enum RemoteControlError {
+  #[error("Remote control through a local socket is not supported on this platform")]
+  Unsupported,
+  #[error("Another player instance is already listening on local control socket {0:?}")]
+  AlreadyListening(PathBuf),
+  #[error("Failed to bind or connect to local control socket {1:?}")]
+  SocketFail(#[source] std::io::Error, PathBuf),
+  #[error("Failed to send remote command or receive its reply")]
+  IoFail(#[from] std::io::Error),
+  #[error("Player instance failed to execute remote command: {0}")]
+  CommandFail(String),
+}
+
+// Server
+
+/// Local control socket of a running player instance. Received commands, except `status` which is replied to with the
+/// status set by `set_status`, are received through `subscribe`. The socket file is removed when this is dropped.
+pub struct RemoteControlServer {
+  commands: broadcast::Sender<RemoteCommand>,
+  status: Arc<Mutex<RemoteStatus>>,
+  path: PathBuf,
+}
+
+impl RemoteControlServer {
+  /// Listens on the local control socket at `path`, replacing a stale socket file left behind by a player instance that
+  /// did not exit cleanly. Fails if another player instance is listening at `path`.
+  #[cfg(unix)]
+  pub fn listen(path: impl Into<PathBuf>) -> Result<Self, RemoteControlError> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    let path = path.into();
+    // Only replace sockets, to not remove other files when a wrong path is given.
+    if std::fs::symlink_metadata(&path).map_or(false, |metadata| metadata.file_type().is_socket()) {
+      if UnixStream::connect(&path).is_ok() {
+        return Err(RemoteControlError::AlreadyListening(path));
+      }
+      std::fs::remove_file(&path).map_err(|e| RemoteControlError::SocketFail(e, path.clone()))?;
+    }
+    let listener = UnixListener::bind(&path).map_err(|e| RemoteControlError::SocketFail(e, path.clone()))?;
+    let (commands, _) = broadcast::channel(16);
+    let status = Arc::new(Mutex::new(RemoteStatus::default()));
+    {
+      let commands = commands.clone();
+      let status = status.clone();
+      std::thread::Builder::new()
+        .name("remote control".to_string())
+        .spawn(move || {
+          for stream in listener.incoming() {
+            match stream {
+              Ok(stream) => if let Err(e) = handle_connection(&stream, &stream, &commands, &status) {
+                warn!("Failed to handle remote control connection: {:?}", e);
+              }
+              Err(e) => error!("Failed to accept remote control connection: {:?}", e),
+            }
+          }
+        })?;
+    }
+    debug!("Listening for remote commands on local control socket {:?}", path);
+    Ok(Self { commands, status, path })
+  }
+
+  #[cfg(not(unix))]
+  pub fn listen(_path: impl Into<PathBuf>) -> Result<Self, RemoteControlError> {
+    Err(RemoteControlError::Unsupported)
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<RemoteCommand> {
+    self.commands.subscribe()
+  }
+
+  /// Sets the status that is replied to the `status` command.
+  pub fn set_status(&self, status: RemoteStatus) {
+    // UNWRAP: errors if a holder of the lock has panicked -> we panic as well.
+    *self.status.lock().unwrap() = status;
+  }
+
+  pub fn path(&self) -> &Path { &self.path }
+}
+
+impl Drop for RemoteControlServer {
+  fn drop(&mut self) {
+    std::fs::remove_file(&self.path).ok(); // OK: socket file already removed -> we don't care.
+  }
+}
+
+/// Reads one command from `reader` and writes its reply to `writer`.
+fn handle_connection(reader: impl std::io::Read, mut writer: impl Write, commands: &broadcast::Sender<RemoteCommand>, status: &Mutex<RemoteStatus>) -> std::io::Result<()> {
+  let mut line = String::new();
+  BufReader::new(reader).read_line(&mut line)?;
+  let reply = match line.parse() {
+    // UNWRAP: errors if a holder of the lock has panicked -> we panic as well.
+    Ok(RemoteCommand::Status) => status.lock().unwrap().to_string(),
+    Ok(command) => match commands.send(command) {
+      Ok(_) => "ok".to_string(),
+      Err(_) => "error: player is not ready to receive commands".to_string(),
+    }
+    Err(e) => format!("error: {}", e),
+  };
+  writeln!(writer, "{}", reply)
+}
+
+// Client
+
+/// Sends `command` to the player instance listening on the local control socket at `path`, returning its reply.
+#[cfg(unix)]
+pub fn send_remote_command(path: impl AsRef<Path>, command: RemoteCommand) -> Result<String, RemoteControlError> {
+  use std::os::unix::net::UnixStream;
+  let path = path.as_ref();
+  let mut stream = UnixStream::connect(path).map_err(|e| RemoteControlError::SocketFail(e, path.to_path_buf()))?;
+  writeln!(stream, "{}", command)?;
+  let mut reply = String::new();
+  BufReader::new(&stream).read_line(&mut reply)?;
+  let reply = reply.trim_end();
+  match reply.strip_prefix("error: ") {
+    Some(message) => Err(RemoteControlError::CommandFail(message.to_string())),
+    None => Ok(reply.to_string()),
+  }
+}
+
+#[cfg(not(unix))]
+pub fn send_remote_command(_path: impl AsRef<Path>, _command: RemoteCommand) -> Result<String, RemoteControlError> {
+  Err(RemoteControlError::Unsupported)
+}
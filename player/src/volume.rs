@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use musium_core::api::QuietHours;
+
+/// Limits on the volume of a player, which are enforced regardless of the volume that clients request.
+#[derive(Clone, PartialEq, Debug)]
+pub struct VolumeLimits {
+  /// Maximum volume between 0.0 and 1.0 per output device, by name of the output device.
+  pub output_max_volumes: HashMap<String, f64>,
+  /// Daily window during which the volume is limited to `quiet_hours_max_volume`, or `None` to have no quiet hours.
+  pub quiet_hours: Option<QuietHours>,
+  /// Maximum volume between 0.0 and 1.0 during quiet hours.
+  pub quiet_hours_max_volume: f64,
+  /// Duration in seconds of fades when pausing, resuming, and stopping outside of quiet hours.
+  pub fade_duration: f64,
+  /// Duration in seconds of fades when pausing, resuming, and stopping during quiet hours.
+  pub quiet_hours_fade_duration: f64,
+}
+
+impl VolumeLimits {
+  /// Gets the maximum volume when playing on the output device with `output_device_name`.
+  pub fn max_volume(&self, output_device_name: Option<&str>, is_quiet_hours: bool) -> f64 {
+    let output_max_volume = output_device_name
+      .and_then(|name| self.output_max_volumes.get(name))
+      .copied()
+      .unwrap_or(1.0);
+    let quiet_hours_max_volume = if is_quiet_hours { self.quiet_hours_max_volume } else { 1.0 };
+    output_max_volume.min(quiet_hours_max_volume).clamp(0.0, 1.0)
+  }
+
+  pub fn fade_duration(&self, is_quiet_hours: bool) -> f64 {
+    if is_quiet_hours { self.quiet_hours_fade_duration } else { self.fade_duration }
+  }
+
+  /// Returns whether the current local time is within the quiet hours.
+  pub fn is_quiet_hours_now(&self) -> bool {
+    self.quiet_hours.map_or(false, |quiet_hours| quiet_hours.is_active_now())
+  }
+}
+
+impl Default for VolumeLimits {
+  fn default() -> Self {
+    Self {
+      output_max_volumes: HashMap::new(),
+      quiet_hours: None,
+      quiet_hours_max_volume: 0.3,
+      fade_duration: 0.0,
+      quiet_hours_fade_duration: 3.0,
+    }
+  }
+}
+
+/// Maximum volume of the output device with `output_device_name`, parsed from `<output device name>=<max volume>`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OutputMaxVolume {
+  pub output_device_name: String,
+  pub max_volume: f64,
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid output maximum volume '{0}'; expected an output device name and a volume between 0.0 and 1.0, such as 'Speakers=0.5'")]
+pub struct ParseOutputMaxVolumeError(String);
+
+impl FromStr for OutputMaxVolume {
+  type Err = ParseOutputMaxVolumeError;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    // Split at the last `=`, as output device names may contain `=`.
+    s.rsplit_once('=')
+      .and_then(|(name, max_volume)| Some((name, max_volume.trim().parse::<f64>().ok()?)))
+      .filter(|(name, max_volume)| !name.is_empty() && (0.0..=1.0).contains(max_volume))
+      .map(|(name, max_volume)| OutputMaxVolume { output_device_name: name.to_string(), max_volume })
+      .ok_or_else(|| ParseOutputMaxVolumeError(s.to_string()))
+  }
+}
@@ -107,7 +107,7 @@ impl<C: Client, AO: AudioOutput> WorkerTask<C, AO> {
     use musium_core::api::PlaySource::*;
     let play_source = self.client.play_track_by_id(id).await.map_err(|e| ClientPlayTrackFail(e))?;
     match play_source {
-      Some(AudioData { codec, data }) => self.audio_output.set_audio_data(codec, data).await.map_err(|e| SetAudioDataFail(e))?,
+      Some(AudioData { codec, data, .. }) => self.audio_output.set_audio_data(codec, data).await.map_err(|e| SetAudioDataFail(e))?,
       Some(ExternallyPlayedOnSpotify) => {}
       None => {}
     };
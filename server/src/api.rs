@@ -1,27 +1,126 @@
 use std::backtrace::Backtrace;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
 use std::num::ParseIntError;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use actix_files::NamedFile;
-use actix_web::{Either, http, HttpRequest, HttpResponse, ResponseError, web};
-use actix_web::error::UrlGenerationError;
+use actix_web::{http, HttpRequest, HttpResponse, ResponseError, web};
+use actix_web::error::{PayloadError, UrlGenerationError};
 use actix_web::http::StatusCode;
 use actix_web::web::Query;
+use chrono::Utc;
 use serde::Deserialize;
 use thiserror::Error;
+use tokio_stream::StreamExt;
 use tracing::{event, Level};
 
-use musium_backend::database::{Database, DatabaseConnectError, DatabaseQueryError, user::UserAddVerifyError};
+use musium_backend::database::{Database, DatabaseConnectError, DatabaseConnection, DatabaseQueryError, user::{DEFAULT_INVITE_VALIDITY_HOURS, UserAddVerifyError}};
+use musium_backend::cover::CoverFetchError;
+use musium_backend::database::audio_features::AnalyzeAudioFeaturesError;
+use musium_backend::database::content_filter::SetContentFilterError;
+use musium_backend::database::cover::AlbumCoverError;
+use musium_backend::database::media_cache::MediaVariantError;
 use musium_backend::database::playback::{BackendPlaySource, PlayError};
+use musium_backend::database::playlist::PlaylistError;
+use musium_backend::database::relink::RelinkError;
+use musium_backend::database::source::local::RelocateLocalSourceError;
 use musium_backend::database::source::spotify;
+use musium_backend::database::sync::merge::ResolveMetadataConflictError;
+use musium_backend::database::sync::preview::SyncPreviewError;
+use musium_backend::database::track_issue::ValidateLocalTracksError;
+use musium_backend::database::track_relation::TrackRelationError;
+use musium_backend::database::track_upload::TrackUploadError;
+use musium_backend::database::undo::UndoError;
+use musium_backend::database::user_preference::UserPreferencesError;
+use musium_backend::database::lyrics::TrackLyricsError;
+use musium_backend::database::managed_library::{OrganizeManagedLibraryError, OrganizeScope};
+use musium_backend::database::waveform::TrackWaveformError;
+use musium_backend::event::{EventBus, wait_for_event};
+use musium_backend::listening_session::{ListeningSessionError, ListeningSessions};
+use musium_backend::managed_library::ManagedLibrarySettings;
+use musium_backend::party::{Parties, PartyError};
 use musium_backend::sync::{SyncClient, SyncClientError};
-use musium_core::api::InternalServerError;
-use musium_core::model::{NewLocalSource, NewUser};
+use musium_backend::webhook::{check_webhook_url, WebhookUrlError};
+use musium_backend::year_review::render_year_review_html;
+use musium_core::api::{ALBUM_ID_HEADER, AlbumCoverFetch, API_VERSION, ARTWORK_SIZES, ArtworkQuery, Capabilities, AlbumCoverUpload, AlbumSplit, AudioFeaturesFilter, Event, EventsQuery, HiddenFilter, HomeAssistantCommand, HomeAssistantPlayerState, HomeAssistantState, InternalServerError, ListeningSessionCommand, LocalSourceRelocation, MaintenanceStatus, MetadataConflictResolution, NewListeningSession, NewParty, NewPartyGuest, NewTrackRelation, NewUserInviteProperties, PageQuery, PlaylistProperties, PlayTracksQuery, RandomTracksQuery, ServerInfo, SignedTrackUrl, SkipReport, SortNameOverride, SpotifySourceInfo, TrackArtistsQuery, TrackFilter, TrackRatings, TrackRecommendationRequest, TrackSearch, TrackSort, TrackUpload, UserPreferences, UserStatsQuery, WaitQuery, WebhookInfo, YearReviewQuery};
+use musium_core::model::{ContentFilter, HiddenItemKind, MediaBlob, MetadataField, MetadataMergePolicySetting, NewLocalSource, NewPlaylist, NewUser, NewWebhook, NotificationPreference, PlaybackPreference, UserContentFilter};
 
-use crate::auth::LoggedInUser;
+use crate::api_version::MIN_API_VERSION;
+use crate::auth::{Admins, FilePathAccess, LoggedInGuest, LoggedInUser};
+use crate::health::BackgroundWorkers;
+use crate::event::{EventSocket, sse_stream};
+use crate::maintenance::{MaintenanceError, MaintenanceMode};
+use crate::public_url::PublicUrl;
+use crate::url_signing::{SignedTrackQuery, UrlSigner};
 
 // TODO: all async functions that touch the database are blocking! this should not be the case!
 
+// Server info
+
+/// Names of the features the server supports that were added after the first version of the API, so that clients can
+/// hide features that an older server does not support.
+const CAPABILITIES: &[&str] = &[
+  "album_cover",
+  "alphabet_index",
+  "artist_info",
+  "audio_features",
+  "classical",
+  "content_filter",
+  "hidden_items",
+  "home_assistant",
+  "kiosk_login",
+  "listening_session",
+  "lyrics",
+  "maintenance",
+  "managed_library",
+  "media_cache",
+  "metadata_merge",
+  "notifications",
+  "party",
+  "play_tracks",
+  "playlist_mixes",
+  "poll_events",
+  "random_tracks",
+  "recommendations",
+  "registration",
+  "replay_gain",
+  "skip_tracking",
+  "sort_names",
+  "sse_events",
+  "sync_history",
+  "sync_preview",
+  "track_credits",
+  "track_issues",
+  "track_pages",
+  "track_query",
+  "track_technical_info",
+  "track_upload",
+  "track_versions",
+  "undo",
+  "user_stats",
+  "waveform",
+  "webhooks",
+  "year_review",
+];
+
+pub async fn show_server_info() -> HttpResponse {
+  HttpResponse::Ok().json(ServerInfo {
+    version: env!("CARGO_PKG_VERSION").to_string(),
+    api_version: API_VERSION,
+    min_api_version: MIN_API_VERSION,
+    capabilities: CAPABILITIES.iter().map(|capability| capability.to_string()).collect(),
+  })
+}
+
+pub async fn show_capabilities(
+  capabilities: web::Data<Capabilities>,
+) -> HttpResponse {
+  HttpResponse::Ok().json(capabilities.get_ref())
+}
+
 // Local source
 
 pub(crate) async fn list_local_sources(
@@ -32,6 +131,14 @@ pub(crate) async fn list_local_sources(
   Ok(HttpResponse::Ok().json(local_sources))
 }
 
+pub(crate) async fn list_deleted_local_sources(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let local_sources = database.connect()?.list_deleted_local_sources()?;
+  Ok(HttpResponse::Ok().json(local_sources))
+}
+
 pub(crate) async fn show_local_source_by_id(
   id: web::Path<i32>,
   database: web::Data<Database>,
@@ -58,14 +165,60 @@ pub(crate) async fn set_local_source_enabled(
   Ok(HttpResponse::Ok().json(database.connect()?.set_local_source_enabled_by_id(*id, *enabled)?))
 }
 
+pub(crate) async fn set_local_source_skip_if_unavailable(
+  id: web::Path<i32>,
+  skip_if_unavailable: web::Json<bool>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.set_local_source_skip_if_unavailable_by_id(*id, *skip_if_unavailable)?))
+}
+
+pub(crate) async fn relocate_local_source(
+  id: web::Path<i32>,
+  relocation: web::Json<LocalSourceRelocation>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  Ok(HttpResponse::Ok().json(database.connect()?.relocate_local_source_by_id(*id, &relocation)?))
+}
+
+pub(crate) async fn delete_local_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.delete_local_source_by_id(*id, logged_in_user.user.id)?))
+}
+
+pub(crate) async fn restore_local_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.restore_local_source_by_id(*id)?))
+}
+
 // Spotify source
 
 pub(crate) async fn list_spotify_sources(
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let local_sources = database.connect()?.list_spotify_sources()?;
-  Ok(HttpResponse::Ok().json(local_sources))
+  let spotify_sources: Vec<SpotifySourceInfo> = database.connect()?.list_spotify_sources()?.into_iter().map(Into::into).collect();
+  Ok(HttpResponse::Ok().json(spotify_sources))
+}
+
+pub(crate) async fn list_deleted_spotify_sources(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let spotify_sources: Vec<SpotifySourceInfo> = database.connect()?.list_deleted_spotify_sources()?.into_iter().map(Into::into).collect();
+  Ok(HttpResponse::Ok().json(spotify_sources))
 }
 
 pub(crate) async fn show_spotify_source_by_id(
@@ -73,17 +226,27 @@ pub(crate) async fn show_spotify_source_by_id(
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let local_source = database.connect()?.get_spotify_source_by_id(*id)?;
-  Ok(HttpResponse::Ok().json(local_source))
+  let spotify_source = database.connect()?.get_spotify_source_by_id(*id)?.map(SpotifySourceInfo::from);
+  Ok(HttpResponse::Ok().json(spotify_source))
+}
+
+/// Gets the URI that Spotify redirects to after authorizing: the redirect URI in `capabilities` if it was given or derived
+/// from the public URL at startup, or the URL of the authorization callback as reached by `request` otherwise.
+fn spotify_redirect_uri(request: &HttpRequest, capabilities: &Capabilities) -> Result<String, InternalError> {
+  match &capabilities.spotify_redirect_uri {
+    Some(redirect_uri) => Ok(redirect_uri.clone()),
+    None => Ok(request.url_for_static("spotify_authorization_callback").map_err(|e| InternalError::UrlGenerationFail(e))?.to_string()),
+  }
 }
 
 pub(crate) async fn request_spotify_authorization(
   request: HttpRequest,
   database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
   logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
   use InternalError::*;
-  let redirect_uri = request.url_for_static("spotify_authorization_callback").map_err(|e| UrlGenerationFail(e))?.to_string();
+  let redirect_uri = spotify_redirect_uri(&request, &capabilities)?;
   // TODO: do not use user ID as state, since it is easily guessable.
   let url = database.connect()?.create_spotify_authorization_url(&logged_in_user.user, redirect_uri, Some(format!("{}", logged_in_user.user.id)))?;
   Ok(HttpResponse::TemporaryRedirect().append_header((http::header::LOCATION, url)).finish())
@@ -100,15 +263,16 @@ pub(crate) async fn spotify_authorization_callback(
   request: HttpRequest,
   query: Query<SpotifyCallbackData>,
   database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
   //logged_in_user: LoggedInUser, // TODO: require a logged-in user.
 ) -> Result<HttpResponse, InternalError> {
   use InternalError::*;
   match query.into_inner() {
     SpotifyCallbackData { code: Some(code), error: None, state: Some(state) } => {
-      let redirect_uri = request.url_for_static("spotify_authorization_callback").map_err(|e| UrlGenerationFail(e))?.to_string();
+      let redirect_uri = spotify_redirect_uri(&request, &capabilities)?;
       let user_id = i32::from_str(&state)?; // TODO: do not abuse state to carry the user ID.
       let spotify_source = database.connect()?.create_spotify_source_from_authorization_callback(user_id, code, redirect_uri, Some(state)).await?;
-      Ok(HttpResponse::Ok().json(spotify_source))
+      Ok(HttpResponse::Ok().json(SpotifySourceInfo::from(spotify_source)))
     }
     SpotifyCallbackData { error: Some(error), .. } => {
       Err(SpotifyAuthorizationCallbackFail(error))
@@ -125,7 +289,23 @@ pub(crate) async fn set_spotify_source_enabled(
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  Ok(HttpResponse::Ok().json(database.connect()?.set_spotify_source_enabled_by_id(*id, *enabled)?))
+  Ok(HttpResponse::Ok().json(database.connect()?.set_spotify_source_enabled_by_id(*id, *enabled)?.map(SpotifySourceInfo::from)))
+}
+
+pub(crate) async fn delete_spotify_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.delete_spotify_source_by_id(*id, logged_in_user.user.id)?.map(SpotifySourceInfo::from)))
+}
+
+pub(crate) async fn restore_spotify_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.restore_spotify_source_by_id(*id)?.map(SpotifySourceInfo::from)))
 }
 
 pub(crate) async fn show_spotify_me(
@@ -136,13 +316,69 @@ pub(crate) async fn show_spotify_me(
   Ok(HttpResponse::Ok().json(me_info))
 }
 
+// Spotify write back
+
+pub(crate) async fn save_spotify_album(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !capabilities.spotify_write_back { return Err(InternalError::SpotifyWriteBackDisabledFail); }
+  let saved = database.connect()?.save_spotify_album(*id, &logged_in_user.user).await?;
+  Ok(if saved { HttpResponse::NoContent().finish() } else { HttpResponse::NotFound().finish() })
+}
+
+pub(crate) async fn remove_saved_spotify_album(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !capabilities.spotify_write_back { return Err(InternalError::SpotifyWriteBackDisabledFail); }
+  let removed = database.connect()?.remove_saved_spotify_album(*id, &logged_in_user.user).await?;
+  Ok(if removed { HttpResponse::NoContent().finish() } else { HttpResponse::NotFound().finish() })
+}
+
+pub(crate) async fn follow_spotify_artist(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !capabilities.spotify_write_back { return Err(InternalError::SpotifyWriteBackDisabledFail); }
+  let followed = database.connect()?.follow_spotify_artist(*id, &logged_in_user.user).await?;
+  Ok(if followed { HttpResponse::NoContent().finish() } else { HttpResponse::NotFound().finish() })
+}
+
+pub(crate) async fn add_tracks_to_spotify_playlist(
+  id: web::Path<String>,
+  track_ids: web::Json<Vec<i32>>,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !capabilities.spotify_write_back { return Err(InternalError::SpotifyWriteBackDisabledFail); }
+  let result = database.connect()?.add_tracks_to_spotify_playlist(&id, &track_ids, &logged_in_user.user).await?;
+  Ok(HttpResponse::Ok().json(result))
+}
+
 // Albums
 
 pub async fn list_albums(
+  filter: Query<HiddenFilter>,
   database: web::Data<Database>,
-  _logged_in_user: LoggedInUser,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_albums(logged_in_user.user.id, *filter)?))
+}
+
+pub async fn show_album_alphabet_index(
+  filter: Query<HiddenFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  Ok(HttpResponse::Ok().json(database.connect()?.list_albums()?))
+  Ok(HttpResponse::Ok().json(database.connect()?.get_album_alphabet_index(logged_in_user.user.id, *filter)?))
 }
 
 pub async fn show_album_by_id(
@@ -163,212 +399,1850 @@ pub async fn list_tracks(
   Ok(HttpResponse::Ok().json(database.connect()?.list_tracks()?))
 }
 
-pub async fn show_track_by_id(
-  id: web::Path<i32>,
+pub async fn list_tracks_page(
+  page: Query<PageQuery>,
+  sort: Query<TrackSort>,
+  filter: Query<TrackFilter>,
   database: web::Data<Database>,
-  _logged_in_user: LoggedInUser,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let track = database.connect()?.get_track_by_id(*id)?;
-  Ok(HttpResponse::Ok().json(track))
+  Ok(HttpResponse::Ok().json(database.connect()?.list_tracks_page(logged_in_user.user.id, *page, *sort, &filter)?))
 }
 
-// Artist
+pub async fn sample_random_tracks(
+  random: Query<RandomTracksQuery>,
+  filter: Query<TrackFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.sample_random_tracks(logged_in_user.user.id, random.count, &filter)?))
+}
 
-pub async fn list_artists(
+pub async fn list_tracks_to_play(
+  query: Query<PlayTracksQuery>,
   database: web::Data<Database>,
-  _logged_in_user: LoggedInUser,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  Ok(HttpResponse::Ok().json(database.connect()?.list_artists()?))
+  Ok(HttpResponse::Ok().json(database.connect()?.list_tracks_to_play(logged_in_user.user.id, *query)?))
 }
 
-pub async fn show_artist_by_id(
+pub async fn report_track_skip(
   id: web::Path<i32>,
+  report: web::Json<SkipReport>,
   database: web::Data<Database>,
-  _logged_in_user: LoggedInUser,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let artist = database.connect()?.get_artist_by_id(*id)?;
-  Ok(HttpResponse::Ok().json(artist))
+  Ok(HttpResponse::Ok().json(database.connect()?.report_skip(logged_in_user.user.id, *id, report.position_relative)?))
 }
 
-// Playback
+pub async fn recommend_tracks(
+  request: web::Json<TrackRecommendationRequest>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.recommend_tracks(logged_in_user.user.id, &request)?))
+}
 
-pub async fn show_track_play_source_kind(
+pub async fn query_tracks_page(
+  page: Query<PageQuery>,
+  sort: Query<TrackSort>,
+  filter: web::Json<TrackFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_tracks_page(logged_in_user.user.id, *page, *sort, &filter)?))
+}
+
+pub async fn show_track_by_id(
   id: web::Path<i32>,
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let play_source_kind = database.connect()?.get_track_play_source_kind_by_id(*id)?;
-  Ok(HttpResponse::Ok().json(play_source_kind))
+  let track = database.connect()?.get_track_by_id(*id)?;
+  Ok(HttpResponse::Ok().json(track))
 }
 
-pub async fn play_track_by_id(
+pub async fn show_track_technical_info(
   id: web::Path<i32>,
   database: web::Data<Database>,
+  file_path_access: web::Data<FilePathAccess>,
   logged_in_user: LoggedInUser,
-) -> Result<Either<NamedFile, HttpResponse>, InternalError> {
-  if let Some(play_source) = database.connect()?.play_track_by_id(*id, logged_in_user.user.id).await? {
-    let response = match play_source {
-      BackendPlaySource::AudioData(path) => Either::Left(NamedFile::open_async(path).await?),
-      BackendPlaySource::ExternallyPlayedOnSpotify => Either::Right(HttpResponse::Accepted().finish()),
-    };
-    Ok(response)
+) -> Result<HttpResponse, InternalError> {
+  if let Some(mut technical_info) = database.connect()?.get_track_technical_info(*id)? {
+    if !file_path_access.may_see_paths(&logged_in_user) {
+      technical_info.hide_paths();
+    }
+    Ok(HttpResponse::Ok().json(technical_info))
   } else {
-    Ok(Either::Right(HttpResponse::NotFound().finish()))
+    Ok(HttpResponse::NotFound().finish())
   }
 }
 
-// Users
+const TRACK_SEARCH_LIMIT: i64 = 50;
 
-pub async fn list_users(
+pub async fn search_tracks(
+  search: Query<TrackSearch>,
   database: web::Data<Database>,
-  _logged_in_user: LoggedInUser,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  Ok(HttpResponse::Ok().json(database.connect()?.list_users()?))
+  let database = database.connect()?;
+  let content_filter = database.get_user_content_filter(logged_in_user.user.id)?.filter;
+  Ok(HttpResponse::Ok().json(database.search_tracks(&search, content_filter, TRACK_SEARCH_LIMIT)?))
 }
 
-pub async fn show_user_by_id(
+pub async fn show_track_waveform(
   id: web::Path<i32>,
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let user = database.connect()?.get_user_by_id(*id)?;
-  Ok(HttpResponse::Ok().json(user))
+  if let Some(waveform) = database.connect()?.get_or_generate_track_waveform(*id)? {
+    Ok(HttpResponse::Ok().json(waveform))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn show_my_user(
-  logged_in_user: LoggedInUser,
-) -> HttpResponse {
-  HttpResponse::Ok().json(logged_in_user)
+pub async fn show_track_lyrics(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(lyrics) = database.connect()?.get_track_lyrics(*id)? {
+    Ok(HttpResponse::Ok().json(lyrics))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn create_user(
-  new_user: web::Json<NewUser>,
+pub async fn show_track_audio_features(
+  id: web::Path<i32>,
   database: web::Data<Database>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  Ok(HttpResponse::Ok().json(database.connect()?.create_user(new_user.0)?))
+  let audio_features = database.connect()?.get_track_audio_features(*id)?;
+  Ok(HttpResponse::Ok().json(audio_features))
 }
 
-pub async fn delete_user_by_name(
-  name: web::Json<String>,
+// Relinking
+
+pub async fn move_track_to_album(
+  path: web::Path<(i32, i32)>,
   database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
   logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  use InternalError::*;
-  if *name == logged_in_user.user.name {
-    return Err(CannotDeleteLoggedInUserFail);
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
   }
-  if database.connect()?.delete_user_by_name(&*name)? {
-    Ok(HttpResponse::Ok().finish())
+  let (id, album_id) = path.into_inner();
+  let database = database.connect()?;
+  if let Some(track) = database.move_track_to_album(id, album_id)? {
+    organize_edited_tracks(&database, &managed_library, OrganizeScope::Track(id));
+    Ok(HttpResponse::Ok().json(track))
   } else {
     Ok(HttpResponse::NotFound().finish())
   }
 }
 
-pub async fn delete_user_by_id(
+pub async fn set_track_artists(
   id: web::Path<i32>,
+  query: Query<TrackArtistsQuery>,
+  artist_ids: web::Json<Vec<i32>>,
   database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
   logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  use InternalError::*;
-  if *id == logged_in_user.user.id {
-    return Err(CannotDeleteLoggedInUserFail);
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
   }
-  if database.connect()?.delete_user_by_id(*id)? {
-    Ok(HttpResponse::Ok().finish())
+  let database = database.connect()?;
+  if let Some(artists) = database.set_track_artists(*id, query.role, &artist_ids)? {
+    organize_edited_tracks(&database, &managed_library, OrganizeScope::Track(*id));
+    Ok(HttpResponse::Ok().json(artists))
   } else {
     Ok(HttpResponse::NotFound().finish())
   }
 }
 
-// User data
-
-pub async fn set_user_album_rating(
-  logged_in_user: LoggedInUser,
+pub async fn set_album_artists(
   id: web::Path<i32>,
-  rating: web::Path<i32>,
+  artist_ids: web::Json<Vec<i32>>,
   database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let rating = database.connect()?.set_user_album_rating(logged_in_user.user.id, *id, *rating)?;
-  Ok(HttpResponse::Ok().json(rating))
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let database = database.connect()?;
+  if let Some(artists) = database.set_album_artists(*id, &artist_ids)? {
+    organize_edited_tracks(&database, &managed_library, OrganizeScope::Album(*id));
+    Ok(HttpResponse::Ok().json(artists))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn set_user_track_rating(
-  logged_in_user: LoggedInUser,
+pub async fn split_album(
   id: web::Path<i32>,
-  rating: web::Path<i32>,
+  split: web::Json<AlbumSplit>,
   database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let rating = database.connect()?.set_user_track_rating(logged_in_user.user.id, *id, *rating)?;
-  Ok(HttpResponse::Ok().json(rating))
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let database = database.connect()?;
+  if let Some(album) = database.split_album(*id, &split)? {
+    organize_edited_tracks(&database, &managed_library, OrganizeScope::Album(album.id));
+    Ok(HttpResponse::Ok().json(album))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn set_user_artist_rating(
+// Managed library
+
+/// Organizes the files of the tracks in `scope` of the managed library after their metadata was edited, if the managed
+/// library is organized. Failures are logged but do not fail the edit, as the edit was already made.
+fn organize_edited_tracks(database: &DatabaseConnection, managed_library: &Option<ManagedLibrarySettings>, scope: OrganizeScope) {
+  if let Some(managed_library) = managed_library {
+    if let Err(e) = database.organize_managed_library(managed_library, scope) {
+      event!(Level::ERROR, "{:?}", musium_core::format_error::FormatError::new(&e));
+    }
+  }
+}
+
+/// Organizes all files of the managed library, returning the number of files that were moved.
+pub async fn organize_managed_library(
+  database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
   logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let managed_library = managed_library.as_ref().as_ref().ok_or(InternalError::ManagedLibraryDisabledFail)?;
+  Ok(HttpResponse::Ok().json(database.connect()?.organize_managed_library(managed_library, OrganizeScope::All)?))
+}
+
+// Tags
+
+pub async fn list_tags(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_tags()?))
+}
+
+pub async fn list_track_tags(
   id: web::Path<i32>,
-  rating: web::Path<i32>,
   database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let rating = database.connect()?.set_user_artist_rating(logged_in_user.user.id, *id, *rating)?;
-  Ok(HttpResponse::Ok().json(rating))
+  Ok(HttpResponse::Ok().json(database.connect()?.list_track_tags(*id)?))
 }
 
-// Sync
+pub async fn add_track_tag(
+  path: web::Path<(i32, String)>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let (id, name) = path.into_inner();
+  let database = database.connect()?;
+  if database.get_track_by_id(id)?.is_none() {
+    return Ok(HttpResponse::NotFound().finish());
+  }
+  Ok(HttpResponse::Ok().json(database.add_track_tag(id, &name)?))
+}
 
-pub async fn get_sync_status(
+pub async fn add_tracks_tag(
+  name: web::Path<String>,
+  track_ids: web::Json<Vec<i32>>,
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.get_status(database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  Ok(HttpResponse::Ok().json(database.connect()?.add_tracks_tag(&track_ids, &name)?))
 }
 
-pub async fn sync_all_sources(
+pub async fn remove_track_tag(
+  path: web::Path<(i32, String)>,
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.sync_all_sources(database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  let (id, name) = path.into_inner();
+  if database.connect()?.remove_track_tag(id, &name)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn sync_local_sources(
+// Track relations
+
+pub async fn list_track_relations(
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.sync_local_sources(database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  Ok(HttpResponse::Ok().json(database.connect()?.list_track_relations()?))
 }
 
-pub async fn sync_local_source(
+pub async fn list_track_versions(
   id: web::Path<i32>,
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.sync_local_source(id.into_inner(), database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  Ok(HttpResponse::Ok().json(database.connect()?.list_track_versions(*id)?))
 }
 
-pub async fn sync_spotify_sources(
+pub async fn add_track_relation(
+  new_relation: web::Json<NewTrackRelation>,
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.sync_spotify_sources(database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  if let Some(relation) = database.connect()?.add_track_relation(new_relation.into_inner())? {
+    Ok(HttpResponse::Ok().json(relation))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
 }
 
-pub async fn sync_spotify_source(
-  id: web::Path<i32>,
+pub async fn remove_track_relation(
+  path: web::Path<(i32, i32)>,
   database: web::Data<Database>,
-  sync_client: web::Data<SyncClient>,
   _logged_in_user: LoggedInUser,
 ) -> Result<HttpResponse, InternalError> {
-  let sync_status = sync_client.sync_spotify_source(id.into_inner(), database.into_inner()).await?;
-  Ok(HttpResponse::Ok().json(sync_status))
+  let (original_track_id, version_track_id) = path.into_inner();
+  if database.connect()?.remove_track_relation(original_track_id, version_track_id)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Classical
+
+pub async fn list_composers(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_composers()?))
+}
+
+pub async fn list_composer_works(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_composer_works(*id)?))
+}
+
+pub async fn list_work_tracks(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_work_tracks(logged_in_user.user.id, *id)?))
+}
+
+// Track upload
+
+/// Maximum size in bytes of uploaded tracks.
+pub(crate) const TRACK_UPLOAD_LIMIT: usize = 1024 * 1024 * 1024;
+
+/// Number of track uploads started by this server, for giving the partial file of each upload a unique name.
+static TRACK_UPLOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Streams the request body into a file in the managed local source, synchronizes that file, and returns the created
+/// track. The body is written to a hidden partial file first, which is only linked to its destination once the upload is
+/// complete, so that synchronization never picks up incomplete uploads. Files are written on the blocking thread pool.
+pub async fn upload_track(
+  upload: Query<TrackUpload>,
+  mut payload: web::Payload,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  use InternalError::*;
+  if !admins.is_admin(&logged_in_user) {
+    return Err(NotAdminFail);
+  }
+  let managed_local_source_id = capabilities.managed_local_source_id.ok_or(TrackUploadDisabledFail)?;
+  let database = database.connect()?;
+  let (local_source, path) = database.get_track_upload_path(managed_local_source_id, &upload.file_name)?;
+  // Unique per upload, so that concurrent uploads with the same file name do not write into the same partial file.
+  let upload_number = TRACK_UPLOAD_COUNT.fetch_add(1, Ordering::Relaxed);
+  let partial_path = path.with_file_name(format!(".{}.{}-{}.part", upload.file_name, std::process::id(), upload_number));
+  let written = async {
+    let create_path = partial_path.clone();
+    let mut file = web::block(move || OpenOptions::new().write(true).create_new(true).open(create_path))
+      .await.map_err(|_| BlockingThreadPoolGoneFail)??;
+    let mut size = 0;
+    while let Some(chunk) = payload.next().await {
+      let chunk = chunk.map_err(TrackUploadReceiveFail)?;
+      size += chunk.len();
+      if size > TRACK_UPLOAD_LIMIT {
+        return Err(TrackUploadTooLargeFail(TRACK_UPLOAD_LIMIT));
+      }
+      file = web::block(move || -> std::io::Result<File> {
+        file.write_all(&chunk)?;
+        Ok(file)
+      }).await.map_err(|_| BlockingThreadPoolGoneFail)??;
+    }
+    let (link_partial_path, link_path) = (partial_path.clone(), path.clone());
+    let linked = web::block(move || -> std::io::Result<()> {
+      file.sync_all()?;
+      // Link instead of renaming, as linking atomically fails when another upload with the same file name completed in
+      // the meantime, whereas renaming would replace its file.
+      std::fs::hard_link(&link_partial_path, &link_path)?;
+      let _ = std::fs::remove_file(&link_partial_path);
+      Ok(())
+    }).await.map_err(|_| BlockingThreadPoolGoneFail)?;
+    match linked {
+      Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(TrackUploadFail(TrackUploadError::FileExistsFail(upload.file_name.clone()), Backtrace::capture())),
+      linked => Ok::<_, InternalError>(linked?),
+    }
+  }.await;
+  if let Err(e) = written {
+    let _ = web::block(move || std::fs::remove_file(partial_path)).await;
+    return Err(e);
+  }
+  match database.import_uploaded_track(&local_source, &path) {
+    Ok(track) => {
+      organize_edited_tracks(&database, &managed_library, OrganizeScope::Track(track.id));
+      Ok(HttpResponse::Created().json(track))
+    }
+    Err(e) => {
+      // Remove the file, as it was not imported, and would otherwise prevent uploading it again with the same name.
+      let _ = web::block(move || std::fs::remove_file(path)).await;
+      Err(e.into())
+    }
+  }
+}
+
+// Audio features
+
+pub async fn filter_tracks_by_audio_features(
+  filter: Query<AudioFeaturesFilter>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.filter_tracks_by_audio_features(filter.into_inner())?))
+}
+
+/// Starts analyzing audio features of local tracks in the background, as analysis takes a long time.
+pub async fn analyze_audio_features(
+  database: web::Data<Database>,
+  background_workers: web::Data<BackgroundWorkers>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let busy = background_workers.audio_feature_analysis.busy();
+  actix_web::rt::spawn(async move {
+    let _busy = busy;
+    let result = web::block(move || -> Result<usize, InternalError> {
+      Ok(database.connect()?.analyze_audio_features()?)
+    }).await;
+    match result {
+      Ok(Ok(count)) => event!(Level::INFO, "Analyzed audio features of {} track(s)", count),
+      Ok(Err(e)) => event!(Level::ERROR, "{:?}", musium_core::format_error::FormatError::new(&e)),
+      Err(e) => event!(Level::ERROR, "Analyzing audio features failed: {:?}", e),
+    }
+  });
+  Ok(HttpResponse::Accepted().finish())
+}
+
+// Artist
+
+pub async fn list_artists(
+  filter: Query<HiddenFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_artists(logged_in_user.user.id, *filter)?))
+}
+
+pub async fn show_artist_alphabet_index(
+  filter: Query<HiddenFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_artist_alphabet_index(logged_in_user.user.id, *filter)?))
+}
+
+pub async fn show_artist_by_id(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let artist = database.connect()?.get_artist_detail_by_id(*id)?;
+  Ok(HttpResponse::Ok().json(artist))
+}
+
+pub async fn show_artist_image(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  query: Query<ArtworkQuery>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(response) = reject_artwork_size(&query) { return Ok(response); }
+  Ok(artwork_response(&request, database.connect()?.get_artist_image(*id, query.size)?))
+}
+
+// Playback
+
+pub async fn show_track_play_source_kind(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let play_source_kind = database.connect()?.get_track_play_source_kind_by_id(*id, logged_in_user.user.id)?;
+  Ok(HttpResponse::Ok().json(play_source_kind))
+}
+
+pub async fn play_track_by_id(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  // Plays of read-only users are not recorded, as they share a single account.
+  play_track_response(&request, *id, logged_in_user.user.id, !logged_in_user.read_only, &database).await
+}
+
+pub async fn show_signed_track_url(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  url_signer: web::Data<UrlSigner>,
+  database: web::Data<Database>,
+  public_url: web::Data<PublicUrl>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  use InternalError::*;
+  if database.connect()?.get_track_by_id(*id)?.is_none() {
+    return Ok(HttpResponse::NotFound().finish());
+  }
+  let (query, expires_at) = url_signer.sign_track(*id, logged_in_user.user.id);
+  let mut url = public_url.url_for(&request, "signed_play_track", &[(*id).to_string()]).map_err(|e| UrlGenerationFail(e))?;
+  url.set_query(Some(&query));
+  Ok(HttpResponse::Ok().json(SignedTrackUrl { url: url.to_string(), expires_at }))
+}
+
+/// Plays a track without an authenticated session, authorized by the signature in `query` instead.
+pub async fn play_signed_track(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  query: Query<SignedTrackQuery>,
+  url_signer: web::Data<UrlSigner>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  if !url_signer.verify_track(*id, &query) {
+    return Ok(HttpResponse::Forbidden().json(InternalServerError { message: "Signed URL is invalid or has expired".to_string() }));
+  }
+  play_track_response(&request, *id, query.user, true, &database).await
+}
+
+async fn play_track_response(request: &HttpRequest, track_id: i32, user_id: i32, record_play: bool, database: &Database) -> Result<HttpResponse, InternalError> {
+  if let Some(play_source) = database.connect()?.play_track_by_id(track_id, user_id, record_play).await? {
+    let response = match play_source {
+      BackendPlaySource::AudioData { path, album_id, replay_gain } => {
+        let mut response = NamedFile::open_async(path).await?.into_response(request);
+        let headers = response.headers_mut();
+        headers.insert(http::header::HeaderName::from_static(ALBUM_ID_HEADER), album_id.into());
+        for (name, value) in replay_gain.iter().flat_map(|replay_gain| replay_gain.to_headers()) {
+          if let Ok(value) = http::header::HeaderValue::from_str(&value) {
+            headers.insert(http::header::HeaderName::from_static(name), value);
+          }
+        }
+        response
+      }
+      BackendPlaySource::ExternallyPlayedOnSpotify => HttpResponse::Accepted().finish(),
+    };
+    Ok(response)
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn get_playback_preference(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_playback_preference(logged_in_user.user.id)?))
+}
+
+pub async fn set_playback_preference(
+  preference: web::Json<PlaybackPreference>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  // Always set the preference of the logged-in user, ignoring the user ID in the request.
+  let preference = PlaybackPreference { user_id: logged_in_user.user.id, ..preference.0 };
+  Ok(HttpResponse::Ok().json(database.connect()?.set_playback_preference(preference)?))
+}
+
+// User statistics
+
+pub async fn get_user_stats(
+  query: Query<UserStatsQuery>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_user_stats(logged_in_user.user.id, *query)?))
+}
+
+pub async fn get_year_review(
+  year: web::Path<i32>,
+  query: Query<YearReviewQuery>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(review) = database.connect()?.get_year_review(logged_in_user.user.id, *year, *query)? {
+    Ok(HttpResponse::Ok().json(review))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn get_year_review_html(
+  year: web::Path<i32>,
+  query: Query<YearReviewQuery>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(review) = database.connect()?.get_year_review(logged_in_user.user.id, *year, *query)? {
+    let html = render_year_review_html(&review, &logged_in_user.user.name);
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(html))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// User preferences
+
+pub async fn get_user_preferences(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_user_preferences(logged_in_user.user.id)?))
+}
+
+pub async fn set_user_preferences(
+  preferences: web::Json<UserPreferences>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.set_user_preferences(logged_in_user.user.id, preferences.into_inner())?))
+}
+
+// Content filter
+
+pub async fn get_my_content_filter(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_user_content_filter(logged_in_user.user.id)?))
+}
+
+pub async fn set_my_content_filter(
+  filter: web::Json<ContentFilter>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.set_own_content_filter(logged_in_user.user.id, filter.into_inner())?))
+}
+
+pub async fn get_user_content_filter(
+  user_id: web::Path<i32>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  Ok(HttpResponse::Ok().json(database.connect()?.get_user_content_filter(*user_id)?))
+}
+
+pub async fn set_user_content_filter(
+  user_id: web::Path<i32>,
+  content_filter: web::Json<UserContentFilter>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  // Always set the content filter of the user in the path, ignoring the user ID in the request.
+  let content_filter = UserContentFilter { user_id: *user_id, ..content_filter.0 };
+  Ok(HttpResponse::Ok().json(database.connect()?.set_user_content_filter(content_filter)?))
+}
+
+// Users
+
+pub async fn list_users(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_users()?))
+}
+
+pub async fn show_user_by_id(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let user = database.connect()?.get_user_by_id(*id)?;
+  Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn show_my_user(
+  logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(logged_in_user)
+}
+
+pub async fn create_user(
+  new_user: web::Json<NewUser>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.create_user(new_user.0)?))
+}
+
+// User invites
+
+pub async fn create_user_invite(
+  properties: web::Json<NewUserInviteProperties>,
+  database: web::Data<Database>,
+  capabilities: web::Data<Capabilities>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  if !capabilities.registration { return Err(InternalError::RegistrationDisabledFail); }
+  let valid_for_hours = properties.valid_for_hours.unwrap_or(DEFAULT_INVITE_VALIDITY_HOURS);
+  Ok(HttpResponse::Ok().json(database.connect()?.create_user_invite(logged_in_user.user.id, valid_for_hours)?))
+}
+
+pub async fn list_user_invites(
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  Ok(HttpResponse::Ok().json(database.connect()?.list_user_invites()?))
+}
+
+pub async fn delete_user_by_name(
+  name: web::Json<String>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  use InternalError::*;
+  if *name == logged_in_user.user.name {
+    return Err(CannotDeleteLoggedInUserFail);
+  }
+  if database.connect()?.delete_user_by_name(&*name)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn delete_user_by_id(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  use InternalError::*;
+  if *id == logged_in_user.user.id {
+    return Err(CannotDeleteLoggedInUserFail);
+  }
+  if database.connect()?.delete_user_by_id(*id)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// User data
+
+pub async fn set_user_album_rating(
+  logged_in_user: LoggedInUser,
+  id: web::Path<i32>,
+  rating: web::Path<i32>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let rating = database.connect()?.set_user_album_rating(logged_in_user.user.id, *id, *rating)?;
+  Ok(HttpResponse::Ok().json(rating))
+}
+
+pub async fn set_user_track_rating(
+  logged_in_user: LoggedInUser,
+  id: web::Path<i32>,
+  rating: web::Path<i32>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let rating = database.connect()?.set_user_track_rating(logged_in_user.user.id, *id, *rating)?;
+  Ok(HttpResponse::Ok().json(rating))
+}
+
+pub async fn set_user_track_ratings(
+  logged_in_user: LoggedInUser,
+  ratings: web::Json<TrackRatings>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let ratings = database.connect()?.set_user_track_ratings(logged_in_user.user.id, &ratings.track_ids, ratings.rating)?;
+  Ok(HttpResponse::Ok().json(ratings))
+}
+
+pub async fn set_user_artist_rating(
+  logged_in_user: LoggedInUser,
+  id: web::Path<i32>,
+  rating: web::Path<i32>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let rating = database.connect()?.set_user_artist_rating(logged_in_user.user.id, *id, *rating)?;
+  Ok(HttpResponse::Ok().json(rating))
+}
+
+pub async fn list_hidden_items(
+  logged_in_user: LoggedInUser,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_hidden_item_infos(logged_in_user.user.id)?))
+}
+
+pub async fn hide_item(
+  logged_in_user: LoggedInUser,
+  path: web::Path<(HiddenItemKind, i32)>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let (kind, item_id) = path.into_inner();
+  Ok(HttpResponse::Ok().json(database.connect()?.hide_item(logged_in_user.user.id, kind, item_id)?))
+}
+
+pub async fn unhide_item(
+  logged_in_user: LoggedInUser,
+  path: web::Path<(HiddenItemKind, i32)>,
+  database: web::Data<Database>,
+) -> Result<HttpResponse, InternalError> {
+  let (kind, item_id) = path.into_inner();
+  if database.connect()?.unhide_item(logged_in_user.user.id, kind, item_id)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Playlists
+
+pub async fn list_playlists(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_playlists(logged_in_user.user.id)?))
+}
+
+pub async fn list_playlist_mixes(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_playlist_mixes(logged_in_user.user.id)?))
+}
+
+pub async fn show_playlist_by_id(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_playlist_by_id(*id, logged_in_user.user.id)?))
+}
+
+pub async fn create_playlist(
+  properties: web::Json<PlaylistProperties>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let PlaylistProperties { name, public } = properties.into_inner();
+  let new_playlist = NewPlaylist { owner_user_id: logged_in_user.user.id, name, public };
+  Ok(HttpResponse::Ok().json(database.connect()?.create_playlist(new_playlist)?))
+}
+
+pub async fn update_playlist(
+  id: web::Path<i32>,
+  properties: web::Json<PlaylistProperties>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let PlaylistProperties { name, public } = properties.into_inner();
+  let database = database.connect()?;
+  if let Some(playlist) = database.update_playlist(*id, name, public, logged_in_user.user.id)? {
+    let message = format!("{} changed playlist '{}'", logged_in_user.user.name, playlist.name);
+    publish_playlist_change(&database, &event_bus, *id, &logged_in_user, message)?;
+    Ok(HttpResponse::Ok().json(playlist))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn delete_playlist(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if database.connect()?.delete_playlist(*id, logged_in_user.user.id)? {
+    event_bus.publish(Event::PlaylistDeleted(*id));
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn add_playlist_track(
+  path: web::Path<(i32, i32)>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let (id, track_id) = path.into_inner();
+  let database = database.connect()?;
+  if let Some(playlist_track) = database.add_playlist_track(id, track_id, logged_in_user.user.id)? {
+    let message = format!("{} added a track to a shared playlist", logged_in_user.user.name);
+    publish_playlist_change(&database, &event_bus, id, &logged_in_user, message)?;
+    Ok(HttpResponse::Ok().json(playlist_track))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn add_playlist_tracks(
+  id: web::Path<i32>,
+  track_ids: web::Json<Vec<i32>>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let database = database.connect()?;
+  if let Some(playlist_tracks) = database.add_playlist_tracks(*id, &track_ids, logged_in_user.user.id)? {
+    let message = format!("{} added {} tracks to a shared playlist", logged_in_user.user.name, playlist_tracks.len());
+    publish_playlist_change(&database, &event_bus, *id, &logged_in_user, message)?;
+    Ok(HttpResponse::Ok().json(playlist_tracks))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn remove_playlist_track(
+  path: web::Path<(i32, i32)>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let (id, playlist_track_id) = path.into_inner();
+  let database = database.connect()?;
+  if database.remove_playlist_track(id, playlist_track_id, logged_in_user.user.id)? {
+    let message = format!("{} removed a track from a shared playlist", logged_in_user.user.name);
+    publish_playlist_change(&database, &event_bus, id, &logged_in_user, message)?;
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn add_playlist_collaborator(
+  path: web::Path<(i32, i32)>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let (id, user_id) = path.into_inner();
+  let database = database.connect()?;
+  if let Some(collaborator) = database.add_playlist_collaborator(id, user_id, logged_in_user.user.id)? {
+    let message = format!("{} shared a playlist", logged_in_user.user.name);
+    publish_playlist_change(&database, &event_bus, id, &logged_in_user, message)?;
+    Ok(HttpResponse::Ok().json(collaborator))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn remove_playlist_collaborator(
+  path: web::Path<(i32, i32)>,
+  database: web::Data<Database>,
+  event_bus: web::Data<EventBus>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let (id, user_id) = path.into_inner();
+  let database = database.connect()?;
+  if database.remove_playlist_collaborator(id, user_id, logged_in_user.user.id)? {
+    event_bus.publish(Event::PlaylistChanged(id));
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+fn publish_playlist_change(
+  database: &DatabaseConnection,
+  event_bus: &EventBus,
+  playlist_id: i32,
+  logged_in_user: &LoggedInUser,
+  message: String,
+) -> Result<(), InternalError> {
+  event_bus.publish(Event::PlaylistChanged(playlist_id));
+  for notification in database.notify_playlist_change(playlist_id, logged_in_user.user.id, message)? {
+    event_bus.publish(Event::Notification(notification));
+  }
+  Ok(())
+}
+
+// Listening sessions
+
+pub async fn list_listening_sessions(
+  listening_sessions: web::Data<ListeningSessions>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(listening_sessions.list())
+}
+
+pub async fn show_listening_session_by_id(
+  id: web::Path<i32>,
+  listening_sessions: web::Data<ListeningSessions>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(listening_sessions.get(*id))
+}
+
+pub async fn create_listening_session(
+  new_listening_session: web::Json<NewListeningSession>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(listening_sessions.create(logged_in_user.user.id, new_listening_session.into_inner()))
+}
+
+pub async fn join_listening_session(
+  id: web::Path<i32>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  if let Some(listening_session) = listening_sessions.join(*id, logged_in_user.user.id) {
+    HttpResponse::Ok().json(listening_session)
+  } else {
+    HttpResponse::NotFound().finish()
+  }
+}
+
+pub async fn leave_listening_session(
+  id: web::Path<i32>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  if listening_sessions.leave(*id, logged_in_user.user.id) {
+    HttpResponse::Ok().finish()
+  } else {
+    HttpResponse::NotFound().finish()
+  }
+}
+
+pub async fn command_listening_session(
+  id: web::Path<i32>,
+  command: web::Json<ListeningSessionCommand>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(listening_session) = listening_sessions.command(*id, logged_in_user.user.id, command.into_inner())? {
+    Ok(HttpResponse::Ok().json(listening_session))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Party
+
+pub async fn show_party_by_id(
+  id: web::Path<i32>,
+  parties: web::Data<Parties>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(parties.get(*id))
+}
+
+pub async fn create_party(
+  new_party: web::Json<NewParty>,
+  parties: web::Data<Parties>,
+  logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(parties.create(logged_in_user.user.id, new_party.into_inner()))
+}
+
+pub async fn end_party(
+  id: web::Path<i32>,
+  parties: web::Data<Parties>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if parties.end(*id, logged_in_user.user.id)? {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn invite_party_guest(
+  id: web::Path<i32>,
+  new_guest: web::Json<NewPartyGuest>,
+  parties: web::Data<Parties>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(guest) = parties.invite_guest(*id, logged_in_user.user.id, new_guest.into_inner())? {
+    Ok(HttpResponse::Ok().json(guest))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn dequeue_party_track(
+  id: web::Path<i32>,
+  parties: web::Data<Parties>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(parties.dequeue(*id, logged_in_user.user.id)?))
+}
+
+// Party guest
+
+pub async fn show_guest_party(
+  parties: web::Data<Parties>,
+  logged_in_guest: LoggedInGuest,
+) -> HttpResponse {
+  HttpResponse::Ok().json(parties.get(logged_in_guest.guest.party_id))
+}
+
+pub async fn guest_search_tracks(
+  search: Query<TrackSearch>,
+  database: web::Data<Database>,
+  parties: web::Data<Parties>,
+  logged_in_guest: LoggedInGuest,
+) -> Result<HttpResponse, InternalError> {
+  let database = database.connect()?;
+  let content_filter = guest_content_filter(&database, &parties, &logged_in_guest)?;
+  Ok(HttpResponse::Ok().json(database.search_tracks(&search, content_filter, TRACK_SEARCH_LIMIT)?))
+}
+
+pub async fn guest_enqueue_track(
+  track_id: web::Path<i32>,
+  database: web::Data<Database>,
+  parties: web::Data<Parties>,
+  logged_in_guest: LoggedInGuest,
+) -> Result<HttpResponse, InternalError> {
+  let database = database.connect()?;
+  let content_filter = guest_content_filter(&database, &parties, &logged_in_guest)?;
+  // Tracks that the content filter leaves out cannot be found by guests, so do not let guests enqueue them either.
+  match database.get_track_by_id(*track_id)? {
+    Some(track) if content_filter.allows(track.explicit) => {}
+    _ => return Ok(HttpResponse::NotFound().finish()),
+  }
+  Ok(HttpResponse::Ok().json(parties.enqueue(&logged_in_guest.guest, *track_id)?))
+}
+
+/// Gets the content filter of the host of the party of `logged_in_guest`, which guests are subject to so that they
+/// cannot request tracks that the host would not play.
+fn guest_content_filter(database: &DatabaseConnection, parties: &Parties, logged_in_guest: &LoggedInGuest) -> Result<ContentFilter, InternalError> {
+  Ok(match parties.get(logged_in_guest.guest.party_id) {
+    Some(party) => database.get_user_content_filter(party.host_user_id)?.filter,
+    None => ContentFilter::default(),
+  })
+}
+
+// Home Assistant
+
+/// Gets the playback state of the logged-in user in the shape of the media player integration of Home Assistant.
+pub async fn show_home_assistant_state(
+  database: web::Data<Database>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let state = match listening_sessions.find_by_user(logged_in_user.user.id) {
+    Some(session) => {
+      let media = match session.track_id {
+        Some(track_id) => database.connect()?.get_home_assistant_media(track_id)?,
+        None => None,
+      };
+      let now = Utc::now().naive_utc();
+      let player_state = match (&media, session.playing) {
+        (None, _) => HomeAssistantPlayerState::Idle,
+        (Some(_), true) => HomeAssistantPlayerState::Playing,
+        (Some(_), false) => HomeAssistantPlayerState::Paused,
+      };
+      HomeAssistantState {
+        state: player_state,
+        listening_session_id: Some(session.id),
+        media_position: media.as_ref().map(|_| session.expected_position_ms(now) as f64 / 1000.0),
+        media_position_updated_at: media.as_ref().map(|_| now),
+        media,
+      }
+    }
+    None => HomeAssistantState { state: HomeAssistantPlayerState::Idle, listening_session_id: None, media: None, media_position: None, media_position_updated_at: None },
+  };
+  Ok(HttpResponse::Ok().json(state))
+}
+
+/// Applies a control verb of Home Assistant to the listening session hosted by the logged-in user. Responds with 404 Not
+/// Found if the user does not host a listening session.
+pub async fn control_home_assistant(
+  command: web::Json<HomeAssistantCommand>,
+  listening_sessions: web::Data<ListeningSessions>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let user_id = logged_in_user.user.id;
+  let session = match listening_sessions.find_by_user(user_id).filter(|session| session.host_user_id == user_id) {
+    Some(session) => session,
+    None => return Ok(HttpResponse::NotFound().finish()),
+  };
+  let command = match command.into_inner() {
+    HomeAssistantCommand::Play => ListeningSessionCommand::Play,
+    HomeAssistantCommand::Pause => ListeningSessionCommand::Pause,
+    HomeAssistantCommand::PlayPause if session.playing => ListeningSessionCommand::Pause,
+    HomeAssistantCommand::PlayPause => ListeningSessionCommand::Play,
+    HomeAssistantCommand::Seek { position } => ListeningSessionCommand::Seek { position_ms: (position.max(0.0) * 1000.0) as u64 },
+    HomeAssistantCommand::PlayMedia { track_id } => ListeningSessionCommand::ChangeTrack { track_id },
+  };
+  Ok(HttpResponse::Ok().json(listening_sessions.command(session.id, user_id, command)?))
+}
+
+pub async fn show_library_summary(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_library_summary()?))
+}
+
+// Reports
+
+pub async fn list_missing_albums(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_missing_albums()?))
+}
+
+// Notifications
+
+pub async fn list_new_releases(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_new_releases()?))
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct ListNotificationsQuery {
+  #[serde(default)]
+  unread_only: bool,
+}
+
+pub(crate) async fn list_notifications(
+  query: Query<ListNotificationsQuery>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_notifications(logged_in_user.user.id, query.unread_only)?))
+}
+
+pub async fn mark_notification_read(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(notification) = database.connect()?.mark_notification_read(*id, logged_in_user.user.id)? {
+    Ok(HttpResponse::Ok().json(notification))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn mark_all_notifications_read(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.mark_all_notifications_read(logged_in_user.user.id)?))
+}
+
+pub async fn get_notification_preference(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_notification_preference(logged_in_user.user.id)?))
+}
+
+pub async fn set_notification_preference(
+  preference: web::Json<NotificationPreference>,
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  // Always set the preference of the logged-in user, ignoring the user ID in the request.
+  let preference = NotificationPreference { user_id: logged_in_user.user.id, ..preference.0 };
+  Ok(HttpResponse::Ok().json(database.connect()?.set_notification_preference(preference)?))
+}
+
+// Maintenance
+
+pub async fn get_maintenance_status(
+  maintenance_mode: web::Data<MaintenanceMode>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok().json(maintenance_mode.status())
+}
+
+pub async fn set_maintenance_status(
+  status: web::Json<MaintenanceStatus>,
+  maintenance_mode: web::Data<MaintenanceMode>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let status = maintenance_mode.set_status(status.into_inner()).await?;
+  event!(Level::INFO, ?status, user = %logged_in_user.user.name, "Maintenance mode changed");
+  Ok(HttpResponse::Ok().json(status))
+}
+
+pub async fn get_media_cache_status(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.get_media_cache_status()?))
+}
+
+pub async fn list_track_issues(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_track_issues()?))
+}
+
+/// Starts validating the files of local tracks in the background, as decoding every file takes a long time.
+pub async fn validate_local_tracks(
+  database: web::Data<Database>,
+  background_workers: web::Data<BackgroundWorkers>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let busy = background_workers.track_validation.busy();
+  actix_web::rt::spawn(async move {
+    let _busy = busy;
+    let result = web::block(move || -> Result<usize, InternalError> {
+      Ok(database.connect()?.validate_local_tracks()?)
+    }).await;
+    match result {
+      Ok(Ok(count)) => event!(Level::INFO, "Validated local tracks, found issues in {} track(s)", count),
+      Ok(Err(e)) => event!(Level::ERROR, "{:?}", musium_core::format_error::FormatError::new(&e)),
+      Err(e) => event!(Level::ERROR, "Validating local tracks failed: {:?}", e),
+    }
+  });
+  Ok(HttpResponse::Accepted().finish())
+}
+
+// Undo
+
+pub async fn list_undo_log(
+  database: web::Data<Database>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_undo_log(logged_in_user.user.id)?))
+}
+
+pub async fn undo(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let database = database.connect()?;
+  if let Some(entry) = database.undo(*id, logged_in_user.user.id)? {
+    organize_edited_tracks(&database, &managed_library, OrganizeScope::All);
+    Ok(HttpResponse::Ok().json(entry))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Metadata merging
+
+pub async fn list_metadata_merge_policies(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_metadata_merge_policies()?))
+}
+
+pub async fn set_metadata_merge_policy(
+  setting: web::Json<MetadataMergePolicySetting>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  Ok(HttpResponse::Ok().json(database.connect()?.set_metadata_merge_policy(setting.into_inner())?))
+}
+
+pub async fn remove_metadata_merge_policy(
+  field: web::Path<MetadataField>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  if let Some(setting) = database.connect()?.remove_metadata_merge_policy(field.into_inner())? {
+    Ok(HttpResponse::Ok().json(setting))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn list_metadata_conflicts(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_metadata_conflicts()?))
+}
+
+pub async fn resolve_metadata_conflict(
+  id: web::Path<i32>,
+  resolution: web::Json<MetadataConflictResolution>,
+  database: web::Data<Database>,
+  managed_library: web::Data<Option<ManagedLibrarySettings>>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let database = database.connect()?;
+  if let Some(conflict) = database.resolve_metadata_conflict(*id, &resolution.value)? {
+    let scope = match conflict.field {
+      MetadataField::AlbumName => OrganizeScope::Album(conflict.entity_id),
+      MetadataField::TrackTitle | MetadataField::TrackNumber | MetadataField::DiscNumber | MetadataField::TrackArtists => OrganizeScope::Track(conflict.entity_id),
+      MetadataField::ArtistName => OrganizeScope::All,
+    };
+    organize_edited_tracks(&database, &managed_library, scope);
+    Ok(HttpResponse::Ok().json(conflict))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn dismiss_metadata_conflict(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(conflict) = database.connect()?.dismiss_metadata_conflict(*id)? {
+    Ok(HttpResponse::Ok().json(conflict))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Sort names
+
+pub async fn list_sort_articles(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_sort_articles()?))
+}
+
+pub async fn set_sort_articles(
+  articles: web::Json<Vec<String>>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  Ok(HttpResponse::Ok().json(database.connect()?.set_sort_articles(&articles)?))
+}
+
+pub async fn set_album_sort_name(
+  id: web::Path<i32>,
+  sort_name_override: web::Json<SortNameOverride>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  if let Some(album) = database.connect()?.set_album_sort_name(*id, sort_name_override.sort_name.as_deref())? {
+    Ok(HttpResponse::Ok().json(album))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn set_artist_sort_name(
+  id: web::Path<i32>,
+  sort_name_override: web::Json<SortNameOverride>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  if let Some(artist) = database.connect()?.set_artist_sort_name(*id, sort_name_override.sort_name.as_deref())? {
+    Ok(HttpResponse::Ok().json(artist))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Album cover
+
+/// Maximum size in bytes of uploaded album covers.
+pub(crate) const ALBUM_COVER_UPLOAD_LIMIT: usize = 16 * 1024 * 1024;
+
+pub async fn show_album_cover(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  query: Query<ArtworkQuery>,
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if let Some(response) = reject_artwork_size(&query) { return Ok(response); }
+  Ok(artwork_response(&request, database.connect()?.get_album_cover(*id, query.size)?))
+}
+
+pub async fn set_album_cover(
+  request: HttpRequest,
+  id: web::Path<i32>,
+  upload: Query<AlbumCoverUpload>,
+  data: web::Bytes,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let mime_type = request.headers().get(http::header::CONTENT_TYPE).and_then(|mime_type| mime_type.to_str().ok()).unwrap_or_default();
+  if let Some(result) = database.connect()?.set_album_cover(*id, mime_type, data.to_vec(), upload.embed)? {
+    Ok(HttpResponse::Ok().json(result))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+pub async fn fetch_album_cover(
+  id: web::Path<i32>,
+  fetch: web::Json<AlbumCoverFetch>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  if let Some(result) = database.connect()?.fetch_album_cover(*id, &fetch).await? {
+    Ok(HttpResponse::Ok().json(result))
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+// Artwork
+
+/// Cache header of album covers and artist images. Clients revalidate with the ETag after it expires, so that changed
+/// covers are picked up.
+const ARTWORK_CACHE_CONTROL: &str = "private, max-age=604800";
+
+fn reject_artwork_size(query: &ArtworkQuery) -> Option<HttpResponse> {
+  match query.size {
+    Some(size) if !ARTWORK_SIZES.contains(&size) => Some(HttpResponse::BadRequest().json(InternalServerError {
+      message: format!("Artwork size {} is not one of {:?}", size, ARTWORK_SIZES)
+    })),
+    _ => None,
+  }
+}
+
+/// Creates a response with artwork `blob` and its content hash as strong ETag, or a 304 Not Modified response if the
+/// request already has that version, or a 404 Not Found response if there is no artwork.
+fn artwork_response(request: &HttpRequest, blob: Option<MediaBlob>) -> HttpResponse {
+  let blob = match blob {
+    Some(blob) => blob,
+    None => return HttpResponse::NotFound().finish(),
+  };
+  let etag = http::header::EntityTag::strong(blob.hash);
+  let not_modified = request.headers().get(http::header::IF_NONE_MATCH)
+    .and_then(|value| value.to_str().ok())
+    .map_or(false, |value| value.split(',').any(|tag| tag.trim() == etag.to_string() || tag.trim() == "*"));
+  let mut response = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+  response
+    .insert_header(http::header::ETag(etag))
+    .insert_header((http::header::CACHE_CONTROL, ARTWORK_CACHE_CONTROL));
+  if not_modified {
+    response.finish()
+  } else {
+    response.content_type(blob.mime_type).body(blob.data)
+  }
+}
+
+// Events
+
+pub async fn events(
+  request: HttpRequest,
+  stream: web::Payload,
+  event_bus: web::Data<EventBus>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  actix_web_actors::ws::start(EventSocket::new(event_bus.subscribe()), &request, stream)
+    .map_err(|e| InternalError::WebSocketStartFail(e))
+}
+
+/// Streams events as server-sent events, which browsers and tools such as curl can consume without a WebSocket client.
+pub async fn sse_events(
+  event_bus: web::Data<EventBus>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("text/event-stream")
+    .insert_header((http::header::CACHE_CONTROL, "no-cache"))
+    .streaming(sse_stream(event_bus.subscribe()))
+}
+
+/// Maximum time that long-polling requests are held.
+const MAX_POLL_WAIT: Duration = Duration::from_secs(60);
+
+/// Parses the wait time of a long-polling request, capped at `MAX_POLL_WAIT`, or returns a 400 Bad Request response if
+/// it is malformed.
+fn parse_wait(query: &WaitQuery) -> Result<Option<Duration>, HttpResponse> {
+  query.duration()
+    .map(|wait| wait.map(|wait| wait.min(MAX_POLL_WAIT)))
+    .map_err(|_| HttpResponse::BadRequest().json(InternalServerError {
+      message: format!("Wait time {:?} is not a number of seconds, such as '30s'", query.wait.as_deref().unwrap_or_default())
+    }))
+}
+
+/// Polls for events as a fallback for clients that cannot use the WebSocket of `events`, waiting for an event to be
+/// published if there are none yet.
+pub async fn poll_events(
+  events: Query<EventsQuery>,
+  wait: Query<WaitQuery>,
+  event_bus: web::Data<EventBus>,
+  _logged_in_user: LoggedInUser,
+) -> HttpResponse {
+  let wait = match parse_wait(&wait) {
+    Ok(wait) => wait,
+    Err(response) => return response,
+  };
+  let poll = match wait {
+    Some(wait) => event_bus.wait_poll(events.since, wait).await,
+    None => event_bus.poll(events.since),
+  };
+  HttpResponse::Ok().json(poll)
+}
+
+// Sync
+
+/// Gets the sync status. When a wait time is given, waits for the sync status to change before responding, or until the
+/// wait time has passed.
+pub async fn get_sync_status(
+  wait: Query<WaitQuery>,
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  event_bus: web::Data<EventBus>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let wait = match parse_wait(&wait) {
+    Ok(wait) => wait,
+    Err(response) => return Ok(response),
+  };
+  // Subscribe before getting the status, so that changes in between are not missed.
+  let mut rx = event_bus.subscribe();
+  let mut sync_status = sync_client.get_status(database.into_inner()).await?;
+  if let Some(wait) = wait {
+    let changed = wait_for_event(&mut rx, wait, |event| match event {
+      Event::SyncStatusChanged(sync_status) => Some(sync_status),
+      _ => None,
+    }).await;
+    if let Some(changed) = changed {
+      sync_status = changed;
+    }
+  }
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+pub async fn sync_all_sources(
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let sync_status = sync_client.sync_all_sources(database.into_inner()).await?;
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+pub async fn sync_local_sources(
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let sync_status = sync_client.sync_local_sources(database.into_inner()).await?;
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+pub async fn sync_local_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let sync_status = sync_client.sync_local_source(id.into_inner(), database.into_inner()).await?;
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+pub async fn sync_spotify_sources(
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let sync_status = sync_client.sync_spotify_sources(database.into_inner()).await?;
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+pub async fn sync_spotify_source(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  sync_client: web::Data<SyncClient>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let sync_status = sync_client.sync_spotify_source(id.into_inner(), database.into_inner()).await?;
+  Ok(HttpResponse::Ok().json(sync_status))
+}
+
+/// Previews synchronizing with all local sources without writing to the database. Does not go through the sync client,
+/// as it does not modify the database and can therefore run concurrently with a sync.
+pub async fn preview_sync(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.preview_local_sources_sync()?))
+}
+
+pub async fn show_sync_lock(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  let result = if let Some(sync_lock) = database.connect()?.get_sync_lock()? {
+    HttpResponse::Ok().json(sync_lock)
+  } else {
+    HttpResponse::NotFound().finish()
+  };
+  Ok(result)
+}
+
+pub async fn force_release_sync_lock(
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let result = if let Some(sync_lock) = database.connect()?.force_release_sync_lock()? {
+    HttpResponse::Ok().json(sync_lock)
+  } else {
+    HttpResponse::NotFound().finish()
+  };
+  Ok(result)
+}
+
+pub async fn list_sync_history(
+  database: web::Data<Database>,
+  _logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  Ok(HttpResponse::Ok().json(database.connect()?.list_sync_runs()?))
+}
+
+// Webhooks
+
+pub async fn list_webhooks(
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let webhooks: Vec<WebhookInfo> = database.connect()?.list_webhooks()?.into_iter().map(Into::into).collect();
+  Ok(HttpResponse::Ok().json(webhooks))
+}
+
+pub async fn create_webhook(
+  new_webhook: web::Json<NewWebhook>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  check_webhook_url(&new_webhook.url)?;
+  let webhook = database.connect()?.create_webhook(&new_webhook)?;
+  Ok(HttpResponse::Ok().json(WebhookInfo::from(webhook)))
+}
+
+pub async fn set_webhook_enabled(
+  id: web::Path<i32>,
+  enabled: web::Json<bool>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let webhook = database.connect()?.set_webhook_enabled_by_id(*id, *enabled)?;
+  Ok(HttpResponse::Ok().json(webhook.map(WebhookInfo::from)))
+}
+
+pub async fn delete_webhook(
+  id: web::Path<i32>,
+  database: web::Data<Database>,
+  admins: web::Data<Admins>,
+  logged_in_user: LoggedInUser,
+) -> Result<HttpResponse, InternalError> {
+  if !admins.is_admin(&logged_in_user) {
+    return Err(InternalError::NotAdminFail);
+  }
+  let webhook = database.connect()?.delete_webhook_by_id(*id)?;
+  Ok(HttpResponse::Ok().json(webhook.map(WebhookInfo::from)))
 }
 
 // Error type
@@ -383,6 +2257,8 @@ pub enum InternalError {
   CannotDeleteLoggedInUserFail,
   #[error("URL generation failed: {0:?}")]
   UrlGenerationFail(UrlGenerationError),
+  #[error("Failed to relocate local source")]
+  RelocateLocalSourceFail(#[from] RelocateLocalSourceError, Backtrace),
   #[error("Failed to create a Spotify authorization URL")]
   SpotifySourceCreateAuthorizationUrlFail(#[from] spotify::CreateAuthorizationUrlError, Backtrace),
   #[error("Spotify authorization callback resulted in an error: {0}")]
@@ -393,20 +2269,117 @@ pub enum InternalError {
   SpotifySourceCreateFail(#[from] spotify::CreateError, Backtrace),
   #[error("Failed to request Spotify user info")]
   SpotifyMeInfoError(#[from] spotify::MeInfoError, Backtrace),
+  #[error("Writing back to Spotify is disabled on this server")]
+  SpotifyWriteBackDisabledFail,
+  #[error("Failed to write back to Spotify")]
+  SpotifyWriteBackFail(#[from] spotify::WriteBackError, Backtrace),
   #[error(transparent)]
   ParseUserIdFail(#[from] ParseIntError),
   #[error("Failed to add a user")]
   UserAddFail(#[from] UserAddVerifyError, Backtrace),
+  #[error("Registration is disabled on this server")]
+  RegistrationDisabledFail,
+  #[error("Failed to set user preferences")]
+  UserPreferencesFail(#[from] UserPreferencesError, Backtrace),
+  #[error("Failed to set content filter")]
+  ContentFilterFail(#[from] SetContentFilterError, Backtrace),
+  #[error("Only admins may administer the server and other users")]
+  NotAdminFail,
   #[error("I/O failure")]
   IoFail(#[from] std::io::Error, Backtrace),
   #[error("Failed to play track")]
   PlayFail(#[from] PlayError, Backtrace),
+  #[error("Failed to analyze audio features")]
+  AnalyzeAudioFeaturesFail(#[from] AnalyzeAudioFeaturesError, Backtrace),
+  #[error("Failed to get or generate track waveform")]
+  TrackWaveformFail(#[from] TrackWaveformError, Backtrace),
+  #[error("Failed to validate local tracks")]
+  ValidateLocalTracksFail(#[from] ValidateLocalTracksError, Backtrace),
+  #[error("Failed to get track lyrics")]
+  TrackLyricsFail(#[from] TrackLyricsError, Backtrace),
   #[error("Failed to start sync or get sync status")]
   SyncFail(#[from] SyncClientError, Backtrace),
+  #[error("Failed to preview sync")]
+  SyncPreviewFail(#[from] SyncPreviewError, Backtrace),
+  #[error("Failed to query or modify playlist")]
+  PlaylistFail(#[from] PlaylistError, Backtrace),
+  #[error("Failed to control listening session")]
+  ListeningSessionFail(#[from] ListeningSessionError, Backtrace),
+  #[error("Failed to undo operation")]
+  UndoFail(#[from] UndoError, Backtrace),
+  #[error("Failed to resolve metadata conflict")]
+  ResolveMetadataConflictFail(#[from] ResolveMetadataConflictError, Backtrace),
+  #[error("Failed to relink track or album")]
+  RelinkFail(#[from] RelinkError, Backtrace),
+  #[error("Failed to link versions of tracks")]
+  TrackRelationFail(#[from] TrackRelationError, Backtrace),
+  #[error("Failed to set album cover")]
+  AlbumCoverFail(#[from] AlbumCoverError, Backtrace),
+  #[error("Failed to get album cover or artist image")]
+  MediaVariantFail(#[from] MediaVariantError, Backtrace),
+  #[error("Failed to manage party")]
+  PartyFail(#[from] PartyError, Backtrace),
+  #[error("Uploading tracks is disabled on this server")]
+  TrackUploadDisabledFail,
+  #[error("Failed to receive uploaded track: {0}")]
+  TrackUploadReceiveFail(PayloadError),
+  #[error("Uploaded track exceeds the maximum size of {0} bytes")]
+  TrackUploadTooLargeFail(usize),
+  #[error("Failed to upload track")]
+  TrackUploadFail(#[from] TrackUploadError, Backtrace),
+  #[error("Blocking thread pool is gone")]
+  BlockingThreadPoolGoneFail,
+  #[error("The managed library is not organized on this server")]
+  ManagedLibraryDisabledFail,
+  #[error("Failed to organize the managed library")]
+  OrganizeManagedLibraryFail(#[from] OrganizeManagedLibraryError, Backtrace),
+  #[error("Invalid webhook URL")]
+  WebhookUrlFail(#[from] WebhookUrlError, Backtrace),
+  #[error("Failed to set maintenance mode")]
+  MaintenanceFail(#[from] MaintenanceError, Backtrace),
+  #[error("Failed to start WebSocket connection: {0:?}")]
+  WebSocketStartFail(actix_web::Error),
 }
 
 impl ResponseError for InternalError {
-  fn status_code(&self) -> StatusCode { StatusCode::INTERNAL_SERVER_ERROR }
+  fn status_code(&self) -> StatusCode {
+    match self {
+      InternalError::RelocateLocalSourceFail(RelocateLocalSourceError::DirectoryInUse(..), _) => StatusCode::CONFLICT,
+      InternalError::PlaylistFail(PlaylistError::PermissionDenied(..), _) => StatusCode::FORBIDDEN,
+      InternalError::ListeningSessionFail(ListeningSessionError::NotHost(..), _) => StatusCode::FORBIDDEN,
+      InternalError::PartyFail(PartyError::NotHost(..), _) => StatusCode::FORBIDDEN,
+      InternalError::PartyFail(PartyError::InvalidGuestToken, _) => StatusCode::UNAUTHORIZED,
+      InternalError::PartyFail(PartyError::RateLimited(_), _) => StatusCode::TOO_MANY_REQUESTS,
+      InternalError::SpotifyWriteBackDisabledFail => StatusCode::FORBIDDEN,
+      InternalError::RegistrationDisabledFail => StatusCode::FORBIDDEN,
+      InternalError::ContentFilterFail(SetContentFilterError::PinnedFail(..), _) => StatusCode::FORBIDDEN,
+      InternalError::NotAdminFail => StatusCode::FORBIDDEN,
+      InternalError::UserPreferencesFail(UserPreferencesError::DatabaseQueryFail(..), _) => StatusCode::INTERNAL_SERVER_ERROR,
+      InternalError::UserPreferencesFail(UserPreferencesError::PlaybackPreferenceFail(..), _) => StatusCode::INTERNAL_SERVER_ERROR,
+      InternalError::UserPreferencesFail(_, _) => StatusCode::BAD_REQUEST,
+      InternalError::SpotifyWriteBackFail(spotify::WriteBackError::NoSpotifySource(..), _) => StatusCode::BAD_REQUEST,
+      InternalError::SpotifyWriteBackFail(spotify::WriteBackError::SpotifyApiFail(..), _) => StatusCode::BAD_GATEWAY,
+      InternalError::ResolveMetadataConflictFail(ResolveMetadataConflictError::InvalidValue(..), _) => StatusCode::BAD_REQUEST,
+      InternalError::RelinkFail(RelinkError::DatabaseQueryFail(..), _) => StatusCode::INTERNAL_SERVER_ERROR,
+      InternalError::RelinkFail(_, _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackRelationFail(TrackRelationError::SelfRelationFail(_), _) => StatusCode::BAD_REQUEST,
+      InternalError::AlbumCoverFail(AlbumCoverError::DatabaseQueryFail(..), _) => StatusCode::INTERNAL_SERVER_ERROR,
+      InternalError::AlbumCoverFail(AlbumCoverError::FetchFail(CoverFetchError::InvalidReleaseIdFail(_), _), _) => StatusCode::BAD_REQUEST,
+      InternalError::AlbumCoverFail(AlbumCoverError::FetchFail(..), _) => StatusCode::BAD_GATEWAY,
+      InternalError::AlbumCoverFail(_, _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackUploadDisabledFail => StatusCode::FORBIDDEN,
+      InternalError::ManagedLibraryDisabledFail => StatusCode::FORBIDDEN,
+      InternalError::TrackUploadReceiveFail(_) => StatusCode::BAD_REQUEST,
+      InternalError::WebhookUrlFail(_, _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackUploadTooLargeFail(_) => StatusCode::PAYLOAD_TOO_LARGE,
+      InternalError::TrackUploadFail(TrackUploadError::FileExistsFail(_), _) => StatusCode::CONFLICT,
+      InternalError::TrackUploadFail(TrackUploadError::InvalidFileNameFail(_), _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackUploadFail(TrackUploadError::UnsupportedFileTypeFail(_), _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackUploadFail(TrackUploadError::ReadFail(..), _) => StatusCode::BAD_REQUEST,
+      InternalError::TrackUploadFail(TrackUploadError::NoTagFail(_), _) => StatusCode::BAD_REQUEST,
+      _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+  }
 
   fn error_response(&self) -> HttpResponse {
     let format_error = musium_core::format_error::FormatError::new(self);
@@ -0,0 +1,33 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::HttpResponse;
+
+use musium_core::api::{API_PATH_PREFIX, API_VERSION, API_VERSION_HEADER, InternalServerError};
+
+/// Oldest version of the HTTP API that the server still serves.
+pub const MIN_API_VERSION: u32 = 1;
+
+/// Returns `path` without the versioned API prefix, so that requests to the versioned endpoints and to their
+/// unversioned compatibility aliases can be handled alike.
+pub fn unversioned_path(path: &str) -> &str {
+  match path.strip_prefix('/').and_then(|path| path.strip_prefix(API_PATH_PREFIX)) {
+    Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+    _ => path,
+  }
+}
+
+/// Returns a 406 Not Acceptable response if `request` expects a version of the HTTP API that the server does not
+/// serve, or `None` if the request is allowed. Requests without a version header are always allowed, for
+/// compatibility with clients that predate API versioning.
+pub fn reject(request: &ServiceRequest) -> Option<HttpResponse> {
+  let header = request.headers().get(API_VERSION_HEADER)?;
+  let version = header.to_str().ok().and_then(|version| version.trim().parse::<u32>().ok());
+  match version {
+    Some(version) if version >= MIN_API_VERSION && version <= API_VERSION => None,
+    Some(version) => Some(HttpResponse::NotAcceptable().json(InternalServerError {
+      message: format!("API version {} is not supported; server supports API versions {} to {}", version, MIN_API_VERSION, API_VERSION)
+    })),
+    None => Some(HttpResponse::NotAcceptable().json(InternalServerError {
+      message: format!("Invalid API version header '{}'", String::from_utf8_lossy(header.as_bytes()))
+    })),
+  }
+}
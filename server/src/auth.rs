@@ -1,26 +1,74 @@
 use std::backtrace::Backtrace;
-use std::future::Future;
+use std::future::{Future, ready, Ready};
 use std::pin::Pin;
 
-use actix_identity::Identity;
+use actix_identity::{CookieIdentityPolicy, Identity, IdentityPolicy};
 use actix_web::{FromRequest, HttpRequest, HttpResponse, ResponseError, web};
-use actix_web::dev::{Payload, PayloadStream};
+use actix_web::dev::{Payload, PayloadStream, ServiceRequest, ServiceResponse};
 use actix_web::error::BlockingError;
-use actix_web::http::StatusCode;
+use actix_web::http::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{event, Level};
 
-use musium_backend::database::{Database, DatabaseConnectError, user::UserAddVerifyError};
-use musium_core::api::InternalServerError;
+use musium_backend::database::{Database, DatabaseConnectError, DatabaseQueryError, user::{RegisterUserError, UserAddVerifyError}};
+use musium_backend::party::{Parties, PartyError};
+use musium_core::api::{Capabilities, InternalServerError, KioskLogin, PartyGuest, UserRegistration};
 use musium_core::format_error::FormatError;
-use musium_core::model::{User, UserLogin};
+use musium_core::model::{NewUser, User, UserLogin};
+
+use crate::api_version::unversioned_path;
+use crate::public_url::PublicUrl;
 
 // Logged-in user
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoggedInUser {
   pub user: User,
+  /// Whether this user logged in through the kiosk login, and may therefore only browse and play.
+  #[serde(default)]
+  pub read_only: bool,
+}
+
+/// Unversioned routes that read-only users may request with the GET or HEAD method, to browse and play. Other routes
+/// that are requested with these methods may still modify data or reveal administrative information, such as requesting
+/// Spotify authorization.
+const READ_ONLY_ALLOWED_READ_ROUTES: &[&str] = &[
+  "/info", "/capabilities",
+  "/album", "/album/alphabet_index", "/album/{id}", "/album/{id}/cover",
+  "/track", "/track/page", "/track/random", "/track/play", "/track/{id}", "/track/{id}/technical", "/track/{id}/waveform",
+  "/track/{id}/lyrics", "/track/{id}/audio_features", "/track/{id}/tag", "/track/{id}/versions",
+  "/track/play_source_kind/{id}", "/track/play/{id}",
+  "/composer", "/composer/{id}/works", "/work/{id}/tracks",
+  "/artist", "/artist/alphabet_index", "/artist/{id}", "/artist/{id}/image",
+  "/search/track", "/tag", "/audio_features",
+  "/playlist", "/playlist/mix", "/playlist/{id}",
+  "/playback/preferences", "/user/me", "/user/me/content_filter",
+  "/events", "/events/poll", "/events/sse",
+];
+
+/// Unversioned routes that are requested with a modifying method but only read data, which read-only users may
+/// therefore request.
+const READ_ONLY_ALLOWED_ROUTES: &[&str] = &["/track/query", "/track/recommend"];
+
+impl LoggedInUser {
+  /// Returns whether this user may make `req`. Read-only users may only request the routes to browse and play, which do
+  /// not modify data.
+  fn may_request(&self, req: &HttpRequest) -> bool {
+    if !self.read_only { return true; }
+    let pattern = match req.match_pattern() {
+      Some(pattern) => pattern,
+      None => return false,
+    };
+    let route = match req.app_data::<web::Data<PublicUrl>>() {
+      Some(public_url) => public_url.strip_path_prefix(&pattern),
+      None => &pattern,
+    };
+    let route = unversioned_path(route);
+    let method = req.method();
+    let allowed_routes = if method == Method::GET || method == Method::HEAD { READ_ONLY_ALLOWED_READ_ROUTES } else { READ_ONLY_ALLOWED_ROUTES };
+    allowed_routes.contains(&route)
+  }
 }
 
 // Login
@@ -35,11 +83,25 @@ pub enum InternalLoginError {
   BlockingThreadPoolGoneFail,
   #[error("Failed to serialize identity")]
   SerializeIdentityFail(#[from] serde_json::Error),
+  #[error("Registration is disabled on this server")]
+  RegistrationDisabledFail,
+  #[error("Failed to register user")]
+  RegisterFail(#[from] RegisterUserError, Backtrace),
+  #[error("Kiosk login is disabled on this server")]
+  KioskDisabledFail,
+  #[error("Failed to get kiosk user")]
+  KioskUserGetFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Kiosk user '{0}' does not exist")]
+  KioskUserMissingFail(String),
 }
 
 impl ResponseError for InternalLoginError {
   fn status_code(&self) -> StatusCode {
     match self {
+      InternalLoginError::RegistrationDisabledFail => StatusCode::FORBIDDEN,
+      InternalLoginError::RegisterFail(RegisterUserError::InvalidInviteFail, _) => StatusCode::FORBIDDEN,
+      InternalLoginError::RegisterFail(RegisterUserError::NameTakenFail(_), _) => StatusCode::CONFLICT,
+      InternalLoginError::KioskDisabledFail => StatusCode::NOT_FOUND,
       _ => StatusCode::INTERNAL_SERVER_ERROR
     }
   }
@@ -69,7 +131,7 @@ pub async fn login(user_login: web::Json<UserLogin>, identity: Identity, databas
       Err(e)
     }
     Ok(Ok(Some(user))) => {
-      identity.remember(serde_json::to_string(&LoggedInUser { user: user.clone() })?);
+      identity.remember(serde_json::to_string(&LoggedInUser { user: user.clone(), read_only: false })?);
       Ok(HttpResponse::Ok().json(&user))
     }
     Ok(Ok(None)) => {
@@ -78,6 +140,150 @@ pub async fn login(user_login: web::Json<UserLogin>, identity: Identity, databas
   }
 }
 
+// Setup
+
+/// Creates the first user of a new server and logs in as that user. Disabled (404 Not Found) once any user exists.
+pub async fn setup(new_user: web::Json<NewUser>, identity: Identity, database: web::Data<Database>) -> Result<HttpResponse, InternalLoginError> {
+  use InternalLoginError::*;
+
+  let result: Result<Result<Option<User>, InternalLoginError>, BlockingError> = web::block(move || {
+    let backend_connected = database.connect()?;
+    Ok(backend_connected.create_first_user(new_user.into_inner())?)
+  }).await;
+
+  match result {
+    Err(_) => {
+      Err(BlockingThreadPoolGoneFail)
+    }
+    Ok(Err(e)) => {
+      Err(e)
+    }
+    Ok(Ok(Some(user))) => {
+      event!(Level::INFO, user.name = %user.name, "Created first user through setup");
+      identity.remember(serde_json::to_string(&LoggedInUser { user: user.clone(), read_only: false })?);
+      Ok(HttpResponse::Ok().json(&user))
+    }
+    Ok(Ok(None)) => {
+      Ok(HttpResponse::NotFound().finish())
+    }
+  }
+}
+
+// Registration
+
+/// Registers a new user by redeeming an invite code, and logs in as that user.
+pub async fn register(registration: web::Json<UserRegistration>, identity: Identity, database: web::Data<Database>, capabilities: web::Data<Capabilities>) -> Result<HttpResponse, InternalLoginError> {
+  use InternalLoginError::*;
+
+  if !capabilities.registration { return Err(RegistrationDisabledFail); }
+  let result: Result<Result<User, InternalLoginError>, BlockingError> = web::block(move || {
+    let backend_connected = database.connect()?;
+    Ok(backend_connected.register_user(registration.into_inner())?)
+  }).await;
+
+  match result {
+    Err(_) => {
+      Err(BlockingThreadPoolGoneFail)
+    }
+    Ok(Err(e)) => {
+      Err(e)
+    }
+    Ok(Ok(user)) => {
+      identity.remember(serde_json::to_string(&LoggedInUser { user: user.clone(), read_only: false })?);
+      Ok(HttpResponse::Ok().json(&user))
+    }
+  }
+}
+
+// File path access
+
+/// Users that may see the paths of local files on the server, for revealing files in the file manager of clients that
+/// run on the same machine as the server. Read-only users may never see paths.
+#[derive(Clone, Default, Debug)]
+pub struct FilePathAccess {
+  user_names: Vec<String>,
+}
+
+impl FilePathAccess {
+  pub fn new(user_names: Vec<String>) -> Self { Self { user_names } }
+
+  /// Returns whether `logged_in_user` may see the paths of local files.
+  pub fn may_see_paths(&self, logged_in_user: &LoggedInUser) -> bool {
+    !logged_in_user.read_only && self.user_names.contains(&logged_in_user.user.name)
+  }
+}
+
+// Admins
+
+/// Users that may administer the server and other users, such as switching maintenance mode, creating invite codes, and
+/// pinning the content filter of other users. Read-only users are never admins.
+#[derive(Clone, Default, Debug)]
+pub struct Admins {
+  user_names: Vec<String>,
+}
+
+impl Admins {
+  pub fn new(user_names: Vec<String>) -> Self { Self { user_names } }
+
+  /// Returns whether `logged_in_user` is an admin.
+  pub fn is_admin(&self, logged_in_user: &LoggedInUser) -> bool {
+    !logged_in_user.read_only && self.user_names.contains(&logged_in_user.user.name)
+  }
+}
+
+// Kiosk login
+
+/// Kiosk login settings of the server: the user that kiosk logins log in as, and the token they must provide.
+#[derive(Clone)]
+pub struct KioskSettings {
+  user_name: String,
+  token_hash: blake3::Hash,
+}
+
+impl KioskSettings {
+  pub fn new(user_name: String, token: &str) -> Self {
+    Self { user_name, token_hash: blake3::hash(token.as_bytes()) }
+  }
+
+  fn verify_token(&self, token: &str) -> bool {
+    // Comparison of hashes is constant-time.
+    blake3::hash(token.as_bytes()) == self.token_hash
+  }
+}
+
+/// Logs in as the kiosk user with a read-only identity, which can browse and play but not modify data. Disabled (404
+/// Not Found) when the server has no kiosk settings.
+pub async fn kiosk_login(kiosk_login: web::Json<KioskLogin>, identity: Identity, database: web::Data<Database>, kiosk: web::Data<Option<KioskSettings>>) -> Result<HttpResponse, InternalLoginError> {
+  use InternalLoginError::*;
+
+  let kiosk = kiosk.as_ref().as_ref().ok_or(KioskDisabledFail)?;
+  if !kiosk.verify_token(&kiosk_login.token) {
+    return Ok(HttpResponse::Unauthorized().finish());
+  }
+  let user_name = kiosk.user_name.clone();
+  let result: Result<Result<Option<User>, InternalLoginError>, BlockingError> = web::block(move || {
+    let backend_connected = database.connect()?;
+    Ok(backend_connected.get_user_by_name(&user_name)?)
+  }).await;
+
+  match result {
+    Err(_) => {
+      Err(BlockingThreadPoolGoneFail)
+    }
+    Ok(Err(e)) => {
+      Err(e)
+    }
+    Ok(Ok(Some(user))) => {
+      event!(Level::INFO, user.name = %user.name, "Logged in as kiosk user");
+      identity.remember(serde_json::to_string(&LoggedInUser { user: user.clone(), read_only: true })?);
+      Ok(HttpResponse::Ok().json(&user))
+    }
+    Ok(Ok(None)) => {
+      Err(KioskUserMissingFail(kiosk.user_name.clone()))
+    }
+  }
+}
+
 // Logout
 
 pub async fn logout(identity: Identity) -> HttpResponse {
@@ -85,6 +291,52 @@ pub async fn logout(identity: Identity) -> HttpResponse {
   HttpResponse::Ok().finish()
 }
 
+// Cookie identity policy
+
+/// Cookie identity policy that supports rotating the cookie secret key. Identity cookies signed with the previous key
+/// are still accepted, and are re-issued with the current key, so that users stay logged in when the key is rotated.
+/// Cookies are restricted to `cookie_path`, so that they are not sent to other applications behind the same reverse
+/// proxy.
+pub struct RotatingCookieIdentityPolicy {
+  current: CookieIdentityPolicy,
+  previous: Option<CookieIdentityPolicy>,
+}
+
+/// Marks requests whose identity cookie was signed with the previous key.
+struct PreviousKeyIdentity;
+
+impl RotatingCookieIdentityPolicy {
+  pub fn new(current_key: &[u8], previous_key: Option<&[u8]>, cookie_path: &str) -> Self {
+    let create = |key: &[u8]| CookieIdentityPolicy::new(key)
+      .name("auth")
+      .path(cookie_path)
+      .secure(false);
+    Self { current: create(current_key), previous: previous_key.map(create) }
+  }
+}
+
+impl IdentityPolicy for RotatingCookieIdentityPolicy {
+  type Future = Ready<Result<Option<String>, actix_web::Error>>;
+  type ResponseFuture = Ready<Result<(), actix_web::Error>>;
+
+  fn from_request(&self, request: &mut ServiceRequest) -> Self::Future {
+    let result = self.current.from_request(request).into_inner();
+    if let (Ok(None), Some(previous)) = (&result, &self.previous) {
+      let result = previous.from_request(request).into_inner();
+      if let Ok(Some(_)) = result {
+        request.extensions_mut().insert(PreviousKeyIdentity);
+      }
+      return ready(result);
+    }
+    ready(result)
+  }
+
+  fn to_response<B>(&self, identity: Option<String>, changed: bool, response: &mut ServiceResponse<B>) -> Self::ResponseFuture {
+    let reissue = identity.is_some() && response.request().extensions().get::<PreviousKeyIdentity>().is_some();
+    self.current.to_response(identity, changed || reissue, response)
+  }
+}
+
 // Logged-in user extractor
 
 #[derive(Debug, Error)]
@@ -95,12 +347,15 @@ pub enum LoggedInUserExtractInternalError {
   DeserializeIdentityFail(#[from] serde_json::Error),
   #[error("Not logged in")]
   NotLoggedInFail,
+  #[error("Logged in as a read-only kiosk user")]
+  ReadOnlyFail,
 }
 
 impl ResponseError for LoggedInUserExtractInternalError {
   fn status_code(&self) -> StatusCode {
     match self {
       Self::NotLoggedInFail => StatusCode::UNAUTHORIZED,
+      Self::ReadOnlyFail => StatusCode::FORBIDDEN,
       _ => StatusCode::INTERNAL_SERVER_ERROR
     }
   }
@@ -109,6 +364,7 @@ impl ResponseError for LoggedInUserExtractInternalError {
     let status_code = self.status_code();
     match self {
       Self::NotLoggedInFail => HttpResponse::build(status_code).finish(),
+      Self::ReadOnlyFail => HttpResponse::build(status_code).json(InternalServerError { message: self.to_string() }),
       _ => {
         let format_error = FormatError::new(self);
         event!(Level::ERROR, "{:?}", format_error);
@@ -127,9 +383,11 @@ impl FromRequest for LoggedInUser {
   fn from_request(req: &HttpRequest, payload: &mut Payload<PayloadStream>) -> Self::Future {
     use LoggedInUserExtractInternalError::*;
     let identity = Identity::from_request(req, payload);
+    let req = req.clone();
     Box::pin(async move {
       if let Some(serialized_identity) = identity.await?.identity() {
-        let logged_in_user = serde_json::from_str(&serialized_identity)?;
+        let logged_in_user: LoggedInUser = serde_json::from_str(&serialized_identity)?;
+        if !logged_in_user.may_request(&req) { return Err(ReadOnlyFail); }
         Ok(logged_in_user)
       } else {
         Err(NotLoggedInFail)
@@ -137,3 +395,106 @@ impl FromRequest for LoggedInUser {
     })
   }
 }
+
+// Party guest extractor
+
+/// Guest of a party, authenticated by the guest token in the `X-Musium-Guest-Token` header instead of a logged-in user.
+/// Every extraction counts as a request against the rate limit of the party.
+#[derive(Debug)]
+pub struct LoggedInGuest {
+  pub guest: PartyGuest,
+}
+
+pub const GUEST_TOKEN_HEADER: &str = "X-Musium-Guest-Token";
+
+#[derive(Debug, Error)]
+pub enum LoggedInGuestExtractError {
+  #[error("Party registry is not available")]
+  PartiesMissingFail,
+  #[error("No guest token was provided")]
+  NoGuestTokenFail,
+  #[error(transparent)]
+  PartyFail(#[from] PartyError),
+}
+
+impl ResponseError for LoggedInGuestExtractError {
+  fn status_code(&self) -> StatusCode {
+    match self {
+      Self::NoGuestTokenFail => StatusCode::UNAUTHORIZED,
+      Self::PartyFail(PartyError::RateLimited(_)) => StatusCode::TOO_MANY_REQUESTS,
+      Self::PartyFail(_) => StatusCode::UNAUTHORIZED,
+      _ => StatusCode::INTERNAL_SERVER_ERROR
+    }
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    let status_code = self.status_code();
+    if let Self::PartiesMissingFail = self {
+      let format_error = FormatError::new(self);
+      event!(Level::ERROR, "{:?}", format_error);
+    }
+    HttpResponse::build(status_code).json(InternalServerError {
+      message: self.to_string()
+    })
+  }
+}
+
+impl FromRequest for LoggedInGuest {
+  type Error = LoggedInGuestExtractError;
+  type Future = Ready<Result<LoggedInGuest, LoggedInGuestExtractError>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload<PayloadStream>) -> Self::Future {
+    use LoggedInGuestExtractError::*;
+    let result = (|| {
+      let parties = req.app_data::<web::Data<Parties>>().ok_or(PartiesMissingFail)?;
+      let token = req.headers().get(GUEST_TOKEN_HEADER).and_then(|v| v.to_str().ok()).ok_or(NoGuestTokenFail)?;
+      let guest = parties.authorize_guest_request(token)?;
+      Ok(LoggedInGuest { guest })
+    })();
+    ready(result)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::{App, test};
+
+  use musium_core::api::API_PATH_PREFIX;
+
+  use super::*;
+
+  /// Responds with 200 OK if a read-only user may make `req`, and with 403 Forbidden otherwise.
+  async fn read_only_may_request(req: HttpRequest) -> HttpResponse {
+    let logged_in_user = LoggedInUser { user: User { id: 1, name: "kiosk".to_string() }, read_only: true };
+    if logged_in_user.may_request(&req) { HttpResponse::Ok().finish() } else { HttpResponse::Forbidden().finish() }
+  }
+
+  async fn read_only_request_status(method: Method, uri: &str) -> StatusCode {
+    let app = test::init_service(App::new()
+      .service(web::scope(&format!("/{}", API_PATH_PREFIX))
+        .route("/album/{id}", web::get().to(read_only_may_request))
+        .route("/track/query", web::post().to(read_only_may_request))
+        .route("/source/spotify/request_authorization", web::get().to(read_only_may_request))
+        .route("/playlist", web::post().to(read_only_may_request))
+      )
+    ).await;
+    let request = test::TestRequest::default().method(method).uri(uri).to_request();
+    test::call_service(&app, request).await.status()
+  }
+
+  #[actix_rt::test]
+  async fn read_only_user_may_browse() {
+    assert_eq!(read_only_request_status(Method::GET, &format!("/{}/album/5", API_PATH_PREFIX)).await, StatusCode::OK);
+    assert_eq!(read_only_request_status(Method::POST, &format!("/{}/track/query", API_PATH_PREFIX)).await, StatusCode::OK);
+  }
+
+  #[actix_rt::test]
+  async fn read_only_user_is_refused_spotify_authorization() {
+    assert_eq!(read_only_request_status(Method::GET, &format!("/{}/source/spotify/request_authorization", API_PATH_PREFIX)).await, StatusCode::FORBIDDEN);
+  }
+
+  #[actix_rt::test]
+  async fn read_only_user_is_refused_modification() {
+    assert_eq!(read_only_request_status(Method::POST, &format!("/{}/playlist", API_PATH_PREFIX)).await, StatusCode::FORBIDDEN);
+  }
+}
@@ -0,0 +1,92 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::web::Bytes;
+use actix_web_actors::ws;
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt};
+use tokio_stream::wrappers::{BroadcastStream, IntervalStream};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tracing::{event, Level};
+
+use musium_core::api::Event;
+
+/// WebSocket connection that forwards events from the event bus to a client as JSON text messages.
+pub struct EventSocket {
+  rx: Option<broadcast::Receiver<Event>>,
+}
+
+impl EventSocket {
+  pub fn new(rx: broadcast::Receiver<Event>) -> Self {
+    Self { rx: Some(rx) }
+  }
+}
+
+impl Actor for EventSocket {
+  type Context = ws::WebsocketContext<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    if let Some(rx) = self.rx.take() {
+      ctx.add_stream(BroadcastStream::new(rx));
+    }
+  }
+}
+
+impl StreamHandler<Result<Event, BroadcastStreamRecvError>> for EventSocket {
+  fn handle(&mut self, item: Result<Event, BroadcastStreamRecvError>, ctx: &mut Self::Context) {
+    match item {
+      Ok(event) => match serde_json::to_string(&event) {
+        Ok(text) => ctx.text(text),
+        Err(e) => event!(Level::ERROR, ?event, "Failed to serialize event: {:?}", e),
+      },
+      Err(BroadcastStreamRecvError::Lagged(count)) => event!(Level::WARN, "WebSocket connection lagged behind, skipped {} event(s)", count),
+    }
+  }
+
+  fn finished(&mut self, _ctx: &mut Self::Context) {
+    // Event bus was dropped, but keep the WebSocket connection open until the client closes it.
+  }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for EventSocket {
+  fn handle(&mut self, item: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+    match item {
+      Ok(ws::Message::Ping(message)) => ctx.pong(&message),
+      Ok(ws::Message::Close(reason)) => {
+        ctx.close(reason);
+        ctx.stop();
+      }
+      Ok(_) => {} // Ignore other messages; this is a one-way event stream.
+      Err(e) => {
+        event!(Level::ERROR, "WebSocket protocol error: {:?}", e);
+        ctx.stop();
+      }
+    }
+  }
+}
+
+// Server-sent events
+
+/// Interval between comments sent over a server-sent event stream to keep proxies from closing an idle connection.
+const SSE_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Creates a server-sent event stream that forwards events from `rx` as JSON data, interleaved with keep-alive comments.
+pub fn sse_stream(rx: broadcast::Receiver<Event>) -> impl Stream<Item=Result<Bytes, Infallible>> {
+  let events = BroadcastStream::new(rx).filter_map(|item| match item {
+    Ok(event) => match serde_json::to_string(&event) {
+      Ok(text) => Some(Bytes::from(format!("data: {}\n\n", text))),
+      Err(e) => {
+        event!(Level::ERROR, ?event, "Failed to serialize event: {:?}", e);
+        None
+      }
+    },
+    Err(BroadcastStreamRecvError::Lagged(count)) => {
+      event!(Level::WARN, "Server-sent event stream lagged behind, skipped {} event(s)", count);
+      None
+    }
+  });
+  let keep_alive = IntervalStream::new(tokio::time::interval(SSE_KEEP_ALIVE_INTERVAL))
+    .map(|_| Bytes::from_static(b": keep-alive\n\n"));
+  events.merge(keep_alive).map(Ok)
+}
@@ -0,0 +1,85 @@
+use actix_web::{HttpResponse, web};
+use tracing::{event, Level};
+
+use musium_backend::database::Database;
+use musium_backend::worker::WorkerState;
+use musium_core::api::{BackgroundWorkerState, BackgroundWorkerStatus, ReadinessStatus};
+use musium_core::format_error::FormatError;
+
+/// Number of bytes that must be available on the volume of the database for the server to be ready, so that the
+/// database can still grow and syncs do not fail halfway through.
+const MINIMUM_AVAILABLE_DISK_SPACE: u64 = 64 * 1024 * 1024;
+
+/// States of the background workers of the server, reported by the readiness health probe.
+#[derive(Clone)]
+pub struct BackgroundWorkers {
+  pub sync: WorkerState,
+  pub webhook_dispatch: WorkerState,
+  pub release_check: WorkerState,
+  pub mix_generation: WorkerState,
+  /// Analysis of audio features, which is busy while an analysis started through the API is running.
+  pub audio_feature_analysis: WorkerState,
+  /// Validation of local tracks, which is busy while a validation started through the API is running.
+  pub track_validation: WorkerState,
+}
+
+impl BackgroundWorkers {
+  pub fn statuses(&self) -> Vec<BackgroundWorkerStatus> {
+    [
+      ("sync", &self.sync),
+      ("webhook_dispatch", &self.webhook_dispatch),
+      ("release_check", &self.release_check),
+      ("mix_generation", &self.mix_generation),
+      ("audio_feature_analysis", &self.audio_feature_analysis),
+      ("track_validation", &self.track_validation),
+    ].into_iter().map(|(name, state)| BackgroundWorkerStatus { name: name.to_string(), state: state.get() }).collect()
+  }
+}
+
+/// Liveness health probe, which succeeds as long as the server is able to handle requests.
+pub async fn liveness() -> HttpResponse {
+  HttpResponse::Ok().finish()
+}
+
+/// Readiness health probe, which succeeds when a database connection can be acquired from the pool, all migrations
+/// have been applied to the database, enough disk space is available on the volume of the database, and no background
+/// worker has stopped. Responds with a 503 Service Unavailable otherwise.
+pub async fn readiness(database: web::Data<Database>, background_workers: web::Data<BackgroundWorkers>) -> HttpResponse {
+  let status = web::block(move || {
+    let mut status = ReadinessStatus::default();
+    status.background_workers = background_workers.statuses();
+    let workers_running = status.background_workers.iter().all(|worker| worker.state != BackgroundWorkerState::Stopped);
+    if !workers_running {
+      event!(Level::WARN, "Not ready, one or more background workers have stopped");
+    }
+    match database.available_disk_space() {
+      Ok(available_disk_space) => status.available_disk_space = Some(available_disk_space),
+      Err(e) => event!(Level::WARN, "Not ready, failed to get available disk space of the database volume: {:?}", e),
+    }
+    let enough_disk_space = status.available_disk_space.map_or(false, |space| space >= MINIMUM_AVAILABLE_DISK_SPACE);
+    if status.available_disk_space.is_some() && !enough_disk_space {
+      event!(Level::WARN, "Not ready, not enough disk space available on the database volume");
+    }
+    let connection = match database.connect() {
+      Ok(connection) => connection,
+      Err(e) => {
+        event!(Level::WARN, "Not ready, database is not connected: {:?}", FormatError::new(&e));
+        return status;
+      }
+    };
+    status.database_connected = true;
+    match connection.list_pending_migrations() {
+      Ok(pending_migrations) => {
+        status.ready = pending_migrations.is_empty() && enough_disk_space && workers_running;
+        status.pending_migrations = pending_migrations;
+      }
+      Err(e) => event!(Level::WARN, "Not ready, failed to list pending migrations: {:?}", FormatError::new(&e)),
+    }
+    status
+  }).await;
+  match status {
+    Ok(status) if status.ready => HttpResponse::Ok().json(status),
+    Ok(status) => HttpResponse::ServiceUnavailable().json(status),
+    Err(_) => HttpResponse::ServiceUnavailable().json(ReadinessStatus::default()),
+  }
+}
@@ -1,6 +1,8 @@
 #![feature(backtrace)]
 
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use dotenv;
@@ -8,20 +10,40 @@ use metrics_core::{Builder, Drain, Observe};
 use metrics_observer_yaml::{YamlBuilder, YamlObserver};
 use metrics_runtime::{Controller, Receiver};
 use structopt::StructOpt;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 use tracing_subscriber::prelude::*;
+use url::Url;
 
+use musium_backend::artist_info::ArtistEnrichmentSettings;
 use musium_backend::database::Database;
+use musium_backend::managed_library::{LibraryTemplate, ManagedLibrarySettings};
 use musium_backend::password::PasswordHasher;
+use musium_backend::tagger_hook::TaggerHookSettings;
+use musium_core::api::API_PATH_PREFIX;
 use musium_core::model::NewUser;
 use musium_spotify_client::SpotifyClient;
 
-use crate::serve::serve;
+use crate::auth::{Admins, FilePathAccess, KioskSettings};
+use crate::public_url::PublicUrl;
+use crate::rate_limit::RateLimiter;
+use crate::serve::{CorsSettings, serve, SPOTIFY_AUTHORIZATION_CALLBACK_PATH};
+use crate::systemd;
 
 pub mod serve;
 pub mod auth;
 pub mod api;
+pub mod api_version;
+pub mod event;
+pub mod health;
+pub mod maintenance;
+pub mod public_url;
+pub mod rate_limit;
+pub mod systemd;
+pub mod url_signing;
+pub mod validation;
+pub mod web_ui;
+pub mod zeroconf;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "server", about = "Musium server")]
@@ -30,15 +52,32 @@ struct Opt {
   #[structopt(long, env = "MUSIUM_DATABASE_URL", parse(from_os_str))]
   database_file: PathBuf,
 
-  /// Address (IP:port) to bind the HTTP server to
+  /// Address (IP:port) to bind the HTTP server to. Ignored when a listening socket is passed by the service manager
+  /// through socket activation
   #[structopt(long, env = "MUSIUM_BIND_ADDRESS", default_value = "127.0.0.1:8088")]
   bind_address: String,
   /// Password hasher secret key to use
-  #[structopt(long, env = "MUSIUM_PASSWORD_HASHER_SECRET_KEY")]
-  password_hasher_secret_key: String,
+  #[structopt(long, env = "MUSIUM_PASSWORD_HASHER_SECRET_KEY", required_unless = "password_hasher_secret_key_file", hide_env_values = true)]
+  password_hasher_secret_key: Option<String>,
+  /// File to read the password hasher secret key from, instead of passing the key directly. Trailing newlines are
+  /// ignored
+  #[structopt(long, env = "MUSIUM_PASSWORD_HASHER_SECRET_KEY_FILE", parse(from_os_str), conflicts_with = "password_hasher_secret_key")]
+  password_hasher_secret_key_file: Option<PathBuf>,
   /// Cookie identity secret key to use
-  #[structopt(long, env = "MUSIUM_COOKIE_IDENTITY_SECRET_KEY")]
-  cookie_identity_secret_key: String,
+  #[structopt(long, env = "MUSIUM_COOKIE_IDENTITY_SECRET_KEY", required_unless = "cookie_identity_secret_key_file", hide_env_values = true)]
+  cookie_identity_secret_key: Option<String>,
+  /// File to read the cookie identity secret key from, instead of passing the key directly. Trailing newlines are
+  /// ignored
+  #[structopt(long, env = "MUSIUM_COOKIE_IDENTITY_SECRET_KEY_FILE", parse(from_os_str), conflicts_with = "cookie_identity_secret_key")]
+  cookie_identity_secret_key_file: Option<PathBuf>,
+  /// Previous cookie identity secret key. Identity cookies signed with this key are still accepted and are re-issued
+  /// with the current key, so that rotating the key does not log out every user
+  #[structopt(long, env = "MUSIUM_PREVIOUS_COOKIE_IDENTITY_SECRET_KEY", hide_env_values = true)]
+  previous_cookie_identity_secret_key: Option<String>,
+  /// File to read the previous cookie identity secret key from, instead of passing the key directly. Trailing
+  /// newlines are ignored
+  #[structopt(long, env = "MUSIUM_PREVIOUS_COOKIE_IDENTITY_SECRET_KEY_FILE", parse(from_os_str), conflicts_with = "previous_cookie_identity_secret_key")]
+  previous_cookie_identity_secret_key_file: Option<PathBuf>,
 
   /// Spotify client ID to use
   #[structopt(long, env = "MUSIUM_SPOTIFY_CLIENT_ID")]
@@ -46,13 +85,133 @@ struct Opt {
   /// Spotify client secret to use
   #[structopt(long, env = "MUSIUM_SPOTIFY_CLIENT_SECRET")]
   spotify_client_secret: String,
+  /// Whether to allow users to write back to their Spotify account, such as saving albums, following artists, and
+  /// adding tracks to playlists. Users must re-authorize their Spotify source to grant the required permissions
+  #[structopt(long, env = "MUSIUM_SPOTIFY_WRITE_BACK")]
+  spotify_write_back: bool,
+  /// URI that Spotify redirects to after authorizing a Spotify source, such as
+  /// `https://example.com/api/v1/source/spotify/request_authorization/callback`, which must be registered as a redirect
+  /// URI of the Spotify app. Defaults to the callback under the public URL, or the URL at which the server is reached
+  #[structopt(long, env = "MUSIUM_SPOTIFY_REDIRECT_URI")]
+  spotify_redirect_uri: Option<Url>,
 
-  /// Name of the admin user that is created by default.
-  #[structopt(long, env = "MUSIUM_LOGIN_NAME")]
-  admin_name: String,
-  /// Password of the admin user that is created by default.
-  #[structopt(long, env = "MUSIUM_LOGIN_PASSWORD")]
-  admin_password: String,
+  /// Name of the admin user to create when no users exist. When not given, the first user must be created through the
+  /// setup endpoint instead
+  #[structopt(long, env = "MUSIUM_LOGIN_NAME", requires = "admin_password")]
+  admin_name: Option<String>,
+  /// Password of the admin user to create when no users exist
+  #[structopt(long, env = "MUSIUM_LOGIN_PASSWORD", requires = "admin_name", hide_env_values = true)]
+  admin_password: Option<String>,
+  /// Name of a user that may administer other users, such as pinning their content filter. Can be given multiple times.
+  /// The admin user given with `--admin-name` is always an admin
+  #[structopt(long = "admin-user", env = "MUSIUM_ADMIN_USERS", use_delimiter = true)]
+  admin_users: Vec<String>,
+
+  /// Whether to allow new users to register themselves with invite codes created by existing users
+  #[structopt(long, env = "MUSIUM_REGISTRATION")]
+  registration: bool,
+
+  /// ID of the local source that musium manages, into which uploaded tracks are stored. Uploading tracks is disabled
+  /// when not given
+  #[structopt(long, env = "MUSIUM_MANAGED_LOCAL_SOURCE_ID")]
+  managed_local_source_id: Option<i32>,
+  /// Whether to organize the files of the managed local source into paths given by the managed library template. Files
+  /// are moved when they are uploaded or synchronized, and when their metadata is edited
+  #[structopt(long, env = "MUSIUM_ORGANIZE_MANAGED_LIBRARY", requires = "managed_local_source_id")]
+  organize_managed_library: bool,
+  /// Template of the paths of files in the managed library, relative to its directory and without file extension. May
+  /// contain the placeholders `{album_artist}`, `{artist}`, `{album}`, `{title}`, `{track}` (padded to two digits), and
+  /// `{disc}`
+  #[structopt(long, env = "MUSIUM_MANAGED_LIBRARY_TEMPLATE", default_value = "{album_artist}/{album}/{track} {title}")]
+  managed_library_template: LibraryTemplate,
+
+  /// Command to run for each album that a sync added, such as `beet`, after which the tags of the files of the album are
+  /// read again. The command is run with the tagger hook arguments followed by the directories of the album, and with
+  /// the `MUSIUM_ALBUM_ID` and `MUSIUM_ALBUM_NAME` environment variables set
+  #[structopt(long, env = "MUSIUM_TAGGER_HOOK", parse(from_os_str))]
+  tagger_hook: Option<PathBuf>,
+  /// Argument to pass to the tagger hook before the directories of the album, such as `import`. Can be given multiple
+  /// times
+  #[structopt(long = "tagger-hook-arg", env = "MUSIUM_TAGGER_HOOK_ARGS", use_delimiter = true, requires = "tagger_hook", allow_hyphen_values = true)]
+  tagger_hook_args: Vec<String>,
+  /// Library database of beets, usually `library.db` in the beets configuration directory. When given, the metadata of
+  /// local tracks in this database overrides the metadata from their tags, so that metadata curated with beets is
+  /// canonical. The database is only read
+  #[structopt(long, env = "MUSIUM_BEETS_DATABASE", parse(from_os_str))]
+  beets_database: Option<PathBuf>,
+
+  /// Name of the existing user that kiosk logins log in as. Kiosk logins provide the kiosk token instead of a password,
+  /// and can only browse and play, not modify ratings, playlists, sources, or other data. Kiosk login is disabled when
+  /// not given
+  #[structopt(long, env = "MUSIUM_KIOSK_USER")]
+  kiosk_user: Option<String>,
+  /// Token that kiosk logins must provide. Required when a kiosk user is given
+  #[structopt(long, env = "MUSIUM_KIOSK_TOKEN", hide_env_values = true)]
+  kiosk_token: Option<String>,
+  /// File to read the kiosk token from, instead of passing the token directly. Trailing newlines are ignored
+  #[structopt(long, env = "MUSIUM_KIOSK_TOKEN_FILE", parse(from_os_str), conflicts_with = "kiosk_token")]
+  kiosk_token_file: Option<PathBuf>,
+
+  /// Name of a user that may see the paths of local files, so that clients running on the same machine as the server
+  /// can reveal files in the file manager. Can be given multiple times. Only give this for trusted local deployments.
+  /// Paths are hidden from all users when not given
+  #[structopt(long = "file-path-user", env = "MUSIUM_FILE_PATH_USERS", use_delimiter = true)]
+  file_path_users: Vec<String>,
+
+  /// Interval in seconds between checks for new releases of followed artists
+  #[structopt(long, env = "MUSIUM_RELEASE_CHECK_INTERVAL", default_value = "86400")]
+  release_check_interval: u64,
+  /// Interval in seconds between checks for stale daily and weekly mixes of users, which are then regenerated
+  #[structopt(long, env = "MUSIUM_MIX_GENERATION_INTERVAL", default_value = "3600")]
+  mix_generation_interval: u64,
+
+  /// Maximum total size in MiB of media that can be evicted from the media cache, such as waveforms and artist images
+  #[structopt(long, env = "MUSIUM_MEDIA_CACHE_SIZE_LIMIT", default_value = "512")]
+  media_cache_size_limit: i64,
+
+  /// Whether to fetch images and biographies of artists from Wikipedia after each sync. Always enabled when a TheAudioDB
+  /// API key is given
+  #[structopt(long, env = "MUSIUM_ARTIST_ENRICHMENT")]
+  artist_enrichment: bool,
+  /// TheAudioDB API key to use for fetching images and biographies of artists, before falling back to Wikipedia
+  #[structopt(long, env = "MUSIUM_THEAUDIODB_API_KEY")]
+  theaudiodb_api_key: Option<String>,
+
+  /// Origin that browser-based clients may call the API from, such as `https://musium.example.com`. Can be given
+  /// multiple times. Use `*` to allow any origin. Cross-origin requests are blocked when not given
+  #[structopt(long = "cors-allowed-origin", env = "MUSIUM_CORS_ALLOWED_ORIGINS", use_delimiter = true)]
+  cors_allowed_origins: Vec<String>,
+  /// Whether to allow cross-origin requests to include credentials, such as the login cookie
+  #[structopt(long, env = "MUSIUM_CORS_ALLOW_CREDENTIALS")]
+  cors_allow_credentials: bool,
+
+  /// Public URL at which clients reach the server, such as `https://example.com/musium/` when served under a path
+  /// prefix behind a reverse proxy that forwards the path unchanged. All routes and cookies are placed under the path of
+  /// the URL, and redirect URLs, such as the Spotify authorization redirect URI, use it instead of the host and scheme of
+  /// requests. The server is served at `/` when not given
+  #[structopt(long, env = "MUSIUM_PUBLIC_URL")]
+  public_url: Option<Url>,
+  /// Address of a reverse proxy whose `Forwarded` and `X-Forwarded-For` headers are trusted for the address of the
+  /// client, such as for rate limiting requests without a logged-in user. Can be given multiple times. Forwarded headers
+  /// are ignored when not given, so the address of the peer is used
+  #[structopt(long = "trusted-proxy", env = "MUSIUM_TRUSTED_PROXIES", use_delimiter = true)]
+  trusted_proxies: Vec<IpAddr>,
+
+  /// Directory of a single-page web UI to serve at the path of the public URL, or `/`. Paths that do not exist in the
+  /// directory are answered with its `index.html`, so that the web UI can handle routing. Only the versioned API under
+  /// `/api/v1` is served along with the web UI, so clients that use the unversioned API must be updated. Relative paths
+  /// are resolved relative to the current directory
+  #[structopt(long, env = "MUSIUM_WEB_UI_DIR", parse(from_os_str))]
+  web_ui_dir: Option<PathBuf>,
+
+  /// Name to announce the server under on the local network through zeroconf (mDNS-SD), so that clients and Home
+  /// Assistant can discover it. The server is not announced when not given
+  #[structopt(long, env = "MUSIUM_ZEROCONF_NAME")]
+  zeroconf_name: Option<String>,
+
+  /// Starts the server in read-only maintenance mode with given reason, rejecting requests that modify the database
+  #[structopt(long, env = "MUSIUM_MAINTENANCE_REASON")]
+  maintenance_reason: Option<String>,
 
   /// Whether to print metrics to stderr before the program exits
   #[structopt(long, env = "MUSIUM_PRINT_METRICS")]
@@ -83,22 +242,100 @@ fn main() -> Result<()> {
   // Create database
   let spotify_sync = SpotifyClient::new_from_client_id_secret(opt.spotify_client_id, opt.spotify_client_secret)
     .with_context(|| "Creating Spotify synchronizer failed")?;
-  let password_hasher = PasswordHasher::new(opt.password_hasher_secret_key.as_bytes());
+  let password_hasher_secret_key = read_secret(opt.password_hasher_secret_key.clone(), opt.password_hasher_secret_key_file.as_ref())
+    .with_context(|| "Failed to read password hasher secret key")?
+    .with_context(|| "No password hasher secret key was given")?;
+  let password_hasher = PasswordHasher::new(password_hasher_secret_key.as_bytes());
   let database = Database::new(
     opt.database_file.to_string_lossy(),
     spotify_sync,
     password_hasher,
+    opt.media_cache_size_limit * 1024 * 1024,
+    opt.beets_database.clone(),
   )
     .with_context(|| "Failed to create database")?;
-  database.connect()
-    .with_context(|| "Failed to connect to database to create the admin user")?
-    .create_user(NewUser { name: opt.admin_name, password: opt.admin_password })
-    .ok();
+  if let (Some(admin_name), Some(admin_password)) = (opt.admin_name, opt.admin_password) {
+    let admin = database.connect()
+      .with_context(|| "Failed to connect to database to create the admin user")?
+      .create_first_user(NewUser { name: admin_name, password: admin_password })
+      .with_context(|| "Failed to create the admin user")?;
+    if let Some(admin) = admin {
+      info!(admin.name = %admin.name, "Created admin user");
+    }
+  } else if !database.connect()
+    .with_context(|| "Failed to connect to database to check for users")?
+    .has_users()
+    .with_context(|| "Failed to check for users")? {
+    info!("No users exist yet; create the first user with the setup endpoint");
+  }
   // Run HTTP server
   let bind_address = opt.bind_address.clone();
-  let cookie_identity_secret_key = opt.cookie_identity_secret_key.clone();
+  let inherited_listener = systemd::take_inherited_listener()
+    .with_context(|| "Failed to take listening socket passed by the service manager")?;
+  let cookie_identity_secret_key = read_secret(opt.cookie_identity_secret_key.clone(), opt.cookie_identity_secret_key_file.as_ref())
+    .with_context(|| "Failed to read cookie identity secret key")?
+    .with_context(|| "No cookie identity secret key was given")?;
+  let previous_cookie_identity_secret_key = read_secret(opt.previous_cookie_identity_secret_key.clone(), opt.previous_cookie_identity_secret_key_file.as_ref())
+    .with_context(|| "Failed to read previous cookie identity secret key")?
+    .map(|key| key.into_bytes());
+  let release_check_interval = Duration::from_secs(opt.release_check_interval);
+  let mix_generation_interval = Duration::from_secs(opt.mix_generation_interval);
+  let maintenance_reason = opt.maintenance_reason.clone();
+  let artist_enrichment = if opt.artist_enrichment || opt.theaudiodb_api_key.is_some() {
+    Some(ArtistEnrichmentSettings { theaudiodb_api_key: opt.theaudiodb_api_key.clone() })
+  } else {
+    None
+  };
+  let cors = CorsSettings { allowed_origins: opt.cors_allowed_origins.clone(), allow_credentials: opt.cors_allow_credentials };
+  let web_ui_dir = opt.web_ui_dir.clone();
+  let spotify_write_back = opt.spotify_write_back;
+  let registration = opt.registration;
+  let managed_local_source_id = opt.managed_local_source_id;
+  let managed_library = match managed_local_source_id {
+    Some(local_source_id) if opt.organize_managed_library => Some(ManagedLibrarySettings { local_source_id, template: opt.managed_library_template.clone() }),
+    _ => None,
+  };
+  let tagger_hook = opt.tagger_hook.clone().map(|program| TaggerHookSettings { program, args: opt.tagger_hook_args.clone() });
+  let kiosk = match opt.kiosk_user.clone() {
+    Some(user_name) => {
+      let token = read_secret(opt.kiosk_token.clone(), opt.kiosk_token_file.as_ref())
+        .with_context(|| "Failed to read kiosk token")?
+        .with_context(|| "No kiosk token was given for the kiosk user")?;
+      Some(KioskSettings::new(user_name, &token))
+    }
+    None => None,
+  };
+  let file_path_access = FilePathAccess::new(opt.file_path_users.clone());
+  let admins = Admins::new(opt.admin_users.iter().cloned().chain(opt.admin_name.clone()).collect());
+  let rate_limiter = RateLimiter::new(opt.trusted_proxies.clone());
+  let zeroconf_name = opt.zeroconf_name.clone();
+  let public_url = PublicUrl::new(opt.public_url.clone());
+  let spotify_redirect_uri = opt.spotify_redirect_uri.clone();
+  if let Some(spotify_redirect_uri) = &spotify_redirect_uri {
+    validate_spotify_redirect_uri(spotify_redirect_uri)?;
+  }
+  if let Some(web_ui_dir) = &web_ui_dir {
+    if !web_ui_dir.join("index.html").is_file() {
+      anyhow::bail!("Web UI directory '{}' does not contain an 'index.html' file", web_ui_dir.display());
+    }
+    if let Some(spotify_redirect_uri) = &spotify_redirect_uri {
+      let versioned_callback_path = format!("/{}{}", API_PATH_PREFIX, SPOTIFY_AUTHORIZATION_CALLBACK_PATH);
+      if !spotify_redirect_uri.path().ends_with(&versioned_callback_path) {
+        anyhow::bail!("Spotify redirect URI '{}' must end with the path of the versioned Spotify authorization callback '{}' when serving a web UI", spotify_redirect_uri, versioned_callback_path);
+      }
+    }
+  }
+  if let Some(managed_local_source_id) = managed_local_source_id {
+    let managed_local_source = database.connect()
+      .with_context(|| "Failed to connect to database to check the managed local source")?
+      .get_local_source_by_id(managed_local_source_id)
+      .with_context(|| "Failed to get the managed local source")?;
+    if managed_local_source.map_or(true, |local_source| local_source.deleted_at.is_some()) {
+      anyhow::bail!("Managed local source '{}' does not exist or was deleted", managed_local_source_id);
+    }
+  }
   actix_rt::System::new()
-    .block_on(async move { serve(database, bind_address, cookie_identity_secret_key).await })
+    .block_on(async move { serve(database, bind_address, inherited_listener, cookie_identity_secret_key, previous_cookie_identity_secret_key, release_check_interval, mix_generation_interval, maintenance_reason, artist_enrichment, cors, web_ui_dir, spotify_write_back, registration, managed_local_source_id, managed_library, tagger_hook, kiosk, file_path_access, admins, rate_limiter, zeroconf_name, public_url, spotify_redirect_uri).await })
     .with_context(|| "HTTP server failed")?;
   // Print metrics
   if opt.print_metrics {
@@ -108,3 +345,47 @@ fn main() -> Result<()> {
   }
   Ok(())
 }
+
+/// Returns the secret `value` if it is given, or otherwise the contents of the secret `file` without trailing newlines
+/// if it is given, or `None` if neither is given.
+fn read_secret(value: Option<String>, file: Option<&PathBuf>) -> Result<Option<String>> {
+  if let Some(value) = value {
+    return Ok(Some(value));
+  }
+  match file {
+    Some(file) => {
+      let contents = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read secret file '{}'", file.display()))?;
+      Ok(Some(contents.trim_end_matches(&['\n', '\r'][..]).to_string()))
+    }
+    None => Ok(None),
+  }
+}
+
+/// Validates that `uri` can be registered as a Spotify redirect URI, and that it ends with the path of the Spotify
+/// authorization callback, as the server does not handle any other path. Warns when the URI uses plain HTTP for a host
+/// other than a loopback address, which Spotify does not accept.
+fn validate_spotify_redirect_uri(uri: &Url) -> Result<()> {
+  if uri.scheme() != "http" && uri.scheme() != "https" {
+    anyhow::bail!("Spotify redirect URI '{}' must use the http or https scheme", uri);
+  }
+  let host = match uri.host() {
+    Some(host) => host,
+    None => anyhow::bail!("Spotify redirect URI '{}' has no host", uri),
+  };
+  if uri.fragment().is_some() || uri.query().is_some() {
+    anyhow::bail!("Spotify redirect URI '{}' must not have a query or fragment", uri);
+  }
+  if !uri.path().ends_with(SPOTIFY_AUTHORIZATION_CALLBACK_PATH) {
+    anyhow::bail!("Spotify redirect URI '{}' must end with the path of the Spotify authorization callback '{}'", uri, SPOTIFY_AUTHORIZATION_CALLBACK_PATH);
+  }
+  let is_loopback = match host {
+    url::Host::Ipv4(address) => address.is_loopback(),
+    url::Host::Ipv6(address) => address.is_loopback(),
+    url::Host::Domain(_) => false,
+  };
+  if uri.scheme() == "http" && !is_loopback {
+    warn!("Spotify redirect URI '{}' uses plain HTTP for a host that is not a loopback address, which Spotify does not accept", uri);
+  }
+  Ok(())
+}
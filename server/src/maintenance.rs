@@ -0,0 +1,166 @@
+use std::backtrace::Backtrace;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use actix_web::{HttpResponse, web};
+use actix_web::dev::ServiceRequest;
+use actix_web::http::Method;
+use thiserror::Error;
+use tracing::{event, Level};
+
+use musium_backend::database::{Database, DatabaseConnectError, DatabaseQueryError};
+use musium_backend::database::sync_lock::{SyncLockError, SyncLockHeartbeat};
+use musium_backend::worker::WorkerState;
+use musium_core::api::{BackgroundWorkerState, InternalServerError, MaintenanceStatus};
+use musium_core::format_error::FormatError;
+
+use crate::api_version::unversioned_path;
+use crate::public_url::PublicUrl;
+
+/// Read-only maintenance mode of the server. While enabled, requests that may modify the database are rejected with a
+/// 503 Service Unavailable response that includes the reason, while reads and playback continue. The database is
+/// quiesced as well, so that reads skip their incidental writes and background tasks are paused. This allows backups
+/// and migrations to run against a quiesced database. Enabling maintenance mode waits for running syncs to complete, and
+/// holds the sync lock while enabled, so that no sync of any server instance writes to the database during maintenance.
+#[derive(Clone)]
+pub struct MaintenanceMode {
+  reason: Arc<RwLock<Option<String>>>,
+  database: Database,
+  sync: WorkerState,
+  /// Heartbeat that renews the sync lock while maintenance mode is enabled, or `None` if the sync lock is not held.
+  sync_lock_heartbeat: Arc<Mutex<Option<SyncLockHeartbeat>>>,
+}
+
+#[derive(Debug, Error)]
+pub enum MaintenanceError {
+  #[error("Failed to connect to the database")]
+  DatabaseConnectFail(#[from] DatabaseConnectError, Backtrace),
+  #[error("Failed to acquire the sync lock")]
+  AcquireSyncLockFail(#[from] SyncLockError, Backtrace),
+  #[error("Failed to release the sync lock")]
+  ReleaseSyncLockFail(#[from] DatabaseQueryError, Backtrace),
+  #[error("Blocking thread pool is gone")]
+  BlockingThreadPoolGoneFail,
+}
+
+/// Unversioned path prefixes of non-reading endpoints that are allowed during maintenance, as they do not modify the database:
+/// logging in and out, controlling maintenance mode itself, in-memory listening sessions and parties, previewing a sync,
+/// and querying tracks.
+const ALLOWED_PATH_PREFIXES: &[&str] = &["/login", "/logout", "/maintenance", "/session", "/party", "/guest", "/sync/preview", "/track/query", "/home_assistant/control"];
+
+/// Unversioned path prefixes of reading endpoints that do modify the database, and are therefore rejected during maintenance.
+const REJECTED_READ_PATH_PREFIXES: &[&str] = &["/source/spotify/request_authorization/callback"];
+
+/// Unversioned path prefixes of non-reading endpoints within allowed path prefixes that do modify the database, and are
+/// therefore rejected during maintenance.
+const REJECTED_PATH_PREFIXES: &[&str] = &["/maintenance/issues"];
+
+const DEFAULT_REASON: &str = "Server is in maintenance mode";
+
+/// Interval at which enabling maintenance mode checks whether running syncs have completed.
+const SYNC_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+impl MaintenanceMode {
+  /// Creates the maintenance mode of the server, which is enabled with `reason` if it is given. The sync lock is only
+  /// acquired here if it is free, as no sync of this server runs yet, and waiting for syncs of other server instances
+  /// would block startup.
+  pub fn new(reason: Option<String>, database: Database, sync: WorkerState) -> Self {
+    database.set_quiesced(reason.is_some());
+    let maintenance_mode = Self { reason: Arc::new(RwLock::new(reason)), database, sync, sync_lock_heartbeat: Arc::new(Mutex::new(None)) };
+    if maintenance_mode.status().enabled {
+      match maintenance_mode.try_acquire_sync_lock() {
+        Ok(true) => {}
+        Ok(false) => event!(Level::WARN, "Maintenance mode is enabled while another server instance holds the sync lock"),
+        Err(e) => event!(Level::ERROR, "{:?}", FormatError::new(&e)),
+      }
+    }
+    maintenance_mode
+  }
+
+  pub fn status(&self) -> MaintenanceStatus {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let reason = self.reason.read().unwrap().clone();
+    MaintenanceStatus { enabled: reason.is_some(), reason }
+  }
+
+  /// Sets the maintenance mode to `status`. When enabling, requests that modify the database are rejected immediately,
+  /// but this only returns once running syncs have completed and the sync lock is acquired, so that the database is
+  /// quiesced when the returned status is reported. When disabling, the sync lock is released.
+  pub async fn set_status(&self, status: MaintenanceStatus) -> Result<MaintenanceStatus, MaintenanceError> {
+    let reason = if status.enabled { Some(status.reason.unwrap_or_else(|| DEFAULT_REASON.to_string())) } else { None };
+    let enabled = reason.is_some();
+    self.database.set_quiesced(enabled);
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    *self.reason.write().unwrap() = reason;
+    if enabled {
+      self.wait_for_syncs().await?;
+    } else {
+      let maintenance_mode = self.clone();
+      web::block(move || maintenance_mode.release_sync_lock()).await.map_err(|_| MaintenanceError::BlockingThreadPoolGoneFail)??;
+    }
+    Ok(self.status())
+  }
+
+  /// Waits until the sync lock is acquired, which waits for syncs of all server instances that hold it, and then until
+  /// the sync worker of this server is idle, as it still runs tasks after a sync that released the sync lock.
+  async fn wait_for_syncs(&self) -> Result<(), MaintenanceError> {
+    loop {
+      let maintenance_mode = self.clone();
+      let acquired = web::block(move || maintenance_mode.try_acquire_sync_lock()).await.map_err(|_| MaintenanceError::BlockingThreadPoolGoneFail)??;
+      if acquired && self.sync.get() != BackgroundWorkerState::Busy {
+        return Ok(());
+      }
+      actix_rt::time::sleep(SYNC_WAIT_INTERVAL).await;
+    }
+  }
+
+  /// Acquires the sync lock for maintenance, and keeps renewing it, unless it is already held for maintenance. Returns
+  /// `false` if a sync holds the sync lock.
+  fn try_acquire_sync_lock(&self) -> Result<bool, MaintenanceError> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let mut sync_lock_heartbeat = self.sync_lock_heartbeat.lock().unwrap();
+    if sync_lock_heartbeat.is_some() {
+      return Ok(true);
+    }
+    match self.database.connect()?.acquire_sync_lock(&sync_lock_holder()) {
+      Ok(_) => {
+        *sync_lock_heartbeat = Some(SyncLockHeartbeat::spawn(self.database.clone(), sync_lock_holder()));
+        Ok(true)
+      }
+      Err(SyncLockError::HeldByOtherInstance(..)) => Ok(false),
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  /// Stops renewing and releases the sync lock if it is held for maintenance.
+  fn release_sync_lock(&self) -> Result<(), MaintenanceError> {
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    if self.sync_lock_heartbeat.lock().unwrap().take().is_some() {
+      self.database.connect()?.release_sync_lock(&sync_lock_holder())?;
+    }
+    Ok(())
+  }
+
+  /// Returns a 503 Service Unavailable response if `request` must be rejected because maintenance mode is enabled, or
+  /// `None` if the request is allowed.
+  pub fn reject(request: &ServiceRequest) -> Option<HttpResponse> {
+    let maintenance_mode = request.app_data::<web::Data<MaintenanceMode>>()?;
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let reason = maintenance_mode.reason.read().unwrap().clone()?;
+    let path = unversioned_path(PublicUrl::unprefixed_path(request.request()));
+    let method = request.method();
+    let is_read = method == Method::GET || method == Method::HEAD || method == Method::OPTIONS;
+    if is_read && !REJECTED_READ_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+      return None;
+    }
+    if !is_read && ALLOWED_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) && !REJECTED_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+      return None;
+    }
+    Some(HttpResponse::ServiceUnavailable().json(InternalServerError { message: reason }))
+  }
+}
+
+/// Holder of the sync lock while maintenance mode of this server is enabled.
+fn sync_lock_holder() -> String {
+  format!("maintenance-{}", std::process::id())
+}
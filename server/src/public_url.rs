@@ -0,0 +1,86 @@
+use actix_web::HttpRequest;
+use actix_web::error::UrlGenerationError;
+use actix_web::web;
+use url::Url;
+
+use musium_core::api::API_PATH_PREFIX;
+
+/// Public URL at which clients reach the server, such as `https://example.com/musium/` when the server is served under
+/// a path prefix behind a reverse proxy. All routes are registered under the path of the public URL, and URLs that are
+/// handed to clients and third parties, such as the Spotify authorization redirect URI, use its scheme and host instead
+/// of those of the request.
+#[derive(Clone, Default, Debug)]
+pub struct PublicUrl {
+  url: Option<Url>,
+  /// Path of the public URL without trailing slash, such as `/musium`, or empty when served at `/`.
+  path_prefix: String,
+}
+
+impl PublicUrl {
+  pub fn new(url: Option<Url>) -> Self {
+    let path_prefix = url.as_ref().map(|url| url.path().trim_end_matches('/').to_string()).unwrap_or_default();
+    Self { url, path_prefix }
+  }
+
+  /// Gets the path prefix under which all routes are registered, such as `/musium`, or an empty string when served at
+  /// `/`.
+  #[inline]
+  pub fn path_prefix(&self) -> &str { &self.path_prefix }
+
+  /// Gets the path of cookies, so that they are only sent to the server and not to other applications on the same host.
+  pub fn cookie_path(&self) -> &str {
+    if self.path_prefix.is_empty() { "/" } else { &self.path_prefix }
+  }
+
+  /// Gets the public URL of the endpoint at `path` of the versioned API, such as `/info`, or `None` if no public URL is
+  /// given.
+  pub fn api_url(&self, path: &str) -> Option<Url> {
+    self.url.as_ref().map(|url| {
+      let mut api_url = url.clone();
+      api_url.set_path(&format!("{}/{}{}", self.path_prefix, API_PATH_PREFIX, path));
+      api_url.set_query(None);
+      api_url
+    })
+  }
+
+  /// Returns `path` without the path prefix, so that requests can be handled alike regardless of where the server is
+  /// served.
+  pub fn strip_path_prefix<'a>(&self, path: &'a str) -> &'a str {
+    match path.strip_prefix(self.path_prefix.as_str()) {
+      Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+      _ => path,
+    }
+  }
+
+  /// Returns the path of `request` without the path prefix of the public URL of the server, or the path of `request` if
+  /// the server has no public URL.
+  pub fn unprefixed_path(request: &HttpRequest) -> &str {
+    match request.app_data::<web::Data<PublicUrl>>() {
+      Some(public_url) => public_url.strip_path_prefix(request.path()),
+      None => request.path(),
+    }
+  }
+
+  /// Generates the URL of the resource with `name`, like `HttpRequest::url_for`, but with the scheme and host of the
+  /// public URL instead of those of `request` when a public URL is given.
+  pub fn url_for<U, I>(&self, request: &HttpRequest, name: &str, elements: U) -> Result<Url, UrlGenerationError> where
+    U: IntoIterator<Item=I>,
+    I: AsRef<str>,
+  {
+    let url = request.url_for(name, elements)?;
+    Ok(match &self.url {
+      Some(public_url) => {
+        let mut public_resource_url = public_url.clone();
+        public_resource_url.set_path(url.path());
+        public_resource_url.set_query(url.query());
+        public_resource_url
+      }
+      None => url,
+    })
+  }
+
+  /// Generates the URL of the resource with `name` that has no dynamic segments, like `HttpRequest::url_for_static`.
+  pub fn url_for_static(&self, request: &HttpRequest, name: &str) -> Result<Url, UrlGenerationError> {
+    self.url_for(request, name, &[""; 0])
+  }
+}
@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_identity::RequestIdentity;
+use actix_web::{HttpResponse, web};
+use actix_web::dev::ServiceRequest;
+use actix_web::http::{header, Method};
+
+use musium_core::api::InternalServerError;
+
+use crate::api_version::unversioned_path;
+use crate::auth::LoggedInUser;
+use crate::public_url::PublicUrl;
+
+/// Per-user rate limiter for expensive endpoints. Requests exceeding the limit of an endpoint within its window are
+/// rejected with a 429 Too Many Requests response. Requests without a logged-in user are limited per client address.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+  windows: Arc<Mutex<HashMap<(String, &'static str), Window>>>,
+  /// Addresses of reverse proxies whose forwarded headers are trusted for the client address of requests.
+  trusted_proxies: Arc<HashSet<IpAddr>>,
+}
+
+struct Window {
+  expires_at: Instant,
+  count: u32,
+}
+
+struct Limit {
+  method: Method,
+  /// Unversioned path prefix of the limited endpoints.
+  path_prefix: &'static str,
+  max_requests: u32,
+  window: Duration,
+}
+
+const fn limit(method: Method, path_prefix: &'static str, max_requests: u32, window_secs: u64) -> Limit {
+  Limit { method, path_prefix, max_requests, window: Duration::from_secs(window_secs) }
+}
+
+const LIMITS: &[Limit] = &[
+  limit(Method::POST, "/sync", 10, 60),
+  limit(Method::POST, "/setup", 10, 60),
+  limit(Method::POST, "/register", 10, 60),
+  limit(Method::POST, "/audio_features/analyze", 10, 60),
+  limit(Method::POST, "/maintenance/issues/validate", 10, 60),
+  limit(Method::GET, "/search", 120, 60),
+  limit(Method::GET, "/guest/search", 120, 60),
+];
+
+impl RateLimiter {
+  /// Creates a rate limiter that trusts the forwarded headers of requests from `trusted_proxies` only.
+  pub fn new(trusted_proxies: impl IntoIterator<Item=IpAddr>) -> Self {
+    Self { windows: Default::default(), trusted_proxies: Arc::new(trusted_proxies.into_iter().collect()) }
+  }
+
+  /// Returns a 429 Too Many Requests response if `request` exceeds the rate limit of its endpoint, or `None` if the
+  /// request is allowed.
+  pub fn reject(request: &ServiceRequest) -> Option<HttpResponse> {
+    let rate_limiter = request.app_data::<web::Data<RateLimiter>>()?;
+    let path = unversioned_path(PublicUrl::unprefixed_path(request.request()));
+    let limit = LIMITS.iter().find(|limit| request.method() == limit.method && path.starts_with(limit.path_prefix))?;
+    let key = match request.get_identity().and_then(|identity| serde_json::from_str::<LoggedInUser>(&identity).ok()) {
+      Some(logged_in_user) => format!("user:{}", logged_in_user.user.id),
+      None => format!("address:{}", rate_limiter.client_address(request)),
+    };
+    let now = Instant::now();
+    // UNWRAP: errors if holder of the lock has panicked -> we panic as well.
+    let mut windows = rate_limiter.windows.lock().unwrap();
+    windows.retain(|_, window| window.expires_at > now);
+    let window = windows.entry((key, limit.path_prefix)).or_insert(Window { expires_at: now + limit.window, count: 0 });
+    if window.count >= limit.max_requests {
+      let retry_after = window.expires_at.duration_since(now).as_secs().max(1);
+      return Some(HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after))
+        .json(InternalServerError { message: format!("Too many requests to '{}', retry after {} seconds", limit.path_prefix, retry_after) }));
+    }
+    window.count += 1;
+    None
+  }
+
+  /// Gets the address of the client of `request`, which is the address that the request was forwarded for if the peer
+  /// is a trusted proxy, and the address of the peer otherwise. Forwarded headers from other peers are ignored, as any
+  /// client can set them to evade rate limits.
+  fn client_address(&self, request: &ServiceRequest) -> String {
+    match request.peer_addr().map(|address| address.ip()) {
+      Some(peer_ip) if self.trusted_proxies.contains(&peer_ip) => request.connection_info().realip_remote_addr()
+        .map(|address| address.to_string())
+        .unwrap_or_else(|| peer_ip.to_string()),
+      Some(peer_ip) => peer_ip.to_string(),
+      None => String::new(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+
+  use actix_web::{App, test};
+  use actix_web::dev::Service;
+  use actix_web::http::StatusCode;
+
+  use super::*;
+
+  const PEER_ADDRESS: &str = "192.0.2.1:4000";
+
+  /// Sends one more request to the rate limited registration endpoint than is allowed, each forwarded for another client
+  /// address by the peer, and returns the status of the last request.
+  async fn last_forwarded_registration_status(rate_limiter: RateLimiter) -> StatusCode {
+    let app = test::init_service(App::new()
+      .app_data(web::Data::new(rate_limiter))
+      .wrap_fn(|request, service| {
+        let response = match RateLimiter::reject(&request) {
+          Some(response) => Err(request.into_response(response)),
+          None => Ok(service.call(request)),
+        };
+        async move {
+          match response {
+            Ok(future) => future.await,
+            Err(response) => Ok(response),
+          }
+        }
+      })
+      .route("/register", web::post().to(|| async { HttpResponse::Ok().finish() }))
+    ).await;
+    let max_requests = LIMITS.iter().find(|limit| limit.path_prefix == "/register").unwrap().max_requests;
+    let mut status = StatusCode::OK;
+    for i in 0..=max_requests {
+      let request = test::TestRequest::post()
+        .uri("/register")
+        .peer_addr(PEER_ADDRESS.parse().unwrap())
+        .insert_header(("x-forwarded-for", format!("198.51.100.{}", i)))
+        .to_request();
+      status = test::call_service(&app, request).await.status();
+    }
+    status
+  }
+
+  #[actix_rt::test]
+  async fn forwarded_header_of_untrusted_peer_is_ignored() {
+    assert_eq!(last_forwarded_registration_status(RateLimiter::default()).await, StatusCode::TOO_MANY_REQUESTS);
+  }
+
+  #[actix_rt::test]
+  async fn forwarded_header_of_trusted_proxy_is_honoured() {
+    let proxy_address: SocketAddr = PEER_ADDRESS.parse().unwrap();
+    assert_eq!(last_forwarded_registration_status(RateLimiter::new([proxy_address.ip()])).await, StatusCode::OK);
+  }
+}
@@ -1,81 +1,467 @@
 use std::net;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use actix_identity::{CookieIdentityPolicy, IdentityService};
-use actix_web::{App, HttpResponse, HttpServer, middleware, web};
+use actix_cors::Cors;
+use actix_identity::IdentityService;
+use actix_web::{App, http, HttpResponse, HttpServer, middleware, web};
+use actix_web::middleware::Condition;
+use actix_web::dev::Service;
+use url::Url;
 
+use musium_backend::artist_info::ArtistEnrichmentSettings;
 use musium_backend::database::Database;
+use musium_backend::event::EventBus;
+use musium_backend::listening_session::ListeningSessions;
+use musium_backend::managed_library::ManagedLibrarySettings;
+use musium_backend::tagger_hook::TaggerHookSettings;
+use musium_backend::mix::MixGenerationTask;
+use musium_backend::party::Parties;
+use musium_backend::release_check::ReleaseCheckTask;
 use musium_backend::sync::SyncClient;
+use musium_backend::webhook::WebhookDispatchTask;
+use musium_backend::worker::WorkerState;
+use musium_core::api::{ALBUM_ID_HEADER, API_PATH_PREFIX, API_VERSION, API_VERSION_HEADER, Capabilities, ReplayGain};
 
 use crate::api::*;
+use crate::api_version;
 use crate::auth::*;
+use crate::health::{self, BackgroundWorkers};
+use crate::maintenance::MaintenanceMode;
+use crate::public_url::PublicUrl;
+use crate::rate_limit::RateLimiter;
+use crate::systemd;
+use crate::url_signing::UrlSigner;
+use crate::validation;
+use crate::web_ui::configure_web_ui;
+use crate::zeroconf::ZeroconfAnnouncement;
 
-pub async fn serve<A: net::ToSocketAddrs, C: Into<Vec<u8>>>(database: Database, bind_address: A, cookie_identity_secret_key: C) -> std::io::Result<()> {
+/// Path of the Spotify authorization callback, relative to the versioned API.
+pub const SPOTIFY_AUTHORIZATION_CALLBACK_PATH: &str = "/source/spotify/request_authorization/callback";
+
+/// Cross-origin resource sharing settings, allowing browser-based clients served from `allowed_origins` to call the
+/// API. Cross-origin requests are blocked when `allowed_origins` is empty. An origin of `*` allows any origin.
+#[derive(Clone, Default, Debug)]
+pub struct CorsSettings {
+  pub allowed_origins: Vec<String>,
+  /// Whether to allow cross-origin requests to include credentials, such as the identity cookie.
+  pub allow_credentials: bool,
+}
+
+impl CorsSettings {
+  fn create_cors(&self) -> Cors {
+    let mut cors = Cors::default()
+      .allow_any_method()
+      .allow_any_header()
+      .expose_headers([
+        API_VERSION_HEADER,
+        ALBUM_ID_HEADER,
+        ReplayGain::TRACK_GAIN_HEADER,
+        ReplayGain::TRACK_PEAK_HEADER,
+        ReplayGain::ALBUM_GAIN_HEADER,
+        ReplayGain::ALBUM_PEAK_HEADER,
+        "retry-after",
+      ])
+      .max_age(3600);
+    for origin in &self.allowed_origins {
+      cors = if origin == "*" { cors.allow_any_origin() } else { cors.allowed_origin(origin) };
+    }
+    if self.allow_credentials {
+      cors = cors.supports_credentials();
+    }
+    cors
+  }
+}
+
+/// Serves the HTTP API, on `inherited_listener` if given, or by binding to `bind_address` otherwise.
+pub async fn serve<A: net::ToSocketAddrs, C: Into<Vec<u8>>>(
+  database: Database,
+  bind_address: A,
+  inherited_listener: Option<net::TcpListener>,
+  cookie_identity_secret_key: C,
+  previous_cookie_identity_secret_key: Option<Vec<u8>>,
+  release_check_interval: Duration,
+  mix_generation_interval: Duration,
+  maintenance_reason: Option<String>,
+  artist_enrichment: Option<ArtistEnrichmentSettings>,
+  cors: CorsSettings,
+  web_ui_dir: Option<PathBuf>,
+  spotify_write_back: bool,
+  registration: bool,
+  managed_local_source_id: Option<i32>,
+  managed_library: Option<ManagedLibrarySettings>,
+  tagger_hook: Option<TaggerHookSettings>,
+  kiosk: Option<KioskSettings>,
+  file_path_access: FilePathAccess,
+  admins: Admins,
+  rate_limiter: RateLimiter,
+  zeroconf_name: Option<String>,
+  public_url: PublicUrl,
+  spotify_redirect_uri: Option<Url>,
+) -> std::io::Result<()> {
   let database_data = web::Data::new(database);
-  let sync_client_data = web::Data::new(SyncClient::new());
+  let spotify_redirect_uri = spotify_redirect_uri.or_else(|| public_url.api_url(SPOTIFY_AUTHORIZATION_CALLBACK_PATH));
+  let capabilities_data = web::Data::new(Capabilities {
+    // Spotify client credentials are required options of the server, so Spotify sources are always supported.
+    spotify: true,
+    spotify_write_back,
+    transcoding: false,
+    lyrics: true,
+    scrobbling: false,
+    websocket_events: true,
+    artist_enrichment: artist_enrichment.is_some(),
+    registration,
+    managed_local_source_id,
+    managed_library_template: managed_library.as_ref().map(|managed_library| managed_library.template.to_string()),
+    kiosk: kiosk.is_some(),
+    spotify_redirect_uri: spotify_redirect_uri.map(|uri| uri.to_string()),
+  });
+  let kiosk_data = web::Data::new(kiosk);
+  let file_path_access_data = web::Data::new(file_path_access);
+  let admins_data = web::Data::new(admins);
+  let path_prefix = public_url.path_prefix().to_string();
+  let public_url_data = web::Data::new(public_url);
+  let event_bus = EventBus::default();
+  let sync_client_data = web::Data::new(SyncClient::new(event_bus.clone(), artist_enrichment, managed_library.clone(), tagger_hook));
+  let managed_library_data = web::Data::new(managed_library);
+  let release_check_task = ReleaseCheckTask::spawn(database_data.clone().into_inner(), event_bus.clone(), release_check_interval);
+  let mix_generation_task = MixGenerationTask::spawn(database_data.clone().into_inner(), mix_generation_interval);
+  let webhook_dispatch_task = WebhookDispatchTask::spawn(database_data.clone().into_inner(), &event_bus);
+  let background_workers_data = web::Data::new(BackgroundWorkers {
+    sync: sync_client_data.worker_state(),
+    webhook_dispatch: webhook_dispatch_task.state(),
+    release_check: release_check_task.state(),
+    mix_generation: mix_generation_task.state(),
+    audio_feature_analysis: WorkerState::default(),
+    track_validation: WorkerState::default(),
+  });
+  let listening_sessions_data = web::Data::new(ListeningSessions::new(event_bus.clone()));
+  let parties_data = web::Data::new(Parties::new(event_bus.clone()));
+  let event_bus_data = web::Data::new(event_bus);
+  let maintenance_mode_data = web::Data::new(MaintenanceMode::new(maintenance_reason, database_data.get_ref().clone(), background_workers_data.sync.clone()));
+  let rate_limiter_data = web::Data::new(rate_limiter);
   let cookie_identity_secret_key = cookie_identity_secret_key.into();
-  HttpServer::new(move || {
+  let url_signer_data = web::Data::new(UrlSigner::new(&cookie_identity_secret_key));
+  let http_server = HttpServer::new(move || {
     App::new()
+      .wrap_fn(|request, service| {
+        let response = match validation::reject(&request).or_else(|| RateLimiter::reject(&request)) {
+          Some(response) => Err(request.into_response(response)),
+          None => Ok(service.call(request)),
+        };
+        async move {
+          match response {
+            Ok(future) => future.await,
+            Err(response) => Ok(response),
+          }
+        }
+      })
+      .wrap_fn(|request, service| {
+        let response = match MaintenanceMode::reject(&request) {
+          Some(response) => Err(request.into_response(response)),
+          None => Ok(service.call(request)),
+        };
+        async move {
+          match response {
+            Ok(future) => future.await,
+            Err(response) => Ok(response),
+          }
+        }
+      })
+      .wrap_fn(|request, service| {
+        let response = match api_version::reject(&request) {
+          Some(response) => Err(request.into_response(response)),
+          None => Ok(service.call(request)),
+        };
+        async move {
+          let mut response = match response {
+            Ok(future) => future.await?,
+            Err(response) => response,
+          };
+          response.headers_mut().insert(http::header::HeaderName::from_static(API_VERSION_HEADER), API_VERSION.into());
+          Ok(response)
+        }
+      })
       .wrap(middleware::Logger::default())
-      .wrap(IdentityService::new(
-        CookieIdentityPolicy::new(&cookie_identity_secret_key)
-          .name("auth")
-          .secure(false)
-      ))
+      .wrap(IdentityService::new(RotatingCookieIdentityPolicy::new(
+        &cookie_identity_secret_key,
+        previous_cookie_identity_secret_key.as_deref(),
+        public_url_data.cookie_path(),
+      )))
+      // CORS middleware must be outermost, so that preflight requests are answered before any other middleware.
+      .wrap(Condition::new(!cors.allowed_origins.is_empty(), cors.create_cors()))
       .app_data(database_data.clone())
       .app_data(sync_client_data.clone())
-      .route("/", web::get().to(index))
-      // Auth
-      .route("/login", web::post().to(login))
-      .route("/logout", web::delete().to(logout))
-      // API
-      // Local source
-      .route("/source/local", web::get().to(list_local_sources))
-      .route("/source/local/{id}", web::get().to(show_local_source_by_id))
-      .route("/source/local", web::post().to(create_or_enable_local_source))
-      .route("/source/local/set_enabled/{id}", web::post().to(set_local_source_enabled))
-      // Spotify source
-      .route("/source/spotify", web::get().to(list_spotify_sources))
-      .route("/source/spotify/{id}", web::get().to(show_spotify_source_by_id))
-      .route("/source/spotify/request_authorization", web::get().to(request_spotify_authorization))
-      .service(web::resource("/source/spotify/request_authorization/callback")
-        .name("spotify_authorization_callback")
-        .route(web::get().to(spotify_authorization_callback))
+      .app_data(event_bus_data.clone())
+      .app_data(listening_sessions_data.clone())
+      .app_data(parties_data.clone())
+      .app_data(maintenance_mode_data.clone())
+      .app_data(background_workers_data.clone())
+      .app_data(capabilities_data.clone())
+      .app_data(kiosk_data.clone())
+      .app_data(managed_library_data.clone())
+      .app_data(file_path_access_data.clone())
+      .app_data(admins_data.clone())
+      .app_data(public_url_data.clone())
+      .app_data(rate_limiter_data.clone())
+      .app_data(url_signer_data.clone())
+      .app_data(web::JsonConfig::default().limit(validation::JSON_BODY_LIMIT).error_handler(validation::json_error_handler))
+      .app_data(web::QueryConfig::default().error_handler(validation::query_error_handler))
+      .app_data(web::PathConfig::default().error_handler(validation::path_error_handler))
+      // Health probes, outside of the API and the path prefix of the public URL, as probes do not go through the reverse proxy.
+      .route("/healthz", web::get().to(health::liveness))
+      .route("/readyz", web::get().to(health::readiness))
+      // All services are registered under the path prefix of the public URL, which is empty when served at `/`.
+      .service(web::scope(public_url_data.path_prefix())
+        // Versioned API
+        .service(web::scope(&format!("/{}", API_PATH_PREFIX))
+          .configure(configure_api)
+          .service(web::resource(SPOTIFY_AUTHORIZATION_CALLBACK_PATH)
+            .name("spotify_authorization_callback")
+            .route(web::get().to(spotify_authorization_callback))
+          )
+          .service(web::resource("/track/{id}/signed_play")
+            .name("signed_play_track")
+            .route(web::get().to(play_signed_track))
+          )
+        )
+        // Web UI, which must be last as it matches every path. When the web UI is served, only the versioned API is served,
+        // as the unversioned compatibility aliases of the versioned API, for clients that predate API versioning, would
+        // shadow deep links of the web UI, such as `/album/5`.
+        .configure(|config| match &web_ui_dir {
+          Some(web_ui_dir) => configure_web_ui(config, web_ui_dir),
+          None => {
+            config
+              .configure(configure_api)
+              .route(SPOTIFY_AUTHORIZATION_CALLBACK_PATH, web::get().to(spotify_authorization_callback))
+              .route("/", web::get().to(index));
+          }
+        })
       )
-      .route("/source/spotify/set_enabled/{id}", web::post().to(set_spotify_source_enabled))
-      .route("/source/spotify/me", web::get().to(show_spotify_me))
-      // Album
-      .route("/album", web::get().to(list_albums))
-      .route("/album/{id}", web::get().to(show_album_by_id))
-      // Track
-      .route("/track", web::get().to(list_tracks))
-      .route("/track/{id}", web::get().to(show_track_by_id))
-      .route("/track/play_source_kind/{id}", web::get().to(play_track_by_id))
-      .route("/track/play/{id}", web::get().to(play_track_by_id))
-      // Artist
-      .route("/artist", web::get().to(list_artists))
-      .route("/artist/{id}", web::get().to(show_artist_by_id))
-      // User
-      .route("/user", web::get().to(list_users))
-      .route("/user/me", web::get().to(show_my_user))
-      .route("/user/{id}", web::get().to(show_user_by_id))
-      .route("/user", web::post().to(create_user))
-      .route("/user", web::delete().to(delete_user_by_name))
-      .route("/user/{id}", web::delete().to(delete_user_by_id))
-      // User data
-      .route("/user/data/album/{id}/rating/{rating}", web::put().to(set_user_album_rating))
-      .route("/user/data/track/{id}/rating/{rating}", web::put().to(set_user_track_rating))
-      .route("/user/data/artist/{id}/rating/{rating}", web::put().to(set_user_artist_rating))
-      // Scan
-      .route("/sync", web::get().to(get_sync_status))
-      .route("/sync", web::post().to(sync_all_sources))
-      .route("/sync/local", web::post().to(sync_local_sources))
-      .route("/sync/local/{id}", web::post().to(sync_local_source))
-      .route("/sync/spotify", web::post().to(sync_spotify_sources))
-      .route("/sync/spotify/{id}", web::post().to(sync_spotify_source))
-  })
-    .bind(bind_address)?
-    .run()
-    .await
+  });
+  let http_server = match inherited_listener {
+    Some(listener) => http_server.listen(listener)?,
+    None => http_server.bind(bind_address)?,
+  };
+  let _zeroconf_announcement = zeroconf_name.and_then(|name| ZeroconfAnnouncement::announce(&name, &http_server.addrs(), &path_prefix));
+  let server = http_server.run();
+  systemd::notify_ready();
+  let result = server.await;
+  systemd::notify_stopping();
+  result
+}
+
+/// Configures the endpoints of the HTTP API, except for the Spotify authorization callback and signed track playback,
+/// which are named resources that may only be registered once.
+fn configure_api(config: &mut web::ServiceConfig) {
+  config
+    // Server info
+    .route("/info", web::get().to(show_server_info))
+    .route("/capabilities", web::get().to(show_capabilities))
+    // Auth
+    .route("/login", web::post().to(login))
+    .route("/logout", web::delete().to(logout))
+    .route("/setup", web::post().to(setup))
+    .route("/register", web::post().to(register))
+    .route("/kiosk_login", web::post().to(kiosk_login))
+    // Local source
+    .route("/source/local", web::get().to(list_local_sources))
+    .route("/source/local/deleted", web::get().to(list_deleted_local_sources))
+    .route("/source/local/{id}", web::get().to(show_local_source_by_id))
+    .route("/source/local", web::post().to(create_or_enable_local_source))
+    .route("/source/local/set_enabled/{id}", web::post().to(set_local_source_enabled))
+    .route("/source/local/set_skip_if_unavailable/{id}", web::post().to(set_local_source_skip_if_unavailable))
+    .route("/source/local/{id}", web::delete().to(delete_local_source))
+    .route("/source/local/restore/{id}", web::post().to(restore_local_source))
+    .route("/source/local/{id}/relocate", web::post().to(relocate_local_source))
+    // Spotify source
+    .route("/source/spotify", web::get().to(list_spotify_sources))
+    .route("/source/spotify/deleted", web::get().to(list_deleted_spotify_sources))
+    .route("/source/spotify/{id}", web::get().to(show_spotify_source_by_id))
+    .route("/source/spotify/request_authorization", web::get().to(request_spotify_authorization))
+    .route("/source/spotify/set_enabled/{id}", web::post().to(set_spotify_source_enabled))
+    .route("/source/spotify/{id}", web::delete().to(delete_spotify_source))
+    .route("/source/spotify/restore/{id}", web::post().to(restore_spotify_source))
+    .route("/source/spotify/me", web::get().to(show_spotify_me))
+    // Spotify write back
+    .route("/album/{id}/spotify/saved", web::put().to(save_spotify_album))
+    .route("/album/{id}/spotify/saved", web::delete().to(remove_saved_spotify_album))
+    .route("/artist/{id}/spotify/followed", web::put().to(follow_spotify_artist))
+    .route("/spotify/playlist/{id}/tracks", web::post().to(add_tracks_to_spotify_playlist))
+    // Album
+    .route("/album", web::get().to(list_albums))
+    .route("/album/alphabet_index", web::get().to(show_album_alphabet_index))
+    .route("/album/{id}", web::get().to(show_album_by_id))
+    // Track
+    .route("/track", web::get().to(list_tracks))
+    .route("/track/page", web::get().to(list_tracks_page))
+    .route("/track/query", web::post().to(query_tracks_page))
+    .route("/track/random", web::get().to(sample_random_tracks))
+    .route("/track/play", web::get().to(list_tracks_to_play))
+    .route("/track/recommend", web::post().to(recommend_tracks))
+    .route("/track/upload", web::post().to(upload_track))
+    .route("/track/{id}", web::get().to(show_track_by_id))
+    .route("/track/{id}/technical", web::get().to(show_track_technical_info))
+    .route("/track/{id}/waveform", web::get().to(show_track_waveform))
+    .route("/track/{id}/lyrics", web::get().to(show_track_lyrics))
+    .route("/track/{id}/skip", web::post().to(report_track_skip))
+    .route("/track/{id}/audio_features", web::get().to(show_track_audio_features))
+    .route("/track/{id}/tag", web::get().to(list_track_tags))
+    .route("/track/{id}/tag/{name}", web::put().to(add_track_tag))
+    .route("/track/{id}/tag/{name}", web::delete().to(remove_track_tag))
+    .route("/track/{id}/versions", web::get().to(list_track_versions))
+    // Track relation
+    .route("/track_relation", web::get().to(list_track_relations))
+    .route("/track_relation", web::post().to(add_track_relation))
+    .route("/track_relation/{original_id}/{version_id}", web::delete().to(remove_track_relation))
+    // Classical
+    .route("/composer", web::get().to(list_composers))
+    .route("/composer/{id}/works", web::get().to(list_composer_works))
+    .route("/work/{id}/tracks", web::get().to(list_work_tracks))
+    // Relinking
+    .route("/track/{id}/album/{album_id}", web::put().to(move_track_to_album))
+    .route("/track/{id}/artists", web::put().to(set_track_artists))
+    .route("/album/{id}/artists", web::put().to(set_album_artists))
+    .route("/album/{id}/split", web::post().to(split_album))
+    // Search
+    .route("/search/track", web::get().to(search_tracks))
+    // Tag
+    .route("/tag", web::get().to(list_tags))
+    .route("/tag/{name}/tracks", web::put().to(add_tracks_tag))
+    // Audio features
+    .route("/audio_features", web::get().to(filter_tracks_by_audio_features))
+    .route("/audio_features/analyze", web::post().to(analyze_audio_features))
+    .route("/track/play_source_kind/{id}", web::get().to(play_track_by_id))
+    .route("/track/play/{id}", web::get().to(play_track_by_id))
+    .route("/track/{id}/signed_url", web::get().to(show_signed_track_url))
+    // Playback
+    .route("/playback/preferences", web::get().to(get_playback_preference))
+    .route("/playback/preferences", web::put().to(set_playback_preference))
+    // Artist
+    .route("/artist", web::get().to(list_artists))
+    .route("/artist/alphabet_index", web::get().to(show_artist_alphabet_index))
+    .route("/artist/{id}", web::get().to(show_artist_by_id))
+    .route("/artist/{id}/image", web::get().to(show_artist_image))
+    // User
+    .route("/user", web::get().to(list_users))
+    .route("/user/me", web::get().to(show_my_user))
+    .route("/user/me/preferences", web::get().to(get_user_preferences))
+    .route("/user/me/preferences", web::put().to(set_user_preferences))
+    .route("/user/me/content_filter", web::get().to(get_my_content_filter))
+    .route("/user/me/content_filter", web::put().to(set_my_content_filter))
+    .route("/user/me/stats", web::get().to(get_user_stats))
+    .route("/user/me/year_review/{year}", web::get().to(get_year_review))
+    .route("/user/me/year_review/{year}/html", web::get().to(get_year_review_html))
+    .route("/user/{id}", web::get().to(show_user_by_id))
+    .route("/user", web::post().to(create_user))
+    .route("/user", web::delete().to(delete_user_by_name))
+    .route("/user/{id}", web::delete().to(delete_user_by_id))
+    // User invites
+    .route("/admin/invites", web::get().to(list_user_invites))
+    .route("/admin/invites", web::post().to(create_user_invite))
+    // User content filters
+    .route("/admin/user/{id}/content_filter", web::get().to(get_user_content_filter))
+    .route("/admin/user/{id}/content_filter", web::put().to(set_user_content_filter))
+    // User data
+    .route("/user/data/album/{id}/rating/{rating}", web::put().to(set_user_album_rating))
+    .route("/user/data/track/{id}/rating/{rating}", web::put().to(set_user_track_rating))
+    .route("/user/data/track/rating", web::put().to(set_user_track_ratings))
+    .route("/user/data/artist/{id}/rating/{rating}", web::put().to(set_user_artist_rating))
+    .route("/user/data/hidden", web::get().to(list_hidden_items))
+    .route("/user/data/hidden/{kind}/{id}", web::put().to(hide_item))
+    .route("/user/data/hidden/{kind}/{id}", web::delete().to(unhide_item))
+    // Playlist
+    .route("/playlist", web::get().to(list_playlists))
+    .route("/playlist", web::post().to(create_playlist))
+    .route("/playlist/mix", web::get().to(list_playlist_mixes))
+    .route("/playlist/{id}", web::get().to(show_playlist_by_id))
+    .route("/playlist/{id}", web::put().to(update_playlist))
+    .route("/playlist/{id}", web::delete().to(delete_playlist))
+    .route("/playlist/{id}/track/{track_id}", web::post().to(add_playlist_track))
+    .route("/playlist/{id}/tracks", web::post().to(add_playlist_tracks))
+    .route("/playlist/{id}/playlist_track/{playlist_track_id}", web::delete().to(remove_playlist_track))
+    .route("/playlist/{id}/collaborator/{user_id}", web::put().to(add_playlist_collaborator))
+    .route("/playlist/{id}/collaborator/{user_id}", web::delete().to(remove_playlist_collaborator))
+    // Listening session
+    .route("/session", web::get().to(list_listening_sessions))
+    .route("/session", web::post().to(create_listening_session))
+    .route("/session/{id}", web::get().to(show_listening_session_by_id))
+    .route("/session/{id}/join", web::post().to(join_listening_session))
+    .route("/session/{id}/leave", web::post().to(leave_listening_session))
+    .route("/session/{id}/command", web::post().to(command_listening_session))
+    // Party
+    .route("/party", web::post().to(create_party))
+    .route("/party/{id}", web::get().to(show_party_by_id))
+    .route("/party/{id}", web::delete().to(end_party))
+    .route("/party/{id}/guest", web::post().to(invite_party_guest))
+    .route("/party/{id}/dequeue", web::post().to(dequeue_party_track))
+    // Party guest
+    .route("/guest/party", web::get().to(show_guest_party))
+    .route("/guest/search", web::get().to(guest_search_tracks))
+    .route("/guest/queue/{track_id}", web::post().to(guest_enqueue_track))
+    // Home Assistant
+    .route("/home_assistant/state", web::get().to(show_home_assistant_state))
+    .route("/home_assistant/control", web::post().to(control_home_assistant))
+    .route("/home_assistant/library", web::get().to(show_library_summary))
+    // Reports
+    .route("/reports/missing_albums", web::get().to(list_missing_albums))
+    // Notifications
+    .route("/notifications", web::get().to(list_new_releases))
+    .route("/notifications/inbox", web::get().to(list_notifications))
+    .route("/notifications/inbox/read", web::put().to(mark_all_notifications_read))
+    .route("/notifications/inbox/{id}/read", web::put().to(mark_notification_read))
+    .route("/notifications/preferences", web::get().to(get_notification_preference))
+    .route("/notifications/preferences", web::put().to(set_notification_preference))
+    // Maintenance
+    .route("/maintenance", web::get().to(get_maintenance_status))
+    .route("/maintenance", web::put().to(set_maintenance_status))
+    .route("/maintenance/issues", web::get().to(list_track_issues))
+    .route("/maintenance/issues/validate", web::post().to(validate_local_tracks))
+    .route("/media_cache", web::get().to(get_media_cache_status))
+    // Managed library
+    .route("/managed_library/organize", web::post().to(organize_managed_library))
+    // Undo
+    .route("/undo", web::get().to(list_undo_log))
+    .route("/undo/{id}", web::post().to(undo))
+    // Metadata merging
+    .route("/metadata/merge_policy", web::get().to(list_metadata_merge_policies))
+    .route("/metadata/merge_policy", web::put().to(set_metadata_merge_policy))
+    .route("/metadata/merge_policy/{field}", web::delete().to(remove_metadata_merge_policy))
+    .route("/metadata/conflict", web::get().to(list_metadata_conflicts))
+    .route("/metadata/conflict/{id}/resolve", web::post().to(resolve_metadata_conflict))
+    .route("/metadata/conflict/{id}", web::delete().to(dismiss_metadata_conflict))
+    // Sort names
+    .route("/metadata/sort_article", web::get().to(list_sort_articles))
+    .route("/metadata/sort_article", web::put().to(set_sort_articles))
+    .route("/album/{id}/sort_name", web::put().to(set_album_sort_name))
+    .route("/artist/{id}/sort_name", web::put().to(set_artist_sort_name))
+    // Album cover
+    .service(web::resource("/album/{id}/cover")
+      .app_data(web::PayloadConfig::new(ALBUM_COVER_UPLOAD_LIMIT))
+      .route(web::get().to(show_album_cover))
+      .route(web::put().to(set_album_cover))
+    )
+    .route("/album/{id}/cover/fetch", web::post().to(fetch_album_cover))
+    // Events
+    .route("/events", web::get().to(events))
+    .route("/events/poll", web::get().to(poll_events))
+    .route("/events/sse", web::get().to(sse_events))
+    // Scan
+    .route("/sync", web::get().to(get_sync_status))
+    .route("/sync", web::post().to(sync_all_sources))
+    .route("/sync/local", web::post().to(sync_local_sources))
+    .route("/sync/local/{id}", web::post().to(sync_local_source))
+    .route("/sync/spotify", web::post().to(sync_spotify_sources))
+    .route("/sync/spotify/{id}", web::post().to(sync_spotify_source))
+    .route("/sync/preview", web::post().to(preview_sync))
+    .route("/sync/history", web::get().to(list_sync_history))
+    .route("/sync/lock", web::get().to(show_sync_lock))
+    .route("/sync/lock", web::delete().to(force_release_sync_lock))
+    // Webhooks
+    .route("/webhook", web::get().to(list_webhooks))
+    .route("/webhook", web::post().to(create_webhook))
+    .route("/webhook/set_enabled/{id}", web::post().to(set_webhook_enabled))
+    .route("/webhook/{id}", web::delete().to(delete_webhook));
 }
 
 async fn index() -> HttpResponse {
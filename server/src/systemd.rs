@@ -0,0 +1,57 @@
+use std::net::TcpListener;
+
+use tracing::{event, Level};
+
+/// Takes the first listening socket passed by the service manager through socket activation (`LISTEN_FDS`), if any.
+/// Serving from an inherited socket allows the service manager to hold on to connections while the server restarts.
+pub fn take_inherited_listener() -> std::io::Result<Option<TcpListener>> {
+  let mut listen_fd = listenfd::ListenFd::from_env();
+  let listener = listen_fd.take_tcp_listener(0)?;
+  if let Some(listener) = &listener {
+    // Inherited sockets may be in non-blocking mode, which the HTTP server does not expect of std listeners.
+    listener.set_nonblocking(false)?;
+    event!(Level::INFO, local_address = ?listener.local_addr().ok(), "Using inherited listening socket");
+  }
+  Ok(listener)
+}
+
+/// Notifies the service manager that the server is ready to accept connections, and starts sending watchdog
+/// keep-alives if the service manager requested them (`WATCHDOG_USEC`). Does nothing when not started by a service
+/// manager that supports notifications (`NOTIFY_SOCKET`). Must be called from within an actix system.
+#[cfg(unix)]
+pub fn notify_ready() {
+  use std::time::Duration;
+
+  use sd_notify::NotifyState;
+  if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+    event!(Level::WARN, "Failed to notify service manager of readiness: {}", e);
+    return;
+  }
+  let mut watchdog_usec = 0;
+  if sd_notify::watchdog_enabled(false, &mut watchdog_usec) {
+    // Send keep-alives at half the watchdog interval, as recommended by systemd.
+    let keep_alive_interval = Duration::from_micros(watchdog_usec) / 2;
+    event!(Level::INFO, ?keep_alive_interval, "Sending watchdog keep-alives to service manager");
+    actix_rt::spawn(async move {
+      let mut interval = actix_rt::time::interval(keep_alive_interval);
+      loop {
+        interval.tick().await;
+        if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+          event!(Level::WARN, "Failed to send watchdog keep-alive to service manager: {}", e);
+        }
+      }
+    });
+  }
+}
+
+#[cfg(not(unix))]
+pub fn notify_ready() {}
+
+/// Notifies the service manager that the server is shutting down.
+#[cfg(unix)]
+pub fn notify_stopping() {
+  sd_notify::notify(false, &[sd_notify::NotifyState::Stopping]).ok(); // OK: shutting down anyway -> we don't care.
+}
+
+#[cfg(not(unix))]
+pub fn notify_stopping() {}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use actix_web::{HttpRequest, HttpResponse, ResponseError, web};
+use actix_web::dev::ServiceRequest;
+use actix_web::error::{Error, JsonPayloadError, PathError, QueryPayloadError};
+
+use musium_core::api::InternalServerError;
+use musium_core::model::{MAX_RATING, MIN_RATING};
+
+use crate::api_version::unversioned_path;
+use crate::public_url::PublicUrl;
+
+/// Maximum size in bytes of JSON request bodies.
+pub const JSON_BODY_LIMIT: usize = 1024 * 1024;
+
+/// Maximum number of items that can be requested with the `limit` query parameter.
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+/// Path segments that are followed by a name instead of an ID, such as a tag name, which are therefore not validated.
+const NAME_PATH_SEGMENTS: &[&str] = &["tag", "merge_policy"];
+
+// Request validation
+
+/// Returns a 400 Bad Request response if `request` has invalid path or query parameters, or `None` if the request is
+/// valid. Numeric path segments must be positive IDs, except for ratings which must be within `MIN_RATING` and
+/// `MAX_RATING`. The `limit` and `offset` query parameters used for pagination must be within bounds.
+pub fn reject(request: &ServiceRequest) -> Option<HttpResponse> {
+  validate_path(unversioned_path(PublicUrl::unprefixed_path(request.request())))
+    .or_else(|| validate_query(request.query_string()))
+    .map(|message| HttpResponse::BadRequest().json(InternalServerError { message }))
+}
+
+fn validate_path(path: &str) -> Option<String> {
+  let mut previous_segment = "";
+  for segment in path.split('/') {
+    if NAME_PATH_SEGMENTS.contains(&previous_segment) {
+      previous_segment = segment;
+      continue;
+    }
+    if let Ok(value) = segment.parse::<i64>() {
+      if previous_segment == "rating" {
+        if value < MIN_RATING as i64 || value > MAX_RATING as i64 {
+          return Some(format!("Rating {} is not between {} and {}", value, MIN_RATING, MAX_RATING));
+        }
+      } else if value <= 0 || value > i32::MAX as i64 {
+        return Some(format!("ID {} in path segment after '{}' is not a positive 32-bit integer", value, previous_segment));
+      }
+    }
+    previous_segment = segment;
+  }
+  None
+}
+
+fn validate_query(query_string: &str) -> Option<String> {
+  if query_string.is_empty() { return None; }
+  let query = match web::Query::<HashMap<String, String>>::from_query(query_string) {
+    Ok(query) => query.into_inner(),
+    Err(e) => return Some(format!("Invalid query string: {}", e)),
+  };
+  if let Some(limit) = query.get("limit") {
+    match limit.parse::<i64>() {
+      Ok(limit) if limit >= 1 && limit <= MAX_PAGE_LIMIT => {}
+      _ => return Some(format!("Query parameter 'limit' must be an integer between 1 and {}", MAX_PAGE_LIMIT)),
+    }
+  }
+  if let Some(offset) = query.get("offset") {
+    match offset.parse::<i64>() {
+      Ok(offset) if offset >= 0 => {}
+      _ => return Some("Query parameter 'offset' must be a non-negative integer".to_string()),
+    }
+  }
+  None
+}
+
+// Extractor error handlers
+
+/// Turns JSON body extraction errors, such as bodies exceeding `JSON_BODY_LIMIT`, into structured error responses.
+pub fn json_error_handler(error: JsonPayloadError, _request: &HttpRequest) -> Error {
+  let response = HttpResponse::build(error.status_code()).json(InternalServerError { message: error.to_string() });
+  actix_web::error::InternalError::from_response(error, response).into()
+}
+
+/// Turns query parameter extraction errors into structured 400 Bad Request responses.
+pub fn query_error_handler(error: QueryPayloadError, _request: &HttpRequest) -> Error {
+  let response = HttpResponse::BadRequest().json(InternalServerError { message: error.to_string() });
+  actix_web::error::InternalError::from_response(error, response).into()
+}
+
+/// Turns path parameter extraction errors into structured 400 Bad Request responses.
+pub fn path_error_handler(error: PathError, _request: &HttpRequest) -> Error {
+  let response = HttpResponse::BadRequest().json(InternalServerError { message: error.to_string() });
+  actix_web::error::InternalError::from_response(error, response).into()
+}
@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use actix_files::{Files, NamedFile};
+use actix_web::{http, web};
+use actix_web::dev::{fn_service, Service, ServiceRequest, ServiceResponse};
+
+use musium_core::api::API_PATH_PREFIX;
+
+use crate::public_url::PublicUrl;
+
+/// Cache header of HTML documents, which must always be revalidated so that clients pick up new versions of the web UI.
+const HTML_CACHE_CONTROL: &str = "no-cache";
+/// Cache header of other assets, such as scripts, stylesheets, and images.
+const ASSET_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// Configures serving the single-page web UI from `dir` at `/`, relative to the path prefix of the server. Requests for paths that do not exist in `dir` are
+/// answered with its `index.html`, so that the web UI can handle routing, except for requests under the API prefix,
+/// which are answered with 404 Not Found. Must be configured after all other services, as it matches every path.
+pub fn configure_web_ui(config: &mut web::ServiceConfig, dir: &Path) {
+  let index_file: PathBuf = dir.join("index.html");
+  let files = Files::new("/", dir)
+    .index_file("index.html")
+    .default_handler(fn_service(move |request: ServiceRequest| {
+      let index_file = index_file.clone();
+      async move {
+        let (request, _) = request.into_parts();
+        let is_api_request = PublicUrl::unprefixed_path(&request).trim_start_matches('/').starts_with(API_PATH_PREFIX);
+        let response = if is_api_request {
+          actix_web::HttpResponse::NotFound().finish()
+        } else {
+          NamedFile::open(index_file)?.into_response(&request)
+        };
+        Ok(ServiceResponse::new(request, response))
+      }
+    }));
+  config.service(web::scope("")
+    .wrap_fn(|request, service| {
+      let future = service.call(request);
+      async move {
+        let mut response = future.await?;
+        let is_html = response.headers().get(http::header::CONTENT_TYPE)
+          .and_then(|content_type| content_type.to_str().ok())
+          .map_or(false, |content_type| content_type.starts_with("text/html"));
+        let cache_control = if is_html { HTML_CACHE_CONTROL } else { ASSET_CACHE_CONTROL };
+        if response.status().is_success() {
+          response.headers_mut().insert(http::header::CACHE_CONTROL, http::header::HeaderValue::from_static(cache_control));
+        }
+        Ok(response)
+      }
+    })
+    .service(files)
+  );
+}
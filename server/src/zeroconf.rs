@@ -0,0 +1,57 @@
+use std::net::SocketAddr;
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use tracing::{event, Level};
+
+use musium_core::api::{API_PATH_PREFIX, API_VERSION, ZEROCONF_SERVICE_TYPE};
+
+/// Announcement of the server on the local network through zeroconf (mDNS-SD), so that clients and home automation such
+/// as Home Assistant can discover it. The announcement is withdrawn when this is dropped.
+pub struct ZeroconfAnnouncement {
+  daemon: ServiceDaemon,
+  fullname: String,
+}
+
+impl ZeroconfAnnouncement {
+  /// Announces the server listening on `addresses` under `instance_name`, serving the API under `path_prefix`. Returns
+  /// `None` if the server only listens on loopback addresses, which are not reachable from the local network, or if
+  /// announcing fails.
+  pub fn announce(instance_name: &str, addresses: &[SocketAddr], path_prefix: &str) -> Option<Self> {
+    let port = match addresses.iter().find(|address| !address.ip().is_loopback()) {
+      Some(address) => address.port(),
+      None => {
+        event!(Level::WARN, ?addresses, "Not announcing server through zeroconf, as it only listens on loopback addresses");
+        return None;
+      }
+    };
+    let result = ServiceDaemon::new().and_then(|daemon| {
+      let api_version = API_VERSION.to_string();
+      let path = format!("{}/{}", path_prefix, API_PATH_PREFIX);
+      let properties = [("api_version", api_version.as_str()), ("path", path.as_str()), ("version", env!("CARGO_PKG_VERSION"))];
+      let host_name = format!("{}.local.", instance_name);
+      let service_info = ServiceInfo::new(ZEROCONF_SERVICE_TYPE, instance_name, &host_name, "", port, &properties[..])?
+        .enable_addr_auto();
+      let fullname = service_info.get_fullname().to_string();
+      daemon.register(service_info)?;
+      Ok(Self { daemon, fullname })
+    });
+    match result {
+      Ok(announcement) => {
+        event!(Level::INFO, fullname = %announcement.fullname, port, "Announced server through zeroconf");
+        Some(announcement)
+      }
+      Err(e) => {
+        event!(Level::ERROR, "Failed to announce server through zeroconf: {:?}", e);
+        None
+      }
+    }
+  }
+}
+
+impl Drop for ZeroconfAnnouncement {
+  fn drop(&mut self) {
+    if let Err(e) = self.daemon.unregister(&self.fullname).and_then(|_| self.daemon.shutdown()) {
+      event!(Level::WARN, "Failed to withdraw zeroconf announcement: {:?}", e);
+    }
+  }
+}
@@ -1,11 +1,11 @@
 #![feature(backtrace)]
 
 use std::backtrace::Backtrace;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
 
-use chrono::{Duration, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use itertools::Itertools;
 use reqwest::{Client, header, IntoUrl, RequestBuilder, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
@@ -92,7 +92,7 @@ impl SpotifyClient {
       if let Some(state) = state {
         map.insert("state", state.into());
       }
-      map.insert("scope", "user-read-playback-state user-modify-playback-state user-read-currently-playing user-follow-read".to_owned());
+      map.insert("scope", "user-read-playback-state user-modify-playback-state user-read-currently-playing user-follow-read user-follow-modify user-library-read user-library-modify playlist-read-private playlist-read-collaborative playlist-modify-public playlist-modify-private".to_owned());
       map
     };
     let request = self.http_client
@@ -454,19 +454,43 @@ pub struct AlbumSimple {
   pub id: String,
   pub name: String,
   pub artists: Vec<ArtistSimple>,
+  pub release_date: String,
 }
 
 impl SpotifyClient {
   #[instrument(level = "trace", skip(self, authorization))]
   pub async fn get_albums_of_followed_artists(&self, authorization: &mut Authorization) -> Result<impl Iterator<Item=Album>, HttpRequestError> {
+    let (albums, _) = self.get_new_albums_of_followed_artists(&HashSet::new(), authorization).await?;
+    Ok(albums.into_iter())
+  }
+
+  /// Gets the albums of the followed artists of the user, except for albums with an ID in `known_album_ids`, which are
+  /// only listed instead of fetched. Returns the fetched albums, and the IDs of known albums that are still albums of
+  /// followed artists.
+  #[instrument(level = "trace", skip(self, known_album_ids, authorization))]
+  pub async fn get_new_albums_of_followed_artists(&self, known_album_ids: &HashSet<String>, authorization: &mut Authorization) -> Result<(Vec<Album>, HashSet<String>), HttpRequestError> {
     let mut all_albums = Vec::new();
+    let mut seen_album_ids = HashSet::new();
+    let mut listed_known_album_ids = HashSet::new();
     let followed_artist = self.get_followed_artists(authorization).await?;
     for artist in followed_artist {
       let artist_albums_simple = self.get_artist_albums_simple(artist.id, authorization).await?;
-      let albums = self.get_albums(artist_albums_simple.into_iter().map(|a| a.id), authorization).await?;
-      all_albums.extend(albums)
+      let mut new_album_ids = Vec::new();
+      for album in artist_albums_simple {
+        // Albums with multiple followed artists are listed for each artist: only fetch them once.
+        if !seen_album_ids.insert(album.id.clone()) { continue; }
+        if known_album_ids.contains(&album.id) {
+          listed_known_album_ids.insert(album.id);
+        } else {
+          new_album_ids.push(album.id);
+        }
+      }
+      if !new_album_ids.is_empty() {
+        let albums = self.get_albums(new_album_ids, authorization).await?;
+        all_albums.extend(albums)
+      }
     }
-    Ok(all_albums.into_iter())
+    Ok((all_albums, listed_known_album_ids))
   }
 
   #[instrument(level = "trace", skip(self, authorization))]
@@ -515,6 +539,175 @@ impl SpotifyClient {
   }
 }
 
+// Library
+
+impl SpotifyClient {
+  /// Saves the albums with `album_ids` to the library of the user.
+  #[instrument(level = "trace", skip(self, album_ids, authorization))]
+  pub async fn save_album(&self, album_ids: impl IntoIterator<Item=String>, authorization: &mut Authorization) -> Result<(), HttpRequestError> {
+    let url = self.api_base_url.join("me/albums")?;
+    for mut album_ids_per_20 in &album_ids.into_iter().chunks(20) {
+      let request = self.http_client
+        .put(url.clone())
+        .query(&[("ids", album_ids_per_20.join(","))])
+        ;
+      self.send_request(request, [StatusCode::OK], authorization).await?;
+    }
+    Ok(())
+  }
+
+  /// Removes the albums with `album_ids` from the library of the user.
+  #[instrument(level = "trace", skip(self, album_ids, authorization))]
+  pub async fn remove_saved_album(&self, album_ids: impl IntoIterator<Item=String>, authorization: &mut Authorization) -> Result<(), HttpRequestError> {
+    let url = self.api_base_url.join("me/albums")?;
+    for mut album_ids_per_20 in &album_ids.into_iter().chunks(20) {
+      let request = self.http_client
+        .delete(url.clone())
+        .query(&[("ids", album_ids_per_20.join(","))])
+        ;
+      self.send_request(request, [StatusCode::OK], authorization).await?;
+    }
+    Ok(())
+  }
+
+  /// Follows the artists with `artist_ids` for the user.
+  #[instrument(level = "trace", skip(self, artist_ids, authorization))]
+  pub async fn follow_artist(&self, artist_ids: impl IntoIterator<Item=String>, authorization: &mut Authorization) -> Result<(), HttpRequestError> {
+    let url = self.api_base_url.join("me/following")?;
+    for mut artist_ids_per_50 in &artist_ids.into_iter().chunks(50) {
+      let request = self.http_client
+        .put(url.clone())
+        .query(&[("type", "artist".to_owned()), ("ids", artist_ids_per_50.join(","))])
+        ;
+      self.send_request(request, [StatusCode::NO_CONTENT], authorization).await?;
+    }
+    Ok(())
+  }
+
+  /// Appends the tracks with `track_ids` to the end of the playlist with `playlist_id`, which the user must own or
+  /// collaborate on.
+  #[instrument(level = "trace", skip(self, track_ids, authorization))]
+  pub async fn add_tracks_to_playlist(&self, playlist_id: &String, track_ids: impl IntoIterator<Item=String>, authorization: &mut Authorization) -> Result<(), HttpRequestError> {
+    let url = self.api_base_url.join(&format!("playlists/{}/tracks", playlist_id))?;
+    #[derive(Serialize, Debug)]
+    struct Body {
+      uris: Vec<String>,
+    }
+    for track_ids_per_100 in &track_ids.into_iter().chunks(100) {
+      let body = Body { uris: track_ids_per_100.map(|id| format!("spotify:track:{}", id)).collect() };
+      let request = self.http_client
+        .post(url.clone())
+        .json(&body)
+        ;
+      self.send_request(request, [StatusCode::CREATED], authorization).await?;
+    }
+    Ok(())
+  }
+}
+
+// Saved albums and playlists
+
+/// Album saved to the library of the user.
+#[derive(Deserialize, Debug)]
+pub struct SavedAlbum {
+  pub added_at: DateTime<Utc>,
+  pub album: Album,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlaylistSimple {
+  pub id: String,
+  pub name: String,
+  /// Version of the playlist, which changes whenever the playlist changes.
+  pub snapshot_id: String,
+}
+
+impl SpotifyClient {
+  /// Gets the albums saved to the library of the user after `added_after`, or all saved albums if `None`, most recently
+  /// saved first. Stops paging at the first album that was saved at or before `added_after`, as saved albums are listed
+  /// most recently saved first.
+  #[instrument(level = "trace", skip(self, authorization))]
+  pub async fn get_saved_albums_added_after(&self, added_after: Option<DateTime<Utc>>, authorization: &mut Authorization) -> Result<Vec<SavedAlbum>, HttpRequestError> {
+    let url = self.api_base_url.join("me/albums")?;
+    let mut all_albums = Vec::new();
+    let mut offset = 0;
+    loop {
+      let request = self.http_client
+        .get(url.clone())
+        .query(&[("limit", "50"), ("offset", &offset.to_string())])
+        ;
+      let response = self.send_request(request, [StatusCode::OK], authorization).await?;
+      let albums: Paging<SavedAlbum> = response.json().await?;
+      let len = albums.items.len();
+      let total = albums.total;
+      for album in albums.items {
+        if added_after.map_or(false, |added_after| album.added_at <= added_after) {
+          return Ok(all_albums);
+        }
+        all_albums.push(album);
+      }
+      offset += len;
+      if len == 0 || offset >= total { break; }
+    }
+    Ok(all_albums)
+  }
+
+  /// Gets the playlists that the user owns or follows.
+  #[instrument(level = "trace", skip(self, authorization))]
+  pub async fn get_playlists(&self, authorization: &mut Authorization) -> Result<Vec<PlaylistSimple>, HttpRequestError> {
+    let url = self.api_base_url.join("me/playlists")?;
+    let mut all_playlists = Vec::new();
+    let mut offset = 0;
+    loop {
+      let request = self.http_client
+        .get(url.clone())
+        .query(&[("limit", "50"), ("offset", &offset.to_string())])
+        ;
+      let response = self.send_request(request, [StatusCode::OK], authorization).await?;
+      let playlists: Paging<PlaylistSimple> = response.json().await?;
+      let len = playlists.items.len();
+      all_playlists.extend(playlists.items);
+      offset += len;
+      if len == 0 || offset >= playlists.total { break; }
+    }
+    Ok(all_playlists)
+  }
+
+  /// Gets the IDs of the albums of the tracks of the playlist with `playlist_id`. Local files and unavailable tracks in
+  /// the playlist have no album ID and are skipped.
+  #[instrument(level = "trace", skip(self, authorization))]
+  pub async fn get_playlist_album_ids(&self, playlist_id: &str, authorization: &mut Authorization) -> Result<Vec<String>, HttpRequestError> {
+    let url = self.api_base_url.join(&format!("playlists/{}/tracks", playlist_id))?;
+    #[derive(Deserialize, Debug)]
+    struct PlaylistTrack {
+      track: Option<PlaylistTrackTrack>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct PlaylistTrackTrack {
+      album: PlaylistTrackAlbum,
+    }
+    #[derive(Deserialize, Debug)]
+    struct PlaylistTrackAlbum {
+      id: Option<String>,
+    }
+    let mut album_ids = Vec::new();
+    let mut offset = 0;
+    loop {
+      let request = self.http_client
+        .get(url.clone())
+        .query(&[("fields", "items(track(album(id))),offset,total"), ("limit", "100"), ("offset", &offset.to_string())])
+        ;
+      let response = self.send_request(request, [StatusCode::OK], authorization).await?;
+      let tracks: Paging<PlaylistTrack> = response.json().await?;
+      let len = tracks.items.len();
+      album_ids.extend(tracks.items.into_iter().filter_map(|item| item.track.and_then(|track| track.album.id)));
+      offset += len;
+      if len == 0 || offset >= tracks.total { break; }
+    }
+    Ok(album_ids)
+  }
+}
+
 // Track
 
 #[derive(Deserialize, Debug)]
@@ -524,6 +717,9 @@ pub struct TrackSimple {
   pub artists: Vec<ArtistSimple>,
   pub track_number: i32,
   pub disc_number: i32,
+  /// Whether the track has explicit lyrics, which is `false` when unknown.
+  #[serde(default)]
+  pub explicit: bool,
 }
 
 // Player